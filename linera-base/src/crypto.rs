@@ -24,8 +24,11 @@ use {
     std::ops::RangeInclusive,
 };
 
+pub use self::bls::{BlsKeyPair, BlsPublicKey, BlsSignature};
 use crate::doc_scalar;
 
+mod bls;
+
 /// A signature key-pair.
 pub struct KeyPair(dalek::SigningKey);
 
@@ -67,6 +70,12 @@ pub enum CryptoError {
     IncorrectPublicKeySize(usize),
     #[error("Could not parse integer")]
     ParseIntError(#[from] ParseIntError),
+    #[error("Byte slice has length {0} but a `BlsPublicKey` requires exactly 48 bytes")]
+    IncorrectBlsPublicKeySize(usize),
+    #[error("Byte slice has length {0} but a `BlsSignature` requires exactly 96 bytes")]
+    IncorrectBlsSignatureSize(usize),
+    #[error("Bytes do not encode a valid BLS {0}")]
+    InvalidBlsEncoding(&'static str),
 }
 
 impl PublicKey {
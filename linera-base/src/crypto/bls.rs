@@ -0,0 +1,455 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! BLS signatures over the BLS12-381 curve, which can be aggregated into a single signature.
+//!
+//! Public keys live in G1 and signatures in G2 ("minimal public key size" variant). Since
+//! the signatures of a certificate are all over the same message, aggregating them is
+//! only safe if the signers' public keys come with a proof of possession of the
+//! corresponding secret key: otherwise a rogue key could be chosen so as to forge an
+//! aggregate signature.
+
+use std::{fmt, str::FromStr};
+
+use bls12_381::{
+    hash_to_curve::{ExpandMsgXmd, HashToCurve},
+    multi_miller_loop, G1Affine, G1Projective, G2Affine, G2Prepared, G2Projective, Gt, Scalar,
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(with_getrandom)]
+use super::CryptoRng;
+use super::{BcsSignable, CryptoError, HasTypeName as _, Hashable as _};
+use crate::doc_scalar;
+
+/// The domain separation tag for signatures.
+const SIGNATURE_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+/// The domain separation tag for proofs of possession.
+const PROOF_OF_POSSESSION_DST: &[u8] = b"BLS_POP_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+const PUBLIC_KEY_LENGTH: usize = 48;
+const SIGNATURE_LENGTH: usize = 96;
+
+/// A BLS key-pair.
+pub struct BlsKeyPair {
+    secret: Scalar,
+    public: BlsPublicKey,
+}
+
+/// A BLS public key, as a compressed G1 point.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Hash)]
+pub struct BlsPublicKey(pub [u8; PUBLIC_KEY_LENGTH]);
+
+/// A BLS signature, as a compressed G2 point. This can be an aggregate of several
+/// signatures over the same value.
+#[derive(Eq, PartialEq, Copy, Clone, Hash)]
+pub struct BlsSignature(pub [u8; SIGNATURE_LENGTH]);
+
+impl BlsKeyPair {
+    #[cfg(all(with_getrandom, with_testing))]
+    /// Generates a new key-pair.
+    pub fn generate() -> Self {
+        let mut rng = rand::rngs::OsRng;
+        Self::generate_from(&mut rng)
+    }
+
+    #[cfg(with_getrandom)]
+    /// Generates a new key-pair from the given RNG. Use with care.
+    pub fn generate_from<R: CryptoRng>(rng: &mut R) -> Self {
+        loop {
+            let mut bytes = [0u8; 64];
+            rng.fill_bytes(&mut bytes);
+            let secret = Scalar::from_bytes_wide(&bytes);
+            if secret != Scalar::zero() {
+                return Self::from_secret(secret);
+            }
+        }
+    }
+
+    fn from_secret(secret: Scalar) -> Self {
+        let public = G1Affine::from(G1Projective::generator() * secret);
+        BlsKeyPair {
+            secret,
+            public: BlsPublicKey(public.to_compressed()),
+        }
+    }
+
+    /// Obtains the public key of a key-pair.
+    pub fn public(&self) -> BlsPublicKey {
+        self.public
+    }
+
+    /// Copies the key-pair, **including the secret key**.
+    ///
+    /// The `Clone` and `Copy` traits are deliberately not implemented for `BlsKeyPair` to
+    /// prevent accidental copies of secret keys.
+    pub fn copy(&self) -> BlsKeyPair {
+        BlsKeyPair {
+            secret: self.secret,
+            public: self.public,
+        }
+    }
+
+    /// Signs the public key with the secret key, proving that this key-pair's owner
+    /// knows the secret key.
+    pub fn proof_of_possession(&self) -> BlsSignature {
+        self.sign_bytes(&self.public.0, PROOF_OF_POSSESSION_DST)
+    }
+
+    fn sign_bytes(&self, message: &[u8], dst: &[u8]) -> BlsSignature {
+        let signature = hash_to_g2(message, dst) * self.secret;
+        BlsSignature(G2Affine::from(signature).to_compressed())
+    }
+}
+
+impl BlsPublicKey {
+    fn point(&self) -> Result<G1Affine, CryptoError> {
+        Option::from(G1Affine::from_compressed(&self.0))
+            .filter(|point: &G1Affine| !bool::from(point.is_identity()))
+            .ok_or(CryptoError::InvalidBlsEncoding("public key"))
+    }
+
+    /// Checks a proof of possession of the secret key for this public key.
+    pub fn check_proof_of_possession(&self, proof: &BlsSignature) -> Result<(), CryptoError> {
+        proof
+            .verify_bytes(&self.0, PROOF_OF_POSSESSION_DST, self.point()?)
+            .map_err(|error| CryptoError::InvalidSignature {
+                error,
+                type_name: "BlsPublicKey".to_string(),
+            })
+    }
+}
+
+impl BlsSignature {
+    /// Computes a signature.
+    pub fn new<T>(value: &T, secret: &BlsKeyPair) -> Self
+    where
+        T: BcsSignable,
+    {
+        let mut message = Vec::new();
+        value.write(&mut message);
+        secret.sign_bytes(&message, SIGNATURE_DST)
+    }
+
+    fn point(&self) -> Result<G2Affine, CryptoError> {
+        Option::from(G2Affine::from_compressed(&self.0))
+            .ok_or(CryptoError::InvalidBlsEncoding("signature"))
+    }
+
+    fn verify_bytes(&self, message: &[u8], dst: &[u8], public: G1Affine) -> Result<(), String> {
+        let signature = self.point().map_err(|error| error.to_string())?;
+        let hash = G2Affine::from(hash_to_g2(message, dst));
+        // Checks that e(public, hash) = e(generator, signature).
+        let result = multi_miller_loop(&[
+            (&public, &G2Prepared::from(hash)),
+            (&-G1Affine::generator(), &G2Prepared::from(signature)),
+        ])
+        .final_exponentiation();
+        if result == Gt::identity() {
+            Ok(())
+        } else {
+            Err("pairing check failed".to_string())
+        }
+    }
+
+    /// Checks a signature.
+    pub fn check<T>(&self, value: &T, author: BlsPublicKey) -> Result<(), CryptoError>
+    where
+        T: BcsSignable + fmt::Debug,
+    {
+        self.verify_aggregate(value, [&author])
+    }
+
+    /// Aggregates signatures of the same value into a single signature.
+    pub fn aggregate<'a, I>(signatures: I) -> Result<Self, CryptoError>
+    where
+        I: IntoIterator<Item = &'a BlsSignature>,
+    {
+        let mut aggregate = G2Projective::identity();
+        for signature in signatures {
+            aggregate += signature.point()?;
+        }
+        Ok(BlsSignature(G2Affine::from(aggregate).to_compressed()))
+    }
+
+    /// Checks an aggregate signature of the given value by all of the `authors`.
+    ///
+    /// The authors' public keys must have been checked with a proof of possession.
+    pub fn verify_aggregate<'a, T, I>(&self, value: &T, authors: I) -> Result<(), CryptoError>
+    where
+        T: BcsSignable,
+        I: IntoIterator<Item = &'a BlsPublicKey>,
+    {
+        let mut public = G1Projective::identity();
+        for author in authors {
+            public += author.point()?;
+        }
+        if bool::from(public.is_identity()) {
+            return Err(CryptoError::InvalidBlsEncoding("aggregate public key"));
+        }
+        let mut message = Vec::new();
+        value.write(&mut message);
+        self.verify_bytes(&message, SIGNATURE_DST, G1Affine::from(public))
+            .map_err(|error| CryptoError::InvalidSignature {
+                error,
+                type_name: T::type_name().to_string(),
+            })
+    }
+}
+
+fn hash_to_g2(message: &[u8], dst: &[u8]) -> G2Projective {
+    <G2Projective as HashToCurve<ExpandMsgXmd<sha2::Sha256>>>::hash_to_curve(message, dst)
+}
+
+/// A byte array serialized as a tuple, like the arrays of up to 32 bytes supported by serde.
+struct ByteArray<const N: usize>([u8; N]);
+
+impl<const N: usize> Serialize for ByteArray<N> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        use serde::ser::SerializeTuple as _;
+
+        let mut tuple = serializer.serialize_tuple(N)?;
+        for byte in &self.0 {
+            tuple.serialize_element(byte)?;
+        }
+        tuple.end()
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for ByteArray<N> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        struct ByteArrayVisitor<const N: usize>;
+
+        impl<'de, const N: usize> serde::de::Visitor<'de> for ByteArrayVisitor<N> {
+            type Value = ByteArray<N>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(formatter, "an array of {N} bytes")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::SeqAccess<'de>,
+            {
+                let mut bytes = [0u8; N];
+                for (index, byte) in bytes.iter_mut().enumerate() {
+                    *byte = seq
+                        .next_element()?
+                        .ok_or_else(|| serde::de::Error::invalid_length(index, &self))?;
+                }
+                Ok(ByteArray(bytes))
+            }
+        }
+
+        deserializer.deserialize_tuple(N, ByteArrayVisitor)
+    }
+}
+
+impl Serialize for BlsPublicKey {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.to_string())
+        } else {
+            serializer.serialize_newtype_struct("BlsPublicKey", &ByteArray(self.0))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for BlsPublicKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            let value = Self::from_str(&s).map_err(serde::de::Error::custom)?;
+            Ok(value)
+        } else {
+            #[derive(Deserialize)]
+            #[serde(rename = "BlsPublicKey")]
+            struct Foo(ByteArray<PUBLIC_KEY_LENGTH>);
+
+            let value = Foo::deserialize(deserializer)?;
+            Ok(Self(value.0 .0))
+        }
+    }
+}
+
+impl Serialize for BlsSignature {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.to_string())
+        } else {
+            serializer.serialize_newtype_struct("BlsSignature", &ByteArray(self.0))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for BlsSignature {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            let value = Self::from_str(&s).map_err(serde::de::Error::custom)?;
+            Ok(value)
+        } else {
+            #[derive(Deserialize)]
+            #[serde(rename = "BlsSignature")]
+            struct Foo(ByteArray<SIGNATURE_LENGTH>);
+
+            let value = Foo::deserialize(deserializer)?;
+            Ok(Self(value.0 .0))
+        }
+    }
+}
+
+impl Serialize for BlsKeyPair {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        // This is only used for JSON configuration.
+        if !serializer.is_human_readable() {
+            return Err(serde::ser::Error::custom(
+                "BLS key-pairs can only be serialized in human-readable formats",
+            ));
+        }
+        serializer.serialize_str(&hex::encode(self.secret.to_bytes()))
+    }
+}
+
+impl<'de> Deserialize<'de> for BlsKeyPair {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        // This is only used for JSON configuration.
+        if !deserializer.is_human_readable() {
+            return Err(serde::de::Error::custom(
+                "BLS key-pairs can only be deserialized from human-readable formats",
+            ));
+        }
+        let s = String::deserialize(deserializer)?;
+        let value = hex::decode(s).map_err(serde::de::Error::custom)?;
+        let bytes = value[..].try_into().map_err(serde::de::Error::custom)?;
+        let secret = Option::<Scalar>::from(Scalar::from_bytes(&bytes))
+            .ok_or_else(|| serde::de::Error::custom("invalid BLS secret key"))?;
+        Ok(BlsKeyPair::from_secret(secret))
+    }
+}
+
+impl FromStr for BlsPublicKey {
+    type Err = CryptoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = hex::decode(s)?;
+        (value.as_slice()).try_into()
+    }
+}
+
+impl TryFrom<&[u8]> for BlsPublicKey {
+    type Error = CryptoError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let bytes = value
+            .try_into()
+            .map_err(|_| CryptoError::IncorrectBlsPublicKeySize(value.len()))?;
+        Ok(BlsPublicKey(bytes))
+    }
+}
+
+impl FromStr for BlsSignature {
+    type Err = CryptoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = hex::decode(s)?;
+        (value.as_slice()).try_into()
+    }
+}
+
+impl TryFrom<&[u8]> for BlsSignature {
+    type Error = CryptoError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let bytes = value
+            .try_into()
+            .map_err(|_| CryptoError::IncorrectBlsSignatureSize(value.len()))?;
+        Ok(BlsSignature(bytes))
+    }
+}
+
+impl fmt::Display for BlsPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.0[..]))
+    }
+}
+
+impl fmt::Display for BlsSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.0[..]))
+    }
+}
+
+impl fmt::Debug for BlsPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.0[..8]))
+    }
+}
+
+impl fmt::Debug for BlsSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.0[..8]))
+    }
+}
+
+doc_scalar!(BlsPublicKey, "A BLS public key");
+doc_scalar!(BlsSignature, "A BLS signature, possibly aggregated");
+
+#[cfg(all(with_getrandom, with_testing))]
+#[test]
+fn test_bls_signatures() {
+    use super::TestString;
+
+    let key1 = BlsKeyPair::generate();
+    let key2 = BlsKeyPair::generate();
+    let key3 = BlsKeyPair::generate();
+    let (addr1, addr2, addr3) = (key1.public(), key2.public(), key3.public());
+
+    let ts = TestString("hello".into());
+    let tsx = TestString("hellox".into());
+
+    let s1 = BlsSignature::new(&ts, &key1);
+    assert!(s1.check(&ts, addr1).is_ok());
+    assert!(s1.check(&ts, addr2).is_err());
+    assert!(s1.check(&tsx, addr1).is_err());
+
+    let s2 = BlsSignature::new(&ts, &key2);
+    let aggregate = BlsSignature::aggregate([&s1, &s2]).unwrap();
+    assert!(aggregate.verify_aggregate(&ts, [&addr1, &addr2]).is_ok());
+    assert!(aggregate.verify_aggregate(&ts, [&addr1, &addr3]).is_err());
+    assert!(aggregate.verify_aggregate(&ts, [&addr1]).is_err());
+    assert!(aggregate.verify_aggregate(&tsx, [&addr1, &addr2]).is_err());
+
+    assert!(addr1
+        .check_proof_of_possession(&key1.proof_of_possession())
+        .is_ok());
+    assert!(addr1
+        .check_proof_of_possession(&key2.proof_of_possession())
+        .is_err());
+    // A proof of possession is not a signature of the public key as a value.
+    assert!(addr1.check_proof_of_possession(&s1).is_err());
+}
@@ -296,6 +296,9 @@ pub struct SendMessageRequest<Message> {
     pub is_tracked: bool,
     /// The grant resources forwarded with the message.
     pub grant: Resources,
+    /// The time from which the receiver must reject the message instead of executing it, if
+    /// any. Tracked messages are then bounced back to the sender.
+    pub expiry: Option<Timestamp>,
     /// The message itself.
     pub message: Message,
 }
@@ -313,6 +316,7 @@ where
             authenticated: self.authenticated,
             is_tracked: self.is_tracked,
             grant: self.grant,
+            expiry: self.expiry,
             message,
         }
     }
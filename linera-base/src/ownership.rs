@@ -4,10 +4,14 @@
 //! Structures defining the set of owners and super owners, as well as the consensus
 //! round types and timeouts for chains.
 
-use std::{collections::BTreeMap, iter};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    iter,
+};
 
 use linera_witty::{WitLoad, WitStore, WitType};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use thiserror::Error;
 
 use crate::{
@@ -42,10 +46,38 @@ impl Default for TimeoutConfig {
     }
 }
 
+/// The social-recovery configuration of a chain: a set of guardians who can jointly hand the
+/// chain over to a new owner, e.g. if the owners' keys were lost.
+#[derive(PartialEq, Eq, Clone, Hash, Debug, Serialize, Deserialize, WitLoad, WitStore, WitType)]
+pub struct RecoveryConfig {
+    /// The guardians who can initiate an ownership change.
+    pub guardians: BTreeMap<Owner, PublicKey>,
+    /// The number of guardians who must agree on the same new owner.
+    pub threshold: u32,
+    /// The time the owners have to cancel a recovery once enough guardians agreed on it.
+    pub delay: TimeDelta,
+}
+
+impl RecoveryConfig {
+    /// Creates a `RecoveryConfig` with the given guardians.
+    pub fn new(
+        guardians: impl IntoIterator<Item = PublicKey>,
+        threshold: u32,
+        delay: TimeDelta,
+    ) -> Self {
+        RecoveryConfig {
+            guardians: guardians
+                .into_iter()
+                .map(|public_key| (Owner::from(public_key), public_key))
+                .collect(),
+            threshold,
+            delay,
+        }
+    }
+}
+
 /// Represents the owner(s) of a chain.
-#[derive(
-    PartialEq, Eq, Clone, Hash, Debug, Default, Serialize, Deserialize, WitLoad, WitStore, WitType,
-)]
+#[derive(PartialEq, Eq, Clone, Hash, Debug, Default, WitLoad, WitStore, WitType)]
 pub struct ChainOwnership {
     /// Super owners can propose fast blocks in the first round, and regular blocks in any round.
     pub super_owners: BTreeMap<Owner, PublicKey>,
@@ -55,6 +87,179 @@ pub struct ChainOwnership {
     pub multi_leader_rounds: u32,
     /// The timeout configuration: how long fast, multi-leader and single-leader rounds last.
     pub timeout_config: TimeoutConfig,
+    /// If set, a block proposed by a regular owner must be approved by regular owners whose
+    /// weights add up to at least this value, including the proposer's own weight.
+    ///
+    /// This is one of the [`OwnershipExtensions`].
+    pub approval_threshold: Option<u64>,
+    /// If set, the guardians who can transfer the chain to a new owner.
+    ///
+    /// This is one of the [`OwnershipExtensions`].
+    pub recovery: Option<RecoveryConfig>,
+}
+
+/// The parts of a chain's ownership that its original binary format did not have.
+///
+/// That format is part of the hashes of existing blocks and execution states, so it cannot
+/// change: an ownership without extensions is still serialized exactly like before. The
+/// extensions of any other ownership are serialized in place of its fast round duration,
+/// using a variant that the original format did not have.
+#[derive(PartialEq, Eq, Clone, Hash, Debug, Default, Serialize, Deserialize)]
+pub struct OwnershipExtensions {
+    /// The total weight of regular owners that must approve every block, if any.
+    pub approval_threshold: Option<u64>,
+    /// The guardians who can transfer the chain to a new owner, if any.
+    pub recovery: Option<RecoveryConfig>,
+}
+
+impl Serialize for ChainOwnership {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            ChainOwnershipFull::from(self).serialize(serializer)
+        } else {
+            ChainOwnershipBinary::from(self).serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ChainOwnership {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            let ownership = ChainOwnershipFull::deserialize(deserializer)?;
+            Ok(ChainOwnership {
+                super_owners: ownership.super_owners.into_owned(),
+                owners: ownership.owners.into_owned(),
+                multi_leader_rounds: ownership.multi_leader_rounds,
+                timeout_config: ownership.timeout_config.into_owned(),
+                approval_threshold: ownership.approval_threshold,
+                recovery: ownership.recovery.map(Cow::into_owned),
+            })
+        } else {
+            let ownership = ChainOwnershipBinary::deserialize(deserializer)?;
+            let timeout_config = ownership.timeout_config;
+            let (fast_round_duration, extensions) = timeout_config.fast_round_duration.into_parts();
+            Ok(ChainOwnership {
+                super_owners: ownership.super_owners.into_owned(),
+                owners: ownership.owners.into_owned(),
+                multi_leader_rounds: ownership.multi_leader_rounds,
+                timeout_config: TimeoutConfig {
+                    fast_round_duration,
+                    base_timeout: timeout_config.base_timeout,
+                    timeout_increment: timeout_config.timeout_increment,
+                    fallback_duration: timeout_config.fallback_duration,
+                },
+                approval_threshold: extensions.approval_threshold,
+                recovery: extensions.recovery,
+            })
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "ChainOwnership")]
+struct ChainOwnershipFull<'a> {
+    super_owners: Cow<'a, BTreeMap<Owner, PublicKey>>,
+    owners: Cow<'a, BTreeMap<Owner, (PublicKey, u64)>>,
+    multi_leader_rounds: u32,
+    timeout_config: Cow<'a, TimeoutConfig>,
+    #[serde(default)]
+    approval_threshold: Option<u64>,
+    #[serde(default)]
+    recovery: Option<Cow<'a, RecoveryConfig>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "ChainOwnership")]
+struct ChainOwnershipBinary<'a> {
+    super_owners: Cow<'a, BTreeMap<Owner, PublicKey>>,
+    owners: Cow<'a, BTreeMap<Owner, (PublicKey, u64)>>,
+    multi_leader_rounds: u32,
+    timeout_config: OwnershipTimeoutConfig,
+}
+
+/// The serialized timeout configuration of a chain's ownership, which also holds the
+/// ownership's extensions, if any.
+#[derive(Serialize, Deserialize)]
+struct OwnershipTimeoutConfig {
+    fast_round_duration: FastRoundDuration,
+    base_timeout: TimeDelta,
+    timeout_increment: TimeDelta,
+    fallback_duration: TimeDelta,
+}
+
+/// The serialized fast round duration of a chain's ownership, together with the
+/// ownership's extensions, if any.
+///
+/// Ownerships used to be serialized with a plain `Option<TimeDelta>` in this position. The
+/// first two variants are serialized like `None` and `Some`, and are used for ownerships
+/// without extensions, so that these keep their format and older ownerships can still be
+/// read.
+#[derive(Serialize, Deserialize)]
+pub enum FastRoundDuration {
+    None,
+    Some(TimeDelta),
+    WithExtensions(Option<TimeDelta>, OwnershipExtensions),
+}
+
+impl FastRoundDuration {
+    fn new(fast_round_duration: Option<TimeDelta>, extensions: OwnershipExtensions) -> Self {
+        if extensions != OwnershipExtensions::default() {
+            return FastRoundDuration::WithExtensions(fast_round_duration, extensions);
+        }
+        match fast_round_duration {
+            None => FastRoundDuration::None,
+            Some(duration) => FastRoundDuration::Some(duration),
+        }
+    }
+
+    fn into_parts(self) -> (Option<TimeDelta>, OwnershipExtensions) {
+        match self {
+            FastRoundDuration::None => (None, OwnershipExtensions::default()),
+            FastRoundDuration::Some(duration) => (Some(duration), OwnershipExtensions::default()),
+            FastRoundDuration::WithExtensions(fast_round_duration, extensions) => {
+                (fast_round_duration, extensions)
+            }
+        }
+    }
+}
+
+impl<'a> From<&'a ChainOwnership> for ChainOwnershipFull<'a> {
+    fn from(ownership: &'a ChainOwnership) -> Self {
+        ChainOwnershipFull {
+            super_owners: Cow::Borrowed(&ownership.super_owners),
+            owners: Cow::Borrowed(&ownership.owners),
+            multi_leader_rounds: ownership.multi_leader_rounds,
+            timeout_config: Cow::Borrowed(&ownership.timeout_config),
+            approval_threshold: ownership.approval_threshold,
+            recovery: ownership.recovery.as_ref().map(Cow::Borrowed),
+        }
+    }
+}
+
+impl<'a> From<&'a ChainOwnership> for ChainOwnershipBinary<'a> {
+    fn from(ownership: &'a ChainOwnership) -> Self {
+        let timeout_config = &ownership.timeout_config;
+        ChainOwnershipBinary {
+            super_owners: Cow::Borrowed(&ownership.super_owners),
+            owners: Cow::Borrowed(&ownership.owners),
+            multi_leader_rounds: ownership.multi_leader_rounds,
+            timeout_config: OwnershipTimeoutConfig {
+                fast_round_duration: FastRoundDuration::new(
+                    timeout_config.fast_round_duration,
+                    ownership.extensions(),
+                ),
+                base_timeout: timeout_config.base_timeout,
+                timeout_increment: timeout_config.timeout_increment,
+                fallback_duration: timeout_config.fallback_duration,
+            },
+        }
+    }
 }
 
 impl ChainOwnership {
@@ -65,6 +270,8 @@ impl ChainOwnership {
             owners: BTreeMap::new(),
             multi_leader_rounds: 2,
             timeout_config: TimeoutConfig::default(),
+            approval_threshold: None,
+            recovery: None,
         }
     }
 
@@ -82,6 +289,8 @@ impl ChainOwnership {
                 .collect(),
             multi_leader_rounds,
             timeout_config,
+            approval_threshold: None,
+            recovery: None,
         }
     }
 
@@ -92,6 +301,58 @@ impl ChainOwnership {
         self
     }
 
+    /// Requires every block proposed by a regular owner to be approved by regular owners
+    /// with the given total weight.
+    pub fn with_approval_threshold(mut self, threshold: u64) -> Self {
+        self.approval_threshold = Some(threshold);
+        self
+    }
+
+    /// Allows the given guardians to transfer the chain to a new owner.
+    pub fn with_recovery(mut self, recovery: RecoveryConfig) -> Self {
+        self.recovery = Some(recovery);
+        self
+    }
+
+    /// Returns the parts of the ownership that its original binary format did not have.
+    pub fn extensions(&self) -> OwnershipExtensions {
+        OwnershipExtensions {
+            approval_threshold: self.approval_threshold,
+            recovery: self.recovery.clone(),
+        }
+    }
+
+    /// Returns the ownership with the given extensions, replacing the current ones.
+    pub fn with_extensions(mut self, extensions: OwnershipExtensions) -> Self {
+        let OwnershipExtensions {
+            approval_threshold,
+            recovery,
+        } = extensions;
+        self.approval_threshold = approval_threshold;
+        self.recovery = recovery;
+        self
+    }
+
+    /// Returns the total weight of the given regular owners. Owners that are not regular
+    /// owners, and duplicates, are ignored.
+    pub fn approval_weight<'a>(&self, approvers: impl IntoIterator<Item = &'a Owner>) -> u64 {
+        approvers
+            .into_iter()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .filter_map(|owner| self.owners.get(owner))
+            .fold(0, |weight, (_, owner_weight)| {
+                weight.saturating_add(*owner_weight)
+            })
+    }
+
+    /// Returns whether the given regular owners' approvals satisfy the approval threshold.
+    pub fn is_approved_by<'a>(&self, approvers: impl IntoIterator<Item = &'a Owner>) -> bool {
+        self.approval_threshold.map_or(true, |threshold| {
+            self.approval_weight(approvers) >= threshold
+        })
+    }
+
     /// Returns whether there are any owners or super owners or it is a public chain.
     pub fn is_active(&self) -> bool {
         !self.super_owners.is_empty()
@@ -108,10 +369,32 @@ impl ChainOwnership {
         }
     }
 
+    /// Returns the given guardian's public key, if they are a guardian but not an owner or
+    /// super owner.
+    pub fn verify_guardian(&self, owner: &Owner) -> Option<PublicKey> {
+        if self.verify_owner(owner).is_some() {
+            return None;
+        }
+        self.recovery.as_ref()?.guardians.get(owner).copied()
+    }
+
+    /// Returns an iterator over all guardians.
+    pub fn guardians(&self) -> impl Iterator<Item = &Owner> {
+        self.recovery
+            .iter()
+            .flat_map(|recovery| recovery.guardians.keys())
+    }
+
     /// Returns the duration of the given round.
+    ///
+    /// If the chain has guardians, the fast round always times out, by default after the base
+    /// timeout: guardians can only propose in later rounds.
     pub fn round_timeout(&self, round: Round) -> Option<TimeDelta> {
         let tc = &self.timeout_config;
         match round {
+            Round::Fast if self.recovery.is_some() => {
+                Some(tc.fast_round_duration.unwrap_or(tc.base_timeout))
+            }
             Round::Fast => tc.fast_round_duration,
             Round::MultiLeader(r) if r.saturating_add(1) == self.multi_leader_rounds => {
                 Some(tc.base_timeout)
@@ -202,6 +485,8 @@ mod tests {
                 timeout_increment: TimeDelta::from_secs(1),
                 fallback_duration: TimeDelta::from_secs(60 * 60),
             },
+            approval_threshold: None,
+            recovery: None,
         };
 
         assert_eq!(
@@ -226,6 +511,121 @@ mod tests {
             Some(TimeDelta::from_secs(18))
         );
     }
+
+    #[test]
+    fn test_ownership_approval_threshold() {
+        use crate::crypto::KeyPair;
+
+        let keys = (0..3)
+            .map(|_| KeyPair::generate().public())
+            .collect::<Vec<_>>();
+        let [owner0, owner1, owner2] = [0, 1, 2].map(|i| Owner::from(keys[i]));
+        let super_owner = Owner::from(KeyPair::generate().public());
+        let ownership = ChainOwnership::multiple(
+            [(keys[0], 100), (keys[1], 100), (keys[2], 200)],
+            10,
+            TimeoutConfig::default(),
+        );
+        assert!(ownership.is_approved_by([&owner0]));
+
+        let ownership = ownership.with_approval_threshold(300);
+        assert_eq!(ownership.approval_weight([&owner0, &owner1, &owner0]), 200);
+        assert_eq!(ownership.approval_weight([&owner0, &super_owner]), 100);
+        assert!(!ownership.is_approved_by([&owner0, &owner1, &owner1]));
+        assert!(!ownership.is_approved_by([&owner2]));
+        assert!(ownership.is_approved_by([&owner0, &owner2]));
+        assert!(ownership.is_approved_by([&owner0, &owner1, &owner2]));
+    }
+
+    #[test]
+    fn test_ownership_guardians() {
+        use crate::crypto::KeyPair;
+
+        let owner_key = KeyPair::generate().public();
+        let guardian_keys = (0..2)
+            .map(|_| KeyPair::generate().public())
+            .collect::<Vec<_>>();
+        let ownership = ChainOwnership::single(owner_key);
+        assert_eq!(
+            ownership.verify_guardian(&Owner::from(guardian_keys[0])),
+            None
+        );
+        assert_eq!(ownership.round_timeout(Round::Fast), None);
+
+        let recovery = RecoveryConfig::new(
+            guardian_keys.iter().copied().chain([owner_key]),
+            2,
+            TimeDelta::from_secs(60),
+        );
+        let ownership = ownership.with_recovery(recovery);
+        assert_eq!(
+            ownership.verify_guardian(&Owner::from(guardian_keys[1])),
+            Some(guardian_keys[1])
+        );
+        // Owners are not treated as guardians, even if they are listed as such.
+        assert_eq!(ownership.verify_guardian(&Owner::from(owner_key)), None);
+        assert_eq!(ownership.guardians().count(), 3);
+        // With guardians, the fast round times out after the base timeout.
+        assert_eq!(
+            ownership.round_timeout(Round::Fast),
+            Some(TimeDelta::from_secs(10))
+        );
+    }
+
+    #[test]
+    fn test_ownership_serialization() {
+        use crate::crypto::KeyPair;
+
+        /// The binary format of `ChainOwnership` before it had extensions.
+        #[derive(Serialize)]
+        struct LegacyChainOwnership {
+            super_owners: BTreeMap<Owner, PublicKey>,
+            owners: BTreeMap<Owner, (PublicKey, u64)>,
+            multi_leader_rounds: u32,
+            timeout_config: TimeoutConfig,
+        }
+
+        let super_key = KeyPair::generate().public();
+        let keys = (0..2)
+            .map(|_| KeyPair::generate().public())
+            .collect::<Vec<_>>();
+        let mut ownership = ChainOwnership::single(super_key)
+            .with_regular_owner(keys[0], 100)
+            .with_regular_owner(keys[1], 200);
+        for fast_round_duration in [None, Some(TimeDelta::from_secs(5))] {
+            ownership.timeout_config.fast_round_duration = fast_round_duration;
+            let legacy = LegacyChainOwnership {
+                super_owners: ownership.super_owners.clone(),
+                owners: ownership.owners.clone(),
+                multi_leader_rounds: ownership.multi_leader_rounds,
+                timeout_config: ownership.timeout_config.clone(),
+            };
+            let bytes = bcs::to_bytes(&ownership).unwrap();
+            assert_eq!(bytes, bcs::to_bytes(&legacy).unwrap());
+            assert_eq!(
+                bcs::from_bytes::<ChainOwnership>(&bytes).unwrap(),
+                ownership
+            );
+        }
+
+        let ownership = ownership
+            .with_approval_threshold(300)
+            .with_recovery(RecoveryConfig::new(keys, 1, TimeDelta::from_secs(60)));
+        let bytes = bcs::to_bytes(&ownership).unwrap();
+        assert_eq!(
+            bcs::from_bytes::<ChainOwnership>(&bytes).unwrap(),
+            ownership
+        );
+        let json = serde_json::to_string(&ownership).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ChainOwnership>(&json).unwrap(),
+            ownership
+        );
+    }
 }
 
 doc_scalar!(ChainOwnership, "Represents the owner(s) of a chain");
+doc_scalar!(
+    RecoveryConfig,
+    "The social-recovery configuration of a chain: guardians, threshold and delay"
+);
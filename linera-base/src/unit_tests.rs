@@ -3,7 +3,7 @@
 
 //! Unit tests for `linera-base` types.
 
-use std::{collections::BTreeMap, fmt::Debug};
+use std::{collections::BTreeMap, fmt::Debug, iter};
 
 use linera_witty::{Layout, WitLoad, WitStore};
 use test_case::test_case;
@@ -14,7 +14,7 @@ use crate::{
     identifiers::{
         Account, ApplicationId, BytecodeId, ChainId, ChannelName, Destination, MessageId, Owner,
     },
-    ownership::{ChainOwnership, TimeoutConfig},
+    ownership::{ChainOwnership, RecoveryConfig, TimeoutConfig},
 };
 
 /// Test roundtrip of types used in the WIT interface.
@@ -75,6 +75,7 @@ fn send_message_request_test_case() -> SendMessageRequest<Vec<u8>> {
             write_operations: 0,
             storage_size_delta: 0,
         },
+        expiry: Some(Timestamp::from(1_000_000)),
         message: (0..=255).cycle().take(2_000).collect(),
     }
 }
@@ -166,5 +167,15 @@ fn chain_ownership_test_case() -> ChainOwnership {
             timeout_increment: TimeDelta::from_secs(3_600),
             fallback_duration: TimeDelta::from_secs(10_000),
         },
+        approval_threshold: Some(2),
+        recovery: Some(RecoveryConfig {
+            guardians: iter::once((
+                Owner(CryptoHash::test_hash("Frank")),
+                PublicKey::test_key(5),
+            ))
+            .collect(),
+            threshold: 1,
+            delay: TimeDelta::from_secs(86_400),
+        }),
     }
 }
@@ -17,15 +17,16 @@ use linera_base::{
     },
     ensure,
     identifiers::{
-        ChainId, ChannelName, Destination, GenericApplicationId, MessageId, Owner, StreamId,
+        ChainId, ChannelName, Destination, GenericApplicationId, MessageId, StreamId,
         UserApplicationId,
     },
 };
 use linera_execution::{
-    system::OpenChainConfig, ExecutionError, ExecutionOutcome, ExecutionRuntimeContext,
-    ExecutionStateView, Message, MessageContext, Operation, OperationContext, Query, QueryContext,
-    RawExecutionOutcome, RawOutgoingMessage, ResourceController, ResourceTracker, Response,
-    ServiceRuntimeEndpoint, TransactionTracker,
+    system::{OpenChainConfig, SystemMessage, SystemOperation},
+    ExecutionError, ExecutionOutcome, ExecutionRuntimeContext, ExecutionStateView, FeePayer,
+    Message, MessageContext, Operation, OperationContext, Query, QueryContext, RawExecutionOutcome,
+    RawOutgoingMessage, ResourceController, ResourceTracker, Response, ServiceRuntimeEndpoint,
+    TransactionTracker,
 };
 use linera_views::{
     context::Context,
@@ -204,7 +205,7 @@ static STATE_HASH_COMPUTATION_LATENCY: LazyLock<HistogramVec> = LazyLock::new(||
 });
 
 /// The BCS-serialized size of an empty `ExecutedBlock`.
-const EMPTY_EXECUTED_BLOCK_SIZE: usize = 91;
+const EMPTY_EXECUTED_BLOCK_SIZE: usize = 92;
 
 /// An origin, cursor and timestamp of a unskippable bundle in our inbox.
 #[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
@@ -493,6 +494,58 @@ where
         Ok(())
     }
 
+    /// Verifies that the block doesn't refund a hash lock while the inboxes still contain a
+    /// redemption that was sent before the lock's deadline. Otherwise the owner could take the
+    /// funds back even though the recipient revealed the preimage in time.
+    ///
+    /// This must be called after the block's incoming bundles were removed from the inboxes.
+    pub async fn check_no_pending_lock_redemptions(&self, block: &Block) -> Result<(), ChainError> {
+        let mut deadlines = BTreeMap::new();
+        for operation in &block.operations {
+            if let Operation::System(SystemOperation::RefundLock { hash }) = operation {
+                if let Some(lock) = self.execution_state.system.hash_locks.get(hash).await? {
+                    deadlines.insert(*hash, lock.deadline);
+                }
+            }
+        }
+        if deadlines.is_empty() {
+            return Ok(());
+        }
+        let chain_id = self.chain_id();
+        let pairs = self.inboxes.try_load_all_entries().await?;
+        let max_stream_queries = self.context().max_stream_queries();
+        let deadlines = &deadlines;
+        let stream = stream::iter(pairs)
+            .map(|(origin, inbox)| async move {
+                for bundle in inbox.added_bundles.elements().await? {
+                    for posted_message in &bundle.messages {
+                        let Message::System(SystemMessage::RedeemLock {
+                            preimage,
+                            timestamp,
+                        }) = &posted_message.message
+                        else {
+                            continue;
+                        };
+                        let hash = preimage.hash();
+                        if deadlines
+                            .get(&hash)
+                            .is_some_and(|deadline| timestamp < deadline)
+                        {
+                            return Err(ChainError::PendingLockRedemption {
+                                chain_id,
+                                origin: origin.into(),
+                                hash,
+                            });
+                        }
+                    }
+                }
+                Ok::<(), ChainError>(())
+            })
+            .buffer_unordered(max_stream_queries);
+        stream.try_collect::<Vec<_>>().await?;
+        Ok(())
+    }
+
     pub async fn next_block_height_to_receive(
         &self,
         origin: &Origin,
@@ -733,7 +786,10 @@ where
         let mut resource_controller = ResourceController {
             policy: Arc::new(committee.policy().clone()),
             tracker: ResourceTracker::default(),
-            account: block.authenticated_signer,
+            account: FeePayer {
+                owner: block.authenticated_signer,
+                sponsor: block.sponsor(),
+            },
         };
         resource_controller
             .track_executed_block_size(EMPTY_EXECUTED_BLOCK_SIZE)
@@ -753,6 +809,23 @@ where
                 ChainError::ClosedChain
             );
         }
+        if let Some(signer) = &block.authenticated_signer {
+            let ownership = self.execution_state.system.ownership.get();
+            if ownership.verify_guardian(signer).is_some() {
+                // Guardians can only propose blocks to recover the chain.
+                ensure!(
+                    block.incoming_bundles.is_empty()
+                        && block.operations.iter().all(|operation| matches!(
+                            operation,
+                            Operation::System(
+                                SystemOperation::InitiateRecovery { .. }
+                                    | SystemOperation::CompleteRecovery
+                            )
+                        )),
+                    ChainError::UnauthorizedGuardianBlock
+                );
+            }
+        }
         let app_permissions = self.execution_state.system.application_permissions.get();
         let mut mandatory = HashSet::<UserApplicationId>::from_iter(
             app_permissions.mandatory_applications.iter().cloned(),
@@ -780,6 +853,41 @@ where
             mandatory.is_empty(),
             ChainError::MissingMandatoryApplications(mandatory.into_iter().collect())
         );
+        let sponsors = block
+            .operations
+            .iter()
+            .filter(|operation| is_use_sponsor(operation))
+            .count();
+        ensure!(sponsors <= 1, ChainError::MultipleSponsors);
+        if let Some(sponsor) = block.sponsor() {
+            let sponsorship = self
+                .execution_state
+                .system
+                .sponsorships
+                .get(&sponsor)
+                .await?
+                .ok_or(ChainError::UnknownSponsor(sponsor))?;
+            // The sponsor also pays for the incoming messages, so they must match as well.
+            let app_ids = block
+                .operations
+                .iter()
+                .filter(|operation| !is_use_sponsor(operation))
+                .map(Operation::application_id)
+                .chain(
+                    block
+                        .incoming_messages()
+                        .map(|posted| posted.message.application_id()),
+                );
+            for app_id in app_ids {
+                ensure!(
+                    sponsorship.covers(&app_id),
+                    ChainError::SponsoredApplications(
+                        sponsor,
+                        sponsorship.applications.clone().unwrap()
+                    )
+                );
+            }
+        }
 
         // Execute each incoming bundle as a transaction, then each operation.
         // Collect messages, events and oracle responses, each as one list per transaction.
@@ -904,6 +1012,39 @@ where
                 .map_err(|err| ChainError::ExecutionError(err, ChainExecutionContext::Block))?;
         }
 
+        // Pay the fees into the validators' reward pool. Other chains send them to the admin
+        // chain in batches, along with the messages of the last transaction, so blocks
+        // without any transactions leave them for a later block.
+        let with_block_context =
+            |error: ExecutionError| ChainError::ExecutionError(error, ChainExecutionContext::Block);
+        let system = &mut self.execution_state.system;
+        system
+            .collected_fees
+            .get_mut()
+            .try_add_assign(resource_controller.tracker.fees)?;
+        if !messages.is_empty() {
+            if let Some(message) = system
+                .pay_collected_fees(chain_id, block.epoch)
+                .await
+                .map_err(|error| with_block_context(error.into()))?
+            {
+                let outcome = RawExecutionOutcome::default().with_message(message);
+                let (fee_messages, _) = self
+                    .process_execution_outcomes(
+                        block.height,
+                        vec![ExecutionOutcome::System(outcome)],
+                    )
+                    .await?;
+                resource_controller
+                    .track_executed_block_size_of(&fee_messages)
+                    .map_err(with_block_context)?;
+                messages
+                    .last_mut()
+                    .expect("messages are not empty")
+                    .extend(fee_messages);
+            }
+        }
+
         // Recompute the state hash.
         let state_hash = {
             #[cfg(with_metrics)]
@@ -962,7 +1103,7 @@ where
         txn_index: u32,
         local_time: Timestamp,
         txn_tracker: &mut TransactionTracker,
-        resource_controller: &mut ResourceController<Option<Owner>>,
+        resource_controller: &mut ResourceController<FeePayer>,
     ) -> Result<(), ChainError> {
         #[cfg(with_metrics)]
         let _message_latency = MESSAGE_EXECUTION_LATENCY.measure_latency();
@@ -984,6 +1125,18 @@ where
                 };
                 // Once a chain is closed, accepting incoming messages is not allowed.
                 ensure!(!self.is_closed(), ChainError::ClosedChain);
+                // Expired messages must be rejected, so that tracked ones bounce back.
+                if let Some(expiry) = posted_message.expiry {
+                    ensure!(
+                        block.timestamp < expiry,
+                        ChainError::ExpiredMessage {
+                            chain_id: block.chain_id,
+                            origin: Box::new(incoming_bundle.origin.clone()),
+                            posted_message: posted_message.clone(),
+                            expiry,
+                        }
+                    );
+                }
 
                 self.execution_state
                     .execute_message(
@@ -1118,6 +1271,7 @@ where
             authenticated,
             grant,
             kind,
+            expiry,
             message,
         } in raw_outcome.messages
         {
@@ -1138,6 +1292,7 @@ where
                 grant,
                 refund_grant_to,
                 kind,
+                expiry,
                 message: lift(message),
             });
         }
@@ -1274,6 +1429,14 @@ where
     }
 }
 
+/// Returns whether the operation only designates the sponsor of its block.
+fn is_use_sponsor(operation: &Operation) -> bool {
+    matches!(
+        operation,
+        Operation::System(SystemOperation::UseSponsor { .. })
+    )
+}
+
 #[test]
 fn empty_executed_block_size() {
     let executed_block = crate::data_types::ExecutedBlock {
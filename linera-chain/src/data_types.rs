@@ -6,7 +6,10 @@ use std::{borrow::Cow, collections::HashSet, fmt};
 
 use async_graphql::SimpleObject;
 use linera_base::{
-    crypto::{BcsHashable, BcsSignable, CryptoError, CryptoHash, KeyPair, PublicKey, Signature},
+    crypto::{
+        BcsHashable, BcsSignable, BlsKeyPair, BlsPublicKey, BlsSignature, CryptoError, CryptoHash,
+        KeyPair, PublicKey, Signature,
+    },
     data_types::{Amount, Blob, BlockHeight, OracleResponse, Round, Timestamp},
     doc_scalar, ensure,
     identifiers::{
@@ -79,6 +82,17 @@ impl Block {
         blob_ids
     }
 
+    /// Returns the account paying for the execution fees of the block before the chain's
+    /// own balance, if the block has a [`SystemOperation::UseSponsor`] operation.
+    pub fn sponsor(&self) -> Option<Account> {
+        self.operations
+            .iter()
+            .find_map(|operation| match operation {
+                Operation::System(SystemOperation::UseSponsor { sponsor }) => Some(*sponsor),
+                _ => None,
+            })
+    }
+
     /// Returns whether the block contains only rejected incoming messages, which
     /// makes it admissible even on closed chains.
     pub fn has_only_rejected_messages(&self) -> bool {
@@ -263,10 +277,12 @@ pub struct BlockProposal {
     pub signature: Signature,
     pub blobs: Vec<Blob>,
     pub validated_block_certificate: Option<LiteCertificate<'static>>,
+    /// Signatures of the block by other regular owners, if the chain requires approvals.
+    pub approvals: Vec<(Owner, Signature)>,
 }
 
 /// A posted message together with routing information.
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize, SimpleObject)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, SimpleObject)]
 pub struct OutgoingMessage {
     /// The destination of the message.
     pub destination: Destination,
@@ -278,12 +294,16 @@ pub struct OutgoingMessage {
     pub refund_grant_to: Option<Account>,
     /// The kind of message being sent.
     pub kind: MessageKind,
+    /// The time from which the receiver must reject the message, if any.
+    ///
+    /// This is serialized together with the kind: see [`MessageKindAndExpiry`].
+    pub expiry: Option<Timestamp>,
     /// The message itself.
     pub message: Message,
 }
 
 /// A message together with kind, authentication and grant information.
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize, SimpleObject)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, SimpleObject)]
 pub struct PostedMessage {
     /// The user authentication carried by the message, if any.
     pub authenticated_signer: Option<Owner>,
@@ -293,6 +313,10 @@ pub struct PostedMessage {
     pub refund_grant_to: Option<Account>,
     /// The kind of message being sent.
     pub kind: MessageKind,
+    /// The time from which the receiver must reject the message, if any.
+    ///
+    /// This is serialized together with the kind: see [`MessageKindAndExpiry`].
+    pub expiry: Option<Timestamp>,
     /// The index of the message in the sending block.
     pub index: u32,
     /// The message itself.
@@ -326,6 +350,7 @@ impl OutgoingMessage {
             grant,
             refund_grant_to,
             kind,
+            expiry,
             message,
         } = self;
         PostedMessage {
@@ -333,12 +358,144 @@ impl OutgoingMessage {
             grant,
             refund_grant_to,
             kind,
+            expiry,
             index,
             message,
         }
     }
 }
 
+/// The serialized kind of a message, together with the message's expiry, if any.
+///
+/// Messages used to be serialized with a plain [`MessageKind`] in this position. The first
+/// four variants are serialized like the variants of `MessageKind`, and are used for
+/// messages without an expiry, so that these keep their format and older messages can still
+/// be read.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum MessageKindAndExpiry {
+    Simple,
+    Protected,
+    Tracked,
+    Bouncing,
+    WithExpiry(MessageKind, Timestamp),
+}
+
+impl MessageKindAndExpiry {
+    fn new(kind: MessageKind, expiry: Option<Timestamp>) -> Self {
+        match (kind, expiry) {
+            (kind, Some(expiry)) => MessageKindAndExpiry::WithExpiry(kind, expiry),
+            (MessageKind::Simple, None) => MessageKindAndExpiry::Simple,
+            (MessageKind::Protected, None) => MessageKindAndExpiry::Protected,
+            (MessageKind::Tracked, None) => MessageKindAndExpiry::Tracked,
+            (MessageKind::Bouncing, None) => MessageKindAndExpiry::Bouncing,
+        }
+    }
+
+    fn into_parts(self) -> (MessageKind, Option<Timestamp>) {
+        match self {
+            MessageKindAndExpiry::Simple => (MessageKind::Simple, None),
+            MessageKindAndExpiry::Protected => (MessageKind::Protected, None),
+            MessageKindAndExpiry::Tracked => (MessageKind::Tracked, None),
+            MessageKindAndExpiry::Bouncing => (MessageKind::Bouncing, None),
+            MessageKindAndExpiry::WithExpiry(kind, expiry) => (kind, Some(expiry)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "OutgoingMessage")]
+struct OutgoingMessageRepr<'a> {
+    destination: Cow<'a, Destination>,
+    authenticated_signer: Option<Owner>,
+    grant: Amount,
+    refund_grant_to: Option<Account>,
+    kind: MessageKindAndExpiry,
+    message: Cow<'a, Message>,
+}
+
+impl Serialize for OutgoingMessage {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        OutgoingMessageRepr {
+            destination: Cow::Borrowed(&self.destination),
+            authenticated_signer: self.authenticated_signer,
+            grant: self.grant,
+            refund_grant_to: self.refund_grant_to,
+            kind: MessageKindAndExpiry::new(self.kind, self.expiry),
+            message: Cow::Borrowed(&self.message),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for OutgoingMessage {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let repr = OutgoingMessageRepr::deserialize(deserializer)?;
+        let (kind, expiry) = repr.kind.into_parts();
+        Ok(OutgoingMessage {
+            destination: repr.destination.into_owned(),
+            authenticated_signer: repr.authenticated_signer,
+            grant: repr.grant,
+            refund_grant_to: repr.refund_grant_to,
+            kind,
+            expiry,
+            message: repr.message.into_owned(),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "PostedMessage")]
+struct PostedMessageRepr<'a> {
+    authenticated_signer: Option<Owner>,
+    grant: Amount,
+    refund_grant_to: Option<Account>,
+    kind: MessageKindAndExpiry,
+    index: u32,
+    message: Cow<'a, Message>,
+}
+
+impl Serialize for PostedMessage {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        PostedMessageRepr {
+            authenticated_signer: self.authenticated_signer,
+            grant: self.grant,
+            refund_grant_to: self.refund_grant_to,
+            kind: MessageKindAndExpiry::new(self.kind, self.expiry),
+            index: self.index,
+            message: Cow::Borrowed(&self.message),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PostedMessage {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let repr = PostedMessageRepr::deserialize(deserializer)?;
+        let (kind, expiry) = repr.kind.into_parts();
+        Ok(PostedMessage {
+            authenticated_signer: repr.authenticated_signer,
+            grant: repr.grant,
+            refund_grant_to: repr.refund_grant_to,
+            kind,
+            expiry,
+            index: repr.index,
+            message: repr.message.into_owned(),
+        })
+    }
+}
+
 /// A [`Block`], together with the outcome from its execution.
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize, SimpleObject)]
 pub struct ExecutedBlock {
@@ -435,12 +592,14 @@ pub struct LiteValue {
 struct ValueHashAndRound(CryptoHash, Round);
 
 /// A vote on a statement from a validator.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug)]
 pub struct Vote {
     pub value: HashedCertificateValue,
     pub round: Round,
     pub validator: ValidatorName,
     pub signature: Signature,
+    /// The validator's BLS signature on the same statement, if it has a BLS key.
+    pub bls_signature: Option<BlsSignature>,
 }
 
 impl Vote {
@@ -453,9 +612,18 @@ impl Vote {
             round,
             validator: ValidatorName(key_pair.public()),
             signature,
+            bls_signature: None,
         }
     }
 
+    /// Adds a BLS signature to the vote, so that it can be aggregated with other votes.
+    pub fn with_bls_signature(mut self, bls_key_pair: Option<&BlsKeyPair>) -> Self {
+        let hash_and_round = ValueHashAndRound(self.value.hash, self.round);
+        self.bls_signature =
+            bls_key_pair.map(|key_pair| BlsSignature::new(&hash_and_round, key_pair));
+        self
+    }
+
     /// Returns the vote, with a `LiteValue` instead of the full value.
     pub fn lite(&self) -> LiteVote {
         LiteVote {
@@ -463,6 +631,7 @@ impl Vote {
             round: self.round,
             validator: self.validator,
             signature: self.signature,
+            bls_signature: self.bls_signature,
         }
     }
 
@@ -473,13 +642,15 @@ impl Vote {
 }
 
 /// A vote on a statement from a validator, represented as a `LiteValue`.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug)]
 #[cfg_attr(with_testing, derive(Eq, PartialEq))]
 pub struct LiteVote {
     pub value: LiteValue,
     pub round: Round,
     pub validator: ValidatorName,
     pub signature: Signature,
+    /// The validator's BLS signature on the same statement, if it has a BLS key.
+    pub bls_signature: Option<BlsSignature>,
 }
 
 impl LiteVote {
@@ -493,12 +664,13 @@ impl LiteVote {
             round: self.round,
             validator: self.validator,
             signature: self.signature,
+            bls_signature: self.bls_signature,
         })
     }
 }
 
 /// A certified statement from the committee, without the value.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug)]
 #[cfg_attr(with_testing, derive(Eq, PartialEq))]
 pub struct LiteCertificate<'a> {
     /// Hash and chain ID of the certified value (used as key for storage).
@@ -507,6 +679,219 @@ pub struct LiteCertificate<'a> {
     pub round: Round,
     /// Signatures on the value.
     pub signatures: Cow<'a, [(ValidatorName, Signature)]>,
+    /// An aggregate BLS signature on the value, replacing the individual signatures of
+    /// its signers.
+    pub aggregate: Option<AggregateSignature>,
+}
+
+/// The BLS signatures of several validators on a value, aggregated into one signature.
+///
+/// This is the compact certificate format: its size does not grow with the number of
+/// signers beyond their names. It can only be used for validators that registered a BLS
+/// key in the committee.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AggregateSignature {
+    /// The validators whose signatures were aggregated, in strictly increasing order.
+    pub signers: Vec<ValidatorName>,
+    /// The aggregated signature.
+    pub signature: BlsSignature,
+}
+
+/// The serialized round of a vote, together with the vote's BLS signature, if any.
+///
+/// Votes used to be serialized with a plain [`Round`] in this position. The first four
+/// variants are serialized like the variants of `Round`, and are used for votes without a
+/// BLS signature, so that these keep their format and older votes can still be read.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum VoteRound {
+    Fast,
+    MultiLeader(u32),
+    SingleLeader(u32),
+    Validator(u32),
+    WithBlsSignature(Round, BlsSignature),
+}
+
+/// The serialized round of a certificate, together with its aggregate signature, if any.
+///
+/// Certificates used to be serialized with a plain [`Round`] in this position. The first
+/// four variants are serialized like the variants of `Round`, and are used for certificates
+/// without an aggregate signature, so that these keep their format and older certificates
+/// can still be read.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum CertificateRound<'a> {
+    Fast,
+    MultiLeader(u32),
+    SingleLeader(u32),
+    Validator(u32),
+    WithAggregateSignature(Round, Cow<'a, AggregateSignature>),
+}
+
+impl VoteRound {
+    fn new(round: Round, bls_signature: Option<BlsSignature>) -> Self {
+        match (round, bls_signature) {
+            (round, Some(bls_signature)) => VoteRound::WithBlsSignature(round, bls_signature),
+            (Round::Fast, None) => VoteRound::Fast,
+            (Round::MultiLeader(n), None) => VoteRound::MultiLeader(n),
+            (Round::SingleLeader(n), None) => VoteRound::SingleLeader(n),
+            (Round::Validator(n), None) => VoteRound::Validator(n),
+        }
+    }
+
+    fn into_parts(self) -> (Round, Option<BlsSignature>) {
+        match self {
+            VoteRound::Fast => (Round::Fast, None),
+            VoteRound::MultiLeader(n) => (Round::MultiLeader(n), None),
+            VoteRound::SingleLeader(n) => (Round::SingleLeader(n), None),
+            VoteRound::Validator(n) => (Round::Validator(n), None),
+            VoteRound::WithBlsSignature(round, bls_signature) => (round, Some(bls_signature)),
+        }
+    }
+}
+
+impl<'a> CertificateRound<'a> {
+    fn new(round: Round, aggregate: Option<&'a AggregateSignature>) -> Self {
+        match (round, aggregate) {
+            (round, Some(aggregate)) => {
+                CertificateRound::WithAggregateSignature(round, Cow::Borrowed(aggregate))
+            }
+            (Round::Fast, None) => CertificateRound::Fast,
+            (Round::MultiLeader(n), None) => CertificateRound::MultiLeader(n),
+            (Round::SingleLeader(n), None) => CertificateRound::SingleLeader(n),
+            (Round::Validator(n), None) => CertificateRound::Validator(n),
+        }
+    }
+
+    fn into_parts(self) -> (Round, Option<AggregateSignature>) {
+        match self {
+            CertificateRound::Fast => (Round::Fast, None),
+            CertificateRound::MultiLeader(n) => (Round::MultiLeader(n), None),
+            CertificateRound::SingleLeader(n) => (Round::SingleLeader(n), None),
+            CertificateRound::Validator(n) => (Round::Validator(n), None),
+            CertificateRound::WithAggregateSignature(round, aggregate) => {
+                (round, Some(aggregate.into_owned()))
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Vote")]
+struct VoteRepr<'a> {
+    value: Cow<'a, HashedCertificateValue>,
+    round: VoteRound,
+    validator: ValidatorName,
+    signature: Signature,
+}
+
+impl Serialize for Vote {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        VoteRepr {
+            value: Cow::Borrowed(&self.value),
+            round: VoteRound::new(self.round, self.bls_signature),
+            validator: self.validator,
+            signature: self.signature,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Vote {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let repr = VoteRepr::deserialize(deserializer)?;
+        let (round, bls_signature) = repr.round.into_parts();
+        Ok(Vote {
+            value: repr.value.into_owned(),
+            round,
+            validator: repr.validator,
+            signature: repr.signature,
+            bls_signature,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "LiteVote")]
+struct LiteVoteRepr<'a> {
+    value: Cow<'a, LiteValue>,
+    round: VoteRound,
+    validator: ValidatorName,
+    signature: Signature,
+}
+
+impl Serialize for LiteVote {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        LiteVoteRepr {
+            value: Cow::Borrowed(&self.value),
+            round: VoteRound::new(self.round, self.bls_signature),
+            validator: self.validator,
+            signature: self.signature,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for LiteVote {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let repr = LiteVoteRepr::deserialize(deserializer)?;
+        let (round, bls_signature) = repr.round.into_parts();
+        Ok(LiteVote {
+            value: repr.value.into_owned(),
+            round,
+            validator: repr.validator,
+            signature: repr.signature,
+            bls_signature,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "LiteCertificate")]
+struct LiteCertificateRepr<'a> {
+    value: Cow<'a, LiteValue>,
+    round: CertificateRound<'a>,
+    signatures: Cow<'a, [(ValidatorName, Signature)]>,
+}
+
+impl<'a> Serialize for LiteCertificate<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        LiteCertificateRepr {
+            value: Cow::Borrowed(&self.value),
+            round: CertificateRound::new(self.round, self.aggregate.as_ref()),
+            signatures: Cow::Borrowed(&self.signatures[..]),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, 'a> Deserialize<'de> for LiteCertificate<'a> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let repr = LiteCertificateRepr::deserialize(deserializer)?;
+        let (round, aggregate) = repr.round.into_parts();
+        Ok(LiteCertificate {
+            value: repr.value.into_owned(),
+            round,
+            signatures: Cow::Owned(repr.signatures.into_owned()),
+            aggregate,
+        })
+    }
 }
 
 impl<'a> LiteCertificate<'a> {
@@ -526,32 +911,68 @@ impl<'a> LiteCertificate<'a> {
             value,
             round,
             signatures,
+            aggregate: None,
         }
     }
 
     /// Creates a `LiteCertificate` from a list of votes, without cryptographically checking the
     /// signatures. Returns `None` if the votes are empty or don't have matching values and rounds.
-    pub fn try_from_votes(votes: impl IntoIterator<Item = LiteVote>) -> Option<Self> {
+    ///
+    /// If the committee accepts compact certificates, all votes carry a BLS signature and
+    /// all voters registered a BLS key in the committee, the certificate uses the compact
+    /// format with an aggregate signature. Otherwise, or if the aggregate signature turns out
+    /// to be invalid, it contains the individual signatures.
+    pub fn try_from_votes(
+        votes: impl IntoIterator<Item = LiteVote>,
+        committee: &Committee,
+    ) -> Option<Self> {
         let mut votes = votes.into_iter();
         let LiteVote {
             value,
             round,
             validator,
             signature,
+            bls_signature,
         } = votes.next()?;
         let mut signatures = vec![(validator, signature)];
+        let mut bls_signatures = bls_signature.map(|signature| vec![(validator, signature)]);
         for vote in votes {
             if vote.value.value_hash != value.value_hash || vote.round != round {
                 return None;
             }
             signatures.push((vote.validator, vote.signature));
+            match (&mut bls_signatures, vote.bls_signature) {
+                (Some(bls_signatures), Some(signature)) => {
+                    bls_signatures.push((vote.validator, signature))
+                }
+                _ => bls_signatures = None,
+            }
+        }
+        let hash_and_round = ValueHashAndRound(value.value_hash, round);
+        if let Some(aggregate) = bls_signatures
+            .filter(|_| committee.compact_certificates())
+            .and_then(|bls_signatures| AggregateSignature::new(bls_signatures, committee))
+            .filter(|aggregate| aggregate.check(&hash_and_round, committee).is_ok())
+        {
+            return Some(LiteCertificate {
+                value,
+                round,
+                signatures: Cow::Owned(Vec::new()),
+                aggregate: Some(aggregate),
+            });
         }
         Some(LiteCertificate::new(value, round, signatures))
     }
 
     /// Verifies the certificate.
     pub fn check(&self, committee: &Committee) -> Result<&LiteValue, ChainError> {
-        check_signatures(&self.value, self.round, &self.signatures, committee)?;
+        check_signatures(
+            &self.value,
+            self.round,
+            &self.signatures,
+            self.aggregate.as_ref(),
+            committee,
+        )?;
         Ok(&self.value)
     }
 
@@ -565,6 +986,7 @@ impl<'a> LiteCertificate<'a> {
             value,
             round: self.round,
             signatures: self.signatures.into_owned(),
+            aggregate: self.aggregate,
         })
     }
 
@@ -574,12 +996,59 @@ impl<'a> LiteCertificate<'a> {
             value: self.value.clone(),
             round: self.round,
             signatures: Cow::Owned(self.signatures.clone().into_owned()),
+            aggregate: self.aggregate.clone(),
         }
     }
 }
 
+impl AggregateSignature {
+    /// Aggregates the BLS signatures of the given validators. Returns `None` if one of
+    /// them has no BLS key in the committee or a signature is malformed.
+    pub fn new(
+        mut signatures: Vec<(ValidatorName, BlsSignature)>,
+        committee: &Committee,
+    ) -> Option<Self> {
+        signatures.sort_by_key(|(validator, _)| *validator);
+        if signatures
+            .iter()
+            .any(|(validator, _)| committee.bls_public_key(validator).is_none())
+        {
+            return None;
+        }
+        let signature =
+            BlsSignature::aggregate(signatures.iter().map(|(_, signature)| signature)).ok()?;
+        Some(AggregateSignature {
+            signers: signatures
+                .into_iter()
+                .map(|(validator, _)| validator)
+                .collect(),
+            signature,
+        })
+    }
+
+    /// Verifies the aggregate signature against the signers' BLS keys in the committee.
+    fn check(
+        &self,
+        hash_and_round: &ValueHashAndRound,
+        committee: &Committee,
+    ) -> Result<(), ChainError> {
+        let public_keys = self
+            .signers
+            .iter()
+            .map(|validator| {
+                committee
+                    .bls_public_key(validator)
+                    .ok_or(ChainError::MissingBlsKey(*validator))
+            })
+            .collect::<Result<Vec<BlsPublicKey>, _>>()?;
+        self.signature
+            .verify_aggregate(hash_and_round, &public_keys)?;
+        Ok(())
+    }
+}
+
 /// A certified statement from the committee.
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug)]
 #[cfg_attr(with_testing, derive(Eq, PartialEq))]
 pub struct Certificate {
     /// The certified value.
@@ -588,6 +1057,9 @@ pub struct Certificate {
     pub round: Round,
     /// Signatures on the value.
     signatures: Vec<(ValidatorName, Signature)>,
+    /// An aggregate BLS signature on the value, replacing the individual signatures of
+    /// its signers.
+    aggregate: Option<AggregateSignature>,
 }
 
 impl fmt::Display for Origin {
@@ -787,6 +1259,14 @@ impl MessageBundle {
         self.messages.iter().any(PostedMessage::is_protected)
     }
 
+    /// Returns whether any of the messages has expired at the given time, so that the
+    /// bundle must be rejected.
+    pub fn is_expired(&self, timestamp: Timestamp) -> bool {
+        self.messages
+            .iter()
+            .any(|posted_message| posted_message.is_expired(timestamp))
+    }
+
     /// Returns whether this bundle must be added to the inbox.
     ///
     /// If this is `false`, it gets handled immediately and should never be received in a block.
@@ -817,6 +1297,11 @@ impl PostedMessage {
     pub fn is_bouncing(&self) -> bool {
         matches!(self.kind, MessageKind::Bouncing)
     }
+
+    /// Returns whether the message has expired at the given time.
+    pub fn is_expired(&self, timestamp: Timestamp) -> bool {
+        self.expiry.is_some_and(|expiry| expiry <= timestamp)
+    }
 }
 
 impl ExecutedBlock {
@@ -994,6 +1479,7 @@ impl BlockProposal {
             signature,
             blobs,
             validated_block_certificate: None,
+            approvals: Vec::new(),
         }
     }
 
@@ -1021,9 +1507,19 @@ impl BlockProposal {
             signature,
             blobs,
             validated_block_certificate: Some(lite_cert),
+            approvals: Vec::new(),
         }
     }
 
+    /// Adds other owners' approvals of the proposed block.
+    pub fn with_approvals(
+        mut self,
+        approvals: impl IntoIterator<Item = (Owner, Signature)>,
+    ) -> Self {
+        self.approvals.extend(approvals);
+        self
+    }
+
     pub fn check_signature(&self, public_key: PublicKey) -> Result<(), CryptoError> {
         self.signature.check(&self.content, public_key)
     }
@@ -1039,9 +1535,18 @@ impl LiteVote {
             round,
             validator: ValidatorName(key_pair.public()),
             signature,
+            bls_signature: None,
         }
     }
 
+    /// Adds a BLS signature to the vote, so that it can be aggregated with other votes.
+    pub fn with_bls_signature(mut self, bls_key_pair: Option<&BlsKeyPair>) -> Self {
+        let hash_and_round = ValueHashAndRound(self.value.value_hash, self.round);
+        self.bls_signature =
+            bls_key_pair.map(|key_pair| BlsSignature::new(&hash_and_round, key_pair));
+        self
+    }
+
     /// Verifies the signature in the vote.
     pub fn check(&self) -> Result<(), ChainError> {
         let hash_and_round = ValueHashAndRound(self.value.value_hash, self.round);
@@ -1067,6 +1572,7 @@ impl<'a> SignatureAggregator<'a> {
                 value,
                 round,
                 signatures: Vec::new(),
+                aggregate: None,
             },
         }
     }
@@ -1109,27 +1615,51 @@ fn is_strictly_ordered(values: &[(ValidatorName, Signature)]) -> bool {
     values.windows(2).all(|pair| pair[0].0 < pair[1].0)
 }
 
+// Checks if the signers of an aggregate signature are strictly ordered.
+fn is_aggregate_strictly_ordered(aggregate: &Option<AggregateSignature>) -> bool {
+    aggregate.as_ref().map_or(true, |aggregate| {
+        aggregate.signers.windows(2).all(|pair| pair[0] < pair[1])
+    })
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Certificate")]
+struct CertificateRepr<'a> {
+    value: Cow<'a, HashedCertificateValue>,
+    round: CertificateRound<'a>,
+    signatures: Cow<'a, [(ValidatorName, Signature)]>,
+}
+
+impl Serialize for Certificate {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        CertificateRepr {
+            value: Cow::Borrowed(&self.value),
+            round: CertificateRound::new(self.round, self.aggregate.as_ref()),
+            signatures: Cow::Borrowed(&self.signatures[..]),
+        }
+        .serialize(serializer)
+    }
+}
+
 impl<'de> Deserialize<'de> for Certificate {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
-        #[derive(Debug, Deserialize)]
-        #[serde(rename = "Certificate")]
-        struct CertificateHelper {
-            value: HashedCertificateValue,
-            round: Round,
-            signatures: Vec<(ValidatorName, Signature)>,
-        }
-
-        let helper: CertificateHelper = Deserialize::deserialize(deserializer)?;
-        if !is_strictly_ordered(&helper.signatures) {
+        let repr = CertificateRepr::deserialize(deserializer)?;
+        let (round, aggregate) = repr.round.into_parts();
+        let signatures = repr.signatures.into_owned();
+        if !is_strictly_ordered(&signatures) || !is_aggregate_strictly_ordered(&aggregate) {
             Err(serde::de::Error::custom("Vector is not strictly sorted"))
         } else {
             Ok(Self {
-                value: helper.value,
-                round: helper.round,
-                signatures: helper.signatures,
+                value: repr.value.into_owned(),
+                round,
+                signatures,
+                aggregate,
             })
         }
     }
@@ -1151,13 +1681,29 @@ impl Certificate {
             value,
             round,
             signatures,
+            aggregate: None,
         }
     }
 
+    /// Sets the aggregate BLS signature of the certificate. In the compact format, this
+    /// replaces the individual signatures.
+    pub fn with_aggregate_signature(mut self, aggregate: Option<AggregateSignature>) -> Self {
+        self.aggregate = aggregate.map(|mut aggregate| {
+            aggregate.signers.sort();
+            aggregate
+        });
+        self
+    }
+
     pub fn signatures(&self) -> &Vec<(ValidatorName, Signature)> {
         &self.signatures
     }
 
+    /// Returns the aggregate BLS signature, if the certificate uses the compact format.
+    pub fn aggregate_signature(&self) -> Option<&AggregateSignature> {
+        self.aggregate.as_ref()
+    }
+
     // Adds a signature to the certificate's list of signatures
     // It's the responsibility of the caller to not insert duplicates
     pub fn add_signature(
@@ -1177,7 +1723,13 @@ impl Certificate {
         &'a self,
         committee: &Committee,
     ) -> Result<&'a HashedCertificateValue, ChainError> {
-        check_signatures(&self.lite_value(), self.round, &self.signatures, committee)?;
+        check_signatures(
+            &self.lite_value(),
+            self.round,
+            &self.signatures,
+            self.aggregate.as_ref(),
+            committee,
+        )?;
         Ok(&self.value)
     }
 
@@ -1187,6 +1739,7 @@ impl Certificate {
             value: self.lite_value(),
             round: self.round,
             signatures: Cow::Borrowed(&self.signatures),
+            aggregate: self.aggregate.clone(),
         }
     }
 
@@ -1213,6 +1766,10 @@ impl Certificate {
         self.signatures
             .binary_search_by(|(name, _)| name.cmp(validator_name))
             .is_ok()
+            || self
+                .aggregate
+                .as_ref()
+                .is_some_and(|aggregate| aggregate.signers.binary_search(validator_name).is_ok())
     }
 
     /// Returns the bundles of messages sent via the given medium to the specified
@@ -1270,12 +1827,16 @@ fn check_signatures(
     value: &LiteValue,
     round: Round,
     signatures: &[(ValidatorName, Signature)],
+    aggregate: Option<&AggregateSignature>,
     committee: &Committee,
 ) -> Result<(), ChainError> {
     // Check the quorum.
     let mut weight = 0;
     let mut used_validators = HashSet::new();
-    for (validator, _) in signatures {
+    let aggregate_signers = aggregate
+        .into_iter()
+        .flat_map(|aggregate| &aggregate.signers);
+    for validator in signatures.iter().map(|(v, _)| v).chain(aggregate_signers) {
         // Check that each validator only appears once.
         ensure!(
             !used_validators.contains(validator),
@@ -1293,12 +1854,19 @@ fn check_signatures(
     );
     // All that is left is checking signatures!
     let hash_and_round = ValueHashAndRound(value.value_hash, round);
-    Signature::verify_batch(&hash_and_round, signatures.iter().map(|(v, s)| (&v.0, s)))?;
+    if !signatures.is_empty() {
+        Signature::verify_batch(&hash_and_round, signatures.iter().map(|(v, s)| (&v.0, s)))?;
+    }
+    if let Some(aggregate) = aggregate {
+        aggregate.check(&hash_and_round, committee)?;
+    }
     Ok(())
 }
 
 impl BcsSignable for ProposalContent {}
 
+impl BcsSignable for Block {}
+
 impl BcsSignable for ValueHashAndRound {}
 
 impl BcsHashable for CertificateValue {}
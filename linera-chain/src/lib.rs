@@ -18,9 +18,9 @@ use data_types::{MessageBundle, Origin, PostedMessage};
 use linera_base::{
     crypto::{CryptoError, CryptoHash},
     data_types::{ArithmeticError, BlockHeight, Round, Timestamp},
-    identifiers::{ApplicationId, ChainId},
+    identifiers::{Account, ApplicationId, ChainId},
 };
-use linera_execution::ExecutionError;
+use linera_execution::{committee::ValidatorName, ExecutionError};
 use linera_views::views::ViewError;
 use rand_distr::WeightedError;
 use thiserror::Error;
@@ -78,6 +78,25 @@ pub enum ChainError {
         origin: Box<Origin>,
         posted_message: PostedMessage,
     },
+    #[error(
+        "Block proposed to {chain_id:?} is attempting to accept message {posted_message:?} \
+        from origin {origin:?}, which expired at {expiry}"
+    )]
+    ExpiredMessage {
+        chain_id: ChainId,
+        origin: Box<Origin>,
+        posted_message: PostedMessage,
+        expiry: Timestamp,
+    },
+    #[error(
+        "Block proposed to {chain_id:?} is attempting to refund hash lock {hash}, but \
+        {origin:?} sent a redemption before its deadline that is still in the inbox"
+    )]
+    PendingLockRedemption {
+        chain_id: ChainId,
+        origin: Box<Origin>,
+        hash: CryptoHash,
+    },
     #[error(
         "Block proposed to {chain_id:?} is attempting to skip a message bundle \
          that cannot be skipped: {bundle:?}"
@@ -129,6 +148,8 @@ pub enum ChainError {
     CertificateRequiresQuorum,
     #[error("Certificate signature verification failed: {error}")]
     CertificateSignatureVerificationFailed { error: String },
+    #[error("Validator {0} has no BLS key to verify an aggregate signature")]
+    MissingBlsKey(ValidatorName),
     #[error("Internal error {0}")]
     InternalError(String),
     #[error("Insufficient balance to pay the fees")]
@@ -141,6 +162,17 @@ pub enum ChainError {
     AuthorizedApplications(Vec<ApplicationId>),
     #[error("Missing operations or messages from mandatory applications: {0:?}")]
     MissingMandatoryApplications(Vec<ApplicationId>),
+    #[error("The account {0} is not sponsoring this chain")]
+    UnknownSponsor(Account),
+    #[error("A block can use at most one sponsor")]
+    MultipleSponsors,
+    #[error(
+        "All operations and incoming messages sponsored by {0} must be from one of the \
+        following applications: {1:?}"
+    )]
+    SponsoredApplications(Account, Vec<ApplicationId>),
+    #[error("Blocks proposed by guardians can only contain recovery operations")]
+    UnauthorizedGuardianBlock,
     #[error("Can't use grant across different broadcast messages")]
     GrantUseOnBroadcast,
     #[error("ExecutedBlock contains fewer oracle responses than requests")]
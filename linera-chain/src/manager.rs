@@ -71,7 +71,7 @@
 use std::collections::BTreeMap;
 
 use linera_base::{
-    crypto::{KeyPair, PublicKey},
+    crypto::{BlsKeyPair, KeyPair, PublicKey},
     data_types::{ArithmeticError, Blob, BlockHeight, Round, Timestamp},
     doc_scalar, ensure,
     identifiers::{BlobId, ChainId, Owner},
@@ -235,6 +235,7 @@ impl ChainManager {
         // In leader rotation mode, the round must equal the expected one exactly.
         // Only the first single-leader round can be entered at any time.
         if self.is_super(owner)
+            || self.ownership.verify_guardian(owner).is_some()
             || (new_round <= Round::SingleLeader(0) && !expected_round.is_fast())
         {
             ensure!(
@@ -288,6 +289,7 @@ impl ChainManager {
         height: BlockHeight,
         epoch: Epoch,
         key_pair: Option<&KeyPair>,
+        bls_key_pair: Option<&BlsKeyPair>,
         local_time: Timestamp,
     ) -> bool {
         let Some(key_pair) = key_pair else {
@@ -296,8 +298,11 @@ impl ChainManager {
         let Some(round_timeout) = self.round_timeout else {
             return false; // The current round does not time out.
         };
-        if local_time < round_timeout || self.ownership.owners.is_empty() {
-            return false; // Round has not timed out yet, or there are no regular owners.
+        if local_time < round_timeout
+            || (self.ownership.owners.is_empty() && self.ownership.recovery.is_none())
+        {
+            // Round has not timed out yet, or there are no regular owners or guardians.
+            return false;
         }
         let current_round = self.current_round;
         if let Some(vote) = &self.timeout_vote {
@@ -306,7 +311,8 @@ impl ChainManager {
             }
         }
         let value = HashedCertificateValue::new_timeout(chain_id, height, epoch);
-        self.timeout_vote = Some(Vote::new(value, current_round, key_pair));
+        let vote = Vote::new(value, current_round, key_pair).with_bls_signature(bls_key_pair);
+        self.timeout_vote = Some(vote);
         true
     }
 
@@ -320,6 +326,7 @@ impl ChainManager {
         height: BlockHeight,
         epoch: Epoch,
         key_pair: Option<&KeyPair>,
+        bls_key_pair: Option<&BlsKeyPair>,
     ) -> bool {
         let Some(key_pair) = key_pair else {
             return false; // We are not a validator.
@@ -329,7 +336,8 @@ impl ChainManager {
         }
         let value = HashedCertificateValue::new_timeout(chain_id, height, epoch);
         let last_regular_round = Round::SingleLeader(u32::MAX);
-        self.fallback_vote = Some(Vote::new(value, last_regular_round, key_pair));
+        let vote = Vote::new(value, last_regular_round, key_pair).with_bls_signature(bls_key_pair);
+        self.fallback_vote = Some(vote);
         true
     }
 
@@ -376,6 +384,7 @@ impl ChainManager {
         proposal: BlockProposal,
         outcome: BlockExecutionOutcome,
         key_pair: Option<&KeyPair>,
+        bls_key_pair: Option<&BlsKeyPair>,
         local_time: Timestamp,
     ) {
         // Record the proposed block, so it can be supplied to clients that request it.
@@ -409,7 +418,8 @@ impl ChainManager {
             } else {
                 HashedCertificateValue::new_validated(executed_block)
             };
-            self.pending = Some(Vote::new(value, round, key_pair));
+            let vote = Vote::new(value, round, key_pair).with_bls_signature(bls_key_pair);
+            self.pending = Some(vote);
         }
     }
 
@@ -418,6 +428,7 @@ impl ChainManager {
         &mut self,
         certificate: Certificate,
         key_pair: Option<&KeyPair>,
+        bls_key_pair: Option<&BlsKeyPair>,
         local_time: Timestamp,
     ) {
         let round = certificate.round;
@@ -435,7 +446,7 @@ impl ChainManager {
         self.update_current_round(local_time);
         if let Some(key_pair) = key_pair {
             // Vote to confirm.
-            let vote = Vote::new(value, round, key_pair);
+            let vote = Vote::new(value, round, key_pair).with_bls_signature(bls_key_pair);
             // Ok to overwrite validation votes with confirmation votes at equal or higher round.
             self.pending = Some(vote);
         }
@@ -486,10 +497,47 @@ impl ChainManager {
 
     /// Returns the public key of the block proposal's signer, if they are a valid owner and allowed
     /// to propose a block in the proposal's round.
+    ///
+    /// If the chain has an approval threshold, a regular owner's proposal must also carry valid
+    /// approvals from enough other owners, unless the block was already validated by a quorum.
+    ///
+    /// Guardians can propose blocks signed by themselves in any round except the fast one, so
+    /// that they can recover a chain whose owners are unavailable. They can skip the fast round
+    /// like super owners, and on chains with guardians the fast round also times out, so a
+    /// single-owner chain can always leave it.
     pub fn verify_owner(&self, proposal: &BlockProposal) -> Option<PublicKey> {
         if let Some(public_key) = self.ownership.super_owners.get(&proposal.owner) {
             return Some(*public_key);
         }
+        if let Some(public_key) = self.ownership.verify_guardian(&proposal.owner) {
+            let block = &proposal.content.block;
+            return (!proposal.content.round.is_fast()
+                && block.authenticated_signer == Some(proposal.owner))
+            .then_some(public_key);
+        }
+        let public_key = self.verify_round_leader(proposal)?;
+        if matches!(proposal.content.round, Round::Validator(_))
+            || proposal.validated_block_certificate.is_some()
+            || self.ownership.approval_threshold.is_none()
+        {
+            return Some(public_key);
+        }
+        let mut approvers = vec![&proposal.owner];
+        for (owner, signature) in &proposal.approvals {
+            let (approver_key, _) = self.ownership.owners.get(owner)?;
+            signature
+                .check(&proposal.content.block, *approver_key)
+                .ok()?;
+            approvers.push(owner);
+        }
+        self.ownership
+            .is_approved_by(approvers)
+            .then_some(public_key)
+    }
+
+    /// Returns the public key of the block proposal's signer, if they are a regular owner or
+    /// fallback owner allowed to propose a block in the proposal's round.
+    fn verify_round_leader(&self, proposal: &BlockProposal) -> Option<PublicKey> {
         match proposal.content.round {
             Round::Fast => {
                 None // Only super owners can propose in the first round.
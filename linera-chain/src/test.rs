@@ -126,6 +126,8 @@ impl VoteTestExt for Vote {
         let state = ValidatorState {
             network_address: "".to_string(),
             votes: 100,
+            bls_key: None,
+            reward_account: None,
         };
         let committee = Committee::new(
             vec![(self.validator, state)].into_iter().collect(),
@@ -150,6 +152,7 @@ impl<T: Into<Message>> MessageTestExt for T {
             grant: Amount::ZERO,
             refund_grant_to: None,
             kind,
+            expiry: None,
             index,
             message: self.into(),
         }
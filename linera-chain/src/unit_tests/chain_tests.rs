@@ -12,7 +12,7 @@ use linera_base::{
         Amount, ApplicationPermissions, Blob, BlockHeight, Bytecode, Timestamp,
         UserApplicationDescription,
     },
-    identifiers::{ApplicationId, BytecodeId, ChainId, MessageId},
+    identifiers::{Account, ApplicationId, BytecodeId, ChainId, Destination, MessageId},
     ownership::ChainOwnership,
 };
 use linera_execution::{
@@ -110,7 +110,7 @@ async fn test_block_size_limit() {
     let mut chain = ChainStateView::new(chain_id).await;
 
     // The size of the executed valid block below.
-    let maximum_executed_block_size = 675;
+    let maximum_executed_block_size = 681;
 
     // Initialize the chain.
     let mut config = make_open_chain_config();
@@ -122,6 +122,8 @@ async fn test_block_size_limit() {
                 ValidatorState {
                     network_address: PublicKey::test_key(1).to_string(),
                     votes: 1,
+                    bls_key: None,
+                    reward_account: None,
                 },
             )]),
             ResourceControlPolicy {
@@ -270,3 +272,163 @@ async fn test_application_permissions() {
     let valid_block = make_child_block(&value).with_operation(app_operation);
     chain.execute_block(&valid_block, time, None).await.unwrap();
 }
+
+#[tokio::test]
+async fn test_sponsored_messages() {
+    let time = Timestamp::from(0);
+    let message_id = make_admin_message_id(BlockHeight(3));
+    let chain_id = ChainId::child(message_id);
+    let mut chain = ChainStateView::new(chain_id).await;
+
+    let config = make_open_chain_config();
+    chain
+        .execute_init_message(message_id, &config, time, time)
+        .await
+        .unwrap();
+    let open_chain_message = Message::System(SystemMessage::OpenChain(config));
+    let open_chain_bundle = IncomingBundle {
+        origin: Origin::chain(admin_id()),
+        bundle: MessageBundle {
+            certificate_hash: CryptoHash::test_hash("certificate"),
+            height: BlockHeight(1),
+            transaction_index: 0,
+            timestamp: Timestamp::from(0),
+            messages: vec![open_chain_message.to_posted(0, MessageKind::Protected)],
+        },
+        action: MessageAction::Accept,
+    };
+
+    // The sponsor only pays for the operations and messages of one application.
+    let (app_description, _, _) = make_app_description();
+    let application_id = ApplicationId::from(&app_description);
+    let sponsor = Account::chain(ChainId::root(1));
+    let sponsor_message = SystemMessage::Sponsor {
+        owner: None,
+        budget: Amount::ONE,
+        applications: Some(vec![application_id]),
+    };
+    let sponsor_bundle = IncomingBundle {
+        origin: Origin::chain(sponsor.chain_id),
+        bundle: MessageBundle {
+            certificate_hash: CryptoHash::test_hash("sponsor certificate"),
+            height: BlockHeight(0),
+            transaction_index: 0,
+            timestamp: Timestamp::from(0),
+            messages: vec![sponsor_message.to_posted(0, MessageKind::Tracked)],
+        },
+        action: MessageAction::Accept,
+    };
+    let block = make_first_block(chain_id)
+        .with_incoming_bundle(open_chain_bundle)
+        .with_incoming_bundle(sponsor_bundle);
+    let outcome = chain.execute_block(&block, time, None).await.unwrap();
+    let value = HashedCertificateValue::new_confirmed(outcome.with(block));
+
+    // A sponsored block cannot receive messages from other applications.
+    let credit_message = SystemMessage::Credit {
+        target: None,
+        amount: Amount::ONE,
+        source: None,
+    };
+    let credit_bundle = IncomingBundle {
+        origin: Origin::chain(admin_id()),
+        bundle: MessageBundle {
+            certificate_hash: CryptoHash::test_hash("other certificate"),
+            height: BlockHeight(2),
+            transaction_index: 0,
+            timestamp: Timestamp::from(0),
+            messages: vec![credit_message.to_posted(0, MessageKind::Tracked)],
+        },
+        action: MessageAction::Accept,
+    };
+    let block = make_child_block(&value).with_incoming_bundle(credit_bundle);
+    let sponsored_block = block
+        .clone()
+        .with_operation(SystemOperation::UseSponsor { sponsor });
+    assert_eq!(sponsored_block.sponsor(), Some(sponsor));
+    let result = chain.execute_block(&sponsored_block, time, None).await;
+    assert_matches!(result, Err(ChainError::SponsoredApplications(account, app_ids))
+        if account == sponsor && app_ids == vec![application_id]
+    );
+
+    // A block cannot use more than one sponsor.
+    let doubly_sponsored_block = sponsored_block.with_operation(SystemOperation::UseSponsor {
+        sponsor: Account::chain(ChainId::root(2)),
+    });
+    let result = chain
+        .execute_block(&doubly_sponsored_block, time, None)
+        .await;
+    assert_matches!(result, Err(ChainError::MultipleSponsors));
+
+    // Without the sponsor, the chain pays for the message itself.
+    chain.execute_block(&block, time, None).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_expired_messages() {
+    let time = Timestamp::from(1_000);
+    let message_id = make_admin_message_id(BlockHeight(3));
+    let chain_id = ChainId::child(message_id);
+    let mut chain = ChainStateView::new(chain_id).await;
+
+    let config = make_open_chain_config();
+    chain
+        .execute_init_message(message_id, &config, time, time)
+        .await
+        .unwrap();
+    let open_chain_message = Message::System(SystemMessage::OpenChain(config));
+    let open_chain_bundle = IncomingBundle {
+        origin: Origin::chain(admin_id()),
+        bundle: MessageBundle {
+            certificate_hash: CryptoHash::test_hash("certificate"),
+            height: BlockHeight(1),
+            transaction_index: 0,
+            timestamp: Timestamp::from(0),
+            messages: vec![open_chain_message.to_posted(0, MessageKind::Protected)],
+        },
+        action: MessageAction::Accept,
+    };
+
+    // A tracked transfer that expired at the block's timestamp.
+    let credit_message = SystemMessage::Credit {
+        target: None,
+        amount: Amount::ONE,
+        source: None,
+    };
+    let mut posted_credit = credit_message.to_posted(0, MessageKind::Tracked);
+    posted_credit.expiry = Some(time);
+    let credit_bundle = IncomingBundle {
+        origin: Origin::chain(admin_id()),
+        bundle: MessageBundle {
+            certificate_hash: CryptoHash::test_hash("other certificate"),
+            height: BlockHeight(2),
+            transaction_index: 0,
+            timestamp: Timestamp::from(0),
+            messages: vec![posted_credit],
+        },
+        action: MessageAction::Accept,
+    };
+    assert!(credit_bundle.bundle.is_expired(time));
+
+    // Accepting the expired message is not allowed.
+    let invalid_block = make_first_block(chain_id)
+        .with_timestamp(time)
+        .with_incoming_bundle(open_chain_bundle.clone())
+        .with_incoming_bundle(credit_bundle.clone());
+    let result = chain.execute_block(&invalid_block, time, None).await;
+    assert_matches!(result, Err(ChainError::ExpiredMessage { expiry, .. }) if expiry == time);
+
+    // Rejecting it bounces the transfer back to the sender.
+    let valid_block = make_first_block(chain_id)
+        .with_timestamp(time)
+        .with_incoming_bundle(open_chain_bundle)
+        .with_incoming_bundle(IncomingBundle {
+            action: MessageAction::Reject,
+            ..credit_bundle
+        });
+    let outcome = chain.execute_block(&valid_block, time, None).await.unwrap();
+    let bounced = outcome.messages.iter().flatten().collect::<Vec<_>>();
+    assert_eq!(bounced.len(), 1);
+    assert_eq!(bounced[0].destination, Destination::Recipient(admin_id()));
+    assert_eq!(bounced[0].kind, MessageKind::Bouncing);
+}
@@ -2,10 +2,17 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use linera_base::data_types::Amount;
+use std::collections::BTreeMap;
+
+use linera_base::{crypto::BlsKeyPair, data_types::Amount};
+use linera_execution::{
+    committee::{BlsValidatorKey, ValidatorState},
+    system::SystemMessage,
+    ResourceControlPolicy,
+};
 
 use super::*;
-use crate::test::{make_first_block, BlockTestExt};
+use crate::test::{make_first_block, BlockTestExt, MessageTestExt};
 
 #[test]
 fn test_signed_values() {
@@ -74,3 +81,283 @@ fn test_certificates() {
         .is_none());
     assert!(builder.append(v3.validator, v3.signature).is_err());
 }
+
+#[test]
+fn test_aggregated_certificates() {
+    let keys = (0..4)
+        .map(|_| (KeyPair::generate(), BlsKeyPair::generate()))
+        .collect::<Vec<_>>();
+    let validators = keys
+        .iter()
+        .enumerate()
+        .map(|(index, (key_pair, bls_key_pair))| {
+            let state = ValidatorState {
+                network_address: index.to_string(),
+                votes: 1,
+                bls_key: Some(BlsValidatorKey::new(bls_key_pair)),
+                reward_account: None,
+            };
+            (ValidatorName(key_pair.public()), state)
+        })
+        .collect::<BTreeMap<_, _>>();
+    let committee = Committee::new(validators.clone(), ResourceControlPolicy::default())
+        .with_compact_certificates();
+    assert!(committee.compact_certificates());
+
+    let block =
+        make_first_block(ChainId::root(1)).with_simple_transfer(ChainId::root(1), Amount::ONE);
+    let executed_block = BlockExecutionOutcome {
+        messages: vec![Vec::new()],
+        state_hash: CryptoHash::test_hash("state"),
+        oracle_responses: vec![Vec::new()],
+        events: vec![Vec::new()],
+    }
+    .with(block);
+    let value = HashedCertificateValue::new_confirmed(executed_block);
+    let votes = keys
+        .iter()
+        .enumerate()
+        .map(|(index, (key_pair, bls_key_pair))| {
+            // The last validator does not sign with its BLS key.
+            LiteVote::new(value.lite(), Round::Fast, key_pair)
+                .with_bls_signature((index < 3).then_some(bls_key_pair))
+        })
+        .collect::<Vec<_>>();
+
+    // Until the committee accepts compact certificates, BLS signatures are not aggregated.
+    let transition_committee = Committee::new(validators, ResourceControlPolicy::default());
+    let lite_certificate =
+        LiteCertificate::try_from_votes(votes[..3].iter().cloned(), &transition_committee).unwrap();
+    assert!(lite_certificate.aggregate.is_none());
+    assert_eq!(lite_certificate.signatures.len(), 3);
+
+    // Votes with BLS signatures produce a compact certificate.
+    let lite_certificate =
+        LiteCertificate::try_from_votes(votes[..3].iter().cloned(), &committee).unwrap();
+    assert!(lite_certificate.signatures.is_empty());
+    assert_eq!(
+        lite_certificate.aggregate.as_ref().unwrap().signers.len(),
+        3
+    );
+    assert!(lite_certificate.check(&committee).is_ok());
+    let certificate = lite_certificate.with_value(value.clone()).unwrap();
+    assert!(certificate.check(&committee).is_ok());
+    assert!(certificate.is_signed_by(&votes[0].validator));
+    assert!(!certificate.is_signed_by(&votes[3].validator));
+
+    // The compact format is not valid for committees without the signers' BLS keys.
+    let old_committee = Committee::make_simple(votes.iter().map(|vote| vote.validator).collect());
+    assert!(certificate.check(&old_committee).is_err());
+
+    // A vote without a BLS signature falls back to individual signatures.
+    let lite_certificate =
+        LiteCertificate::try_from_votes(votes[1..].iter().cloned(), &committee).unwrap();
+    assert!(lite_certificate.aggregate.is_none());
+    assert_eq!(lite_certificate.signatures.len(), 3);
+    assert!(lite_certificate.check(&committee).is_ok());
+
+    // A forged aggregate signature is rejected.
+    let mut aggregate = certificate.aggregate_signature().unwrap().clone();
+    aggregate.signers.pop();
+    let forged = Certificate::new(value, Round::Fast, Vec::new())
+        .with_aggregate_signature(Some(aggregate.clone()));
+    assert!(forged.check(&committee).is_err());
+
+    // The aggregate signature must still form a quorum.
+    aggregate.signature = BlsSignature::aggregate(
+        votes[..2]
+            .iter()
+            .map(|vote| vote.bls_signature.as_ref().unwrap()),
+    )
+    .unwrap();
+    let lite_certificate = LiteCertificate {
+        aggregate: Some(aggregate),
+        ..LiteCertificate::new(certificate.lite_value(), Round::Fast, Vec::new())
+    };
+    assert!(matches!(
+        lite_certificate.check(&committee),
+        Err(ChainError::CertificateRequiresQuorum)
+    ));
+}
+
+#[test]
+fn test_legacy_vote_and_certificate_formats() {
+    // Votes and certificates as they were serialized before BLS signatures were introduced.
+    #[derive(Serialize)]
+    struct LegacyLiteVote {
+        value: LiteValue,
+        round: Round,
+        validator: ValidatorName,
+        signature: Signature,
+    }
+
+    #[derive(Serialize)]
+    struct LegacyCertificate<T> {
+        value: T,
+        round: Round,
+        signatures: Vec<(ValidatorName, Signature)>,
+    }
+
+    let key_pair = KeyPair::generate();
+    let block =
+        make_first_block(ChainId::root(1)).with_simple_transfer(ChainId::root(1), Amount::ONE);
+    let executed_block = BlockExecutionOutcome {
+        messages: vec![Vec::new()],
+        state_hash: CryptoHash::test_hash("state"),
+        oracle_responses: vec![Vec::new()],
+        events: vec![Vec::new()],
+    }
+    .with(block);
+    let value = HashedCertificateValue::new_confirmed(executed_block);
+    let rounds = [
+        Round::Fast,
+        Round::MultiLeader(1),
+        Round::SingleLeader(2),
+        Round::Validator(3),
+    ];
+    for round in rounds {
+        let vote = LiteVote::new(value.lite(), round, &key_pair);
+        let legacy_bytes = bcs::to_bytes(&LegacyLiteVote {
+            value: value.lite(),
+            round,
+            validator: vote.validator,
+            signature: vote.signature,
+        })
+        .unwrap();
+        assert_eq!(bcs::from_bytes::<LiteVote>(&legacy_bytes).unwrap(), vote);
+        assert_eq!(bcs::to_bytes(&vote).unwrap(), legacy_bytes);
+
+        let signatures = vec![(vote.validator, vote.signature)];
+        let certificate = Certificate::new(value.clone(), round, signatures.clone());
+        let legacy_bytes = bcs::to_bytes(&LegacyCertificate {
+            value: value.clone(),
+            round,
+            signatures: signatures.clone(),
+        })
+        .unwrap();
+        assert_eq!(
+            bcs::from_bytes::<Certificate>(&legacy_bytes).unwrap(),
+            certificate
+        );
+        assert_eq!(bcs::to_bytes(&certificate).unwrap(), legacy_bytes);
+
+        let lite_certificate = certificate.lite_certificate();
+        let legacy_bytes = bcs::to_bytes(&LegacyCertificate {
+            value: value.lite(),
+            round,
+            signatures,
+        })
+        .unwrap();
+        assert_eq!(
+            bcs::from_bytes::<LiteCertificate>(&legacy_bytes).unwrap(),
+            lite_certificate
+        );
+        assert_eq!(bcs::to_bytes(&lite_certificate).unwrap(), legacy_bytes);
+    }
+
+    // Votes and certificates with BLS signatures use the new format.
+    let bls_key_pair = BlsKeyPair::generate();
+    let vote =
+        LiteVote::new(value.lite(), Round::Fast, &key_pair).with_bls_signature(Some(&bls_key_pair));
+    let bytes = bcs::to_bytes(&vote).unwrap();
+    assert_eq!(bcs::from_bytes::<LiteVote>(&bytes).unwrap(), vote);
+    let aggregate = AggregateSignature {
+        signers: vec![vote.validator],
+        signature: vote.bls_signature.unwrap(),
+    };
+    let certificate = Certificate::new(value, Round::Validator(3), Vec::new())
+        .with_aggregate_signature(Some(aggregate));
+    let bytes = bcs::to_bytes(&certificate).unwrap();
+    assert_eq!(bcs::from_bytes::<Certificate>(&bytes).unwrap(), certificate);
+    let bytes = bcs::to_bytes(&certificate.lite_certificate()).unwrap();
+    assert_eq!(
+        bcs::from_bytes::<LiteCertificate>(&bytes).unwrap(),
+        certificate.lite_certificate()
+    );
+}
+
+#[test]
+fn test_legacy_message_formats() {
+    // Messages as they were serialized before they could expire.
+    #[derive(Serialize)]
+    struct LegacyOutgoingMessage {
+        destination: Destination,
+        authenticated_signer: Option<Owner>,
+        grant: Amount,
+        refund_grant_to: Option<Account>,
+        kind: MessageKind,
+        message: Message,
+    }
+
+    #[derive(Serialize)]
+    struct LegacyPostedMessage {
+        authenticated_signer: Option<Owner>,
+        grant: Amount,
+        refund_grant_to: Option<Account>,
+        kind: MessageKind,
+        index: u32,
+        message: Message,
+    }
+
+    let credit = SystemMessage::Credit {
+        target: None,
+        amount: Amount::ONE,
+        source: None,
+    };
+    let kinds = [
+        MessageKind::Simple,
+        MessageKind::Protected,
+        MessageKind::Tracked,
+        MessageKind::Bouncing,
+    ];
+    for kind in kinds {
+        let outgoing = OutgoingMessage {
+            destination: Destination::Recipient(ChainId::root(2)),
+            authenticated_signer: None,
+            grant: Amount::ZERO,
+            refund_grant_to: None,
+            kind,
+            expiry: None,
+            message: credit.clone().into(),
+        };
+        let legacy_bytes = bcs::to_bytes(&LegacyOutgoingMessage {
+            destination: outgoing.destination.clone(),
+            authenticated_signer: None,
+            grant: Amount::ZERO,
+            refund_grant_to: None,
+            kind,
+            message: outgoing.message.clone(),
+        })
+        .unwrap();
+        assert_eq!(
+            bcs::from_bytes::<OutgoingMessage>(&legacy_bytes).unwrap(),
+            outgoing
+        );
+        assert_eq!(bcs::to_bytes(&outgoing).unwrap(), legacy_bytes);
+
+        let posted = outgoing.into_posted(3);
+        let legacy_bytes = bcs::to_bytes(&LegacyPostedMessage {
+            authenticated_signer: None,
+            grant: Amount::ZERO,
+            refund_grant_to: None,
+            kind,
+            index: 3,
+            message: posted.message.clone(),
+        })
+        .unwrap();
+        assert_eq!(
+            bcs::from_bytes::<PostedMessage>(&legacy_bytes).unwrap(),
+            posted
+        );
+        assert_eq!(bcs::to_bytes(&posted).unwrap(), legacy_bytes);
+    }
+
+    // Messages with an expiry use the new format.
+    let posted = credit.to_posted(0, MessageKind::Tracked);
+    let posted = PostedMessage {
+        expiry: Some(Timestamp::from(1_000)),
+        ..posted
+    };
+    let bytes = bcs::to_bytes(&posted).unwrap();
+    assert_eq!(bcs::from_bytes::<PostedMessage>(&bytes).unwrap(), posted);
+}
@@ -14,7 +14,7 @@ use linera_base::{
     data_types::Timestamp,
     identifiers::{ChainId, Destination},
 };
-use linera_chain::data_types::OutgoingMessage;
+use linera_chain::data_types::{Medium, OutgoingMessage};
 use linera_core::{
     client::{ChainClient, ChainClientError},
     node::ValidatorNodeProvider,
@@ -168,6 +168,10 @@ impl ChainListener {
         let (listener, _listen_handle, mut local_stream) = client.listen().await?;
         client.synchronize_from_validators().await?;
         drop(linera_base::task::spawn(listener.in_current_span()));
+        // Catch up with the committees that were created while we were not listening.
+        Self::migrate_epoch(&client).await;
+        context.lock().await.update_wallet(&client).await?;
+        let admin_id = client.state().admin_id();
         let mut timeout = storage.clock().current_time();
         loop {
             let sleep = Box::pin(storage.clock().sleep_until(timeout));
@@ -203,7 +207,15 @@ impl ChainListener {
             info!("Received new notification: {:?}", notification);
             Self::maybe_sleep(config.delay_before_ms).await;
             match &notification.reason {
-                Reason::NewIncomingBundle { .. } => timeout = storage.clock().current_time(),
+                Reason::NewIncomingBundle { origin, .. } => {
+                    // Committee changes are received right away, even if the rest of the
+                    // inbox is not processed automatically.
+                    if origin.sender == admin_id && matches!(origin.medium, Medium::Channel(_)) {
+                        Self::migrate_epoch(&client).await;
+                        context.lock().await.update_wallet(&client).await?;
+                    }
+                    timeout = storage.clock().current_time();
+                }
                 Reason::NewBlock { .. } | Reason::NewRound { .. } => {
                     if let Err(error) = client.update_validators().await {
                         warn!(
@@ -274,6 +286,36 @@ impl ChainListener {
         Ok(())
     }
 
+    /// Receives the pending committee changes on the chain, and reports if that fails.
+    async fn migrate_epoch<P, S>(client: &ChainClient<P, S>)
+    where
+        P: ValidatorNodeProvider + Sync + 'static,
+        S: Storage + Clone + Send + Sync + 'static,
+    {
+        match client.migrate_epoch().await {
+            Err(ChainClientError::CannotFindKeyForChain(_)) => {}
+            Err(error) => error!(
+                %error,
+                "Failed to migrate chain {} to the latest epoch.", client.chain_id()
+            ),
+            Ok((certs, None)) if certs.is_empty() => {}
+            Ok((certs, None)) => {
+                info!(
+                    "Migrated to the latest epoch. {} blocks created.",
+                    certs.len()
+                );
+            }
+            Ok((certs, Some(timeout))) => {
+                warn!(
+                    "{} blocks created. Chain {} could not be migrated to the latest epoch \
+                     before the round timeout: {timeout:?}",
+                    certs.len(),
+                    client.chain_id(),
+                );
+            }
+        }
+    }
+
     async fn maybe_sleep(delay_ms: u64) {
         if delay_ms > 0 {
             linera_base::time::timer::sleep(Duration::from_millis(delay_ms)).await;
@@ -216,10 +216,18 @@ where
             chain.pending_block.clone(),
             chain.pending_blobs.clone(),
         );
+        let rules = self
+            .options
+            .message_policy_config
+            .as_ref()
+            .map(|config| config.rules.clone())
+            .unwrap_or_default();
         chain_client.options_mut().message_policy = MessagePolicy::new(
             self.options.blanket_message_policy,
             self.options.restrict_chain_ids_to.clone(),
-        );
+        )
+        .with_rules(rules);
+        chain_client.options_mut().sponsor = self.options.sponsor;
         Ok(chain_client)
     }
 
@@ -5,16 +5,17 @@ use std::{collections::HashSet, env, fmt, iter, num::NonZeroU16, path::PathBuf,
 
 use chrono::{DateTime, Utc};
 use linera_base::{
-    crypto::{CryptoHash, PublicKey},
+    crypto::{BlsPublicKey, BlsSignature, CryptoHash, PublicKey},
     data_types::{Amount, ApplicationPermissions, TimeDelta},
     identifiers::{
         Account, ApplicationId, BytecodeId, ChainId, MessageId, Owner, UserApplicationId,
     },
-    ownership::{ChainOwnership, TimeoutConfig},
+    ownership::{ChainOwnership, RecoveryConfig, TimeoutConfig},
 };
-use linera_core::client::BlanketMessagePolicy;
+use linera_core::client::{BlanketMessagePolicy, MessagePolicyConfig};
 use linera_execution::{
-    committee::ValidatorName, ResourceControlPolicy, WasmRuntime, WithWasmDefault as _,
+    committee::{Epoch, ValidatorName},
+    ResourceControlPolicy, WasmRuntime, WithWasmDefault as _,
 };
 use linera_views::store::CommonStoreConfig;
 
@@ -146,6 +147,15 @@ pub struct ClientOptions {
     /// an empty string.
     #[arg(long, value_parser = util::parse_chain_set)]
     pub restrict_chain_ids_to: Option<HashSet<ChainId>>,
+
+    /// A JSON file with rules that override the blanket policy for the incoming messages
+    /// they match, e.g. by sender, application, grant or size, and set their priorities.
+    #[arg(long, value_parser = util::read_message_policy_config)]
+    pub message_policy_config: Option<MessagePolicyConfig>,
+
+    /// An account that sponsors our chains and pays for the fees of the proposed blocks.
+    #[arg(long)]
+    pub sponsor: Option<Account>,
 }
 
 impl ClientOptions {
@@ -353,6 +363,34 @@ pub enum ClientCommand {
         ownership_config: ChainOwnershipConfig,
     },
 
+    /// Vote to transfer a chain to a new owner, as one of its guardians.
+    ///
+    /// Once enough guardians agree on the same new owner, the recovery can be completed
+    /// after the recovery delay, unless an owner cancels it.
+    InitiateRecovery {
+        /// The ID of the chain to recover.
+        #[arg(long)]
+        chain_id: Option<ChainId>,
+
+        /// Public key of the new owner
+        new_public_key: PublicKey,
+    },
+
+    /// Cancel the recovery initiated by the guardians of a chain, as one of its owners.
+    CancelRecovery {
+        /// The ID of the chain whose recovery will be canceled.
+        #[arg(long)]
+        chain_id: Option<ChainId>,
+    },
+
+    /// Transfer a chain to the new owner approved by its guardians, once the recovery delay
+    /// is over.
+    CompleteRecovery {
+        /// The ID of the chain to recover.
+        #[arg(long)]
+        chain_id: Option<ChainId>,
+    },
+
     /// Changes the application permissions configuration.
     ChangeApplicationPermissions {
         /// The ID of the chain to which the new permissions will be applied.
@@ -373,6 +411,177 @@ pub enum ClientCommand {
         chain_id: ChainId,
     },
 
+    /// Set aside a budget to pay for the fees of another chain.
+    ///
+    /// The budget is used by the blocks of the beneficiary chain that name the sponsoring
+    /// account, e.g. with the `--sponsor` option.
+    Sponsor {
+        /// Sponsoring account (must be one of our chains)
+        #[arg(long = "from")]
+        sponsor: Account,
+
+        /// The chain whose fees are paid
+        #[arg(long)]
+        beneficiary: ChainId,
+
+        /// If present, only operations from the specified applications are sponsored, and
+        /// no system operations. Otherwise all operations are sponsored.
+        #[arg(long)]
+        applications: Option<Vec<ApplicationId>>,
+
+        /// Amount set aside for the fees
+        budget: Amount,
+    },
+
+    /// Revoke the sponsorship of another chain and return the remaining budget.
+    RevokeSponsorship {
+        /// Sponsoring account (must be one of our chains)
+        #[arg(long = "from")]
+        sponsor: Account,
+
+        /// The sponsored chain
+        beneficiary: ChainId,
+    },
+
+    /// Lock funds until a secret is revealed, for a cross-chain atomic swap.
+    ///
+    /// The funds are released to the recipient if the preimage of the hash is revealed to
+    /// the sending chain before the timeout, e.g. with `redeem-lock`. Afterwards, they can
+    /// only be refunded with `refund-lock`.
+    LockFunds {
+        /// Sending account (must be one of our chains)
+        #[arg(long = "from")]
+        sender: Account,
+
+        /// Recipient account
+        #[arg(long = "to")]
+        recipient: Account,
+
+        /// The hash of the secret that redeems the lock
+        #[arg(long, required_unless_present = "secret")]
+        hash: Option<CryptoHash>,
+
+        /// The secret that redeems the lock, if we generated it
+        #[arg(long, conflicts_with = "hash")]
+        secret: Option<String>,
+
+        /// How long the lock can be redeemed, in milliseconds.
+        #[arg(long = "timeout-ms", value_parser = util::parse_millis_delta)]
+        timeout: TimeDelta,
+
+        /// Amount to lock
+        amount: Amount,
+    },
+
+    /// Reveal a secret to another chain to redeem its lock with the matching hash.
+    RedeemLock {
+        /// The chain sending the secret (must be one of our chains)
+        #[arg(long = "from")]
+        chain_id: Option<ChainId>,
+
+        /// The chain where the funds are locked
+        #[arg(long)]
+        target: ChainId,
+
+        /// The secret that redeems the lock
+        secret: String,
+    },
+
+    /// Return the funds of an expired lock to the account that locked them.
+    RefundLock {
+        /// The chain where the funds are locked (must be one of our chains)
+        #[arg(long = "from")]
+        chain_id: Option<ChainId>,
+
+        /// The hash of the secret that would have redeemed the lock
+        hash: CryptoHash,
+    },
+
+    /// Claim a validator's share of the fees collected in an epoch.
+    ///
+    /// The block must be signed with the validator's key. The rewards are sent to the
+    /// reward account registered for the validator in that epoch's committee.
+    ClaimValidatorRewards {
+        /// Chain ID (must be one of our chains, owned by the validator's key)
+        #[arg(long = "from")]
+        chain_id: Option<ChainId>,
+
+        /// The epoch whose rewards are claimed
+        #[arg(long)]
+        epoch: Epoch,
+    },
+
+    /// Register a validator candidate for the stake-based committee election, bonding its
+    /// initial stake from the chain's balance.
+    ///
+    /// The block must be signed with the validator's key.
+    RegisterValidator {
+        /// Chain ID (must be one of our chains, owned by the validator's key)
+        #[arg(long = "from")]
+        chain_id: Option<ChainId>,
+
+        /// The public key of the validator.
+        #[arg(long)]
+        name: ValidatorName,
+
+        /// Network address
+        #[arg(long)]
+        address: String,
+
+        /// The initial stake of the validator
+        #[arg(long)]
+        stake: Amount,
+
+        /// The BLS public key of the validator, used for aggregated certificates
+        #[arg(long, requires = "bls_proof_of_possession")]
+        bls_public_key: Option<BlsPublicKey>,
+
+        /// The validator's proof of possession of its BLS secret key
+        #[arg(long, requires = "bls_public_key")]
+        bls_proof_of_possession: Option<BlsSignature>,
+
+        /// The account that receives the validator's share of the fees
+        #[arg(long)]
+        reward_account: Option<Account>,
+    },
+
+    /// Bond stake from the chain's balance to a registered validator, on behalf of the
+    /// chain's owner.
+    Bond {
+        /// Chain ID (must be one of our chains)
+        #[arg(long = "from")]
+        chain_id: Option<ChainId>,
+
+        /// The public key of the validator.
+        #[arg(long)]
+        validator: ValidatorName,
+
+        /// Amount to bond
+        amount: Amount,
+    },
+
+    /// Unbond some of the chain owner's stake from a validator. The stake can be withdrawn
+    /// after the unbonding period.
+    Unbond {
+        /// Chain ID (must be one of our chains)
+        #[arg(long = "from")]
+        chain_id: Option<ChainId>,
+
+        /// The public key of the validator.
+        #[arg(long)]
+        validator: ValidatorName,
+
+        /// Amount to unbond
+        amount: Amount,
+    },
+
+    /// Withdraw the chain owner's unbonded stake whose unbonding period is over.
+    WithdrawUnbonded {
+        /// Chain ID (must be one of our chains)
+        #[arg(long = "from")]
+        chain_id: Option<ChainId>,
+    },
+
     /// Read the current native-token balance of the given account directly from the local
     /// state.
     ///
@@ -425,6 +634,13 @@ pub enum ClientCommand {
         chain_id: Option<ChainId>,
     },
 
+    /// Receive the pending committee changes from the admin chain, without processing the
+    /// rest of the inbox, so that the chain can use the latest epoch.
+    MigrateEpoch {
+        /// The chain to migrate. If omitted, migrates all the chains we own in the wallet.
+        chain_id: Option<ChainId>,
+    },
+
     /// Show the version and genesis config hash of a new validator, and print a warning if it is
     /// incompatible.
     QueryValidator {
@@ -452,6 +668,18 @@ pub enum ClientCommand {
         #[arg(long, default_value = "1")]
         votes: u64,
 
+        /// The BLS public key of the validator, used for aggregated certificates
+        #[arg(long, requires = "bls_proof_of_possession")]
+        bls_public_key: Option<BlsPublicKey>,
+
+        /// The validator's proof of possession of its BLS secret key
+        #[arg(long, requires = "bls_public_key")]
+        bls_proof_of_possession: Option<BlsSignature>,
+
+        /// The account that receives the validator's share of the fees
+        #[arg(long)]
+        reward_account: Option<Account>,
+
         /// Skip the version and genesis config checks.
         #[arg(long)]
         skip_online_check: bool,
@@ -467,6 +695,29 @@ pub enum ClientCommand {
     /// Deprecates all committees except the last one.
     FinalizeCommittee,
 
+    /// Enable the stake-based committee election, or change its parameters (admin only)
+    ConfigureStaking {
+        /// The minimum total stake that a validator needs to be elected.
+        #[arg(long)]
+        minimum_stake: Amount,
+
+        /// The amount of stake that corresponds to one vote.
+        #[arg(long)]
+        stake_per_vote: Amount,
+
+        /// How long unbonded stake remains locked before it can be withdrawn, in
+        /// milliseconds.
+        #[arg(
+            long = "unbonding-period-ms",
+            default_value = "604800000", // 1 week
+            value_parser = util::parse_millis_delta
+        )]
+        unbonding_period: TimeDelta,
+    },
+
+    /// Create a new committee from the validators' current stakes (admin only)
+    ElectCommittee,
+
     /// View or update the resource control policy
     ResourceControlPolicy {
         /// Set the base price for creating a block.
@@ -528,6 +779,25 @@ pub enum ClientCommand {
         /// Set the maximum write data per block.
         #[arg(long)]
         maximum_bytes_written_per_block: Option<u64>,
+
+        /// Submit the changes as a proposal from the given chain, to be voted on by the
+        /// validators, instead of creating a new committee. The chain must be owned by a
+        /// validator's key.
+        #[arg(long)]
+        propose_from: Option<ChainId>,
+    },
+
+    /// Vote for a proposed change to the resource control policy, as a validator.
+    ///
+    /// The block must be signed with the validator's key. Once a quorum of validators voted
+    /// for the proposal, the new policy is applied in a new epoch.
+    VotePolicy {
+        /// Chain ID (must be one of our chains, owned by the validator's key)
+        #[arg(long = "from")]
+        chain_id: Option<ChainId>,
+
+        /// The ID of the proposal
+        proposal_id: u64,
     },
 
     /// Send one transfer per chain in bulk mode
@@ -550,6 +820,11 @@ pub enum ClientCommand {
         #[arg(long, default_value = "1")]
         transactions_per_block: usize,
 
+        /// How many block proposals or certificates to send in each request. The
+        /// validators split batches across their shards.
+        #[arg(long, default_value = "1")]
+        batch_size: usize,
+
         /// The application ID of a fungible token on the wallet's default chain.
         /// If none is specified, the benchmark uses the native token.
         #[arg(long)]
@@ -1097,6 +1372,29 @@ pub struct ChainOwnershipConfig {
         value_parser = util::parse_millis_delta
     )]
     pub fallback_duration: TimeDelta,
+
+    /// If set, every block must be approved by regular owners whose weights add up to at
+    /// least this value, including the owner who proposes it.
+    #[arg(long)]
+    approval_threshold: Option<u64>,
+
+    /// Public keys of the guardians who can jointly transfer the chain to a new owner.
+    #[arg(long, num_args(0..))]
+    guardian_public_keys: Vec<PublicKey>,
+
+    /// The number of guardians who must agree on a new owner. By default, all guardians
+    /// must agree.
+    #[arg(long)]
+    recovery_threshold: Option<u32>,
+
+    /// The time the owners have to cancel a recovery initiated by the guardians, in
+    /// milliseconds.
+    #[arg(
+        long = "recovery-delay-ms",
+        default_value = "604800000", // 1 week
+        value_parser = util::parse_millis_delta
+    )]
+    recovery_delay: TimeDelta,
 }
 
 impl TryFrom<ChainOwnershipConfig> for ChainOwnership {
@@ -1112,6 +1410,10 @@ impl TryFrom<ChainOwnershipConfig> for ChainOwnership {
             base_timeout,
             timeout_increment,
             fallback_duration,
+            approval_threshold,
+            guardian_public_keys,
+            recovery_threshold,
+            recovery_delay,
         } = config;
         if !owner_weights.is_empty() && owner_weights.len() != owner_public_keys.len() {
             return Err(Error::MisalignedWeights {
@@ -1135,11 +1437,17 @@ impl TryFrom<ChainOwnershipConfig> for ChainOwnership {
             timeout_increment,
             fallback_duration,
         };
+        let recovery = (!guardian_public_keys.is_empty()).then(|| {
+            let threshold = recovery_threshold.unwrap_or(guardian_public_keys.len() as u32);
+            RecoveryConfig::new(guardian_public_keys, threshold, recovery_delay)
+        });
         Ok(ChainOwnership {
             super_owners,
             owners,
             multi_leader_rounds,
             timeout_config,
+            approval_threshold,
+            recovery,
         })
     }
 }
@@ -8,12 +8,12 @@ use std::{
 };
 
 use linera_base::{
-    crypto::{BcsSignable, CryptoHash, CryptoRng, KeyPair, PublicKey},
+    crypto::{BcsSignable, BlsKeyPair, CryptoHash, CryptoRng, KeyPair, PublicKey},
     data_types::{Amount, Timestamp},
     identifiers::{ChainDescription, ChainId},
 };
 use linera_execution::{
-    committee::{Committee, ValidatorName, ValidatorState},
+    committee::{BlsValidatorKey, Committee, ValidatorName, ValidatorState},
     ResourceControlPolicy,
 };
 use linera_rpc::config::{ValidatorInternalNetworkConfig, ValidatorPublicNetworkConfig};
@@ -26,6 +26,8 @@ pub enum Error {
     Io(#[from] std::io::Error),
     #[error("chain error: {0}")]
     Chain(#[from] linera_chain::ChainError),
+    #[error("invalid BLS key in the genesis committee: {0}")]
+    InvalidBlsKey(linera_base::crypto::CryptoError),
     #[error("persistence error: {0}")]
     Persistence(Box<dyn std::error::Error + Send + Sync>),
 }
@@ -48,6 +50,9 @@ pub struct ValidatorConfig {
     pub name: ValidatorName,
     /// The network configuration for the validator.
     pub network: ValidatorPublicNetworkConfig,
+    /// The BLS key of the validator, used for aggregated certificates.
+    #[serde(default)]
+    pub bls_key: Option<BlsValidatorKey>,
 }
 
 /// The private configuration of a validator service.
@@ -55,6 +60,10 @@ pub struct ValidatorConfig {
 pub struct ValidatorServerConfig {
     pub validator: ValidatorConfig,
     pub key: KeyPair,
+    /// The BLS key-pair used to sign votes that can be aggregated into compact
+    /// certificates.
+    #[serde(default)]
+    pub bls_key: Option<BlsKeyPair>,
     pub internal_network: ValidatorInternalNetworkConfig,
 }
 
@@ -80,6 +89,8 @@ impl CommitteeConfig {
                     ValidatorState {
                         network_address: v.network.to_string(),
                         votes: 100,
+                        bls_key: v.bls_key,
+                        reward_account: None,
                     },
                 )
             })
@@ -214,6 +225,7 @@ impl GenesisConfig {
         S: Storage + Clone + Send + Sync + 'static,
     {
         let committee = self.create_committee();
+        committee.check_bls_keys().map_err(Error::InvalidBlsKey)?;
         for (chain_number, (public_key, balance)) in (0..).zip(&self.chains) {
             let description = ChainDescription::Root(chain_number);
             storage
@@ -231,7 +243,10 @@ impl GenesisConfig {
     }
 
     pub fn create_committee(&self) -> Committee {
-        self.committee.clone().into_committee(self.policy.clone())
+        self.committee
+            .clone()
+            .into_committee(self.policy.clone())
+            .with_compact_certificates()
     }
 
     pub fn hash(&self) -> CryptoHash {
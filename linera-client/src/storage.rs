@@ -335,10 +335,8 @@ impl StorageConfigNamespace {
             #[cfg(feature = "storage-service")]
             StorageConfig::Service { endpoint } => {
                 let endpoint = endpoint.clone();
-                let config = ServiceStoreConfig {
-                    endpoint,
-                    common_config,
-                };
+                let config =
+                    ServiceStoreConfig::new(endpoint, common_config).with_security_from_env()?;
                 Ok(StoreConfig::Service(config, namespace))
             }
             StorageConfig::Memory => {
@@ -3,7 +3,7 @@
 
 #![allow(clippy::large_futures)]
 
-use std::{collections::BTreeMap, sync::Arc};
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
 
 use async_trait::async_trait;
 use futures::{lock::Mutex, FutureExt as _};
@@ -18,7 +18,11 @@ use linera_core::{
     node::CrossChainMessageDelivery,
     test_utils::{MemoryStorageBuilder, NodeProvider, StorageBuilder as _, TestBuilder},
 };
-use linera_execution::{system::Recipient, ResourceControlPolicy};
+use linera_execution::{
+    committee::{Committee, Epoch},
+    system::Recipient,
+    ResourceControlPolicy,
+};
 use linera_rpc::{
     config::{NetworkProtocol, ValidatorPublicNetworkPreConfig},
     simple::TransportProtocol,
@@ -119,6 +123,7 @@ fn make_genesis_config(builder: &TestBuilder<MemoryStorageBuilder>) -> GenesisCo
         .map(|name| ValidatorConfig {
             name: *name,
             network: network.clone(),
+            bls_key: None,
         })
         .collect();
     let mut genesis_config = GenesisConfig::new(
@@ -203,3 +208,78 @@ async fn test_chain_listener() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Tests that the chain listener migrates its chains to a new epoch as soon as the committee is
+/// created, without processing the rest of the inbox.
+#[test_log::test(tokio::test)]
+async fn test_chain_listener_migrates_epoch() -> anyhow::Result<()> {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+    let config = ChainListenerConfig {
+        skip_process_inbox: true,
+        ..ChainListenerConfig::default()
+    };
+    let storage_builder = MemoryStorageBuilder::default();
+    let clock = storage_builder.clock().clone();
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let description0 = ChainDescription::Root(0);
+    let description1 = ChainDescription::Root(1);
+    let chain_id1 = ChainId::from(description1);
+    let client0 = builder
+        .add_initial_chain(description0, Amount::from_tokens(3))
+        .await?;
+    let client1 = builder
+        .add_initial_chain(description1, Amount::ZERO)
+        .await?;
+
+    // Start a chain listener for chain 1 with a new key, and transfer the chain to it.
+    let genesis_config = make_genesis_config(&builder);
+    let storage = builder.make_storage().await?;
+    let delivery = CrossChainMessageDelivery::NonBlocking;
+    let mut context = ClientContext {
+        wallet: Wallet::new(genesis_config, Some(37)),
+        client: Arc::new(Client::new(
+            builder.make_node_provider(),
+            storage.clone(),
+            10,
+            delivery,
+            false,
+            [chain_id1],
+            format!("Client node for {:.8}", chain_id1),
+        )),
+    };
+    let key_pair = KeyPair::generate_from(&mut rng);
+    let public_key = key_pair.public();
+    client1
+        .change_ownership(ChainOwnership::single(public_key))
+        .await?;
+    context
+        .update_wallet_for_new_chain(chain_id1, Some(key_pair), clock.current_time())
+        .await?;
+    let context = Arc::new(Mutex::new(context));
+    let listener = ChainListener::new(config);
+    listener.run(context, storage).await;
+
+    // The admin chain sends a token to chain 1, and then creates a new committee.
+    client0
+        .transfer(None, Amount::ONE, Recipient::chain(chain_id1))
+        .await?;
+    let validators = builder.initial_committee.validators().clone();
+    let committee = Committee::new(validators, ResourceControlPolicy::default());
+    client0.stage_new_committee(committee).await?;
+
+    // The listener should eventually receive the new committee, but not the token.
+    for i in 0.. {
+        client1.synchronize_from_validators().boxed().await?;
+        let info = client1.chain_info().await?;
+        if info.epoch == Some(Epoch::from(1)) {
+            assert_eq!(info.chain_balance, Amount::ZERO);
+            break;
+        }
+        linera_base::time::timer::sleep(Duration::from_millis(100)).await;
+        if i == 30 {
+            panic!("Chain 1 was not migrated: {:?}", info.epoch);
+        }
+    }
+
+    Ok(())
+}
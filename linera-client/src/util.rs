@@ -1,7 +1,7 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{collections::HashSet, num::ParseIntError, str::FromStr};
+use std::{collections::HashSet, fs::File, io, num::ParseIntError, str::FromStr};
 
 use futures::future;
 use linera_base::{
@@ -10,7 +10,9 @@ use linera_base::{
     identifiers::ChainId,
     time::Duration,
 };
-use linera_core::{data_types::RoundTimeout, node::NotificationStream, worker::Reason};
+use linera_core::{
+    client::MessagePolicyConfig, data_types::RoundTimeout, node::NotificationStream, worker::Reason,
+};
 use tokio_stream::StreamExt as _;
 
 pub fn parse_millis(s: &str) -> Result<Duration, ParseIntError> {
@@ -21,6 +23,11 @@ pub fn parse_millis_delta(s: &str) -> Result<TimeDelta, ParseIntError> {
     Ok(TimeDelta::from_millis(s.parse()?))
 }
 
+pub fn read_message_policy_config(path: &str) -> Result<MessagePolicyConfig, io::Error> {
+    let file = File::open(path)?;
+    Ok(serde_json::from_reader(io::BufReader::new(file))?)
+}
+
 pub fn parse_chain_set(s: &str) -> Result<HashSet<ChainId>, CryptoError> {
     match s.trim() {
         "" => Ok(HashSet::new()),
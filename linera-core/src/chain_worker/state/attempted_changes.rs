@@ -123,6 +123,7 @@ where
             actions.notifications.push(Notification {
                 chain_id,
                 reason: Reason::NewRound { height, round },
+                sequence_number: 0,
             })
         }
         let info = ChainInfoResponse::new(&self.state.chain, self.state.config.key_pair());
@@ -138,8 +139,19 @@ where
         local_time: Timestamp,
     ) -> Result<(), WorkerError> {
         // Create the vote and store it in the chain state.
+        let system = &self.state.chain.execution_state.system;
+        let bls_key_pair = self
+            .state
+            .config
+            .bls_key_pair(system.compact_certificates());
         let manager = self.state.chain.manager.get_mut();
-        manager.create_vote(proposal, outcome, self.state.config.key_pair(), local_time);
+        manager.create_vote(
+            proposal,
+            outcome,
+            self.state.config.key_pair(),
+            bls_key_pair,
+            local_time,
+        );
         // Cache the value we voted on, so the client doesn't have to send it again.
         if let Some(vote) = manager.pending() {
             self.state
@@ -209,9 +221,15 @@ where
             .check_no_missing_blobs(executed_block.required_blob_ids(), blobs)
             .await?;
         let old_round = self.state.chain.manager.get().current_round;
+        let system = &self.state.chain.execution_state.system;
+        let bls_key_pair = self
+            .state
+            .config
+            .bls_key_pair(system.compact_certificates());
         self.state.chain.manager.get_mut().create_final_vote(
             certificate,
             self.state.config.key_pair(),
+            bls_key_pair,
             self.state.storage.clock().current_time(),
         );
         let info = ChainInfoResponse::new(&self.state.chain, self.state.config.key_pair());
@@ -221,6 +239,7 @@ where
             actions.notifications.push(Notification {
                 chain_id: self.state.chain_id(),
                 reason: Reason::NewRound { height, round },
+                sequence_number: 0,
             })
         }
         Ok((info, actions, false))
@@ -357,6 +376,7 @@ where
                 height: block.height,
                 hash: certificate.value.hash(),
             },
+            sequence_number: 0,
         });
         // Persist chain.
         self.save().await?;
@@ -470,9 +490,14 @@ where
             let chain_id = chain.chain_id();
             let height = chain.tip_state.get().next_block_height;
             let key_pair = self.state.config.key_pair();
+            let system = &chain.execution_state.system;
+            let bls_key_pair = self
+                .state
+                .config
+                .bls_key_pair(system.compact_certificates());
             let local_time = self.state.storage.clock().current_time();
             let manager = chain.manager.get_mut();
-            if manager.vote_timeout(chain_id, height, *epoch, key_pair, local_time) {
+            if manager.vote_timeout(chain_id, height, *epoch, key_pair, bls_key_pair, local_time) {
                 self.save().await?;
             }
         }
@@ -497,8 +522,13 @@ where
                 let chain_id = chain.chain_id();
                 let height = chain.tip_state.get().next_block_height;
                 let key_pair = self.state.config.key_pair();
+                let system = &chain.execution_state.system;
+                let bls_key_pair = self
+                    .state
+                    .config
+                    .bls_key_pair(system.compact_certificates());
                 let manager = chain.manager.get_mut();
-                if manager.vote_fallback(chain_id, height, *epoch, key_pair) {
+                if manager.vote_fallback(chain_id, height, *epoch, key_pair, bls_key_pair) {
                     self.save().await?;
                 }
             }
@@ -554,7 +584,7 @@ impl<'a> CrossChainUpdateHelper<'a> {
         CrossChainUpdateHelper {
             allow_messages_from_deprecated_epochs: config.allow_messages_from_deprecated_epochs,
             current_epoch: *chain.execution_state.system.epoch.get(),
-            committees: chain.execution_state.system.committees.get(),
+            committees: chain.execution_state.system.committees(),
         }
     }
 
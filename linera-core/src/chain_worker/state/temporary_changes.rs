@@ -15,7 +15,7 @@ use linera_chain::{
         Block, BlockExecutionOutcome, BlockProposal, ChannelFullName, ExecutedBlock,
         HashedCertificateValue, IncomingBundle, Medium, MessageAction, ProposalContent,
     },
-    manager,
+    manager, ChainError,
 };
 use linera_execution::{ChannelSubscription, Query, Response};
 use linera_storage::{Clock as _, Storage};
@@ -160,6 +160,7 @@ where
             blobs,
             validated_block_certificate,
             signature: _,
+            approvals: _,
         } = proposal;
         ensure!(
             validated_block_certificate.is_some() == forced_oracle_responses.is_some(),
@@ -221,6 +222,16 @@ where
         );
         self.0.storage.clock().sleep_until(block.timestamp).await;
         let local_time = self.0.storage.clock().current_time();
+        // The block timestamp is chosen by the proposer, so execution only checks expiry against
+        // that. Unless a quorum already validated the block, we also check it against our clock,
+        // and against the redemptions in our inboxes.
+        if validated_block_certificate.is_none() {
+            check_no_expired_messages(block, local_time)?;
+            self.0
+                .chain
+                .check_no_pending_lock_redemptions(block)
+                .await?;
+        }
         let outcome = Box::pin(self.0.chain.execute_block(
             block,
             local_time,
@@ -263,7 +274,7 @@ where
         let chain = &self.0.chain;
         let mut info = ChainInfo::from(chain);
         if query.request_committees {
-            info.requested_committees = Some(chain.execution_state.system.committees.get().clone());
+            info.requested_committees = Some(chain.execution_state.system.committees().clone());
         }
         if let Some(owner) = query.request_owner_balance {
             info.requested_owner_balance =
@@ -335,6 +346,27 @@ where
     }
 }
 
+/// Returns an error if the block accepts a message that expired at the given time.
+fn check_no_expired_messages(block: &Block, local_time: Timestamp) -> Result<(), ChainError> {
+    let accepted_bundles = block
+        .incoming_bundles
+        .iter()
+        .filter(|incoming_bundle| matches!(incoming_bundle.action, MessageAction::Accept));
+    for incoming_bundle in accepted_bundles {
+        for posted_message in &incoming_bundle.bundle.messages {
+            if let Some(expiry) = posted_message.expiry.filter(|expiry| *expiry <= local_time) {
+                return Err(ChainError::ExpiredMessage {
+                    chain_id: block.chain_id,
+                    origin: Box::new(incoming_bundle.origin.clone()),
+                    posted_message: posted_message.clone(),
+                    expiry,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
 impl<StorageClient> Drop for ChainWorkerStateWithTemporaryChanges<'_, StorageClient>
 where
     StorageClient: Storage + Clone + Send + Sync + 'static,
@@ -8,7 +8,7 @@ use std::{
 };
 
 use linera_base::{
-    crypto::{CryptoHash, KeyPair, PublicKey},
+    crypto::{CryptoHash, KeyPair, PublicKey, Signature},
     data_types::{Blob, BlockHeight, Timestamp},
     identifiers::{BlobId, ChainId, Owner},
 };
@@ -43,6 +43,8 @@ pub struct ChainState {
     /// This contains blobs belonging to our `pending_block` that may not even have
     /// been processed by (i.e. been proposed to) our own local chain manager yet.
     pending_blobs: BTreeMap<BlobId, Blob>,
+    /// Other owners' signatures of our `pending_block`, if the chain requires approvals.
+    block_approvals: BTreeMap<Owner, Signature>,
 
     /// A mutex that is held whilst we are performing operations that should not be
     /// attempted by multiple clients at the same time.
@@ -71,6 +73,7 @@ impl ChainState {
             next_block_height,
             pending_block: None,
             pending_blobs,
+            block_approvals: BTreeMap::new(),
             received_certificate_trackers: HashMap::new(),
             client_mutex: Arc::default(),
         };
@@ -102,6 +105,9 @@ impl ChainState {
 
     pub(super) fn set_pending_block(&mut self, block: Block) {
         if block.height == self.next_block_height {
+            if self.pending_block.as_ref() != Some(&block) {
+                self.block_approvals.clear();
+            }
             self.pending_block = Some(block);
         } else {
             tracing::error!(
@@ -120,6 +126,14 @@ impl ChainState {
         self.pending_blobs.insert(blob.id(), blob);
     }
 
+    pub fn block_approvals(&self) -> &BTreeMap<Owner, Signature> {
+        &self.block_approvals
+    }
+
+    pub(super) fn insert_block_approval(&mut self, owner: Owner, signature: Signature) {
+        self.block_approvals.insert(owner, signature);
+    }
+
     pub fn known_key_pairs(&self) -> &BTreeMap<Owner, KeyPair> {
         &self.known_key_pairs
     }
@@ -163,6 +177,7 @@ impl ChainState {
     pub(super) fn clear_pending_block(&mut self) {
         self.pending_block = None;
         self.pending_blobs.clear();
+        self.block_approvals.clear();
     }
 
     pub(super) fn client_mutex(&self) -> Arc<Mutex<()>> {
@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use std::{
+    cmp::Reverse,
     collections::{hash_map, BTreeMap, HashMap, HashSet},
     convert::Infallible,
     iter,
@@ -26,37 +27,37 @@ use linera_base::data_types::Bytecode;
 use linera_base::prometheus_util::MeasureLatency as _;
 use linera_base::{
     abi::Abi,
-    crypto::{CryptoHash, KeyPair, PublicKey},
+    crypto::{CryptoHash, KeyPair, PublicKey, Signature},
     data_types::{
         Amount, ApplicationPermissions, ArithmeticError, Blob, BlockHeight, Round, Timestamp,
     },
     ensure,
     identifiers::{
-        Account, ApplicationId, BlobId, BlobType, BytecodeId, ChainId, MessageId, Owner,
-        UserApplicationId,
+        Account, ApplicationId, BlobId, BlobType, BytecodeId, ChainId, GenericApplicationId,
+        MessageId, Owner, UserApplicationId,
     },
     ownership::{ChainOwnership, TimeoutConfig},
 };
 use linera_chain::{
     data_types::{
         Block, BlockProposal, Certificate, CertificateValue, ExecutedBlock, HashedCertificateValue,
-        IncomingBundle, LiteCertificate, LiteVote, MessageAction, PostedMessage,
+        IncomingBundle, LiteCertificate, LiteVote, Medium, MessageAction, PostedMessage,
     },
     manager::ChainManagerInfo,
     ChainError, ChainExecutionContext, ChainStateView,
 };
 use linera_execution::{
-    committee::{Committee, Epoch, ValidatorName},
+    committee::{BlsValidatorKey, Committee, Epoch, ValidatorName},
     system::{
-        AdminOperation, OpenChainConfig, Recipient, SystemChannel, SystemOperation,
-        CREATE_APPLICATION_MESSAGE_INDEX, OPEN_CHAIN_MESSAGE_INDEX,
+        AdminOperation, OpenChainConfig, Preimage, Recipient, StakingConfig, SystemChannel,
+        SystemOperation, CREATE_APPLICATION_MESSAGE_INDEX, OPEN_CHAIN_MESSAGE_INDEX,
     },
-    ExecutionError, Message, Operation, Query, Response, SystemExecutionError, SystemMessage,
-    SystemQuery, SystemResponse,
+    ExecutionError, Message, Operation, Query, ResourceControlPolicy, Response,
+    SystemExecutionError, SystemMessage, SystemQuery, SystemResponse,
 };
 use linera_storage::{Clock as _, Storage};
 use linera_views::views::ViewError;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tokio::sync::OwnedRwLockReadGuard;
 use tokio_stream::wrappers::UnboundedReceiverStream;
@@ -71,7 +72,7 @@ use crate::{
         CrossChainMessageDelivery, NodeError, NotificationStream, ValidatorNode,
         ValidatorNodeProvider,
     },
-    notifier::ChannelNotifier,
+    notifier::{ChannelNotifier, NotificationFilter},
     remote_node::RemoteNode,
     updater::{communicate_with_quorum, CommunicateAction, CommunicationError, ValidatorUpdater},
     worker::{Notification, Reason, WorkerError, WorkerState},
@@ -277,6 +278,7 @@ impl<P, S: Storage + Clone> Client<P, S> {
                 max_pending_message_bundles: self.max_pending_message_bundles,
                 message_policy: self.message_policy.clone(),
                 cross_chain_message_delivery: self.cross_chain_message_delivery,
+                sponsor: None,
             },
         }
     }
@@ -329,11 +331,15 @@ pub struct MessagePolicy {
     /// accepted. `Option::None` means that messages from all chains are accepted. An empty
     /// `HashSet` denotes that messages from no chains are accepted.
     restrict_chain_ids_to: Option<HashSet<ChainId>>,
+    /// Rules that override the blanket policy for the bundles they match. The first matching
+    /// rule applies.
+    rules: Vec<MessageRule>,
 }
 
-#[derive(Copy, Clone, Debug, clap::ValueEnum)]
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, clap::ValueEnum)]
 pub enum BlanketMessagePolicy {
     /// Automatically accept all incoming messages. Reject them only if execution fails.
+    #[default]
     Accept,
     /// Automatically reject tracked messages, ignore or skip untracked messages, but accept
     /// protected ones.
@@ -343,6 +349,66 @@ pub enum BlanketMessagePolicy {
     Ignore,
 }
 
+/// A rule of a [`MessagePolicy`], for the incoming bundles that match all its conditions.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MessageRule {
+    /// If set, only bundles from one of these chains match.
+    pub senders: Option<HashSet<ChainId>>,
+    /// If set, only bundles whose messages all belong to one of these applications match.
+    pub applications: Option<HashSet<GenericApplicationId>>,
+    /// Only bundles whose messages carry at least this grant in total match.
+    pub min_grant: Amount,
+    /// If set, only bundles of at most this size in bytes match.
+    pub max_size: Option<u64>,
+    /// How to handle the matching bundles.
+    pub action: BlanketMessagePolicy,
+    /// The priority of the matching bundles. Bundles with a higher priority are included in
+    /// blocks first.
+    pub priority: i32,
+}
+
+/// The rule-based part of a [`MessagePolicy`], usually read from a configuration file.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MessagePolicyConfig {
+    /// The rules, in the order in which they are tried.
+    pub rules: Vec<MessageRule>,
+}
+
+impl MessageRule {
+    /// Returns whether the bundle meets all the conditions of this rule.
+    fn matches(&self, bundle: &IncomingBundle) -> bool {
+        if let Some(senders) = &self.senders {
+            if !senders.contains(&bundle.origin.sender) {
+                return false;
+            }
+        }
+        if let Some(applications) = &self.applications {
+            if !bundle.bundle.messages.iter().all(|posted_message| {
+                applications.contains(&posted_message.message.application_id())
+            }) {
+                return false;
+            }
+        }
+        let grant = bundle
+            .bundle
+            .messages
+            .iter()
+            .map(|posted_message| &posted_message.grant)
+            .sum::<Amount>();
+        if grant < self.min_grant {
+            return false;
+        }
+        if let Some(max_size) = self.max_size {
+            let size = bcs::serialized_size(&bundle.bundle).map_or(u64::MAX, |size| size as u64);
+            if size > max_size {
+                return false;
+            }
+        }
+        true
+    }
+}
+
 impl MessagePolicy {
     pub fn new(
         blanket: BlanketMessagePolicy,
@@ -351,33 +417,45 @@ impl MessagePolicy {
         Self {
             blanket,
             restrict_chain_ids_to,
+            rules: Vec::new(),
         }
     }
 
+    /// Returns this policy with the given rules overriding the blanket policy.
+    pub fn with_rules(mut self, rules: Vec<MessageRule>) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Applies the policy to the bundle. Returns the bundle's priority, or `None` if it
+    /// should not be included in the block.
     #[tracing::instrument(level = "trace", skip(self))]
-    fn handle(&self, bundle: &mut IncomingBundle) -> bool {
-        if self.is_reject() {
-            if bundle.bundle.is_skippable() {
-                return false;
-            } else if bundle.bundle.is_tracked() {
-                bundle.action = MessageAction::Reject;
+    fn handle(&self, bundle: &mut IncomingBundle) -> Option<i32> {
+        let rule = self.rules.iter().find(|rule| rule.matches(bundle));
+        match rule.map_or(self.blanket, |rule| rule.action) {
+            BlanketMessagePolicy::Accept => {}
+            BlanketMessagePolicy::Reject => {
+                if bundle.bundle.is_skippable() {
+                    return None;
+                } else if bundle.bundle.is_tracked() {
+                    bundle.action = MessageAction::Reject;
+                }
             }
+            BlanketMessagePolicy::Ignore => return None,
         }
         let sender = bundle.origin.sender;
-        match &self.restrict_chain_ids_to {
-            None => true,
-            Some(chains) => chains.contains(&sender),
+        if let Some(chains) = &self.restrict_chain_ids_to {
+            if !chains.contains(&sender) {
+                return None;
+            }
         }
+        Some(rule.map_or(0, |rule| rule.priority))
     }
 
+    /// Returns whether all messages other than `OpenChain` are ignored.
     #[tracing::instrument(level = "trace", skip(self))]
     fn is_ignore(&self) -> bool {
-        matches!(self.blanket, BlanketMessagePolicy::Ignore)
-    }
-
-    #[tracing::instrument(level = "trace", skip(self))]
-    fn is_reject(&self) -> bool {
-        matches!(self.blanket, BlanketMessagePolicy::Reject)
+        matches!(self.blanket, BlanketMessagePolicy::Ignore) && self.rules.is_empty()
     }
 }
 
@@ -390,6 +468,8 @@ pub struct ChainClientOptions {
     pub message_policy: MessagePolicy,
     /// Whether to block on cross-chain message delivery.
     pub cross_chain_message_delivery: CrossChainMessageDelivery,
+    /// The account paying for the fees of the proposed blocks, if the chain is sponsored.
+    pub sponsor: Option<Account>,
 }
 
 /// Client to operate a chain by interacting with validators and the given local storage
@@ -470,6 +550,12 @@ pub enum ChainClientError {
     #[error("The state of the client is incompatible with the proposed block: {0}")]
     BlockProposalError(&'static str),
 
+    #[error(
+        "The block is approved by owners with a total weight of {weight}, \
+         but the chain requires {threshold}"
+    )]
+    InsufficientBlockApprovals { weight: u64, threshold: u64 },
+
     #[error(
         "Cannot accept a certificate from a committee that was retired. \
          Try a newer certificate from the same origin"
@@ -673,6 +759,57 @@ where
         {
             return Ok(Vec::new()); // OpenChain is already received, other are ignored.
         }
+        let (open_chain_bundle, requested_pending_message_bundles) =
+            self.requested_pending_message_bundles().await?;
+        let mut pending_message_bundles = open_chain_bundle.into_iter().collect::<Vec<_>>();
+        if self.options.message_policy.is_ignore() {
+            return Ok(pending_message_bundles); // Ignore messages other than OpenChain.
+        }
+        let mut blocked_origins = HashSet::new();
+        let mut origin_priorities = HashMap::new();
+        let mut prioritized_bundles = Vec::new();
+        for mut bundle in requested_pending_message_bundles {
+            if blocked_origins.contains(&bundle.origin) {
+                continue;
+            }
+            let Some(priority) = self.options.message_policy.handle(&mut bundle) else {
+                if !bundle.bundle.is_skippable() {
+                    // The later bundles from this origin cannot be received before this one.
+                    blocked_origins.insert(bundle.origin.clone());
+                }
+                continue;
+            };
+            // Bundles from the same origin must stay in order, so they cannot take precedence
+            // over earlier ones.
+            let priority = *origin_priorities
+                .entry(bundle.origin.clone())
+                .and_modify(|origin_priority: &mut i32| {
+                    *origin_priority = (*origin_priority).min(priority)
+                })
+                .or_insert(priority);
+            prioritized_bundles.push((priority, bundle));
+        }
+        prioritized_bundles.sort_by_key(|(priority, _)| Reverse(*priority));
+        for (_, bundle) in prioritized_bundles {
+            if pending_message_bundles.len() >= self.options.max_pending_message_bundles {
+                warn!(
+                    "Limiting block to {} incoming message bundles",
+                    self.options.max_pending_message_bundles
+                );
+                break;
+            }
+            pending_message_bundles.push(bundle);
+        }
+        Ok(pending_message_bundles)
+    }
+
+    /// Obtains the pending message bundles of the local chain. The first incoming message of
+    /// any child chain must be `OpenChain`: Before the chain's first block, the bundle with
+    /// that message is returned separately, since it must be included before all others.
+    #[tracing::instrument(level = "trace")]
+    async fn requested_pending_message_bundles(
+        &self,
+    ) -> Result<(Option<IncomingBundle>, Vec<IncomingBundle>), ChainClientError> {
         let query = ChainInfoQuery::new(self.chain_id).with_pending_message_bundles();
         let info = self
             .client
@@ -685,9 +822,6 @@ where
             ChainClientError::WalletSynchronizationError
         );
         let mut requested_pending_message_bundles = info.requested_pending_message_bundles;
-        let mut pending_message_bundles = vec![];
-        // The first incoming message of any child chain must be `OpenChain`. We must have it in
-        // our inbox, and include it before all other messages.
         if info.next_block_height == BlockHeight::ZERO
             && info
                 .description
@@ -709,25 +843,48 @@ where
                 return Err(LocalNodeError::InactiveChain(self.chain_id).into());
             };
             let open_chain_bundle = requested_pending_message_bundles.remove(index);
-            pending_message_bundles.push(open_chain_bundle);
-        }
-        if self.options.message_policy.is_ignore() {
-            return Ok(pending_message_bundles); // Ignore messages other than OpenChain.
+            return Ok((Some(open_chain_bundle), requested_pending_message_bundles));
         }
-        for mut bundle in requested_pending_message_bundles {
-            if pending_message_bundles.len() >= self.options.max_pending_message_bundles {
-                warn!(
-                    "Limiting block to {} incoming message bundles",
-                    self.options.max_pending_message_bundles
-                );
-                break;
-            }
-            if !self.options.message_policy.handle(&mut bundle) {
-                continue;
-            }
-            pending_message_bundles.push(bundle);
+        Ok((None, requested_pending_message_bundles))
+    }
+
+    /// Obtains up to `self.options.max_pending_message_bundles` pending message bundles that
+    /// create or remove committees, i.e. the bundles from the admin chain's channel, ignoring
+    /// the message policy.
+    #[tracing::instrument(level = "trace")]
+    async fn pending_reconfiguration_bundles(
+        &self,
+    ) -> Result<Vec<IncomingBundle>, ChainClientError> {
+        let admin_id = self.state().admin_id();
+        let (open_chain_bundle, requested_pending_message_bundles) =
+            self.requested_pending_message_bundles().await?;
+        let reconfiguration_bundles = requested_pending_message_bundles
+            .into_iter()
+            .filter(|bundle| {
+                bundle.origin.sender == admin_id
+                    && matches!(bundle.origin.medium, Medium::Channel(_))
+            })
+            .take_while(|bundle| {
+                bundle.bundle.messages.iter().all(|posted_message| {
+                    matches!(
+                        posted_message.message,
+                        Message::System(
+                            SystemMessage::CreateCommittee { .. }
+                                | SystemMessage::CreateCommitteeWithExtensions { .. }
+                                | SystemMessage::RemoveCommittee { .. }
+                        )
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+        if reconfiguration_bundles.is_empty() {
+            return Ok(Vec::new());
         }
-        Ok(pending_message_bundles)
+        Ok(open_chain_bundle
+            .into_iter()
+            .chain(reconfiguration_bundles)
+            .take(self.options.max_pending_message_bundles)
+            .collect())
     }
 
     /// Obtains the current epoch of the given chain as well as its set of trusted committees.
@@ -831,6 +988,7 @@ where
             .ownership
             .all_owners()
             .chain(&manager.leader)
+            .chain(manager.ownership.guardians())
             .filter(|owner| self.state().known_key_pairs().contains_key(owner));
         let Some(identity) = identities.next() else {
             return Err(ChainClientError::CannotFindKeyForChain(self.chain_id));
@@ -860,6 +1018,14 @@ where
         Ok(self.key_pair().await?.public())
     }
 
+    /// Returns whether the current identity is a guardian of the chain rather than an owner.
+    #[tracing::instrument(level = "trace")]
+    async fn is_guardian(&self) -> Result<bool, ChainClientError> {
+        let identity = self.identity().await?;
+        let ownership = self.chain_info().await?.manager.ownership;
+        Ok(ownership.verify_guardian(&identity).is_some())
+    }
+
     /// Prepares the chain for the next operation, i.e. makes sure we have synchronized it up to
     /// its current height.
     #[tracing::instrument(level = "trace")]
@@ -1021,7 +1187,7 @@ where
         // * `communicate_with_quorum` ensured a sufficient "weight" of
         // (non-error) answers were returned by validators.
         // * each answer is a vote signed by the expected validator.
-        let certificate = LiteCertificate::try_from_votes(votes)
+        let certificate = LiteCertificate::try_from_votes(votes, committee)
             .ok_or_else(|| {
                 ChainClientError::InternalError("Vote values or rounds don't match; this is a bug")
             })?
@@ -1797,12 +1963,32 @@ where
         let proposal = if let Some(cert) = manager.requested_locked {
             Box::new(BlockProposal::new_retry(round, *cert, &key_pair, blobs))
         } else {
-            Box::new(BlockProposal::new_initial(
-                round,
-                block.clone(),
-                &key_pair,
-                blobs,
-            ))
+            let approvals = {
+                let state = self.state();
+                if state.pending_block().as_ref() == Some(&block) {
+                    state.block_approvals().clone()
+                } else {
+                    BTreeMap::new()
+                }
+            };
+            let owner = Owner::from(key_pair.public());
+            if let Some(threshold) = manager.ownership.approval_threshold {
+                if !manager.ownership.super_owners.contains_key(&owner)
+                    && !matches!(round, Round::Validator(_))
+                {
+                    let weight = manager
+                        .ownership
+                        .approval_weight(approvals.keys().chain([&owner]));
+                    ensure!(
+                        weight >= threshold,
+                        ChainClientError::InsufficientBlockApprovals { weight, threshold }
+                    );
+                }
+            }
+            Box::new(
+                BlockProposal::new_initial(round, block.clone(), &key_pair, blobs)
+                    .with_approvals(approvals),
+            )
         };
         // Check the final block proposal. This will be cheaper after #1401.
         self.client
@@ -1892,6 +2078,19 @@ where
     async fn execute_block(
         &self,
         operations: Vec<Operation>,
+    ) -> Result<ExecuteBlockOutcome, ChainClientError> {
+        self.execute_block_receiving(operations, false).await
+    }
+
+    /// Executes a new block. If `reconfigurations_only` is set, the only incoming messages
+    /// it receives are the ones that create or remove committees.
+    ///
+    /// This must be preceded by a call to `prepare_chain()`.
+    #[tracing::instrument(level = "trace", skip(operations))]
+    async fn execute_block_receiving(
+        &self,
+        operations: Vec<Operation>,
+        reconfigurations_only: bool,
     ) -> Result<ExecuteBlockOutcome, ChainClientError> {
         #[cfg(with_metrics)]
         let _latency = metrics::EXECUTE_BLOCK_LATENCY.measure_latency();
@@ -1907,7 +2106,14 @@ where
                 return Ok(ExecuteBlockOutcome::WaitForTimeout(timeout))
             }
         }
-        let incoming_bundles = self.pending_message_bundles().await?;
+        // Guardians' blocks cannot contain any incoming messages.
+        let incoming_bundles = if self.is_guardian().await? {
+            Vec::new()
+        } else if reconfigurations_only {
+            self.pending_reconfiguration_bundles().await?
+        } else {
+            self.pending_message_bundles().await?
+        };
         let confirmed_value = self.set_pending_block(incoming_bundles, operations).await?;
         match self.process_pending_block_without_prepare().await? {
             ClientOutcome::Committed(Some(certificate))
@@ -1928,6 +2134,15 @@ where
         }
     }
 
+    /// Prepends the operation designating the configured sponsor, if any, to the operations
+    /// of a new block.
+    fn with_sponsor(&self, mut operations: Vec<Operation>) -> Vec<Operation> {
+        if let Some(sponsor) = self.options.sponsor {
+            operations.insert(0, SystemOperation::UseSponsor { sponsor }.into());
+        }
+        operations
+    }
+
     /// Sets the pending block, so that next time `process_pending_block_without_prepare` is
     /// called, it will be proposed to the validators.
     #[tracing::instrument(level = "trace", skip(incoming_bundles, operations))]
@@ -1937,6 +2152,16 @@ where
         operations: Vec<Operation>,
     ) -> Result<HashedCertificateValue, ChainClientError> {
         let timestamp = self.next_timestamp(&incoming_bundles).await;
+        // Expired messages must be rejected, which bounces them back if they are tracked.
+        let incoming_bundles = incoming_bundles
+            .into_iter()
+            .map(|mut bundle| {
+                if bundle.bundle.is_expired(timestamp) {
+                    bundle.action = MessageAction::Reject;
+                }
+                bundle
+            })
+            .collect();
         let identity = self.identity().await?;
         let previous_block_hash;
         let height;
@@ -1949,7 +2174,7 @@ where
             epoch: self.epoch().await?,
             chain_id: self.chain_id,
             incoming_bundles,
-            operations,
+            operations: self.with_sponsor(operations),
             previous_block_hash,
             height,
             authenticated_signer: Some(identity),
@@ -2075,7 +2300,7 @@ where
             epoch: self.epoch().await?,
             chain_id: self.chain_id,
             incoming_bundles,
-            operations: Vec::new(),
+            operations: self.with_sponsor(Vec::new()),
             previous_block_hash: self.block_hash(),
             height: self.next_block_height(),
             authenticated_signer: owner,
@@ -2304,11 +2529,18 @@ where
                 "Conflicting proposal in the current round.",
             ));
         };
-        let can_propose = match round {
-            Round::Fast => manager.ownership.super_owners.contains_key(&identity),
-            Round::MultiLeader(_) => true,
-            Round::SingleLeader(_) | Round::Validator(_) => manager.leader == Some(identity),
+        // Guardians can propose in any round except the fast one.
+        let is_guardian = manager.ownership.verify_guardian(&identity).is_some();
+        let round = match round {
+            Round::Fast if is_guardian => manager.ownership.next_round(round).unwrap_or(round),
+            round => round,
         };
+        let can_propose = is_guardian
+            || match round {
+                Round::Fast => manager.ownership.super_owners.contains_key(&identity),
+                Round::MultiLeader(_) => true,
+                Round::SingleLeader(_) | Round::Validator(_) => manager.leader == Some(identity),
+            };
         if can_propose {
             let certificate = self.propose_block(block.clone(), round, manager).await?;
             Ok(ClientOutcome::Committed(Some(certificate)))
@@ -2331,6 +2563,61 @@ where
         self.state_mut().clear_pending_block();
     }
 
+    /// Signs another owner's pending block for this chain, approving it.
+    ///
+    /// The block is executed locally first, so that only valid blocks get approved. The
+    /// returned signature must be passed to the proposer's [`ChainClient::add_block_approval`].
+    #[tracing::instrument(level = "trace", skip(block))]
+    pub async fn approve_block(
+        &self,
+        block: Block,
+    ) -> Result<(Owner, Signature), ChainClientError> {
+        ensure!(
+            block.chain_id == self.chain_id,
+            ChainClientError::BlockProposalError("The block belongs to a different chain")
+        );
+        let info = self.synchronize_from_validators().await?;
+        ensure!(
+            block.height == info.next_block_height,
+            ChainClientError::BlockProposalError("Unexpected block height")
+        );
+        let key_pair = self.key_pair().await?;
+        let owner = Owner::from(key_pair.public());
+        ensure!(
+            info.manager.ownership.owners.contains_key(&owner),
+            ChainClientError::BlockProposalError("Only regular owners can approve blocks")
+        );
+        self.stage_block_execution(block.clone()).await?;
+        Ok((owner, Signature::new(&block, &key_pair)))
+    }
+
+    /// Adds another owner's approval of our pending block. It will be included in the block
+    /// proposal, which can be retried using [`ChainClient::process_pending_block`].
+    #[tracing::instrument(level = "trace", skip(signature))]
+    pub async fn add_block_approval(
+        &self,
+        owner: Owner,
+        signature: Signature,
+    ) -> Result<(), ChainClientError> {
+        let ownership = self.chain_info().await?.manager.ownership;
+        let Some((public_key, _)) = ownership.owners.get(&owner) else {
+            return Err(ChainClientError::BlockProposalError(
+                "Only regular owners can approve blocks",
+            ));
+        };
+        let mut state = self.state_mut();
+        let Some(block) = state.pending_block() else {
+            return Err(ChainClientError::BlockProposalError(
+                "There is no pending block to approve",
+            ));
+        };
+        signature
+            .check(block, *public_key)
+            .map_err(|_| ChainClientError::BlockProposalError("Invalid block approval"))?;
+        state.insert_block_approval(owner, signature);
+        Ok(())
+    }
+
     /// Processes a confirmed block for which this chain is a recipient and updates validators.
     #[tracing::instrument(
         level = "trace",
@@ -2409,12 +2696,15 @@ where
                     .zip(iter::repeat(100)),
             );
             owners.push((new_public_key, new_weight));
-            let operations = vec![Operation::System(SystemOperation::ChangeOwnership {
-                super_owners: Vec::new(),
+            let new_ownership = ChainOwnership::multiple(
                 owners,
-                multi_leader_rounds: ownership.multi_leader_rounds,
-                timeout_config: ownership.timeout_config,
-            })];
+                ownership.multi_leader_rounds,
+                ownership.timeout_config.clone(),
+            )
+            .with_extensions(ownership.extensions());
+            let operations = vec![Operation::System(SystemOperation::change_ownership(
+                new_ownership,
+            ))];
             match self.execute_block(operations).await? {
                 ExecuteBlockOutcome::Executed(certificate) => {
                     return Ok(ClientOutcome::Committed(certificate));
@@ -2439,12 +2729,9 @@ where
         &self,
         ownership: ChainOwnership,
     ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
-        self.execute_operation(Operation::System(SystemOperation::ChangeOwnership {
-            super_owners: ownership.super_owners.values().cloned().collect(),
-            owners: ownership.owners.values().cloned().collect(),
-            multi_leader_rounds: ownership.multi_leader_rounds,
-            timeout_config: ownership.timeout_config.clone(),
-        }))
+        self.execute_operation(Operation::System(SystemOperation::change_ownership(
+            ownership,
+        )))
         .await
     }
 
@@ -2511,6 +2798,214 @@ where
             .await
     }
 
+    /// Claims this chain's owner's share of the fees collected in the given epoch, as a
+    /// validator. The block must be signed with the validator's key.
+    #[tracing::instrument(level = "trace")]
+    pub async fn claim_validator_rewards(
+        &self,
+        epoch: Epoch,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::ClaimValidatorRewards {
+            epoch,
+        }))
+        .await
+    }
+
+    /// Registers this chain's owner as a validator candidate on the admin chain, bonding
+    /// `stake` units of value from the given owner's account (or the chain's account). The
+    /// block must be signed with the validator's key.
+    #[tracing::instrument(level = "trace")]
+    pub async fn register_validator(
+        &self,
+        owner: Option<Owner>,
+        name: ValidatorName,
+        network_address: String,
+        bls_key: Option<BlsValidatorKey>,
+        reward_account: Option<Account>,
+        stake: Amount,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::RegisterValidator {
+            owner,
+            name,
+            network_address,
+            bls_key,
+            reward_account,
+            stake,
+        }))
+        .await
+    }
+
+    /// Bonds `amount` units of value from the given owner's account (or the chain's
+    /// account) to a validator, on behalf of the block's signer.
+    #[tracing::instrument(level = "trace")]
+    pub async fn bond(
+        &self,
+        owner: Option<Owner>,
+        validator: ValidatorName,
+        amount: Amount,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::Bond {
+            owner,
+            validator,
+            amount,
+        }))
+        .await
+    }
+
+    /// Unbonds `amount` units of the block signer's stake from a validator.
+    #[tracing::instrument(level = "trace")]
+    pub async fn unbond(
+        &self,
+        validator: ValidatorName,
+        amount: Amount,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::Unbond {
+            validator,
+            amount,
+        }))
+        .await
+    }
+
+    /// Withdraws the block signer's unbonded stake whose unbonding period is over.
+    #[tracing::instrument(level = "trace")]
+    pub async fn withdraw_unbonded(&self) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::WithdrawUnbonded))
+            .await
+    }
+
+    /// Proposes a new resource control policy, as a validator of the current epoch. The
+    /// block must be signed with the validator's key.
+    #[tracing::instrument(level = "trace")]
+    pub async fn propose_policy(
+        &self,
+        policy: ResourceControlPolicy,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::ProposePolicy { policy }))
+            .await
+    }
+
+    /// Votes for a policy proposal, as a validator of the current epoch. The block must be
+    /// signed with the validator's key.
+    #[tracing::instrument(level = "trace")]
+    pub async fn vote_policy(
+        &self,
+        proposal_id: u64,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::VotePolicy {
+            proposal_id,
+        }))
+        .await
+    }
+
+    /// Sets aside `budget` units of value from the given owner's account (or the
+    /// unattributed account) to pay for the fees of the `beneficiary` chain, optionally only
+    /// for operations of the given applications.
+    #[tracing::instrument(level = "trace")]
+    pub async fn sponsor(
+        &self,
+        owner: Option<Owner>,
+        beneficiary: ChainId,
+        budget: Amount,
+        applications: Option<Vec<UserApplicationId>>,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::Sponsor {
+            owner,
+            beneficiary,
+            budget,
+            applications,
+        }))
+        .await
+    }
+
+    /// Revokes the sponsorship of the `beneficiary` chain by the given owner's account (or
+    /// the unattributed account). The remaining budget is returned.
+    #[tracing::instrument(level = "trace")]
+    pub async fn revoke_sponsorship(
+        &self,
+        owner: Option<Owner>,
+        beneficiary: ChainId,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::RevokeSponsorship {
+            owner,
+            beneficiary,
+        }))
+        .await
+    }
+
+    /// Votes to transfer this chain to `new_owner`, as one of its guardians.
+    #[tracing::instrument(level = "trace")]
+    pub async fn initiate_recovery(
+        &self,
+        new_owner: PublicKey,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::InitiateRecovery {
+            new_owner,
+        }))
+        .await
+    }
+
+    /// Cancels the recovery initiated by the guardians of this chain, as one of its owners.
+    #[tracing::instrument(level = "trace")]
+    pub async fn cancel_recovery(&self) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::CancelRecovery))
+            .await
+    }
+
+    /// Transfers this chain to the new owner approved by its guardians, once the recovery
+    /// delay is over.
+    #[tracing::instrument(level = "trace")]
+    pub async fn complete_recovery(&self) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::CompleteRecovery))
+            .await
+    }
+
+    /// Locks `amount` units of value from the given owner's account (or the unattributed
+    /// account) until the preimage of `hash` is revealed before the `deadline`, which
+    /// releases them to the `recipient`.
+    #[tracing::instrument(level = "trace")]
+    pub async fn lock_funds(
+        &self,
+        owner: Option<Owner>,
+        recipient: Account,
+        amount: Amount,
+        hash: CryptoHash,
+        deadline: Timestamp,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::LockFunds {
+            owner,
+            recipient,
+            amount,
+            hash,
+            deadline,
+        }))
+        .await
+    }
+
+    /// Reveals the preimage to the `chain_id` chain, to redeem its lock with the matching
+    /// hash.
+    #[tracing::instrument(level = "trace")]
+    pub async fn redeem_lock(
+        &self,
+        chain_id: ChainId,
+        preimage: Preimage,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::RedeemLock {
+            chain_id,
+            preimage,
+        }))
+        .await
+    }
+
+    /// Returns the funds of an expired lock on this chain to the account that locked them.
+    #[tracing::instrument(level = "trace")]
+    pub async fn refund_lock(
+        &self,
+        hash: CryptoHash,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::RefundLock { hash }))
+            .await
+    }
+
     /// Publishes some bytecode.
     #[cfg(not(target_arch = "wasm32"))]
     #[tracing::instrument(level = "trace", skip(contract, service))]
@@ -2653,9 +3148,45 @@ where
             let epoch = self.epoch().await?;
             match self
                 .execute_block(vec![Operation::System(SystemOperation::Admin(
-                    AdminOperation::CreateCommittee {
+                    AdminOperation::create_committee(epoch.try_add_one()?, committee.clone()),
+                ))])
+                .await?
+            {
+                ExecuteBlockOutcome::Executed(certificate) => {
+                    return Ok(ClientOutcome::Committed(certificate))
+                }
+                ExecuteBlockOutcome::Conflict(_) => continue,
+                ExecuteBlockOutcome::WaitForTimeout(timeout) => {
+                    return Ok(ClientOutcome::WaitForTimeout(timeout));
+                }
+            };
+        }
+    }
+
+    /// Enables the stake-based committee election, or changes its parameters (admin chains
+    /// only).
+    #[tracing::instrument(level = "trace")]
+    pub async fn configure_staking(
+        &self,
+        config: StakingConfig,
+    ) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        self.execute_operation(Operation::System(SystemOperation::Admin(
+            AdminOperation::ConfigureStaking(config),
+        )))
+        .await
+    }
+
+    /// Creates a new committee from the validators' current stakes and starts using it
+    /// (admin chains only).
+    #[tracing::instrument(level = "trace")]
+    pub async fn elect_committee(&self) -> Result<ClientOutcome<Certificate>, ChainClientError> {
+        loop {
+            self.prepare_chain().await?;
+            let epoch = self.epoch().await?;
+            match self
+                .execute_block(vec![Operation::System(SystemOperation::Admin(
+                    AdminOperation::ElectCommittee {
                         epoch: epoch.try_add_one()?,
-                        committee: committee.clone(),
                     },
                 ))])
                 .await?
@@ -2713,6 +3244,32 @@ where
         }
     }
 
+    /// Creates blocks without any operations that only receive the messages creating or
+    /// removing committees, so that the chain migrates to the latest epoch without processing
+    /// the rest of its inbox. This may require several blocks.
+    ///
+    /// If not all certificates could be processed due to a timeout, the timestamp for when to retry
+    /// is returned, too.
+    #[tracing::instrument(level = "trace")]
+    pub async fn migrate_epoch(
+        &self,
+    ) -> Result<(Vec<Certificate>, Option<RoundTimeout>), ChainClientError> {
+        self.prepare_chain().await?;
+        let mut certificates = Vec::new();
+        loop {
+            if self.pending_reconfiguration_bundles().await?.is_empty() {
+                return Ok((certificates, None));
+            }
+            match self.execute_block_receiving(vec![], true).await? {
+                ExecuteBlockOutcome::Executed(certificate)
+                | ExecuteBlockOutcome::Conflict(certificate) => certificates.push(certificate),
+                ExecuteBlockOutcome::WaitForTimeout(timeout) => {
+                    return Ok((certificates, Some(timeout)));
+                }
+            }
+        }
+    }
+
     /// Starts listening to the admin chain for new committees. (This is only useful for
     /// other genesis chains or for testing.)
     #[tracing::instrument(level = "trace")]
@@ -2946,7 +3503,7 @@ where
         let mut senders = HashMap::new(); // Senders to cancel notification streams.
         let notifications = self.subscribe().await?;
         let (abortable_notifications, abort) = stream::abortable(self.subscribe().await?);
-        if let Err(error) = self.synchronize_from_validators().await {
+        if let Err(error) = Box::pin(self.synchronize_from_validators()).await {
             error!("Failed to synchronize from validators: {}", error);
         }
 
@@ -3022,7 +3579,10 @@ where
             let hash_map::Entry::Vacant(entry) = senders.entry(name) else {
                 continue;
             };
-            let (mut stream, abort) = match node.subscribe(vec![chain_id]).await {
+            let (mut stream, abort) = match node
+                .subscribe(vec![chain_id], NotificationFilter::default())
+                .await
+            {
                 Err(error) => {
                     info!(?error, "Could not connect to validator {name}");
                     continue;
@@ -3032,21 +3592,60 @@ where
             let this = self.clone();
             let local_node = local_node.clone();
             let remote_node = RemoteNode { name, node };
+            let stream_abort = abort.clone();
             validator_tasks.push(async move {
+                let mut last_sequence_number = None;
                 while let Some(notification) = stream.next().await {
+                    // The notifications of a chain are numbered consecutively: a jump means
+                    // that some were dropped, or that the validator's chain worker restarted.
+                    let sequence_number = notification.sequence_number;
+                    let has_gap = last_sequence_number
+                        .is_some_and(|last: u64| sequence_number > last.saturating_add(1));
+                    last_sequence_number = last_sequence_number.max(Some(sequence_number));
                     this.process_notification(
                         remote_node.clone(),
                         local_node.clone(),
                         notification,
                     )
                     .await;
+                    if has_gap {
+                        warn!("Notifications from validator {name} may be missing; synchronizing");
+                        this.resynchronize_from_validator(remote_node.clone()).await;
+                    }
+                }
+                if stream_abort.is_aborted() {
+                    return;
                 }
+                // The validator could not resume the stream, so some notifications may
+                // have been missed.
+                warn!("Notification stream from validator {name} ended; synchronizing");
+                this.resynchronize_from_validator(remote_node).await;
+                // Subscribe again the next time the streams are updated.
+                stream_abort.abort();
             });
             entry.insert(abort);
         }
         Ok(validator_tasks.collect())
     }
 
+    /// Synchronizes the chain state and the received certificates with a validator whose
+    /// notifications may have been missed.
+    #[tracing::instrument(level = "trace")]
+    async fn resynchronize_from_validator(&self, remote_node: RemoteNode<P::Node>) {
+        if let Err(error) = self
+            .try_synchronize_chain_state_from(&remote_node, self.chain_id)
+            .await
+        {
+            error!("Failed to synchronize the chain state: {error}");
+        }
+        if let Err(error) = self
+            .find_received_certificates_from_validator(remote_node)
+            .await
+        {
+            error!("Failed to synchronize the received certificates: {error}");
+        }
+    }
+
     /// Attempts to download new received certificates from a particular validator.
     ///
     /// This is similar to `find_received_certificates` but for only one validator.
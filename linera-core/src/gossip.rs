@@ -0,0 +1,394 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Gossip of new blocks between the validators of a committee.
+//!
+//! When a validator confirms a block, it announces the certificate to a few other
+//! validators of the chain's committee. A validator that receives an announcement for a
+//! block it does not have yet downloads the missing certificates of the chain from the
+//! announcing validator and processes them. Lagging validators thereby catch up without
+//! waiting for a client to update them.
+//!
+//! Before downloading anything else, the announced certificate itself is downloaded and
+//! checked against the committee, so that a validator cannot make its peers download
+//! blocks for a made-up announcement. The number of blocks downloaded for an announcement
+//! and the number of chains synchronized at the same time are bounded.
+
+use std::{
+    collections::{btree_map, BTreeMap},
+    sync::{Arc, Mutex},
+};
+
+use futures::future;
+use linera_base::{crypto::CryptoHash, data_types::BlockHeight, identifiers::ChainId};
+use linera_chain::data_types::{Certificate, CertificateValue};
+use linera_execution::committee::{Committee, ValidatorName};
+use linera_storage::Storage;
+use rand::seq::IteratorRandom as _;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::{debug, warn};
+
+use crate::{
+    data_types::ChainInfoQuery,
+    node::{NodeError, ValidatorNode, ValidatorNodeProvider},
+    remote_node::RemoteNode,
+    worker::{NetworkActions, WorkerError, WorkerState},
+};
+
+#[cfg(test)]
+#[path = "unit_tests/gossip_tests.rs"]
+mod unit_tests;
+
+/// The default maximal number of blocks downloaded for a single announcement.
+pub const DEFAULT_MAX_HEIGHT_GAP: u64 = 1000;
+
+/// The default maximal number of chains synchronized at the same time.
+pub const DEFAULT_MAX_CONCURRENT_SYNCHRONIZATIONS: usize = 10;
+
+/// An announcement that a validator has the certificate for a new block.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CertificateAnnouncement {
+    /// The chain of the block.
+    pub chain_id: ChainId,
+    /// The height of the block.
+    pub height: BlockHeight,
+    /// The hash of the certificate.
+    pub hash: CryptoHash,
+    /// The validator announcing the block, from which it can be downloaded.
+    pub origin: ValidatorName,
+}
+
+/// Error type for the certificate gossip.
+#[derive(Debug, Error)]
+pub enum GossipError {
+    #[error(transparent)]
+    WorkerError(#[from] WorkerError),
+    #[error(transparent)]
+    NodeError(#[from] NodeError),
+    #[error("The committee of chain {0} is unknown")]
+    UnknownCommittee(ChainId),
+    #[error("Validator {origin} is not in the committee of chain {chain_id}")]
+    UnknownOrigin {
+        origin: ValidatorName,
+        chain_id: ChainId,
+    },
+    #[error("Validator {origin} did not provide the certificates of chain {chain_id} up to height {height}")]
+    MissingCertificates {
+        origin: ValidatorName,
+        chain_id: ChainId,
+        height: BlockHeight,
+    },
+    #[error("Validator {origin} announced an invalid certificate for chain {chain_id}")]
+    InvalidAnnouncement {
+        origin: ValidatorName,
+        chain_id: ChainId,
+    },
+    #[error("Chain {chain_id} is more than {max_height_gap} blocks behind the announcement")]
+    HeightGapTooLarge {
+        chain_id: ChainId,
+        max_height_gap: u64,
+    },
+}
+
+/// One of the synchronizations that may run at the same time.
+pub struct SynchronizationSlot {
+    _permit: OwnedSemaphorePermit,
+}
+
+/// Announces the new blocks of a validator to its peers, and downloads the blocks they
+/// announce.
+pub struct CertificateGossip<P, S>
+where
+    S: Storage,
+{
+    name: ValidatorName,
+    fanout: usize,
+    node_provider: P,
+    state: WorkerState<S>,
+    /// The maximal number of blocks downloaded for a single announcement.
+    max_height_gap: u64,
+    /// The slots of the synchronizations that may run at the same time.
+    synchronizations: Arc<Semaphore>,
+    /// The latest announcement for each chain that is being synchronized.
+    pending: Arc<Mutex<BTreeMap<ChainId, CertificateAnnouncement>>>,
+}
+
+impl<P, S> Clone for CertificateGossip<P, S>
+where
+    P: Clone,
+    S: Storage + Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name,
+            fanout: self.fanout,
+            node_provider: self.node_provider.clone(),
+            state: self.state.clone(),
+            max_height_gap: self.max_height_gap,
+            synchronizations: self.synchronizations.clone(),
+            pending: self.pending.clone(),
+        }
+    }
+}
+
+impl<P, S> CertificateGossip<P, S>
+where
+    P: ValidatorNodeProvider,
+    S: Storage + Clone + Send + Sync + 'static,
+{
+    /// Creates the gossip for the validator with the given worker state, announcing each
+    /// new block to `fanout` other validators.
+    ///
+    /// # Panics
+    ///
+    /// If the validator doesn't have a key pair assigned to it.
+    pub fn new(state: WorkerState<S>, node_provider: P, fanout: usize) -> Self {
+        Self {
+            name: state
+                .validator_name()
+                .expect("Gossiping validators should have a key pair"),
+            fanout,
+            node_provider,
+            state,
+            max_height_gap: DEFAULT_MAX_HEIGHT_GAP,
+            synchronizations: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_SYNCHRONIZATIONS)),
+            pending: Arc::default(),
+        }
+    }
+
+    /// Sets the maximal number of blocks downloaded for a single announcement.
+    pub fn with_max_height_gap(mut self, max_height_gap: u64) -> Self {
+        self.max_height_gap = max_height_gap;
+        self
+    }
+
+    /// Sets the maximal number of chains synchronized at the same time.
+    pub fn with_max_concurrent_synchronizations(mut self, max_synchronizations: usize) -> Self {
+        self.synchronizations = Arc::new(Semaphore::new(max_synchronizations));
+        self
+    }
+
+    /// Reserves a slot to handle an announcement, or returns `None` if too many chains are
+    /// being synchronized already.
+    pub fn try_reserve_synchronization(&self) -> Option<SynchronizationSlot> {
+        let permit = self.synchronizations.clone().try_acquire_owned().ok()?;
+        Some(SynchronizationSlot { _permit: permit })
+    }
+
+    /// Announces a new block of this validator to randomly chosen validators of the
+    /// chain's committee. Failures to reach a validator are logged.
+    pub async fn announce(
+        &self,
+        chain_id: ChainId,
+        height: BlockHeight,
+        hash: CryptoHash,
+    ) -> Result<(), GossipError> {
+        let committee = self.committee(chain_id).await?;
+        let peers = committee
+            .validator_addresses()
+            .filter(|(name, _)| *name != self.name)
+            .choose_multiple(&mut rand::thread_rng(), self.fanout);
+        let announcement = CertificateAnnouncement {
+            chain_id,
+            height,
+            hash,
+            origin: self.name,
+        };
+        future::join_all(peers.into_iter().map(|(name, address)| {
+            let announcement = announcement.clone();
+            async move {
+                let result = match self.node_provider.make_node(address) {
+                    Ok(node) => node.handle_certificate_announcement(announcement).await,
+                    Err(error) => Err(error),
+                };
+                if let Err(error) = result {
+                    debug!("Failed to announce a block to validator {name}: {error}");
+                }
+            }
+        }))
+        .await;
+        Ok(())
+    }
+
+    /// Downloads and processes the certificates that this validator is missing to reach
+    /// the announced block. The resulting network actions are added to `actions`, including
+    /// when the synchronization fails halfway.
+    ///
+    /// If the chain is already being synchronized, the announcement is handed over to the
+    /// running synchronization instead. The slot is released when this returns.
+    pub async fn handle_announcement(
+        &self,
+        mut announcement: CertificateAnnouncement,
+        _slot: SynchronizationSlot,
+        actions: &mut NetworkActions,
+    ) -> Result<(), GossipError> {
+        let chain_id = announcement.chain_id;
+        match self.pending.lock().unwrap().entry(chain_id) {
+            btree_map::Entry::Occupied(mut entry) => {
+                if entry.get().height < announcement.height {
+                    entry.insert(announcement);
+                }
+                return Ok(());
+            }
+            btree_map::Entry::Vacant(entry) => {
+                entry.insert(announcement.clone());
+            }
+        }
+        let _pending = PendingChain {
+            pending: &self.pending,
+            chain_id,
+        };
+        loop {
+            self.synchronize(&announcement, actions).await?;
+            let pending = self.pending.lock().unwrap();
+            let latest = &pending[&chain_id];
+            if latest.height <= announcement.height {
+                return Ok(());
+            }
+            announcement = latest.clone();
+        }
+    }
+
+    /// Downloads the missing certificates of the announced chain from the announcing
+    /// validator and processes them.
+    async fn synchronize(
+        &self,
+        announcement: &CertificateAnnouncement,
+        actions: &mut NetworkActions,
+    ) -> Result<(), GossipError> {
+        let chain_id = announcement.chain_id;
+        let query = ChainInfoQuery::new(chain_id).with_committees();
+        let (response, _) = self.state.handle_chain_info_query(query).await?;
+        let next_block_height = response.info.next_block_height;
+        if next_block_height > announcement.height {
+            return Ok(());
+        }
+        let max_height_gap = self.max_height_gap;
+        let missing = (announcement.height.0 - next_block_height.0)
+            .checked_add(1)
+            .filter(|missing| *missing <= max_height_gap)
+            .ok_or(GossipError::HeightGapTooLarge {
+                chain_id,
+                max_height_gap,
+            })?;
+        let committee = response
+            .latest_committee()
+            .ok_or(GossipError::UnknownCommittee(chain_id))?;
+        let origin = announcement.origin;
+        let address = committee
+            .network_address(&origin)
+            .ok_or(GossipError::UnknownOrigin { origin, chain_id })?;
+        let remote_node = RemoteNode {
+            name: origin,
+            node: self.node_provider.make_node(address)?,
+        };
+
+        // Only trust the announcement once its certificate is known to be valid.
+        let announced = remote_node
+            .node
+            .download_certificate(announcement.hash)
+            .await?;
+        let is_valid = announced.hash() == announcement.hash
+            && announced.value().chain_id() == chain_id
+            && announced.value().height() == announcement.height
+            && matches!(announced.value(), CertificateValue::ConfirmedBlock { .. })
+            && response
+                .info
+                .requested_committees
+                .as_ref()
+                .and_then(|committees| committees.get(&announced.value().epoch()))
+                .is_some_and(|committee| announced.check(committee).is_ok());
+        if !is_valid {
+            return Err(GossipError::InvalidAnnouncement { origin, chain_id });
+        }
+
+        let certificates = if missing > 1 {
+            remote_node
+                .try_query_certificates_from(chain_id, next_block_height, missing - 1)
+                .await?
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        let mut expected_height = next_block_height;
+        for certificate in certificates.into_iter().chain([announced]) {
+            if certificate.value().chain_id() != chain_id
+                || certificate.value().height() != expected_height
+                || !matches!(certificate.value(), CertificateValue::ConfirmedBlock { .. })
+            {
+                break;
+            }
+            self.process_certificate(&remote_node, certificate, actions)
+                .await?;
+            expected_height = expected_height.try_add_one().map_err(WorkerError::from)?;
+        }
+        if expected_height <= announcement.height {
+            return Err(GossipError::MissingCertificates {
+                origin,
+                chain_id,
+                height: announcement.height,
+            });
+        }
+        debug!(
+            "Synchronized chain {chain_id} up to height {} from validator {origin}",
+            announcement.height
+        );
+        Ok(())
+    }
+
+    /// Processes a downloaded certificate, downloading the blobs it needs from the same
+    /// validator if this validator does not have them.
+    async fn process_certificate(
+        &self,
+        remote_node: &RemoteNode<P::Node>,
+        certificate: Certificate,
+        actions: &mut NetworkActions,
+    ) -> Result<(), GossipError> {
+        let result = self
+            .state
+            .handle_certificate(certificate.clone(), vec![], None)
+            .await;
+        let (_, new_actions) = match result {
+            Err(WorkerError::BlobsNotFound(blob_ids)) => {
+                let blobs = remote_node.try_download_blobs(&blob_ids).await;
+                if blobs.len() < blob_ids.len() {
+                    warn!(
+                        "Validator {} did not provide the blobs of certificate {}",
+                        remote_node.name,
+                        certificate.hash()
+                    );
+                }
+                self.state
+                    .handle_certificate(certificate, blobs, None)
+                    .await?
+            }
+            result => result?,
+        };
+        actions.extend(new_actions);
+        Ok(())
+    }
+
+    /// Returns the latest committee of a chain, as known to this validator.
+    async fn committee(&self, chain_id: ChainId) -> Result<Committee, GossipError> {
+        let query = ChainInfoQuery::new(chain_id).with_committees();
+        let (response, _) = self.state.handle_chain_info_query(query).await?;
+        response
+            .latest_committee()
+            .cloned()
+            .ok_or(GossipError::UnknownCommittee(chain_id))
+    }
+}
+
+/// Ends the synchronization of a chain when dropped.
+struct PendingChain<'a> {
+    pending: &'a Mutex<BTreeMap<ChainId, CertificateAnnouncement>>,
+    chain_id: ChainId,
+}
+
+impl Drop for PendingChain<'_> {
+    fn drop(&mut self) {
+        self.pending.lock().unwrap().remove(&self.chain_id);
+    }
+}
@@ -27,6 +27,8 @@ use thiserror::Error;
 
 use crate::{
     data_types::{ChainInfoQuery, ChainInfoResponse},
+    gossip::CertificateAnnouncement,
+    notifier::NotificationFilter,
     worker::{Notification, WorkerError},
 };
 
@@ -41,6 +43,42 @@ pub enum CrossChainMessageDelivery {
     Blocking,
 }
 
+/// A block proposal or a certificate, submitted to a validator as part of a batch.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(with_testing, derive(Eq, PartialEq))]
+pub enum BatchItem {
+    BlockProposal(Box<BlockProposal>),
+    Certificate {
+        certificate: Box<Certificate>,
+        blobs: Vec<Blob>,
+    },
+}
+
+impl BatchItem {
+    /// Returns the chain that the item is for.
+    pub fn chain_id(&self) -> ChainId {
+        match self {
+            BatchItem::BlockProposal(proposal) => proposal.content.block.chain_id,
+            BatchItem::Certificate { certificate, .. } => certificate.value().chain_id(),
+        }
+    }
+}
+
+impl From<BlockProposal> for BatchItem {
+    fn from(proposal: BlockProposal) -> Self {
+        BatchItem::BlockProposal(Box::new(proposal))
+    }
+}
+
+impl From<Certificate> for BatchItem {
+    fn from(certificate: Certificate) -> Self {
+        BatchItem::Certificate {
+            certificate: Box::new(certificate),
+            blobs: Vec::new(),
+        }
+    }
+}
+
 /// How to communicate with a validator node.
 #[allow(async_fn_in_trait)]
 #[cfg_attr(not(web), trait_variant::make(Send))]
@@ -71,6 +109,14 @@ pub trait ValidatorNode {
         delivery: CrossChainMessageDelivery,
     ) -> Result<ChainInfoResponse, NodeError>;
 
+    /// Processes a batch of block proposals and certificates, possibly for different
+    /// chains. Returns the result of each item, in the same order.
+    async fn handle_batch(
+        &self,
+        items: Vec<BatchItem>,
+        delivery: CrossChainMessageDelivery,
+    ) -> Result<Vec<Result<ChainInfoResponse, NodeError>>, NodeError>;
+
     /// Handles information queries for this chain.
     async fn handle_chain_info_query(
         &self,
@@ -83,8 +129,13 @@ pub trait ValidatorNode {
     /// Gets the network's genesis config hash.
     async fn get_genesis_config_hash(&self) -> Result<CryptoHash, NodeError>;
 
-    /// Subscribes to receiving notifications for a collection of chains.
-    async fn subscribe(&self, chains: Vec<ChainId>) -> Result<Self::NotificationStream, NodeError>;
+    /// Subscribes to receiving the notifications for a collection of chains that match the
+    /// filter.
+    async fn subscribe(
+        &self,
+        chains: Vec<ChainId>,
+        filter: NotificationFilter,
+    ) -> Result<Self::NotificationStream, NodeError>;
 
     async fn download_blob_content(&self, blob_id: BlobId) -> Result<BlobContent, NodeError>;
 
@@ -103,6 +154,12 @@ pub trait ValidatorNode {
 
     /// Returns the hash of the `Certificate` that last used a blob.
     async fn blob_last_used_by(&self, blob_id: BlobId) -> Result<CryptoHash, NodeError>;
+
+    /// Announces a new block of another validator, which this validator may then download.
+    async fn handle_certificate_announcement(
+        &self,
+        announcement: CertificateAnnouncement,
+    ) -> Result<(), NodeError>;
 }
 
 /// Turn an address into a validator node.
@@ -222,6 +279,12 @@ pub enum NodeError {
     InvalidCertificateForBlob(BlobId),
     #[error("Local error handling validator response")]
     LocalError { error: String },
+
+    #[error("The validator is overloaded; retry in {retry_after_ms} ms")]
+    Overloaded { retry_after_ms: u64 },
+
+    #[error("Certificate gossip is not supported by transport: {transport}")]
+    GossipNotSupported { transport: String },
 }
 
 impl From<tonic::Status> for NodeError {
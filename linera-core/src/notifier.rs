@@ -1,14 +1,22 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::sync::Arc;
-
-use dashmap::DashMap;
-use linera_base::identifiers::ChainId;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+};
+
+use dashmap::{mapref::entry::Entry, DashMap};
+use linera_base::identifiers::{ChainId, GenericApplicationId};
+use linera_chain::data_types::Medium;
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 use tracing::trace;
 
-use crate::worker;
+use crate::worker::{self, Reason, ReasonKind};
 
 // TODO(#2171): replace this with a Tokio broadcast channel
 
@@ -16,45 +24,287 @@ use crate::worker;
 /// from the validator.
 /// Clients will be evicted if their connections are terminated.
 pub struct ChannelNotifier<N> {
-    inner: DashMap<ChainId, Vec<UnboundedSender<N>>>,
+    inner: DashMap<ChainId, ChainEntry<N>>,
+    replay: Option<ReplayBuffer>,
+}
+
+/// The subscribers of a chain, and its recent notifications if they are kept.
+struct ChainEntry<N> {
+    subscribers: Vec<Subscriber<N>>,
+    /// The most recent notifications, by increasing sequence number.
+    recent: VecDeque<(u64, N)>,
+}
+
+impl<N> Default for ChainEntry<N> {
+    fn default() -> Self {
+        Self {
+            subscribers: Vec::new(),
+            recent: VecDeque::new(),
+        }
+    }
+}
+
+/// How many notifications a [`ChannelNotifier`] keeps so that interrupted subscriptions
+/// can be resumed.
+#[derive(Clone, Copy, Debug)]
+pub struct ReplayConfig {
+    /// The number of recent notifications kept for each chain.
+    pub notifications_per_chain: NonZeroUsize,
+    /// The number of chains without subscribers whose recent notifications are kept.
+    pub idle_chains: NonZeroUsize,
+}
+
+struct ReplayBuffer {
+    notifications_per_chain: usize,
+    /// The chains that have no subscribers left, least recently notified first.
+    idle_chains: Mutex<LruCache<ChainId, ()>>,
+}
+
+/// The notifications that some chains had since a subscription was interrupted are no
+/// longer available.
+#[derive(Debug, Error)]
+#[error("missed notifications of chains {0:?} are no longer available")]
+pub struct NotificationGap(pub Vec<ChainId>);
+
+/// A client waiting for notifications, and the notifications it wants.
+struct Subscriber<N> {
+    sender: UnboundedSender<N>,
+    filter: Arc<NotificationFilter>,
+}
+
+/// Restricts the notifications delivered to a subscriber. Empty lists don't restrict
+/// anything.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct NotificationFilter {
+    /// The kinds of notifications to deliver.
+    pub reasons: Vec<ReasonKind>,
+    /// The chains that incoming bundles must come from.
+    pub origins: Vec<ChainId>,
+    /// The applications whose channels incoming bundles must be received through. Direct
+    /// messages are not sent through a channel, so they never match.
+    pub applications: Vec<GenericApplicationId>,
+}
+
+impl NotificationFilter {
+    /// Returns whether a notification with the given reason must be delivered.
+    pub fn matches(&self, reason: &Reason) -> bool {
+        if !self.reasons.is_empty() && !self.reasons.contains(&reason.kind()) {
+            return false;
+        }
+        let Reason::NewIncomingBundle { origin, .. } = reason else {
+            return true;
+        };
+        if !self.origins.is_empty() && !self.origins.contains(&origin.sender) {
+            return false;
+        }
+        if self.applications.is_empty() {
+            return true;
+        }
+        match &origin.medium {
+            Medium::Direct => false,
+            Medium::Channel(channel) => self.applications.contains(&channel.application_id),
+        }
+    }
+}
+
+/// A notification that subscribers can filter and resume from.
+pub trait ChainNotification {
+    /// Returns the reason for the notification. Notifications without a known reason are
+    /// delivered to every subscriber.
+    fn reason(&self) -> Option<Reason>;
+
+    /// Returns the sequence number of the notification. Notifications without one are not
+    /// kept for resuming subscriptions.
+    fn sequence_number(&self) -> Option<u64>;
+}
+
+impl ChainNotification for worker::Notification {
+    fn reason(&self) -> Option<Reason> {
+        Some(self.reason.clone())
+    }
+
+    fn sequence_number(&self) -> Option<u64> {
+        Some(self.sequence_number)
+    }
+}
+
+impl<T: ChainNotification, E> ChainNotification for Result<T, E> {
+    fn reason(&self) -> Option<Reason> {
+        self.as_ref().ok()?.reason()
+    }
+
+    fn sequence_number(&self) -> Option<u64> {
+        self.as_ref().ok()?.sequence_number()
+    }
 }
 
 impl<N> Default for ChannelNotifier<N> {
     fn default() -> Self {
         Self {
             inner: DashMap::default(),
+            replay: None,
         }
     }
 }
 
 impl<N> ChannelNotifier<N> {
+    /// Creates a notifier that keeps recent notifications, so that subscriptions can be
+    /// resumed with [`ChannelNotifier::resume_with_filter`].
+    pub fn with_replay(config: ReplayConfig) -> Self {
+        Self {
+            inner: DashMap::default(),
+            replay: Some(ReplayBuffer {
+                notifications_per_chain: config.notifications_per_chain.get(),
+                idle_chains: Mutex::new(LruCache::new(config.idle_chains)),
+            }),
+        }
+    }
+
     /// Creates a subscription given a collection of ChainIds and a sender to the client.
     pub fn subscribe(&self, chain_ids: Vec<ChainId>) -> UnboundedReceiver<N> {
+        self.subscribe_with_filter(chain_ids, NotificationFilter::default())
+    }
+
+    /// Creates a subscription to the notifications of the given chains that match the
+    /// filter.
+    pub fn subscribe_with_filter(
+        &self,
+        chain_ids: Vec<ChainId>,
+        filter: NotificationFilter,
+    ) -> UnboundedReceiver<N> {
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let filter = Arc::new(filter);
         for id in chain_ids {
-            let mut senders = self.inner.entry(id).or_default();
-            senders.push(tx.clone());
+            self.inner
+                .entry(id)
+                .or_default()
+                .subscribers
+                .push(Subscriber {
+                    sender: tx.clone(),
+                    filter: filter.clone(),
+                });
+            self.mark_active(id);
         }
         rx
     }
+
+    /// Records that a chain has subscribers again.
+    fn mark_active(&self, chain_id: ChainId) {
+        if let Some(replay) = &self.replay {
+            replay.idle_chains.lock().unwrap().pop(&chain_id);
+        }
+    }
+
+    /// Records that a chain has no subscribers left. Its entry is removed, unless its
+    /// recent notifications are kept for resuming subscriptions.
+    fn mark_idle(&self, chain_id: ChainId) {
+        let evicted = match &self.replay {
+            None => Some(chain_id),
+            Some(replay) => {
+                let mut idle_chains = replay.idle_chains.lock().unwrap();
+                idle_chains
+                    .push(chain_id, ())
+                    .map(|(evicted, ())| evicted)
+                    .filter(|evicted| *evicted != chain_id)
+            }
+        };
+        if let Some(evicted) = evicted {
+            trace!("No more subscribers for chain {evicted:?}. Removing entry.");
+            self.inner
+                .remove_if(&evicted, |_, entry| entry.subscribers.is_empty());
+        }
+    }
 }
 
 impl<N> ChannelNotifier<N>
 where
-    N: Clone,
+    N: Clone + ChainNotification,
 {
+    /// Creates a subscription to the notifications of the given chains that match the
+    /// filter. For the chains in `resume_from`, the kept notifications that came after the
+    /// given sequence number are delivered first.
+    ///
+    /// Fails if some of these notifications are no longer kept, or were never received.
+    pub fn resume_with_filter(
+        &self,
+        chain_ids: Vec<ChainId>,
+        filter: NotificationFilter,
+        resume_from: &BTreeMap<ChainId, u64>,
+    ) -> Result<UnboundedReceiver<N>, NotificationGap> {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let filter = Arc::new(filter);
+        let mut missing = Vec::new();
+        for id in chain_ids {
+            let mut entry = match (self.inner.entry(id), resume_from.get(&id)) {
+                (Entry::Vacant(_), Some(_)) => {
+                    missing.push(id);
+                    continue;
+                }
+                (entry, _) => entry.or_default(),
+            };
+            if let Some(&last) = resume_from.get(&id) {
+                // Notifications dropped before reaching the notifier leave holes in the
+                // sequence numbers, which cannot be replayed either.
+                let is_contiguous = entry
+                    .recent
+                    .iter()
+                    .map(|(number, _)| *number)
+                    .filter(|number| *number > last)
+                    .zip(last.saturating_add(1)..)
+                    .all(|(number, expected)| number == expected);
+                if !is_contiguous
+                    || !entry
+                        .recent
+                        .front()
+                        .is_some_and(|(first, _)| *first <= last.saturating_add(1))
+                {
+                    missing.push(id);
+                    continue;
+                }
+                for (_, notification) in entry.recent.iter().filter(|(number, notification)| {
+                    *number > last
+                        && notification
+                            .reason()
+                            .map_or(true, |reason| filter.matches(&reason))
+                }) {
+                    // The receiver is still in scope.
+                    let _ = tx.send(notification.clone());
+                }
+            }
+            entry.subscribers.push(Subscriber {
+                sender: tx.clone(),
+                filter: filter.clone(),
+            });
+            drop(entry);
+            self.mark_active(id);
+        }
+        if !missing.is_empty() {
+            return Err(NotificationGap(missing));
+        }
+        Ok(rx)
+    }
+
     /// Notifies all the clients waiting for a notification from a given chain.
     pub fn notify_chain(&self, chain_id: &ChainId, notification: &N) {
-        let senders_is_empty = {
-            let Some(mut senders) = self.inner.get_mut(chain_id) else {
+        let reason = notification.reason();
+        let is_idle = {
+            let Some(mut entry) = self.inner.get_mut(chain_id) else {
                 trace!("Chain {chain_id:?} has no subscribers.");
                 return;
             };
             let mut dead_senders = vec![];
-            let senders = senders.value_mut();
-
-            for (index, sender) in senders.iter_mut().enumerate() {
-                if sender.send(notification.clone()).is_err() {
+            let senders = &mut entry.subscribers;
+
+            for (index, subscriber) in senders.iter_mut().enumerate() {
+                let wanted = reason
+                    .as_ref()
+                    .map_or(true, |reason| subscriber.filter.matches(reason));
+                let is_dead = if wanted {
+                    subscriber.sender.send(notification.clone()).is_err()
+                } else {
+                    subscriber.sender.is_closed()
+                };
+                if is_dead {
                     dead_senders.push(index);
                 }
             }
@@ -64,12 +314,20 @@ where
                 senders.remove(index);
             }
 
-            senders.is_empty()
+            if let (Some(replay), Some(number)) = (&self.replay, notification.sequence_number()) {
+                let recent = &mut entry.recent;
+                let index = recent.partition_point(|(other, _)| *other < number);
+                recent.insert(index, (number, notification.clone()));
+                if recent.len() > replay.notifications_per_chain {
+                    recent.pop_front();
+                }
+            }
+
+            entry.subscribers.is_empty()
         };
 
-        if senders_is_empty {
-            trace!("No more subscribers for chain {chain_id:?}. Removing entry.");
-            self.inner.remove(chain_id);
+        if is_idle {
+            self.mark_idle(*chain_id);
         }
     }
 }
@@ -105,8 +363,22 @@ pub mod tests {
         time::Duration,
     };
 
+    use assert_matches::assert_matches;
+    use linera_base::{crypto::CryptoHash, data_types::BlockHeight};
+    use linera_chain::data_types::{ChannelFullName, Origin};
+
     use super::*;
 
+    impl ChainNotification for () {
+        fn reason(&self) -> Option<Reason> {
+            None
+        }
+
+        fn sequence_number(&self) -> Option<u64> {
+            None
+        }
+    }
+
     #[test]
     fn test_concurrent() {
         let notifier = ChannelNotifier::default();
@@ -217,4 +489,227 @@ pub mod tests {
         notifier.notify_chain(&chain_d, &());
         assert_eq!(notifier.inner.len(), 0);
     }
+
+    #[test]
+    fn test_filter() {
+        let notifier = ChannelNotifier::default();
+        let chain = ChainId::root(0);
+        let sender = ChainId::root(1);
+        let other_sender = ChainId::root(2);
+        let notification = |reason| worker::Notification {
+            chain_id: chain,
+            reason,
+            sequence_number: 0,
+        };
+        let new_block = notification(Reason::NewBlock {
+            height: BlockHeight(1),
+            hash: CryptoHash::test_hash("block"),
+        });
+        let direct_bundle = |sender| {
+            notification(Reason::NewIncomingBundle {
+                origin: Origin {
+                    sender,
+                    medium: Medium::Direct,
+                },
+                height: BlockHeight(0),
+            })
+        };
+        let system_channel_bundle = notification(Reason::NewIncomingBundle {
+            origin: Origin {
+                sender,
+                medium: Medium::Channel(ChannelFullName {
+                    application_id: GenericApplicationId::System,
+                    name: b"channel".to_vec().into(),
+                }),
+            },
+            height: BlockHeight(0),
+        });
+
+        let mut rx_all = notifier.subscribe(vec![chain]);
+        let mut rx_bundles = notifier.subscribe_with_filter(
+            vec![chain],
+            NotificationFilter {
+                reasons: vec![ReasonKind::NewIncomingBundle],
+                ..NotificationFilter::default()
+            },
+        );
+        let mut rx_sender = notifier.subscribe_with_filter(
+            vec![chain],
+            NotificationFilter {
+                origins: vec![sender],
+                ..NotificationFilter::default()
+            },
+        );
+        let mut rx_system = notifier.subscribe_with_filter(
+            vec![chain],
+            NotificationFilter {
+                applications: vec![GenericApplicationId::System],
+                ..NotificationFilter::default()
+            },
+        );
+
+        for notification in [
+            &new_block,
+            &direct_bundle(sender),
+            &direct_bundle(other_sender),
+            &system_channel_bundle,
+        ] {
+            notifier.notify_chain(&chain, notification);
+        }
+
+        let received = |rx: &mut UnboundedReceiver<worker::Notification>| {
+            std::iter::from_fn(|| rx.try_recv().ok()).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            received(&mut rx_all),
+            vec![
+                new_block.clone(),
+                direct_bundle(sender),
+                direct_bundle(other_sender),
+                system_channel_bundle.clone(),
+            ]
+        );
+        assert_eq!(
+            received(&mut rx_bundles),
+            vec![
+                direct_bundle(sender),
+                direct_bundle(other_sender),
+                system_channel_bundle.clone(),
+            ]
+        );
+        assert_eq!(
+            received(&mut rx_sender),
+            vec![
+                new_block.clone(),
+                direct_bundle(sender),
+                system_channel_bundle.clone(),
+            ]
+        );
+        assert_eq!(
+            received(&mut rx_system),
+            vec![new_block, system_channel_bundle]
+        );
+
+        // Closed subscribers are evicted even if they don't want the notification.
+        rx_all.close();
+        rx_sender.close();
+        rx_system.close();
+        rx_bundles.close();
+        notifier.notify_chain(
+            &chain,
+            &notification(Reason::NewRound {
+                height: BlockHeight(1),
+                round: linera_base::data_types::Round::Fast,
+            }),
+        );
+        assert_eq!(notifier.inner.len(), 0);
+    }
+
+    #[test]
+    fn test_resume() {
+        let notifier = ChannelNotifier::with_replay(ReplayConfig {
+            notifications_per_chain: NonZeroUsize::new(2).unwrap(),
+            idle_chains: NonZeroUsize::new(1).unwrap(),
+        });
+        let chain_a = ChainId::root(0);
+        let chain_b = ChainId::root(1);
+        let notification = |chain_id, sequence_number| worker::Notification {
+            chain_id,
+            reason: Reason::NewBlock {
+                height: BlockHeight(sequence_number),
+                hash: CryptoHash::test_hash("block"),
+            },
+            sequence_number,
+        };
+        let received = |rx: &mut UnboundedReceiver<worker::Notification>| {
+            std::iter::from_fn(|| rx.try_recv().ok())
+                .map(|notification| notification.sequence_number)
+                .collect::<Vec<_>>()
+        };
+
+        // Notifications of chains that were never subscribed to are not kept.
+        notifier.notify_chain(&chain_a, &notification(chain_a, 10));
+        assert_eq!(notifier.inner.len(), 0);
+
+        let mut rx = notifier.subscribe(vec![chain_a]);
+        for number in 11..=13 {
+            notifier.notify_chain(&chain_a, &notification(chain_a, number));
+        }
+        assert_eq!(received(&mut rx), vec![11, 12, 13]);
+
+        // The subscriber disconnects and misses a notification.
+        drop(rx);
+        notifier.notify_chain(&chain_a, &notification(chain_a, 14));
+        assert_eq!(notifier.inner.len(), 1);
+
+        let mut rx = notifier
+            .resume_with_filter(
+                vec![chain_a],
+                NotificationFilter::default(),
+                &BTreeMap::from([(chain_a, 13)]),
+            )
+            .unwrap();
+        notifier.notify_chain(&chain_a, &notification(chain_a, 15));
+        assert_eq!(received(&mut rx), vec![14, 15]);
+
+        // Only the last two notifications are kept.
+        assert_matches!(
+            notifier.resume_with_filter(
+                vec![chain_a],
+                NotificationFilter::default(),
+                &BTreeMap::from([(chain_a, 12)]),
+            ),
+            Err(NotificationGap(chains)) if chains == vec![chain_a]
+        );
+
+        // Resuming from the latest notification replays nothing.
+        let mut rx_up_to_date = notifier
+            .resume_with_filter(
+                vec![chain_a],
+                NotificationFilter::default(),
+                &BTreeMap::from([(chain_a, 15)]),
+            )
+            .unwrap();
+        assert_eq!(received(&mut rx_up_to_date), Vec::<u64>::new());
+
+        // Notifications that never reached the notifier cannot be replayed.
+        let rx_gap = notifier.subscribe(vec![chain_a]);
+        notifier.notify_chain(&chain_a, &notification(chain_a, 17));
+        assert_matches!(
+            notifier.resume_with_filter(
+                vec![chain_a],
+                NotificationFilter::default(),
+                &BTreeMap::from([(chain_a, 15)]),
+            ),
+            Err(NotificationGap(chains)) if chains == vec![chain_a]
+        );
+        drop(rx_gap);
+        notifier.notify_chain(&chain_a, &notification(chain_a, 18));
+        let mut rx_after_gap = notifier
+            .resume_with_filter(
+                vec![chain_a],
+                NotificationFilter::default(),
+                &BTreeMap::from([(chain_a, 17)]),
+            )
+            .unwrap();
+        assert_eq!(received(&mut rx_after_gap), vec![18]);
+        drop(rx_after_gap);
+
+        // Once chain B becomes idle, the notifications of chain A are no longer kept.
+        drop(rx);
+        drop(rx_up_to_date);
+        notifier.notify_chain(&chain_a, &notification(chain_a, 19));
+        let rx_b = notifier.subscribe(vec![chain_b]);
+        drop(rx_b);
+        notifier.notify_chain(&chain_b, &notification(chain_b, 0));
+        assert_eq!(notifier.inner.len(), 1);
+        assert_matches!(
+            notifier.resume_with_filter(
+                vec![chain_a],
+                NotificationFilter::default(),
+                &BTreeMap::from([(chain_a, 19)]),
+            ),
+            Err(NotificationGap(_))
+        );
+    }
 }
@@ -5,13 +5,15 @@
 #[path = "./wasm_client_tests.rs"]
 mod wasm;
 
+use std::collections::HashSet;
+
 use assert_matches::assert_matches;
 use futures::StreamExt;
 use linera_base::{
     crypto::*,
     data_types::*,
     identifiers::{Account, BlobId, BlobType, ChainDescription, ChainId, MessageId, Owner},
-    ownership::{ChainOwnership, TimeoutConfig},
+    ownership::{ChainOwnership, RecoveryConfig, TimeoutConfig},
 };
 use linera_chain::{
     data_types::{
@@ -41,7 +43,7 @@ use crate::test_utils::ServiceStorageBuilder;
 use crate::{
     client::{
         BlanketMessagePolicy, ChainClient, ChainClientError, ClientOutcome, MessageAction,
-        MessagePolicy,
+        MessagePolicy, MessageRule,
     },
     local_node::LocalNodeError,
     node::{
@@ -128,6 +130,7 @@ where
         Some(Notification {
             reason: Reason::NewBlock { height, .. },
             chain_id,
+            ..
         }) if chain_id == ChainId::root(1) && height == BlockHeight::ZERO
     );
     Ok(())
@@ -489,6 +492,172 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_block_approvals<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let client_a = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    let key_pair_b = KeyPair::generate();
+    let key_pair_c = KeyPair::generate();
+    let owner_c = Owner::from(key_pair_c.public());
+    let ownership = ChainOwnership::multiple(
+        [
+            (client_a.public_key().await?, 100),
+            (key_pair_b.public(), 100),
+            (key_pair_c.public(), 100),
+        ],
+        10,
+        TimeoutConfig::default(),
+    )
+    .with_approval_threshold(200);
+    client_a.change_ownership(ownership).await.unwrap().unwrap();
+    let client_b = builder
+        .make_client(
+            client_a.chain_id,
+            key_pair_b,
+            client_a.block_hash(),
+            BlockHeight::from(1),
+        )
+        .await?;
+
+    // Without a second owner's approval, the transfer cannot be proposed.
+    let result = client_a
+        .transfer_to_account(None, Amount::ONE, Account::chain(ChainId::root(2)))
+        .await;
+    assert_matches!(
+        result,
+        Err(ChainClientError::InsufficientBlockApprovals {
+            weight: 100,
+            threshold: 200
+        })
+    );
+    let block = client_a.pending_block().clone().unwrap();
+
+    // Owner B approves the block. Their signature is not a valid approval by owner C.
+    let (owner_b, signature) = client_b.approve_block(block).await.unwrap();
+    assert_matches!(
+        client_a.add_block_approval(owner_c, signature).await,
+        Err(ChainClientError::BlockProposalError(_))
+    );
+    client_a
+        .add_block_approval(owner_b, signature)
+        .await
+        .unwrap();
+
+    // Now the block can be committed.
+    let certificate = client_a.process_pending_block().await.unwrap().unwrap();
+    assert!(certificate.is_some());
+    assert_eq!(client_a.next_block_height(), BlockHeight::from(2));
+    assert!(client_a.pending_block().is_none());
+    assert_eq!(
+        client_a.local_balance().await.unwrap(),
+        Amount::from_tokens(3)
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_guardian_recovery<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let clock = storage_builder.clock().clone();
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let client_a = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    let chain_id = client_a.chain_id;
+    let guardian_key_pairs = [KeyPair::generate(), KeyPair::generate()];
+    let new_key_pair = KeyPair::generate();
+    let new_public_key = new_key_pair.public();
+    let recovery = RecoveryConfig::new(
+        guardian_key_pairs.iter().map(KeyPair::public),
+        2,
+        TimeDelta::from_secs(60),
+    );
+    let ownership = ChainOwnership::single(client_a.public_key().await?).with_recovery(recovery);
+    client_a.change_ownership(ownership).await.unwrap().unwrap();
+
+    // The single-owner chain starts in the fast round, but with guardians that round times out.
+    let manager = client_a.chain_info().await?.manager;
+    assert_eq!(manager.current_round, Round::Fast);
+    clock.set(manager.round_timeout.unwrap());
+    let certificate = client_a.request_leader_timeout().await.unwrap();
+    assert_eq!(certificate.round, Round::Fast);
+
+    // Both guardians vote for the new owner. Their blocks are confirmed even though the owner
+    // is not involved.
+    let [key_pair_1, key_pair_2] = guardian_key_pairs;
+    let guardian_1 = builder
+        .make_client(
+            chain_id,
+            key_pair_1,
+            client_a.block_hash(),
+            BlockHeight::from(1),
+        )
+        .await?;
+    let certificate = guardian_1
+        .initiate_recovery(new_public_key)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!certificate.round.is_fast());
+    assert_eq!(guardian_1.next_block_height(), BlockHeight::from(2));
+
+    // At the next height the chain is back in the fast round; the second guardian skips it.
+    let guardian_2 = builder
+        .make_client(
+            chain_id,
+            key_pair_2,
+            guardian_1.block_hash(),
+            BlockHeight::from(2),
+        )
+        .await?;
+    let certificate = guardian_2
+        .initiate_recovery(new_public_key)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!certificate.round.is_fast());
+
+    // After the delay, the recovery can be completed and the new owner controls the chain.
+    clock.add(TimeDelta::from_secs(60));
+    guardian_2.complete_recovery().await.unwrap().unwrap();
+    let client_b = builder
+        .make_client(
+            chain_id,
+            new_key_pair,
+            guardian_2.block_hash(),
+            BlockHeight::from(4),
+        )
+        .await?;
+    client_b
+        .transfer_to_account(None, Amount::ONE, Account::chain(ChainId::root(2)))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(client_b.next_block_height(), BlockHeight::from(5));
+    assert_eq!(
+        client_b.local_balance().await.unwrap(),
+        Amount::from_tokens(3)
+    );
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
@@ -1297,6 +1466,67 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_migrate_epoch<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1).await?;
+    let admin = builder
+        .add_initial_chain(ChainDescription::Root(0), Amount::from_tokens(3))
+        .await?;
+    let user = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::ZERO)
+        .await?;
+
+    // The user chain has a pending transfer, followed by the new committee.
+    admin
+        .transfer_to_account(None, Amount::ONE, Account::chain(ChainId::root(1)))
+        .await
+        .unwrap()
+        .unwrap();
+    let validators = builder.initial_committee.validators().clone();
+    let committee = Committee::new(validators, ResourceControlPolicy::default());
+    admin.stage_new_committee(committee).await.unwrap();
+    user.synchronize_from_validators().await.unwrap();
+
+    // Migrating only receives the committee change, not the transfer.
+    let (certificates, timeout) = user.migrate_epoch().await.unwrap();
+    assert!(timeout.is_none());
+    assert_eq!(certificates.len(), 1);
+    let block = certificates[0].value().block().unwrap();
+    assert!(block.operations.is_empty());
+    assert!(!block.incoming_bundles.is_empty());
+    for incoming_bundle in &block.incoming_bundles {
+        assert_eq!(incoming_bundle.origin.sender, ChainId::root(0));
+        assert_matches!(incoming_bundle.origin.medium, Medium::Channel(_));
+        assert!(incoming_bundle
+            .bundle
+            .messages
+            .iter()
+            .all(|posted_message| {
+                matches!(
+                    posted_message.message,
+                    Message::System(SystemMessage::CreateCommittee { .. })
+                )
+            }));
+    }
+    assert_eq!(user.epoch().await.unwrap(), Epoch::from(1));
+    assert_eq!(user.chain_info().await?.chain_balance, Amount::ZERO);
+
+    // There is nothing left to migrate, and the transfer is still in the inbox.
+    let (certificates, _) = user.migrate_epoch().await.unwrap();
+    assert!(certificates.is_empty());
+    user.process_inbox().await.unwrap();
+    assert_eq!(user.chain_info().await?.chain_balance, Amount::ONE);
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
 #[test_log::test(tokio::test)]
@@ -2403,6 +2633,89 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[test_log::test(tokio::test)]
+async fn test_message_policy_rules<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 1)
+        .await?
+        .with_policy(ResourceControlPolicy::only_fuel());
+    let sender1 = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    let mut receiver = builder
+        .add_initial_chain(ChainDescription::Root(2), Amount::ZERO)
+        .await?;
+    let sender3 = builder
+        .add_initial_chain(ChainDescription::Root(3), Amount::from_tokens(4))
+        .await?;
+    let recipient = Recipient::chain(ChainId::root(2));
+    for sender in [&sender1, &sender3] {
+        let cert = sender
+            .transfer(None, Amount::ONE, recipient)
+            .await
+            .unwrap()
+            .unwrap();
+        receiver
+            .receive_certificate_and_update_validators(cert)
+            .await?;
+    }
+
+    // Bundles from chain 3 take precedence.
+    receiver.options_mut().max_pending_message_bundles = 1;
+    receiver.options_mut().message_policy = MessagePolicy::new(BlanketMessagePolicy::Accept, None)
+        .with_rules(vec![MessageRule {
+            senders: Some(HashSet::from([ChainId::root(3)])),
+            priority: 1,
+            ..MessageRule::default()
+        }]);
+    let certs = receiver.process_inbox().await?.0;
+    let senders = certs
+        .iter()
+        .map(|cert| {
+            let block = cert.value().block().unwrap();
+            block.incoming_bundles[0].origin.sender
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(senders, vec![ChainId::root(3), ChainId::root(1)]);
+    assert_eq!(
+        receiver.local_balance().await.unwrap(),
+        Amount::from_tokens(2)
+    );
+
+    // Bundles from chain 1 are now ignored, unless they carry a grant.
+    receiver.options_mut().message_policy = MessagePolicy::new(BlanketMessagePolicy::Accept, None)
+        .with_rules(vec![
+            MessageRule {
+                min_grant: Amount::ONE,
+                ..MessageRule::default()
+            },
+            MessageRule {
+                senders: Some(HashSet::from([ChainId::root(1)])),
+                action: BlanketMessagePolicy::Ignore,
+                ..MessageRule::default()
+            },
+        ]);
+    let cert = sender1
+        .transfer(None, Amount::ONE, recipient)
+        .await
+        .unwrap()
+        .unwrap();
+    receiver
+        .receive_certificate_and_update_validators(cert)
+        .await?;
+    assert!(receiver.process_inbox().await?.0.is_empty());
+    assert_eq!(
+        receiver.local_balance().await.unwrap(),
+        Amount::from_tokens(2)
+    );
+
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
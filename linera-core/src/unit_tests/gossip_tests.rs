@@ -0,0 +1,170 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use linera_base::{
+    data_types::{Amount, BlockHeight},
+    identifiers::{Account, ChainDescription, ChainId},
+};
+use test_case::test_case;
+
+use super::{CertificateAnnouncement, CertificateGossip, GossipError};
+#[cfg(feature = "rocksdb")]
+use crate::test_utils::RocksDbStorageBuilder;
+#[cfg(feature = "storage-service")]
+use crate::test_utils::ServiceStorageBuilder;
+use crate::{
+    data_types::ChainInfoQuery,
+    node::ValidatorNode,
+    test_utils::{FaultType, MemoryStorageBuilder, StorageBuilder, TestBuilder},
+    worker::NetworkActions,
+};
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[test_log::test(tokio::test)]
+async fn test_lagging_validator_catches_up_from_announcement<B>(
+    storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    builder.set_fault_type([3], FaultType::Offline).await;
+    let recipient = Account::chain(ChainId::root(2));
+    sender
+        .transfer_to_account(None, Amount::ONE, recipient)
+        .await
+        .unwrap()
+        .unwrap();
+    let certificate = sender
+        .transfer_to_account(None, Amount::ONE, recipient)
+        .await
+        .unwrap()
+        .unwrap();
+    builder.set_fault_type([3], FaultType::Honest).await;
+
+    let query = ChainInfoQuery::new(sender.chain_id());
+    let info = builder
+        .node(3)
+        .handle_chain_info_query(query.clone())
+        .await?;
+    assert_eq!(info.info.next_block_height, BlockHeight::ZERO);
+
+    let gossip = CertificateGossip::new(
+        builder.node(3).state().await,
+        builder.make_node_provider(),
+        3,
+    );
+    let announcement = CertificateAnnouncement {
+        chain_id: sender.chain_id(),
+        height: BlockHeight::from(1),
+        hash: certificate.hash(),
+        origin: builder.node(0).name(),
+    };
+    let mut actions = NetworkActions::default();
+    let slot = gossip.try_reserve_synchronization().unwrap();
+    gossip
+        .handle_announcement(announcement.clone(), slot, &mut actions)
+        .await?;
+    assert!(!actions.cross_chain_requests.is_empty());
+
+    let info = builder.node(3).handle_chain_info_query(query).await?;
+    assert_eq!(info.info.next_block_height, BlockHeight::from(2));
+    builder
+        .check_that_validators_have_certificate(sender.chain_id(), BlockHeight::from(1), 4)
+        .await
+        .unwrap();
+
+    // Announcing a block that the validator already has is a no-op.
+    let mut actions = NetworkActions::default();
+    let slot = gossip.try_reserve_synchronization().unwrap();
+    gossip
+        .handle_announcement(announcement, slot, &mut actions)
+        .await?;
+    assert!(actions.cross_chain_requests.is_empty());
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "storage-service", test_case(ServiceStorageBuilder::new().await; "storage_service"))]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[test_log::test(tokio::test)]
+async fn test_invalid_announcements_are_rejected<B>(storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let mut builder = TestBuilder::new(storage_builder, 4, 0).await?;
+    let sender = builder
+        .add_initial_chain(ChainDescription::Root(1), Amount::from_tokens(4))
+        .await?;
+    builder.set_fault_type([3], FaultType::Offline).await;
+    let recipient = Account::chain(ChainId::root(2));
+    let certificate = sender
+        .transfer_to_account(None, Amount::ONE, recipient)
+        .await
+        .unwrap()
+        .unwrap();
+    builder.set_fault_type([3], FaultType::Honest).await;
+
+    let gossip = CertificateGossip::new(
+        builder.node(3).state().await,
+        builder.make_node_provider(),
+        3,
+    )
+    .with_max_height_gap(10)
+    .with_max_concurrent_synchronizations(1);
+    let announcement = CertificateAnnouncement {
+        chain_id: sender.chain_id(),
+        height: BlockHeight::ZERO,
+        hash: certificate.hash(),
+        origin: builder.node(0).name(),
+    };
+
+    // Only one chain is synchronized at a time.
+    let slot = gossip.try_reserve_synchronization().unwrap();
+    assert!(gossip.try_reserve_synchronization().is_none());
+    drop(slot);
+
+    // Announcements too far ahead are ignored.
+    let far_ahead = CertificateAnnouncement {
+        height: BlockHeight(u64::MAX),
+        ..announcement.clone()
+    };
+    let slot = gossip.try_reserve_synchronization().unwrap();
+    let result = gossip
+        .handle_announcement(far_ahead, slot, &mut NetworkActions::default())
+        .await;
+    assert!(matches!(result, Err(GossipError::HeightGapTooLarge { .. })));
+
+    // The announced certificate must be at the announced height.
+    let wrong_height = CertificateAnnouncement {
+        height: BlockHeight::from(5),
+        ..announcement.clone()
+    };
+    let slot = gossip.try_reserve_synchronization().unwrap();
+    let result = gossip
+        .handle_announcement(wrong_height, slot, &mut NetworkActions::default())
+        .await;
+    assert!(matches!(
+        result,
+        Err(GossipError::InvalidAnnouncement { .. })
+    ));
+
+    let query = ChainInfoQuery::new(sender.chain_id());
+    let info = builder.node(3).handle_chain_info_query(query).await?;
+    assert_eq!(info.info.next_block_height, BlockHeight::ZERO);
+
+    // The genuine announcement is accepted.
+    let slot = gossip.try_reserve_synchronization().unwrap();
+    gossip
+        .handle_announcement(announcement, slot, &mut NetworkActions::default())
+        .await?;
+    let query = ChainInfoQuery::new(sender.chain_id());
+    let info = builder.node(3).handle_chain_info_query(query).await?;
+    assert_eq!(info.info.next_block_height, BlockHeight::from(1));
+    Ok(())
+}
@@ -10,6 +10,7 @@ use std::{
 
 use async_trait::async_trait;
 use futures::{
+    future,
     lock::{Mutex, MutexGuard},
     Future,
 };
@@ -49,11 +50,12 @@ use {
 use crate::{
     client::{ChainClient, Client},
     data_types::*,
+    gossip::CertificateAnnouncement,
     node::{
-        CrossChainMessageDelivery, NodeError, NotificationStream, ValidatorNode,
+        BatchItem, CrossChainMessageDelivery, NodeError, NotificationStream, ValidatorNode,
         ValidatorNodeProvider,
     },
-    notifier::ChannelNotifier,
+    notifier::{ChannelNotifier, NotificationFilter},
     worker::{NetworkActions, Notification, WorkerState},
 };
 
@@ -132,6 +134,22 @@ where
         .await
     }
 
+    async fn handle_batch(
+        &self,
+        items: Vec<BatchItem>,
+        delivery: CrossChainMessageDelivery,
+    ) -> Result<Vec<Result<ChainInfoResponse, NodeError>>, NodeError> {
+        Ok(future::join_all(items.into_iter().map(|item| async move {
+            match item {
+                BatchItem::BlockProposal(proposal) => self.handle_block_proposal(*proposal).await,
+                BatchItem::Certificate { certificate, blobs } => {
+                    self.handle_certificate(*certificate, blobs, delivery).await
+                }
+            }
+        }))
+        .await)
+    }
+
     async fn handle_chain_info_query(
         &self,
         query: ChainInfoQuery,
@@ -142,9 +160,15 @@ where
         .await
     }
 
-    async fn subscribe(&self, chains: Vec<ChainId>) -> Result<NotificationStream, NodeError> {
-        self.spawn_and_receive(move |validator, sender| validator.do_subscribe(chains, sender))
-            .await
+    async fn subscribe(
+        &self,
+        chains: Vec<ChainId>,
+        filter: NotificationFilter,
+    ) -> Result<NotificationStream, NodeError> {
+        self.spawn_and_receive(move |validator, sender| {
+            validator.do_subscribe(chains, filter, sender)
+        })
+        .await
     }
 
     async fn get_version_info(&self) -> Result<VersionInfo, NodeError> {
@@ -195,6 +219,13 @@ where
         })
         .await
     }
+
+    async fn handle_certificate_announcement(
+        &self,
+        _announcement: CertificateAnnouncement,
+    ) -> Result<(), NodeError> {
+        Ok(())
+    }
 }
 
 impl<S> LocalValidatorClient<S>
@@ -225,6 +256,11 @@ where
         self.client.lock().await.fault_type
     }
 
+    /// Returns the worker state of the local validator.
+    pub async fn state(&self) -> WorkerState<S> {
+        self.client.lock().await.state.clone()
+    }
+
     /// Obtains the basic `ChainInfo` data for the local validator chain, with chain manager values.
     pub async fn chain_info_with_manager_values(
         &mut self,
@@ -416,10 +452,11 @@ where
     async fn do_subscribe(
         self,
         chains: Vec<ChainId>,
+        filter: NotificationFilter,
         sender: oneshot::Sender<Result<NotificationStream, NodeError>>,
     ) -> Result<(), Result<NotificationStream, NodeError>> {
         let validator = self.client.lock().await;
-        let rx = validator.notifier.subscribe(chains);
+        let rx = validator.notifier.subscribe_with_filter(chains, filter);
         let stream: NotificationStream = Box::pin(UnboundedReceiverStream::new(rx));
         sender.send(Ok(stream))
     }
@@ -516,8 +553,14 @@ where
 {
     type Node = LocalValidatorClient<S>;
 
-    fn make_node(&self, _name: &str) -> Result<Self::Node, NodeError> {
-        unimplemented!()
+    fn make_node(&self, address: &str) -> Result<Self::Node, NodeError> {
+        // Test committees use the validator names as network addresses.
+        let error = || NodeError::CannotResolveValidatorAddress {
+            address: address.to_string(),
+        };
+        let name = address.parse::<ValidatorName>().map_err(|_| error())?;
+        let client = self.0.get(&name).ok_or_else(error)?.clone();
+        Ok(LocalValidatorClient { name, client })
     }
 
     fn make_nodes_from_list<A>(
@@ -227,6 +227,7 @@ where
                 grant: Amount::ZERO,
                 refund_grant_to: None,
                 kind: MessageKind::Protected,
+                expiry: None,
                 message: Message::System(SystemMessage::ApplicationCreated),
             }]],
             events: vec![Vec::new()],
@@ -37,7 +37,8 @@ use linera_chain::{
 use linera_execution::{
     committee::{Committee, Epoch, ValidatorName},
     system::{
-        AdminOperation, OpenChainConfig, Recipient, SystemChannel, SystemMessage, SystemOperation,
+        AdminOperation, OpenChainConfig, Preimage, Recipient, SystemChannel, SystemMessage,
+        SystemOperation,
     },
     test_utils::{register_mock_applications, ExpectedCall, SystemExecutionState},
     ChannelSubscription, ExecutionError, Message, MessageKind, Query, QueryContext, Response,
@@ -65,7 +66,7 @@ use crate::{
     data_types::*,
     test_utils::{MemoryStorageBuilder, StorageBuilder},
     worker::{
-        Notification,
+        Notification, NotificationSequenceNumbers,
         Reason::{self, NewBlock, NewIncomingBundle},
         WorkerError, WorkerState,
     },
@@ -284,6 +285,7 @@ where
                             grant: Amount::ZERO,
                             refund_grant_to: None,
                             kind: MessageKind::Bouncing,
+                            expiry: None,
                             message: posted_message.message.clone(),
                         }]
                     } else {
@@ -341,6 +343,7 @@ fn direct_outgoing_message(
         grant: Amount::ZERO,
         refund_grant_to: None,
         kind,
+        expiry: None,
         message: Message::System(message),
     }
 }
@@ -356,6 +359,7 @@ fn channel_outgoing_message(
         grant: Amount::ZERO,
         refund_grant_to: None,
         kind,
+        expiry: None,
         message: Message::System(message),
     }
 }
@@ -566,6 +570,147 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_handle_block_proposal_expired_message<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let storage = storage_builder.build().await?;
+    let clock = storage_builder.clock();
+    let key_pair = KeyPair::generate();
+    let balances = vec![(
+        ChainDescription::Root(1),
+        key_pair.public(),
+        Amount::from_tokens(5),
+    )];
+    let (_committee, worker) = init_worker_with_chains(storage, balances).await;
+
+    let expiry = Timestamp::from(1_000);
+    let mut posted_message = system_credit_message(Amount::ONE).to_posted(0, MessageKind::Tracked);
+    posted_message.expiry = Some(expiry);
+    let incoming_bundle = IncomingBundle {
+        origin: Origin::chain(ChainId::root(2)),
+        bundle: MessageBundle {
+            certificate_hash: CryptoHash::test_hash("certificate"),
+            height: BlockHeight::ZERO,
+            timestamp: Timestamp::from(0),
+            transaction_index: 0,
+            messages: vec![posted_message],
+        },
+        action: MessageAction::Accept,
+    };
+
+    // The block's timestamp is before the expiry, but the validator's clock is not.
+    clock.set(expiry);
+    let block_proposal = make_first_block(ChainId::root(1))
+        .with_timestamp(Timestamp::from(0))
+        .with_incoming_bundle(incoming_bundle)
+        .into_fast_proposal(&key_pair);
+    assert_matches!(
+        worker.handle_block_proposal(block_proposal).await,
+        Err(WorkerError::ChainError(error))
+            if matches!(*error, ChainError::ExpiredMessage { expiry: e, .. } if e == expiry)
+    );
+    Ok(())
+}
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_handle_block_proposal_refund_with_pending_redemption<B>(
+    mut storage_builder: B,
+) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let storage = storage_builder.build().await?;
+    let clock = storage_builder.clock();
+    let key_pairs = generate_key_pairs(2);
+    let balances = vec![
+        (
+            ChainDescription::Root(1),
+            key_pairs[0].public(),
+            Amount::from_tokens(5),
+        ),
+        (
+            ChainDescription::Root(2),
+            key_pairs[1].public(),
+            Amount::from_tokens(5),
+        ),
+    ];
+    let (committee, worker) = init_worker_with_chains(storage, balances).await;
+    let preimage = Preimage(b"secret".to_vec());
+    let hash = preimage.hash();
+    let deadline = Timestamp::from(1_000);
+
+    // Chain 1 locks a token for chain 2.
+    let block0 = make_first_block(ChainId::root(1)).with_operation(SystemOperation::LockFunds {
+        owner: None,
+        recipient: Account::chain(ChainId::root(2)),
+        amount: Amount::ONE,
+        hash,
+        deadline,
+    });
+    let (executed_block0, _) = worker.stage_block_execution(block0).await?;
+    let value0 = HashedCertificateValue::new_confirmed(executed_block0);
+    let certificate0 = make_certificate(&committee, &worker, value0.clone());
+    worker
+        .fully_handle_certificate(certificate0, vec![])
+        .await?;
+
+    // Chain 2 reveals the preimage before the deadline, but chain 1 doesn't process it yet.
+    let redeem_block =
+        make_first_block(ChainId::root(2)).with_operation(SystemOperation::RedeemLock {
+            chain_id: ChainId::root(1),
+            preimage,
+        });
+    let (executed_redeem_block, _) = worker.stage_block_execution(redeem_block).await?;
+    let redeem_value = HashedCertificateValue::new_confirmed(executed_redeem_block);
+    let redeem_certificate = make_certificate(&committee, &worker, redeem_value);
+    worker
+        .fully_handle_certificate(redeem_certificate, vec![])
+        .await?;
+    let bundle = {
+        let chain = worker.chain_state_view(ChainId::root(1)).await?;
+        let inbox = chain
+            .inboxes
+            .try_load_entry(&Origin::chain(ChainId::root(2)))
+            .await?
+            .expect("Missing inbox for `ChainId::root(2)` in `ChainId::root(1)`");
+        inbox.added_bundles.front().await?.unwrap()
+    };
+
+    // After the deadline, the owner cannot refund the lock while the redemption is pending.
+    clock.set(deadline);
+    let refund_proposal = make_child_block(&value0)
+        .with_timestamp(deadline)
+        .with_operation(SystemOperation::RefundLock { hash })
+        .into_fast_proposal(&key_pairs[0]);
+    assert_matches!(
+        worker.handle_block_proposal(refund_proposal).await,
+        Err(WorkerError::ChainError(error))
+            if matches!(*error, ChainError::PendingLockRedemption { hash: h, .. } if h == hash)
+    );
+
+    // Processing the redemption releases the funds to chain 2 instead.
+    let redeem_proposal = make_child_block(&value0)
+        .with_timestamp(deadline)
+        .with_incoming_bundle(IncomingBundle {
+            origin: Origin::chain(ChainId::root(2)),
+            bundle,
+            action: MessageAction::Accept,
+        })
+        .into_fast_proposal(&key_pairs[0]);
+    worker.handle_block_proposal(redeem_proposal).await?;
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
@@ -789,28 +934,32 @@ where
                 reason: NewBlock {
                     height: BlockHeight(0),
                     hash: certificate0.hash(),
-                }
+                },
+                sequence_number: 0,
             },
             Notification {
                 chain_id: ChainId::root(2),
                 reason: NewIncomingBundle {
                     origin: Origin::chain(ChainId::root(1)),
                     height: BlockHeight(0)
-                }
+                },
+                sequence_number: 0,
             },
             Notification {
                 chain_id: ChainId::root(1),
                 reason: NewBlock {
                     height: BlockHeight(1),
                     hash: certificate1.hash(),
-                }
+                },
+                sequence_number: 1,
             },
             Notification {
                 chain_id: ChainId::root(2),
                 reason: NewIncomingBundle {
                     origin: Origin::chain(ChainId::root(1)),
                     height: BlockHeight(1)
-                }
+                },
+                sequence_number: 1,
             }
         ]
     );
@@ -1757,7 +1906,8 @@ where
             reason: Reason::NewIncomingBundle {
                 origin: Origin::chain(ChainId::root(1)),
                 height: BlockHeight::ZERO,
-            }
+            },
+            sequence_number: 0,
         }]
     );
     let chain = worker.chain_state_view(ChainId::root(2)).await?;
@@ -2432,7 +2582,7 @@ where
             }])
         );
         assert_eq!(channel_inbox.removed_bundles.count(), 0);
-        assert_eq!(user_chain.execution_state.system.committees.get().len(), 1);
+        assert_eq!(user_chain.execution_state.system.committees().len(), 1);
     }
     // Make the child receive the pending messages.
     let certificate3 = make_certificate(
@@ -2536,7 +2686,7 @@ where
                 .len(),
             1
         );
-        assert_eq!(user_chain.execution_state.system.committees.get().len(), 2);
+        assert_eq!(user_chain.execution_state.system.committees().len(), 2);
         user_chain.validate_incoming_bundles().await?;
         {
             let inbox = user_chain
@@ -3382,6 +3532,80 @@ where
     Ok(())
 }
 
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
+#[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
+#[cfg_attr(feature = "scylladb", test_case(ScyllaDbStorageBuilder::default(); "scylla_db"))]
+#[test_log::test(tokio::test)]
+async fn test_approval_threshold<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let storage = storage_builder.build().await?;
+    let chain_id = ChainId::root(0);
+    let key_pairs = generate_key_pairs(3);
+    let owners = key_pairs
+        .iter()
+        .map(|key_pair| Owner::from(key_pair.public()))
+        .collect::<Vec<_>>();
+    let balances = vec![(
+        ChainDescription::Root(0),
+        key_pairs[0].public(),
+        Amount::from_tokens(2),
+    )];
+    let (committee, worker) = init_worker_with_chains(storage, balances).await;
+
+    // Require two of the three owners to approve every block.
+    let ownership = ChainOwnership::multiple(
+        key_pairs.iter().map(|key_pair| (key_pair.public(), 100)),
+        10,
+        TimeoutConfig::default(),
+    )
+    .with_approval_threshold(200);
+    let block0 =
+        make_first_block(chain_id).with_operation(SystemOperation::change_ownership(ownership));
+    let (executed_block0, _) = worker.stage_block_execution(block0).await?;
+    let value0 = HashedCertificateValue::new_confirmed(executed_block0);
+    let certificate0 = make_certificate(&committee, &worker, value0.clone());
+    let response = worker
+        .fully_handle_certificate(certificate0, vec![])
+        .await?;
+    assert_eq!(response.info.manager.current_round, Round::MultiLeader(0));
+
+    // A single owner cannot propose a block on their own.
+    let block1 = make_child_block(&value0);
+    let proposal = block1
+        .clone()
+        .into_proposal_with_round(&key_pairs[0], Round::MultiLeader(0));
+    let result = worker.handle_block_proposal(proposal).await;
+    assert_matches!(result, Err(WorkerError::InvalidOwner));
+
+    // Approvals must be signed by the approving owner.
+    let proposal = block1
+        .clone()
+        .into_proposal_with_round(&key_pairs[0], Round::MultiLeader(0))
+        .with_approvals([(owners[1], Signature::new(&block1, &key_pairs[2]))]);
+    let result = worker.handle_block_proposal(proposal).await;
+    assert_matches!(result, Err(WorkerError::InvalidOwner));
+
+    // The proposer's own approval doesn't count twice.
+    let proposal = block1
+        .clone()
+        .into_proposal_with_round(&key_pairs[0], Round::MultiLeader(0))
+        .with_approvals([(owners[0], Signature::new(&block1, &key_pairs[0]))]);
+    let result = worker.handle_block_proposal(proposal).await;
+    assert_matches!(result, Err(WorkerError::InvalidOwner));
+
+    // With another owner's approval the threshold is reached.
+    let proposal = block1
+        .clone()
+        .into_proposal_with_round(&key_pairs[0], Round::MultiLeader(0))
+        .with_approvals([(owners[2], Signature::new(&block1, &key_pairs[2]))]);
+    let (response, _) = worker.handle_block_proposal(proposal).await?;
+    assert!(response.info.manager.pending.is_some());
+    Ok(())
+}
+
 #[test_case(MemoryStorageBuilder::default(); "memory")]
 #[cfg_attr(feature = "rocksdb", test_case(RocksDbStorageBuilder::new().await; "rocks_db"))]
 #[cfg_attr(feature = "dynamodb", test_case(DynamoDbStorageBuilder::default(); "dynamo_db"))]
@@ -3809,3 +4033,69 @@ where
 
     Ok(())
 }
+
+#[test_case(MemoryStorageBuilder::default(); "memory")]
+#[test_log::test(tokio::test)]
+async fn test_drain_chain_worker<B>(mut storage_builder: B) -> anyhow::Result<()>
+where
+    B: StorageBuilder,
+{
+    let key_pair = KeyPair::generate();
+    let chain_id = ChainId::root(1);
+    let (_, worker) = init_worker_with_chain(
+        storage_builder.build().await?,
+        ChainDescription::Root(1),
+        key_pair.public(),
+        Amount::from_tokens(5),
+    )
+    .await;
+    let query = ChainInfoQuery::new(chain_id);
+    worker.handle_chain_info_query(query.clone()).await?;
+    assert!(worker.chain_workers.lock().unwrap().contains(&chain_id));
+
+    worker.drain_chain_worker(chain_id).await?;
+    assert!(!worker.chain_workers.lock().unwrap().contains(&chain_id));
+    // Draining a chain without a running worker does nothing.
+    worker.drain_chain_worker(chain_id).await?;
+
+    // The state of the chain is loaded again from the storage.
+    let (response, _) = worker.handle_chain_info_query(query).await?;
+    assert_eq!(response.info.chain_balance, Amount::from_tokens(5));
+    Ok(())
+}
+
+#[test]
+fn test_notification_sequence_numbers_keep_increasing() {
+    let mut sequence_numbers = NotificationSequenceNumbers::default();
+    let (chain1, chain2) = (ChainId::root(1), ChainId::root(2));
+    let now = Timestamp::from(0);
+    assert_eq!(sequence_numbers.assign(chain1, now), 0);
+    assert_eq!(sequence_numbers.assign(chain1, now), 1);
+    assert_eq!(sequence_numbers.assign(chain2, now), 0);
+
+    // Chains handed over to another worker are no longer tracked...
+    sequence_numbers.forget(&chain1);
+    sequence_numbers.forget(&chain1);
+    assert_eq!(sequence_numbers.next.len(), 1);
+    // ... and never reuse their numbers when they are numbered anew.
+    assert_eq!(sequence_numbers.assign(chain1, now), 2);
+    sequence_numbers.forget(&chain1);
+    assert_eq!(sequence_numbers.assign(chain1, Timestamp::from(10)), 10);
+}
+
+#[test]
+fn test_notification_sequence_numbers_are_bounded() {
+    let mut sequence_numbers = NotificationSequenceNumbers::new(NonZeroUsize::new(2).unwrap());
+    let chains = [ChainId::root(1), ChainId::root(2), ChainId::root(3)];
+    let now = Timestamp::from(0);
+    assert_eq!(sequence_numbers.assign(chains[0], now), 0);
+    assert_eq!(sequence_numbers.assign(chains[1], now), 0);
+    assert_eq!(sequence_numbers.assign(chains[0], now), 1);
+
+    // The least recently notified chain is forgotten...
+    assert_eq!(sequence_numbers.assign(chains[2], now), 0);
+    assert_eq!(sequence_numbers.next.len(), 2);
+    assert_eq!(sequence_numbers.assign(chains[0], now), 2);
+    // ... and does not reuse its numbers.
+    assert_eq!(sequence_numbers.assign(chains[1], now), 1);
+}
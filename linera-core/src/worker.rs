@@ -13,8 +13,10 @@ use std::{
 #[cfg(with_testing)]
 use linera_base::crypto::PublicKey;
 use linera_base::{
-    crypto::{CryptoHash, KeyPair},
-    data_types::{ArithmeticError, Blob, BlockHeight, Round, UserApplicationDescription},
+    crypto::{BlsKeyPair, CryptoHash, KeyPair},
+    data_types::{
+        ArithmeticError, Blob, BlockHeight, Round, Timestamp, UserApplicationDescription,
+    },
     doc_scalar,
     identifiers::{BlobId, ChainId, Owner, UserApplicationId},
     time::timer::{sleep, timeout},
@@ -26,8 +28,11 @@ use linera_chain::{
     },
     ChainStateView,
 };
-use linera_execution::{committee::Epoch, Query, Response};
-use linera_storage::Storage;
+use linera_execution::{
+    committee::{Epoch, ValidatorName},
+    Query, Response,
+};
+use linera_storage::{Clock as _, Storage};
 use lru::LruCache;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -44,6 +49,7 @@ use crate::{
     chain_worker::{ChainWorkerActor, ChainWorkerConfig, ChainWorkerRequest},
     data_types::{ChainInfoQuery, ChainInfoResponse, CrossChainRequest},
     join_set_ext::{JoinSet, JoinSetExt},
+    node::BatchItem,
     notifier::Notifier,
     value_cache::ValueCache,
 };
@@ -111,6 +117,11 @@ impl NetworkActions {
 pub struct Notification {
     pub chain_id: ChainId,
     pub reason: Reason,
+    /// The position of this notification among those of its chain, assigned by the
+    /// [`WorkerState`]. It increases by one with each notification, and jumps forward when
+    /// the worker or the chain's worker restarts. Other jumps mean that notifications were
+    /// dropped.
+    pub sequence_number: u64,
 }
 
 doc_scalar!(
@@ -135,6 +146,25 @@ pub enum Reason {
     },
 }
 
+/// The kind of a [`Reason`], without its details.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ReasonKind {
+    NewBlock,
+    NewIncomingBundle,
+    NewRound,
+}
+
+impl Reason {
+    /// Returns the kind of this reason.
+    pub fn kind(&self) -> ReasonKind {
+        match self {
+            Reason::NewBlock { .. } => ReasonKind::NewBlock,
+            Reason::NewIncomingBundle { .. } => ReasonKind::NewIncomingBundle,
+            Reason::NewRound { .. } => ReasonKind::NewRound,
+        }
+    }
+}
+
 /// Error type for worker operations..
 #[derive(Debug, Error)]
 pub enum WorkerError {
@@ -210,6 +240,8 @@ pub enum WorkerError {
     InvalidBlockProposal(String),
     #[error("The worker is too busy to handle new chains")]
     FullChainWorkerCache,
+    #[error("The worker of chain {0} still has requests in progress")]
+    ChainWorkerBusy(ChainId),
     #[error("Failed to join spawned worker task")]
     JoinError,
 }
@@ -242,6 +274,8 @@ where
     /// One-shot channels to notify callers when messages of a particular chain have been
     /// delivered.
     delivery_notifiers: Arc<Mutex<DeliveryNotifiers>>,
+    /// The sequence numbers of the next notifications of the recently active chains.
+    notification_sequence_numbers: Arc<Mutex<NotificationSequenceNumbers>>,
     /// The set of spawned [`ChainWorkerActor`] tasks.
     chain_worker_tasks: Arc<Mutex<JoinSet>>,
     /// The cache of running [`ChainWorkerActor`]s.
@@ -255,6 +289,60 @@ type ChainActorEndpoint<StorageClient> =
 pub(crate) type DeliveryNotifiers =
     HashMap<ChainId, BTreeMap<BlockHeight, Vec<oneshot::Sender<()>>>>;
 
+/// The maximal number of chains whose notification sequence numbers are tracked.
+const MAX_NUMBERED_CHAINS: usize = 100_000;
+
+/// The sequence numbers of the next notifications of the most recently notified chains.
+///
+/// This is independent of the cache of chain workers, so that the numbers of a chain keep
+/// following each other when its worker is evicted and started again.
+struct NotificationSequenceNumbers {
+    next: LruCache<ChainId, u64>,
+    /// No chain is numbered below this again: a chain that is forgotten and numbered anew
+    /// must not reuse the numbers it had.
+    floor: u64,
+}
+
+impl Default for NotificationSequenceNumbers {
+    fn default() -> Self {
+        Self::new(NonZeroUsize::new(MAX_NUMBERED_CHAINS).unwrap())
+    }
+}
+
+impl NotificationSequenceNumbers {
+    /// Creates a tracker for the sequence numbers of up to `capacity` chains.
+    fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            next: LruCache::new(capacity),
+            floor: 0,
+        }
+    }
+
+    /// Returns the sequence number of the next notification of `chain_id`. The first one
+    /// is the current time in microseconds, so that the numbers keep increasing when the
+    /// worker restarts.
+    fn assign(&mut self, chain_id: ChainId, now: Timestamp) -> u64 {
+        if let Some(next) = self.next.get_mut(&chain_id) {
+            let number = *next;
+            *next += 1;
+            return number;
+        }
+        let number = now.micros().max(self.floor);
+        if let Some((_, next)) = self.next.push(chain_id, number + 1) {
+            // The least recently notified chain is forgotten.
+            self.floor = self.floor.max(next);
+        }
+        number
+    }
+
+    /// Stops tracking the sequence numbers of a chain.
+    fn forget(&mut self, chain_id: &ChainId) {
+        if let Some(next) = self.next.pop(chain_id) {
+            self.floor = self.floor.max(next);
+        }
+    }
+}
+
 impl<StorageClient> WorkerState<StorageClient>
 where
     StorageClient: Storage,
@@ -274,6 +362,7 @@ where
             recent_blobs: Arc::new(ValueCache::default()),
             tracked_chains: None,
             delivery_notifiers: Arc::default(),
+            notification_sequence_numbers: Arc::default(),
             chain_worker_tasks: Arc::default(),
             chain_workers: Arc::new(Mutex::new(LruCache::new(chain_worker_limit))),
         }
@@ -294,11 +383,20 @@ where
             recent_blobs: Arc::new(ValueCache::default()),
             tracked_chains: Some(tracked_chains),
             delivery_notifiers: Arc::default(),
+            notification_sequence_numbers: Arc::default(),
             chain_worker_tasks: Arc::default(),
             chain_workers: Arc::new(Mutex::new(LruCache::new(chain_worker_limit))),
         }
     }
 
+    /// Configures the BLS key pair used to sign votes that can be aggregated into compact
+    /// certificates.
+    #[tracing::instrument(level = "trace", skip(self, bls_key_pair))]
+    pub fn with_bls_key_pair(mut self, bls_key_pair: Option<BlsKeyPair>) -> Self {
+        self.chain_worker_config.bls_key_pair = bls_key_pair.map(Arc::new);
+        self
+    }
+
     #[tracing::instrument(level = "trace", skip(self, value))]
     pub fn with_allow_inactive_chains(mut self, value: bool) -> Self {
         self.chain_worker_config.allow_inactive_chains = value;
@@ -338,6 +436,14 @@ where
         self
     }
 
+    /// Returns the name of this validator, or `None` if it has no key pair.
+    #[tracing::instrument(level = "trace", skip(self))]
+    pub fn validator_name(&self) -> Option<ValidatorName> {
+        self.chain_worker_config
+            .key_pair()
+            .map(|key_pair| ValidatorName(key_pair.public()))
+    }
+
     #[tracing::instrument(level = "trace", skip(self))]
     pub fn nickname(&self) -> &str {
         &self.nickname
@@ -715,6 +821,43 @@ where
         Ok(sender)
     }
 
+    /// Stops the [`ChainWorkerActor`] of a chain once it has no requests in progress, so that
+    /// another worker sharing the same storage can take the chain over.
+    ///
+    /// A new actor is started if more requests for the chain are received afterwards, so
+    /// the caller must stop sending them to this worker first.
+    #[instrument(level = "trace", skip(self))]
+    pub async fn drain_chain_worker(&self, chain_id: ChainId) -> Result<(), WorkerError> {
+        timeout(Duration::from_secs(10), async move {
+            loop {
+                {
+                    let mut chain_workers = self.chain_workers.lock().unwrap();
+                    match chain_workers.peek(&chain_id) {
+                        None => break,
+                        // Callers hold a copy of the endpoint until they get their response.
+                        Some(endpoint) if endpoint.strong_count() <= 1 => {
+                            chain_workers.pop(&chain_id);
+                            self.notification_sequence_numbers
+                                .lock()
+                                .unwrap()
+                                .forget(&chain_id);
+                            break;
+                        }
+                        Some(_) => {}
+                    }
+                }
+                sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .map_err(|_| WorkerError::ChainWorkerBusy(chain_id))?;
+        self.chain_worker_tasks
+            .lock()
+            .unwrap()
+            .reap_finished_tasks();
+        Ok(())
+    }
+
     /// Retrieves an endpoint to a [`ChainWorkerActor`] from the cache, attempting to create one
     /// and add it to the cache if needed.
     ///
@@ -775,7 +918,9 @@ where
         NUM_ROUNDS_IN_BLOCK_PROPOSAL
             .with_label_values(&[round.type_name()])
             .observe(round.number() as f64);
-        Ok(response)
+        let (info, mut actions) = response;
+        self.assign_sequence_numbers(&mut actions);
+        Ok((info, actions))
     }
 
     /// Processes a certificate, e.g. to extend a chain with a confirmed block.
@@ -813,7 +958,7 @@ where
             false,
         );
 
-        let (info, actions) = match certificate.value() {
+        let (info, mut actions) = match certificate.value() {
             CertificateValue::ValidatedBlock { .. } => {
                 // Confirm the validated block.
                 let validation_outcomes = self.process_validated_block(certificate, &blobs).await?;
@@ -858,9 +1003,40 @@ where
                     .inc_by(confirmed_transactions);
             }
         }
+        self.assign_sequence_numbers(&mut actions);
         Ok((info, actions))
     }
 
+    /// Numbers the notifications in `actions` in the order they will be emitted.
+    ///
+    /// The numbers of a chain jump forward if it was not notified for a long time, or was
+    /// handed over to another worker in the meantime, as when the worker restarts.
+    fn assign_sequence_numbers(&self, actions: &mut NetworkActions) {
+        if actions.notifications.is_empty() {
+            return;
+        }
+        let now = self.storage.clock().current_time();
+        let mut sequence_numbers = self.notification_sequence_numbers.lock().unwrap();
+        for notification in &mut actions.notifications {
+            notification.sequence_number = sequence_numbers.assign(notification.chain_id, now);
+        }
+    }
+
+    /// Processes a block proposal or a certificate from a batch.
+    pub async fn handle_batch_item(
+        &self,
+        item: BatchItem,
+        notify_when_messages_are_delivered: Option<oneshot::Sender<()>>,
+    ) -> Result<(ChainInfoResponse, NetworkActions), WorkerError> {
+        match item {
+            BatchItem::BlockProposal(proposal) => self.handle_block_proposal(*proposal).await,
+            BatchItem::Certificate { certificate, blobs } => {
+                self.handle_certificate(*certificate, blobs, notify_when_messages_are_delivered)
+                    .await
+            }
+        }
+    }
+
     #[instrument(skip_all, fields(
         nick = self.nickname,
         chain_id = format!("{:.8}", query.chain_id)
@@ -876,7 +1052,9 @@ where
             })
             .await;
         trace!("{} --> {:?}", self.nickname, result);
-        result
+        let (info, mut actions) = result?;
+        self.assign_sequence_numbers(&mut actions);
+        Ok((info, actions))
     }
 
     #[instrument(skip_all, fields(
@@ -915,6 +1093,7 @@ where
                     actions.notifications.push(Notification {
                         chain_id: recipient,
                         reason: Reason::NewIncomingBundle { origin, height },
+                        sequence_number: 0,
                     });
                 }
                 actions
@@ -924,6 +1103,7 @@ where
                         recipient,
                         latest_heights,
                     });
+                self.assign_sequence_numbers(&mut actions);
                 Ok(actions)
             }
             CrossChainRequest::ConfirmUpdatedRecipient {
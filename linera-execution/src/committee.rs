@@ -6,8 +6,9 @@ use std::{borrow::Cow, collections::BTreeMap, str::FromStr};
 
 use async_graphql::InputObject;
 use linera_base::{
-    crypto::{CryptoError, PublicKey},
-    data_types::ArithmeticError,
+    crypto::{BlsKeyPair, BlsPublicKey, BlsSignature, CryptoError, PublicKey},
+    data_types::{Amount, ArithmeticError},
+    identifiers::{Account, Owner},
 };
 use serde::{Deserialize, Serialize};
 
@@ -91,12 +92,108 @@ impl<'de> Deserialize<'de> for ValidatorName {
 }
 
 /// Public state of validator.
-#[derive(Eq, PartialEq, Hash, Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Eq, PartialEq, Hash, Clone, Debug, Default)]
 pub struct ValidatorState {
     /// The network address (in a string format understood by the networking layer).
     pub network_address: String,
     /// The voting power.
     pub votes: u64,
+    /// The BLS key used for aggregated certificates, if the validator registered one.
+    ///
+    /// This is not part of the binary serialization: see [`CommitteeExtensions`].
+    pub bls_key: Option<BlsValidatorKey>,
+    /// The account that receives the validator's share of the fees, if it designated one.
+    ///
+    /// This is not part of the binary serialization: see [`CommitteeExtensions`].
+    pub reward_account: Option<Account>,
+}
+
+impl Serialize for ValidatorState {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        if serializer.is_human_readable() {
+            ValidatorStateFull::from(self).serialize(serializer)
+        } else {
+            ValidatorStateMinimal::from(self).serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ValidatorState {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            let state = ValidatorStateFull::deserialize(deserializer)?;
+            Ok(ValidatorState {
+                network_address: state.network_address.into_owned(),
+                votes: state.votes,
+                bls_key: state.bls_key,
+                reward_account: state.reward_account,
+            })
+        } else {
+            let state = ValidatorStateMinimal::deserialize(deserializer)?;
+            Ok(ValidatorState {
+                network_address: state.network_address.into_owned(),
+                votes: state.votes,
+                bls_key: None,
+                reward_account: None,
+            })
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "ValidatorState")]
+struct ValidatorStateFull<'a> {
+    network_address: Cow<'a, str>,
+    votes: u64,
+    #[serde(default)]
+    bls_key: Option<BlsValidatorKey>,
+    #[serde(default)]
+    reward_account: Option<Account>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "ValidatorState")]
+struct ValidatorStateMinimal<'a> {
+    network_address: Cow<'a, str>,
+    votes: u64,
+}
+
+impl<'a> From<&'a ValidatorState> for ValidatorStateFull<'a> {
+    fn from(state: &'a ValidatorState) -> Self {
+        ValidatorStateFull {
+            network_address: Cow::Borrowed(&state.network_address),
+            votes: state.votes,
+            bls_key: state.bls_key,
+            reward_account: state.reward_account,
+        }
+    }
+}
+
+impl<'a> From<&'a ValidatorState> for ValidatorStateMinimal<'a> {
+    fn from(state: &'a ValidatorState) -> Self {
+        ValidatorStateMinimal {
+            network_address: Cow::Borrowed(&state.network_address),
+            votes: state.votes,
+        }
+    }
+}
+
+/// A validator's BLS public key, with a proof that the validator owns the secret key.
+///
+/// The proof of possession prevents rogue-key attacks on aggregate signatures, where a
+/// public key is chosen as a function of the other validators' keys.
+#[derive(Eq, PartialEq, Hash, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct BlsValidatorKey {
+    /// The BLS public key.
+    pub public_key: BlsPublicKey,
+    /// The public key, signed with the corresponding secret key.
+    pub proof_of_possession: BlsSignature,
 }
 
 /// A set of validators (identified by their public keys) and their voting rights.
@@ -112,6 +209,61 @@ pub struct Committee {
     validity_threshold: u64,
     /// The policy agreed on for this epoch.
     policy: ResourceControlPolicy,
+    /// Whether certificates in this epoch may aggregate the validators' BLS signatures.
+    ///
+    /// This is not part of the binary serialization: see [`CommitteeExtensions`].
+    #[graphql(skip)]
+    compact_certificates: bool,
+}
+
+/// The parts of a committee that its binary serialization leaves out.
+///
+/// The binary format of [`Committee`] predates these fields, and is part of the hashes of
+/// existing blocks, so it cannot change. The extensions are serialized next to the
+/// committee instead, e.g. in `SystemMessage::CreateCommitteeWithExtensions`.
+#[derive(Eq, PartialEq, Hash, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CommitteeExtensions {
+    /// Whether certificates in the committee's epoch may aggregate the validators' BLS
+    /// signatures.
+    pub compact_certificates: bool,
+    /// The BLS keys that the validators registered.
+    pub bls_keys: BTreeMap<ValidatorName, BlsValidatorKey>,
+    /// The accounts that receive the validators' shares of the fees.
+    pub reward_accounts: BTreeMap<ValidatorName, Account>,
+}
+
+/// Committees indexed by epoch, whose binary serialization includes the extensions of
+/// each committee.
+#[derive(Eq, PartialEq, Clone, Debug, Default)]
+pub struct ExtendedCommittees(pub BTreeMap<Epoch, Committee>);
+
+impl Serialize for ExtendedCommittees {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        serializer.collect_map(
+            self.0
+                .iter()
+                .map(|(epoch, committee)| (epoch, (committee, committee.extensions()))),
+        )
+    }
+}
+
+impl<'de> Deserialize<'de> for ExtendedCommittees {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        let committees =
+            BTreeMap::<Epoch, (Committee, CommitteeExtensions)>::deserialize(deserializer)?
+                .into_iter()
+                .map(|(epoch, (committee, extensions))| {
+                    (epoch, committee.with_extensions(extensions))
+                })
+                .collect();
+        Ok(ExtendedCommittees(committees))
+    }
 }
 
 impl Serialize for Committee {
@@ -150,6 +302,8 @@ struct CommitteeFull<'a> {
     quorum_threshold: u64,
     validity_threshold: u64,
     policy: Cow<'a, ResourceControlPolicy>,
+    #[serde(default)]
+    compact_certificates: bool,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -169,8 +323,10 @@ impl TryFrom<CommitteeFull<'static>> for Committee {
             quorum_threshold,
             validity_threshold,
             policy,
+            compact_certificates,
         } = committee_full;
-        let committee = Committee::new(validators.into_owned(), policy.into_owned());
+        let mut committee = Committee::new(validators.into_owned(), policy.into_owned());
+        committee.compact_certificates = compact_certificates;
         if total_votes != committee.total_votes {
             Err(format!(
                 "invalid committee: total_votes is {}; should be {}",
@@ -200,6 +356,7 @@ impl<'a> From<&'a Committee> for CommitteeFull<'a> {
             quorum_threshold,
             validity_threshold,
             policy,
+            compact_certificates,
         } = committee;
         CommitteeFull {
             validators: Cow::Borrowed(validators),
@@ -207,6 +364,7 @@ impl<'a> From<&'a Committee> for CommitteeFull<'a> {
             quorum_threshold: *quorum_threshold,
             validity_threshold: *validity_threshold,
             policy: Cow::Borrowed(policy),
+            compact_certificates: *compact_certificates,
         }
     }
 }
@@ -226,6 +384,7 @@ impl<'a> From<&'a Committee> for CommitteeMinimal<'a> {
             quorum_threshold: _,
             validity_threshold: _,
             policy,
+            compact_certificates: _,
         } = committee;
         CommitteeMinimal {
             validators: Cow::Borrowed(validators),
@@ -274,6 +433,22 @@ impl From<PublicKey> for ValidatorName {
     }
 }
 
+impl BlsValidatorKey {
+    /// Creates the public BLS key of a validator, with its proof of possession.
+    pub fn new(key_pair: &BlsKeyPair) -> Self {
+        BlsValidatorKey {
+            public_key: key_pair.public(),
+            proof_of_possession: key_pair.proof_of_possession(),
+        }
+    }
+
+    /// Verifies the proof of possession.
+    pub fn check(&self) -> Result<(), CryptoError> {
+        self.public_key
+            .check_proof_of_possession(&self.proof_of_possession)
+    }
+}
+
 impl Epoch {
     #[inline]
     pub fn try_add_one(self) -> Result<Self, ArithmeticError> {
@@ -307,6 +482,7 @@ impl Committee {
             quorum_threshold,
             validity_threshold,
             policy,
+            compact_certificates: false,
         }
     }
 
@@ -320,6 +496,8 @@ impl Committee {
                     ValidatorState {
                         network_address: k.to_string(),
                         votes: 1,
+                        bls_key: None,
+                        reward_account: None,
                     },
                 )
             })
@@ -340,6 +518,100 @@ impl Committee {
             .map(|(name, validator)| (name.0, validator.votes))
     }
 
+    /// Returns the BLS public key that the validator registered, if any.
+    pub fn bls_public_key(&self, author: &ValidatorName) -> Option<BlsPublicKey> {
+        self.validators
+            .get(author)
+            .and_then(|state| state.bls_key.as_ref())
+            .map(|bls_key| bls_key.public_key)
+    }
+
+    /// Returns whether certificates in this committee's epoch may aggregate the validators'
+    /// BLS signatures.
+    pub fn compact_certificates(&self) -> bool {
+        self.compact_certificates
+    }
+
+    /// Allows compact certificates in this committee's epoch if all validators registered
+    /// a BLS key.
+    ///
+    /// Committees from before BLS keys were introduced never allow them, so that the
+    /// first epoch with compact certificates is one whose committee was created after all
+    /// validators were upgraded.
+    pub fn with_compact_certificates(mut self) -> Self {
+        self.compact_certificates = !self.validators.is_empty()
+            && self
+                .validators
+                .values()
+                .all(|state| state.bls_key.is_some());
+        self
+    }
+
+    /// Returns the parts of the committee that its binary serialization leaves out.
+    pub fn extensions(&self) -> CommitteeExtensions {
+        CommitteeExtensions {
+            compact_certificates: self.compact_certificates,
+            bls_keys: self
+                .validators
+                .iter()
+                .filter_map(|(name, state)| Some((*name, state.bls_key?)))
+                .collect(),
+            reward_accounts: self
+                .validators
+                .iter()
+                .filter_map(|(name, state)| Some((*name, state.reward_account?)))
+                .collect(),
+        }
+    }
+
+    /// Returns the committee with the given extensions, replacing the current ones.
+    pub fn with_extensions(mut self, extensions: CommitteeExtensions) -> Self {
+        let CommitteeExtensions {
+            compact_certificates,
+            bls_keys,
+            reward_accounts,
+        } = extensions;
+        for (name, state) in &mut self.validators {
+            state.bls_key = bls_keys.get(name).copied();
+            state.reward_account = reward_accounts.get(name).copied();
+        }
+        self.compact_certificates = compact_certificates;
+        self
+    }
+
+    /// Returns the committee as it is after a round trip through its binary serialization.
+    pub fn without_extensions(&self) -> Self {
+        self.clone().with_extensions(CommitteeExtensions::default())
+    }
+
+    /// Verifies the proofs of possession of all registered BLS keys.
+    pub fn check_bls_keys(&self) -> Result<(), CryptoError> {
+        self.validators
+            .values()
+            .filter_map(|state| state.bls_key.as_ref())
+            .try_for_each(BlsValidatorKey::check)
+    }
+
+    /// Returns the validator whose key corresponds to the given owner, if any.
+    pub fn validator_by_owner(&self, owner: &Owner) -> Option<(&ValidatorName, &ValidatorState)> {
+        self.validators
+            .iter()
+            .find(|(name, _)| Owner::from(name.0) == *owner)
+    }
+
+    /// Returns the validator's share of the given amount, in proportion to its votes.
+    pub fn reward_share(&self, author: &ValidatorName, amount: Amount) -> Amount {
+        if self.total_votes == 0 {
+            return Amount::ZERO;
+        }
+        let attos = u128::from(amount);
+        let votes = u128::from(self.weight(author));
+        let total_votes = u128::from(self.total_votes);
+        // Avoid overflowing when multiplying large amounts by the votes.
+        let share = (attos / total_votes) * votes + (attos % total_votes) * votes / total_votes;
+        Amount::from_attos(share)
+    }
+
     pub fn network_address(&self, author: &ValidatorName) -> Option<&str> {
         self.validators
             .get(author)
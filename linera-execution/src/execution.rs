@@ -30,11 +30,12 @@ use {
 
 use super::{runtime::ServiceRuntimeRequest, ExecutionRequest};
 use crate::{
-    resources::ResourceController, system::SystemExecutionStateView, ContractSyncRuntime,
-    ExecutionError, ExecutionOutcome, ExecutionRuntimeConfig, ExecutionRuntimeContext, Message,
-    MessageContext, MessageKind, Operation, OperationContext, Query, QueryContext,
-    RawExecutionOutcome, RawOutgoingMessage, Response, ServiceSyncRuntime, SystemMessage,
-    TransactionTracker, UserApplicationDescription, UserApplicationId,
+    resources::{FeePayer, ResourceController},
+    system::SystemExecutionStateView,
+    ContractSyncRuntime, ExecutionError, ExecutionOutcome, ExecutionRuntimeConfig,
+    ExecutionRuntimeContext, Message, MessageContext, MessageKind, Operation, OperationContext,
+    Query, QueryContext, RawExecutionOutcome, RawOutgoingMessage, Response, ServiceSyncRuntime,
+    SystemMessage, TransactionTracker, UserApplicationDescription, UserApplicationId,
 };
 
 /// A view accessing the execution state of a chain.
@@ -101,7 +102,7 @@ where
         let mut resource_controller = ResourceController {
             policy: Arc::new(policy),
             tracker,
-            account: None,
+            account: FeePayer::default(),
         };
         let mut txn_tracker = TransactionTracker::new(next_message_index, None);
         self.run_user_action(
@@ -161,7 +162,7 @@ where
         refund_grant_to: Option<Account>,
         grant: Option<&mut Amount>,
         txn_tracker: &mut TransactionTracker,
-        resource_controller: &mut ResourceController<Option<Owner>>,
+        resource_controller: &mut ResourceController<FeePayer>,
     ) -> Result<(), ExecutionError> {
         let ExecutionRuntimeConfig {} = self.context().extra().execution_runtime_config();
         self.run_user_action_with_runtime(
@@ -188,7 +189,7 @@ where
         refund_grant_to: Option<Account>,
         grant: Option<&mut Amount>,
         txn_tracker: &mut TransactionTracker,
-        resource_controller: &mut ResourceController<Option<Owner>>,
+        resource_controller: &mut ResourceController<FeePayer>,
     ) -> Result<(), ExecutionError> {
         let mut cloned_grant = grant.as_ref().map(|x| **x);
         let initial_balance = resource_controller
@@ -275,6 +276,7 @@ where
                     authenticated: false,
                     grant: Amount::ZERO,
                     kind: MessageKind::Simple,
+                    expiry: None,
                     message: SystemMessage::RegisterApplications { applications },
                 })
             })
@@ -304,7 +306,7 @@ where
         local_time: Timestamp,
         operation: Operation,
         txn_tracker: &mut TransactionTracker,
-        resource_controller: &mut ResourceController<Option<Owner>>,
+        resource_controller: &mut ResourceController<FeePayer>,
     ) -> Result<(), ExecutionError> {
         assert_eq!(context.chain_id, self.context().extra().chain_id());
         match operation {
@@ -355,7 +357,7 @@ where
         message: Message,
         grant: Option<&mut Amount>,
         txn_tracker: &mut TransactionTracker,
-        resource_controller: &mut ResourceController<Option<Owner>>,
+        resource_controller: &mut ResourceController<FeePayer>,
     ) -> Result<(), ExecutionError> {
         assert_eq!(context.chain_id, self.context().extra().chain_id());
         match message {
@@ -406,6 +408,7 @@ where
                     authenticated: true,
                     grant,
                     kind: MessageKind::Bouncing,
+                    expiry: None,
                     message,
                 });
                 txn_tracker.add_system_outcome(outcome)?;
@@ -424,6 +427,7 @@ where
                     authenticated: true,
                     grant,
                     kind: MessageKind::Bouncing,
+                    expiry: None,
                     message: bytes,
                 });
                 txn_tracker.add_user_outcome(application_id, outcome)?;
@@ -446,6 +450,7 @@ where
             authenticated: false,
             grant: Amount::ZERO,
             kind: MessageKind::Tracked,
+            expiry: None,
             message: SystemMessage::Credit {
                 amount,
                 source: context.authenticated_signer,
@@ -261,7 +261,7 @@ where
                     ownership,
                     admin_id: self.system.admin_id.get().ok_or_else(inactive_err)?,
                     epoch: self.system.epoch.get().ok_or_else(inactive_err)?,
-                    committees: self.system.committees.get().clone(),
+                    committees: self.system.committees().clone(),
                     balance,
                     application_permissions,
                 };
@@ -4,16 +4,17 @@
 use std::collections::BTreeMap;
 
 use linera_base::{
+    crypto::CryptoHash,
     data_types::{Amount, Timestamp},
     doc_scalar,
-    identifiers::{ChainDescription, ChainId, Owner},
+    identifiers::{Account, ChainDescription, ChainId, Owner},
     ownership::ChainOwnership,
 };
 use linera_views::{context::Context, map_view::MapView};
 
 use crate::{
     committee::{Committee, Epoch, ValidatorName, ValidatorState},
-    system::{Recipient, UserData},
+    system::{HashLock, PendingRecovery, Recipient, Sponsorship, UserData},
     ChannelSubscription, ExecutionStateView, SystemExecutionStateView,
 };
 
@@ -21,7 +22,20 @@ doc_scalar!(
     Epoch,
     "A number identifying the configuration of the chain (aka the committee)"
 );
+doc_scalar!(
+    HashLock,
+    "Funds that are released to a recipient by revealing the preimage of a hash before a \
+    deadline"
+);
+doc_scalar!(
+    PendingRecovery,
+    "An ownership change initiated by the guardians of a chain"
+);
 doc_scalar!(Recipient, "The recipient of a transfer");
+doc_scalar!(
+    Sponsorship,
+    "A sponsor's pre-authorization to pay for the fees of the blocks of a chain"
+);
 doc_scalar!(UserData, "Optional user message attached to a transfer");
 doc_scalar!(ValidatorName, "The identity of a validator");
 
@@ -80,7 +94,7 @@ impl<C: Send + Sync + Context> SystemExecutionStateView<C> {
 
     #[graphql(derived(name = "committees"))]
     async fn _committees(&self) -> &BTreeMap<Epoch, Committee> {
-        self.committees.get()
+        self.committees()
     }
 
     #[graphql(derived(name = "ownership"))]
@@ -102,4 +116,24 @@ impl<C: Send + Sync + Context> SystemExecutionStateView<C> {
     async fn _timestamp(&self) -> &Timestamp {
         self.timestamp.get()
     }
+
+    #[graphql(derived(name = "policy_proposals"))]
+    async fn _policy_proposals(&self) -> &MapView<C, u64, PolicyProposal> {
+        &self.policy_proposals
+    }
+
+    #[graphql(derived(name = "sponsorships"))]
+    async fn _sponsorships(&self) -> &MapView<C, Account, Sponsorship> {
+        &self.sponsorships
+    }
+
+    #[graphql(derived(name = "pending_recovery"))]
+    async fn _pending_recovery(&self) -> &PendingRecovery {
+        self.pending_recovery.get()
+    }
+
+    #[graphql(derived(name = "hash_locks"))]
+    async fn _hash_locks(&self) -> &MapView<C, CryptoHash, HashLock> {
+        &self.hash_locks
+    }
 }
@@ -63,7 +63,7 @@ pub use crate::{
     execution::{ExecutionStateView, ServiceRuntimeEndpoint},
     execution_state_actor::ExecutionRequest,
     policy::ResourceControlPolicy,
-    resources::{ResourceController, ResourceTracker},
+    resources::{FeePayer, ResourceController, ResourceTracker},
     runtime::{
         ContractSyncRuntimeHandle, ServiceRuntimeRequest, ServiceSyncRuntime,
         ServiceSyncRuntimeHandle,
@@ -680,6 +680,8 @@ pub struct RawOutgoingMessage<Message, Grant = Resources> {
     pub grant: Grant,
     /// The kind of outgoing message being sent.
     pub kind: MessageKind,
+    /// The time from which the receiver must reject the message, if any.
+    pub expiry: Option<Timestamp>,
     /// The message itself.
     pub message: Message,
 }
@@ -691,6 +693,7 @@ impl<Message> From<SendMessageRequest<Message>> for RawOutgoingMessage<Message,
             authenticated,
             grant,
             is_tracked,
+            expiry,
             message,
         } = request;
 
@@ -705,6 +708,7 @@ impl<Message> From<SendMessageRequest<Message>> for RawOutgoingMessage<Message,
             authenticated,
             grant,
             kind,
+            expiry,
             message,
         }
     }
@@ -822,6 +826,7 @@ impl<Message> RawOutgoingMessage<Message, Resources> {
             authenticated,
             grant,
             kind,
+            expiry,
             message,
         } = self;
         Ok(RawOutgoingMessage {
@@ -829,6 +834,7 @@ impl<Message> RawOutgoingMessage<Message, Resources> {
             authenticated,
             grant: policy.total_price(&grant)?,
             kind,
+            expiry,
             message,
         })
     }
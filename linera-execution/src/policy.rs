@@ -5,12 +5,13 @@
 
 use std::fmt;
 
-use async_graphql::InputObject;
+use async_graphql::{InputObject, SimpleObject};
 use linera_base::data_types::{Amount, ArithmeticError, Resources};
 use serde::{Deserialize, Serialize};
 
 /// A collection of prices and limits associated with block execution.
-#[derive(Eq, PartialEq, Hash, Clone, Debug, Serialize, Deserialize, InputObject)]
+#[derive(Eq, PartialEq, Hash, Clone, Debug, Serialize, Deserialize, InputObject, SimpleObject)]
+#[graphql(input_name = "ResourceControlPolicyInput")]
 pub struct ResourceControlPolicy {
     /// The base price for creating a new block.
     pub block: Amount,
@@ -9,7 +9,7 @@ use custom_debug_derive::Debug;
 use linera_base::{
     data_types::{Amount, ArithmeticError},
     ensure,
-    identifiers::Owner,
+    identifiers::{Account, Owner},
 };
 use linera_views::{context::Context, views::ViewError};
 use serde::Serialize;
@@ -29,6 +29,15 @@ pub struct ResourceController<Account = Amount, Tracker = ResourceTracker> {
     pub account: Account,
 }
 
+/// The accounts paying for the resource usage of a block.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FeePayer {
+    /// The user signing for the block, whose account is used after the chain account.
+    pub owner: Option<Owner>,
+    /// The sponsor whose budget on this chain is used before the chain account, if any.
+    pub sponsor: Option<Account>,
+}
+
 /// The resources used so far by an execution process.
 #[derive(Copy, Debug, Clone, Default)]
 pub struct ResourceTracker {
@@ -58,6 +67,8 @@ pub struct ResourceTracker {
     pub message_bytes: u64,
     /// The amount allocated to message grants.
     pub grants: Amount,
+    /// The total amount charged in fees.
+    pub fees: Amount,
 }
 
 /// How to access the balance of an account.
@@ -98,8 +109,8 @@ where
     }
 
     /// Subtracts an amount from a balance and reports an error if that is impossible.
-    fn update_balance(&mut self, fees: Amount) -> Result<(), ExecutionError> {
-        self.account.try_sub_assign(fees).map_err(|_| {
+    fn debit(&mut self, amount: Amount) -> Result<(), ExecutionError> {
+        self.account.try_sub_assign(amount).map_err(|_| {
             SystemExecutionError::InsufficientFundingForFees {
                 balance: self.balance().unwrap_or(Amount::MAX),
             }
@@ -107,6 +118,14 @@ where
         Ok(())
     }
 
+    /// Charges fees to the balance and records them, so that they can be distributed to
+    /// the validators.
+    fn update_balance(&mut self, fees: Amount) -> Result<(), ExecutionError> {
+        self.debit(fees)?;
+        self.tracker.as_mut().fees.try_add_assign(fees)?;
+        Ok(())
+    }
+
     /// Obtains the amount of fuel that could be spent by consuming the entire balance.
     pub(crate) fn remaining_fuel(&self) -> u64 {
         self.policy
@@ -121,7 +140,7 @@ where
     /// Tracks the allocation of a grant.
     pub fn track_grant(&mut self, grant: Amount) -> Result<(), ExecutionError> {
         self.tracker.as_mut().grants.try_add_assign(grant)?;
-        self.update_balance(grant)
+        self.debit(grant)
     }
 
     /// Tracks the creation of a block.
@@ -380,7 +399,7 @@ impl BalanceHolder for Sources<'_> {
     }
 }
 
-impl ResourceController<Option<Owner>, ResourceTracker> {
+impl ResourceController<FeePayer, ResourceTracker> {
     /// Provides a reference to the current execution state and obtains a temporary object
     /// where the accounting functions of [`ResourceController`] are available.
     pub async fn with_state<'a, C>(
@@ -405,16 +424,21 @@ impl ResourceController<Option<Owner>, ResourceTracker> {
         C: Context + Clone + Send + Sync + 'static,
     {
         let mut sources = Vec::new();
-        // First, use the grant (e.g. for messages) and otherwise use the chain account
-        // (e.g. for blocks and operations).
+        // First, use the grant (e.g. for messages) and otherwise use the sponsor's budget,
+        // if any, and the chain account (e.g. for blocks and operations).
         if let Some(grant) = grant {
             sources.push(grant);
         } else {
+            if let Some(sponsor) = &self.account.sponsor {
+                if let Some(sponsorship) = view.system.sponsorships.get_mut(sponsor).await? {
+                    sources.push(&mut sponsorship.budget);
+                }
+            }
             sources.push(view.system.balance.get_mut());
         }
         // Then the local account, if any. Currently, any negative fee (e.g. storage
         // refund) goes preferably to this account.
-        if let Some(owner) = &self.account {
+        if let Some(owner) = &self.account.owner {
             if let Some(balance) = view.system.balances.get_mut(owner).await? {
                 sources.push(balance);
             }
@@ -0,0 +1,152 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Changes to the resource control policy, voted on by the validators.
+
+use std::collections::BTreeSet;
+
+use async_graphql::SimpleObject;
+use linera_base::{
+    data_types::Amount,
+    ensure,
+    identifiers::{ChainId, Owner},
+};
+use linera_views::context::Context;
+use serde::{Deserialize, Serialize};
+
+use super::{SystemExecutionError, SystemExecutionStateView, SystemMessage};
+use crate::{
+    committee::{Committee, Epoch, ValidatorName},
+    ExecutionRuntimeContext, RawOutgoingMessage, ResourceControlPolicy,
+};
+
+/// The maximum number of proposals that each validator can have open, i.e. not applied
+/// yet, in an epoch.
+pub const MAX_OPEN_POLICY_PROPOSALS: usize = 3;
+
+/// A proposed change to the resource control policy, voted on by the validators.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct PolicyProposal {
+    /// The proposed policy.
+    pub policy: ResourceControlPolicy,
+    /// The validator who made the proposal.
+    pub proposer: ValidatorName,
+    /// The epoch whose committee votes on the proposal.
+    pub epoch: Epoch,
+    /// The validators who voted for the proposal, including the proposer.
+    pub votes: BTreeSet<ValidatorName>,
+    /// The epoch in which the policy was applied, if the proposal was approved.
+    pub applied_in: Option<Epoch>,
+}
+
+impl<C> SystemExecutionStateView<C>
+where
+    C: Context + Clone + Send + Sync + 'static,
+    C::Extra: ExecutionRuntimeContext,
+{
+    /// Returns the current epoch and the name of the signer, as a validator of the current
+    /// committee. Policy proposals and votes are only accepted on the admin chain.
+    fn policy_voter(
+        &self,
+        chain_id: ChainId,
+        authenticated_signer: Option<Owner>,
+    ) -> Result<(Epoch, ValidatorName), SystemExecutionError> {
+        ensure!(
+            *self.admin_id.get() == Some(chain_id),
+            SystemExecutionError::AdminOperationOnNonAdminChain
+        );
+        let owner = authenticated_signer.ok_or(SystemExecutionError::UnauthenticatedPolicyVote)?;
+        let (epoch, committee) = self
+            .current_committee()
+            .ok_or(SystemExecutionError::InactiveChain)?;
+        let (name, _) = committee
+            .validator_by_owner(&owner)
+            .ok_or(SystemExecutionError::UnauthenticatedPolicyVote)?;
+        Ok((epoch, *name))
+    }
+
+    pub(super) async fn propose_policy(
+        &mut self,
+        chain_id: ChainId,
+        authenticated_signer: Option<Owner>,
+        policy: ResourceControlPolicy,
+    ) -> Result<Vec<RawOutgoingMessage<SystemMessage, Amount>>, SystemExecutionError> {
+        let (epoch, proposer) = self.policy_voter(chain_id, authenticated_signer)?;
+        let mut open_proposals = 0;
+        self.policy_proposals
+            .for_each_index_value(|_, proposal| {
+                if proposal.proposer == proposer
+                    && proposal.epoch == epoch
+                    && proposal.applied_in.is_none()
+                {
+                    open_proposals += 1;
+                }
+                Ok(())
+            })
+            .await?;
+        ensure!(
+            open_proposals < MAX_OPEN_POLICY_PROPOSALS,
+            SystemExecutionError::TooManyPolicyProposals(proposer)
+        );
+        let proposal_id = self.policy_proposals.count().await? as u64;
+        let proposal = PolicyProposal {
+            policy,
+            proposer,
+            epoch,
+            votes: BTreeSet::from([proposer]),
+            applied_in: None,
+        };
+        self.apply_policy_proposal_if_approved(proposal_id, proposal)
+    }
+
+    pub(super) async fn vote_policy(
+        &mut self,
+        chain_id: ChainId,
+        authenticated_signer: Option<Owner>,
+        proposal_id: u64,
+    ) -> Result<Vec<RawOutgoingMessage<SystemMessage, Amount>>, SystemExecutionError> {
+        let (epoch, voter) = self.policy_voter(chain_id, authenticated_signer)?;
+        let mut proposal = self
+            .policy_proposals
+            .get(&proposal_id)
+            .await?
+            .ok_or(SystemExecutionError::UnknownPolicyProposal(proposal_id))?;
+        ensure!(
+            proposal.applied_in.is_none(),
+            SystemExecutionError::PolicyProposalAlreadyApplied(proposal_id)
+        );
+        ensure!(
+            proposal.epoch == epoch,
+            SystemExecutionError::ExpiredPolicyProposal(proposal_id)
+        );
+        proposal.votes.insert(voter);
+        self.apply_policy_proposal_if_approved(proposal_id, proposal)
+    }
+
+    /// Stores the proposal. If the validators who voted for it form a quorum, creates a new
+    /// committee with the proposed policy and returns the message announcing it.
+    fn apply_policy_proposal_if_approved(
+        &mut self,
+        proposal_id: u64,
+        mut proposal: PolicyProposal,
+    ) -> Result<Vec<RawOutgoingMessage<SystemMessage, Amount>>, SystemExecutionError> {
+        let (epoch, committee) = self
+            .current_committee()
+            .ok_or(SystemExecutionError::InactiveChain)?;
+        let weight = proposal
+            .votes
+            .iter()
+            .map(|name| committee.weight(name))
+            .sum::<u64>();
+        let mut messages = Vec::new();
+        if weight >= committee.quorum_threshold() {
+            let new_committee =
+                Committee::new(committee.validators().clone(), proposal.policy.clone());
+            let new_epoch = epoch.try_add_one()?;
+            messages.push(self.create_committee(new_epoch, new_committee)?);
+            proposal.applied_in = Some(new_epoch);
+        }
+        self.policy_proposals.insert(&proposal_id, proposal)?;
+        Ok(messages)
+    }
+}
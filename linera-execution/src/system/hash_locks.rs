@@ -0,0 +1,153 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Funds locked until the preimage of a hash is revealed.
+
+use custom_debug_derive::Debug;
+use linera_base::{
+    crypto::{BcsHashable, CryptoHash},
+    data_types::{Amount, Timestamp},
+    ensure, hex_debug,
+    identifiers::{Account, Owner},
+};
+use linera_views::context::Context;
+use serde::{Deserialize, Serialize};
+
+use super::{SystemExecutionError, SystemExecutionStateView, SystemMessage};
+use crate::{Destination, ExecutionRuntimeContext, MessageKind, RawOutgoingMessage};
+
+/// Funds that are released to a recipient by revealing the preimage of a hash before a
+/// deadline, and that can be refunded after it.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct HashLock {
+    /// The account on this chain that locked the funds and is refunded after the deadline.
+    pub owner: Option<Owner>,
+    /// The account that receives the funds when the lock is redeemed.
+    pub recipient: Account,
+    /// The locked amount.
+    pub amount: Amount,
+    /// The time from which the lock can no longer be redeemed, only refunded.
+    pub deadline: Timestamp,
+}
+
+/// The secret that redeems a [`HashLock`].
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct Preimage(
+    #[serde(with = "serde_bytes")]
+    #[debug(with = "hex_debug")]
+    pub Vec<u8>,
+);
+
+impl BcsHashable for Preimage {}
+
+impl Preimage {
+    /// Returns the hash that identifies the locks redeemed by this preimage.
+    pub fn hash(&self) -> CryptoHash {
+        CryptoHash::new(self)
+    }
+}
+
+impl<C> SystemExecutionStateView<C>
+where
+    C: Context + Clone + Send + Sync + 'static,
+    C::Extra: ExecutionRuntimeContext,
+{
+    /// Debits the locked amount from the lock owner's account, or from the unattributed
+    /// account, and records the lock under the given hash.
+    pub(super) async fn lock_funds(
+        &mut self,
+        authenticated_signer: Option<Owner>,
+        hash: CryptoHash,
+        lock: HashLock,
+    ) -> Result<(), SystemExecutionError> {
+        if lock.owner.is_some() {
+            ensure!(
+                authenticated_signer == lock.owner,
+                SystemExecutionError::UnauthenticatedTransferOwner
+            );
+        }
+        ensure!(
+            lock.amount > Amount::ZERO,
+            SystemExecutionError::IncorrectTransferAmount
+        );
+        ensure!(
+            lock.deadline > *self.timestamp.get(),
+            SystemExecutionError::InvalidHashLockDeadline
+        );
+        ensure!(
+            !self.hash_locks.contains_key(&hash).await?,
+            SystemExecutionError::HashLockAlreadyExists(hash)
+        );
+        let balance = match &lock.owner {
+            Some(owner) => self.balances.get_mut_or_default(owner).await?,
+            None => self.balance.get_mut(),
+        };
+        balance
+            .try_sub_assign(lock.amount)
+            .map_err(|_| SystemExecutionError::InsufficientFunding { balance: *balance })?;
+        self.hash_locks.insert(&hash, lock)?;
+        Ok(())
+    }
+
+    /// Removes the lock redeemed by the preimage, and returns the message crediting the
+    /// locked amount to the recipient.
+    ///
+    /// The preimage is sent in a protected message, which cannot be rejected. So if there is
+    /// no such lock, or if its deadline had passed when the preimage was sent at `timestamp`,
+    /// this does nothing and the lock can still be refunded.
+    pub(super) async fn redeem_lock(
+        &mut self,
+        preimage: Preimage,
+        timestamp: Timestamp,
+    ) -> Result<Option<RawOutgoingMessage<SystemMessage, Amount>>, SystemExecutionError> {
+        let hash = preimage.hash();
+        let Some(lock) = self.hash_locks.get(&hash).await? else {
+            return Ok(None);
+        };
+        if timestamp >= lock.deadline {
+            return Ok(None);
+        }
+        self.hash_locks.remove(&hash)?;
+        Ok(Some(RawOutgoingMessage {
+            destination: Destination::Recipient(lock.recipient.chain_id),
+            authenticated: false,
+            grant: Amount::ZERO,
+            kind: MessageKind::Tracked,
+            expiry: None,
+            message: SystemMessage::Credit {
+                amount: lock.amount,
+                source: lock.owner,
+                target: lock.recipient.owner,
+            },
+        }))
+    }
+
+    /// Removes an expired lock and returns the locked amount to its owner.
+    ///
+    /// Validators don't vote for blocks that refund a lock while a redemption sent before the
+    /// deadline is still in the chain's inbox, so that redemption is processed first.
+    pub(super) async fn refund_lock(
+        &mut self,
+        authenticated_signer: Option<Owner>,
+        hash: CryptoHash,
+    ) -> Result<(), SystemExecutionError> {
+        let lock = self
+            .hash_locks
+            .get(&hash)
+            .await?
+            .ok_or(SystemExecutionError::UnknownHashLock(hash))?;
+        if lock.owner.is_some() {
+            ensure!(
+                authenticated_signer == lock.owner,
+                SystemExecutionError::UnauthenticatedTransferOwner
+            );
+        }
+        ensure!(
+            *self.timestamp.get() >= lock.deadline,
+            SystemExecutionError::HashLockNotExpired(lock.deadline)
+        );
+        self.hash_locks.remove(&hash)?;
+        self.credit(lock.owner, lock.amount).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,2958 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+mod governance;
+mod hash_locks;
+mod recovery;
+mod sponsorship;
+mod staking;
+
+#[cfg(with_metrics)]
+use std::sync::LazyLock;
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    iter, mem,
+};
+
+use async_graphql::Enum;
+use custom_debug_derive::Debug;
+use linera_base::{
+    crypto::{CryptoHash, PublicKey},
+    data_types::{
+        Amount, ApplicationPermissions, ArithmeticError, BlobContent, OracleResponse, Timestamp,
+    },
+    ensure, hex_debug,
+    identifiers::{
+        Account, BlobId, BlobType, BytecodeId, ChainDescription, ChainId, MessageId, Owner,
+    },
+    ownership::{ChainOwnership, OwnershipExtensions, TimeoutConfig},
+};
+use linera_views::{
+    context::Context,
+    map_view::HashedMapView,
+    register_view::HashedRegisterView,
+    set_view::HashedSetView,
+    views::{ClonableView, HashableView, View, ViewError},
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+#[cfg(with_metrics)]
+use {linera_base::prometheus_util, prometheus::IntCounterVec};
+
+pub use self::{
+    governance::{PolicyProposal, MAX_OPEN_POLICY_PROPOSALS},
+    hash_locks::{HashLock, Preimage},
+    recovery::PendingRecovery,
+    sponsorship::Sponsorship,
+    staking::{StakingConfig, Unbonding, ValidatorStake},
+};
+#[cfg(test)]
+use crate::test_utils::SystemExecutionState;
+use crate::{
+    committee::{
+        BlsValidatorKey, Committee, CommitteeExtensions, Epoch, ExtendedCommittees, ValidatorName,
+    },
+    ApplicationRegistryView, ChannelName, ChannelSubscription, Destination,
+    ExecutionRuntimeContext, MessageContext, MessageKind, OperationContext, QueryContext,
+    RawExecutionOutcome, RawOutgoingMessage, ResourceControlPolicy, TransactionTracker,
+    UserApplicationDescription, UserApplicationId,
+};
+
+/// The relative index of the `OpenChain` message created by the `OpenChain` operation.
+pub static OPEN_CHAIN_MESSAGE_INDEX: u32 = 0;
+/// The relative index of the `ApplicationCreated` message created by the `CreateApplication`
+/// operation.
+pub static CREATE_APPLICATION_MESSAGE_INDEX: u32 = 0;
+/// The amount of fees that chains other than the admin chain collect before paying them
+/// into the reward pool, so that most blocks don't send a message to the admin chain.
+pub const FEE_PAYMENT_THRESHOLD: Amount = Amount::ONE;
+
+/// The number of times the [`SystemOperation::OpenChain`] was executed.
+#[cfg(with_metrics)]
+static OPEN_CHAIN_COUNT: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "open_chain_count",
+        "The number of times the `OpenChain` operation was executed",
+        &[],
+    )
+    .expect("Counter creation should not fail")
+});
+
+/// A view accessing the execution state of the system of a chain.
+#[derive(Debug, ClonableView, HashableView)]
+pub struct SystemExecutionStateView<C> {
+    /// How the chain was created. May be unknown for inactive chains.
+    pub description: HashedRegisterView<C, Option<ChainDescription>>,
+    /// The number identifying the current configuration.
+    pub epoch: HashedRegisterView<C, Option<Epoch>>,
+    /// The admin of the chain.
+    pub admin_id: HashedRegisterView<C, Option<ChainId>>,
+    /// Track the channels that we have subscribed to.
+    pub subscriptions: HashedSetView<C, ChannelSubscription>,
+    /// The committees that we trust, indexed by epoch number, as stored before committees
+    /// had extensions. They are moved to `extended_committees` the first time the
+    /// committees change. Use `committees()` to read the current ones.
+    // Not using a `MapView` because the set active of committees is supposed to be
+    // small. Plus, currently, we would create the `BTreeMap` anyway in various places
+    // (e.g. the `OpenChain` operation).
+    pub legacy_committees: HashedRegisterView<C, BTreeMap<Epoch, Committee>>,
+    /// Ownership of the chain.
+    pub ownership: HashedRegisterView<C, ChainOwnership>,
+    /// Balance of the chain. (Available to any user able to create blocks in the chain.)
+    pub balance: HashedRegisterView<C, Amount>,
+    /// Balances attributed to a given owner.
+    pub balances: HashedMapView<C, Owner, Amount>,
+    /// The timestamp of the most recent block.
+    pub timestamp: HashedRegisterView<C, Timestamp>,
+    /// Track the locations of known bytecodes as well as the descriptions of known applications.
+    pub registry: ApplicationRegistryView<C>,
+    /// Whether this chain has been closed.
+    pub closed: HashedRegisterView<C, bool>,
+    /// Permissions for applications on this chain.
+    pub application_permissions: HashedRegisterView<C, ApplicationPermissions>,
+    /// Fees charged on this chain that were not paid into a reward pool yet.
+    pub collected_fees: HashedRegisterView<C, Amount>,
+    /// The fees to be distributed to the validators, indexed by epoch. Only used on the
+    /// admin chain.
+    pub reward_pools: HashedMapView<C, Epoch, RewardPool>,
+    /// The parameters of the stake-based committee election, if enabled. Only used on the
+    /// admin chain.
+    pub staking_config: HashedRegisterView<C, Option<StakingConfig>>,
+    /// The validators registered for the committee election, with their stakes. Only used
+    /// on the admin chain.
+    pub validator_stakes: HashedMapView<C, ValidatorName, ValidatorStake>,
+    /// The unbonded stakes that are waiting to be withdrawn, by delegator. Only used on the
+    /// admin chain.
+    pub unbondings: HashedMapView<C, Owner, Vec<Unbonding>>,
+    /// The proposed changes to the resource control policy, by proposal ID. Only used on
+    /// the admin chain.
+    pub policy_proposals: HashedMapView<C, u64, PolicyProposal>,
+    /// The budgets that sponsors on other chains have set aside for the fees of this chain.
+    pub sponsorships: HashedMapView<C, Account, Sponsorship>,
+    /// The ownership change that the guardians of this chain initiated, if any.
+    pub pending_recovery: HashedRegisterView<C, PendingRecovery>,
+    /// The funds locked on this chain until they are redeemed or refunded, indexed by the
+    /// hash of the preimage that redeems them.
+    pub hash_locks: HashedMapView<C, CryptoHash, HashLock>,
+    /// The committees that we trust, indexed by epoch number, with their extensions.
+    pub extended_committees: HashedRegisterView<C, ExtendedCommittees>,
+}
+
+/// The fees paid to the validators of an epoch.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct RewardPool {
+    /// The total amount of fees collected in the epoch.
+    pub total: Amount,
+    /// The amounts that the validators have claimed so far.
+    pub claimed: BTreeMap<ValidatorName, Amount>,
+}
+
+/// The configuration for a new chain.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct OpenChainConfig {
+    pub ownership: ChainOwnership,
+    pub admin_id: ChainId,
+    pub epoch: Epoch,
+    pub committees: BTreeMap<Epoch, Committee>,
+    pub balance: Amount,
+    pub application_permissions: ApplicationPermissions,
+}
+
+/// A system operation.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub enum SystemOperation {
+    /// Transfers `amount` units of value from the given owner's account to the recipient.
+    /// If no owner is given, try to take the units out of the unattributed account.
+    Transfer {
+        owner: Option<Owner>,
+        recipient: Recipient,
+        amount: Amount,
+    },
+    /// Claims `amount` units of value from the given owner's account in the remote
+    /// `target` chain. Depending on its configuration, the `target` chain may refuse to
+    /// process the message.
+    Claim {
+        owner: Owner,
+        target_id: ChainId,
+        recipient: Recipient,
+        amount: Amount,
+    },
+    /// Creates (or activates) a new chain.
+    /// This will automatically subscribe to the future committees created by `admin_id`.
+    OpenChain(OpenChainConfig),
+    /// Closes the chain.
+    CloseChain,
+    /// Changes the ownership of the chain.
+    ChangeOwnership {
+        /// Super owners can propose fast blocks in the first round, and regular blocks in any round.
+        super_owners: Vec<PublicKey>,
+        /// The regular owners, with their weights that determine how often they are round leader.
+        owners: Vec<(PublicKey, u64)>,
+        /// The number of initial rounds after 0 in which all owners are allowed to propose blocks.
+        multi_leader_rounds: u32,
+        /// The timeout configuration: how long fast, multi-leader and single-leader rounds last.
+        timeout_config: TimeoutConfig,
+    },
+    /// Changes the application permissions configuration on this chain.
+    ChangeApplicationPermissions(ApplicationPermissions),
+    /// Subscribes to a system channel.
+    Subscribe {
+        chain_id: ChainId,
+        channel: SystemChannel,
+    },
+    /// Unsubscribes from a system channel.
+    Unsubscribe {
+        chain_id: ChainId,
+        channel: SystemChannel,
+    },
+    /// Publishes a new application bytecode.
+    PublishBytecode { bytecode_id: BytecodeId },
+    /// Publishes a new data blob.
+    PublishDataBlob { blob_hash: CryptoHash },
+    /// Reads a blob and discards the result.
+    // TODO(#2490): Consider removing this.
+    ReadBlob { blob_id: BlobId },
+    /// Creates a new application.
+    CreateApplication {
+        bytecode_id: BytecodeId,
+        #[serde(with = "serde_bytes")]
+        #[debug(with = "hex_debug")]
+        parameters: Vec<u8>,
+        #[serde(with = "serde_bytes")]
+        #[debug(with = "hex_debug")]
+        instantiation_argument: Vec<u8>,
+        required_application_ids: Vec<UserApplicationId>,
+    },
+    /// Requests a message from another chain to register a user application on this chain.
+    RequestApplication {
+        chain_id: ChainId,
+        application_id: UserApplicationId,
+    },
+    /// Operations that are only allowed on the admin chain.
+    Admin(AdminOperation),
+    /// Claims the signer's share of the fees collected in the given epoch, as a validator.
+    /// The rewards are sent to the account designated in the validator's state.
+    ClaimValidatorRewards { epoch: Epoch },
+    /// Registers the signer as a validator candidate on the admin chain, bonding `stake`
+    /// units of value from the given owner's account (or the unattributed account).
+    RegisterValidator {
+        owner: Option<Owner>,
+        name: ValidatorName,
+        network_address: String,
+        bls_key: Option<BlsValidatorKey>,
+        reward_account: Option<Account>,
+        stake: Amount,
+    },
+    /// Bonds `amount` units of value from the given owner's account (or the unattributed
+    /// account) to a validator, on behalf of the signer.
+    Bond {
+        owner: Option<Owner>,
+        validator: ValidatorName,
+        amount: Amount,
+    },
+    /// Unbonds `amount` units of the signer's stake from a validator. The amount can be
+    /// withdrawn after the unbonding period.
+    Unbond {
+        validator: ValidatorName,
+        amount: Amount,
+    },
+    /// Withdraws the signer's unbonded stake whose unbonding period is over.
+    WithdrawUnbonded,
+    /// Proposes a new resource control policy, as a validator of the current epoch. Once
+    /// approved by a quorum of validators, the policy is applied in a new epoch.
+    ProposePolicy { policy: ResourceControlPolicy },
+    /// Votes for a policy proposal, as a validator of the current epoch.
+    VotePolicy { proposal_id: u64 },
+    /// Sets aside `budget` units of value from the given owner's account (or the
+    /// unattributed account) to pay for the fees of the blocks of the `beneficiary` chain
+    /// that name this account as their sponsor. If `applications` is set, only blocks whose
+    /// operations and incoming messages all belong to these applications are sponsored.
+    Sponsor {
+        owner: Option<Owner>,
+        beneficiary: ChainId,
+        budget: Amount,
+        applications: Option<Vec<UserApplicationId>>,
+    },
+    /// Revokes the sponsorship of the `beneficiary` chain by the given owner's account (or
+    /// the unattributed account). The remaining budget is returned to the account.
+    RevokeSponsorship {
+        owner: Option<Owner>,
+        beneficiary: ChainId,
+    },
+    /// Votes to transfer the chain to `new_owner`, as one of its guardians. Once enough
+    /// guardians agree, the recovery can be completed after the configured delay, unless an
+    /// owner cancels it.
+    InitiateRecovery { new_owner: PublicKey },
+    /// Cancels the pending recovery and discards the guardians' votes, as an owner.
+    CancelRecovery,
+    /// Makes the new owner approved by the guardians the only super owner of the chain,
+    /// once the recovery delay is over.
+    CompleteRecovery,
+    /// Locks `amount` units of value from the given owner's account (or the unattributed
+    /// account) until the preimage of `hash` is revealed before the `deadline`, which
+    /// releases them to the `recipient`. After the deadline, the funds can be refunded.
+    LockFunds {
+        owner: Option<Owner>,
+        recipient: Account,
+        amount: Amount,
+        hash: CryptoHash,
+        deadline: Timestamp,
+    },
+    /// Reveals a preimage to the `chain_id` chain, to redeem its lock with the matching
+    /// hash.
+    RedeemLock {
+        chain_id: ChainId,
+        preimage: Preimage,
+    },
+    /// Returns the funds of an expired lock to the account that locked them.
+    RefundLock { hash: CryptoHash },
+    /// Pays for the execution fees of the whole block with the budget that `sponsor` set
+    /// aside for this chain, before the chain's own balance. A block can use at most one
+    /// sponsor. The sponsor is an operation rather than a field of the block, so that the
+    /// format of blocks without one is unchanged.
+    UseSponsor { sponsor: Account },
+    /// Same as `ChangeOwnership`, for an ownership with extensions, e.g. an approval
+    /// threshold or guardians.
+    ChangeOwnershipWithExtensions {
+        super_owners: Vec<PublicKey>,
+        owners: Vec<(PublicKey, u64)>,
+        multi_leader_rounds: u32,
+        timeout_config: TimeoutConfig,
+        extensions: OwnershipExtensions,
+    },
+}
+
+/// Operations that are only allowed on the admin chain.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub enum AdminOperation {
+    /// Registers a new committee. This will notify the subscribers of the admin chain so that they
+    /// can migrate to the new epoch by accepting the resulting `CreateCommittee` as an incoming
+    /// message in a block.
+    CreateCommittee { epoch: Epoch, committee: Committee },
+    /// Removes a committee. Once the resulting `RemoveCommittee` message is accepted by a chain,
+    /// blocks from the retired epoch will not be accepted until they are followed (hence
+    /// re-certified) by a block certified by a recent committee.
+    RemoveCommittee { epoch: Epoch },
+    /// Enables the stake-based committee election, or changes its parameters.
+    ConfigureStaking(StakingConfig),
+    /// Registers a new committee whose voting rights are derived from the validators'
+    /// current stakes, with the same policy as the current committee.
+    ElectCommittee { epoch: Epoch },
+    /// Same as `CreateCommittee`, for a committee with extensions, which the binary
+    /// serialization of `Committee` leaves out.
+    CreateCommitteeWithExtensions {
+        epoch: Epoch,
+        committee: Committee,
+        extensions: CommitteeExtensions,
+    },
+}
+
+/// A system message meant to be executed on a remote chain.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub enum SystemMessage {
+    /// Credits `amount` units of value to the account `target` -- unless the message is
+    /// bouncing, in which case `source` is credited instead.
+    Credit {
+        target: Option<Owner>,
+        amount: Amount,
+        source: Option<Owner>,
+    },
+    /// Withdraws `amount` units of value from the account and starts a transfer to credit
+    /// the recipient. The message must be properly authenticated. Receiver chains may
+    /// refuse it depending on their configuration.
+    Withdraw {
+        owner: Owner,
+        amount: Amount,
+        recipient: Recipient,
+    },
+    /// Creates (or activates) a new chain.
+    OpenChain(OpenChainConfig),
+    /// Adds a new epoch and committee.
+    CreateCommittee { epoch: Epoch, committee: Committee },
+    /// Removes an old committee.
+    RemoveCommittee { epoch: Epoch },
+    /// Subscribes to a channel.
+    Subscribe {
+        id: ChainId,
+        subscription: ChannelSubscription,
+    },
+    /// Unsubscribes from a channel.
+    Unsubscribe {
+        id: ChainId,
+        subscription: ChannelSubscription,
+    },
+    /// Notifies that a new application was created.
+    ApplicationCreated,
+    /// Shares information about some applications to help the recipient use them.
+    /// Applications must be registered after their dependencies.
+    RegisterApplications {
+        applications: Vec<UserApplicationDescription>,
+    },
+    /// Requests a `RegisterApplication` message from the target chain to register the specified
+    /// application on the sender chain.
+    RequestApplication(UserApplicationId),
+    /// Adds the fees charged on the sender chain to the admin chain's reward pool.
+    CollectFees { epoch: Epoch, amount: Amount },
+    /// Pays a validator's share of the fees collected in an epoch. The message must be
+    /// authenticated by the validator's key.
+    ClaimValidatorRewards { epoch: Epoch },
+    /// Registers a validator candidate with its initial stake. The message must be
+    /// authenticated by the validator's key. If it bounces, `source` is refunded.
+    RegisterValidator {
+        name: ValidatorName,
+        network_address: String,
+        bls_key: Option<BlsValidatorKey>,
+        reward_account: Option<Account>,
+        amount: Amount,
+        source: Option<Owner>,
+    },
+    /// Bonds `amount` units of value to a validator on behalf of the message's signer. If
+    /// the message bounces, `source` is refunded.
+    Bond {
+        validator: ValidatorName,
+        amount: Amount,
+        source: Option<Owner>,
+    },
+    /// Unbonds some of the message signer's stake from a validator.
+    Unbond {
+        validator: ValidatorName,
+        amount: Amount,
+    },
+    /// Withdraws the message signer's unbonded stake whose unbonding period is over.
+    WithdrawUnbonded,
+    /// Registers a policy proposal. The message must be authenticated by a validator's key.
+    ProposePolicy { policy: ResourceControlPolicy },
+    /// Registers a vote for a policy proposal. The message must be authenticated by a
+    /// validator's key.
+    VotePolicy { proposal_id: u64 },
+    /// Adds `budget` to the sponsorship by the account `owner` on the sender chain, and
+    /// sets the sponsored applications. If the message bounces, `owner` is refunded.
+    Sponsor {
+        owner: Option<Owner>,
+        budget: Amount,
+        applications: Option<Vec<UserApplicationId>>,
+    },
+    /// Revokes the sponsorship by the account `owner` on the sender chain and returns the
+    /// remaining budget.
+    RevokeSponsorship { owner: Option<Owner> },
+    /// Releases the funds of the lock whose hash matches the preimage to its recipient,
+    /// unless the lock's deadline had passed at the `timestamp` when the preimage was sent.
+    RedeemLock {
+        preimage: Preimage,
+        timestamp: Timestamp,
+    },
+    /// Adds a new epoch and a committee with extensions, which the binary serialization of
+    /// `Committee` leaves out.
+    CreateCommitteeWithExtensions {
+        epoch: Epoch,
+        committee: Committee,
+        extensions: CommitteeExtensions,
+    },
+}
+
+impl SystemOperation {
+    /// Returns the operation changing the chain's ownership to the given one, in the format
+    /// that keeps its extensions, if it has any.
+    pub fn change_ownership(ownership: ChainOwnership) -> Self {
+        let extensions = ownership.extensions();
+        let super_owners = ownership.super_owners.into_values().collect();
+        let owners = ownership.owners.into_values().collect();
+        let multi_leader_rounds = ownership.multi_leader_rounds;
+        let timeout_config = ownership.timeout_config;
+        if extensions == OwnershipExtensions::default() {
+            SystemOperation::ChangeOwnership {
+                super_owners,
+                owners,
+                multi_leader_rounds,
+                timeout_config,
+            }
+        } else {
+            SystemOperation::ChangeOwnershipWithExtensions {
+                super_owners,
+                owners,
+                multi_leader_rounds,
+                timeout_config,
+                extensions,
+            }
+        }
+    }
+}
+
+impl AdminOperation {
+    /// Returns the operation registering the given committee, in the format that keeps its
+    /// extensions, if it has any.
+    pub fn create_committee(epoch: Epoch, committee: Committee) -> Self {
+        let extensions = committee.extensions();
+        if extensions == CommitteeExtensions::default() {
+            AdminOperation::CreateCommittee { epoch, committee }
+        } else {
+            AdminOperation::CreateCommitteeWithExtensions {
+                epoch,
+                committee: committee.without_extensions(),
+                extensions,
+            }
+        }
+    }
+}
+
+impl SystemMessage {
+    /// Returns the message announcing the given committee, in the format that keeps its
+    /// extensions, if it has any.
+    pub fn create_committee(epoch: Epoch, committee: Committee) -> Self {
+        let extensions = committee.extensions();
+        if extensions == CommitteeExtensions::default() {
+            SystemMessage::CreateCommittee { epoch, committee }
+        } else {
+            SystemMessage::CreateCommitteeWithExtensions {
+                epoch,
+                committee: committee.without_extensions(),
+                extensions,
+            }
+        }
+    }
+}
+
+/// A query to the system state.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct SystemQuery;
+
+/// The response to a system query.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct SystemResponse {
+    pub chain_id: ChainId,
+    pub balance: Amount,
+}
+
+/// The channels available in the system application.
+#[derive(
+    Enum, Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize, clap::ValueEnum,
+)]
+pub enum SystemChannel {
+    /// Channel used to broadcast reconfigurations.
+    Admin,
+}
+
+impl SystemChannel {
+    /// The [`ChannelName`] of this [`SystemChannel`].
+    pub fn name(&self) -> ChannelName {
+        bcs::to_bytes(self)
+            .expect("`SystemChannel` can be serialized")
+            .into()
+    }
+}
+
+impl Display for SystemChannel {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        let display_name = match self {
+            SystemChannel::Admin => "Admin",
+        };
+
+        write!(formatter, "{display_name}")
+    }
+}
+
+/// The recipient of a transfer.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
+pub enum Recipient {
+    /// This is mainly a placeholder for future extensions.
+    Burn,
+    /// Transfers to the balance of the given account.
+    Account(Account),
+}
+
+impl Recipient {
+    /// Returns the default recipient for the given chain (no owner).
+    pub fn chain(chain_id: ChainId) -> Recipient {
+        Recipient::Account(Account::chain(chain_id))
+    }
+
+    /// Returns the default recipient for the root chain with the given index.
+    #[cfg(with_testing)]
+    pub fn root(index: u32) -> Recipient {
+        Recipient::chain(ChainId::root(index))
+    }
+}
+
+/// Optional user message attached to a transfer.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Hash, Default, Debug, Serialize, Deserialize)]
+pub struct UserData(pub Option<[u8; 32]>);
+
+impl UserData {
+    pub fn from_option_string(opt_str: Option<String>) -> Result<Self, usize> {
+        // Convert the Option<String> to Option<[u8; 32]>
+        let option_array = match opt_str {
+            Some(s) => {
+                // Convert the String to a Vec<u8>
+                let vec = s.into_bytes();
+                if vec.len() <= 32 {
+                    // Create an array from the Vec<u8>
+                    let mut array = [b' '; 32];
+
+                    // Copy bytes from the vector into the array
+                    let len = vec.len().min(32);
+                    array[..len].copy_from_slice(&vec[..len]);
+
+                    Some(array)
+                } else {
+                    return Err(vec.len());
+                }
+            }
+            None => None,
+        };
+
+        // Return the UserData with the converted Option<[u8; 32]>
+        Ok(UserData(option_array))
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum SystemExecutionError {
+    #[error(transparent)]
+    ArithmeticError(#[from] ArithmeticError),
+    #[error(transparent)]
+    ViewError(#[from] ViewError),
+
+    #[error("Invalid admin ID in new chain: {0}")]
+    InvalidNewChainAdminId(ChainId),
+    #[error("Invalid committees")]
+    InvalidCommittees,
+    #[error("{epoch:?} is not recognized by chain {chain_id:}")]
+    InvalidEpoch { chain_id: ChainId, epoch: Epoch },
+    #[error("Transfer must have positive amount")]
+    IncorrectTransferAmount,
+    #[error("Transfer from owned account must be authenticated by the right signer")]
+    UnauthenticatedTransferOwner,
+    #[error("The transferred amount must not exceed the current chain balance: {balance}")]
+    InsufficientFunding { balance: Amount },
+    #[error("Required execution fees exceeded the total funding available: {balance}")]
+    InsufficientFundingForFees { balance: Amount },
+    #[error("Claim must have positive amount")]
+    IncorrectClaimAmount,
+    #[error("Claim must be authenticated by the right signer")]
+    UnauthenticatedClaimOwner,
+    #[error("Admin operations are only allowed on the admin chain.")]
+    AdminOperationOnNonAdminChain,
+    #[error("Failed to create new committee")]
+    InvalidCommitteeCreation,
+    #[error("Invalid BLS key in the new committee: {0}")]
+    InvalidCommitteeBlsKey(Box<linera_base::crypto::CryptoError>),
+    #[error("Failed to remove committee")]
+    InvalidCommitteeRemoval,
+    #[error(
+        "Chain {0} tried to subscribe to the admin channel ({1}) of a chain that is not the admin chain"
+    )]
+    InvalidAdminSubscription(ChainId, SystemChannel),
+    #[error("Cannot subscribe to a channel ({1}) on the same chain ({0})")]
+    SelfSubscription(ChainId, SystemChannel),
+    #[error("Chain {0} tried to subscribe to channel {1} but it is already subscribed")]
+    AlreadySubscribedToChannel(ChainId, SystemChannel),
+    #[error("Invalid unsubscription request to channel {1} on chain {0}")]
+    InvalidUnsubscription(ChainId, SystemChannel),
+    #[error("Amount overflow")]
+    AmountOverflow,
+    #[error("Amount underflow")]
+    AmountUnderflow,
+    #[error("Chain balance overflow")]
+    BalanceOverflow,
+    #[error("Chain balance underflow")]
+    BalanceUnderflow,
+    #[error("Cannot set epoch to a lower value")]
+    CannotRewindEpoch,
+    #[error("Cannot decrease the chain's timestamp")]
+    TicksOutOfOrder,
+    #[error("Application {0:?} is not registered by the chain")]
+    UnknownApplicationId(Box<UserApplicationId>),
+    #[error("Chain is not active yet.")]
+    InactiveChain,
+    #[error("Rewards must be claimed by a validator of the epoch")]
+    UnauthenticatedRewardClaim,
+    #[error("Validator {0} has not designated an account for its rewards")]
+    MissingRewardAccount(ValidatorName),
+    #[error("There are no rewards left to claim in {0:?}")]
+    NoRewardsToClaim(Epoch),
+    #[error("Staking is not enabled on the admin chain")]
+    StakingDisabled,
+    #[error("Invalid staking configuration: the stake per vote must be positive")]
+    InvalidStakingConfig,
+    #[error("Staking operations must be authenticated by the right signer")]
+    UnauthenticatedStakingOperation,
+    #[error("Validator {0} is already registered")]
+    ValidatorAlreadyRegistered(ValidatorName),
+    #[error("Validator {0} is not registered")]
+    UnregisteredValidator(ValidatorName),
+    #[error("The stake must be at least {minimum_stake}")]
+    InsufficientStake { minimum_stake: Amount },
+    #[error("The unbonded amount must not exceed the bonded stake: {bonded}")]
+    InsufficientBond { bonded: Amount },
+    #[error("There is no unbonded stake ready to be withdrawn")]
+    NoUnbondedStake,
+    #[error("No validator has enough stake to be elected")]
+    NoElectableValidators,
+    #[error(
+        "Policy proposals and votes must be authenticated by a validator of the current epoch"
+    )]
+    UnauthenticatedPolicyVote,
+    #[error("Policy proposal {0} does not exist")]
+    UnknownPolicyProposal(u64),
+    #[error("Policy proposal {0} was made in an earlier epoch")]
+    ExpiredPolicyProposal(u64),
+    #[error("Policy proposal {0} was already applied")]
+    PolicyProposalAlreadyApplied(u64),
+    #[error(
+        "Validator {0} already has {} open policy proposals",
+        MAX_OPEN_POLICY_PROPOSALS
+    )]
+    TooManyPolicyProposals(ValidatorName),
+    #[error(
+        "Invalid approval threshold: it must be at least 1, and without super owners at most \
+        the total weight of the owners"
+    )]
+    InvalidApprovalThreshold,
+    #[error(
+        "Invalid recovery configuration: the threshold must be between 1 and the number of \
+        guardians"
+    )]
+    InvalidRecoveryConfig,
+    #[error("Social recovery is not enabled on this chain")]
+    RecoveryDisabled,
+    #[error("Recoveries must be initiated by a guardian of the chain")]
+    UnauthorizedGuardian,
+    #[error("Recoveries can only be canceled by an owner of the chain")]
+    UnauthorizedRecoveryCancellation,
+    #[error("No recovery has been approved by enough guardians")]
+    NoApprovedRecovery,
+    #[error("The recovery cannot be completed before {0}")]
+    RecoveryDelayNotOver(Timestamp),
+    #[error("Funds are already locked with the hash {0}")]
+    HashLockAlreadyExists(CryptoHash),
+    #[error("The deadline of a hash lock must be in the future")]
+    InvalidHashLockDeadline,
+    #[error("No funds are locked with the hash {0}")]
+    UnknownHashLock(CryptoHash),
+    #[error("The hash lock cannot be refunded before {0}")]
+    HashLockNotExpired(Timestamp),
+
+    #[error("Blob not found on storage read: {0}")]
+    BlobNotFoundOnRead(BlobId),
+    #[error("Oracle response mismatch")]
+    OracleResponseMismatch,
+    #[error("No recorded response for oracle query")]
+    MissingOracleResponse,
+}
+
+impl<C> SystemExecutionStateView<C>
+where
+    C: Context + Clone + Send + Sync + 'static,
+    C::Extra: ExecutionRuntimeContext,
+{
+    /// Invariant for the states of active chains.
+    pub fn is_active(&self) -> bool {
+        self.description.get().is_some()
+            && self.ownership.get().is_active()
+            && self.current_committee().is_some()
+            && self.admin_id.get().is_some()
+    }
+
+    /// Returns the committees that we trust, indexed by epoch number.
+    pub fn committees(&self) -> &BTreeMap<Epoch, Committee> {
+        let legacy_committees = self.legacy_committees.get();
+        if legacy_committees.is_empty() {
+            &self.extended_committees.get().0
+        } else {
+            legacy_committees
+        }
+    }
+
+    /// Returns the committees that we trust, for modification. This moves them out of
+    /// `legacy_committees` if they are still stored there.
+    pub fn committees_mut(&mut self) -> &mut BTreeMap<Epoch, Committee> {
+        if !self.legacy_committees.get().is_empty() {
+            let committees = mem::take(self.legacy_committees.get_mut());
+            self.extended_committees.set(ExtendedCommittees(committees));
+        }
+        &mut self.extended_committees.get_mut().0
+    }
+
+    /// Returns the current committee, if any.
+    pub fn current_committee(&self) -> Option<(Epoch, &Committee)> {
+        let epoch = self.epoch.get().as_ref()?;
+        let committee = self.committees().get(epoch)?;
+        Some((*epoch, committee))
+    }
+
+    /// Returns whether certificates for the current epoch may aggregate the validators'
+    /// BLS signatures.
+    pub fn compact_certificates(&self) -> bool {
+        self.current_committee()
+            .is_some_and(|(_, committee)| committee.compact_certificates())
+    }
+
+    /// Executes the sender's side of an operation and returns a list of actions to be
+    /// taken.
+    pub async fn execute_operation(
+        &mut self,
+        context: OperationContext,
+        operation: SystemOperation,
+        txn_tracker: &mut TransactionTracker,
+    ) -> Result<Option<(UserApplicationId, Vec<u8>)>, SystemExecutionError> {
+        use SystemOperation::*;
+        let mut outcome = RawExecutionOutcome {
+            authenticated_signer: context.authenticated_signer,
+            refund_grant_to: context.refund_grant_to(),
+            ..RawExecutionOutcome::default()
+        };
+        let mut new_application = None;
+        match operation {
+            OpenChain(config) => {
+                let next_message_id = context.next_message_id(txn_tracker.next_message_index());
+                let messages = self.open_chain(config, next_message_id)?;
+                outcome.messages.extend(messages);
+                #[cfg(with_metrics)]
+                OPEN_CHAIN_COUNT.with_label_values(&[]).inc();
+            }
+            ChangeOwnership {
+                super_owners,
+                owners,
+                multi_leader_rounds,
+                timeout_config,
+            } => {
+                self.change_ownership(
+                    super_owners,
+                    owners,
+                    multi_leader_rounds,
+                    timeout_config,
+                    OwnershipExtensions::default(),
+                )?;
+            }
+            ChangeOwnershipWithExtensions {
+                super_owners,
+                owners,
+                multi_leader_rounds,
+                timeout_config,
+                extensions,
+            } => {
+                self.change_ownership(
+                    super_owners,
+                    owners,
+                    multi_leader_rounds,
+                    timeout_config,
+                    extensions,
+                )?;
+            }
+            ChangeApplicationPermissions(application_permissions) => {
+                self.application_permissions.set(application_permissions);
+            }
+            CloseChain => {
+                let messages = self.close_chain(context.chain_id).await?;
+                outcome.messages.extend(messages);
+            }
+            Transfer {
+                owner,
+                amount,
+                recipient,
+                ..
+            } => {
+                let message = self
+                    .transfer(context.authenticated_signer, owner, recipient, amount)
+                    .await?;
+
+                if let Some(message) = message {
+                    outcome.messages.push(message)
+                }
+            }
+            Claim {
+                owner,
+                target_id,
+                recipient,
+                amount,
+            } => {
+                let message = self
+                    .claim(
+                        context.authenticated_signer,
+                        owner,
+                        target_id,
+                        recipient,
+                        amount,
+                    )
+                    .await?;
+
+                outcome.messages.push(message)
+            }
+            Admin(admin_operation) => {
+                ensure!(
+                    *self.admin_id.get() == Some(context.chain_id),
+                    SystemExecutionError::AdminOperationOnNonAdminChain
+                );
+                match admin_operation {
+                    AdminOperation::CreateCommittee { epoch, committee } => {
+                        let message = self.create_committee(epoch, committee)?;
+                        outcome.messages.push(message);
+                    }
+                    AdminOperation::CreateCommitteeWithExtensions {
+                        epoch,
+                        committee,
+                        extensions,
+                    } => {
+                        let committee = committee.with_extensions(extensions);
+                        let message = self.create_committee(epoch, committee)?;
+                        outcome.messages.push(message);
+                    }
+                    AdminOperation::RemoveCommittee { epoch } => {
+                        ensure!(
+                            self.committees_mut().remove(&epoch).is_some(),
+                            SystemExecutionError::InvalidCommitteeRemoval
+                        );
+                        // Unclaimed rewards go to the validators of the current epoch.
+                        if let Some(pool) = self.reward_pools.get(&epoch).await? {
+                            self.reward_pools.remove(&epoch)?;
+                            let unclaimed = pool.total.saturating_sub(pool.claimed.values().sum());
+                            let current_epoch = self.epoch.get().expect("chain is active");
+                            self.add_to_reward_pool(current_epoch, unclaimed).await?;
+                        }
+                        let message = RawOutgoingMessage {
+                            destination: Destination::Subscribers(SystemChannel::Admin.name()),
+                            authenticated: false,
+                            grant: Amount::ZERO,
+                            kind: MessageKind::Protected,
+                            expiry: None,
+                            message: SystemMessage::RemoveCommittee { epoch },
+                        };
+                        outcome.messages.push(message);
+                    }
+                    AdminOperation::ConfigureStaking(config) => {
+                        ensure!(
+                            config.stake_per_vote > Amount::ZERO,
+                            SystemExecutionError::InvalidStakingConfig
+                        );
+                        self.staking_config.set(Some(config));
+                    }
+                    AdminOperation::ElectCommittee { epoch } => {
+                        let committee = self.elect_committee().await?;
+                        let message = self.create_committee(epoch, committee)?;
+                        outcome.messages.push(message);
+                    }
+                }
+            }
+            Subscribe { chain_id, channel } => {
+                ensure!(
+                    context.chain_id != chain_id,
+                    SystemExecutionError::SelfSubscription(context.chain_id, channel)
+                );
+                if channel == SystemChannel::Admin {
+                    ensure!(
+                        self.admin_id.get().as_ref() == Some(&chain_id),
+                        SystemExecutionError::InvalidAdminSubscription(context.chain_id, channel)
+                    );
+                }
+                let subscription = ChannelSubscription {
+                    chain_id,
+                    name: channel.name(),
+                };
+                ensure!(
+                    !self.subscriptions.contains(&subscription).await?,
+                    SystemExecutionError::AlreadySubscribedToChannel(context.chain_id, channel)
+                );
+                self.subscriptions.insert(&subscription)?;
+                let message = RawOutgoingMessage {
+                    destination: Destination::Recipient(chain_id),
+                    authenticated: false,
+                    grant: Amount::ZERO,
+                    kind: MessageKind::Protected,
+                    expiry: None,
+                    message: SystemMessage::Subscribe {
+                        id: context.chain_id,
+                        subscription,
+                    },
+                };
+                outcome.messages.push(message);
+            }
+            Unsubscribe { chain_id, channel } => {
+                let subscription = ChannelSubscription {
+                    chain_id,
+                    name: channel.name(),
+                };
+                ensure!(
+                    self.subscriptions.contains(&subscription).await?,
+                    SystemExecutionError::InvalidUnsubscription(context.chain_id, channel)
+                );
+                self.subscriptions.remove(&subscription)?;
+                let message = RawOutgoingMessage {
+                    destination: Destination::Recipient(chain_id),
+                    authenticated: false,
+                    grant: Amount::ZERO,
+                    kind: MessageKind::Protected,
+                    expiry: None,
+                    message: SystemMessage::Unsubscribe {
+                        id: context.chain_id,
+                        subscription,
+                    },
+                };
+                outcome.messages.push(message);
+            }
+            PublishBytecode { bytecode_id } => {
+                txn_tracker.replay_oracle_response(OracleResponse::Blob(BlobId::new(
+                    bytecode_id.contract_blob_hash,
+                    BlobType::ContractBytecode,
+                )))?;
+                txn_tracker.replay_oracle_response(OracleResponse::Blob(BlobId::new(
+                    bytecode_id.service_blob_hash,
+                    BlobType::ServiceBytecode,
+                )))?;
+            }
+            CreateApplication {
+                bytecode_id,
+                parameters,
+                instantiation_argument,
+                required_application_ids,
+            } => {
+                let id = UserApplicationId {
+                    bytecode_id,
+                    creation: context.next_message_id(txn_tracker.next_message_index()),
+                };
+                for application in required_application_ids.iter().chain(iter::once(&id)) {
+                    self.check_and_record_bytecode_blobs(&application.bytecode_id, txn_tracker)
+                        .await?;
+                }
+                self.registry
+                    .register_new_application(
+                        id,
+                        parameters.clone(),
+                        required_application_ids.clone(),
+                    )
+                    .await?;
+                // Send a message to ourself to increment the message ID.
+                let message = RawOutgoingMessage {
+                    destination: Destination::Recipient(context.chain_id),
+                    authenticated: false,
+                    grant: Amount::ZERO,
+                    kind: MessageKind::Protected,
+                    expiry: None,
+                    message: SystemMessage::ApplicationCreated,
+                };
+                outcome.messages.push(message);
+                new_application = Some((id, instantiation_argument.clone()));
+            }
+            RequestApplication {
+                chain_id,
+                application_id,
+            } => {
+                let message = RawOutgoingMessage {
+                    destination: Destination::Recipient(chain_id),
+                    authenticated: false,
+                    grant: Amount::ZERO,
+                    kind: MessageKind::Simple,
+                    expiry: None,
+                    message: SystemMessage::RequestApplication(application_id),
+                };
+                outcome.messages.push(message);
+            }
+            PublishDataBlob { blob_hash } => {
+                txn_tracker.replay_oracle_response(OracleResponse::Blob(BlobId::new(
+                    blob_hash,
+                    BlobType::Data,
+                )))?;
+            }
+            ReadBlob { blob_id } => {
+                txn_tracker.replay_oracle_response(OracleResponse::Blob(blob_id))?;
+                self.read_blob_content(blob_id).await?;
+            }
+            ClaimValidatorRewards { epoch } => {
+                ensure!(
+                    context.authenticated_signer.is_some(),
+                    SystemExecutionError::UnauthenticatedRewardClaim
+                );
+                let admin_id = self
+                    .admin_id
+                    .get()
+                    .ok_or(SystemExecutionError::InactiveChain)?;
+                let message = RawOutgoingMessage {
+                    destination: Destination::Recipient(admin_id),
+                    authenticated: true,
+                    grant: Amount::ZERO,
+                    kind: MessageKind::Simple,
+                    expiry: None,
+                    message: SystemMessage::ClaimValidatorRewards { epoch },
+                };
+                outcome.messages.push(message);
+            }
+            RegisterValidator {
+                owner,
+                name,
+                network_address,
+                bls_key,
+                reward_account,
+                stake,
+            } => {
+                ensure!(
+                    context.authenticated_signer == Some(Owner::from(name.0)),
+                    SystemExecutionError::UnauthenticatedStakingOperation
+                );
+                self.debit_stake(context.authenticated_signer, owner, stake)
+                    .await?;
+                let message = self.admin_chain_message(SystemMessage::RegisterValidator {
+                    name,
+                    network_address,
+                    bls_key,
+                    reward_account,
+                    amount: stake,
+                    source: owner,
+                })?;
+                outcome.messages.push(message);
+            }
+            Bond {
+                owner,
+                validator,
+                amount,
+            } => {
+                self.debit_stake(context.authenticated_signer, owner, amount)
+                    .await?;
+                let message = self.admin_chain_message(SystemMessage::Bond {
+                    validator,
+                    amount,
+                    source: owner,
+                })?;
+                outcome.messages.push(message);
+            }
+            Unbond { validator, amount } => {
+                ensure!(
+                    context.authenticated_signer.is_some(),
+                    SystemExecutionError::UnauthenticatedStakingOperation
+                );
+                ensure!(
+                    amount > Amount::ZERO,
+                    SystemExecutionError::IncorrectTransferAmount
+                );
+                let message =
+                    self.admin_chain_message(SystemMessage::Unbond { validator, amount })?;
+                outcome.messages.push(message);
+            }
+            WithdrawUnbonded => {
+                ensure!(
+                    context.authenticated_signer.is_some(),
+                    SystemExecutionError::UnauthenticatedStakingOperation
+                );
+                let message = self.admin_chain_message(SystemMessage::WithdrawUnbonded)?;
+                outcome.messages.push(message);
+            }
+            ProposePolicy { policy } => {
+                ensure!(
+                    context.authenticated_signer.is_some(),
+                    SystemExecutionError::UnauthenticatedPolicyVote
+                );
+                let message = self.admin_chain_message(SystemMessage::ProposePolicy { policy })?;
+                outcome.messages.push(message);
+            }
+            VotePolicy { proposal_id } => {
+                ensure!(
+                    context.authenticated_signer.is_some(),
+                    SystemExecutionError::UnauthenticatedPolicyVote
+                );
+                let message =
+                    self.admin_chain_message(SystemMessage::VotePolicy { proposal_id })?;
+                outcome.messages.push(message);
+            }
+            Sponsor {
+                owner,
+                beneficiary,
+                budget,
+                applications,
+            } => {
+                let message = self
+                    .sponsor(
+                        context.authenticated_signer,
+                        owner,
+                        beneficiary,
+                        budget,
+                        applications,
+                    )
+                    .await?;
+                outcome.messages.push(message);
+            }
+            RevokeSponsorship { owner, beneficiary } => {
+                if owner.is_some() {
+                    ensure!(
+                        context.authenticated_signer == owner,
+                        SystemExecutionError::UnauthenticatedTransferOwner
+                    );
+                }
+                // Protected, so that the beneficiary cannot reject it and keep the budget.
+                let message = RawOutgoingMessage {
+                    destination: Destination::Recipient(beneficiary),
+                    authenticated: false,
+                    grant: Amount::ZERO,
+                    kind: MessageKind::Protected,
+                    expiry: None,
+                    message: SystemMessage::RevokeSponsorship { owner },
+                };
+                outcome.messages.push(message);
+            }
+            InitiateRecovery { new_owner } => {
+                self.initiate_recovery(context.authenticated_signer, new_owner)?;
+            }
+            CancelRecovery => {
+                self.cancel_recovery(context.authenticated_signer)?;
+            }
+            CompleteRecovery => {
+                self.complete_recovery()?;
+            }
+            LockFunds {
+                owner,
+                recipient,
+                amount,
+                hash,
+                deadline,
+            } => {
+                let lock = HashLock {
+                    owner,
+                    recipient,
+                    amount,
+                    deadline,
+                };
+                self.lock_funds(context.authenticated_signer, hash, lock)
+                    .await?;
+            }
+            RedeemLock { chain_id, preimage } => {
+                // Protected, so that the lock's chain cannot reject it and refund the lock
+                // instead. The deadline is checked against the time the preimage was revealed.
+                let message = RawOutgoingMessage {
+                    destination: Destination::Recipient(chain_id),
+                    authenticated: false,
+                    grant: Amount::ZERO,
+                    kind: MessageKind::Protected,
+                    expiry: None,
+                    message: SystemMessage::RedeemLock {
+                        preimage,
+                        timestamp: *self.timestamp.get(),
+                    },
+                };
+                outcome.messages.push(message);
+            }
+            RefundLock { hash } => {
+                self.refund_lock(context.authenticated_signer, hash).await?;
+            }
+            // The sponsor is checked and charged by the chain when executing the block.
+            UseSponsor { .. } => {}
+        }
+
+        txn_tracker.add_system_outcome(outcome)?;
+        Ok(new_application)
+    }
+
+    pub async fn transfer(
+        &mut self,
+        authenticated_signer: Option<Owner>,
+        owner: Option<Owner>,
+        recipient: Recipient,
+        amount: Amount,
+    ) -> Result<Option<RawOutgoingMessage<SystemMessage, Amount>>, SystemExecutionError> {
+        if owner.is_some() {
+            ensure!(
+                authenticated_signer == owner,
+                SystemExecutionError::UnauthenticatedTransferOwner
+            );
+        }
+        ensure!(
+            amount > Amount::ZERO,
+            SystemExecutionError::IncorrectTransferAmount
+        );
+        let balance = match &owner {
+            Some(owner) => self.balances.get_mut_or_default(owner).await?,
+            None => self.balance.get_mut(),
+        };
+        balance
+            .try_sub_assign(amount)
+            .map_err(|_| SystemExecutionError::InsufficientFunding { balance: *balance })?;
+        match recipient {
+            Recipient::Account(account) => {
+                let message = RawOutgoingMessage {
+                    destination: Destination::Recipient(account.chain_id),
+                    authenticated: false,
+                    grant: Amount::ZERO,
+                    kind: MessageKind::Tracked,
+                    expiry: None,
+                    message: SystemMessage::Credit {
+                        amount,
+                        source: owner,
+                        target: account.owner,
+                    },
+                };
+
+                Ok(Some(message))
+            }
+            Recipient::Burn => Ok(None),
+        }
+    }
+
+    pub async fn claim(
+        &self,
+        authenticated_signer: Option<Owner>,
+        owner: Owner,
+        target_id: ChainId,
+        recipient: Recipient,
+        amount: Amount,
+    ) -> Result<RawOutgoingMessage<SystemMessage, Amount>, SystemExecutionError> {
+        ensure!(
+            authenticated_signer.as_ref() == Some(&owner),
+            SystemExecutionError::UnauthenticatedClaimOwner
+        );
+        ensure!(
+            amount > Amount::ZERO,
+            SystemExecutionError::IncorrectClaimAmount
+        );
+
+        Ok(RawOutgoingMessage {
+            destination: Destination::Recipient(target_id),
+            authenticated: true,
+            grant: Amount::ZERO,
+            kind: MessageKind::Simple,
+            expiry: None,
+            message: SystemMessage::Withdraw {
+                amount,
+                owner,
+                recipient,
+            },
+        })
+    }
+
+    /// Executes a cross-chain message that represents the recipient's side of an operation.
+    pub async fn execute_message(
+        &mut self,
+        context: MessageContext,
+        message: SystemMessage,
+        txn_tracker: &mut TransactionTracker,
+    ) -> Result<RawExecutionOutcome<SystemMessage, Amount>, SystemExecutionError> {
+        let mut outcome = RawExecutionOutcome::default();
+        use SystemMessage::*;
+        match message {
+            Credit {
+                amount,
+                source,
+                target,
+            } => {
+                let receiver = if context.is_bouncing { source } else { target };
+                self.credit(receiver, amount).await?;
+            }
+            Withdraw {
+                amount,
+                owner,
+                recipient,
+            } => {
+                ensure!(
+                    context.authenticated_signer == Some(owner),
+                    SystemExecutionError::UnauthenticatedClaimOwner
+                );
+
+                let balance = self.balances.get_mut_or_default(&owner).await?;
+                balance
+                    .try_sub_assign(amount)
+                    .map_err(|_| SystemExecutionError::InsufficientFunding { balance: *balance })?;
+                match recipient {
+                    Recipient::Account(account) => {
+                        let message = RawOutgoingMessage {
+                            destination: Destination::Recipient(account.chain_id),
+                            authenticated: false,
+                            grant: Amount::ZERO,
+                            kind: MessageKind::Tracked,
+                            expiry: None,
+                            message: SystemMessage::Credit {
+                                amount,
+                                source: Some(owner),
+                                target: account.owner,
+                            },
+                        };
+                        outcome.messages.push(message);
+                    }
+                    Recipient::Burn => (),
+                }
+            }
+            CreateCommittee { epoch, committee } => {
+                let payment = self
+                    .add_committee(context.chain_id, epoch, committee)
+                    .await?;
+                outcome.messages.extend(payment);
+            }
+            CreateCommitteeWithExtensions {
+                epoch,
+                committee,
+                extensions,
+            } => {
+                let committee = committee.with_extensions(extensions);
+                let payment = self
+                    .add_committee(context.chain_id, epoch, committee)
+                    .await?;
+                outcome.messages.extend(payment);
+            }
+            RemoveCommittee { epoch } => {
+                ensure!(
+                    self.committees_mut().remove(&epoch).is_some(),
+                    SystemExecutionError::InvalidCommitteeRemoval
+                );
+            }
+            CollectFees { epoch, amount } => {
+                // Fees from an epoch whose committee was already removed go to the current one.
+                let epoch = if self.committees().contains_key(&epoch) {
+                    epoch
+                } else {
+                    self.epoch
+                        .get()
+                        .ok_or(SystemExecutionError::InactiveChain)?
+                };
+                self.add_to_reward_pool(epoch, amount).await?;
+            }
+            ClaimValidatorRewards { epoch } => {
+                let message = self
+                    .claim_validator_rewards(context.authenticated_signer, epoch)
+                    .await?;
+                outcome.messages.push(message);
+            }
+            RegisterValidator {
+                name,
+                network_address,
+                bls_key,
+                reward_account,
+                amount,
+                source,
+            } => {
+                if context.is_bouncing {
+                    self.credit(source, amount).await?;
+                } else {
+                    ensure!(
+                        context.authenticated_signer == Some(Owner::from(name.0)),
+                        SystemExecutionError::UnauthenticatedStakingOperation
+                    );
+                    let stake = ValidatorStake {
+                        network_address,
+                        bls_key,
+                        reward_account,
+                        delegations: BTreeMap::from([(Owner::from(name.0), amount)]),
+                    };
+                    self.register_validator(name, stake).await?;
+                }
+            }
+            Bond {
+                validator,
+                amount,
+                source,
+            } => {
+                if context.is_bouncing {
+                    self.credit(source, amount).await?;
+                } else {
+                    self.bond(context.authenticated_signer, validator, amount)
+                        .await?;
+                }
+            }
+            Unbond { validator, amount } => {
+                self.unbond(
+                    context.authenticated_signer,
+                    context.message_id.chain_id,
+                    validator,
+                    amount,
+                )
+                .await?;
+            }
+            WithdrawUnbonded => {
+                let messages = self.withdraw_unbonded(context.authenticated_signer).await?;
+                outcome.messages.extend(messages);
+            }
+            ProposePolicy { policy } => {
+                let messages = self
+                    .propose_policy(context.chain_id, context.authenticated_signer, policy)
+                    .await?;
+                outcome.messages.extend(messages);
+            }
+            VotePolicy { proposal_id } => {
+                let messages = self
+                    .vote_policy(context.chain_id, context.authenticated_signer, proposal_id)
+                    .await?;
+                outcome.messages.extend(messages);
+            }
+            Sponsor {
+                owner,
+                budget,
+                applications,
+            } => {
+                if context.is_bouncing {
+                    self.credit(owner, budget).await?;
+                } else {
+                    let sponsor = Account {
+                        chain_id: context.message_id.chain_id,
+                        owner,
+                    };
+                    self.add_sponsorship(sponsor, budget, applications).await?;
+                }
+            }
+            RevokeSponsorship { owner } => {
+                let sponsor = Account {
+                    chain_id: context.message_id.chain_id,
+                    owner,
+                };
+                let messages = self.revoke_sponsorship(sponsor).await?;
+                outcome.messages.extend(messages);
+            }
+            RedeemLock {
+                preimage,
+                timestamp,
+            } => {
+                let message = self.redeem_lock(preimage, timestamp).await?;
+                outcome.messages.extend(message);
+            }
+            RegisterApplications { applications } => {
+                for application in applications {
+                    self.check_and_record_bytecode_blobs(&application.bytecode_id, txn_tracker)
+                        .await?;
+                    self.registry
+                        .register_application(application.clone())
+                        .await?;
+                }
+            }
+            RequestApplication(application_id) => {
+                let applications = self
+                    .registry
+                    .describe_applications_with_dependencies(
+                        vec![application_id],
+                        &Default::default(),
+                    )
+                    .await?;
+                let message = RawOutgoingMessage {
+                    destination: Destination::Recipient(context.message_id.chain_id),
+                    authenticated: false,
+                    grant: Amount::ZERO,
+                    kind: MessageKind::Simple,
+                    expiry: None,
+                    message: SystemMessage::RegisterApplications { applications },
+                };
+                outcome.messages.push(message);
+            }
+            // These messages are executed immediately when cross-chain requests are received.
+            Subscribe { .. } | Unsubscribe { .. } | OpenChain(_) => {}
+            // This message is only a placeholder: Its ID is part of the application ID.
+            ApplicationCreated => {}
+        }
+        Ok(outcome)
+    }
+
+    /// Pays the fees collected on this chain in the given epoch into its reward pool. On the
+    /// admin chain, this updates the pool directly. Other chains return the message to send
+    /// to the admin chain, once the fees reach [`FEE_PAYMENT_THRESHOLD`] or the chain moved
+    /// to another epoch.
+    pub async fn pay_collected_fees(
+        &mut self,
+        chain_id: ChainId,
+        epoch: Epoch,
+    ) -> Result<Option<RawOutgoingMessage<SystemMessage, Amount>>, SystemExecutionError> {
+        let amount = *self.collected_fees.get();
+        if amount == Amount::ZERO {
+            return Ok(None);
+        }
+        let admin_id = self
+            .admin_id
+            .get()
+            .ok_or(SystemExecutionError::InactiveChain)?;
+        if admin_id == chain_id {
+            self.collected_fees.set(Amount::ZERO);
+            self.add_to_reward_pool(epoch, amount).await?;
+            return Ok(None);
+        }
+        if amount < FEE_PAYMENT_THRESHOLD && *self.epoch.get() == Some(epoch) {
+            return Ok(None);
+        }
+        self.collected_fees.set(Amount::ZERO);
+        Ok(Some(RawOutgoingMessage {
+            destination: Destination::Recipient(admin_id),
+            authenticated: false,
+            grant: Amount::ZERO,
+            kind: MessageKind::Protected,
+            expiry: None,
+            message: SystemMessage::CollectFees { epoch, amount },
+        }))
+    }
+
+    async fn add_to_reward_pool(
+        &mut self,
+        epoch: Epoch,
+        amount: Amount,
+    ) -> Result<(), SystemExecutionError> {
+        let pool = self.reward_pools.get_mut_or_default(&epoch).await?;
+        pool.total.try_add_assign(amount)?;
+        Ok(())
+    }
+
+    /// Marks the signer's share of the epoch's reward pool as claimed and returns the
+    /// message crediting it to the validator's reward account.
+    async fn claim_validator_rewards(
+        &mut self,
+        authenticated_signer: Option<Owner>,
+        epoch: Epoch,
+    ) -> Result<RawOutgoingMessage<SystemMessage, Amount>, SystemExecutionError> {
+        let owner = authenticated_signer.ok_or(SystemExecutionError::UnauthenticatedRewardClaim)?;
+        let committee = self
+            .committees()
+            .get(&epoch)
+            .ok_or(SystemExecutionError::NoRewardsToClaim(epoch))?;
+        let (name, state) = committee
+            .validator_by_owner(&owner)
+            .ok_or(SystemExecutionError::UnauthenticatedRewardClaim)?;
+        let account = state
+            .reward_account
+            .ok_or(SystemExecutionError::MissingRewardAccount(*name))?;
+        let pool = self
+            .reward_pools
+            .get_mut(&epoch)
+            .await?
+            .ok_or(SystemExecutionError::NoRewardsToClaim(epoch))?;
+        let share = committee.reward_share(name, pool.total);
+        let claimed = pool.claimed.entry(*name).or_default();
+        let amount = share.saturating_sub(*claimed);
+        ensure!(
+            amount > Amount::ZERO,
+            SystemExecutionError::NoRewardsToClaim(epoch)
+        );
+        *claimed = share;
+        Ok(RawOutgoingMessage {
+            destination: Destination::Recipient(account.chain_id),
+            authenticated: false,
+            grant: Amount::ZERO,
+            kind: MessageKind::Tracked,
+            expiry: None,
+            message: SystemMessage::Credit {
+                amount,
+                source: None,
+                target: account.owner,
+            },
+        })
+    }
+
+    /// Credits `amount` units of value to the given owner's account, or to the unattributed
+    /// account.
+    async fn credit(&mut self, owner: Option<Owner>, amount: Amount) -> Result<(), ViewError> {
+        match owner {
+            None => {
+                let new_balance = self.balance.get().saturating_add(amount);
+                self.balance.set(new_balance);
+            }
+            Some(owner) => {
+                let balance = self.balances.get_mut_or_default(&owner).await?;
+                *balance = balance.saturating_add(amount);
+            }
+        }
+        Ok(())
+    }
+
+    /// Registers a new committee and returns the message announcing it to the subscribers
+    /// of the admin chain.
+    fn create_committee(
+        &mut self,
+        epoch: Epoch,
+        committee: Committee,
+    ) -> Result<RawOutgoingMessage<SystemMessage, Amount>, SystemExecutionError> {
+        ensure!(
+            epoch == self.epoch.get().expect("chain is active").try_add_one()?,
+            SystemExecutionError::InvalidCommitteeCreation
+        );
+        committee
+            .check_bls_keys()
+            .map_err(|error| SystemExecutionError::InvalidCommitteeBlsKey(Box::new(error)))?;
+        let committee = committee.with_compact_certificates();
+        self.committees_mut().insert(epoch, committee.clone());
+        self.epoch.set(Some(epoch));
+        Ok(RawOutgoingMessage {
+            destination: Destination::Subscribers(SystemChannel::Admin.name()),
+            authenticated: false,
+            grant: Amount::ZERO,
+            kind: MessageKind::Protected,
+            expiry: None,
+            message: SystemMessage::create_committee(epoch, committee),
+        })
+    }
+
+    /// Adds the committee announced by the admin chain, and migrates to its epoch. Returns
+    /// the message paying the fees collected so far into the reward pool of the previous
+    /// epoch, if any.
+    async fn add_committee(
+        &mut self,
+        chain_id: ChainId,
+        epoch: Epoch,
+        committee: Committee,
+    ) -> Result<Option<RawOutgoingMessage<SystemMessage, Amount>>, SystemExecutionError> {
+        let previous_epoch = self.epoch.get().expect("chain is active");
+        ensure!(
+            epoch == previous_epoch.try_add_one()?,
+            SystemExecutionError::InvalidCommitteeCreation
+        );
+        self.committees_mut().insert(epoch, committee);
+        self.epoch.set(Some(epoch));
+        self.pay_collected_fees(chain_id, previous_epoch).await
+    }
+
+    /// Returns an authenticated message to the admin chain. Messages that carry stake are
+    /// tracked, so that they are refunded if they bounce.
+    fn admin_chain_message(
+        &self,
+        message: SystemMessage,
+    ) -> Result<RawOutgoingMessage<SystemMessage, Amount>, SystemExecutionError> {
+        let admin_id = self
+            .admin_id
+            .get()
+            .ok_or(SystemExecutionError::InactiveChain)?;
+        let kind = match message {
+            SystemMessage::RegisterValidator { .. } | SystemMessage::Bond { .. } => {
+                MessageKind::Tracked
+            }
+            _ => MessageKind::Simple,
+        };
+        Ok(RawOutgoingMessage {
+            destination: Destination::Recipient(admin_id),
+            authenticated: true,
+            grant: Amount::ZERO,
+            kind,
+            expiry: None,
+            message,
+        })
+    }
+
+    /// Initializes the system application state on a newly opened chain.
+    pub fn initialize_chain(
+        &mut self,
+        message_id: MessageId,
+        timestamp: Timestamp,
+        config: OpenChainConfig,
+    ) {
+        // Guaranteed under BFT assumptions.
+        assert!(self.description.get().is_none());
+        assert!(!self.ownership.get().is_active());
+        assert!(self.committees().is_empty());
+        let OpenChainConfig {
+            ownership,
+            admin_id,
+            epoch,
+            committees,
+            balance,
+            application_permissions,
+        } = config;
+        let description = ChainDescription::Child(message_id);
+        self.description.set(Some(description));
+        self.epoch.set(Some(epoch));
+        self.extended_committees.set(ExtendedCommittees(committees));
+        self.admin_id.set(Some(admin_id));
+        self.subscriptions
+            .insert(&ChannelSubscription {
+                chain_id: admin_id,
+                name: SystemChannel::Admin.name(),
+            })
+            .expect("serialization failed");
+        self.ownership.set(ownership);
+        self.timestamp.set(timestamp);
+        self.balance.set(balance);
+        self.application_permissions.set(application_permissions);
+    }
+
+    pub async fn handle_query(
+        &mut self,
+        context: QueryContext,
+        _query: SystemQuery,
+    ) -> Result<SystemResponse, SystemExecutionError> {
+        let response = SystemResponse {
+            chain_id: context.chain_id,
+            balance: *self.balance.get(),
+        };
+        Ok(response)
+    }
+
+    /// Replaces the ownership of the chain, after checking that its extensions are valid.
+    fn change_ownership(
+        &mut self,
+        super_owners: Vec<PublicKey>,
+        owners: Vec<(PublicKey, u64)>,
+        multi_leader_rounds: u32,
+        timeout_config: TimeoutConfig,
+        extensions: OwnershipExtensions,
+    ) -> Result<(), SystemExecutionError> {
+        if let Some(recovery) = &extensions.recovery {
+            ensure!(
+                recovery.threshold > 0 && recovery.threshold as usize <= recovery.guardians.len(),
+                SystemExecutionError::InvalidRecoveryConfig
+            );
+        }
+        let ownership = ChainOwnership {
+            super_owners: super_owners
+                .into_iter()
+                .map(|public_key| (Owner::from(public_key), public_key))
+                .collect(),
+            owners: owners
+                .into_iter()
+                .map(|(public_key, weight)| (Owner::from(public_key), (public_key, weight)))
+                .collect(),
+            multi_leader_rounds,
+            timeout_config,
+            ..ChainOwnership::default()
+        }
+        .with_extensions(extensions);
+        if let Some(threshold) = ownership.approval_threshold {
+            // Without super owners, no block could be proposed if the regular
+            // owners could not approve it.
+            ensure!(
+                threshold > 0
+                    && (!ownership.super_owners.is_empty()
+                        || threshold <= ownership.approval_weight(ownership.owners.keys())),
+                SystemExecutionError::InvalidApprovalThreshold
+            );
+        }
+        self.ownership.set(ownership);
+        // A new ownership supersedes any recovery in progress.
+        self.pending_recovery.set(PendingRecovery::default());
+        Ok(())
+    }
+
+    /// Returns the messages to open a new chain, and subtracts the new chain's balance
+    /// from this chain's.
+    pub fn open_chain(
+        &mut self,
+        mut config: OpenChainConfig,
+        next_message_id: MessageId,
+    ) -> Result<[RawOutgoingMessage<SystemMessage, Amount>; 2], SystemExecutionError> {
+        let child_id = ChainId::child(next_message_id);
+        ensure!(
+            self.admin_id.get().as_ref() == Some(&config.admin_id),
+            SystemExecutionError::InvalidNewChainAdminId(child_id)
+        );
+        let admin_id = config.admin_id;
+        // The committees are sent to the new chain in the binary format of `Committee`,
+        // i.e. without their extensions. The new chain only learns the extensions of the
+        // committees created after it.
+        let committees = without_extensions(&config.committees);
+        ensure!(
+            without_extensions(self.committees()) == committees,
+            SystemExecutionError::InvalidCommittees
+        );
+        config.committees = committees;
+        ensure!(
+            self.epoch.get().as_ref() == Some(&config.epoch),
+            SystemExecutionError::InvalidEpoch {
+                chain_id: child_id,
+                epoch: config.epoch,
+            }
+        );
+        let balance = self.balance.get_mut();
+        balance
+            .try_sub_assign(config.balance)
+            .map_err(|_| SystemExecutionError::InsufficientFunding { balance: *balance })?;
+        let open_chain_message = RawOutgoingMessage {
+            destination: Destination::Recipient(child_id),
+            authenticated: false,
+            grant: Amount::ZERO,
+            kind: MessageKind::Protected,
+            expiry: None,
+            message: SystemMessage::OpenChain(config),
+        };
+        let subscription = ChannelSubscription {
+            chain_id: admin_id,
+            name: SystemChannel::Admin.name(),
+        };
+        let subscribe_message = RawOutgoingMessage {
+            destination: Destination::Recipient(admin_id),
+            authenticated: false,
+            grant: Amount::ZERO,
+            kind: MessageKind::Protected,
+            expiry: None,
+            message: SystemMessage::Subscribe {
+                id: child_id,
+                subscription,
+            },
+        };
+        Ok([open_chain_message, subscribe_message])
+    }
+
+    pub async fn close_chain(
+        &mut self,
+        id: ChainId,
+    ) -> Result<Vec<RawOutgoingMessage<SystemMessage, Amount>>, SystemExecutionError> {
+        let mut messages = Vec::new();
+        // Unsubscribe from all channels.
+        self.subscriptions
+            .for_each_index(|subscription| {
+                let message = RawOutgoingMessage {
+                    destination: Destination::Recipient(subscription.chain_id),
+                    authenticated: false,
+                    grant: Amount::ZERO,
+                    kind: MessageKind::Protected,
+                    expiry: None,
+                    message: SystemMessage::Unsubscribe { id, subscription },
+                };
+                messages.push(message);
+                Ok(())
+            })
+            .await?;
+        self.subscriptions.clear();
+        self.closed.set(true);
+        Ok(messages)
+    }
+
+    pub async fn read_blob_content(
+        &mut self,
+        blob_id: BlobId,
+    ) -> Result<BlobContent, SystemExecutionError> {
+        self.context()
+            .extra()
+            .get_blob(blob_id)
+            .await
+            .map_err(|_| SystemExecutionError::BlobNotFoundOnRead(blob_id))
+            .map(Into::into)
+    }
+
+    pub async fn assert_blob_exists(
+        &mut self,
+        blob_id: BlobId,
+    ) -> Result<(), SystemExecutionError> {
+        if self.context().extra().contains_blob(blob_id).await? {
+            Ok(())
+        } else {
+            Err(SystemExecutionError::BlobNotFoundOnRead(blob_id))
+        }
+    }
+
+    async fn check_and_record_bytecode_blobs(
+        &mut self,
+        bytecode_id: &BytecodeId,
+        txn_tracker: &mut TransactionTracker,
+    ) -> Result<(), SystemExecutionError> {
+        let contract_bytecode_blob_id =
+            BlobId::new(bytecode_id.contract_blob_hash, BlobType::ContractBytecode);
+        ensure!(
+            self.context()
+                .extra()
+                .contains_blob(contract_bytecode_blob_id)
+                .await?,
+            SystemExecutionError::BlobNotFoundOnRead(contract_bytecode_blob_id)
+        );
+        txn_tracker.replay_oracle_response(OracleResponse::Blob(contract_bytecode_blob_id))?;
+        let service_bytecode_blob_id =
+            BlobId::new(bytecode_id.service_blob_hash, BlobType::ServiceBytecode);
+        ensure!(
+            self.context()
+                .extra()
+                .contains_blob(service_bytecode_blob_id)
+                .await?,
+            SystemExecutionError::BlobNotFoundOnRead(service_bytecode_blob_id)
+        );
+        txn_tracker.replay_oracle_response(OracleResponse::Blob(service_bytecode_blob_id))?;
+        Ok(())
+    }
+}
+
+/// Returns the committees as they are after a round trip through their binary serialization.
+fn without_extensions(committees: &BTreeMap<Epoch, Committee>) -> BTreeMap<Epoch, Committee> {
+    committees
+        .iter()
+        .map(|(epoch, committee)| (*epoch, committee.without_extensions()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use assert_matches::assert_matches;
+    use linera_base::{
+        crypto::BlsKeyPair,
+        data_types::{Blob, BlockHeight, Bytecode, TimeDelta},
+        identifiers::{ApplicationId, GenericApplicationId},
+        ownership::RecoveryConfig,
+    };
+    use linera_views::context::MemoryContext;
+
+    use super::*;
+    use crate::{
+        committee::ValidatorState, ExecutionOutcome, ExecutionStateView, FeePayer,
+        ResourceController, TestExecutionRuntimeContext,
+    };
+
+    /// Returns an execution state view and a matching operation context, for epoch 1, with root
+    /// chain 0 as the admin ID and one empty committee.
+    async fn new_view_and_context() -> (
+        ExecutionStateView<MemoryContext<TestExecutionRuntimeContext>>,
+        OperationContext,
+    ) {
+        new_view_and_context_with(ChainDescription::Root(5), BTreeMap::new()).await
+    }
+
+    /// Returns the admin chain's execution state view and a matching operation context, for
+    /// epoch 1, with the given committee.
+    async fn new_admin_view_and_context(
+        committee: Committee,
+    ) -> (
+        ExecutionStateView<MemoryContext<TestExecutionRuntimeContext>>,
+        OperationContext,
+    ) {
+        let committees = BTreeMap::from([(Epoch(1), committee)]);
+        new_view_and_context_with(ChainDescription::Root(0), committees).await
+    }
+
+    async fn new_view_and_context_with(
+        description: ChainDescription,
+        committees: BTreeMap<Epoch, Committee>,
+    ) -> (
+        ExecutionStateView<MemoryContext<TestExecutionRuntimeContext>>,
+        OperationContext,
+    ) {
+        let context = OperationContext {
+            chain_id: ChainId::from(description),
+            authenticated_signer: None,
+            authenticated_caller_id: None,
+            height: BlockHeight::from(7),
+            index: Some(2),
+        };
+        let state = SystemExecutionState {
+            description: Some(description),
+            epoch: Some(Epoch(1)),
+            admin_id: Some(ChainId::root(0)),
+            committees,
+            ..SystemExecutionState::default()
+        };
+        let view = state.into_view().await;
+        (view, context)
+    }
+
+    /// Returns the context of a message from the given chain, executed in the block of the
+    /// given operation context.
+    fn message_context(context: OperationContext, sender: ChainId) -> MessageContext {
+        MessageContext {
+            chain_id: context.chain_id,
+            is_bouncing: false,
+            authenticated_signer: context.authenticated_signer,
+            refund_grant_to: None,
+            height: context.height,
+            certificate_hash: CryptoHash::test_hash("certificate"),
+            message_id: MessageId {
+                chain_id: sender,
+                height: BlockHeight::ZERO,
+                index: 0,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn application_message_index() {
+        let (mut view, context) = new_view_and_context().await;
+        let contract = Bytecode::new(b"contract".into());
+        let service = Bytecode::new(b"service".into());
+        let contract_blob = Blob::new_contract_bytecode(contract.compress());
+        let service_blob = Blob::new_service_bytecode(service.compress());
+        let bytecode_id = BytecodeId::new(contract_blob.id().hash, service_blob.id().hash);
+
+        let operation = SystemOperation::CreateApplication {
+            bytecode_id,
+            parameters: vec![],
+            instantiation_argument: vec![],
+            required_application_ids: vec![],
+        };
+        let mut txn_tracker = TransactionTracker::default();
+        view.context()
+            .extra()
+            .add_blobs(vec![contract_blob, service_blob]);
+        let new_application = view
+            .system
+            .execute_operation(context, operation, &mut txn_tracker)
+            .await
+            .unwrap();
+        let [ExecutionOutcome::System(result)] = &txn_tracker.destructure().unwrap().0[..] else {
+            panic!("Unexpected outcome");
+        };
+        assert_eq!(
+            result.messages[CREATE_APPLICATION_MESSAGE_INDEX as usize].message,
+            SystemMessage::ApplicationCreated
+        );
+        let creation = MessageId {
+            chain_id: context.chain_id,
+            height: context.height,
+            index: CREATE_APPLICATION_MESSAGE_INDEX,
+        };
+        let id = ApplicationId {
+            bytecode_id,
+            creation,
+        };
+        assert_eq!(new_application, Some((id, vec![])));
+    }
+
+    #[tokio::test]
+    async fn open_chain_message_index() {
+        let (mut view, context) = new_view_and_context().await;
+        let epoch = view.system.epoch.get().unwrap();
+        let admin_id = view.system.admin_id.get().unwrap();
+        let committees = view.system.committees().clone();
+        let ownership = ChainOwnership::single(PublicKey::test_key(0));
+        let config = OpenChainConfig {
+            ownership,
+            committees,
+            epoch,
+            admin_id,
+            balance: Amount::ZERO,
+            application_permissions: Default::default(),
+        };
+        let mut txn_tracker = TransactionTracker::default();
+        let operation = SystemOperation::OpenChain(config.clone());
+        let new_application = view
+            .system
+            .execute_operation(context, operation, &mut txn_tracker)
+            .await
+            .unwrap();
+        assert_eq!(new_application, None);
+        let [ExecutionOutcome::System(result)] = &txn_tracker.destructure().unwrap().0[..] else {
+            panic!("Unexpected outcome");
+        };
+        assert_eq!(
+            result.messages[OPEN_CHAIN_MESSAGE_INDEX as usize].message,
+            SystemMessage::OpenChain(config)
+        );
+    }
+
+    #[tokio::test]
+    async fn validator_rewards() {
+        let (key0, key1) = (PublicKey::test_key(0), PublicKey::test_key(1));
+        let reward_account = Account::chain(ChainId::root(1));
+        let validators = BTreeMap::from([
+            (
+                ValidatorName(key0),
+                ValidatorState {
+                    network_address: key0.to_string(),
+                    votes: 1,
+                    bls_key: None,
+                    reward_account: Some(reward_account),
+                },
+            ),
+            (
+                ValidatorName(key1),
+                ValidatorState {
+                    network_address: key1.to_string(),
+                    votes: 3,
+                    bls_key: None,
+                    reward_account: None,
+                },
+            ),
+        ]);
+        let committee = Committee::new(validators, ResourceControlPolicy::default());
+        let (mut view, context) = new_admin_view_and_context(committee).await;
+        let context = message_context(context, ChainId::root(2));
+        let mut txn_tracker = TransactionTracker::default();
+        let collect_fees = SystemMessage::CollectFees {
+            epoch: Epoch(1),
+            amount: Amount::from_tokens(4),
+        };
+        view.system
+            .execute_message(context, collect_fees, &mut txn_tracker)
+            .await
+            .unwrap();
+
+        // The first validator has a quarter of the votes.
+        let claim = SystemMessage::ClaimValidatorRewards { epoch: Epoch(1) };
+        let validator_context = MessageContext {
+            authenticated_signer: Some(Owner::from(key0)),
+            ..context
+        };
+        let outcome = view
+            .system
+            .execute_message(validator_context, claim.clone(), &mut txn_tracker)
+            .await
+            .unwrap();
+        assert_eq!(
+            outcome.messages[0].destination,
+            Destination::Recipient(reward_account.chain_id)
+        );
+        assert_eq!(
+            outcome.messages[0].message,
+            SystemMessage::Credit {
+                amount: Amount::ONE,
+                source: None,
+                target: None,
+            }
+        );
+
+        // Rewards can only be claimed once.
+        let result = view
+            .system
+            .execute_message(validator_context, claim.clone(), &mut txn_tracker)
+            .await;
+        assert_matches!(
+            result,
+            Err(SystemExecutionError::NoRewardsToClaim(Epoch(1)))
+        );
+
+        // The second validator didn't designate a reward account.
+        let validator_context = MessageContext {
+            authenticated_signer: Some(Owner::from(key1)),
+            ..context
+        };
+        let result = view
+            .system
+            .execute_message(validator_context, claim.clone(), &mut txn_tracker)
+            .await;
+        assert_matches!(result, Err(SystemExecutionError::MissingRewardAccount(_)));
+
+        // Only validators can claim rewards.
+        let result = view
+            .system
+            .execute_message(context, claim, &mut txn_tracker)
+            .await;
+        assert_matches!(
+            result,
+            Err(SystemExecutionError::UnauthenticatedRewardClaim)
+        );
+    }
+
+    #[tokio::test]
+    async fn fee_payments() {
+        let (mut view, context) = new_view_and_context().await;
+        let admin_id = ChainId::root(0);
+
+        // Small amounts of fees are kept until they reach the threshold.
+        view.system.collected_fees.set(Amount::from_millis(400));
+        let payment = view
+            .system
+            .pay_collected_fees(context.chain_id, Epoch(1))
+            .await
+            .unwrap();
+        assert!(payment.is_none());
+        view.system.collected_fees.set(FEE_PAYMENT_THRESHOLD);
+        let payment = view
+            .system
+            .pay_collected_fees(context.chain_id, Epoch(1))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(payment.destination, Destination::Recipient(admin_id));
+        assert_eq!(payment.kind, MessageKind::Protected);
+        assert_eq!(
+            payment.message,
+            SystemMessage::CollectFees {
+                epoch: Epoch(1),
+                amount: FEE_PAYMENT_THRESHOLD,
+            }
+        );
+        assert_eq!(*view.system.collected_fees.get(), Amount::ZERO);
+
+        // Once the chain moved to another epoch, the remaining fees are paid.
+        view.system.collected_fees.set(Amount::from_millis(400));
+        view.system.epoch.set(Some(Epoch(2)));
+        let payment = view
+            .system
+            .pay_collected_fees(context.chain_id, Epoch(1))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            payment.message,
+            SystemMessage::CollectFees {
+                epoch: Epoch(1),
+                amount: Amount::from_millis(400),
+            }
+        );
+
+        // The admin chain adds its fees to the reward pool directly.
+        view.system.collected_fees.set(Amount::from_millis(400));
+        let payment = view
+            .system
+            .pay_collected_fees(admin_id, Epoch(2))
+            .await
+            .unwrap();
+        assert!(payment.is_none());
+        let pool = view.system.reward_pools.get(&Epoch(2)).await.unwrap();
+        assert_eq!(pool.unwrap().total, Amount::from_millis(400));
+    }
+
+    #[tokio::test]
+    async fn fee_payment_on_epoch_change() {
+        let (mut view, context) = new_view_and_context().await;
+        let context = message_context(context, ChainId::root(0));
+        let mut txn_tracker = TransactionTracker::default();
+        view.system.collected_fees.set(Amount::from_millis(400));
+
+        // The fees collected in the previous epoch are paid into its pool, even below the
+        // threshold.
+        let create_committee = SystemMessage::CreateCommittee {
+            epoch: Epoch(2),
+            committee: Committee::make_simple(vec![ValidatorName(PublicKey::test_key(0))]),
+        };
+        let outcome = view
+            .system
+            .execute_message(context, create_committee, &mut txn_tracker)
+            .await
+            .unwrap();
+        assert_eq!(*view.system.epoch.get(), Some(Epoch(2)));
+        assert_eq!(outcome.messages.len(), 1);
+        assert_eq!(
+            outcome.messages[0].message,
+            SystemMessage::CollectFees {
+                epoch: Epoch(1),
+                amount: Amount::from_millis(400),
+            }
+        );
+        assert_eq!(*view.system.collected_fees.get(), Amount::ZERO);
+    }
+
+    #[tokio::test]
+    async fn stake_based_committee_election() {
+        let (key0, key1) = (PublicKey::test_key(0), PublicKey::test_key(1));
+        let (name0, name1) = (ValidatorName(key0), ValidatorName(key1));
+        let delegator = Owner::from(PublicKey::test_key(2));
+        let committee = Committee::make_simple(vec![name0]);
+        let (mut view, operation_context) = new_admin_view_and_context(committee).await;
+        view.system.staking_config.set(Some(StakingConfig {
+            minimum_stake: Amount::from_tokens(10),
+            stake_per_vote: Amount::ONE,
+            unbonding_period: TimeDelta::from_secs(100),
+        }));
+        let context = MessageContext {
+            authenticated_signer: Some(Owner::from(key0)),
+            ..message_context(operation_context, ChainId::root(2))
+        };
+        let register = |name: ValidatorName, amount| SystemMessage::RegisterValidator {
+            name,
+            network_address: name.to_string(),
+            bls_key: None,
+            reward_account: None,
+            amount,
+            source: None,
+        };
+        let mut txn_tracker = TransactionTracker::default();
+        view.system
+            .execute_message(
+                context,
+                register(name0, Amount::from_tokens(10)),
+                &mut txn_tracker,
+            )
+            .await
+            .unwrap();
+
+        // Validators must be registered by their own key, with the minimum stake.
+        let result = view
+            .system
+            .execute_message(
+                context,
+                register(name1, Amount::from_tokens(10)),
+                &mut txn_tracker,
+            )
+            .await;
+        assert_matches!(
+            result,
+            Err(SystemExecutionError::UnauthenticatedStakingOperation)
+        );
+        let validator_context = MessageContext {
+            authenticated_signer: Some(Owner::from(key1)),
+            ..context
+        };
+        let result = view
+            .system
+            .execute_message(
+                validator_context,
+                register(name1, Amount::from_tokens(5)),
+                &mut txn_tracker,
+            )
+            .await;
+        assert_matches!(result, Err(SystemExecutionError::InsufficientStake { .. }));
+        view.system
+            .execute_message(
+                validator_context,
+                register(name1, Amount::from_tokens(10)),
+                &mut txn_tracker,
+            )
+            .await
+            .unwrap();
+
+        // Delegators can bond stake to registered validators.
+        let delegator_context = MessageContext {
+            authenticated_signer: Some(delegator),
+            ..context
+        };
+        let bond = SystemMessage::Bond {
+            validator: name0,
+            amount: Amount::from_tokens(20),
+            source: None,
+        };
+        view.system
+            .execute_message(delegator_context, bond, &mut txn_tracker)
+            .await
+            .unwrap();
+
+        // The votes of the elected committee are derived from the stakes.
+        let operation = SystemOperation::Admin(AdminOperation::ElectCommittee { epoch: Epoch(2) });
+        view.system
+            .execute_operation(operation_context, operation, &mut txn_tracker)
+            .await
+            .unwrap();
+        assert_eq!(*view.system.epoch.get(), Some(Epoch(2)));
+        let committee = &view.system.committees()[&Epoch(2)];
+        assert_eq!(committee.weight(&name0), 30);
+        assert_eq!(committee.weight(&name1), 10);
+
+        // Unbonded stake can only be withdrawn after the unbonding period.
+        let unbond = SystemMessage::Unbond {
+            validator: name0,
+            amount: Amount::from_tokens(20),
+        };
+        view.system
+            .execute_message(delegator_context, unbond, &mut txn_tracker)
+            .await
+            .unwrap();
+        let result = view
+            .system
+            .execute_message(
+                delegator_context,
+                SystemMessage::WithdrawUnbonded,
+                &mut txn_tracker,
+            )
+            .await;
+        assert_matches!(result, Err(SystemExecutionError::NoUnbondedStake));
+        view.system
+            .timestamp
+            .set(Timestamp::from(0).saturating_add(TimeDelta::from_secs(100)));
+        let outcome = view
+            .system
+            .execute_message(
+                delegator_context,
+                SystemMessage::WithdrawUnbonded,
+                &mut txn_tracker,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            outcome.messages[0].destination,
+            Destination::Recipient(ChainId::root(2))
+        );
+        assert_eq!(
+            outcome.messages[0].message,
+            SystemMessage::Credit {
+                amount: Amount::from_tokens(20),
+                source: None,
+                target: Some(delegator),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn policy_proposals() {
+        let keys = (0..4).map(PublicKey::test_key).collect::<Vec<_>>();
+        let committee = Committee::make_simple(keys.iter().copied().map(ValidatorName).collect());
+        let (mut view, context) = new_admin_view_and_context(committee).await;
+        let context = message_context(context, ChainId::root(2));
+        let validator_context = |index: usize| MessageContext {
+            authenticated_signer: Some(Owner::from(keys[index])),
+            ..context
+        };
+        let policy = ResourceControlPolicy {
+            block: Amount::from_tokens(1),
+            ..ResourceControlPolicy::default()
+        };
+        let mut txn_tracker = TransactionTracker::default();
+
+        // Only validators can make proposals.
+        let proposal = SystemMessage::ProposePolicy {
+            policy: policy.clone(),
+        };
+        let result = view
+            .system
+            .execute_message(context, proposal.clone(), &mut txn_tracker)
+            .await;
+        assert_matches!(result, Err(SystemExecutionError::UnauthenticatedPolicyVote));
+        let outcome = view
+            .system
+            .execute_message(validator_context(0), proposal, &mut txn_tracker)
+            .await
+            .unwrap();
+        assert!(outcome.messages.is_empty());
+
+        // The policy is applied in a new epoch once a quorum of validators voted for it.
+        let vote = SystemMessage::VotePolicy { proposal_id: 0 };
+        let outcome = view
+            .system
+            .execute_message(validator_context(1), vote.clone(), &mut txn_tracker)
+            .await
+            .unwrap();
+        assert!(outcome.messages.is_empty());
+        let outcome = view
+            .system
+            .execute_message(validator_context(2), vote.clone(), &mut txn_tracker)
+            .await
+            .unwrap();
+        assert_matches!(
+            &outcome.messages[..],
+            [RawOutgoingMessage {
+                message: SystemMessage::CreateCommittee {
+                    epoch: Epoch(2),
+                    ..
+                },
+                ..
+            }]
+        );
+        assert_eq!(*view.system.epoch.get(), Some(Epoch(2)));
+        assert_eq!(view.system.committees()[&Epoch(2)].policy(), &policy);
+        let proposal = view.system.policy_proposals.get(&0).await.unwrap().unwrap();
+        assert_eq!(proposal.applied_in, Some(Epoch(2)));
+        assert_eq!(proposal.votes.len(), 3);
+
+        // Applied proposals don't accept any more votes.
+        let result = view
+            .system
+            .execute_message(validator_context(3), vote, &mut txn_tracker)
+            .await;
+        assert_matches!(
+            result,
+            Err(SystemExecutionError::PolicyProposalAlreadyApplied(0))
+        );
+
+        // Validators can only have a limited number of open proposals.
+        let proposal = SystemMessage::ProposePolicy { policy };
+        for _ in 0..MAX_OPEN_POLICY_PROPOSALS {
+            view.system
+                .execute_message(validator_context(3), proposal.clone(), &mut txn_tracker)
+                .await
+                .unwrap();
+        }
+        let result = view
+            .system
+            .execute_message(validator_context(3), proposal, &mut txn_tracker)
+            .await;
+        assert_matches!(
+            result,
+            Err(SystemExecutionError::TooManyPolicyProposals(name)) if name == ValidatorName(keys[3])
+        );
+    }
+
+    #[tokio::test]
+    async fn sponsorships() {
+        let (mut view, context) = new_view_and_context().await;
+        *view.system.balance.get_mut() = Amount::ONE;
+        let owner = Owner::from(PublicKey::test_key(0));
+        let sponsor = Account::owner(ChainId::root(1), owner);
+        let message_context = message_context(context, sponsor.chain_id);
+        let mut txn_tracker = TransactionTracker::default();
+        let sponsorship = SystemMessage::Sponsor {
+            owner: Some(owner),
+            budget: Amount::from_tokens(3),
+            applications: Some(vec![]),
+        };
+        view.system
+            .execute_message(message_context, sponsorship, &mut txn_tracker)
+            .await
+            .unwrap();
+        let recorded = view
+            .system
+            .sponsorships
+            .get(&sponsor)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(recorded.budget, Amount::from_tokens(3));
+        assert!(!recorded.covers(&GenericApplicationId::System));
+
+        // The sponsor's budget is used before the chain's balance.
+        let policy = ResourceControlPolicy {
+            block: Amount::from_tokens(2),
+            ..ResourceControlPolicy::default()
+        };
+        let mut controller = ResourceController {
+            policy: Arc::new(policy),
+            account: FeePayer {
+                owner: None,
+                sponsor: Some(sponsor),
+            },
+            ..ResourceController::default()
+        };
+        controller
+            .with_state(&mut view)
+            .await
+            .unwrap()
+            .track_block()
+            .unwrap();
+        let recorded = view
+            .system
+            .sponsorships
+            .get(&sponsor)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(recorded.budget, Amount::ONE);
+        assert_eq!(*view.system.balance.get(), Amount::ONE);
+
+        // Revoking the sponsorship returns the remaining budget.
+        let revoke = SystemMessage::RevokeSponsorship { owner: Some(owner) };
+        let outcome = view
+            .system
+            .execute_message(message_context, revoke, &mut txn_tracker)
+            .await
+            .unwrap();
+        assert_eq!(
+            outcome.messages[0].destination,
+            Destination::Recipient(sponsor.chain_id)
+        );
+        assert_eq!(
+            outcome.messages[0].message,
+            SystemMessage::Credit {
+                amount: Amount::ONE,
+                source: None,
+                target: Some(owner),
+            }
+        );
+        assert!(view
+            .system
+            .sponsorships
+            .get(&sponsor)
+            .await
+            .unwrap()
+            .is_none());
+
+        // A bouncing sponsorship refunds the sponsor.
+        let bouncing_context = MessageContext {
+            is_bouncing: true,
+            ..message_context
+        };
+        let sponsorship = SystemMessage::Sponsor {
+            owner: Some(owner),
+            budget: Amount::from_tokens(3),
+            applications: None,
+        };
+        view.system
+            .execute_message(bouncing_context, sponsorship, &mut txn_tracker)
+            .await
+            .unwrap();
+        assert_eq!(
+            view.system.balances.get(&owner).await.unwrap(),
+            Some(Amount::from_tokens(3))
+        );
+    }
+
+    #[tokio::test]
+    async fn social_recovery() {
+        let (mut view, context) = new_view_and_context().await;
+        let owner_key = PublicKey::test_key(0);
+        let guardian_keys = [1, 2, 3].map(PublicKey::test_key);
+        let new_owner_key = PublicKey::test_key(4);
+        let recovery = RecoveryConfig::new(guardian_keys, 2, TimeDelta::from_secs(60));
+        view.system
+            .ownership
+            .set(ChainOwnership::single(owner_key).with_recovery(recovery));
+        view.system.timestamp.set(Timestamp::from(1_000));
+        let signed_by = |public_key: PublicKey| OperationContext {
+            authenticated_signer: Some(Owner::from(public_key)),
+            ..context
+        };
+        let initiate = SystemOperation::InitiateRecovery {
+            new_owner: new_owner_key,
+        };
+        let mut txn_tracker = TransactionTracker::default();
+
+        // Only guardians can vote for a new owner.
+        let result = view
+            .system
+            .execute_operation(signed_by(owner_key), initiate.clone(), &mut txn_tracker)
+            .await;
+        assert_matches!(result, Err(SystemExecutionError::UnauthorizedGuardian));
+        view.system
+            .execute_operation(
+                signed_by(guardian_keys[0]),
+                initiate.clone(),
+                &mut txn_tracker,
+            )
+            .await
+            .unwrap();
+        assert_eq!(view.system.pending_recovery.get().approved, None);
+        let result = view
+            .system
+            .execute_operation(context, SystemOperation::CompleteRecovery, &mut txn_tracker)
+            .await;
+        assert_matches!(result, Err(SystemExecutionError::NoApprovedRecovery));
+
+        // Once the threshold is reached, the owner can still cancel the recovery.
+        view.system
+            .execute_operation(
+                signed_by(guardian_keys[1]),
+                initiate.clone(),
+                &mut txn_tracker,
+            )
+            .await
+            .unwrap();
+        let release_time = Timestamp::from(1_000).saturating_add(TimeDelta::from_secs(60));
+        assert_eq!(
+            view.system.pending_recovery.get().approved,
+            Some((new_owner_key, release_time))
+        );
+        let result = view
+            .system
+            .execute_operation(
+                signed_by(guardian_keys[2]),
+                SystemOperation::CancelRecovery,
+                &mut txn_tracker,
+            )
+            .await;
+        assert_matches!(
+            result,
+            Err(SystemExecutionError::UnauthorizedRecoveryCancellation)
+        );
+        view.system
+            .execute_operation(
+                signed_by(owner_key),
+                SystemOperation::CancelRecovery,
+                &mut txn_tracker,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            *view.system.pending_recovery.get(),
+            PendingRecovery::default()
+        );
+
+        // Without a cancellation, the new owner takes over after the delay.
+        for guardian_key in &guardian_keys[1..] {
+            view.system
+                .execute_operation(signed_by(*guardian_key), initiate.clone(), &mut txn_tracker)
+                .await
+                .unwrap();
+        }
+        let result = view
+            .system
+            .execute_operation(context, SystemOperation::CompleteRecovery, &mut txn_tracker)
+            .await;
+        assert_matches!(
+            result,
+            Err(SystemExecutionError::RecoveryDelayNotOver(time)) if time == release_time
+        );
+        view.system.timestamp.set(release_time);
+        view.system
+            .execute_operation(context, SystemOperation::CompleteRecovery, &mut txn_tracker)
+            .await
+            .unwrap();
+        let ownership = view.system.ownership.get();
+        assert_eq!(
+            ownership.verify_owner(&Owner::from(new_owner_key)),
+            Some(new_owner_key)
+        );
+        assert_eq!(ownership.verify_owner(&Owner::from(owner_key)), None);
+        assert!(ownership.recovery.is_some());
+    }
+
+    #[tokio::test]
+    async fn approval_thresholds() {
+        let (mut view, context) = new_view_and_context().await;
+        let (key0, key1) = (PublicKey::test_key(0), PublicKey::test_key(1));
+        let change_ownership =
+            |super_owners, approval_threshold| SystemOperation::ChangeOwnershipWithExtensions {
+                super_owners,
+                owners: vec![(key0, 100), (key1, 200)],
+                multi_leader_rounds: 2,
+                timeout_config: TimeoutConfig::default(),
+                extensions: OwnershipExtensions {
+                    approval_threshold: Some(approval_threshold),
+                    recovery: None,
+                },
+            };
+        let mut txn_tracker = TransactionTracker::default();
+
+        for (super_owners, threshold) in [(vec![], 0), (vec![key1], 0), (vec![], 301)] {
+            let result = view
+                .system
+                .execute_operation(
+                    context,
+                    change_ownership(super_owners, threshold),
+                    &mut txn_tracker,
+                )
+                .await;
+            assert_matches!(result, Err(SystemExecutionError::InvalidApprovalThreshold));
+        }
+        assert_eq!(*view.system.ownership.get(), ChainOwnership::default());
+
+        view.system
+            .execute_operation(context, change_ownership(vec![], 300), &mut txn_tracker)
+            .await
+            .unwrap();
+        assert_eq!(view.system.ownership.get().approval_threshold, Some(300));
+        // Super owners can propose blocks without approvals, whatever the threshold.
+        view.system
+            .execute_operation(context, change_ownership(vec![key1], 301), &mut txn_tracker)
+            .await
+            .unwrap();
+        assert_eq!(view.system.ownership.get().approval_threshold, Some(301));
+    }
+
+    #[tokio::test]
+    async fn hash_locks() {
+        let (mut view, context) = new_view_and_context().await;
+        *view.system.balance.get_mut() = Amount::from_tokens(3);
+        view.system.timestamp.set(Timestamp::from(1_000));
+        let recipient = Account::owner(ChainId::root(1), Owner::from(PublicKey::test_key(0)));
+        let preimage = Preimage(b"secret".to_vec());
+        let hash = preimage.hash();
+        let deadline = Timestamp::from(2_000);
+        let lock_funds = SystemOperation::LockFunds {
+            owner: None,
+            recipient,
+            amount: Amount::ONE,
+            hash,
+            deadline,
+        };
+        let mut txn_tracker = TransactionTracker::default();
+        view.system
+            .execute_operation(context, lock_funds.clone(), &mut txn_tracker)
+            .await
+            .unwrap();
+        assert_eq!(*view.system.balance.get(), Amount::from_tokens(2));
+        let result = view
+            .system
+            .execute_operation(context, lock_funds, &mut txn_tracker)
+            .await;
+        assert_matches!(result, Err(SystemExecutionError::HashLockAlreadyExists(h)) if h == hash);
+
+        // The lock cannot be refunded before the deadline.
+        let refund = SystemOperation::RefundLock { hash };
+        let result = view
+            .system
+            .execute_operation(context, refund.clone(), &mut txn_tracker)
+            .await;
+        assert_matches!(
+            result,
+            Err(SystemExecutionError::HashLockNotExpired(time)) if time == deadline
+        );
+
+        // Revealing the preimage sends it to the lock's chain in a protected message, with the
+        // current time.
+        let operation = SystemOperation::RedeemLock {
+            chain_id: context.chain_id,
+            preimage: preimage.clone(),
+        };
+        let mut redeem_tracker = TransactionTracker::default();
+        view.system
+            .execute_operation(context, operation, &mut redeem_tracker)
+            .await
+            .unwrap();
+        let [ExecutionOutcome::System(result)] = &redeem_tracker.destructure().unwrap().0[..]
+        else {
+            panic!("Unexpected outcome");
+        };
+        let sent_at = Timestamp::from(1_000);
+        let redeem = SystemMessage::RedeemLock {
+            preimage,
+            timestamp: sent_at,
+        };
+        assert_eq!(result.messages[0].kind, MessageKind::Protected);
+        assert_eq!(result.messages[0].message, redeem);
+
+        // A wrong preimage is ignored, since protected messages cannot be rejected.
+        let message_context = message_context(context, recipient.chain_id);
+        let wrong_redeem = SystemMessage::RedeemLock {
+            preimage: Preimage(b"guess".to_vec()),
+            timestamp: sent_at,
+        };
+        let outcome = view
+            .system
+            .execute_message(message_context, wrong_redeem, &mut txn_tracker)
+            .await
+            .unwrap();
+        assert!(outcome.messages.is_empty());
+        assert!(view.system.hash_locks.get(&hash).await.unwrap().is_some());
+
+        // The right preimage redeems the lock, and the funds go to the recipient. What counts
+        // is the time it was sent, even if it arrives after the deadline.
+        view.system.timestamp.set(deadline);
+        let outcome = view
+            .system
+            .execute_message(message_context, redeem, &mut txn_tracker)
+            .await
+            .unwrap();
+        assert_eq!(
+            outcome.messages[0].destination,
+            Destination::Recipient(recipient.chain_id)
+        );
+        assert_eq!(
+            outcome.messages[0].message,
+            SystemMessage::Credit {
+                amount: Amount::ONE,
+                source: None,
+                target: recipient.owner,
+            }
+        );
+        assert!(view.system.hash_locks.get(&hash).await.unwrap().is_none());
+
+        // A preimage sent after the deadline is ignored, and the lock can only be refunded.
+        view.system.timestamp.set(Timestamp::from(1_000));
+        let lock_funds = SystemOperation::LockFunds {
+            owner: None,
+            recipient,
+            amount: Amount::ONE,
+            hash,
+            deadline,
+        };
+        view.system
+            .execute_operation(context, lock_funds, &mut txn_tracker)
+            .await
+            .unwrap();
+        view.system.timestamp.set(deadline);
+        let late_redeem = SystemMessage::RedeemLock {
+            preimage: Preimage(b"secret".to_vec()),
+            timestamp: deadline,
+        };
+        let outcome = view
+            .system
+            .execute_message(message_context, late_redeem, &mut txn_tracker)
+            .await
+            .unwrap();
+        assert!(outcome.messages.is_empty());
+        view.system
+            .execute_operation(context, refund, &mut txn_tracker)
+            .await
+            .unwrap();
+        assert_eq!(*view.system.balance.get(), Amount::from_tokens(2));
+        assert!(view.system.hash_locks.get(&hash).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn legacy_committees() {
+        let (mut view, context) = new_view_and_context().await;
+        let keys = [PublicKey::test_key(0), PublicKey::test_key(1)];
+        let bls_key_pairs = [BlsKeyPair::generate(), BlsKeyPair::generate()];
+        let validators = keys
+            .iter()
+            .zip(&bls_key_pairs)
+            .map(|(key, bls_key_pair)| {
+                let state = ValidatorState {
+                    network_address: key.to_string(),
+                    votes: 1,
+                    bls_key: Some(BlsValidatorKey::new(bls_key_pair)),
+                    reward_account: Some(Account::chain(ChainId::root(1))),
+                };
+                (ValidatorName(*key), state)
+            })
+            .collect();
+        let committee = Committee::new(validators, ResourceControlPolicy::default())
+            .with_compact_certificates();
+        assert!(committee.compact_certificates());
+
+        // The binary format of a committee is unchanged: the extensions are not part of it.
+        let legacy_committee = committee.without_extensions();
+        let bytes = bcs::to_bytes(&committee).unwrap();
+        assert_eq!(bytes, bcs::to_bytes(&legacy_committee).unwrap());
+        assert_eq!(
+            bcs::from_bytes::<Committee>(&bytes).unwrap(),
+            legacy_committee
+        );
+
+        // Committees stored by older versions are still found.
+        let committees = BTreeMap::from([(Epoch(1), legacy_committee)]);
+        view.system.legacy_committees.set(committees.clone());
+        assert_eq!(view.system.committees(), &committees);
+        assert!(!view.system.compact_certificates());
+
+        // A new committee with BLS keys is announced in the extended format, and moves the
+        // existing committees to the new register.
+        let message = SystemMessage::create_committee(Epoch(2), committee.clone());
+        assert_matches!(message, SystemMessage::CreateCommitteeWithExtensions { .. });
+        let context = message_context(context, ChainId::root(0));
+        let mut txn_tracker = TransactionTracker::default();
+        view.system
+            .execute_message(context, message, &mut txn_tracker)
+            .await
+            .unwrap();
+        assert!(view.system.legacy_committees.get().is_empty());
+        assert_eq!(view.system.committees().len(), 2);
+        assert_eq!(view.system.committees()[&Epoch(2)], committee);
+        assert!(view.system.compact_certificates());
+    }
+}
@@ -0,0 +1,103 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Recovery of chains by their guardians.
+
+use std::collections::BTreeMap;
+
+use linera_base::{
+    crypto::PublicKey, data_types::Timestamp, ensure, identifiers::Owner, ownership::ChainOwnership,
+};
+use linera_views::context::Context;
+use serde::{Deserialize, Serialize};
+
+use super::{SystemExecutionError, SystemExecutionStateView};
+use crate::ExecutionRuntimeContext;
+
+/// An ownership change initiated by the guardians of a chain.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct PendingRecovery {
+    /// The new owner that each guardian voted for.
+    pub votes: BTreeMap<Owner, PublicKey>,
+    /// The new owner that enough guardians agreed on, with the time after which the
+    /// recovery can be completed unless an owner cancels it.
+    pub approved: Option<(PublicKey, Timestamp)>,
+}
+
+impl<C> SystemExecutionStateView<C>
+where
+    C: Context + Clone + Send + Sync + 'static,
+    C::Extra: ExecutionRuntimeContext,
+{
+    /// Records the signer's vote, as a guardian, for the new owner. Once enough guardians
+    /// agree, the recovery can be completed after the configured delay.
+    pub(super) fn initiate_recovery(
+        &mut self,
+        authenticated_signer: Option<Owner>,
+        new_owner: PublicKey,
+    ) -> Result<(), SystemExecutionError> {
+        let recovery = self
+            .ownership
+            .get()
+            .recovery
+            .clone()
+            .ok_or(SystemExecutionError::RecoveryDisabled)?;
+        let guardian = authenticated_signer
+            .filter(|signer| recovery.guardians.contains_key(signer))
+            .ok_or(SystemExecutionError::UnauthorizedGuardian)?;
+        let release_time = self.timestamp.get().saturating_add(recovery.delay);
+        let pending = self.pending_recovery.get_mut();
+        pending.votes.insert(guardian, new_owner);
+        if pending.approved.is_none() {
+            let votes = pending
+                .votes
+                .values()
+                .filter(|public_key| **public_key == new_owner)
+                .count();
+            if votes >= recovery.threshold as usize {
+                pending.approved = Some((new_owner, release_time));
+            }
+        }
+        Ok(())
+    }
+
+    /// Discards the recovery in progress, on behalf of one of the chain's owners.
+    pub(super) fn cancel_recovery(
+        &mut self,
+        authenticated_signer: Option<Owner>,
+    ) -> Result<(), SystemExecutionError> {
+        ensure!(
+            authenticated_signer.is_some_and(|signer| self
+                .ownership
+                .get()
+                .verify_owner(&signer)
+                .is_some()),
+            SystemExecutionError::UnauthorizedRecoveryCancellation
+        );
+        self.pending_recovery.set(PendingRecovery::default());
+        Ok(())
+    }
+
+    /// Hands the chain over to the new owner that the guardians approved, once the delay is
+    /// over.
+    pub(super) fn complete_recovery(&mut self) -> Result<(), SystemExecutionError> {
+        let (new_owner, release_time) = self
+            .pending_recovery
+            .get()
+            .approved
+            .ok_or(SystemExecutionError::NoApprovedRecovery)?;
+        ensure!(
+            *self.timestamp.get() >= release_time,
+            SystemExecutionError::RecoveryDelayNotOver(release_time)
+        );
+        let ownership = self.ownership.get();
+        let new_ownership = ChainOwnership {
+            timeout_config: ownership.timeout_config.clone(),
+            recovery: ownership.recovery.clone(),
+            ..ChainOwnership::single(new_owner)
+        };
+        self.ownership.set(new_ownership);
+        self.pending_recovery.set(PendingRecovery::default());
+        Ok(())
+    }
+}
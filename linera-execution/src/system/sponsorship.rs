@@ -0,0 +1,128 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Sponsors paying for the fees of other chains.
+
+use linera_base::{
+    data_types::Amount,
+    ensure,
+    identifiers::{Account, ChainId, GenericApplicationId, Owner},
+};
+use linera_views::context::Context;
+use serde::{Deserialize, Serialize};
+
+use super::{SystemExecutionError, SystemExecutionStateView, SystemMessage};
+use crate::{
+    Destination, ExecutionRuntimeContext, MessageKind, RawOutgoingMessage, UserApplicationId,
+};
+
+/// A sponsor's pre-authorization to pay for the fees of the blocks of a chain.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Sponsorship {
+    /// The amount that the sponsor has left for paying fees.
+    pub budget: Amount,
+    /// The applications whose operations and messages are sponsored, or `None` if all
+    /// operations and messages are.
+    pub applications: Option<Vec<UserApplicationId>>,
+}
+
+impl Sponsorship {
+    /// Returns whether the sponsor pays for operations and messages with the given
+    /// application ID.
+    pub fn covers(&self, app_id: &GenericApplicationId) -> bool {
+        match (app_id, &self.applications) {
+            (_, None) => true,
+            (GenericApplicationId::System, Some(_)) => false,
+            (GenericApplicationId::User(app_id), Some(app_ids)) => app_ids.contains(app_id),
+        }
+    }
+}
+
+impl<C> SystemExecutionStateView<C>
+where
+    C: Context + Clone + Send + Sync + 'static,
+    C::Extra: ExecutionRuntimeContext,
+{
+    /// Debits the budget of a sponsorship from the given owner's account, or from the
+    /// unattributed account, and returns the message that records it on the beneficiary
+    /// chain.
+    pub(super) async fn sponsor(
+        &mut self,
+        authenticated_signer: Option<Owner>,
+        owner: Option<Owner>,
+        beneficiary: ChainId,
+        budget: Amount,
+        applications: Option<Vec<UserApplicationId>>,
+    ) -> Result<RawOutgoingMessage<SystemMessage, Amount>, SystemExecutionError> {
+        if owner.is_some() {
+            ensure!(
+                authenticated_signer == owner,
+                SystemExecutionError::UnauthenticatedTransferOwner
+            );
+        }
+        ensure!(
+            budget > Amount::ZERO,
+            SystemExecutionError::IncorrectTransferAmount
+        );
+        let balance = match &owner {
+            Some(owner) => self.balances.get_mut_or_default(owner).await?,
+            None => self.balance.get_mut(),
+        };
+        balance
+            .try_sub_assign(budget)
+            .map_err(|_| SystemExecutionError::InsufficientFunding { balance: *balance })?;
+        Ok(RawOutgoingMessage {
+            destination: Destination::Recipient(beneficiary),
+            authenticated: false,
+            grant: Amount::ZERO,
+            kind: MessageKind::Tracked,
+            expiry: None,
+            message: SystemMessage::Sponsor {
+                owner,
+                budget,
+                applications,
+            },
+        })
+    }
+
+    /// Adds the budget of a sponsorship received from the given sponsor, and replaces the
+    /// sponsored applications.
+    pub(super) async fn add_sponsorship(
+        &mut self,
+        sponsor: Account,
+        budget: Amount,
+        applications: Option<Vec<UserApplicationId>>,
+    ) -> Result<(), SystemExecutionError> {
+        let sponsorship = self.sponsorships.get_mut_or_default(&sponsor).await?;
+        sponsorship.budget = sponsorship.budget.saturating_add(budget);
+        sponsorship.applications = applications;
+        Ok(())
+    }
+
+    /// Removes the given sponsor's sponsorship, and returns the message returning the
+    /// remaining budget, if any.
+    pub(super) async fn revoke_sponsorship(
+        &mut self,
+        sponsor: Account,
+    ) -> Result<Option<RawOutgoingMessage<SystemMessage, Amount>>, SystemExecutionError> {
+        let Some(sponsorship) = self.sponsorships.get(&sponsor).await? else {
+            return Ok(None);
+        };
+        self.sponsorships.remove(&sponsor)?;
+        if sponsorship.budget == Amount::ZERO {
+            return Ok(None);
+        }
+        Ok(Some(RawOutgoingMessage {
+            destination: Destination::Recipient(sponsor.chain_id),
+            authenticated: false,
+            grant: Amount::ZERO,
+            kind: MessageKind::Tracked,
+            expiry: None,
+            message: SystemMessage::Credit {
+                amount: sponsorship.budget,
+                source: None,
+                target: sponsor.owner,
+            },
+        }))
+    }
+}
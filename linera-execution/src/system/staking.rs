@@ -0,0 +1,279 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Stake-based committee elections.
+
+use std::collections::BTreeMap;
+
+use linera_base::{
+    data_types::{Amount, TimeDelta, Timestamp},
+    ensure,
+    identifiers::{Account, ChainId, Owner},
+};
+use linera_views::context::Context;
+use serde::{Deserialize, Serialize};
+
+use super::{SystemExecutionError, SystemExecutionStateView, SystemMessage};
+use crate::{
+    committee::{BlsValidatorKey, Committee, ValidatorName, ValidatorState},
+    Destination, ExecutionRuntimeContext, MessageKind, RawOutgoingMessage,
+};
+
+/// The parameters of the stake-based committee election.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct StakingConfig {
+    /// The minimum total stake that a validator needs to be elected.
+    pub minimum_stake: Amount,
+    /// The amount of stake that corresponds to one vote in the committee.
+    pub stake_per_vote: Amount,
+    /// How long unbonded stake remains locked before it can be withdrawn.
+    pub unbonding_period: TimeDelta,
+}
+
+/// A validator registered for the committee election.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct ValidatorStake {
+    /// The network address (in a string format understood by the networking layer).
+    pub network_address: String,
+    /// The BLS key used for aggregated certificates, if the validator registered one.
+    pub bls_key: Option<BlsValidatorKey>,
+    /// The account that receives the validator's share of the fees, if it designated one.
+    pub reward_account: Option<Account>,
+    /// The stakes bonded to the validator, including its own, by delegator.
+    pub delegations: BTreeMap<Owner, Amount>,
+}
+
+impl ValidatorStake {
+    /// Returns the total stake bonded to the validator.
+    pub fn total(&self) -> Amount {
+        self.delegations.values().sum()
+    }
+}
+
+/// Stake that was unbonded and can be withdrawn after the unbonding period.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct Unbonding {
+    /// The unbonded amount.
+    pub amount: Amount,
+    /// The account that the amount is returned to.
+    pub recipient: Account,
+    /// The earliest time at which the amount can be withdrawn.
+    pub release_time: Timestamp,
+}
+
+impl<C> SystemExecutionStateView<C>
+where
+    C: Context + Clone + Send + Sync + 'static,
+    C::Extra: ExecutionRuntimeContext,
+{
+    /// Returns a committee whose voting rights are derived from the validators' current
+    /// stakes. Validators below the minimum stake are not elected.
+    pub(super) async fn elect_committee(&self) -> Result<Committee, SystemExecutionError> {
+        let config = self
+            .staking_config
+            .get()
+            .clone()
+            .ok_or(SystemExecutionError::StakingDisabled)?;
+        let (_, current_committee) = self
+            .current_committee()
+            .ok_or(SystemExecutionError::InactiveChain)?;
+        let policy = current_committee.policy().clone();
+        let mut validators = BTreeMap::new();
+        self.validator_stakes
+            .for_each_index_value(|name, stake| {
+                let total = stake.total();
+                if total < config.minimum_stake {
+                    return Ok(());
+                }
+                // Cap the votes so that the committee's thresholds cannot overflow.
+                let votes = u64::try_from(total.saturating_div(config.stake_per_vote))
+                    .unwrap_or(u64::MAX)
+                    .min(u32::MAX.into());
+                if votes > 0 {
+                    let state = ValidatorState {
+                        network_address: stake.network_address,
+                        votes,
+                        bls_key: stake.bls_key,
+                        reward_account: stake.reward_account,
+                    };
+                    validators.insert(name, state);
+                }
+                Ok(())
+            })
+            .await?;
+        ensure!(
+            !validators.is_empty(),
+            SystemExecutionError::NoElectableValidators
+        );
+        Ok(Committee::new(validators, policy))
+    }
+
+    /// Debits the stake to be bonded from the given owner's account, or from the
+    /// unattributed account, on behalf of the signer.
+    pub(super) async fn debit_stake(
+        &mut self,
+        authenticated_signer: Option<Owner>,
+        owner: Option<Owner>,
+        amount: Amount,
+    ) -> Result<(), SystemExecutionError> {
+        ensure!(
+            authenticated_signer.is_some() && (owner.is_none() || owner == authenticated_signer),
+            SystemExecutionError::UnauthenticatedStakingOperation
+        );
+        ensure!(
+            amount > Amount::ZERO,
+            SystemExecutionError::IncorrectTransferAmount
+        );
+        let balance = match &owner {
+            Some(owner) => self.balances.get_mut_or_default(owner).await?,
+            None => self.balance.get_mut(),
+        };
+        balance
+            .try_sub_assign(amount)
+            .map_err(|_| SystemExecutionError::InsufficientFunding { balance: *balance })?;
+        Ok(())
+    }
+
+    pub(super) async fn register_validator(
+        &mut self,
+        name: ValidatorName,
+        stake: ValidatorStake,
+    ) -> Result<(), SystemExecutionError> {
+        let minimum_stake = self
+            .staking_config
+            .get()
+            .as_ref()
+            .ok_or(SystemExecutionError::StakingDisabled)?
+            .minimum_stake;
+        ensure!(
+            stake.total() >= minimum_stake,
+            SystemExecutionError::InsufficientStake { minimum_stake }
+        );
+        ensure!(
+            !self.validator_stakes.contains_key(&name).await?,
+            SystemExecutionError::ValidatorAlreadyRegistered(name)
+        );
+        if let Some(bls_key) = &stake.bls_key {
+            bls_key
+                .check()
+                .map_err(|error| SystemExecutionError::InvalidCommitteeBlsKey(Box::new(error)))?;
+        }
+        self.validator_stakes.insert(&name, stake)?;
+        Ok(())
+    }
+
+    pub(super) async fn bond(
+        &mut self,
+        authenticated_signer: Option<Owner>,
+        validator: ValidatorName,
+        amount: Amount,
+    ) -> Result<(), SystemExecutionError> {
+        ensure!(
+            self.staking_config.get().is_some(),
+            SystemExecutionError::StakingDisabled
+        );
+        let delegator =
+            authenticated_signer.ok_or(SystemExecutionError::UnauthenticatedStakingOperation)?;
+        let stake = self
+            .validator_stakes
+            .get_mut(&validator)
+            .await?
+            .ok_or(SystemExecutionError::UnregisteredValidator(validator))?;
+        stake
+            .delegations
+            .entry(delegator)
+            .or_default()
+            .try_add_assign(amount)?;
+        Ok(())
+    }
+
+    /// Moves some of the signer's stake to the unbonding queue. Once the unbonding period
+    /// is over, it can be withdrawn to the signer's account on the `origin` chain.
+    pub(super) async fn unbond(
+        &mut self,
+        authenticated_signer: Option<Owner>,
+        origin: ChainId,
+        validator: ValidatorName,
+        amount: Amount,
+    ) -> Result<(), SystemExecutionError> {
+        let unbonding_period = self
+            .staking_config
+            .get()
+            .as_ref()
+            .ok_or(SystemExecutionError::StakingDisabled)?
+            .unbonding_period;
+        let delegator =
+            authenticated_signer.ok_or(SystemExecutionError::UnauthenticatedStakingOperation)?;
+        let stake = self
+            .validator_stakes
+            .get_mut(&validator)
+            .await?
+            .ok_or(SystemExecutionError::UnregisteredValidator(validator))?;
+        let bonded = stake.delegations.get_mut(&delegator).ok_or(
+            SystemExecutionError::InsufficientBond {
+                bonded: Amount::ZERO,
+            },
+        )?;
+        bonded
+            .try_sub_assign(amount)
+            .map_err(|_| SystemExecutionError::InsufficientBond { bonded: *bonded })?;
+        if *bonded == Amount::ZERO {
+            stake.delegations.remove(&delegator);
+        }
+        if stake.delegations.is_empty() {
+            self.validator_stakes.remove(&validator)?;
+        }
+        let unbonding = Unbonding {
+            amount,
+            recipient: Account {
+                chain_id: origin,
+                owner: Some(delegator),
+            },
+            release_time: self.timestamp.get().saturating_add(unbonding_period),
+        };
+        self.unbondings
+            .get_mut_or_default(&delegator)
+            .await?
+            .push(unbonding);
+        Ok(())
+    }
+
+    /// Removes the signer's unbonded stake whose unbonding period is over, and returns the
+    /// messages crediting it to the recipients.
+    pub(super) async fn withdraw_unbonded(
+        &mut self,
+        authenticated_signer: Option<Owner>,
+    ) -> Result<Vec<RawOutgoingMessage<SystemMessage, Amount>>, SystemExecutionError> {
+        let delegator =
+            authenticated_signer.ok_or(SystemExecutionError::UnauthenticatedStakingOperation)?;
+        let now = *self.timestamp.get();
+        let (released, pending): (Vec<_>, Vec<_>) = self
+            .unbondings
+            .get(&delegator)
+            .await?
+            .unwrap_or_default()
+            .into_iter()
+            .partition(|unbonding| unbonding.release_time <= now);
+        ensure!(!released.is_empty(), SystemExecutionError::NoUnbondedStake);
+        if pending.is_empty() {
+            self.unbondings.remove(&delegator)?;
+        } else {
+            self.unbondings.insert(&delegator, pending)?;
+        }
+        Ok(released
+            .into_iter()
+            .map(|unbonding| RawOutgoingMessage {
+                destination: Destination::Recipient(unbonding.recipient.chain_id),
+                authenticated: false,
+                grant: Amount::ZERO,
+                kind: MessageKind::Tracked,
+                expiry: None,
+                message: SystemMessage::Credit {
+                    amount: unbonding.amount,
+                    source: None,
+                    target: unbonding.recipient.owner,
+                },
+            })
+            .collect())
+    }
+}
@@ -9,7 +9,7 @@ use std::{
 use linera_base::{
     crypto::CryptoHash,
     data_types::{Amount, ApplicationPermissions, Timestamp},
-    identifiers::{ApplicationId, ChainDescription, ChainId, Owner},
+    identifiers::{Account, ApplicationId, ChainDescription, ChainId, Owner},
     ownership::ChainOwnership,
 };
 use linera_views::{
@@ -21,9 +21,12 @@ use linera_views::{
 
 use crate::{
     applications::ApplicationRegistry,
-    committee::{Committee, Epoch},
+    committee::{Committee, Epoch, ExtendedCommittees, ValidatorName},
     execution::UserAction,
-    system::SystemChannel,
+    system::{
+        HashLock, PendingRecovery, PolicyProposal, RewardPool, Sponsorship, StakingConfig,
+        SystemChannel, Unbonding, ValidatorStake,
+    },
     ChannelSubscription, ExecutionError, ExecutionRuntimeConfig, ExecutionRuntimeContext,
     ExecutionStateView, OperationContext, ResourceControlPolicy, ResourceController,
     ResourceTracker, TestExecutionRuntimeContext, UserApplicationDescription, UserContractCode,
@@ -44,6 +47,15 @@ pub struct SystemExecutionState {
     pub registry: ApplicationRegistry,
     pub closed: bool,
     pub application_permissions: ApplicationPermissions,
+    pub collected_fees: Amount,
+    pub reward_pools: BTreeMap<Epoch, RewardPool>,
+    pub staking_config: Option<StakingConfig>,
+    pub validator_stakes: BTreeMap<ValidatorName, ValidatorStake>,
+    pub unbondings: BTreeMap<Owner, Vec<Unbonding>>,
+    pub policy_proposals: BTreeMap<u64, PolicyProposal>,
+    pub sponsorships: BTreeMap<Account, Sponsorship>,
+    pub pending_recovery: PendingRecovery,
+    pub hash_locks: BTreeMap<CryptoHash, HashLock>,
 }
 
 impl SystemExecutionState {
@@ -101,6 +113,15 @@ impl SystemExecutionState {
             registry,
             closed,
             application_permissions,
+            collected_fees,
+            reward_pools,
+            staking_config,
+            validator_stakes,
+            unbondings,
+            policy_proposals,
+            sponsorships,
+            pending_recovery,
+            hash_locks,
         } = self;
         let extra = TestExecutionRuntimeContext::new(chain_id, execution_runtime_config);
         let namespace = generate_test_namespace();
@@ -123,7 +144,9 @@ impl SystemExecutionState {
                 .insert(&subscription)
                 .expect("serialization of subscription should not fail");
         }
-        view.system.committees.set(committees);
+        view.system
+            .extended_committees
+            .set(ExtendedCommittees(committees));
         view.system.ownership.set(ownership);
         view.system.balance.set(balance);
         for (owner, balance) in balances {
@@ -141,6 +164,45 @@ impl SystemExecutionState {
         view.system
             .application_permissions
             .set(application_permissions);
+        view.system.collected_fees.set(collected_fees);
+        for (epoch, pool) in reward_pools {
+            view.system
+                .reward_pools
+                .insert(&epoch, pool)
+                .expect("insertion of reward pools should not fail");
+        }
+        view.system.staking_config.set(staking_config);
+        for (name, stake) in validator_stakes {
+            view.system
+                .validator_stakes
+                .insert(&name, stake)
+                .expect("insertion of validator stakes should not fail");
+        }
+        for (owner, unbondings) in unbondings {
+            view.system
+                .unbondings
+                .insert(&owner, unbondings)
+                .expect("insertion of unbondings should not fail");
+        }
+        for (proposal_id, proposal) in policy_proposals {
+            view.system
+                .policy_proposals
+                .insert(&proposal_id, proposal)
+                .expect("insertion of policy proposals should not fail");
+        }
+        for (sponsor, sponsorship) in sponsorships {
+            view.system
+                .sponsorships
+                .insert(&sponsor, sponsorship)
+                .expect("insertion of sponsorships should not fail");
+        }
+        view.system.pending_recovery.set(pending_recovery);
+        for (hash, lock) in hash_locks {
+            view.system
+                .hash_locks
+                .insert(&hash, lock)
+                .expect("insertion of hash locks should not fail");
+        }
         view
     }
 }
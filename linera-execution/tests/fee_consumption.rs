@@ -14,7 +14,7 @@ use linera_base::{
 };
 use linera_execution::{
     test_utils::{register_mock_applications, ExpectedCall, SystemExecutionState},
-    ContractRuntime, ExecutionError, ExecutionOutcome, Message, MessageContext,
+    ContractRuntime, ExecutionError, ExecutionOutcome, FeePayer, Message, MessageContext,
     RawExecutionOutcome, ResourceControlPolicy, ResourceController, TransactionTracker,
 };
 use test_case::test_case;
@@ -166,7 +166,10 @@ async fn test_fee_consumption(
     };
     let mut controller = ResourceController {
         policy: Arc::new(prices),
-        account: authenticated_signer,
+        account: FeePayer {
+            owner: authenticated_signer,
+            ..FeePayer::default()
+        },
         ..ResourceController::default()
     };
 
@@ -585,6 +585,7 @@ async fn test_sending_message_from_finalize() -> anyhow::Result<()> {
         authenticated: false,
         is_tracked: false,
         grant: Resources::default(),
+        expiry: None,
         message: b"first".to_vec(),
     };
 
@@ -620,6 +621,7 @@ async fn test_sending_message_from_finalize() -> anyhow::Result<()> {
         authenticated: false,
         is_tracked: false,
         grant: Resources::default(),
+        expiry: None,
         message: b"second".to_vec(),
     };
     let third_message = SendMessageRequest {
@@ -627,6 +629,7 @@ async fn test_sending_message_from_finalize() -> anyhow::Result<()> {
         authenticated: false,
         is_tracked: false,
         grant: Resources::default(),
+        expiry: None,
         message: b"third".to_vec(),
     };
     let fourth_message = SendMessageRequest {
@@ -634,6 +637,7 @@ async fn test_sending_message_from_finalize() -> anyhow::Result<()> {
         authenticated: false,
         is_tracked: false,
         grant: Resources::default(),
+        expiry: None,
         message: b"fourth".to_vec(),
     };
 
@@ -682,6 +686,7 @@ async fn test_sending_message_from_finalize() -> anyhow::Result<()> {
         authenticated: false,
         grant: Amount::ZERO,
         kind: MessageKind::Simple,
+        expiry: None,
         message: SystemMessage::RegisterApplications { applications },
     };
     let account = Account {
@@ -983,6 +988,7 @@ async fn test_simple_message() -> anyhow::Result<()> {
         authenticated: false,
         is_tracked: false,
         grant: Resources::default(),
+        expiry: None,
         message: b"msg".to_vec(),
     };
 
@@ -1025,6 +1031,7 @@ async fn test_simple_message() -> anyhow::Result<()> {
         authenticated: false,
         grant: Amount::ZERO,
         kind: MessageKind::Simple,
+        expiry: None,
         message: SystemMessage::RegisterApplications {
             applications: vec![application_description],
         },
@@ -1086,6 +1093,7 @@ async fn test_message_from_cross_application_call() -> anyhow::Result<()> {
         authenticated: false,
         is_tracked: false,
         grant: Resources::default(),
+        expiry: None,
         message: b"msg".to_vec(),
     };
 
@@ -1126,6 +1134,7 @@ async fn test_message_from_cross_application_call() -> anyhow::Result<()> {
         authenticated: false,
         grant: Amount::ZERO,
         kind: MessageKind::Simple,
+        expiry: None,
         message: SystemMessage::RegisterApplications {
             applications: vec![target_description],
         },
@@ -1204,6 +1213,7 @@ async fn test_message_from_deeper_call() -> anyhow::Result<()> {
         authenticated: false,
         is_tracked: false,
         grant: Resources::default(),
+        expiry: None,
         message: b"msg".to_vec(),
     };
 
@@ -1243,6 +1253,7 @@ async fn test_message_from_deeper_call() -> anyhow::Result<()> {
         authenticated: false,
         grant: Amount::ZERO,
         kind: MessageKind::Simple,
+        expiry: None,
         message: SystemMessage::RegisterApplications {
             applications: vec![target_description],
         },
@@ -1328,6 +1339,7 @@ async fn test_multiple_messages_from_different_applications() -> anyhow::Result<
         authenticated: false,
         is_tracked: false,
         grant: Resources::default(),
+        expiry: None,
         message: b"first".to_vec(),
     };
 
@@ -1366,6 +1378,7 @@ async fn test_multiple_messages_from_different_applications() -> anyhow::Result<
         authenticated: false,
         is_tracked: false,
         grant: Resources::default(),
+        expiry: None,
         message: b"second".to_vec(),
     };
 
@@ -1418,6 +1431,7 @@ async fn test_multiple_messages_from_different_applications() -> anyhow::Result<
         authenticated: false,
         grant: Amount::ZERO,
         kind: MessageKind::Simple,
+        expiry: None,
         message: SystemMessage::RegisterApplications {
             applications: vec![sending_target_description.clone(), caller_description],
         },
@@ -1428,6 +1442,7 @@ async fn test_multiple_messages_from_different_applications() -> anyhow::Result<
         authenticated: false,
         grant: Amount::ZERO,
         kind: MessageKind::Simple,
+        expiry: None,
         message: SystemMessage::RegisterApplications {
             applications: vec![sending_target_description],
         },
@@ -1579,7 +1594,7 @@ async fn test_open_chain() {
         .initialize_chain(message_id, Timestamp::from(0), config.clone());
     assert_eq!(*child_view.system.balance.get(), Amount::ONE);
     assert_eq!(*child_view.system.ownership.get(), child_ownership);
-    assert_eq!(*child_view.system.committees.get(), committees);
+    assert_eq!(*child_view.system.committees(), committees);
     assert_eq!(
         *child_view.system.application_permissions.get(),
         ApplicationPermissions::new_single(application_id)
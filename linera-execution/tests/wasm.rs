@@ -12,10 +12,10 @@ use linera_base::{
 };
 use linera_execution::{
     test_utils::{create_dummy_user_application_description, SystemExecutionState},
-    ExecutionOutcome, ExecutionRuntimeConfig, ExecutionRuntimeContext, Operation, OperationContext,
-    Query, QueryContext, RawExecutionOutcome, ResourceControlPolicy, ResourceController,
-    ResourceTracker, Response, TransactionTracker, WasmContractModule, WasmRuntime,
-    WasmServiceModule,
+    ExecutionOutcome, ExecutionRuntimeConfig, ExecutionRuntimeContext, FeePayer, Operation,
+    OperationContext, Query, QueryContext, RawExecutionOutcome, ResourceControlPolicy,
+    ResourceController, ResourceTracker, Response, TransactionTracker, WasmContractModule,
+    WasmRuntime, WasmServiceModule,
 };
 use linera_views::{context::Context as _, views::View};
 use serde_json::json;
@@ -85,7 +85,7 @@ async fn test_fuel_for_counter_wasm_application(
     let mut controller = ResourceController {
         policy: Arc::new(policy),
         tracker: ResourceTracker::default(),
-        account: None,
+        account: FeePayer::default(),
     };
 
     for increment in &increments {
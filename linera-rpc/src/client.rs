@@ -11,18 +11,24 @@ use linera_chain::data_types::{
 };
 use linera_core::{
     data_types::{ChainInfoQuery, ChainInfoResponse},
-    node::{CrossChainMessageDelivery, NodeError, NotificationStream, ValidatorNode},
+    gossip::CertificateAnnouncement,
+    node::{BatchItem, CrossChainMessageDelivery, NodeError, NotificationStream, ValidatorNode},
+    notifier::NotificationFilter,
 };
 
 use crate::grpc::GrpcClient;
 #[cfg(with_simple_network)]
 use crate::simple::SimpleClient;
+#[cfg(with_websocket)]
+use crate::ws::WebSocketClient;
 
 #[derive(Clone)]
 pub enum Client {
     Grpc(GrpcClient),
     #[cfg(with_simple_network)]
     Simple(SimpleClient),
+    #[cfg(with_websocket)]
+    WebSocket(WebSocketClient),
 }
 
 impl From<GrpcClient> for Client {
@@ -38,6 +44,13 @@ impl From<SimpleClient> for Client {
     }
 }
 
+#[cfg(with_websocket)]
+impl From<WebSocketClient> for Client {
+    fn from(client: WebSocketClient) -> Self {
+        Self::WebSocket(client)
+    }
+}
+
 impl ValidatorNode for Client {
     type NotificationStream = NotificationStream;
 
@@ -50,6 +63,9 @@ impl ValidatorNode for Client {
 
             #[cfg(with_simple_network)]
             Client::Simple(simple_client) => simple_client.handle_block_proposal(proposal).await,
+
+            #[cfg(with_websocket)]
+            Client::WebSocket(ws_client) => ws_client.handle_block_proposal(proposal).await,
         }
     }
 
@@ -71,6 +87,13 @@ impl ValidatorNode for Client {
                     .handle_lite_certificate(certificate, delivery)
                     .await
             }
+
+            #[cfg(with_websocket)]
+            Client::WebSocket(ws_client) => {
+                ws_client
+                    .handle_lite_certificate(certificate, delivery)
+                    .await
+            }
         }
     }
 
@@ -93,6 +116,29 @@ impl ValidatorNode for Client {
                     .handle_certificate(certificate, blobs, delivery)
                     .await
             }
+
+            #[cfg(with_websocket)]
+            Client::WebSocket(ws_client) => {
+                ws_client
+                    .handle_certificate(certificate, blobs, delivery)
+                    .await
+            }
+        }
+    }
+
+    async fn handle_batch(
+        &self,
+        items: Vec<BatchItem>,
+        delivery: CrossChainMessageDelivery,
+    ) -> Result<Vec<Result<ChainInfoResponse, NodeError>>, NodeError> {
+        match self {
+            Client::Grpc(grpc_client) => grpc_client.handle_batch(items, delivery).await,
+
+            #[cfg(with_simple_network)]
+            Client::Simple(simple_client) => simple_client.handle_batch(items, delivery).await,
+
+            #[cfg(with_websocket)]
+            Client::WebSocket(ws_client) => ws_client.handle_batch(items, delivery).await,
         }
     }
 
@@ -105,15 +151,27 @@ impl ValidatorNode for Client {
 
             #[cfg(with_simple_network)]
             Client::Simple(simple_client) => simple_client.handle_chain_info_query(query).await,
+
+            #[cfg(with_websocket)]
+            Client::WebSocket(ws_client) => ws_client.handle_chain_info_query(query).await,
         }
     }
 
-    async fn subscribe(&self, chains: Vec<ChainId>) -> Result<Self::NotificationStream, NodeError> {
+    async fn subscribe(
+        &self,
+        chains: Vec<ChainId>,
+        filter: NotificationFilter,
+    ) -> Result<Self::NotificationStream, NodeError> {
         Ok(match self {
-            Client::Grpc(grpc_client) => Box::pin(grpc_client.subscribe(chains).await?),
+            Client::Grpc(grpc_client) => Box::pin(grpc_client.subscribe(chains, filter).await?),
 
             #[cfg(with_simple_network)]
-            Client::Simple(simple_client) => Box::pin(simple_client.subscribe(chains).await?),
+            Client::Simple(simple_client) => {
+                Box::pin(simple_client.subscribe(chains, filter).await?)
+            }
+
+            #[cfg(with_websocket)]
+            Client::WebSocket(ws_client) => Box::pin(ws_client.subscribe(chains, filter).await?),
         })
     }
 
@@ -123,6 +181,9 @@ impl ValidatorNode for Client {
 
             #[cfg(with_simple_network)]
             Client::Simple(simple_client) => simple_client.get_version_info().await?,
+
+            #[cfg(with_websocket)]
+            Client::WebSocket(ws_client) => ws_client.get_version_info().await?,
         })
     }
 
@@ -132,6 +193,9 @@ impl ValidatorNode for Client {
 
             #[cfg(with_simple_network)]
             Client::Simple(simple_client) => simple_client.get_genesis_config_hash().await?,
+
+            #[cfg(with_websocket)]
+            Client::WebSocket(ws_client) => ws_client.get_genesis_config_hash().await?,
         })
     }
 
@@ -141,6 +205,9 @@ impl ValidatorNode for Client {
 
             #[cfg(with_simple_network)]
             Client::Simple(simple_client) => simple_client.download_blob_content(blob_id).await?,
+
+            #[cfg(with_websocket)]
+            Client::WebSocket(ws_client) => ws_client.download_blob_content(blob_id).await?,
         })
     }
 
@@ -153,6 +220,9 @@ impl ValidatorNode for Client {
 
             #[cfg(with_simple_network)]
             Client::Simple(simple_client) => simple_client.download_certificate_value(hash).await?,
+
+            #[cfg(with_websocket)]
+            Client::WebSocket(ws_client) => ws_client.download_certificate_value(hash).await?,
         })
     }
 
@@ -162,6 +232,9 @@ impl ValidatorNode for Client {
 
             #[cfg(with_simple_network)]
             Client::Simple(simple_client) => simple_client.download_certificate(hash).await?,
+
+            #[cfg(with_websocket)]
+            Client::WebSocket(ws_client) => ws_client.download_certificate(hash).await?,
         })
     }
 
@@ -174,6 +247,9 @@ impl ValidatorNode for Client {
 
             #[cfg(with_simple_network)]
             Client::Simple(simple_client) => simple_client.download_certificates(hashes).await?,
+
+            #[cfg(with_websocket)]
+            Client::WebSocket(ws_client) => ws_client.download_certificates(hashes).await?,
         })
     }
 
@@ -183,6 +259,36 @@ impl ValidatorNode for Client {
 
             #[cfg(with_simple_network)]
             Client::Simple(simple_client) => simple_client.blob_last_used_by(blob_id).await?,
+
+            #[cfg(with_websocket)]
+            Client::WebSocket(ws_client) => ws_client.blob_last_used_by(blob_id).await?,
         })
     }
+
+    async fn handle_certificate_announcement(
+        &self,
+        announcement: CertificateAnnouncement,
+    ) -> Result<(), NodeError> {
+        match self {
+            Client::Grpc(grpc_client) => {
+                grpc_client
+                    .handle_certificate_announcement(announcement)
+                    .await
+            }
+
+            #[cfg(with_simple_network)]
+            Client::Simple(simple_client) => {
+                simple_client
+                    .handle_certificate_announcement(announcement)
+                    .await
+            }
+
+            #[cfg(with_websocket)]
+            Client::WebSocket(ws_client) => {
+                ws_client
+                    .handle_certificate_announcement(announcement)
+                    .await
+            }
+        }
+    }
 }
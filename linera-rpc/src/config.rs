@@ -1,6 +1,8 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
+use std::{collections::BTreeMap, path::PathBuf};
+
 use linera_base::identifiers::ChainId;
 use serde::{Deserialize, Serialize};
 
@@ -41,8 +43,102 @@ pub struct NotificationConfig {
     pub(crate) notification_queue_size: usize,
 }
 
+#[derive(Clone, Debug, clap::Parser)]
+pub struct GossipConfig {
+    /// Announce new blocks to the other validators of the committee, and download the
+    /// blocks they announce.
+    #[arg(long = "gossip")]
+    pub(crate) enabled: bool,
+
+    /// Number of validators to announce each new block to.
+    #[arg(long = "gossip-fanout", default_value = "3")]
+    pub(crate) fanout: usize,
+
+    /// Number of new blocks waiting to be announced before dropping them.
+    #[arg(long = "gossip-queue-size", default_value = "1000")]
+    pub(crate) queue_size: usize,
+
+    /// How many blocks to announce concurrently.
+    #[arg(long = "gossip-max-tasks", default_value = "10")]
+    pub(crate) max_concurrent_tasks: usize,
+
+    /// Timeout for the requests to other validators.
+    #[arg(long = "gossip-timeout-ms", default_value = "5000")]
+    pub(crate) timeout_ms: u64,
+
+    /// Maximal number of blocks downloaded for a single announcement. Chains further
+    /// behind are left to the clients to synchronize.
+    #[arg(long = "gossip-max-height-gap", default_value = "1000")]
+    pub(crate) max_height_gap: u64,
+
+    /// Maximal number of chains synchronized at the same time after announcements.
+    #[arg(long = "gossip-max-synchronizations", default_value = "10")]
+    pub(crate) max_concurrent_synchronizations: usize,
+}
+
 pub type ShardId = usize;
 
+/// How chains are assigned to the shards of a validator.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShardingStrategy {
+    /// The hash of the chain ID modulo the number of shards. Adding a shard reassigns
+    /// most chains.
+    #[default]
+    Modulo,
+    /// Rendezvous hashing: each chain goes to the shard with the highest score for it.
+    /// Adding a shard at the end of the list only moves the chains that the new shard wins.
+    Rendezvous,
+}
+
+/// The table assigning chains to shards.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardAssignment {
+    /// The assignment of the chains without an override.
+    #[serde(default)]
+    pub strategy: ShardingStrategy,
+    /// Chains explicitly assigned to a shard, e.g. after being migrated.
+    #[serde(default)]
+    pub overrides: BTreeMap<ChainId, ShardId>,
+}
+
+impl ShardAssignment {
+    /// Returns the shard of a chain, among `num_shards` shards.
+    pub fn shard_id(&self, chain_id: ChainId, num_shards: usize) -> ShardId {
+        if let Some(shard_id) = self.overrides.get(&chain_id) {
+            if *shard_id < num_shards {
+                return *shard_id;
+            }
+        }
+        match self.strategy {
+            ShardingStrategy::Modulo => {
+                use std::hash::{Hash, Hasher};
+                let mut s = std::collections::hash_map::DefaultHasher::new();
+                chain_id.hash(&mut s);
+                (s.finish() as ShardId) % num_shards
+            }
+            ShardingStrategy::Rendezvous => {
+                let chain_key = u64::from_le_bytes(
+                    chain_id.0.as_bytes()[..8]
+                        .try_into()
+                        .expect("a hash has more than 8 bytes"),
+                );
+                (0..num_shards)
+                    .max_by_key(|shard_id| mix64(chain_key ^ mix64(*shard_id as u64)))
+                    .expect("a validator has at least one shard")
+            }
+        }
+    }
+}
+
+/// The finalizer of SplitMix64, used as a stable hash function for rendezvous hashing.
+fn mix64(mut x: u64) -> u64 {
+    x ^= x >> 30;
+    x = x.wrapping_mul(0xbf58476d1ce4e5b9);
+    x ^= x >> 27;
+    x = x.wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
 /// The network configuration of a shard.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ShardConfig {
@@ -93,6 +189,19 @@ impl NetworkProtocol {
     }
 }
 
+/// The certificates used for mutual TLS on the internal network of a validator. The proxy
+/// and the shards present the same certificate, and only accept peers whose certificate is
+/// signed by the validator's own certificate authority.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InternalTlsConfig {
+    /// The certificate of the validator's internal certificate authority, in PEM format.
+    pub ca_certificate_path: PathBuf,
+    /// The certificate of the proxy and the shards, in PEM format.
+    pub certificate_path: PathBuf,
+    /// The private key of the certificate, in PEM format.
+    pub private_key_path: PathBuf,
+}
+
 /// The network configuration for all shards.
 pub type ValidatorInternalNetworkConfig = ValidatorInternalNetworkPreConfig<NetworkProtocol>;
 
@@ -104,9 +213,16 @@ pub type ValidatorPublicNetworkConfig = ValidatorPublicNetworkPreConfig<NetworkP
 pub struct ValidatorInternalNetworkPreConfig<P> {
     /// The network protocol to use for all shards.
     pub protocol: P,
-    /// The available shards. Each chain UID is mapped to a unique shard in the vector in
-    /// a static way.
+    /// The available shards. Each chain UID is mapped to a unique shard in the vector
+    /// according to `shard_assignment`.
     pub shards: Vec<ShardConfig>,
+    /// The assignment of chains to shards.
+    #[serde(default)]
+    pub shard_assignment: ShardAssignment,
+    /// The certificates for mutual TLS between the proxy and the shards, required when
+    /// the protocol is gRPC with TLS.
+    #[serde(default)]
+    pub tls: Option<InternalTlsConfig>,
     /// The host name of the proxy on the internal network (IP or hostname).
     pub host: String,
     /// The port the proxy listens on on the internal network.
@@ -122,6 +238,8 @@ impl<P> ValidatorInternalNetworkPreConfig<P> {
         ValidatorInternalNetworkPreConfig {
             protocol,
             shards: self.shards.clone(),
+            shard_assignment: self.shard_assignment.clone(),
+            tls: self.tls.clone(),
             host: self.host.clone(),
             port: self.port,
             metrics_host: self.metrics_host.clone(),
@@ -134,6 +252,11 @@ impl ValidatorInternalNetworkConfig {
     pub fn proxy_address(&self) -> String {
         format!("{}://{}:{}", self.protocol.scheme(), self.host, self.port)
     }
+
+    /// The address of a shard, with the scheme of the internal protocol.
+    pub fn shard_address(&self, shard: &ShardConfig) -> String {
+        format!("{}://{}", self.protocol.scheme(), shard.address())
+    }
 }
 
 impl ValidatorPublicNetworkConfig {
@@ -226,12 +349,9 @@ impl std::str::FromStr for NetworkProtocol {
 }
 
 impl<P> ValidatorInternalNetworkPreConfig<P> {
-    /// Static shard assignment
+    /// Shard assignment, as configured at startup.
     pub fn get_shard_id(&self, chain_id: ChainId) -> ShardId {
-        use std::hash::{Hash, Hasher};
-        let mut s = std::collections::hash_map::DefaultHasher::new();
-        chain_id.hash(&mut s);
-        (s.finish() as ShardId) % self.shards.len()
+        self.shard_assignment.shard_id(chain_id, self.shards.len())
     }
 
     pub fn shard(&self, shard_id: ShardId) -> &ShardConfig {
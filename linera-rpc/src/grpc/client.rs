@@ -1,7 +1,13 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::{fmt, future::Future, iter};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    future::Future,
+    iter,
+    sync::{Arc, Mutex},
+};
 
 use futures::{future, stream, StreamExt};
 use linera_base::{
@@ -12,7 +18,9 @@ use linera_base::{
 };
 use linera_chain::data_types::{self, Certificate, CertificateValue, HashedCertificateValue};
 use linera_core::{
-    node::{CrossChainMessageDelivery, NodeError, NotificationStream, ValidatorNode},
+    gossip::CertificateAnnouncement,
+    node::{BatchItem, CrossChainMessageDelivery, NodeError, NotificationStream, ValidatorNode},
+    notifier::NotificationFilter,
     worker::Notification,
 };
 use linera_version::VersionInfo;
@@ -25,15 +33,12 @@ use {
 };
 
 use super::{
-    api::{
-        self, chain_info_result::Inner, validator_node_client::ValidatorNodeClient,
-        SubscriptionRequest,
-    },
-    transport, GrpcError, GRPC_MAX_MESSAGE_SIZE,
+    api::{self, chain_info_result::Inner, validator_node_client::ValidatorNodeClient},
+    transport, GrpcError, GRPC_MAX_MESSAGE_SIZE, RETRY_AFTER_MS_METADATA_KEY,
 };
 use crate::{
-    config::ValidatorPublicNetworkConfig, node_provider::NodeOptions, HandleCertificateRequest,
-    HandleLiteCertRequest,
+    config::ValidatorPublicNetworkConfig, node_provider::NodeOptions, HandleBatchRequest,
+    HandleCertificateRequest, HandleLiteCertRequest, SubscriptionRequest,
 };
 
 #[derive(Clone)]
@@ -95,6 +100,16 @@ impl GrpcClient {
         }
     }
 
+    /// Returns how long an overloaded validator asked us to wait before retrying.
+    pub fn retry_after(status: &Status) -> Duration {
+        let retry_after_ms = status
+            .metadata()
+            .get(RETRY_AFTER_MS_METADATA_KEY)
+            .and_then(|value| value.to_str().ok()?.parse().ok())
+            .unwrap_or_default();
+        Duration::from_millis(retry_after_ms)
+    }
+
     async fn delegate<F, Fut, R, S>(
         &self,
         f: F,
@@ -113,6 +128,22 @@ impl GrpcClient {
         })?;
         loop {
             match f(self.client.clone(), Request::new(request_inner.clone())).await {
+                Err(s) if s.code() == Code::ResourceExhausted => {
+                    let retry_after = Self::retry_after(&s);
+                    if retry_count >= self.max_retries {
+                        return Err(NodeError::Overloaded {
+                            retry_after_ms: retry_after.as_millis() as u64,
+                        });
+                    }
+                    info!("Validator is overloaded: {}; retrying", s.message());
+                    let delay = self
+                        .retry_delay
+                        .saturating_mul(retry_count)
+                        .max(retry_after);
+                    retry_count += 1;
+                    linera_base::time::timer::sleep(delay).await;
+                    continue;
+                }
                 Err(s) if Self::is_retryable(&s) && retry_count < self.max_retries => {
                     let delay = self.retry_delay.saturating_mul(retry_count);
                     retry_count += 1;
@@ -130,7 +161,7 @@ impl GrpcClient {
     }
 
     #[allow(clippy::result_large_err)]
-    fn try_into_chain_info(
+    pub fn try_into_chain_info(
         result: api::ChainInfoResult,
     ) -> Result<linera_core::data_types::ChainInfoResponse, NodeError> {
         let inner = result.inner.ok_or(NodeError::GrpcError {
@@ -206,6 +237,25 @@ impl ValidatorNode for GrpcClient {
         GrpcClient::try_into_chain_info(client_delegate!(self, handle_certificate, request)?)
     }
 
+    #[instrument(target = "grpc_client", skip_all, err, fields(address = self.address))]
+    async fn handle_batch(
+        &self,
+        items: Vec<BatchItem>,
+        delivery: CrossChainMessageDelivery,
+    ) -> Result<Vec<Result<linera_core::data_types::ChainInfoResponse, NodeError>>, NodeError> {
+        let wait_for_outgoing_messages = delivery.wait_for_outgoing_messages();
+        let request = HandleBatchRequest {
+            items,
+            wait_for_outgoing_messages,
+        };
+        let response = client_delegate!(self, handle_batch, request)?;
+        Ok(response
+            .results
+            .into_iter()
+            .map(GrpcClient::try_into_chain_info)
+            .collect())
+    }
+
     #[instrument(target = "grpc_client", skip_all, err, fields(address = self.address))]
     async fn handle_chain_info_query(
         &self,
@@ -215,14 +265,24 @@ impl ValidatorNode for GrpcClient {
     }
 
     #[instrument(target = "grpc_client", skip_all, err, fields(address = self.address))]
-    async fn subscribe(&self, chains: Vec<ChainId>) -> Result<Self::NotificationStream, NodeError> {
+    async fn subscribe(
+        &self,
+        chains: Vec<ChainId>,
+        filter: NotificationFilter,
+    ) -> Result<Self::NotificationStream, NodeError> {
         let retry_delay = self.retry_delay;
         let max_retries = self.max_retries;
         let mut retry_count = 0;
-        let subscription_request = SubscriptionRequest {
-            chain_ids: chains.into_iter().map(|chain| chain.into()).collect(),
-        };
+        let subscription_request = api::SubscriptionRequest::try_from(SubscriptionRequest {
+            chain_ids: chains,
+            filter,
+            resume_from: BTreeMap::new(),
+        })?;
         let mut client = self.client.clone();
+        // The sequence number of the last notification received for each chain, so that
+        // reconnections resume where the previous stream stopped.
+        let cursor = Arc::new(Mutex::new(BTreeMap::<ChainId, u64>::new()));
+        let received_cursor = cursor.clone();
 
         // Make the first connection attempt before returning from this method.
         let mut stream = Some(
@@ -239,13 +299,22 @@ impl ValidatorNode for GrpcClient {
         // `client.subscribe(request)` endlessly and without delay.
         let endlessly_retrying_notification_stream = stream::unfold((), move |()| {
             let mut client = client.clone();
-            let subscription_request = subscription_request.clone();
+            let mut subscription_request = subscription_request.clone();
+            subscription_request.resume_from = cursor
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(chain_id, sequence_number)| api::NotificationCursor {
+                    chain_id: Some((*chain_id).into()),
+                    sequence_number: *sequence_number,
+                })
+                .collect();
             let mut stream = stream.take();
             async move {
                 let stream = if let Some(stream) = stream.take() {
                     future::Either::Right(stream)
                 } else {
-                    match client.subscribe(subscription_request.clone()).await {
+                    match client.subscribe(subscription_request).await {
                         Err(err) => future::Either::Left(stream::iter(iter::once(Err(err)))),
                         Ok(response) => future::Either::Right(response.into_inner()),
                     }
@@ -258,11 +327,18 @@ impl ValidatorNode for GrpcClient {
         // The stream of `Notification`s that inserts increasing delays after retriable errors, and
         // terminates after unexpected or fatal errors.
         let notification_stream = endlessly_retrying_notification_stream
-            .map(|result| {
-                Notification::try_from(result?).map_err(|err| {
+            .map(move |result| {
+                let notification = Notification::try_from(result?).map_err(|err| {
                     let message = format!("Could not deserialize notification: {}", err);
                     tonic::Status::new(Code::Internal, message)
-                })
+                })?;
+                received_cursor
+                    .lock()
+                    .unwrap()
+                    .entry(notification.chain_id)
+                    .and_modify(|last| *last = (*last).max(notification.sequence_number))
+                    .or_insert(notification.sequence_number);
+                Ok(notification)
             })
             .take_while(move |result| {
                 let Err(status) = result else {
@@ -346,6 +422,16 @@ impl ValidatorNode for GrpcClient {
         let req = api::BlobId::try_from(blob_id)?;
         Ok(client_delegate!(self, blob_last_used_by, req)?.try_into()?)
     }
+
+    #[instrument(target = "grpc_client", skip_all, err, fields(address = self.address))]
+    async fn handle_certificate_announcement(
+        &self,
+        announcement: CertificateAnnouncement,
+    ) -> Result<(), NodeError> {
+        let request = api::CertificateAnnouncement::from(announcement);
+        client_delegate!(self, handle_certificate_announcement, request)?;
+        Ok(())
+    }
 }
 
 #[cfg(not(web))]
@@ -371,6 +457,17 @@ impl mass_client::MassClient for GrpcClient {
                             let request = Request::new((*request).try_into()?);
                             client.handle_certificate(request).await?
                         }
+                        RpcMessage::Batch(request) => {
+                            let request = Request::new((*request).try_into()?);
+                            let response = client.handle_batch(request).await?;
+                            let results = response
+                                .into_inner()
+                                .results
+                                .into_iter()
+                                .map(GrpcClient::try_into_chain_info)
+                                .collect::<Vec<_>>();
+                            return Ok(Some(results.into()));
+                        }
                         msg => panic!("attempted to send msg: {:?}", msg),
                     };
                     match response
@@ -13,15 +13,20 @@ use linera_chain::data_types::{
 };
 use linera_core::{
     data_types::{ChainInfoQuery, ChainInfoResponse, CrossChainRequest},
-    node::NodeError,
-    worker::Notification,
+    gossip::CertificateAnnouncement,
+    node::{BatchItem, NodeError},
+    notifier::{ChainNotification, NotificationFilter},
+    worker::{Notification, Reason},
 };
 use linera_execution::committee::ValidatorName;
 use thiserror::Error;
 use tonic::{Code, Status};
 
 use super::api;
-use crate::{HandleCertificateRequest, HandleLiteCertRequest};
+use crate::{
+    config::ShardId, HandleBatchRequest, HandleCertificateRequest, HandleLiteCertRequest,
+    SubscriptionRequest,
+};
 
 #[derive(Error, Debug)]
 pub enum GrpcProtoConversionError {
@@ -139,6 +144,7 @@ impl TryFrom<Notification> for api::Notification {
         Ok(Self {
             chain_id: Some(notification.chain_id.into()),
             reason: bincode::serialize(&notification.reason)?,
+            sequence_number: notification.sequence_number,
         })
     }
 }
@@ -150,6 +156,69 @@ impl TryFrom<api::Notification> for Notification {
         Ok(Self {
             chain_id: try_proto_convert(notification.chain_id)?,
             reason: bincode::deserialize(&notification.reason)?,
+            sequence_number: notification.sequence_number,
+        })
+    }
+}
+
+impl ChainNotification for api::Notification {
+    fn reason(&self) -> Option<Reason> {
+        bincode::deserialize(&self.reason).ok()
+    }
+
+    fn sequence_number(&self) -> Option<u64> {
+        Some(self.sequence_number)
+    }
+}
+
+impl TryFrom<SubscriptionRequest> for api::SubscriptionRequest {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(request: SubscriptionRequest) -> Result<Self, Self::Error> {
+        let filter = if request.filter == NotificationFilter::default() {
+            None
+        } else {
+            Some(bincode::serialize(&request.filter)?)
+        };
+        let resume_from = request
+            .resume_from
+            .into_iter()
+            .map(|(chain_id, sequence_number)| api::NotificationCursor {
+                chain_id: Some(chain_id.into()),
+                sequence_number,
+            })
+            .collect();
+        Ok(Self {
+            chain_ids: request.chain_ids.into_iter().map(Into::into).collect(),
+            filter,
+            resume_from,
+        })
+    }
+}
+
+impl TryFrom<api::SubscriptionRequest> for SubscriptionRequest {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(request: api::SubscriptionRequest) -> Result<Self, Self::Error> {
+        let chain_ids = request
+            .chain_ids
+            .into_iter()
+            .map(ChainId::try_from)
+            .collect::<Result<_, _>>()?;
+        let filter = request
+            .filter
+            .map(|filter| bincode::deserialize(&filter))
+            .transpose()?
+            .unwrap_or_default();
+        let resume_from = request
+            .resume_from
+            .into_iter()
+            .map(|cursor| Ok((try_proto_convert(cursor.chain_id)?, cursor.sequence_number)))
+            .collect::<Result<_, GrpcProtoConversionError>>()?;
+        Ok(Self {
+            chain_ids,
+            filter,
+            resume_from,
         })
     }
 }
@@ -190,6 +259,7 @@ impl TryFrom<BlockProposal> for api::BlockProposal {
                 .validated_block_certificate
                 .map(|cert| bincode::serialize(&cert))
                 .transpose()?,
+            approvals: bincode::serialize(&block_proposal.approvals)?,
         })
     }
 }
@@ -212,6 +282,7 @@ impl TryFrom<api::BlockProposal> for BlockProposal {
                 .validated_block_certificate
                 .map(|bytes| bincode::deserialize(&bytes))
                 .transpose()?,
+            approvals: bincode::deserialize(&block_proposal.approvals)?,
         })
     }
 }
@@ -289,8 +360,15 @@ impl<'a> TryFrom<api::LiteCertificate> for HandleLiteCertRequest<'a> {
         };
         let signatures = bincode::deserialize(&certificate.signatures)?;
         let round = bincode::deserialize(&certificate.round)?;
+        let aggregate = certificate
+            .aggregate_signature
+            .map(|bytes| bincode::deserialize(&bytes))
+            .transpose()?;
         Ok(Self {
-            certificate: LiteCertificate::new(value, round, signatures),
+            certificate: LiteCertificate {
+                aggregate,
+                ..LiteCertificate::new(value, round, signatures)
+            },
             wait_for_outgoing_messages: certificate.wait_for_outgoing_messages,
         })
     }
@@ -306,6 +384,12 @@ impl<'a> TryFrom<HandleLiteCertRequest<'a>> for api::LiteCertificate {
             chain_id: Some(request.certificate.value.chain_id.into()),
             signatures: bincode::serialize(&request.certificate.signatures)?,
             wait_for_outgoing_messages: request.wait_for_outgoing_messages,
+            aggregate_signature: request
+                .certificate
+                .aggregate
+                .as_ref()
+                .map(bincode::serialize)
+                .transpose()?,
         })
     }
 }
@@ -344,15 +428,106 @@ impl TryFrom<HandleCertificateRequest> for api::HandleCertificateRequest {
     }
 }
 
+impl TryFrom<HandleBatchRequest> for api::BatchRequest {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(request: HandleBatchRequest) -> Result<Self, Self::Error> {
+        use api::batch_item::Inner;
+
+        let wait_for_outgoing_messages = request.wait_for_outgoing_messages;
+        let items = request
+            .items
+            .into_iter()
+            .map(|item| {
+                let inner = match item {
+                    BatchItem::BlockProposal(proposal) => {
+                        Inner::BlockProposal((*proposal).try_into()?)
+                    }
+                    BatchItem::Certificate { certificate, blobs } => Inner::Certificate(
+                        HandleCertificateRequest {
+                            certificate: *certificate,
+                            blobs,
+                            wait_for_outgoing_messages,
+                        }
+                        .try_into()?,
+                    ),
+                };
+                Ok(api::BatchItem { inner: Some(inner) })
+            })
+            .collect::<Result<_, GrpcProtoConversionError>>()?;
+        Ok(Self {
+            items,
+            wait_for_outgoing_messages,
+        })
+    }
+}
+
+impl TryFrom<api::BatchRequest> for HandleBatchRequest {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(request: api::BatchRequest) -> Result<Self, Self::Error> {
+        let items = request
+            .items
+            .into_iter()
+            .map(BatchItem::try_from)
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            items,
+            wait_for_outgoing_messages: request.wait_for_outgoing_messages,
+        })
+    }
+}
+
+impl TryFrom<api::BatchItem> for BatchItem {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(item: api::BatchItem) -> Result<Self, Self::Error> {
+        use api::batch_item::Inner;
+
+        Ok(
+            match item.inner.ok_or(GrpcProtoConversionError::MissingField)? {
+                Inner::BlockProposal(proposal) => {
+                    BatchItem::BlockProposal(Box::new(proposal.try_into()?))
+                }
+                Inner::Certificate(request) => {
+                    let HandleCertificateRequest {
+                        certificate, blobs, ..
+                    } = request.try_into()?;
+                    BatchItem::Certificate {
+                        certificate: Box::new(certificate),
+                        blobs,
+                    }
+                }
+            },
+        )
+    }
+}
+
+impl TryFrom<Result<ChainInfoResponse, NodeError>> for api::ChainInfoResult {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(result: Result<ChainInfoResponse, NodeError>) -> Result<Self, Self::Error> {
+        match result {
+            Ok(chain_info_response) => chain_info_response.try_into(),
+            Err(node_error) => node_error.try_into(),
+        }
+    }
+}
+
 impl TryFrom<api::Certificate> for Certificate {
     type Error = GrpcProtoConversionError;
 
     fn try_from(certificate: api::Certificate) -> Result<Self, Self::Error> {
+        let aggregate = certificate
+            .aggregate_signature
+            .map(|bytes| bincode::deserialize(&bytes))
+            .transpose()?;
         Ok(Certificate::new(
             bincode::deserialize(&certificate.value)?,
             bincode::deserialize(&certificate.round)?,
             bincode::deserialize(&certificate.signatures)?,
-        ))
+        )
+        .with_aggregate_signature(aggregate))
     }
 }
 
@@ -364,6 +539,10 @@ impl TryFrom<Certificate> for api::Certificate {
             value: bincode::serialize(&certificate.value)?,
             round: bincode::serialize(&certificate.round)?,
             signatures: bincode::serialize(certificate.signatures())?,
+            aggregate_signature: certificate
+                .aggregate_signature()
+                .map(bincode::serialize)
+                .transpose()?,
         })
     }
 }
@@ -437,6 +616,53 @@ impl TryFrom<api::ChainId> for ChainId {
     }
 }
 
+impl From<(ChainId, ShardId)> for api::ChainAssignment {
+    fn from((chain_id, shard_id): (ChainId, ShardId)) -> Self {
+        Self {
+            chain_id: Some(chain_id.into()),
+            shard_id: shard_id as u32,
+        }
+    }
+}
+
+impl TryFrom<api::ChainAssignment> for (ChainId, ShardId) {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(assignment: api::ChainAssignment) -> Result<Self, Self::Error> {
+        Ok((
+            try_proto_convert(assignment.chain_id)?,
+            assignment.shard_id as ShardId,
+        ))
+    }
+}
+
+impl From<CertificateAnnouncement> for api::CertificateAnnouncement {
+    fn from(announcement: CertificateAnnouncement) -> Self {
+        Self {
+            chain_id: Some(announcement.chain_id.into()),
+            height: Some(announcement.height.into()),
+            hash: Some(announcement.hash.into()),
+            origin: Some(announcement.origin.into()),
+        }
+    }
+}
+
+impl TryFrom<api::CertificateAnnouncement> for CertificateAnnouncement {
+    type Error = GrpcProtoConversionError;
+
+    fn try_from(announcement: api::CertificateAnnouncement) -> Result<Self, Self::Error> {
+        Ok(Self {
+            chain_id: try_proto_convert(announcement.chain_id)?,
+            height: announcement
+                .height
+                .ok_or(GrpcProtoConversionError::MissingField)?
+                .into(),
+            hash: try_proto_convert(announcement.hash)?,
+            origin: try_proto_convert(announcement.origin)?,
+        })
+    }
+}
+
 impl From<PublicKey> for api::PublicKey {
     fn from(public_key: PublicKey) -> Self {
         Self {
@@ -652,14 +878,14 @@ impl TryFrom<api::CertificatesBatchResponse> for Vec<Certificate> {
 
 #[cfg(test)]
 pub mod tests {
-    use std::{borrow::Cow, fmt::Debug};
+    use std::{borrow::Cow, collections::BTreeMap, fmt::Debug};
 
     use linera_base::{
-        crypto::{BcsSignable, CryptoHash, KeyPair},
+        crypto::{BcsSignable, BlsKeyPair, BlsSignature, CryptoHash, KeyPair},
         data_types::{Amount, Round, Timestamp},
     };
     use linera_chain::{
-        data_types::{Block, BlockExecutionOutcome, HashedCertificateValue},
+        data_types::{AggregateSignature, Block, BlockExecutionOutcome, HashedCertificateValue},
         test::make_first_block,
     };
     use linera_core::data_types::ChainInfo;
@@ -792,6 +1018,7 @@ pub mod tests {
     #[test]
     pub fn test_lite_certificate() {
         let key_pair = KeyPair::generate();
+        let bls_key_pair = BlsKeyPair::generate();
         let certificate = LiteCertificate {
             value: LiteValue {
                 value_hash: CryptoHash::new(&Foo("value".into())),
@@ -802,6 +1029,10 @@ pub mod tests {
                 ValidatorName::from(key_pair.public()),
                 Signature::new(&Foo("test".into()), &key_pair),
             )]),
+            aggregate: Some(AggregateSignature {
+                signers: vec![ValidatorName::from(KeyPair::generate().public())],
+                signature: BlsSignature::new(&Foo("test".into()), &bls_key_pair),
+            }),
         };
         let request = HandleLiteCertRequest {
             certificate,
@@ -889,11 +1120,69 @@ pub mod tests {
             signature: Signature::new(&Foo("test".into()), &KeyPair::generate()),
             blobs: vec![],
             validated_block_certificate: Some(cert),
+            approvals: vec![(
+                Owner::from(KeyPair::generate().public()),
+                Signature::new(&Foo("approved".into()), &KeyPair::generate()),
+            )],
         };
 
         round_trip_check::<_, api::BlockProposal>(block_proposal);
     }
 
+    #[test]
+    pub fn test_batch_request() {
+        let key_pair = KeyPair::generate();
+        let block_proposal = BlockProposal {
+            content: ProposalContent {
+                block: make_first_block(ChainId::root(1)),
+                round: Round::Fast,
+                forced_oracle_responses: None,
+            },
+            owner: Owner::from(key_pair.public()),
+            signature: Signature::new(&Foo("test".into()), &key_pair),
+            blobs: vec![],
+            validated_block_certificate: None,
+            approvals: vec![],
+        };
+        let certificate = Certificate::new(
+            HashedCertificateValue::new_confirmed(
+                BlockExecutionOutcome::default().with(get_block()),
+            ),
+            Round::Fast,
+            vec![(
+                ValidatorName::from(key_pair.public()),
+                Signature::new(&Foo("test".into()), &key_pair),
+            )],
+        );
+        let request = HandleBatchRequest {
+            items: vec![block_proposal.into(), certificate.into()],
+            wait_for_outgoing_messages: true,
+        };
+
+        round_trip_check::<_, api::BatchRequest>(request);
+    }
+
+    #[test]
+    pub fn test_subscription_request() {
+        let request = SubscriptionRequest {
+            chain_ids: vec![ChainId::root(0), ChainId::root(1)],
+            filter: NotificationFilter::default(),
+            resume_from: BTreeMap::new(),
+        };
+        round_trip_check::<_, api::SubscriptionRequest>(request);
+
+        let request = SubscriptionRequest {
+            chain_ids: vec![ChainId::root(0)],
+            filter: NotificationFilter {
+                reasons: vec![linera_core::worker::ReasonKind::NewIncomingBundle],
+                origins: vec![ChainId::root(2)],
+                applications: vec![linera_base::identifiers::GenericApplicationId::System],
+            },
+            resume_from: BTreeMap::from([(ChainId::root(0), 42)]),
+        };
+        round_trip_check::<_, api::SubscriptionRequest>(request);
+    }
+
     #[test]
     pub fn test_notification() {
         let notification = Notification {
@@ -902,7 +1191,19 @@ pub mod tests {
                 height: BlockHeight(0),
                 hash: CryptoHash::new(&Foo("".into())),
             },
+            sequence_number: 7,
         };
         round_trip_check::<_, api::Notification>(notification);
     }
+
+    #[test]
+    pub fn test_certificate_announcement() {
+        let announcement = CertificateAnnouncement {
+            chain_id: ChainId::root(0),
+            height: BlockHeight(3),
+            hash: CryptoHash::new(&Foo("block".into())),
+            origin: ValidatorName::from(KeyPair::generate().public()),
+        };
+        round_trip_check::<_, api::CertificateAnnouncement>(announcement);
+    }
 }
@@ -7,6 +7,8 @@ mod node_provider;
 pub mod pool;
 #[cfg(with_server)]
 mod server;
+#[cfg(with_server)]
+mod tls;
 pub mod transport;
 
 pub use client::*;
@@ -14,6 +16,8 @@ pub use conversions::*;
 pub use node_provider::*;
 #[cfg(with_server)]
 pub use server::*;
+#[cfg(with_server)]
+pub use tls::*;
 
 pub mod api {
     tonic::include_proto!("rpc.v1");
@@ -39,11 +43,19 @@ pub enum GrpcError {
     #[cfg(with_server)]
     #[error(transparent)]
     Reflection(#[from] tonic_reflection::server::Error),
+
+    #[cfg(with_server)]
+    #[error(transparent)]
+    InternalTls(#[from] InternalTlsError),
 }
 
 const MEBIBYTE: usize = 1024 * 1024;
 pub const GRPC_MAX_MESSAGE_SIZE: usize = 16 * MEBIBYTE;
 
+/// The metadata key with which a validator that rejects a request because it is overloaded
+/// tells the client how many milliseconds to wait before retrying.
+pub const RETRY_AFTER_MS_METADATA_KEY: &str = "retry-after-ms";
+
 /// Limit of gRPC message size up to which we will try to populate with data when estimating.
 /// We leave 30% of buffer for the rest of the message and potential underestimation.
 pub const GRPC_CHUNKED_MESSAGE_FILL_LIMIT: usize = GRPC_MAX_MESSAGE_SIZE * 7 / 10;
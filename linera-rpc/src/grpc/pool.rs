@@ -24,6 +24,13 @@ impl GrpcConnectionPool {
         self
     }
 
+    /// Sets the TLS configuration of the connections, e.g. to present a client certificate.
+    #[cfg(not(web))]
+    pub fn with_tls(mut self, tls: impl Into<Option<tonic::transport::ClientTlsConfig>>) -> Self {
+        self.options.tls = tls.into();
+        self
+    }
+
     /// Obtains a channel for the current address. Either clones an existing one (thereby
     /// reusing the connection), or creates one if needed. New channels do not create a
     /// connection immediately.
@@ -12,20 +12,21 @@ use std::{
 
 use futures::{
     channel::{mpsc, mpsc::Receiver},
-    future::BoxFuture,
+    future::{self, BoxFuture},
     FutureExt as _, StreamExt,
 };
 use linera_base::identifiers::ChainId;
 use linera_core::{
-    node::NodeError,
-    worker::{NetworkActions, Notification, WorkerError, WorkerState},
+    gossip::CertificateGossip,
+    node::{BatchItem, NodeError},
+    worker::{NetworkActions, Notification, Reason, WorkerError, WorkerState},
     JoinSetExt as _, TaskHandle,
 };
 use linera_storage::Storage;
 use rand::Rng;
 use tokio::{sync::oneshot, task::JoinSet};
 use tokio_util::sync::CancellationToken;
-use tonic::{Request, Response, Status};
+use tonic::{transport::ClientTlsConfig, Request, Response, Status};
 use tower::{builder::ServiceBuilder, Layer, Service};
 use tracing::{debug, error, info, instrument, warn};
 #[cfg(with_metrics)]
@@ -40,17 +41,22 @@ use super::{
         notifier_service_client::NotifierServiceClient,
         validator_worker_client::ValidatorWorkerClient,
         validator_worker_server::{ValidatorWorker as ValidatorWorkerRpc, ValidatorWorkerServer},
-        BlockProposal, ChainInfoQuery, ChainInfoResult, CrossChainRequest, LiteCertificate,
+        BlockProposal, ChainAssignment, ChainInfoQuery, ChainInfoResult, CrossChainRequest,
+        LiteCertificate,
     },
     pool::GrpcConnectionPool,
-    GrpcError, GRPC_MAX_MESSAGE_SIZE,
+    transport, GrpcError, GrpcProtoConversionError, InternalTls, GRPC_MAX_MESSAGE_SIZE,
 };
 use crate::{
-    config::{CrossChainConfig, NotificationConfig, ShardId, ValidatorInternalNetworkConfig},
-    HandleCertificateRequest, HandleLiteCertRequest,
+    config::{
+        CrossChainConfig, GossipConfig, NotificationConfig, ShardId, ValidatorInternalNetworkConfig,
+    },
+    node_provider::NodeProvider,
+    sharding::{ShardRoute, ShardRouter},
+    HandleBatchRequest, HandleCertificateRequest, HandleLiteCertRequest, NodeOptions,
 };
 
-type CrossChainSender = mpsc::Sender<(linera_core::data_types::CrossChainRequest, ShardId)>;
+type CrossChainSender = mpsc::Sender<linera_core::data_types::CrossChainRequest>;
 type NotificationSender = mpsc::Sender<Notification>;
 
 #[cfg(with_metrics)]
@@ -109,8 +115,12 @@ where
     state: WorkerState<S>,
     shard_id: ShardId,
     network: ValidatorInternalNetworkConfig,
+    router: ShardRouter,
     cross_chain_sender: CrossChainSender,
     notification_sender: NotificationSender,
+    gossip: Option<CertificateGossip<NodeProvider, S>>,
+    /// The new blocks to announce to the other validators, if gossip is enabled.
+    announcement_sender: Option<NotificationSender>,
 }
 
 pub struct GrpcServerHandle {
@@ -184,6 +194,8 @@ where
         internal_network: ValidatorInternalNetworkConfig,
         cross_chain_config: CrossChainConfig,
         notification_config: NotificationConfig,
+        gossip_config: GossipConfig,
+        tls: Option<InternalTls>,
         shutdown_signal: CancellationToken,
         join_set: &mut JoinSet<()>,
     ) -> GrpcServerHandle {
@@ -198,6 +210,8 @@ where
         let (notification_sender, notification_receiver) =
             mpsc::channel(notification_config.notification_queue_size);
 
+        let router = ShardRouter::new(&internal_network);
+
         join_set.spawn_task({
             info!(
                 nickname = state.nickname(),
@@ -206,6 +220,8 @@ where
             Self::forward_cross_chain_queries(
                 state.nickname().to_string(),
                 internal_network.clone(),
+                router.clone(),
+                tls.as_ref().map(InternalTls::client_config),
                 cross_chain_config.max_retries,
                 Duration::from_millis(cross_chain_config.retry_delay_ms),
                 Duration::from_millis(cross_chain_config.sender_delay_ms),
@@ -224,18 +240,54 @@ where
             Self::forward_notifications(
                 state.nickname().to_string(),
                 internal_network.proxy_address(),
+                tls.as_ref().map(InternalTls::client_config),
                 notification_receiver,
             )
         });
 
+        let (gossip, announcement_sender) = if gossip_config.enabled {
+            let timeout = Duration::from_millis(gossip_config.timeout_ms);
+            let node_provider = NodeProvider::new(NodeOptions {
+                send_timeout: timeout,
+                recv_timeout: timeout,
+                retry_delay: Duration::ZERO,
+                max_retries: 0,
+            });
+            let gossip = CertificateGossip::new(state.clone(), node_provider, gossip_config.fanout)
+                .with_max_height_gap(gossip_config.max_height_gap)
+                .with_max_concurrent_synchronizations(
+                    gossip_config.max_concurrent_synchronizations,
+                );
+            let (announcement_sender, announcement_receiver) =
+                mpsc::channel(gossip_config.queue_size);
+            join_set.spawn_task({
+                info!(
+                    nickname = state.nickname(),
+                    "spawning gossip thread on {} for shard {}", host, shard_id
+                );
+                Self::announce_blocks(
+                    state.nickname().to_string(),
+                    gossip.clone(),
+                    gossip_config.max_concurrent_tasks,
+                    announcement_receiver,
+                )
+            });
+            (Some(gossip), Some(announcement_sender))
+        } else {
+            (None, None)
+        };
+
         let (mut health_reporter, health_service) = tonic_health::server::health_reporter();
 
         let grpc_server = GrpcServer {
             state,
             shard_id,
             network: internal_network,
+            router,
             cross_chain_sender,
             notification_sender,
+            gossip,
+            announcement_sender,
         };
 
         let worker_node = ValidatorWorkerServer::new(grpc_server)
@@ -253,7 +305,12 @@ where
                 .set_serving::<ValidatorWorkerServer<Self>>()
                 .await;
 
-            tonic::transport::Server::builder()
+            let mut server = tonic::transport::Server::builder();
+            if let Some(tls) = &tls {
+                // Only the proxy and the other shards of this validator can connect.
+                server = server.tls_config(tls.server_config())?;
+            }
+            server
                 .layer(
                     ServiceBuilder::new()
                         .layer(GrpcPrometheusMetricsMiddlewareLayer)
@@ -277,11 +334,15 @@ where
     async fn forward_notifications(
         nickname: String,
         proxy_address: String,
+        tls: Option<ClientTlsConfig>,
         mut receiver: Receiver<Notification>,
     ) {
-        let channel = tonic::transport::Channel::from_shared(proxy_address.clone())
-            .expect("Proxy URI should be valid")
-            .connect_lazy();
+        let options = transport::Options {
+            tls,
+            ..transport::Options::default()
+        };
+        let channel = transport::create_channel(proxy_address, &options)
+            .expect("Proxy URI and TLS configuration should be valid");
         let mut client = NotifierServiceClient::new(channel)
             .max_encoding_message_size(GRPC_MAX_MESSAGE_SIZE)
             .max_decoding_message_size(GRPC_MAX_MESSAGE_SIZE);
@@ -306,25 +367,60 @@ where
         }
     }
 
+    /// Continuously announces the new blocks received from the server loop to the other
+    /// validators.
+    #[instrument(skip(gossip, receiver))]
+    async fn announce_blocks(
+        nickname: String,
+        gossip: CertificateGossip<NodeProvider, S>,
+        max_concurrent_tasks: usize,
+        receiver: Receiver<Notification>,
+    ) {
+        receiver
+            .for_each_concurrent(Some(max_concurrent_tasks), |notification| {
+                let gossip = &gossip;
+                let nickname = &nickname;
+                async move {
+                    let Reason::NewBlock { height, hash } = notification.reason else {
+                        return;
+                    };
+                    let chain_id = notification.chain_id;
+                    if let Err(error) = gossip.announce(chain_id, height, hash).await {
+                        warn!(nickname, %error, %chain_id, %height, "could not announce block");
+                    }
+                }
+            })
+            .await;
+    }
+
     fn handle_network_actions(&self, actions: NetworkActions) {
         let mut cross_chain_sender = self.cross_chain_sender.clone();
         let mut notification_sender = self.notification_sender.clone();
+        let mut announcement_sender = self.announcement_sender.clone();
 
         for request in actions.cross_chain_requests {
-            let shard_id = self.network.get_shard_id(request.target_chain_id());
             tracing::trace!(
                 source_shard_id = self.shard_id,
-                target_shard_id = shard_id,
+                target_chain_id = %request.target_chain_id(),
                 "Scheduling cross-chain query",
             );
 
-            if let Err(error) = cross_chain_sender.try_send((request, shard_id)) {
+            if let Err(error) = cross_chain_sender.try_send(request) {
                 error!(%error, "dropping cross-chain request");
                 break;
             }
         }
 
         for notification in actions.notifications {
+            // Blocks downloaded from other validators are announced as well, so that they
+            // spread through the committee.
+            if let Some(sender) = &mut announcement_sender {
+                if matches!(notification.reason, Reason::NewBlock { .. }) {
+                    if let Err(error) = sender.try_send(notification.clone()) {
+                        warn!(%error, "dropping block announcement");
+                    }
+                }
+            }
             tracing::trace!("Scheduling notification query");
             if let Err(error) = notification_sender.try_send(notification) {
                 error!(%error, "dropping notification");
@@ -333,27 +429,53 @@ where
         }
     }
 
+    /// Registers a request for the given chain, rejecting it if the chain is handled by
+    /// another shard, e.g. because it was migrated.
+    async fn route(&self, request: &impl GrpcProxyable) -> Result<ShardRoute, Status> {
+        let chain_id = request
+            .chain_id()
+            .ok_or(GrpcProtoConversionError::MissingField)?;
+        let route = self.router.route(chain_id).await;
+        if route.shard_id() != self.shard_id {
+            return Err(Status::failed_precondition(format!(
+                "chain {chain_id} is handled by shard {}",
+                route.shard_id()
+            )));
+        }
+        Ok(route)
+    }
+
+    fn check_shard_id(&self, shard_id: ShardId) -> Result<(), Status> {
+        if shard_id >= self.network.shards.len() {
+            return Err(Status::invalid_argument(format!(
+                "invalid shard ID {shard_id}"
+            )));
+        }
+        Ok(())
+    }
+
     #[instrument(skip_all, fields(nickname, %this_shard))]
     #[expect(clippy::too_many_arguments)]
     async fn forward_cross_chain_queries(
         nickname: String,
         network: ValidatorInternalNetworkConfig,
+        router: ShardRouter,
+        tls: Option<ClientTlsConfig>,
         cross_chain_max_retries: u32,
         cross_chain_retry_delay: Duration,
         cross_chain_sender_delay: Duration,
         cross_chain_sender_failure_rate: f32,
         cross_chain_max_concurrent_tasks: usize,
         this_shard: ShardId,
-        receiver: mpsc::Receiver<(linera_core::data_types::CrossChainRequest, ShardId)>,
+        receiver: mpsc::Receiver<linera_core::data_types::CrossChainRequest>,
     ) {
-        let pool = GrpcConnectionPool::default();
+        let pool = GrpcConnectionPool::default().with_tls(tls);
         let max_concurrent_tasks = Some(cross_chain_max_concurrent_tasks);
 
         receiver
-            .for_each_concurrent(max_concurrent_tasks, |(cross_chain_request, shard_id)| {
-                let shard = network.shard(shard_id);
-                let remote_address = format!("http://{}", shard.address());
-
+            .for_each_concurrent(max_concurrent_tasks, |cross_chain_request| {
+                let network = &network;
+                let router = &router;
                 let pool = pool.clone();
                 let nickname = nickname.clone();
 
@@ -373,6 +495,9 @@ where
                         )
                         .await;
 
+                        // The target chain may have been migrated since the last attempt.
+                        let shard_id = router.shard_id(cross_chain_request.target_chain_id());
+                        let remote_address = network.shard_address(network.shard(shard_id));
                         let result = || async {
                             let cross_chain_request = cross_chain_request.clone().try_into()?;
                             let request = Request::new(cross_chain_request);
@@ -439,6 +564,7 @@ where
         &self,
         request: Request<BlockProposal>,
     ) -> Result<Response<ChainInfoResult>, Status> {
+        let _route = self.route(request.get_ref()).await?;
         let start = Instant::now();
         let proposal = request.into_inner().try_into()?;
         tracing::trace!(?proposal, "Handling block proposal");
@@ -468,6 +594,7 @@ where
         &self,
         request: Request<LiteCertificate>,
     ) -> Result<Response<ChainInfoResult>, Status> {
+        let _route = self.route(request.get_ref()).await?;
         let start = Instant::now();
         let HandleLiteCertRequest {
             certificate,
@@ -513,6 +640,7 @@ where
         &self,
         request: Request<api::HandleCertificateRequest>,
     ) -> Result<Response<ChainInfoResult>, Status> {
+        let _route = self.route(request.get_ref()).await?;
         let start = Instant::now();
         let HandleCertificateRequest {
             certificate,
@@ -549,11 +677,73 @@ where
         }
     }
 
+    #[instrument(target = "grpc_server", skip_all, err, fields(nickname = self.state.nickname()))]
+    async fn handle_batch(
+        &self,
+        request: Request<api::BatchRequest>,
+    ) -> Result<Response<api::BatchResult>, Status> {
+        let start = Instant::now();
+        let HandleBatchRequest {
+            items,
+            wait_for_outgoing_messages,
+        } = request.into_inner().try_into()?;
+        let chain_ids = items.iter().map(BatchItem::chain_id).collect::<Vec<_>>();
+        let routes = self.router.route_all(&chain_ids).await;
+        let outcomes = future::join_all(items.into_iter().zip(&routes).map(
+            |(item, route)| async move {
+                // Chains migrated to another shard are rejected individually.
+                if route.shard_id() != self.shard_id {
+                    let error = NodeError::GrpcError {
+                        error: format!(
+                            "chain {} is handled by shard {}",
+                            item.chain_id(),
+                            route.shard_id()
+                        ),
+                    };
+                    return (Err(error), None);
+                }
+                let (sender, receiver) = (wait_for_outgoing_messages
+                    && matches!(item, BatchItem::Certificate { .. }))
+                .then(oneshot::channel)
+                .unzip();
+                match self.state.handle_batch_item(item, sender).await {
+                    Ok((info, actions)) => {
+                        self.handle_network_actions(actions);
+                        (Ok(info), receiver)
+                    }
+                    Err(error) => {
+                        #[cfg(with_metrics)]
+                        {
+                            SERVER_REQUEST_ERROR
+                                .with_label_values(&["handle_batch"])
+                                .inc();
+                        }
+                        warn!(nickname = self.state.nickname(), %error, "Failed to handle batch item");
+                        (Err(NodeError::from(error)), None)
+                    }
+                }
+            },
+        ))
+        .await;
+        let mut results = Vec::with_capacity(outcomes.len());
+        for (result, receiver) in outcomes {
+            if let Some(receiver) = receiver {
+                if let Err(e) = receiver.await {
+                    error!("Failed to wait for message delivery: {e}");
+                }
+            }
+            results.push(result.try_into()?);
+        }
+        Self::log_request_success_and_latency(start, "handle_batch");
+        Ok(Response::new(api::BatchResult { results }))
+    }
+
     #[instrument(target = "grpc_server", skip_all, err, fields(nickname = self.state.nickname(), chain_id = ?request.get_ref().chain_id()))]
     async fn handle_chain_info_query(
         &self,
         request: Request<ChainInfoQuery>,
     ) -> Result<Response<ChainInfoResult>, Status> {
+        let _route = self.route(request.get_ref()).await?;
         let start = Instant::now();
         let query = request.into_inner().try_into()?;
         tracing::trace!(?query, "Handling chain info query");
@@ -581,6 +771,7 @@ where
         &self,
         request: Request<CrossChainRequest>,
     ) -> Result<Response<()>, Status> {
+        let _route = self.route(request.get_ref()).await?;
         let start = Instant::now();
         let request = request.into_inner().try_into()?;
         tracing::trace!(?request, "Handling cross-chain request");
@@ -601,6 +792,75 @@ where
         }
         Ok(Response::new(()))
     }
+
+    #[instrument(target = "grpc_server", skip_all, err, fields(nickname = self.state.nickname()))]
+    async fn drain_chain(&self, request: Request<ChainAssignment>) -> Result<Response<()>, Status> {
+        let (chain_id, shard_id) = request.into_inner().try_into()?;
+        self.check_shard_id(shard_id)?;
+        info!(%chain_id, from_shard = self.shard_id, to_shard = shard_id, "Draining chain");
+        self.router
+            .reassign(chain_id, shard_id, self.state.drain_chain_worker(chain_id))
+            .await
+            .map_err(|error| Status::unavailable(error.to_string()))?;
+        Ok(Response::new(()))
+    }
+
+    #[instrument(target = "grpc_server", skip_all, err, fields(nickname = self.state.nickname()))]
+    async fn reassign_chain(
+        &self,
+        request: Request<ChainAssignment>,
+    ) -> Result<Response<()>, Status> {
+        let (chain_id, shard_id) = request.into_inner().try_into()?;
+        self.check_shard_id(shard_id)?;
+        info!(%chain_id, to_shard = shard_id, "Reassigning chain");
+        self.router.set_shard(chain_id, shard_id);
+        Ok(Response::new(()))
+    }
+
+    #[instrument(target = "grpc_server", skip_all, err, fields(nickname = self.state.nickname(), chain_id = ?request.get_ref().chain_id()))]
+    async fn handle_certificate_announcement(
+        &self,
+        request: Request<api::CertificateAnnouncement>,
+    ) -> Result<Response<()>, Status> {
+        let Some(gossip) = self.gossip.clone() else {
+            return Err(Status::unimplemented("certificate gossip is disabled"));
+        };
+        let Some(slot) = gossip.try_reserve_synchronization() else {
+            return Err(Status::resource_exhausted(
+                "too many chains are being synchronized",
+            ));
+        };
+        let route = self.route(request.get_ref()).await?;
+        let announcement = request.into_inner().try_into()?;
+        tracing::trace!(?announcement, "Handling certificate announcement");
+        // Downloading the missing blocks may take a while: the announcing validator does
+        // not wait for it.
+        let server = self.clone();
+        tokio::spawn(async move {
+            let _route = route;
+            let start = Instant::now();
+            let mut actions = NetworkActions::default();
+            match gossip
+                .handle_announcement(announcement, slot, &mut actions)
+                .await
+            {
+                Ok(()) => {
+                    Self::log_request_success_and_latency(start, "handle_certificate_announcement");
+                }
+                Err(error) => {
+                    #[cfg(with_metrics)]
+                    {
+                        SERVER_REQUEST_ERROR
+                            .with_label_values(&["handle_certificate_announcement"])
+                            .inc();
+                    }
+                    warn!(nickname = server.state.nickname(), %error, "Failed to handle certificate announcement");
+                }
+            }
+            server.handle_network_actions(actions);
+        });
+        Ok(Response::new(()))
+    }
 }
 
 /// Types which are proxyable and expose the appropriate methods to be handled
@@ -627,12 +887,29 @@ impl GrpcProxyable for api::HandleCertificateRequest {
     }
 }
 
+impl GrpcProxyable for api::BatchItem {
+    fn chain_id(&self) -> Option<ChainId> {
+        use super::api::batch_item::Inner;
+
+        match self.inner.as_ref()? {
+            Inner::BlockProposal(proposal) => proposal.chain_id(),
+            Inner::Certificate(request) => request.chain_id(),
+        }
+    }
+}
+
 impl GrpcProxyable for ChainInfoQuery {
     fn chain_id(&self) -> Option<ChainId> {
         self.chain_id.clone()?.try_into().ok()
     }
 }
 
+impl GrpcProxyable for api::CertificateAnnouncement {
+    fn chain_id(&self) -> Option<ChainId> {
+        self.chain_id.clone()?.try_into().ok()
+    }
+}
+
 impl GrpcProxyable for CrossChainRequest {
     fn chain_id(&self) -> Option<ChainId> {
         use super::api::cross_chain_request::Inner;
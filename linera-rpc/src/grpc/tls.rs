@@ -0,0 +1,74 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Mutual TLS on the internal network of a validator.
+
+use tonic::transport::{Certificate, ClientTlsConfig, Identity, ServerTlsConfig};
+
+use crate::config::{
+    InternalTlsConfig, NetworkProtocol, TlsConfig, ValidatorInternalNetworkConfig,
+};
+
+/// The name in the internal certificate of a validator. Clients check it instead of the
+/// host names, so that the same certificate can be used by the proxy and all the shards.
+pub const INTERNAL_TLS_DOMAIN: &str = "linera-validator-internal";
+
+#[derive(thiserror::Error, Debug)]
+pub enum InternalTlsError {
+    #[error("the internal network uses TLS but no certificates are configured")]
+    MissingCertificates,
+
+    #[error("failed to read TLS certificates: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// The loaded certificates for mutual TLS between the proxy and the shards.
+#[derive(Clone, Debug)]
+pub struct InternalTls {
+    ca_certificate: Certificate,
+    identity: Identity,
+}
+
+impl InternalTls {
+    /// Loads the certificates of the internal network, if it uses TLS.
+    pub fn load(
+        network: &ValidatorInternalNetworkConfig,
+    ) -> Result<Option<Self>, InternalTlsError> {
+        match network.protocol {
+            NetworkProtocol::Grpc(TlsConfig::Tls) => {
+                let config = network
+                    .tls
+                    .as_ref()
+                    .ok_or(InternalTlsError::MissingCertificates)?;
+                Ok(Some(Self::read(config)?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn read(config: &InternalTlsConfig) -> std::io::Result<Self> {
+        let ca_certificate = std::fs::read(&config.ca_certificate_path)?;
+        let certificate = std::fs::read(&config.certificate_path)?;
+        let private_key = std::fs::read(&config.private_key_path)?;
+        Ok(Self {
+            ca_certificate: Certificate::from_pem(ca_certificate),
+            identity: Identity::from_pem(certificate, private_key),
+        })
+    }
+
+    /// The configuration of servers, which only accept clients with a certificate signed
+    /// by the validator's authority.
+    pub fn server_config(&self) -> ServerTlsConfig {
+        ServerTlsConfig::new()
+            .identity(self.identity.clone())
+            .client_ca_root(self.ca_certificate.clone())
+    }
+
+    /// The configuration of clients, which present the validator's certificate.
+    pub fn client_config(&self) -> ClientTlsConfig {
+        ClientTlsConfig::new()
+            .ca_certificate(self.ca_certificate.clone())
+            .identity(self.identity.clone())
+            .domain_name(INTERNAL_TLS_DOMAIN)
+    }
+}
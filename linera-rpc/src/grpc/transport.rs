@@ -7,6 +7,8 @@ use crate::NodeOptions;
 pub struct Options {
     pub connect_timeout: Option<linera_base::time::Duration>,
     pub timeout: Option<linera_base::time::Duration>,
+    #[cfg(not(web))]
+    pub tls: Option<tonic::transport::ClientTlsConfig>,
 }
 
 impl From<&'_ NodeOptions> for Options {
@@ -14,6 +16,8 @@ impl From<&'_ NodeOptions> for Options {
         Self {
             connect_timeout: Some(node_options.send_timeout),
             timeout: Some(node_options.recv_timeout),
+            #[cfg(not(web))]
+            tls: None,
         }
     }
 }
@@ -40,6 +44,9 @@ cfg_if::cfg_if! {
             if let Some(timeout) = options.timeout {
                 endpoint = endpoint.timeout(timeout);
             }
+            if let Some(tls) = &options.tls {
+                endpoint = endpoint.tls_config(tls.clone())?;
+            }
             Ok(endpoint.connect_lazy())
         }
     }
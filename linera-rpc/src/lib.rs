@@ -16,8 +16,12 @@ pub mod node_provider;
 pub mod client;
 
 mod message;
+#[cfg(with_server)]
+pub mod sharding;
 #[cfg(with_simple_network)]
 pub mod simple;
+#[cfg(with_websocket)]
+pub mod ws;
 
 pub mod grpc;
 
@@ -39,6 +43,22 @@ pub struct HandleCertificateRequest {
     pub blobs: Vec<linera_base::data_types::Blob>,
 }
 
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(with_testing, derive(Eq, PartialEq))]
+pub struct HandleBatchRequest {
+    pub items: Vec<linera_core::node::BatchItem>,
+    pub wait_for_outgoing_messages: bool,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(with_testing, derive(Eq, PartialEq))]
+pub struct SubscriptionRequest {
+    pub chain_ids: Vec<linera_base::identifiers::ChainId>,
+    pub filter: linera_core::notifier::NotificationFilter,
+    /// The sequence number of the last notification received for some of the chains.
+    pub resume_from: std::collections::BTreeMap<linera_base::identifiers::ChainId, u64>,
+}
+
 pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("file_descriptor_set");
 
 #[cfg(not(target_arch = "wasm32"))]
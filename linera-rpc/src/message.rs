@@ -11,11 +11,14 @@ use linera_chain::data_types::{BlockProposal, Certificate, CertificateValue, Lit
 use linera_core::{
     data_types::{ChainInfoQuery, ChainInfoResponse, CrossChainRequest},
     node::NodeError,
+    worker::Notification,
 };
 use linera_version::VersionInfo;
 use serde::{Deserialize, Serialize};
 
-use crate::{HandleCertificateRequest, HandleLiteCertRequest};
+use crate::{
+    HandleBatchRequest, HandleCertificateRequest, HandleLiteCertRequest, SubscriptionRequest,
+};
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 #[cfg_attr(with_testing, derive(Eq, PartialEq))]
@@ -47,12 +50,19 @@ pub enum RpcMessage {
 
     // Internal to a validator
     CrossChainRequest(Box<CrossChainRequest>),
+
+    // New variants are appended, so that the encoding of the others does not change.
+    Batch(Box<HandleBatchRequest>),
+    BatchResponse(Box<Vec<Result<ChainInfoResponse, NodeError>>>),
+    Subscribe(Box<SubscriptionRequest>),
+    Notification(Box<Notification>),
 }
 
 impl RpcMessage {
     /// Obtains the [`ChainId`] of the chain targeted by this message, if there is one.
     ///
-    /// Only inbound messages have target chains.
+    /// Only inbound messages have target chains. Batches may target several chains, and
+    /// have none.
     pub fn target_chain_id(&self) -> Option<ChainId> {
         use RpcMessage::*;
 
@@ -65,6 +75,8 @@ impl RpcMessage {
             Vote(_)
             | Error(_)
             | ChainInfoResponse(_)
+            | Batch(_)
+            | BatchResponse(_)
             | VersionInfoQuery
             | VersionInfoResponse(_)
             | GenesisConfigHashQuery
@@ -78,7 +90,9 @@ impl RpcMessage {
             | BlobLastUsedBy(_)
             | BlobLastUsedByResponse(_)
             | DownloadCertificateResponse(_)
-            | DownloadCertificatesResponse(_) => {
+            | DownloadCertificatesResponse(_)
+            | Subscribe(_)
+            | Notification(_) => {
                 return None;
             }
         };
@@ -98,10 +112,13 @@ impl RpcMessage {
             | DownloadCertificateValue(_)
             | BlobLastUsedBy(_)
             | DownloadCertificate(_)
-            | DownloadCertificates(_) => true,
+            | DownloadCertificates(_)
+            | Subscribe(_) => true,
             BlockProposal(_)
             | LiteCertificate(_)
             | Certificate(_)
+            | Batch(_)
+            | BatchResponse(_)
             | ChainInfoQuery(_)
             | CrossChainRequest(_)
             | Vote(_)
@@ -113,7 +130,8 @@ impl RpcMessage {
             | DownloadCertificateValueResponse(_)
             | BlobLastUsedByResponse(_)
             | DownloadCertificateResponse(_)
-            | DownloadCertificatesResponse(_) => false,
+            | DownloadCertificatesResponse(_)
+            | Notification(_) => false,
         }
     }
 }
@@ -190,6 +208,18 @@ impl TryFrom<RpcMessage> for Vec<Certificate> {
     }
 }
 
+impl TryFrom<RpcMessage> for Vec<Result<ChainInfoResponse, NodeError>> {
+    type Error = NodeError;
+    fn try_from(message: RpcMessage) -> Result<Self, Self::Error> {
+        use RpcMessage::*;
+        match message {
+            BatchResponse(results) => Ok(*results),
+            Error(error) => Err(*error),
+            _ => Err(NodeError::UnexpectedMessage),
+        }
+    }
+}
+
 impl TryFrom<RpcMessage> for CryptoHash {
     type Error = NodeError;
     fn try_from(message: RpcMessage) -> Result<Self, Self::Error> {
@@ -203,6 +233,18 @@ impl TryFrom<RpcMessage> for CryptoHash {
     }
 }
 
+impl TryFrom<RpcMessage> for Notification {
+    type Error = NodeError;
+    fn try_from(message: RpcMessage) -> Result<Self, Self::Error> {
+        use RpcMessage::*;
+        match message {
+            Notification(notification) => Ok(*notification),
+            Error(error) => Err(*error),
+            _ => Err(NodeError::UnexpectedMessage),
+        }
+    }
+}
+
 impl From<BlockProposal> for RpcMessage {
     fn from(block_proposal: BlockProposal) -> Self {
         RpcMessage::BlockProposal(Box::new(block_proposal))
@@ -221,6 +263,18 @@ impl From<HandleCertificateRequest> for RpcMessage {
     }
 }
 
+impl From<HandleBatchRequest> for RpcMessage {
+    fn from(request: HandleBatchRequest) -> Self {
+        RpcMessage::Batch(Box::new(request))
+    }
+}
+
+impl From<Vec<Result<ChainInfoResponse, NodeError>>> for RpcMessage {
+    fn from(results: Vec<Result<ChainInfoResponse, NodeError>>) -> Self {
+        RpcMessage::BatchResponse(Box::new(results))
+    }
+}
+
 impl From<Vec<CryptoHash>> for RpcMessage {
     fn from(hashes: Vec<CryptoHash>) -> Self {
         RpcMessage::DownloadCertificates(Box::new(hashes))
@@ -286,3 +340,15 @@ impl From<Vec<Certificate>> for RpcMessage {
         RpcMessage::DownloadCertificatesResponse(Box::new(certificates))
     }
 }
+
+impl From<SubscriptionRequest> for RpcMessage {
+    fn from(request: SubscriptionRequest) -> Self {
+        RpcMessage::Subscribe(Box::new(request))
+    }
+}
+
+impl From<Notification> for RpcMessage {
+    fn from(notification: Notification) -> Self {
+        RpcMessage::Notification(Box::new(notification))
+    }
+}
@@ -6,6 +6,8 @@ use linera_core::node::{NodeError, ValidatorNodeProvider};
 
 #[cfg(with_simple_network)]
 use crate::simple::SimpleNodeProvider;
+#[cfg(with_websocket)]
+use crate::ws::WebSocketNodeProvider;
 use crate::{client::Client, grpc::GrpcNodeProvider};
 
 /// A general node provider which delegates node provision to the underlying
@@ -15,6 +17,8 @@ pub struct NodeProvider {
     grpc: GrpcNodeProvider,
     #[cfg(with_simple_network)]
     simple: SimpleNodeProvider,
+    #[cfg(with_websocket)]
+    ws: WebSocketNodeProvider,
 }
 
 impl NodeProvider {
@@ -23,6 +27,8 @@ impl NodeProvider {
             grpc: GrpcNodeProvider::new(options),
             #[cfg(with_simple_network)]
             simple: SimpleNodeProvider::new(options),
+            #[cfg(with_websocket)]
+            ws: WebSocketNodeProvider::new(options),
         }
     }
 }
@@ -34,10 +40,15 @@ impl ValidatorNodeProvider for NodeProvider {
         let address = address.to_lowercase();
 
         #[cfg(with_simple_network)]
-        if address.starts_with("tcp") || address.starts_with("udp") {
+        if address.starts_with("tcp") || address.starts_with("udp") || address.starts_with("quic") {
             return Ok(Client::Simple(self.simple.make_node(&address)?));
         }
 
+        #[cfg(with_websocket)]
+        if address.starts_with("ws") {
+            return Ok(Client::WebSocket(self.ws.make_node(&address)?));
+        }
+
         if address.starts_with("grpc") {
             return Ok(Client::Grpc(self.grpc.make_node(&address)?));
         }
@@ -0,0 +1,336 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Live routing of chains to the shards of a validator.
+//!
+//! A [`ShardRouter`] starts from the [`ShardAssignment`] of the configuration and can be
+//! updated while the validator is running. Requests register themselves with
+//! [`ShardRouter::route`] for as long as they use the chain. A migration with
+//! [`ShardRouter::reassign`] holds back new requests for the chain, waits for the
+//! registered ones to finish, runs the hand-off and then releases the held requests to
+//! the new shard.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use linera_base::identifiers::ChainId;
+use tokio::sync::Notify;
+
+use crate::config::{ShardAssignment, ShardId, ValidatorInternalNetworkPreConfig};
+
+/// The current assignment of chains to shards, together with the requests in progress.
+#[derive(Clone, Debug)]
+pub struct ShardRouter {
+    state: Arc<Mutex<RouterState>>,
+    changed: Arc<Notify>,
+    num_shards: usize,
+}
+
+#[derive(Debug)]
+struct RouterState {
+    assignment: ShardAssignment,
+    in_flight: BTreeMap<ChainId, usize>,
+    migrating: BTreeSet<ChainId>,
+}
+
+/// A request in progress for a chain. The chain is not migrated until this is dropped.
+pub struct ShardRoute {
+    router: ShardRouter,
+    chain_id: ChainId,
+    shard_id: ShardId,
+}
+
+impl ShardRouter {
+    /// Creates a router with the assignment of the given configuration.
+    pub fn new<P>(network: &ValidatorInternalNetworkPreConfig<P>) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(RouterState {
+                assignment: network.shard_assignment.clone(),
+                in_flight: BTreeMap::new(),
+                migrating: BTreeSet::new(),
+            })),
+            changed: Arc::new(Notify::new()),
+            num_shards: network.shards.len(),
+        }
+    }
+
+    /// Returns the shard currently assigned to a chain.
+    pub fn shard_id(&self, chain_id: ChainId) -> ShardId {
+        let state = self.state.lock().unwrap();
+        state.assignment.shard_id(chain_id, self.num_shards)
+    }
+
+    /// Registers a request for a chain and returns the shard that must handle it. Waits
+    /// if the chain is being migrated.
+    pub async fn route(&self, chain_id: ChainId) -> ShardRoute {
+        let mut routes = self.route_all(&[chain_id]).await;
+        routes.pop().expect("one route per chain")
+    }
+
+    /// Registers a request for several chains at once and returns their routes, in the
+    /// same order. Waits until none of the chains is being migrated: a request never holds
+    /// back the migration of some chains while waiting for others.
+    pub async fn route_all(&self, chain_ids: &[ChainId]) -> Vec<ShardRoute> {
+        loop {
+            let changed = self.changed.notified();
+            {
+                let mut state = self.state.lock().unwrap();
+                if chain_ids
+                    .iter()
+                    .all(|chain_id| !state.migrating.contains(chain_id))
+                {
+                    return chain_ids
+                        .iter()
+                        .map(|&chain_id| {
+                            *state.in_flight.entry(chain_id).or_default() += 1;
+                            let shard_id = state.assignment.shard_id(chain_id, self.num_shards);
+                            ShardRoute {
+                                router: self.clone(),
+                                chain_id,
+                                shard_id,
+                            }
+                        })
+                        .collect();
+                }
+            }
+            changed.await;
+        }
+    }
+
+    /// Assigns a chain to a shard, without waiting for requests in progress.
+    pub fn set_shard(&self, chain_id: ChainId, shard_id: ShardId) {
+        assert!(shard_id < self.num_shards, "invalid shard ID {shard_id}");
+        let mut state = self.state.lock().unwrap();
+        state.assignment.overrides.insert(chain_id, shard_id);
+    }
+
+    /// Migrates a chain to another shard: holds back new requests for the chain, waits
+    /// for the ones in progress, runs `hand_off` and, if it succeeds, assigns the chain
+    /// to `shard_id`. Held requests are then routed according to the (new) assignment.
+    pub async fn reassign<T, E>(
+        &self,
+        chain_id: ChainId,
+        shard_id: ShardId,
+        hand_off: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, E> {
+        assert!(shard_id < self.num_shards, "invalid shard ID {shard_id}");
+        loop {
+            let changed = self.changed.notified();
+            {
+                let mut state = self.state.lock().unwrap();
+                if !state.migrating.contains(&chain_id) {
+                    state.migrating.insert(chain_id);
+                    break;
+                }
+            }
+            changed.await;
+        }
+        let _migration = Migration {
+            router: self,
+            chain_id,
+        };
+        loop {
+            let changed = self.changed.notified();
+            if !self.state.lock().unwrap().in_flight.contains_key(&chain_id) {
+                break;
+            }
+            changed.await;
+        }
+        let value = hand_off.await?;
+        self.set_shard(chain_id, shard_id);
+        Ok(value)
+    }
+}
+
+/// Ends a migration when dropped, including when the hand-off fails or is cancelled.
+struct Migration<'a> {
+    router: &'a ShardRouter,
+    chain_id: ChainId,
+}
+
+impl Drop for Migration<'_> {
+    fn drop(&mut self) {
+        let mut state = self.router.state.lock().unwrap();
+        state.migrating.remove(&self.chain_id);
+        self.router.changed.notify_waiters();
+    }
+}
+
+impl ShardRoute {
+    /// The shard that must handle the request.
+    pub fn shard_id(&self) -> ShardId {
+        self.shard_id
+    }
+}
+
+impl Drop for ShardRoute {
+    fn drop(&mut self) {
+        let mut state = self.router.state.lock().unwrap();
+        let count = state
+            .in_flight
+            .get_mut(&self.chain_id)
+            .expect("routed chains are counted");
+        *count -= 1;
+        if *count == 0 {
+            state.in_flight.remove(&self.chain_id);
+            if state.migrating.contains(&self.chain_id) {
+                self.router.changed.notify_waiters();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use linera_base::{crypto::CryptoHash, identifiers::ChainId};
+
+    use super::ShardRouter;
+    use crate::config::{
+        NetworkProtocol, ShardConfig, ShardingStrategy, ValidatorInternalNetworkConfig,
+    };
+
+    fn network(num_shards: u16, strategy: ShardingStrategy) -> ValidatorInternalNetworkConfig {
+        let shards = (0..num_shards)
+            .map(|i| ShardConfig {
+                host: "localhost".into(),
+                port: 9100 + i,
+                metrics_host: "localhost".into(),
+                metrics_port: None,
+            })
+            .collect();
+        let mut network = ValidatorInternalNetworkConfig {
+            protocol: NetworkProtocol::Grpc(crate::config::TlsConfig::ClearText),
+            shards,
+            shard_assignment: Default::default(),
+            tls: None,
+            host: "localhost".into(),
+            port: 9000,
+            metrics_host: "localhost".into(),
+            metrics_port: 9090,
+        };
+        network.shard_assignment.strategy = strategy;
+        network
+    }
+
+    fn chain_ids() -> Vec<ChainId> {
+        (0..1000u32)
+            .map(|i| ChainId(CryptoHash::test_hash(format!("chain {i}"))))
+            .collect()
+    }
+
+    #[test]
+    fn test_rendezvous_only_moves_chains_to_the_new_shard() {
+        let before = network(4, ShardingStrategy::Rendezvous);
+        let after = network(5, ShardingStrategy::Rendezvous);
+        let mut moved = 0;
+        for chain_id in chain_ids() {
+            let old_shard = before.get_shard_id(chain_id);
+            let new_shard = after.get_shard_id(chain_id);
+            if old_shard != new_shard {
+                assert_eq!(new_shard, 4);
+                moved += 1;
+            }
+        }
+        // About a fifth of the chains should move to the new shard.
+        assert!((100..300).contains(&moved), "{moved} chains moved");
+    }
+
+    #[test]
+    fn test_overrides_take_precedence() {
+        let mut network = network(4, ShardingStrategy::Modulo);
+        let chain_id = chain_ids()[0];
+        let target = (network.get_shard_id(chain_id) + 1) % 4;
+        network.shard_assignment.overrides.insert(chain_id, target);
+        assert_eq!(network.get_shard_id(chain_id), target);
+        assert_eq!(ShardRouter::new(&network).shard_id(chain_id), target);
+    }
+
+    #[tokio::test]
+    async fn test_reassign_waits_for_requests_in_progress() {
+        let router = ShardRouter::new(&network(2, ShardingStrategy::Modulo));
+        let chain_id = chain_ids()[0];
+        let target = 1 - router.shard_id(chain_id);
+
+        let route = router.route(chain_id).await;
+        let migration = tokio::spawn({
+            let router = router.clone();
+            async move {
+                router
+                    .reassign(chain_id, target, async { Ok::<_, ()>(()) })
+                    .await
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!migration.is_finished());
+        assert_ne!(router.shard_id(chain_id), target);
+
+        // Requests arriving during the migration are held back and then sent to the new
+        // shard.
+        let held = tokio::spawn({
+            let router = router.clone();
+            async move { router.route(chain_id).await.shard_id() }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!held.is_finished());
+
+        drop(route);
+        migration.await.unwrap().unwrap();
+        assert_eq!(held.await.unwrap(), target);
+        assert_eq!(router.shard_id(chain_id), target);
+    }
+
+    #[tokio::test]
+    async fn test_route_all_waits_for_every_migration() {
+        let router = ShardRouter::new(&network(2, ShardingStrategy::Modulo));
+        let chains = chain_ids();
+        let (migrated, other) = (chains[0], chains[1]);
+        let target = 1 - router.shard_id(migrated);
+
+        let route = router.route(migrated).await;
+        let migration = tokio::spawn({
+            let router = router.clone();
+            async move {
+                router
+                    .reassign(migrated, target, async { Ok::<_, ()>(()) })
+                    .await
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // The other chain is not registered while the batch is held back.
+        let held = tokio::spawn({
+            let router = router.clone();
+            async move {
+                let routes = router.route_all(&[other, migrated]).await;
+                routes
+                    .iter()
+                    .map(|route| route.shard_id())
+                    .collect::<Vec<_>>()
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!held.is_finished());
+        assert!(!router.state.lock().unwrap().in_flight.contains_key(&other));
+
+        drop(route);
+        migration.await.unwrap().unwrap();
+        assert_eq!(held.await.unwrap(), vec![router.shard_id(other), target]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_hand_off_keeps_the_assignment() {
+        let router = ShardRouter::new(&network(2, ShardingStrategy::Modulo));
+        let chain_id = chain_ids()[0];
+        let source = router.shard_id(chain_id);
+        let result = router
+            .reassign(chain_id, 1 - source, async { Err(()) })
+            .await;
+        assert_eq!(result, Err::<(), _>(()));
+        assert_eq!(router.route(chain_id).await.shard_id(), source);
+    }
+}
@@ -17,19 +17,25 @@ use linera_chain::data_types::{
 };
 use linera_core::{
     data_types::{ChainInfoQuery, ChainInfoResponse},
-    node::{CrossChainMessageDelivery, NodeError, NotificationStream, ValidatorNode},
+    gossip::CertificateAnnouncement,
+    node::{BatchItem, CrossChainMessageDelivery, NodeError, NotificationStream, ValidatorNode},
+    notifier::NotificationFilter,
 };
 use linera_version::VersionInfo;
 
-use super::{codec, transport::TransportProtocol};
+use super::{
+    codec,
+    transport::{Connector, TransportProtocol},
+};
 use crate::{
-    config::ValidatorPublicNetworkPreConfig, mass_client, HandleCertificateRequest,
-    HandleLiteCertRequest, RpcMessage,
+    config::ValidatorPublicNetworkPreConfig, mass_client, HandleBatchRequest,
+    HandleCertificateRequest, HandleLiteCertRequest, RpcMessage,
 };
 
 #[derive(Clone)]
 pub struct SimpleClient {
     network: ValidatorPublicNetworkPreConfig<TransportProtocol>,
+    connector: Connector,
     send_timeout: Duration,
     recv_timeout: Duration,
 }
@@ -41,6 +47,7 @@ impl SimpleClient {
         recv_timeout: Duration,
     ) -> Self {
         Self {
+            connector: Connector::new(network.protocol),
             network,
             send_timeout,
             recv_timeout,
@@ -49,7 +56,7 @@ impl SimpleClient {
 
     async fn send_recv_internal(&self, message: RpcMessage) -> Result<RpcMessage, codec::Error> {
         let address = format!("{}:{}", self.network.host, self.network.port);
-        let mut stream = self.network.protocol.connect(address).await?;
+        let mut stream = self.connector.connect(address).await?;
         // Send message
         timer::timeout(self.send_timeout, stream.send(message))
             .await
@@ -112,6 +119,20 @@ impl ValidatorNode for SimpleClient {
         self.query(request.into()).await
     }
 
+    /// Processes a batch of block proposals and certificates.
+    async fn handle_batch(
+        &self,
+        items: Vec<BatchItem>,
+        delivery: CrossChainMessageDelivery,
+    ) -> Result<Vec<Result<ChainInfoResponse, NodeError>>, NodeError> {
+        let wait_for_outgoing_messages = delivery.wait_for_outgoing_messages();
+        let request = HandleBatchRequest {
+            items,
+            wait_for_outgoing_messages,
+        };
+        self.query(request.into()).await
+    }
+
     /// Handles information queries for this chain.
     async fn handle_chain_info_query(
         &self,
@@ -123,6 +144,7 @@ impl ValidatorNode for SimpleClient {
     fn subscribe(
         &self,
         _chains: Vec<ChainId>,
+        _filter: NotificationFilter,
     ) -> impl Future<Output = Result<NotificationStream, NodeError>> + Send {
         let transport = self.network.protocol.to_string();
         async { Err(NodeError::SubscriptionError { transport }) }
@@ -168,11 +190,20 @@ impl ValidatorNode for SimpleClient {
         self.query(RpcMessage::BlobLastUsedBy(Box::new(blob_id)))
             .await
     }
+
+    fn handle_certificate_announcement(
+        &self,
+        _announcement: CertificateAnnouncement,
+    ) -> impl Future<Output = Result<(), NodeError>> + Send {
+        let transport = self.network.protocol.to_string();
+        async { Err(NodeError::GossipNotSupported { transport }) }
+    }
 }
 
 #[derive(Clone)]
 pub struct SimpleMassClient {
     pub network: ValidatorPublicNetworkPreConfig<TransportProtocol>,
+    connector: Connector,
     send_timeout: Duration,
     recv_timeout: Duration,
 }
@@ -184,6 +215,7 @@ impl SimpleMassClient {
         recv_timeout: Duration,
     ) -> Self {
         Self {
+            connector: Connector::new(network.protocol),
             network,
             send_timeout,
             recv_timeout,
@@ -199,7 +231,7 @@ impl mass_client::MassClient for SimpleMassClient {
         max_in_flight: usize,
     ) -> Result<Vec<RpcMessage>, mass_client::MassClientError> {
         let address = format!("{}:{}", self.network.host, self.network.port);
-        let mut stream = self.network.protocol.connect(address).await?;
+        let mut stream = self.connector.connect(address).await?;
         let mut requests = requests.into_iter();
         let mut in_flight = 0;
         let mut responses = Vec::new();
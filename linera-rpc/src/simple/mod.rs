@@ -4,6 +4,7 @@
 mod client;
 mod codec;
 mod node_provider;
+mod quic;
 #[cfg(with_server)]
 mod server;
 mod transport;
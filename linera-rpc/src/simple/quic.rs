@@ -0,0 +1,430 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! QUIC support for the simple network protocol.
+//!
+//! Clients keep one connection per server and send each request on a new bidirectional
+//! stream, so that slow requests do not hold back the others. Like UDP and TCP, QUIC
+//! does not authenticate the validators: servers use an ephemeral self-signed certificate
+//! that clients accept without verification, and the messages themselves are signed.
+
+use std::{collections::HashMap, io, net::SocketAddr, sync::Arc};
+
+use futures::{SinkExt, StreamExt};
+use linera_core::JoinSetExt as _;
+use quinn::{
+    crypto::rustls::{QuicClientConfig, QuicServerConfig},
+    rustls::{
+        self,
+        client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+        crypto::{ring, verify_tls12_signature, verify_tls13_signature, CryptoProvider},
+        pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, ServerName, UnixTime},
+        DigitallySignedStruct, SignatureScheme,
+    },
+    ClientConfig, Connection, ConnectionError, Endpoint, Incoming, RecvStream, SendStream,
+    ServerConfig, TransportConfig, VarInt,
+};
+use tokio::{
+    io::Join,
+    net::{lookup_host, ToSocketAddrs},
+    sync::Mutex,
+    task::JoinSet,
+};
+use tokio_util::{codec::Framed, sync::CancellationToken};
+use tracing::{error, warn};
+
+use super::{
+    codec::{self, Codec},
+    transport::{MessageHandler, REAP_TASKS_THRESHOLD},
+};
+
+/// The ALPN protocol identifier negotiated by clients and servers.
+const ALPN_PROTOCOL: &[u8] = b"linera-simple";
+
+/// The name of the servers in their self-signed certificates.
+const SERVER_NAME: &str = "localhost";
+
+/// The number of requests a client may send at the same time over a connection. The proxy
+/// uses a single connection to each shard for all its clients.
+const MAX_CONCURRENT_STREAMS: u32 = 1024;
+
+/// A single request stream, carrying [`RpcMessage`](crate::RpcMessage)s in both directions.
+pub(super) type QuicStream = Framed<Join<RecvStream, SendStream>, Codec>;
+
+/// Opens request streams to QUIC servers, reusing one connection per server.
+///
+/// Cloned instances share their connections.
+#[derive(Clone, Debug, Default)]
+pub(super) struct QuicConnector {
+    state: Arc<Mutex<ConnectorState>>,
+}
+
+#[derive(Debug, Default)]
+struct ConnectorState {
+    endpoint: Option<Endpoint>,
+    /// The connection to each server. Each one has its own lock, held while connecting, so
+    /// that a slow handshake only holds back the requests to the same server.
+    connections: HashMap<SocketAddr, Arc<Mutex<Option<Connection>>>>,
+}
+
+impl QuicConnector {
+    /// Opens a new stream to the server at `address`.
+    pub(super) async fn open_stream(&self, address: SocketAddr) -> Result<QuicStream, io::Error> {
+        let connection = self.connection(address).await?;
+        let (send, recv) = match connection.open_bi().await {
+            Ok(stream) => stream,
+            Err(_) => {
+                // The cached connection was closed in the meantime, e.g. after being idle.
+                self.forget(address, &connection).await;
+                self.connection(address)
+                    .await?
+                    .open_bi()
+                    .await
+                    .map_err(io::Error::from)?
+            }
+        };
+        Ok(Framed::new(tokio::io::join(recv, send), Codec))
+    }
+
+    /// Returns the open connection to `address`, connecting first if needed.
+    async fn connection(&self, address: SocketAddr) -> Result<Connection, io::Error> {
+        let (endpoint, cell) = {
+            let mut state = self.state.lock().await;
+            let endpoint = match &state.endpoint {
+                Some(endpoint) => endpoint.clone(),
+                None => state.endpoint.insert(client_endpoint()?).clone(),
+            };
+            let cell = state.connections.entry(address).or_default().clone();
+            (endpoint, cell)
+        };
+        let mut cached = cell.lock().await;
+        if let Some(connection) = &*cached {
+            if connection.close_reason().is_none() {
+                return Ok(connection.clone());
+            }
+        }
+        let connection = endpoint
+            .connect(address, SERVER_NAME)
+            .map_err(io::Error::other)?
+            .await
+            .map_err(|error| {
+                error!("Failed to open connection to {address}: {error}");
+                io::Error::from(error)
+            })?;
+        *cached = Some(connection.clone());
+        Ok(connection)
+    }
+
+    /// Drops the cached connection to `address` if it is still `connection`.
+    async fn forget(&self, address: SocketAddr, connection: &Connection) {
+        let Some(cell) = self.state.lock().await.connections.get(&address).cloned() else {
+            return;
+        };
+        let mut cached = cell.lock().await;
+        if cached
+            .as_ref()
+            .is_some_and(|cached| cached.stable_id() == connection.stable_id())
+        {
+            *cached = None;
+        }
+    }
+}
+
+/// Server implementation for QUIC.
+pub struct QuicServer<State> {
+    connection: Connection,
+    handler: State,
+    shutdown_signal: CancellationToken,
+}
+
+impl<State> QuicServer<State>
+where
+    State: MessageHandler + Send + 'static,
+{
+    /// Runs the QUIC server implementation.
+    ///
+    /// Accepts connections and spawns a task with a new [`QuicServer`] instance to serve
+    /// each client.
+    pub async fn run(
+        address: impl ToSocketAddrs,
+        handler: State,
+        shutdown_signal: CancellationToken,
+    ) -> Result<(), io::Error> {
+        let endpoint = Self::bind(address).await?;
+        Self::accept_connections(endpoint, handler, shutdown_signal).await
+    }
+
+    /// Creates the endpoint of the server, ready to receive connections.
+    async fn bind(address: impl ToSocketAddrs) -> Result<Endpoint, io::Error> {
+        let address = lookup_host(address)
+            .await?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::AddrNotAvailable, "no address"))?;
+        Endpoint::server(server_config()?, address)
+    }
+
+    /// Accepts connections on `endpoint` until shutdown.
+    async fn accept_connections(
+        endpoint: Endpoint,
+        handler: State,
+        shutdown_signal: CancellationToken,
+    ) -> Result<(), io::Error> {
+        let connection_shutdown_signal = shutdown_signal.child_token();
+        let mut join_set = JoinSet::new();
+        let mut reap_countdown = REAP_TASKS_THRESHOLD;
+
+        loop {
+            tokio::select! { biased;
+                _ = shutdown_signal.cancelled() => {
+                    join_set.await_all_tasks().await;
+                    return Ok(());
+                }
+                maybe_incoming = endpoint.accept() => match maybe_incoming {
+                    Some(incoming) => {
+                        join_set.spawn_task(Self::serve(
+                            incoming,
+                            handler.clone(),
+                            connection_shutdown_signal.clone(),
+                        ));
+                        reap_countdown -= 1;
+                    }
+                    None => {
+                        join_set.await_all_tasks().await;
+                        return Ok(());
+                    }
+                },
+            }
+
+            if reap_countdown == 0 {
+                join_set.reap_finished_tasks();
+                reap_countdown = REAP_TASKS_THRESHOLD;
+            }
+        }
+    }
+
+    /// Completes the handshake of an incoming connection and serves it.
+    async fn serve(incoming: Incoming, handler: State, shutdown_signal: CancellationToken) {
+        match incoming.await {
+            Ok(connection) => {
+                let server = QuicServer {
+                    connection,
+                    handler,
+                    shutdown_signal,
+                };
+                server.serve_connection().await;
+            }
+            Err(error) => warn!("Failed to accept QUIC connection: {error}"),
+        }
+    }
+
+    /// Serves a client through a single connection, handling each stream in its own task.
+    async fn serve_connection(self) {
+        let mut streams = JoinSet::new();
+        let mut reap_countdown = REAP_TASKS_THRESHOLD;
+
+        loop {
+            tokio::select! { biased;
+                _ = self.shutdown_signal.cancelled() => {
+                    self.connection.close(VarInt::from_u32(0), b"server shutdown");
+                    break;
+                }
+                result = self.connection.accept_bi() => match result {
+                    Ok((send, recv)) => {
+                        let stream = Framed::new(tokio::io::join(recv, send), Codec);
+                        streams.spawn_task(Self::serve_stream(stream, self.handler.clone()));
+                        reap_countdown -= 1;
+                    }
+                    Err(
+                        ConnectionError::ApplicationClosed(_)
+                        | ConnectionError::LocallyClosed
+                        | ConnectionError::TimedOut,
+                    ) => break,
+                    Err(error) => {
+                        error!("Error while accepting QUIC stream: {error}");
+                        break;
+                    }
+                },
+            }
+
+            if reap_countdown == 0 {
+                streams.reap_finished_tasks();
+                reap_countdown = REAP_TASKS_THRESHOLD;
+            }
+        }
+
+        streams.await_all_tasks().await;
+    }
+
+    /// Handles the requests received on a single stream.
+    async fn serve_stream(mut stream: QuicStream, mut handler: State) {
+        while let Some(result) = stream.next().await {
+            match result {
+                Ok(message) => {
+                    if let Some(reply) = handler.handle_message(message).await {
+                        if let Err(error) = stream.send(reply).await {
+                            error!("Failed to send query response: {error}");
+                            return;
+                        }
+                    }
+                }
+                Err(error) => {
+                    if !matches!(
+                        &error,
+                        codec::Error::Io(error)
+                            if error.kind() == io::ErrorKind::UnexpectedEof
+                            || error.kind() == io::ErrorKind::ConnectionReset
+                            || error.kind() == io::ErrorKind::NotConnected
+                    ) {
+                        error!("Error while reading QUIC stream: {error}");
+                    }
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Creates the endpoint used to connect to servers.
+fn client_endpoint() -> Result<Endpoint, io::Error> {
+    let provider = Arc::new(ring::default_provider());
+    let mut crypto = rustls::ClientConfig::builder_with_provider(provider.clone())
+        .with_protocol_versions(&[&rustls::version::TLS13])
+        .map_err(io::Error::other)?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(AnyServerCertificate(provider)))
+        .with_no_client_auth();
+    crypto.alpn_protocols = vec![ALPN_PROTOCOL.to_vec()];
+    let crypto = QuicClientConfig::try_from(crypto).map_err(io::Error::other)?;
+
+    let mut endpoint = Endpoint::client(SocketAddr::from(([0, 0, 0, 0], 0)))?;
+    endpoint.set_default_client_config(ClientConfig::new(Arc::new(crypto)));
+    Ok(endpoint)
+}
+
+/// Creates the configuration of a server, with a new self-signed certificate.
+fn server_config() -> Result<ServerConfig, io::Error> {
+    let certificate = rcgen::generate_simple_self_signed(vec![SERVER_NAME.to_owned()])
+        .map_err(io::Error::other)?;
+    let certificate_der =
+        CertificateDer::from(certificate.serialize_der().map_err(io::Error::other)?);
+    let private_key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(
+        certificate.serialize_private_key_der(),
+    ));
+
+    let mut crypto =
+        rustls::ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_protocol_versions(&[&rustls::version::TLS13])
+            .map_err(io::Error::other)?
+            .with_no_client_auth()
+            .with_single_cert(vec![certificate_der], private_key)
+            .map_err(io::Error::other)?;
+    crypto.alpn_protocols = vec![ALPN_PROTOCOL.to_vec()];
+    let crypto = QuicServerConfig::try_from(crypto).map_err(io::Error::other)?;
+    let mut transport = TransportConfig::default();
+    transport.max_concurrent_bidi_streams(VarInt::from_u32(MAX_CONCURRENT_STREAMS));
+    let mut config = ServerConfig::with_crypto(Arc::new(crypto));
+    config.transport_config(Arc::new(transport));
+    Ok(config)
+}
+
+/// Accepts any server certificate, while still checking the handshake signatures.
+#[derive(Debug)]
+struct AnyServerCertificate(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AnyServerCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        certificate: &CertificateDer<'_>,
+        signature: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            certificate,
+            signature,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        certificate: &CertificateDer<'_>,
+        signature: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            certificate,
+            signature,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use futures::{SinkExt as _, StreamExt as _};
+    use linera_base::port::get_free_endpoint;
+    use tokio_util::sync::CancellationToken;
+
+    use super::{QuicConnector, QuicServer};
+    use crate::{simple::MessageHandler, RpcMessage};
+
+    #[derive(Clone)]
+    struct EchoHandler;
+
+    #[async_trait]
+    impl MessageHandler for EchoHandler {
+        async fn handle_message(&mut self, message: RpcMessage) -> Option<RpcMessage> {
+            Some(message)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_requests_share_a_connection() {
+        let address = get_free_endpoint().await.unwrap();
+        let shutdown_signal = CancellationToken::new();
+        // The server accepts connections as soon as its endpoint exists.
+        let endpoint = QuicServer::<EchoHandler>::bind(address.clone())
+            .await
+            .unwrap();
+        let server = tokio::spawn(QuicServer::accept_connections(
+            endpoint,
+            EchoHandler,
+            shutdown_signal.clone(),
+        ));
+
+        let connector = QuicConnector::default();
+        let address = address.parse().unwrap();
+        let mut streams = Vec::new();
+        for _ in 0..3 {
+            streams.push(connector.open_stream(address).await.unwrap());
+        }
+        // Streams are independent: they can be used in any order.
+        for stream in streams.iter_mut().rev() {
+            stream.send(RpcMessage::VersionInfoQuery).await.unwrap();
+            let reply = stream.next().await.unwrap().unwrap();
+            assert!(matches!(reply, RpcMessage::VersionInfoQuery));
+        }
+        assert_eq!(connector.state.lock().await.connections.len(), 1);
+
+        shutdown_signal.cancel();
+        server.await.unwrap().unwrap();
+    }
+}
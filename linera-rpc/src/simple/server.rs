@@ -2,10 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use async_trait::async_trait;
-use futures::{channel::mpsc, stream::StreamExt};
+use futures::{channel::mpsc, future, stream::StreamExt};
 use linera_base::time::Duration;
 use linera_core::{
-    node::NodeError,
+    node::{BatchItem, NodeError},
     worker::{NetworkActions, WorkerError, WorkerState},
     JoinSetExt as _,
 };
@@ -18,6 +18,7 @@ use tracing::{debug, error, info, instrument, warn};
 use super::transport::{MessageHandler, ServerHandle, TransportProtocol};
 use crate::{
     config::{CrossChainConfig, ShardId, ValidatorInternalNetworkPreConfig},
+    sharding::ShardRouter,
     RpcMessage,
 };
 
@@ -27,6 +28,7 @@ where
     S: Storage,
 {
     network: ValidatorInternalNetworkPreConfig<TransportProtocol>,
+    router: ShardRouter,
     host: String,
     port: u16,
     state: WorkerState<S>,
@@ -49,8 +51,10 @@ where
         shard_id: ShardId,
         cross_chain_config: CrossChainConfig,
     ) -> Self {
+        let router = ShardRouter::new(&network);
         Self {
             network,
+            router,
             host,
             port,
             state,
@@ -267,6 +271,53 @@ where
                     }
                 }
             }
+            RpcMessage::Batch(request) => {
+                let wait_for_outgoing_messages = request.wait_for_outgoing_messages;
+                let state = &self.server.state;
+                let shard_id = self.server.shard_id;
+                let router = &self.server.router;
+                let outcomes = future::join_all(request.items.into_iter().map(|item| async move {
+                    // Chains handled by another shard are rejected individually.
+                    let chain_id = item.chain_id();
+                    let owner = router.shard_id(chain_id);
+                    if owner != shard_id {
+                        let error = NodeError::WorkerError {
+                            error: format!("chain {chain_id} is handled by shard {owner}"),
+                        };
+                        return (Err(error), None);
+                    }
+                    let (sender, receiver) = (wait_for_outgoing_messages
+                        && matches!(item, BatchItem::Certificate { .. }))
+                    .then(oneshot::channel)
+                    .unzip();
+                    let result = state.handle_batch_item(item, sender).await;
+                    (result.map_err(NodeError::from), receiver)
+                }))
+                .await;
+                let mut results = Vec::with_capacity(outcomes.len());
+                let mut receivers = Vec::new();
+                for (result, receiver) in outcomes {
+                    match result {
+                        Ok((info, actions)) => {
+                            // Cross-shard requests
+                            self.handle_network_actions(actions);
+                            receivers.extend(receiver);
+                            results.push(Ok(info));
+                        }
+                        Err(error) => {
+                            warn!(nickname = self.server.state.nickname(), %error, "Failed to handle batch item");
+                            results.push(Err(error));
+                        }
+                    }
+                }
+                for receiver in receivers {
+                    if let Err(e) = receiver.await {
+                        error!("Failed to wait for message delivery: {e}");
+                    }
+                }
+                // Response
+                Ok(Some(results.into()))
+            }
             RpcMessage::ChainInfoQuery(message) => {
                 match self.server.state.handle_chain_info_query(*message).await {
                     Ok((info, actions)) => {
@@ -299,6 +350,7 @@ where
             RpcMessage::Vote(_)
             | RpcMessage::Error(_)
             | RpcMessage::ChainInfoResponse(_)
+            | RpcMessage::BatchResponse(_)
             | RpcMessage::VersionInfoResponse(_)
             | RpcMessage::GenesisConfigHashQuery
             | RpcMessage::GenesisConfigHashResponse(_)
@@ -311,7 +363,9 @@ where
             | RpcMessage::DownloadCertificate(_)
             | RpcMessage::DownloadCertificates(_)
             | RpcMessage::DownloadCertificateResponse(_)
-            | RpcMessage::DownloadCertificatesResponse(_) => Err(NodeError::UnexpectedMessage),
+            | RpcMessage::DownloadCertificatesResponse(_)
+            | RpcMessage::Subscribe(_)
+            | RpcMessage::Notification(_) => Err(NodeError::UnexpectedMessage),
         };
 
         self.server.packets_processed += 1;
@@ -348,7 +402,7 @@ where
 {
     fn handle_network_actions(&mut self, actions: NetworkActions) {
         for request in actions.cross_chain_requests {
-            let shard_id = self.server.network.get_shard_id(request.target_chain_id());
+            let shard_id = self.server.router.shard_id(request.target_chain_id());
             debug!(
                 "[{}] Scheduling cross-chain query: {} -> {}",
                 self.server.state.nickname(),
@@ -21,6 +21,8 @@ use tokio::{
 use tokio_util::{codec::Framed, sync::CancellationToken, udp::UdpFramed};
 use tracing::{error, warn};
 
+use super::quic::QuicConnector;
+pub use super::quic::QuicServer;
 use crate::{
     simple::{codec, codec::Codec},
     RpcMessage,
@@ -30,13 +32,14 @@ use crate::{
 pub const DEFAULT_MAX_DATAGRAM_SIZE: &str = "65507";
 
 /// Number of tasks to spawn before attempting to reap some finished tasks to prevent memory leaks.
-const REAP_TASKS_THRESHOLD: usize = 100;
+pub(super) const REAP_TASKS_THRESHOLD: usize = 100;
 
 // Supported transport protocols.
 #[derive(clap::ValueEnum, Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub enum TransportProtocol {
     Udp,
     Tcp,
+    Quic,
 }
 
 impl std::str::FromStr for TransportProtocol {
@@ -58,6 +61,7 @@ impl TransportProtocol {
         match self {
             TransportProtocol::Udp => "udp",
             TransportProtocol::Tcp => "tcp",
+            TransportProtocol::Quic => "quic",
         }
     }
 }
@@ -114,34 +118,13 @@ impl<T> Transport for T where
 
 impl TransportProtocol {
     /// Creates a transport for this protocol.
+    ///
+    /// Use a [`Connector`] instead to reuse QUIC connections across requests.
     pub async fn connect(
         self,
         address: impl ToSocketAddrs,
     ) -> Result<impl Transport, std::io::Error> {
-        let mut addresses = lookup_host(address)
-            .await
-            .expect("Invalid address to connect to");
-        let address = addresses
-            .next()
-            .expect("Couldn't resolve address to connect to");
-
-        let stream: futures::future::Either<_, _> = match self {
-            TransportProtocol::Udp => {
-                let socket = UdpSocket::bind(&"0.0.0.0:0").await?;
-
-                UdpFramed::new(socket, Codec)
-                    .with(move |message| future::ready(Ok((message, address))))
-                    .map_ok(|(message, _address)| message)
-                    .left_stream()
-            }
-            TransportProtocol::Tcp => {
-                let stream = TcpStream::connect(address).await?;
-
-                Framed::new(stream, Codec).right_stream()
-            }
-        };
-
-        Ok(stream)
+        Connector::new(self).connect(address).await
     }
 
     /// Creates a [`ConnectionPool`] for this protocol.
@@ -151,6 +134,7 @@ impl TransportProtocol {
         let pool: Box<dyn ConnectionPool> = match self {
             Self::Udp => Box::new(UdpConnectionPool::new().await?),
             Self::Tcp => Box::new(TcpConnectionPool::new().await?),
+            Self::Quic => Box::new(QuicConnectionPool::default()),
         };
         Ok(pool)
     }
@@ -169,11 +153,70 @@ impl TransportProtocol {
         let handle = match self {
             Self::Udp => join_set.spawn_task(UdpServer::run(address, state, shutdown_signal)),
             Self::Tcp => join_set.spawn_task(TcpServer::run(address, state, shutdown_signal)),
+            Self::Quic => join_set.spawn_task(QuicServer::run(address, state, shutdown_signal)),
         };
         ServerHandle { handle }
     }
 }
 
+/// Creates [`Transport`]s to servers using a [`TransportProtocol`].
+///
+/// With QUIC, a connector keeps one connection per server, shared with its clones, and
+/// each transport is a new stream on that connection.
+#[derive(Clone, Debug)]
+pub struct Connector {
+    protocol: TransportProtocol,
+    quic: QuicConnector,
+}
+
+impl Connector {
+    /// Creates a connector for the given protocol.
+    pub fn new(protocol: TransportProtocol) -> Self {
+        Self {
+            protocol,
+            quic: QuicConnector::default(),
+        }
+    }
+
+    /// The protocol used by this connector.
+    pub fn protocol(&self) -> TransportProtocol {
+        self.protocol
+    }
+
+    /// Creates a transport to the given address.
+    pub async fn connect(
+        &self,
+        address: impl ToSocketAddrs,
+    ) -> Result<impl Transport, std::io::Error> {
+        let mut addresses = lookup_host(address)
+            .await
+            .expect("Invalid address to connect to");
+        let address = addresses
+            .next()
+            .expect("Couldn't resolve address to connect to");
+
+        let stream: future::Either<_, _> = match self.protocol {
+            TransportProtocol::Udp => {
+                let socket = UdpSocket::bind(&"0.0.0.0:0").await?;
+
+                UdpFramed::new(socket, Codec)
+                    .with(move |message| future::ready(Ok((message, address))))
+                    .map_ok(|(message, _address)| message)
+                    .left_stream()
+                    .left_stream()
+            }
+            TransportProtocol::Tcp => {
+                let stream = TcpStream::connect(address).await?;
+
+                Framed::new(stream, Codec).right_stream().left_stream()
+            }
+            TransportProtocol::Quic => self.quic.open_stream(address).await?.right_stream(),
+        };
+
+        Ok(stream)
+    }
+}
+
 /// An implementation of [`ConnectionPool`] based on UDP.
 struct UdpConnectionPool {
     transport: UdpFramed<Codec>,
@@ -363,6 +406,29 @@ impl ConnectionPool for TcpConnectionPool {
     }
 }
 
+/// An implementation of [`ConnectionPool`] based on QUIC, sending each message on its own
+/// stream.
+#[derive(Default)]
+struct QuicConnectionPool {
+    connector: QuicConnector,
+}
+
+impl ConnectionPool for QuicConnectionPool {
+    fn send_message_to<'a>(
+        &'a mut self,
+        message: RpcMessage,
+        address: &'a str,
+    ) -> future::BoxFuture<'a, Result<(), codec::Error>> {
+        Box::pin(async move {
+            let address = lookup_host(address).await?.next().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::AddrNotAvailable, "no address to connect to")
+            })?;
+            let mut stream = self.connector.open_stream(address).await?;
+            stream.send(message).await
+        })
+    }
+}
+
 /// Server implementation for TCP.
 pub struct TcpServer<State> {
     connection: Framed<TcpStream, Codec>,
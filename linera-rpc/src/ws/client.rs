@@ -0,0 +1,346 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use futures::stream;
+use linera_base::{
+    crypto::CryptoHash,
+    data_types::{Blob, BlobContent},
+    identifiers::{BlobId, ChainId},
+    time::{timer, Duration},
+};
+use linera_chain::data_types::{
+    BlockProposal, Certificate, CertificateValue, HashedCertificateValue, LiteCertificate,
+};
+use linera_core::{
+    data_types::{ChainInfoQuery, ChainInfoResponse},
+    gossip::CertificateAnnouncement,
+    node::{BatchItem, CrossChainMessageDelivery, NodeError, NotificationStream, ValidatorNode},
+    notifier::NotificationFilter,
+};
+use linera_version::VersionInfo;
+use tracing::{info, warn};
+
+use super::transport::{Connection, WebSocketProtocol};
+use crate::{
+    config::ValidatorPublicNetworkPreConfig, node_provider::NodeOptions, HandleBatchRequest,
+    HandleCertificateRequest, HandleLiteCertRequest, RpcMessage, SubscriptionRequest,
+};
+
+#[derive(Clone)]
+pub struct WebSocketClient {
+    network: ValidatorPublicNetworkPreConfig<WebSocketProtocol>,
+    send_timeout: Duration,
+    recv_timeout: Duration,
+    retry_delay: Duration,
+    max_retries: u32,
+    /// A connection kept open after answering a request, to send the next one. It is
+    /// shared by the clones of the client.
+    idle_connection: Arc<Mutex<Option<Connection>>>,
+}
+
+impl WebSocketClient {
+    pub(crate) fn new(
+        network: ValidatorPublicNetworkPreConfig<WebSocketProtocol>,
+        options: NodeOptions,
+    ) -> Self {
+        Self {
+            network,
+            send_timeout: options.send_timeout,
+            recv_timeout: options.recv_timeout,
+            retry_delay: options.retry_delay,
+            max_retries: options.max_retries,
+            idle_connection: Arc::default(),
+        }
+    }
+
+    fn url(&self) -> String {
+        format!(
+            "{}://{}:{}",
+            self.network.protocol.scheme(),
+            self.network.host,
+            self.network.port
+        )
+    }
+
+    /// Opens a new connection and sends the given message.
+    async fn connect_and_send(&self, message: &RpcMessage) -> Result<Connection, NodeError> {
+        let timeout_error = |_| NodeError::ClientIoError {
+            error: "timed out while sending request".to_string(),
+        };
+        timer::timeout(self.send_timeout, async {
+            let mut connection = Connection::connect(&self.url()).await?;
+            connection.send(message).await?;
+            Ok(connection)
+        })
+        .await
+        .map_err(timeout_error)?
+    }
+
+    /// Sends a request on an open connection and waits for the response.
+    async fn send_recv_on(
+        &self,
+        connection: &mut Connection,
+        message: &RpcMessage,
+    ) -> Result<RpcMessage, NodeError> {
+        timer::timeout(self.send_timeout, connection.send(message))
+            .await
+            .map_err(|_| NodeError::ClientIoError {
+                error: "timed out while sending request".to_string(),
+            })??;
+        timer::timeout(self.recv_timeout, connection.receive())
+            .await
+            .map_err(|_| NodeError::ClientIoError {
+                error: "timed out while waiting for response".to_string(),
+            })??
+            .ok_or_else(|| NodeError::ClientIoError {
+                error: "connection closed before response".to_string(),
+            })
+    }
+
+    /// Keeps a connection for the next request, unless another one is already kept.
+    fn release(&self, connection: Connection) {
+        let mut idle_connection = self.idle_connection.lock().unwrap();
+        if idle_connection.is_none() {
+            *idle_connection = Some(connection);
+        }
+    }
+
+    async fn send_recv_internal(&self, message: RpcMessage) -> Result<RpcMessage, NodeError> {
+        let idle_connection = self.idle_connection.lock().unwrap().take();
+        if let Some(mut connection) = idle_connection {
+            // The validator may have closed the connection since it was last used, maybe
+            // without us noticing. Then the request is sent again on a new connection.
+            if let Ok(response) = self.send_recv_on(&mut connection, &message).await {
+                self.release(connection);
+                return Ok(response);
+            }
+        }
+        let mut connection = timer::timeout(self.send_timeout, Connection::connect(&self.url()))
+            .await
+            .map_err(|_| NodeError::ClientIoError {
+                error: "timed out while connecting".to_string(),
+            })??;
+        let response = self.send_recv_on(&mut connection, &message).await?;
+        self.release(connection);
+        Ok(response)
+    }
+
+    async fn query<Response>(&self, query: RpcMessage) -> Result<Response, NodeError>
+    where
+        Response: TryFrom<RpcMessage, Error = NodeError>,
+    {
+        self.send_recv_internal(query).await?.try_into()
+    }
+}
+
+/// The state of a subscription: the current connection, if any, and the sequence number of
+/// the last notification received for each chain, to resume from after reconnecting.
+struct Subscription {
+    client: WebSocketClient,
+    request: SubscriptionRequest,
+    connection: Option<Connection>,
+    retry_count: u32,
+}
+
+impl Subscription {
+    /// Returns the next notification, reconnecting if the connection was lost. Returns
+    /// `None` if the validator ended the subscription or we ran out of retries.
+    async fn next(mut self) -> Option<(linera_core::worker::Notification, Self)> {
+        loop {
+            let result = match &mut self.connection {
+                Some(connection) => connection.receive().await,
+                None => Ok(None),
+            };
+            match result {
+                Ok(Some(RpcMessage::Notification(notification))) => {
+                    self.request
+                        .resume_from
+                        .entry(notification.chain_id)
+                        .and_modify(|last| *last = (*last).max(notification.sequence_number))
+                        .or_insert(notification.sequence_number);
+                    self.retry_count = 0;
+                    return Some((*notification, self));
+                }
+                Ok(Some(RpcMessage::Error(error))) => {
+                    // E.g. the validator no longer has the notifications we missed.
+                    warn!("Subscription ended by validator: {error}");
+                    return None;
+                }
+                Ok(Some(_)) => {
+                    warn!("Unexpected message in subscription");
+                    return None;
+                }
+                Ok(None) | Err(_) => {
+                    if self.retry_count >= self.client.max_retries {
+                        return None;
+                    }
+                    let delay = self.client.retry_delay.saturating_mul(self.retry_count);
+                    self.retry_count += 1;
+                    timer::sleep(delay).await;
+                    info!("Resubscribing to notifications from {}", self.client.url());
+                    let message = self.request.clone().into();
+                    self.connection = self.client.connect_and_send(&message).await.ok();
+                }
+            }
+        }
+    }
+}
+
+impl ValidatorNode for WebSocketClient {
+    type NotificationStream = NotificationStream;
+
+    /// Initiates a new block.
+    async fn handle_block_proposal(
+        &self,
+        proposal: BlockProposal,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        self.query(proposal.into()).await
+    }
+
+    /// Processes a hash certificate.
+    async fn handle_lite_certificate(
+        &self,
+        certificate: LiteCertificate<'_>,
+        delivery: CrossChainMessageDelivery,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        let wait_for_outgoing_messages = delivery.wait_for_outgoing_messages();
+        let request = HandleLiteCertRequest {
+            certificate: certificate.cloned(),
+            wait_for_outgoing_messages,
+        };
+        self.query(request.into()).await
+    }
+
+    /// Processes a certificate.
+    async fn handle_certificate(
+        &self,
+        certificate: Certificate,
+        blobs: Vec<Blob>,
+        delivery: CrossChainMessageDelivery,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        let wait_for_outgoing_messages = delivery.wait_for_outgoing_messages();
+        let request = HandleCertificateRequest {
+            certificate,
+            blobs,
+            wait_for_outgoing_messages,
+        };
+        self.query(request.into()).await
+    }
+
+    /// Processes a batch of block proposals and certificates.
+    async fn handle_batch(
+        &self,
+        items: Vec<BatchItem>,
+        delivery: CrossChainMessageDelivery,
+    ) -> Result<Vec<Result<ChainInfoResponse, NodeError>>, NodeError> {
+        let wait_for_outgoing_messages = delivery.wait_for_outgoing_messages();
+        let request = HandleBatchRequest {
+            items,
+            wait_for_outgoing_messages,
+        };
+        self.query(request.into()).await
+    }
+
+    /// Handles information queries for this chain.
+    async fn handle_chain_info_query(
+        &self,
+        query: ChainInfoQuery,
+    ) -> Result<ChainInfoResponse, NodeError> {
+        self.query(query.into()).await
+    }
+
+    async fn subscribe(
+        &self,
+        chains: Vec<ChainId>,
+        filter: NotificationFilter,
+    ) -> Result<NotificationStream, NodeError> {
+        let request = SubscriptionRequest {
+            chain_ids: chains,
+            filter,
+            resume_from: BTreeMap::new(),
+        };
+        // Make the first connection attempt before returning from this method.
+        let connection = self
+            .connect_and_send(&request.clone().into())
+            .await
+            .map_err(|error| NodeError::SubscriptionFailed {
+                status: error.to_string(),
+            })?;
+        let subscription = Subscription {
+            client: self.clone(),
+            request,
+            connection: Some(connection),
+            retry_count: 0,
+        };
+        Ok(Box::pin(stream::unfold(subscription, Subscription::next)))
+    }
+
+    async fn get_version_info(&self) -> Result<VersionInfo, NodeError> {
+        self.query(RpcMessage::VersionInfoQuery).await
+    }
+
+    async fn get_genesis_config_hash(&self) -> Result<CryptoHash, NodeError> {
+        self.query(RpcMessage::GenesisConfigHashQuery).await
+    }
+
+    async fn download_blob_content(&self, blob_id: BlobId) -> Result<BlobContent, NodeError> {
+        self.query(RpcMessage::DownloadBlobContent(Box::new(blob_id)))
+            .await
+    }
+
+    async fn download_certificate_value(
+        &self,
+        hash: CryptoHash,
+    ) -> Result<HashedCertificateValue, NodeError> {
+        let certificate_value: CertificateValue = self
+            .query(RpcMessage::DownloadCertificateValue(Box::new(hash)))
+            .await?;
+        Ok(certificate_value.with_hash_checked(hash)?)
+    }
+
+    async fn download_certificate(&self, hash: CryptoHash) -> Result<Certificate, NodeError> {
+        self.query(RpcMessage::DownloadCertificate(Box::new(hash)))
+            .await
+    }
+
+    async fn download_certificates(
+        &self,
+        hashes: Vec<CryptoHash>,
+    ) -> Result<Vec<Certificate>, NodeError> {
+        let mut missing_hashes = hashes;
+        let mut certificates = Vec::with_capacity(missing_hashes.len());
+        // The validator may return fewer certificates than requested, to limit the size of
+        // its response.
+        while !missing_hashes.is_empty() {
+            let mut received: Vec<Certificate> = self
+                .query(RpcMessage::DownloadCertificates(Box::new(
+                    missing_hashes.clone(),
+                )))
+                .await?;
+            if received.is_empty() {
+                break;
+            }
+            missing_hashes = missing_hashes.split_off(received.len().min(missing_hashes.len()));
+            certificates.append(&mut received);
+        }
+        Ok(certificates)
+    }
+
+    async fn blob_last_used_by(&self, blob_id: BlobId) -> Result<CryptoHash, NodeError> {
+        self.query(RpcMessage::BlobLastUsedBy(Box::new(blob_id)))
+            .await
+    }
+
+    async fn handle_certificate_announcement(
+        &self,
+        _announcement: CertificateAnnouncement,
+    ) -> Result<(), NodeError> {
+        let transport = self.network.protocol.to_string();
+        Err(NodeError::GossipNotSupported { transport })
+    }
+}
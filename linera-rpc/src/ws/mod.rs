@@ -0,0 +1,13 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A transport for the public API of a validator over WebSockets, usable from browsers.
+//! Each binary frame carries one bincode-serialized [`RpcMessage`](crate::RpcMessage).
+
+mod client;
+mod node_provider;
+mod transport;
+
+pub use client::*;
+pub use node_provider::*;
+pub use transport::*;
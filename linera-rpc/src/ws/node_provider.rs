@@ -0,0 +1,33 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::str::FromStr as _;
+
+use linera_core::node::{NodeError, ValidatorNodeProvider};
+
+use super::WebSocketClient;
+use crate::{config::ValidatorPublicNetworkPreConfig, node_provider::NodeOptions};
+
+/// A client without an address - serves as a client factory.
+#[derive(Copy, Clone)]
+pub struct WebSocketNodeProvider(NodeOptions);
+
+impl WebSocketNodeProvider {
+    pub fn new(options: NodeOptions) -> Self {
+        Self(options)
+    }
+}
+
+impl ValidatorNodeProvider for WebSocketNodeProvider {
+    type Node = WebSocketClient;
+
+    fn make_node(&self, address: &str) -> Result<Self::Node, NodeError> {
+        let network = ValidatorPublicNetworkPreConfig::from_str(address).map_err(|_| {
+            NodeError::CannotResolveValidatorAddress {
+                address: address.to_string(),
+            }
+        })?;
+
+        Ok(WebSocketClient::new(network, self.0))
+    }
+}
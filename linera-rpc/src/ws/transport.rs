@@ -0,0 +1,142 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use futures::{SinkExt as _, StreamExt as _};
+use linera_core::node::NodeError;
+use serde::{Deserialize, Serialize};
+
+use crate::RpcMessage;
+
+/// The URL scheme of a validator's WebSocket endpoint.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum WebSocketProtocol {
+    /// Cleartext WebSockets.
+    Ws,
+    /// WebSockets over TLS. Only supported in browsers, which handle TLS themselves.
+    Wss,
+}
+
+impl std::str::FromStr for WebSocketProtocol {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ws" => Ok(Self::Ws),
+            "wss" => Ok(Self::Wss),
+            _ => Err(format!("unsupported WebSocket protocol: {s}")),
+        }
+    }
+}
+
+impl std::fmt::Display for WebSocketProtocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.scheme())
+    }
+}
+
+impl WebSocketProtocol {
+    pub fn scheme(&self) -> &'static str {
+        match self {
+            WebSocketProtocol::Ws => "ws",
+            WebSocketProtocol::Wss => "wss",
+        }
+    }
+}
+
+fn io_error(error: impl std::fmt::Display) -> NodeError {
+    NodeError::ClientIoError {
+        error: error.to_string(),
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn encode(message: &RpcMessage) -> Result<Vec<u8>, NodeError> {
+    bincode::serialize(message).map_err(io_error)
+}
+
+#[allow(clippy::result_large_err)]
+fn decode(bytes: &[u8]) -> Result<RpcMessage, NodeError> {
+    bincode::deserialize(bytes).map_err(|_| NodeError::InvalidDecoding)
+}
+
+#[cfg(not(web))]
+mod implementation {
+    use async_tungstenite::{
+        tokio::{connect_async, ConnectStream},
+        tungstenite::Message,
+        WebSocketStream,
+    };
+
+    use super::*;
+
+    /// A WebSocket connection to a validator.
+    pub struct Connection(WebSocketStream<ConnectStream>);
+
+    impl Connection {
+        /// Opens a connection to the given URL.
+        pub async fn connect(url: &str) -> Result<Self, NodeError> {
+            let (stream, _) = connect_async(url).await.map_err(io_error)?;
+            Ok(Self(stream))
+        }
+
+        /// Sends a message in a binary frame.
+        pub async fn send(&mut self, message: &RpcMessage) -> Result<(), NodeError> {
+            let frame = Message::Binary(encode(message)?);
+            self.0.send(frame).await.map_err(io_error)
+        }
+
+        /// Receives the next message, or `None` if the connection was closed.
+        pub async fn receive(&mut self) -> Result<Option<RpcMessage>, NodeError> {
+            while let Some(frame) = self.0.next().await {
+                match frame.map_err(io_error)? {
+                    Message::Binary(bytes) => return decode(&bytes).map(Some),
+                    Message::Close(_) => break,
+                    Message::Text(_) | Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => {}
+                }
+            }
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(web)]
+mod implementation {
+    use ws_stream_wasm::{WsMessage, WsMeta, WsStream};
+
+    use super::*;
+
+    /// A WebSocket connection to a validator, opened by the browser.
+    pub struct Connection {
+        _meta: WsMeta,
+        stream: WsStream,
+    }
+
+    impl Connection {
+        /// Opens a connection to the given URL.
+        pub async fn connect(url: &str) -> Result<Self, NodeError> {
+            let (meta, stream) = WsMeta::connect(url, None).await.map_err(io_error)?;
+            Ok(Self {
+                _meta: meta,
+                stream,
+            })
+        }
+
+        /// Sends a message in a binary frame.
+        pub async fn send(&mut self, message: &RpcMessage) -> Result<(), NodeError> {
+            let frame = WsMessage::Binary(encode(message)?);
+            self.stream.send(frame).await.map_err(io_error)
+        }
+
+        /// Receives the next message, or `None` if the connection was closed.
+        pub async fn receive(&mut self) -> Result<Option<RpcMessage>, NodeError> {
+            while let Some(frame) = self.stream.next().await {
+                if let WsMessage::Binary(bytes) = frame {
+                    return decode(&bytes).map(Some);
+                }
+            }
+            Ok(None)
+        }
+    }
+}
+
+pub use implementation::Connection;
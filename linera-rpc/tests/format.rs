@@ -5,13 +5,20 @@
 use linera_base::{
     data_types::{BlobContent, OracleResponse, Round},
     identifiers::{BlobType, ChainDescription, Destination, GenericApplicationId},
-    ownership::ChainOwnership,
+    ownership::{ChainOwnership, FastRoundDuration},
 };
 use linera_chain::{
-    data_types::{CertificateValue, HashedCertificateValue, Medium, MessageAction},
+    data_types::{
+        CertificateRound, CertificateValue, HashedCertificateValue, Medium, MessageAction,
+        MessageKindAndExpiry, VoteRound,
+    },
     manager::ChainManagerInfo,
 };
-use linera_core::{data_types::CrossChainRequest, node::NodeError};
+use linera_core::{
+    data_types::{ChainInfoResponse, CrossChainRequest},
+    node::{BatchItem, NodeError},
+    worker::{Reason, ReasonKind},
+};
 use linera_execution::{
     system::{AdminOperation, Recipient, SystemChannel, SystemMessage, SystemOperation},
     Message, MessageKind, Operation,
@@ -29,6 +36,8 @@ fn get_registry() -> Result<Registry> {
     // 1. Record samples for types with custom deserializers.
     // 2. Trace the main entry point(s) + every enum separately.
     tracer.trace_type::<Round>(&samples)?;
+    tracer.trace_type::<VoteRound>(&samples)?;
+    tracer.trace_type::<CertificateRound>(&samples)?;
     tracer.trace_type::<OracleResponse>(&samples)?;
     tracer.trace_type::<Recipient>(&samples)?;
     tracer.trace_type::<SystemChannel>(&samples)?;
@@ -39,16 +48,22 @@ fn get_registry() -> Result<Registry> {
     tracer.trace_type::<Message>(&samples)?;
     tracer.trace_type::<MessageAction>(&samples)?;
     tracer.trace_type::<MessageKind>(&samples)?;
+    tracer.trace_type::<MessageKindAndExpiry>(&samples)?;
     tracer.trace_type::<HashedCertificateValue>(&samples)?;
     tracer.trace_type::<CertificateValue>(&samples)?;
     tracer.trace_type::<Medium>(&samples)?;
     tracer.trace_type::<Destination>(&samples)?;
     tracer.trace_type::<ChainDescription>(&samples)?;
     tracer.trace_type::<ChainOwnership>(&samples)?;
+    tracer.trace_type::<FastRoundDuration>(&samples)?;
     tracer.trace_type::<GenericApplicationId>(&samples)?;
     tracer.trace_type::<ChainManagerInfo>(&samples)?;
     tracer.trace_type::<CrossChainRequest>(&samples)?;
     tracer.trace_type::<NodeError>(&samples)?;
+    tracer.trace_type::<BatchItem>(&samples)?;
+    tracer.trace_type::<Reason>(&samples)?;
+    tracer.trace_type::<ReasonKind>(&samples)?;
+    tracer.trace_type::<Result<ChainInfoResponse, NodeError>>(&samples)?;
     tracer.trace_type::<RpcMessage>(&samples)?;
     tracer.trace_type::<BlobType>(&samples)?;
     tracer.trace_type::<BlobContent>(&samples)?;
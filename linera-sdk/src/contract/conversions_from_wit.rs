@@ -7,7 +7,7 @@ use linera_base::{
     crypto::{CryptoHash, PublicKey},
     data_types::{Amount, BlockHeight, TimeDelta, Timestamp},
     identifiers::{ApplicationId, BytecodeId, ChainId, MessageId, Owner},
-    ownership::{ChainOwnership, CloseChainError, TimeoutConfig},
+    ownership::{ChainOwnership, CloseChainError, RecoveryConfig, TimeoutConfig},
 };
 
 use super::wit::contract_system_api as wit_system_api;
@@ -125,6 +125,8 @@ impl From<wit_system_api::ChainOwnership> for ChainOwnership {
             owners,
             multi_leader_rounds,
             timeout_config,
+            approval_threshold,
+            recovery,
         } = guest;
         ChainOwnership {
             super_owners: super_owners
@@ -137,6 +139,26 @@ impl From<wit_system_api::ChainOwnership> for ChainOwnership {
                 .collect(),
             multi_leader_rounds,
             timeout_config: timeout_config.into(),
+            approval_threshold,
+            recovery: recovery.map(RecoveryConfig::from),
+        }
+    }
+}
+
+impl From<wit_system_api::RecoveryConfig> for RecoveryConfig {
+    fn from(guest: wit_system_api::RecoveryConfig) -> RecoveryConfig {
+        let wit_system_api::RecoveryConfig {
+            guardians,
+            threshold,
+            delay,
+        } = guest;
+        RecoveryConfig {
+            guardians: guardians
+                .into_iter()
+                .map(|(owner, public_key)| (owner.into(), public_key.into()))
+                .collect(),
+            threshold,
+            delay: delay.into(),
         }
     }
 }
@@ -13,7 +13,7 @@ use linera_base::{
         Account, ApplicationId, BytecodeId, ChainId, ChannelName, Destination, MessageId, Owner,
         StreamName,
     },
-    ownership::{ChainOwnership, TimeoutConfig},
+    ownership::{ChainOwnership, RecoveryConfig, TimeoutConfig},
 };
 
 use super::wit::contract_system_api as wit_system_api;
@@ -121,6 +121,7 @@ impl From<SendMessageRequest<Vec<u8>>> for wit_system_api::SendMessageRequest {
             authenticated: message.authenticated,
             is_tracked: message.is_tracked,
             grant: message.grant.into(),
+            expiry: message.expiry.map(Into::into),
             message: message.message,
         }
     }
@@ -243,6 +244,8 @@ impl From<ChainOwnership> for wit_system_api::ChainOwnership {
             owners,
             multi_leader_rounds,
             timeout_config,
+            approval_threshold,
+            recovery,
         } = ownership;
         Self {
             super_owners: super_owners
@@ -255,6 +258,26 @@ impl From<ChainOwnership> for wit_system_api::ChainOwnership {
                 .collect(),
             multi_leader_rounds,
             timeout_config: timeout_config.into(),
+            approval_threshold,
+            recovery: recovery.map(Into::into),
+        }
+    }
+}
+
+impl From<RecoveryConfig> for wit_system_api::RecoveryConfig {
+    fn from(recovery: RecoveryConfig) -> Self {
+        let RecoveryConfig {
+            guardians,
+            threshold,
+            delay,
+        } = recovery;
+        Self {
+            guardians: guardians
+                .into_iter()
+                .map(|(owner, public_key)| (owner.into(), public_key.into()))
+                .collect(),
+            threshold,
+            delay: delay.into(),
         }
     }
 }
@@ -774,6 +774,7 @@ where
     authenticated: bool,
     is_tracked: bool,
     grant: Resources,
+    expiry: Option<Timestamp>,
     message: Message,
     send_message_requests: Arc<Mutex<Vec<SendMessageRequest<Message>>>>,
 }
@@ -791,6 +792,7 @@ where
             authenticated: false,
             is_tracked: false,
             grant: Resources::default(),
+            expiry: None,
             message,
             send_message_requests,
         }
@@ -815,6 +817,13 @@ where
         self
     }
 
+    /// Makes the receiver reject the message if it is not executed before `expiry`. A tracked
+    /// message then bounces back to the sender.
+    pub fn with_expiry(mut self, expiry: Timestamp) -> Self {
+        self.expiry = Some(expiry);
+        self
+    }
+
     /// Schedules this `Message` to be sent to the `destination`.
     pub fn send_to(self, destination: impl Into<Destination>) {
         let request = SendMessageRequest {
@@ -822,6 +831,7 @@ where
             authenticated: self.authenticated,
             is_tracked: self.is_tracked,
             grant: self.grant,
+            expiry: self.expiry,
             message: self.message,
         };
 
@@ -38,6 +38,7 @@ mod types {
     pub struct Notification {
         pub chain_id: ChainId,
         pub reason: Reason,
+        pub sequence_number: u64,
     }
 
     #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -182,6 +183,7 @@ mod from {
                 grant,
                 refund_grant_to,
                 kind,
+                expiry,
                 index,
                 message,
             } = val;
@@ -190,6 +192,7 @@ mod from {
                 grant,
                 refund_grant_to,
                 kind,
+                expiry,
                 index: index as u32,
                 message,
             }
@@ -206,6 +209,7 @@ mod from {
                 height,
                 timestamp,
                 authenticated_signer,
+                sponsor,
                 previous_block_hash,
             } = val;
             let incoming_bundles = incoming_bundles
@@ -220,6 +224,7 @@ mod from {
                 height,
                 timestamp,
                 authenticated_signer,
+                sponsor,
                 previous_block_hash,
             }
         }
@@ -233,6 +238,7 @@ mod from {
                 grant,
                 refund_grant_to,
                 kind,
+                expiry,
                 message,
             } = val;
             OutgoingMessage {
@@ -241,6 +247,7 @@ mod from {
                 grant,
                 refund_grant_to,
                 kind,
+                expiry,
                 message,
             }
         }
@@ -1,19 +1,27 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use criterion::{criterion_group, criterion_main, Criterion};
+use async_trait::async_trait;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use futures::{
     stream::{self, FuturesUnordered},
-    Stream, StreamExt,
+    SinkExt as _, Stream, StreamExt,
 };
 use linera_base::{
     data_types::Amount,
     identifiers::{Account, ChainId, Owner},
+    port::get_free_endpoint,
     time::{Duration, Instant},
 };
 use linera_execution::system::Recipient;
+use linera_rpc::{
+    simple::{Connector, MessageHandler, TransportProtocol},
+    RpcMessage,
+};
 use linera_sdk::test::{ActiveChain, TestValidator};
-use tokio::runtime::Runtime;
+use linera_version::VersionInfo;
+use tokio::{runtime::Runtime, task::JoinSet};
+use tokio_util::sync::CancellationToken;
 
 /// Benchmarks several transactions transfering tokens across chains.
 fn cross_chain_native_token_transfers(criterion: &mut Criterion) {
@@ -146,5 +154,76 @@ fn prepare_transfers(
         .collect::<FuturesUnordered<_>>()
 }
 
-criterion_group!(benches, cross_chain_native_token_transfers);
+/// Benchmarks the round trips of concurrent requests to a validator over the simple network
+/// protocol, comparing the transports.
+fn simple_network_round_trips(criterion: &mut Criterion) {
+    let requests = 1000;
+    let runtime = Runtime::new().expect("Failed to create Tokio runtime");
+    let mut group = criterion.benchmark_group("simple_network_round_trips");
+
+    for protocol in [TransportProtocol::Tcp, TransportProtocol::Quic] {
+        let shutdown_signal = CancellationToken::new();
+        let address = runtime.block_on(async {
+            let address = get_free_endpoint()
+                .await
+                .expect("Failed to find a free port");
+            let mut join_set = JoinSet::new();
+            protocol.spawn_server(
+                address.clone(),
+                VersionInfoHandler,
+                shutdown_signal.clone(),
+                &mut join_set,
+            );
+            join_set.detach_all();
+            // Let the server start listening.
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            address
+        });
+        let connector = Connector::new(protocol);
+
+        group.bench_function(BenchmarkId::from_parameter(protocol), |bencher| {
+            bencher.to_async(&runtime).iter(|| {
+                (0..requests)
+                    .map(|_| {
+                        let connector = connector.clone();
+                        let address = address.clone();
+                        tokio::spawn(async move {
+                            let mut transport =
+                                connector.connect(address).await.expect("Failed to connect");
+                            transport
+                                .send(RpcMessage::VersionInfoQuery)
+                                .await
+                                .expect("Failed to send request");
+                            transport.next().await.expect("Missing response")
+                        })
+                    })
+                    .collect::<FuturesUnordered<_>>()
+                    .for_each(|result| async move {
+                        result.unwrap().expect("Failed to receive response");
+                    })
+            })
+        });
+
+        shutdown_signal.cancel();
+    }
+
+    group.finish();
+}
+
+/// A validator that only answers with its version.
+#[derive(Clone)]
+struct VersionInfoHandler;
+
+#[async_trait]
+impl MessageHandler for VersionInfoHandler {
+    async fn handle_message(&mut self, _message: RpcMessage) -> Option<RpcMessage> {
+        Some(VersionInfo::default().into())
+    }
+}
+
+criterion_group!(
+    benches,
+    cross_chain_native_token_transfers,
+    simple_network_round_trips
+);
 criterion_main!(benches);
@@ -485,7 +485,7 @@ impl LocalNet {
                 let nickname = format!("validator proxy {validator}");
                 Self::ensure_grpc_server_has_started(&nickname, port, "https").await?;
             }
-            Network::Tcp | Network::Udp => {
+            Network::Tcp | Network::Udp | Network::Quic => {
                 info!("Letting validator proxy {validator} start");
                 linera_base::time::timer::sleep(Duration::from_secs(2)).await;
             }
@@ -599,7 +599,7 @@ impl LocalNet {
                 let nickname = format!("validator server {validator}:{shard}");
                 Self::ensure_grpc_server_has_started(&nickname, port, "https").await?;
             }
-            Network::Tcp | Network::Udp => {
+            Network::Tcp | Network::Udp | Network::Quic => {
                 info!("Letting validator server {validator}:{shard} start");
                 linera_base::time::timer::sleep(Duration::from_secs(2)).await;
             }
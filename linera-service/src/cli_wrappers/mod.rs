@@ -63,6 +63,7 @@ pub enum Network {
     Grpcs,
     Tcp,
     Udp,
+    Quic,
 }
 
 /// Network protocol in use outside and inside a Linera net.
@@ -81,6 +82,7 @@ impl Network {
             Network::Grpcs => "{ Grpc = \"Tls\" }",
             Network::Tcp => "{ Simple = \"Tcp\" }",
             Network::Udp => "{ Simple = \"Udp\" }",
+            Network::Quic => "{ Simple = \"Quic\" }",
         }
     }
 
@@ -90,6 +92,7 @@ impl Network {
             Network::Grpcs => "grpcs",
             Network::Tcp => "tcp",
             Network::Udp => "udp",
+            Network::Quic => "quic",
         }
     }
 
@@ -99,13 +102,14 @@ impl Network {
             Network::Grpcs => Network::Grpc,
             Network::Tcp => Network::Tcp,
             Network::Udp => Network::Udp,
+            Network::Quic => Network::Quic,
         }
     }
 
     pub fn localhost(&self) -> &'static str {
         match self {
             Network::Grpc | Network::Grpcs => "localhost",
-            Network::Tcp | Network::Udp => "127.0.0.1",
+            Network::Tcp | Network::Udp | Network::Quic => "127.0.0.1",
         }
     }
 }
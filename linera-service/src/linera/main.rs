@@ -40,7 +40,8 @@ use linera_core::{
     JoinSetExt as _,
 };
 use linera_execution::{
-    committee::{Committee, ValidatorName, ValidatorState},
+    committee::{BlsValidatorKey, Committee, ValidatorName, ValidatorState},
+    system::{Preimage, StakingConfig},
     Message, ResourceControlPolicy, SystemMessage,
 };
 use linera_service::{
@@ -61,7 +62,8 @@ mod net_up_utils;
 use {
     linera_chain::data_types::HashedCertificateValue,
     linera_core::data_types::ChainInfoResponse,
-    linera_rpc::{HandleCertificateRequest, RpcMessage},
+    linera_core::node::BatchItem,
+    linera_rpc::{HandleBatchRequest, HandleCertificateRequest, RpcMessage},
     std::collections::HashSet,
     tracing::error,
 };
@@ -83,6 +85,57 @@ fn deserialize_response(response: RpcMessage) -> Option<ChainInfoResponse> {
     }
 }
 
+/// Groups block proposals or certificates into batches of `batch_size` items.
+#[cfg(feature = "benchmark")]
+fn into_batches(messages: Vec<RpcMessage>, batch_size: usize) -> Vec<RpcMessage> {
+    if batch_size <= 1 {
+        return messages;
+    }
+    let mut wait_for_outgoing_messages = false;
+    let items = messages
+        .into_iter()
+        .map(|message| match message {
+            RpcMessage::BlockProposal(proposal) => BatchItem::BlockProposal(proposal),
+            RpcMessage::Certificate(request) => {
+                wait_for_outgoing_messages |= request.wait_for_outgoing_messages;
+                BatchItem::Certificate {
+                    certificate: Box::new(request.certificate),
+                    blobs: request.blobs,
+                }
+            }
+            message => panic!("cannot batch message: {:?}", message),
+        })
+        .collect::<Vec<_>>();
+    let mut items = items.into_iter().peekable();
+    let mut batches = Vec::new();
+    while items.peek().is_some() {
+        let request = HandleBatchRequest {
+            items: items.by_ref().take(batch_size).collect(),
+            wait_for_outgoing_messages,
+        };
+        batches.push(request.into());
+    }
+    batches
+}
+
+/// Replaces the responses to batches with the responses to their items.
+#[cfg(feature = "benchmark")]
+fn flatten_batch_responses(responses: Vec<RpcMessage>) -> Vec<RpcMessage> {
+    responses
+        .into_iter()
+        .flat_map(|response| match response {
+            RpcMessage::BatchResponse(results) => results
+                .into_iter()
+                .map(|result| match result {
+                    Ok(response) => response.into(),
+                    Err(error) => error.into(),
+                })
+                .collect(),
+            response => vec![response],
+        })
+        .collect()
+}
+
 struct Job(ClientOptions);
 
 fn read_json(string: Option<String>, path: Option<PathBuf>) -> anyhow::Result<Vec<u8>> {
@@ -242,6 +295,63 @@ impl Runnable for Job {
                 ownership_config,
             } => context.change_ownership(chain_id, ownership_config).await?,
 
+            InitiateRecovery {
+                chain_id,
+                new_public_key,
+            } => {
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let chain_client = context.make_chain_client(chain_id)?;
+                info!(
+                    "Initiating the recovery of chain {} to owner {}",
+                    chain_id, new_public_key
+                );
+                let time_start = Instant::now();
+                let certificate = context
+                    .apply_client_command(&chain_client, |chain_client| {
+                        let chain_client = chain_client.clone();
+                        async move { chain_client.initiate_recovery(new_public_key).await }
+                    })
+                    .await
+                    .context("Failed to initiate recovery")?;
+                let time_total = time_start.elapsed();
+                info!("Operation confirmed after {} ms", time_total.as_millis());
+                debug!("{:?}", certificate);
+            }
+
+            CancelRecovery { chain_id } => {
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let chain_client = context.make_chain_client(chain_id)?;
+                info!("Canceling the recovery of chain {}", chain_id);
+                let time_start = Instant::now();
+                let certificate = context
+                    .apply_client_command(&chain_client, |chain_client| {
+                        let chain_client = chain_client.clone();
+                        async move { chain_client.cancel_recovery().await }
+                    })
+                    .await
+                    .context("Failed to cancel recovery")?;
+                let time_total = time_start.elapsed();
+                info!("Operation confirmed after {} ms", time_total.as_millis());
+                debug!("{:?}", certificate);
+            }
+
+            CompleteRecovery { chain_id } => {
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let chain_client = context.make_chain_client(chain_id)?;
+                info!("Completing the recovery of chain {}", chain_id);
+                let time_start = Instant::now();
+                let certificate = context
+                    .apply_client_command(&chain_client, |chain_client| {
+                        let chain_client = chain_client.clone();
+                        async move { chain_client.complete_recovery().await }
+                    })
+                    .await
+                    .context("Failed to complete recovery")?;
+                let time_total = time_start.elapsed();
+                info!("Operation confirmed after {} ms", time_total.as_millis());
+                debug!("{:?}", certificate);
+            }
+
             ChangeApplicationPermissions {
                 chain_id,
                 application_permissions_config,
@@ -285,6 +395,268 @@ impl Runnable for Job {
                 debug!("{:?}", certificate);
             }
 
+            Sponsor {
+                sponsor,
+                beneficiary,
+                applications,
+                budget,
+            } => {
+                let chain_client = context.make_chain_client(sponsor.chain_id)?;
+                info!(
+                    "Sponsoring chain {} with a budget of {} native tokens from {}",
+                    beneficiary, budget, sponsor
+                );
+                let time_start = Instant::now();
+                let certificate = context
+                    .apply_client_command(&chain_client, |chain_client| {
+                        let chain_client = chain_client.clone();
+                        let applications = applications.clone();
+                        async move {
+                            chain_client
+                                .sponsor(sponsor.owner, beneficiary, budget, applications)
+                                .await
+                        }
+                    })
+                    .await
+                    .context("Failed to sponsor chain")?;
+                let time_total = time_start.elapsed();
+                info!("Operation confirmed after {} ms", time_total.as_millis());
+                debug!("{:?}", certificate);
+            }
+
+            RevokeSponsorship {
+                sponsor,
+                beneficiary,
+            } => {
+                let chain_client = context.make_chain_client(sponsor.chain_id)?;
+                info!(
+                    "Revoking the sponsorship of chain {} by {}",
+                    beneficiary, sponsor
+                );
+                let time_start = Instant::now();
+                let certificate = context
+                    .apply_client_command(&chain_client, |chain_client| {
+                        let chain_client = chain_client.clone();
+                        async move {
+                            chain_client
+                                .revoke_sponsorship(sponsor.owner, beneficiary)
+                                .await
+                        }
+                    })
+                    .await
+                    .context("Failed to revoke sponsorship")?;
+                let time_total = time_start.elapsed();
+                info!("Operation confirmed after {} ms", time_total.as_millis());
+                debug!("{:?}", certificate);
+            }
+
+            LockFunds {
+                sender,
+                recipient,
+                hash,
+                secret,
+                timeout,
+                amount,
+            } => {
+                let hash = match (hash, secret) {
+                    (Some(hash), _) => hash,
+                    (None, Some(secret)) => Preimage(secret.into_bytes()).hash(),
+                    (None, None) => bail!("Either a hash or a secret must be provided"),
+                };
+                let deadline = Timestamp::now().saturating_add(timeout);
+                let chain_client = context.make_chain_client(sender.chain_id)?;
+                info!(
+                    "Locking {} native tokens from {} to {} with hash {} until {}",
+                    amount, sender, recipient, hash, deadline
+                );
+                let time_start = Instant::now();
+                let certificate = context
+                    .apply_client_command(&chain_client, |chain_client| {
+                        let chain_client = chain_client.clone();
+                        async move {
+                            chain_client
+                                .lock_funds(sender.owner, recipient, amount, hash, deadline)
+                                .await
+                        }
+                    })
+                    .await
+                    .context("Failed to lock funds")?;
+                let time_total = time_start.elapsed();
+                info!("Operation confirmed after {} ms", time_total.as_millis());
+                debug!("{:?}", certificate);
+                println!("{}", hash);
+            }
+
+            RedeemLock {
+                chain_id,
+                target,
+                secret,
+            } => {
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let chain_client = context.make_chain_client(chain_id)?;
+                let preimage = Preimage(secret.into_bytes());
+                info!(
+                    "Redeeming the lock with hash {} on chain {} from chain {}",
+                    preimage.hash(),
+                    target,
+                    chain_id
+                );
+                let time_start = Instant::now();
+                let certificate = context
+                    .apply_client_command(&chain_client, |chain_client| {
+                        let chain_client = chain_client.clone();
+                        let preimage = preimage.clone();
+                        async move { chain_client.redeem_lock(target, preimage).await }
+                    })
+                    .await
+                    .context("Failed to redeem lock")?;
+                let time_total = time_start.elapsed();
+                info!("Operation confirmed after {} ms", time_total.as_millis());
+                debug!("{:?}", certificate);
+            }
+
+            RefundLock { chain_id, hash } => {
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let chain_client = context.make_chain_client(chain_id)?;
+                info!(
+                    "Refunding the lock with hash {} on chain {}",
+                    hash, chain_id
+                );
+                let time_start = Instant::now();
+                let certificate = context
+                    .apply_client_command(&chain_client, |chain_client| {
+                        let chain_client = chain_client.clone();
+                        async move { chain_client.refund_lock(hash).await }
+                    })
+                    .await
+                    .context("Failed to refund lock")?;
+                let time_total = time_start.elapsed();
+                info!("Operation confirmed after {} ms", time_total.as_millis());
+                debug!("{:?}", certificate);
+            }
+
+            ClaimValidatorRewards { chain_id, epoch } => {
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let chain_client = context.make_chain_client(chain_id)?;
+                info!(
+                    "Claiming the validator rewards of {:?} from chain {}",
+                    epoch, chain_id
+                );
+                let time_start = Instant::now();
+                let certificate = context
+                    .apply_client_command(&chain_client, |chain_client| {
+                        let chain_client = chain_client.clone();
+                        async move { chain_client.claim_validator_rewards(epoch).await }
+                    })
+                    .await
+                    .context("Failed to claim validator rewards")?;
+                let time_total = time_start.elapsed();
+                info!("Operation confirmed after {} ms", time_total.as_millis());
+                debug!("{:?}", certificate);
+            }
+
+            RegisterValidator {
+                chain_id,
+                name,
+                address,
+                stake,
+                bls_public_key,
+                bls_proof_of_possession,
+                reward_account,
+            } => {
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let chain_client = context.make_chain_client(chain_id)?;
+                info!("Registering validator {} from chain {}", name, chain_id);
+                let time_start = Instant::now();
+                let bls_key = bls_public_key.zip(bls_proof_of_possession).map(
+                    |(public_key, proof_of_possession)| BlsValidatorKey {
+                        public_key,
+                        proof_of_possession,
+                    },
+                );
+                let certificate = context
+                    .apply_client_command(&chain_client, |chain_client| {
+                        let chain_client = chain_client.clone();
+                        let address = address.clone();
+                        async move {
+                            chain_client
+                                .register_validator(
+                                    None,
+                                    name,
+                                    address,
+                                    bls_key,
+                                    reward_account,
+                                    stake,
+                                )
+                                .await
+                        }
+                    })
+                    .await
+                    .context("Failed to register validator")?;
+                let time_total = time_start.elapsed();
+                info!("Operation confirmed after {} ms", time_total.as_millis());
+                debug!("{:?}", certificate);
+            }
+
+            Bond {
+                chain_id,
+                validator,
+                amount,
+            } => {
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let chain_client = context.make_chain_client(chain_id)?;
+                info!("Bonding {} to validator {}", amount, validator);
+                let time_start = Instant::now();
+                let certificate = context
+                    .apply_client_command(&chain_client, |chain_client| {
+                        let chain_client = chain_client.clone();
+                        async move { chain_client.bond(None, validator, amount).await }
+                    })
+                    .await
+                    .context("Failed to bond stake")?;
+                let time_total = time_start.elapsed();
+                info!("Operation confirmed after {} ms", time_total.as_millis());
+                debug!("{:?}", certificate);
+            }
+
+            Unbond {
+                chain_id,
+                validator,
+                amount,
+            } => {
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let chain_client = context.make_chain_client(chain_id)?;
+                info!("Unbonding {} from validator {}", amount, validator);
+                let time_start = Instant::now();
+                let certificate = context
+                    .apply_client_command(&chain_client, |chain_client| {
+                        let chain_client = chain_client.clone();
+                        async move { chain_client.unbond(validator, amount).await }
+                    })
+                    .await
+                    .context("Failed to unbond stake")?;
+                let time_total = time_start.elapsed();
+                info!("Operation confirmed after {} ms", time_total.as_millis());
+                debug!("{:?}", certificate);
+            }
+
+            WithdrawUnbonded { chain_id } => {
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let chain_client = context.make_chain_client(chain_id)?;
+                info!("Withdrawing unbonded stake to chain {}", chain_id);
+                let time_start = Instant::now();
+                let certificate = context
+                    .apply_client_command(&chain_client, |chain_client| {
+                        let chain_client = chain_client.clone();
+                        async move { chain_client.withdraw_unbonded().await }
+                    })
+                    .await
+                    .context("Failed to withdraw unbonded stake")?;
+                let time_total = time_start.elapsed();
+                info!("Operation confirmed after {} ms", time_total.as_millis());
+                debug!("{:?}", certificate);
+            }
+
             LocalBalance { account } => {
                 let account = account.unwrap_or_else(|| context.default_account());
                 let chain_client = context.make_chain_client(account.chain_id)?;
@@ -362,6 +734,46 @@ impl Runnable for Job {
                 );
             }
 
+            MigrateEpoch { chain_id } => {
+                let chain_ids = match chain_id {
+                    Some(chain_id) => vec![chain_id],
+                    None => context.wallet().own_chain_ids(),
+                };
+                let time_start = Instant::now();
+                let mut block_count = 0;
+                let mut failures = Vec::new();
+                for chain_id in chain_ids {
+                    let chain_client = context.make_chain_client(chain_id)?;
+                    info!("Migrating chain {} to the latest epoch", chain_id);
+                    let result = async {
+                        chain_client.synchronize_from_validators().await?;
+                        chain_client.migrate_epoch().await
+                    }
+                    .await;
+                    context.update_and_save_wallet(&chain_client).await?;
+                    match result {
+                        Ok((certificates, None)) => block_count += certificates.len(),
+                        Ok((certificates, Some(timeout))) => {
+                            block_count += certificates.len();
+                            failures.push(format!("{chain_id}: round timeout {timeout:?}"));
+                        }
+                        Err(error) => failures.push(format!("{chain_id}: {error}")),
+                    }
+                }
+                let time_total = time_start.elapsed();
+                info!(
+                    "Migrated chains with {} blocks in {} ms",
+                    block_count,
+                    time_total.as_millis()
+                );
+                ensure!(
+                    failures.is_empty(),
+                    "Failed to migrate {} chains to the latest epoch:\n{}",
+                    failures.len(),
+                    failures.join("\n")
+                );
+            }
+
             QueryValidator { address } => {
                 use linera_core::node::ValidatorNode as _;
 
@@ -444,8 +856,8 @@ impl Runnable for Job {
                 if let SetValidator {
                     name,
                     address,
-                    votes: _,
                     skip_online_check: false,
+                    ..
                 } = &command
                 {
                     let node = context.make_node_provider().make_node(address)?;
@@ -474,8 +886,14 @@ impl Runnable for Job {
                         ),
                     }
                 }
-                let chain_client =
-                    context.make_chain_client(context.wallet.genesis_admin_chain())?;
+                let chain_id = match &command {
+                    ResourceControlPolicy {
+                        propose_from: Some(chain_id),
+                        ..
+                    } => *chain_id,
+                    _ => context.wallet.genesis_admin_chain(),
+                };
+                let chain_client = context.make_chain_client(chain_id)?;
                 let n = context
                     .process_inbox(&chain_client)
                     .await
@@ -498,13 +916,24 @@ impl Runnable for Job {
                                     name,
                                     address,
                                     votes,
+                                    bls_public_key,
+                                    bls_proof_of_possession,
+                                    reward_account,
                                     skip_online_check: _,
                                 } => {
+                                    let bls_key = bls_public_key.zip(bls_proof_of_possession).map(
+                                        |(public_key, proof_of_possession)| BlsValidatorKey {
+                                            public_key,
+                                            proof_of_possession,
+                                        },
+                                    );
                                     validators.insert(
                                         name,
                                         ValidatorState {
                                             network_address: address,
                                             votes,
+                                            bls_key,
+                                            reward_account,
                                         },
                                     );
                                 }
@@ -530,6 +959,7 @@ impl Runnable for Job {
                                     maximum_executed_block_size,
                                     maximum_bytes_read_per_block,
                                     maximum_bytes_written_per_block,
+                                    propose_from,
                                 } => {
                                     if let Some(block) = block {
                                         policy.block = block;
@@ -589,6 +1019,12 @@ impl Runnable for Job {
                                     if committee.policy() == &policy {
                                         return Ok(ClientOutcome::Committed(None));
                                     }
+                                    if propose_from.is_some() {
+                                        return chain_client
+                                            .propose_policy(policy)
+                                            .await
+                                            .map(|outcome| outcome.map(Some));
+                                    }
                                 }
                                 _ => unreachable!(),
                             }
@@ -604,12 +1040,39 @@ impl Runnable for Job {
                 let Some(certificate) = maybe_certificate else {
                     return Ok(());
                 };
-                info!("Created new committee:\n{:?}", certificate);
+                if chain_id == context.wallet.genesis_admin_chain() {
+                    info!("Created new committee:\n{:?}", certificate);
+                } else {
+                    info!("Proposed new resource control policy:\n{:?}", certificate);
+                }
 
                 let time_total = time_start.elapsed();
                 info!("Operations confirmed after {} ms", time_total.as_millis());
             }
 
+            VotePolicy {
+                chain_id,
+                proposal_id,
+            } => {
+                let chain_id = chain_id.unwrap_or_else(|| context.default_chain());
+                let chain_client = context.make_chain_client(chain_id)?;
+                info!(
+                    "Voting for policy proposal {} from chain {}",
+                    proposal_id, chain_id
+                );
+                let time_start = Instant::now();
+                let certificate = context
+                    .apply_client_command(&chain_client, |chain_client| {
+                        let chain_client = chain_client.clone();
+                        async move { chain_client.vote_policy(proposal_id).await }
+                    })
+                    .await
+                    .context("Failed to vote for policy proposal")?;
+                let time_total = time_start.elapsed();
+                info!("Operation confirmed after {} ms", time_total.as_millis());
+                debug!("{:?}", certificate);
+            }
+
             FinalizeCommittee => {
                 info!("Starting operations to remove old committees");
                 let time_start = Instant::now();
@@ -632,12 +1095,62 @@ impl Runnable for Job {
                 info!("Operations confirmed after {} ms", time_total.as_millis());
             }
 
+            ConfigureStaking {
+                minimum_stake,
+                stake_per_vote,
+                unbonding_period,
+            } => {
+                info!("Configuring the committee election");
+                let time_start = Instant::now();
+
+                let chain_client =
+                    context.make_chain_client(context.wallet.genesis_admin_chain())?;
+                let config = StakingConfig {
+                    minimum_stake,
+                    stake_per_vote,
+                    unbonding_period,
+                };
+                context
+                    .apply_client_command(&chain_client, |chain_client| {
+                        let chain_client = chain_client.clone();
+                        let config = config.clone();
+                        async move { chain_client.configure_staking(config).await }
+                    })
+                    .await
+                    .context("Failed to configure staking")?;
+                context.save_wallet().await?;
+
+                let time_total = time_start.elapsed();
+                info!("Operation confirmed after {} ms", time_total.as_millis());
+            }
+
+            ElectCommittee => {
+                info!("Starting operations to elect a new committee");
+                let time_start = Instant::now();
+
+                let chain_client =
+                    context.make_chain_client(context.wallet.genesis_admin_chain())?;
+                let certificate = context
+                    .apply_client_command(&chain_client, |chain_client| {
+                        let chain_client = chain_client.clone();
+                        async move { chain_client.elect_committee().await }
+                    })
+                    .await
+                    .context("Failed to elect committee")?;
+                context.save_wallet().await?;
+                info!("Created new committee:\n{:?}", certificate);
+
+                let time_total = time_start.elapsed();
+                info!("Operations confirmed after {} ms", time_total.as_millis());
+            }
+
             #[cfg(feature = "benchmark")]
             Benchmark {
                 max_in_flight,
                 num_chains,
                 tokens_per_chain,
                 transactions_per_block,
+                batch_size,
                 fungible_application_id,
             } => {
                 // Below all block proposals are supposed to succeed without retries, we
@@ -680,8 +1193,13 @@ impl Runnable for Job {
                 }
 
                 let responses = context
-                    .mass_broadcast("block proposals", max_in_flight, proposals)
+                    .mass_broadcast(
+                        "block proposals",
+                        max_in_flight,
+                        into_batches(proposals, batch_size),
+                    )
                     .await;
+                let responses = flatten_batch_responses(responses);
                 let votes = responses
                     .into_iter()
                     .filter_map(|message| {
@@ -712,8 +1230,13 @@ impl Runnable for Job {
                     })
                     .collect();
                 let responses = context
-                    .mass_broadcast("certificates", max_in_flight, messages)
+                    .mass_broadcast(
+                        "certificates",
+                        max_in_flight,
+                        into_batches(messages, batch_size),
+                    )
                     .await;
+                let responses = flatten_batch_responses(responses);
                 let mut confirmed = HashSet::new();
                 let num_valid = responses.into_iter().fold(0, |acc, message| {
                     match deserialize_response(message) {
@@ -1198,7 +1721,7 @@ impl Job {
             .context("no active chain found")?;
         let peg_chain = chains.remove(&peg_chain_id).unwrap();
         // These are the still-trusted committees. Every chain tip should be signed by one of them.
-        let committees = peg_chain.execution_state.system.committees.get();
+        let committees = peg_chain.execution_state.system.committees();
         for (chain_id, chain) in &chains {
             let Some(hash) = chain.tip_state.get().block_hash else {
                 continue; // This chain was created based on the genesis config.
@@ -1265,19 +1788,36 @@ fn log_file_name_for(command: &ClientCommand) -> Cow<'static, str> {
         | ClientCommand::OpenChain { .. }
         | ClientCommand::OpenMultiOwnerChain { .. }
         | ClientCommand::ChangeOwnership { .. }
+        | ClientCommand::InitiateRecovery { .. }
+        | ClientCommand::CancelRecovery { .. }
+        | ClientCommand::CompleteRecovery { .. }
         | ClientCommand::ChangeApplicationPermissions { .. }
         | ClientCommand::CloseChain { .. }
+        | ClientCommand::Sponsor { .. }
+        | ClientCommand::RevokeSponsorship { .. }
+        | ClientCommand::LockFunds { .. }
+        | ClientCommand::RedeemLock { .. }
+        | ClientCommand::RefundLock { .. }
+        | ClientCommand::ClaimValidatorRewards { .. }
+        | ClientCommand::RegisterValidator { .. }
+        | ClientCommand::Bond { .. }
+        | ClientCommand::Unbond { .. }
+        | ClientCommand::WithdrawUnbonded { .. }
         | ClientCommand::LocalBalance { .. }
         | ClientCommand::QueryBalance { .. }
         | ClientCommand::SyncBalance { .. }
         | ClientCommand::Sync { .. }
         | ClientCommand::ProcessInbox { .. }
+        | ClientCommand::MigrateEpoch { .. }
         | ClientCommand::QueryValidator { .. }
         | ClientCommand::QueryValidators { .. }
         | ClientCommand::SetValidator { .. }
         | ClientCommand::RemoveValidator { .. }
         | ClientCommand::ResourceControlPolicy { .. }
+        | ClientCommand::VotePolicy { .. }
         | ClientCommand::FinalizeCommittee
+        | ClientCommand::ConfigureStaking { .. }
+        | ClientCommand::ElectCommittee
         | ClientCommand::CreateGenesisConfig { .. }
         | ClientCommand::PublishBytecode { .. }
         | ClientCommand::PublishDataBlob { .. }
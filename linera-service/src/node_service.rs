@@ -24,7 +24,7 @@ use linera_base::{
         UserApplicationDescription,
     },
     identifiers::{ApplicationId, BytecodeId, ChainId, Owner, UserApplicationId},
-    ownership::{ChainOwnership, TimeoutConfig},
+    ownership::{ChainOwnership, RecoveryConfig, TimeoutConfig},
     BcsHexParseError,
 };
 use linera_chain::{data_types::HashedCertificateValue, ChainStateView};
@@ -476,21 +476,80 @@ where
             default = 86_400_000
         )]
         fallback_duration_ms: u64,
+        #[graphql(
+            desc = "The total weight of owners that must approve every block; default: no \
+                    approvals needed"
+        )]
+        approval_threshold: Option<u64>,
+        #[graphql(
+            desc = "The guardians who can jointly transfer the chain to a new owner; default: \
+                    no guardians",
+            default
+        )]
+        guardian_public_keys: Vec<PublicKey>,
+        #[graphql(
+            desc = "The number of guardians who must agree on a new owner; default: all of them"
+        )]
+        recovery_threshold: Option<u32>,
+        #[graphql(
+            desc = "The time the owners have to cancel a recovery initiated by the guardians, \
+                    in milliseconds",
+            default = 604_800_000
+        )]
+        recovery_delay_ms: u64,
     ) -> Result<CryptoHash, Error> {
-        let operation = SystemOperation::ChangeOwnership {
-            super_owners: Vec::new(),
-            owners: new_public_keys.into_iter().zip(new_weights).collect(),
-            multi_leader_rounds,
-            timeout_config: TimeoutConfig {
-                fast_round_duration: fast_round_ms.map(TimeDelta::from_millis),
-                base_timeout: TimeDelta::from_millis(base_timeout_ms),
-                timeout_increment: TimeDelta::from_millis(timeout_increment_ms),
-                fallback_duration: TimeDelta::from_millis(fallback_duration_ms),
-            },
+        let recovery = (!guardian_public_keys.is_empty()).then(|| {
+            let threshold = recovery_threshold.unwrap_or(guardian_public_keys.len() as u32);
+            RecoveryConfig::new(
+                guardian_public_keys,
+                threshold,
+                TimeDelta::from_millis(recovery_delay_ms),
+            )
+        });
+        let timeout_config = TimeoutConfig {
+            fast_round_duration: fast_round_ms.map(TimeDelta::from_millis),
+            base_timeout: TimeDelta::from_millis(base_timeout_ms),
+            timeout_increment: TimeDelta::from_millis(timeout_increment_ms),
+            fallback_duration: TimeDelta::from_millis(fallback_duration_ms),
+        };
+        let ownership = ChainOwnership {
+            approval_threshold,
+            recovery,
+            ..ChainOwnership::multiple(
+                new_public_keys.into_iter().zip(new_weights),
+                multi_leader_rounds,
+                timeout_config,
+            )
+        };
+        let operation = SystemOperation::change_ownership(ownership);
+        self.execute_system_operation(operation, chain_id).await
+    }
+
+    /// Votes to transfer the chain to a new owner, as one of its guardians.
+    async fn initiate_recovery(
+        &self,
+        chain_id: ChainId,
+        new_public_key: PublicKey,
+    ) -> Result<CryptoHash, Error> {
+        let operation = SystemOperation::InitiateRecovery {
+            new_owner: new_public_key,
         };
         self.execute_system_operation(operation, chain_id).await
     }
 
+    /// Cancels the recovery initiated by the guardians of the chain, as one of its owners.
+    async fn cancel_recovery(&self, chain_id: ChainId) -> Result<CryptoHash, Error> {
+        self.execute_system_operation(SystemOperation::CancelRecovery, chain_id)
+            .await
+    }
+
+    /// Transfers the chain to the new owner approved by its guardians, once the recovery
+    /// delay is over.
+    async fn complete_recovery(&self, chain_id: ChainId) -> Result<CryptoHash, Error> {
+        self.execute_system_operation(SystemOperation::CompleteRecovery, chain_id)
+            .await
+    }
+
     /// Changes the application permissions configuration on this chain.
     async fn change_application_permissions(
         &self,
@@ -516,8 +575,7 @@ where
         epoch: Epoch,
         committee: Committee,
     ) -> Result<CryptoHash, Error> {
-        let operation =
-            SystemOperation::Admin(AdminOperation::CreateCommittee { epoch, committee });
+        let operation = SystemOperation::Admin(AdminOperation::create_committee(epoch, committee));
         self.execute_system_operation(operation, chain_id).await
     }
 
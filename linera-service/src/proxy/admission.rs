@@ -0,0 +1,442 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Admission control for the requests received by the gRPC proxy.
+
+#[cfg(with_metrics)]
+use std::sync::LazyLock;
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    net::IpAddr,
+    num::NonZeroU32,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use linera_base::{
+    identifiers::ChainId,
+    time::{Duration, Instant},
+};
+use linera_rpc::grpc::RETRY_AFTER_MS_METADATA_KEY;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tonic::{metadata::MetadataValue, Status};
+#[cfg(with_metrics)]
+use {linera_base::prometheus_util, prometheus::IntCounterVec};
+
+#[cfg(with_metrics)]
+static PROXY_REQUEST_REJECTED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "proxy_request_rejected",
+        "Requests rejected by the proxy's admission control",
+        &["method_name", "reason"],
+    )
+    .expect("Counter creation should not fail")
+});
+
+/// How long clients are asked to wait when all the request slots are taken.
+const CONCURRENCY_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Above this many tracked clients or chains, idle token buckets are forgotten.
+const MAX_TRACKED_KEYS: usize = 100_000;
+
+/// Limits on the requests accepted by the proxy. All limits are disabled by default.
+#[derive(Clone, Debug, Default, clap::Args)]
+pub struct AdmissionConfig {
+    /// The maximal sustained number of requests per second accepted from a single client
+    /// IP address.
+    #[arg(long)]
+    pub max_requests_per_second_per_client: Option<NonZeroU32>,
+
+    /// The maximal sustained number of requests per second accepted for a single chain.
+    #[arg(long)]
+    pub max_requests_per_second_per_chain: Option<NonZeroU32>,
+
+    /// The number of requests a client or a chain may send at once.
+    #[arg(long, default_value = "20", value_parser = clap::value_parser!(u32).range(1..))]
+    pub request_burst_size: u32,
+
+    /// The maximal number of requests handled by the proxy at the same time.
+    #[arg(long)]
+    pub max_concurrent_requests: Option<usize>,
+
+    /// The maximal size of a request, in bytes.
+    #[arg(long)]
+    pub max_request_size: Option<usize>,
+
+    /// The maximal number of block proposals and certificates in a batch. Each of them
+    /// counts as a request against the rate limits of the client and of its chain. If
+    /// unset, the number of items in a batch is not limited.
+    #[arg(long)]
+    pub max_batch_size: Option<usize>,
+}
+
+/// Decides whether the proxy accepts a request.
+pub struct AdmissionControl {
+    clients: Option<RateLimiter<IpAddr>>,
+    chains: Option<RateLimiter<ChainId>>,
+    slots: Option<Arc<Semaphore>>,
+    max_request_size: Option<usize>,
+    max_batch_size: Option<usize>,
+}
+
+/// An accepted request. Its slot is released when this is dropped.
+pub struct Admission {
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl AdmissionControl {
+    pub fn new(config: &AdmissionConfig) -> Self {
+        let burst = config.request_burst_size;
+        Self {
+            clients: config
+                .max_requests_per_second_per_client
+                .map(|rate| RateLimiter::new(rate, burst)),
+            chains: config
+                .max_requests_per_second_per_chain
+                .map(|rate| RateLimiter::new(rate, burst)),
+            slots: config
+                .max_concurrent_requests
+                .map(|limit| Arc::new(Semaphore::new(limit))),
+            max_request_size: config.max_request_size,
+            max_batch_size: config.max_batch_size,
+        }
+    }
+
+    /// Accepts a request or returns the status to reject it with. Overloaded requests are
+    /// rejected with `RESOURCE_EXHAUSTED` and tell the client when to retry.
+    pub fn admit(
+        &self,
+        method_name: &str,
+        client: Option<IpAddr>,
+        chain_id: Option<ChainId>,
+        request_size: usize,
+    ) -> Result<Admission, Status> {
+        self.check_request_size(method_name, request_size)?;
+        // Both rate limits are checked before any token is taken, so that a rejected request
+        // does not count against the client or the chain.
+        let tokens = self.check_tokens(method_name, client, chain_id, Instant::now())?;
+        let admission = self.acquire_slot(method_name)?;
+        tokens.take();
+        Ok(admission)
+    }
+
+    /// Accepts a batch of requests for the given chains, or returns the status to reject
+    /// the whole batch with. Each item is then admitted separately against the rate limits
+    /// of the client and of its chain, and only the rejected items fail.
+    pub fn admit_batch(
+        &self,
+        method_name: &str,
+        client: Option<IpAddr>,
+        chain_ids: &[ChainId],
+        request_size: usize,
+    ) -> Result<(Admission, Vec<Result<(), Status>>), Status> {
+        if let Some(max_batch_size) = self.max_batch_size {
+            if chain_ids.len() > max_batch_size {
+                Self::record_rejection(method_name, "batch_size");
+                return Err(Status::invalid_argument(format!(
+                    "batch of {} items exceeds the limit of {max_batch_size} items",
+                    chain_ids.len()
+                )));
+            }
+        }
+        self.check_request_size(method_name, request_size)?;
+        let admission = self.acquire_slot(method_name)?;
+        let now = Instant::now();
+        let items = chain_ids
+            .iter()
+            .map(|chain_id| {
+                self.check_tokens(method_name, client, Some(*chain_id), now)
+                    .map(AvailableTokens::take)
+            })
+            .collect();
+        Ok((admission, items))
+    }
+
+    fn check_request_size(&self, method_name: &str, request_size: usize) -> Result<(), Status> {
+        if let Some(max_request_size) = self.max_request_size {
+            if request_size > max_request_size {
+                Self::record_rejection(method_name, "request_size");
+                return Err(Status::invalid_argument(format!(
+                    "request of {request_size} bytes exceeds the limit of {max_request_size} bytes"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that both the client and the chain have a token available.
+    fn check_tokens(
+        &self,
+        method_name: &str,
+        client: Option<IpAddr>,
+        chain_id: Option<ChainId>,
+        now: Instant,
+    ) -> Result<AvailableTokens<'_>, Status> {
+        let client = match (&self.clients, client) {
+            (Some(clients), Some(client)) => match clients.check(client, now) {
+                Ok(token) => Some(token),
+                Err(retry_after) => {
+                    Self::record_rejection(method_name, "client_rate");
+                    return Err(Self::overloaded(
+                        "too many requests from this client",
+                        retry_after,
+                    ));
+                }
+            },
+            _ => None,
+        };
+        let chain = match (&self.chains, chain_id) {
+            (Some(chains), Some(chain_id)) => match chains.check(chain_id, now) {
+                Ok(token) => Some(token),
+                Err(retry_after) => {
+                    Self::record_rejection(method_name, "chain_rate");
+                    return Err(Self::overloaded(
+                        "too many requests for this chain",
+                        retry_after,
+                    ));
+                }
+            },
+            _ => None,
+        };
+        Ok(AvailableTokens { client, chain })
+    }
+
+    fn acquire_slot(&self, method_name: &str) -> Result<Admission, Status> {
+        let permit = match &self.slots {
+            None => None,
+            Some(slots) => match slots.clone().try_acquire_owned() {
+                Ok(permit) => Some(permit),
+                Err(_) => {
+                    Self::record_rejection(method_name, "concurrency");
+                    return Err(Self::overloaded(
+                        "too many requests in progress",
+                        CONCURRENCY_RETRY_DELAY,
+                    ));
+                }
+            },
+        };
+        Ok(Admission { _permit: permit })
+    }
+
+    fn overloaded(message: &str, retry_after: Duration) -> Status {
+        let mut status = Status::resource_exhausted(message);
+        status.metadata_mut().insert(
+            RETRY_AFTER_MS_METADATA_KEY,
+            MetadataValue::from(retry_after.as_millis() as u64),
+        );
+        status
+    }
+
+    fn record_rejection(method_name: &str, reason: &str) {
+        #![allow(unused_variables)]
+        #[cfg(with_metrics)]
+        PROXY_REQUEST_REJECTED
+            .with_label_values(&[method_name, reason])
+            .inc();
+    }
+}
+
+/// A token bucket for each key: a key may make `rate` requests per second, and up to
+/// `capacity` at once.
+struct RateLimiter<K> {
+    buckets: Mutex<HashMap<K, TokenBucket>>,
+    rate: f64,
+    capacity: f64,
+}
+
+struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+/// A token that is available in the bucket of a key. The bucket stays locked until the
+/// token is taken or dropped.
+struct AvailableToken<'a, K> {
+    buckets: MutexGuard<'a, HashMap<K, TokenBucket>>,
+    key: K,
+}
+
+/// The tokens available for a request from a client for a chain.
+struct AvailableTokens<'a> {
+    client: Option<AvailableToken<'a, IpAddr>>,
+    chain: Option<AvailableToken<'a, ChainId>>,
+}
+
+impl AvailableTokens<'_> {
+    /// Removes the tokens from their buckets.
+    fn take(self) {
+        if let Some(token) = self.client {
+            token.take();
+        }
+        if let Some(token) = self.chain {
+            token.take();
+        }
+    }
+}
+
+impl<K: Eq + Hash> AvailableToken<'_, K> {
+    /// Removes the token from the bucket.
+    fn take(mut self) {
+        let bucket = self
+            .buckets
+            .get_mut(&self.key)
+            .expect("the bucket of an available token exists");
+        bucket.tokens -= 1.0;
+    }
+}
+
+impl<K: Eq + Hash + Clone> RateLimiter<K> {
+    fn new(rate: NonZeroU32, burst: u32) -> Self {
+        Self {
+            buckets: Mutex::default(),
+            rate: f64::from(rate.get()),
+            capacity: f64::from(burst),
+        }
+    }
+
+    /// Takes a token for `key`, or returns how long to wait until one is available.
+    #[cfg(test)]
+    fn try_acquire(&self, key: K, now: Instant) -> Result<(), Duration> {
+        self.check(key, now).map(AvailableToken::take)
+    }
+
+    /// Checks that a token is available for `key`, without taking it, or returns how
+    /// long to wait until one is.
+    fn check(&self, key: K, now: Instant) -> Result<AvailableToken<'_, K>, Duration> {
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_TRACKED_KEYS {
+            buckets.retain(|_, bucket| {
+                bucket.tokens + self.rate * now.duration_since(bucket.updated_at).as_secs_f64()
+                    < self.capacity
+            });
+        }
+        let bucket = buckets.entry(key.clone()).or_insert(TokenBucket {
+            tokens: self.capacity,
+            updated_at: now,
+        });
+        let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + self.rate * elapsed).min(self.capacity);
+        bucket.updated_at = now;
+        if bucket.tokens >= 1.0 {
+            Ok(AvailableToken { buckets, key })
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.rate))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::IpAddr, num::NonZeroU32};
+
+    use linera_base::{
+        identifiers::ChainId,
+        time::{Duration, Instant},
+    };
+    use tonic::Code;
+
+    use super::{AdmissionConfig, AdmissionControl, RateLimiter};
+
+    #[test]
+    fn test_rate_limiter_refills_over_time() {
+        let limiter = RateLimiter::new(NonZeroU32::new(10).unwrap(), 2);
+        let start = Instant::now();
+        // The burst is the number of requests accepted at once.
+        for _ in 0..2 {
+            assert!(limiter.try_acquire("client", start).is_ok());
+        }
+        let retry_after = limiter.try_acquire("client", start).unwrap_err();
+        assert!(retry_after <= Duration::from_millis(100));
+        // Other keys have their own bucket.
+        assert!(limiter.try_acquire("other", start).is_ok());
+        let later = start + Duration::from_millis(250);
+        assert!(limiter.try_acquire("client", later).is_ok());
+        assert!(limiter.try_acquire("client", later).is_ok());
+        assert!(limiter.try_acquire("client", later).is_err());
+    }
+
+    #[test]
+    fn test_rejected_requests_take_no_tokens() {
+        let admission = AdmissionControl::new(&AdmissionConfig {
+            max_requests_per_second_per_client: NonZeroU32::new(1),
+            max_requests_per_second_per_chain: NonZeroU32::new(1),
+            request_burst_size: 2,
+            ..AdmissionConfig::default()
+        });
+        let client = Some(IpAddr::from([127, 0, 0, 1]));
+        let (chain1, chain2) = (Some(ChainId::root(1)), Some(ChainId::root(2)));
+        assert!(admission.admit("test", client, chain1, 10).is_ok());
+        assert!(admission.admit("test", client, chain1, 10).is_ok());
+        // The chain's limit rejects the request without using the client's last token...
+        let other_client = Some(IpAddr::from([127, 0, 0, 2]));
+        assert!(admission.admit("test", other_client, chain1, 10).is_err());
+        assert!(admission.admit("test", other_client, chain2, 10).is_ok());
+        assert!(admission.admit("test", other_client, chain2, 10).is_ok());
+        // ... and the client's limit rejects it without using the chain's tokens.
+        let chain3 = Some(ChainId::root(3));
+        assert!(admission.admit("test", other_client, chain3, 10).is_err());
+        let third_client = Some(IpAddr::from([127, 0, 0, 3]));
+        assert!(admission.admit("test", third_client, chain3, 10).is_ok());
+        assert!(admission.admit("test", third_client, chain3, 10).is_ok());
+    }
+
+    #[test]
+    fn test_admission_control() {
+        let admission = AdmissionControl::new(&AdmissionConfig {
+            max_concurrent_requests: Some(1),
+            max_request_size: Some(100),
+            ..AdmissionConfig::default()
+        });
+        let status = admission.admit("test", None, None, 101).err().unwrap();
+        assert_eq!(status.code(), Code::InvalidArgument);
+
+        let first = admission.admit("test", None, None, 100).unwrap();
+        let status = admission.admit("test", None, None, 10).err().unwrap();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+        assert_eq!(
+            status
+                .metadata()
+                .get(linera_rpc::grpc::RETRY_AFTER_MS_METADATA_KEY),
+            Some(&"100".parse().unwrap())
+        );
+        drop(first);
+        assert!(admission.admit("test", None, None, 10).is_ok());
+    }
+
+    #[test]
+    fn test_batch_admission() {
+        let admission = AdmissionControl::new(&AdmissionConfig {
+            max_requests_per_second_per_client: NonZeroU32::new(1),
+            max_requests_per_second_per_chain: NonZeroU32::new(1),
+            request_burst_size: 3,
+            max_batch_size: Some(3),
+            ..AdmissionConfig::default()
+        });
+        let client = Some(IpAddr::from([127, 0, 0, 1]));
+        let (chain1, chain2) = (ChainId::root(1), ChainId::root(2));
+
+        let status = admission
+            .admit_batch("test", client, &[chain1; 4], 10)
+            .err()
+            .unwrap();
+        assert_eq!(status.code(), Code::InvalidArgument);
+
+        // The items are admitted against their chains: the fourth request for `chain1` is
+        // rejected, without using the client's last token.
+        assert!(admission.admit("test", None, Some(chain1), 10).is_ok());
+        let (_admission, items) = admission
+            .admit_batch("test", client, &[chain1, chain1, chain1], 10)
+            .unwrap();
+        let codes = items
+            .iter()
+            .map(|item| item.as_ref().err().map(|status| status.code()))
+            .collect::<Vec<_>>();
+        assert_eq!(codes, [None, None, Some(Code::ResourceExhausted)]);
+        let (_admission, items) = admission
+            .admit_batch("test", client, &[chain2, chain2], 10)
+            .unwrap();
+        assert!(items[0].is_ok());
+        let status = items[1].as_ref().err().unwrap();
+        assert_eq!(status.message(), "too many requests from this client");
+    }
+}
@@ -8,9 +8,11 @@
 #[cfg(with_metrics)]
 use std::sync::LazyLock;
 use std::{
+    collections::BTreeMap,
     fmt::Debug,
     marker::PhantomData,
     net::SocketAddr,
+    path::{Path, PathBuf},
     sync::Arc,
     task::{Context, Poll},
     time::Duration,
@@ -18,32 +20,46 @@ use std::{
 
 use anyhow::Result;
 use async_trait::async_trait;
-use futures::{future::BoxFuture, FutureExt as _};
+use futures::{
+    future::{self, BoxFuture},
+    FutureExt as _,
+};
 use linera_base::identifiers::ChainId;
-use linera_client::config::GenesisConfig;
-use linera_core::{notifier::ChannelNotifier, JoinSetExt as _};
+use linera_client::{
+    config::{GenesisConfig, ValidatorServerConfig},
+    persistent::{self, PersistExt as _},
+};
+use linera_core::{
+    node::NodeError,
+    notifier::{ChannelNotifier, ReplayConfig},
+    JoinSetExt as _,
+};
 use linera_rpc::{
     config::{
-        ShardConfig, TlsConfig, ValidatorInternalNetworkConfig, ValidatorPublicNetworkConfig,
+        ShardConfig, ShardId, TlsConfig, ValidatorInternalNetworkConfig,
+        ValidatorPublicNetworkConfig,
     },
     grpc::{
         api::{
             notifier_service_server::{NotifierService, NotifierServiceServer},
+            shard_admin_server::{ShardAdmin, ShardAdminServer},
             validator_node_server::{ValidatorNode, ValidatorNodeServer},
             validator_worker_client::ValidatorWorkerClient,
-            BlobContent, BlobId, BlockProposal, Certificate, CertificateValue,
-            CertificatesBatchRequest, CertificatesBatchResponse, ChainInfoQuery, ChainInfoResult,
+            BatchItem, BatchRequest, BatchResult, BlobContent, BlobId, BlockProposal, Certificate,
+            CertificateAnnouncement, CertificateValue, CertificatesBatchRequest,
+            CertificatesBatchResponse, ChainAssignment, ChainInfoQuery, ChainInfoResult,
             CryptoHash, HandleCertificateRequest, LiteCertificate, Notification,
             SubscriptionRequest, VersionInfo,
         },
         pool::GrpcConnectionPool,
-        GrpcProtoConversionError, GrpcProxyable, GRPC_CHUNKED_MESSAGE_FILL_LIMIT,
-        GRPC_MAX_MESSAGE_SIZE,
+        GrpcClient, GrpcProtoConversionError, GrpcProxyable, InternalTls,
+        GRPC_CHUNKED_MESSAGE_FILL_LIMIT, GRPC_MAX_MESSAGE_SIZE,
     },
+    sharding::{ShardRoute, ShardRouter},
 };
 use linera_storage::Storage;
 use prost::Message;
-use tokio::{select, task::JoinSet};
+use tokio::{net::TcpListener, select, sync::Mutex, task::JoinSet};
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use tokio_util::sync::CancellationToken;
 use tonic::{
@@ -58,6 +74,7 @@ use {
     prometheus::{HistogramVec, IntCounterVec},
 };
 
+use crate::admission::{Admission, AdmissionConfig, AdmissionControl};
 #[cfg(with_metrics)]
 use crate::prometheus_server;
 
@@ -155,10 +172,18 @@ pub struct GrpcProxy<S>(Arc<GrpcProxyInner<S>>);
 struct GrpcProxyInner<S> {
     public_config: ValidatorPublicNetworkConfig,
     internal_config: ValidatorInternalNetworkConfig,
+    router: ShardRouter,
+    admission: AdmissionControl,
     genesis_config: GenesisConfig,
     worker_connection_pool: GrpcConnectionPool,
     notifier: ChannelNotifier<Result<Notification, Status>>,
     tls: TlsConfig,
+    internal_tls: Option<InternalTls>,
+    websocket_port: Option<u16>,
+    /// The server configuration, where migrations are recorded so that the proxy and the
+    /// shards still route the migrated chains correctly after a restart. The lock
+    /// serializes the updates of the file.
+    server_config_path: Option<Mutex<PathBuf>>,
     storage: S,
 }
 
@@ -166,6 +191,7 @@ impl<S> GrpcProxy<S>
 where
     S: Storage + Clone + Send + Sync + 'static,
 {
+    #[expect(clippy::too_many_arguments)]
     pub fn new(
         public_config: ValidatorPublicNetworkConfig,
         internal_config: ValidatorInternalNetworkConfig,
@@ -173,17 +199,28 @@ where
         connect_timeout: Duration,
         timeout: Duration,
         tls: TlsConfig,
+        internal_tls: Option<InternalTls>,
         storage: S,
+        admission_config: &AdmissionConfig,
+        replay_config: ReplayConfig,
+        websocket_port: Option<u16>,
+        server_config_path: Option<PathBuf>,
     ) -> Self {
         Self(Arc::new(GrpcProxyInner {
             public_config,
+            router: ShardRouter::new(&internal_config),
+            admission: AdmissionControl::new(admission_config),
             internal_config,
             genesis_config,
             worker_connection_pool: GrpcConnectionPool::default()
                 .with_connect_timeout(connect_timeout)
-                .with_timeout(timeout),
-            notifier: ChannelNotifier::default(),
+                .with_timeout(timeout)
+                .with_tls(internal_tls.as_ref().map(InternalTls::client_config)),
+            internal_tls,
+            notifier: ChannelNotifier::with_replay(replay_config),
             tls,
+            websocket_port,
+            server_config_path: server_config_path.map(Mutex::new),
             storage,
         }))
     }
@@ -198,6 +235,10 @@ where
         NotifierServiceServer::new(self.clone())
     }
 
+    fn as_shard_admin(&self) -> ShardAdminServer<Self> {
+        ShardAdminServer::new(self.clone())
+    }
+
     fn public_address(&self) -> SocketAddr {
         SocketAddr::from(([0, 0, 0, 0], self.0.public_config.port))
     }
@@ -210,20 +251,25 @@ where
         SocketAddr::from(([0, 0, 0, 0], self.0.internal_config.port))
     }
 
-    fn shard_for(&self, proxyable: &impl GrpcProxyable) -> Option<ShardConfig> {
-        Some(
-            self.0
-                .internal_config
-                .get_shard_for(proxyable.chain_id()?)
-                .clone(),
-        )
+    fn websocket_address(&self) -> Option<SocketAddr> {
+        let port = self.0.websocket_port?;
+        Some(SocketAddr::from(([0, 0, 0, 0], port)))
+    }
+
+    /// Returns the shard of the request's chain. Requests for a chain being migrated wait
+    /// for the migration to finish, and the chain is not migrated while the returned
+    /// route is held.
+    async fn shard_for(&self, proxyable: &impl GrpcProxyable) -> Option<(ShardConfig, ShardRoute)> {
+        let route = self.0.router.route(proxyable.chain_id()?).await;
+        let shard = self.0.internal_config.shard(route.shard_id()).clone();
+        Some((shard, route))
     }
 
     fn worker_client_for_shard(
         &self,
         shard: &ShardConfig,
     ) -> Result<ValidatorWorkerClient<Channel>> {
-        let address = shard.http_address();
+        let address = self.0.internal_config.shard_address(shard);
         let channel = self.0.worker_connection_pool.channel(address)?;
         let client = ValidatorWorkerClient::new(channel)
             .max_encoding_message_size(GRPC_MAX_MESSAGE_SIZE)
@@ -241,6 +287,7 @@ where
             public_address = %self.public_address(),
             internal_address = %self.internal_address(),
             metrics_address = %self.metrics_address(),
+            websocket_address = ?self.websocket_address(),
         ),
         err,
     )]
@@ -256,8 +303,9 @@ where
             .set_serving::<ValidatorNodeServer<GrpcProxy<S>>>()
             .await;
         let internal_server = join_set.spawn_task(
-            Server::builder()
+            self.internal_server()?
                 .add_service(self.as_notifier_service())
+                .add_service(self.as_shard_admin())
                 .serve(self.internal_address())
                 .in_current_span(),
         );
@@ -275,13 +323,28 @@ where
                 .add_service(health_service)
                 .add_service(tonic_web::enable(self.as_validator_node()))
                 .add_service(tonic_web::enable(reflection_service))
-                .serve_with_shutdown(self.public_address(), shutdown_signal.cancelled_owned())
+                .serve_with_shutdown(
+                    self.public_address(),
+                    shutdown_signal.clone().cancelled_owned(),
+                )
                 .in_current_span(),
         );
+        let websocket_server = match self.websocket_address() {
+            Some(address) => {
+                let listener = TcpListener::bind(address).await?;
+                join_set.spawn_task(
+                    self.clone()
+                        .serve_websocket(listener, shutdown_signal)
+                        .in_current_span(),
+                )
+            }
+            None => join_set.spawn_task(future::pending()),
+        };
 
         select! {
             internal_res = internal_server => internal_res??,
             public_res = public_server => public_res??,
+            websocket_res = websocket_server => websocket_res??,
         }
         Ok(())
     }
@@ -300,36 +363,63 @@ where
         }
     }
 
+    /// Applies the admission control to a request.
+    fn admit<R: Message>(
+        &self,
+        request: &Request<R>,
+        chain_id: Option<ChainId>,
+        method_name: &str,
+    ) -> Result<Admission, Status> {
+        self.0.admission.admit(
+            method_name,
+            request.remote_addr().map(|address| address.ip()),
+            chain_id,
+            request.get_ref().encoded_len(),
+        )
+    }
+
+    /// Pre-configures the server on the internal network. With mutual TLS, only the shards
+    /// and the operator of this validator can connect.
+    fn internal_server(&self) -> Result<Server> {
+        match &self.0.internal_tls {
+            Some(tls) => Ok(Server::builder().tls_config(tls.server_config())?),
+            None => Ok(Server::builder()),
+        }
+    }
+
     async fn client_for_proxy_worker<R>(
         &self,
         request: Request<R>,
-    ) -> Result<(ValidatorWorkerClient<Channel>, R), Status>
+        method_name: &str,
+    ) -> Result<(ValidatorWorkerClient<Channel>, R, (Admission, ShardRoute)), Status>
     where
-        R: Debug + GrpcProxyable,
+        R: Debug + GrpcProxyable + Message,
     {
         debug!("proxying request from {:?}", request.remote_addr());
+        let admission = self.admit(&request, request.get_ref().chain_id(), method_name)?;
         let inner = request.into_inner();
-        let shard = self
+        let (shard, route) = self
             .shard_for(&inner)
+            .await
             .ok_or_else(|| Status::not_found("could not find shard for message"))?;
         let client = self
             .worker_client_for_shard(&shard)
             .map_err(|_| Status::internal("could not connect to shard"))?;
-        Ok((client, inner))
+        Ok((client, inner, (admission, route)))
     }
 
-    fn log_and_return_proxy_request_outcome(
-        result: Result<Response<ChainInfoResult>, Status>,
+    fn log_and_return_proxy_request_outcome<T>(
+        result: Result<Response<T>, Status>,
         method_name: &str,
-    ) -> Result<Response<ChainInfoResult>, Status> {
+    ) -> Result<Response<T>, Status> {
         #![allow(unused_variables)]
         match result {
-            Ok(chain_info_result) => {
+            Ok(response) => {
                 #[cfg(with_metrics)]
                 PROXY_REQUEST_SUCCESS
                     .with_label_values(&[method_name])
                     .inc();
-                Ok(chain_info_result)
+                Ok(response)
             }
             Err(status) => {
                 #[cfg(with_metrics)]
@@ -352,7 +442,9 @@ where
         &self,
         request: Request<BlockProposal>,
     ) -> Result<Response<ChainInfoResult>, Status> {
-        let (mut client, inner) = self.client_for_proxy_worker(request).await?;
+        let (mut client, inner, _guards) = self
+            .client_for_proxy_worker(request, "handle_block_proposal")
+            .await?;
         Self::log_and_return_proxy_request_outcome(
             client.handle_block_proposal(inner).await,
             "handle_block_proposal",
@@ -364,7 +456,9 @@ where
         &self,
         request: Request<LiteCertificate>,
     ) -> Result<Response<ChainInfoResult>, Status> {
-        let (mut client, inner) = self.client_for_proxy_worker(request).await?;
+        let (mut client, inner, _guards) = self
+            .client_for_proxy_worker(request, "handle_lite_certificate")
+            .await?;
         Self::log_and_return_proxy_request_outcome(
             client.handle_lite_certificate(inner).await,
             "handle_lite_certificate",
@@ -376,19 +470,108 @@ where
         &self,
         request: Request<HandleCertificateRequest>,
     ) -> Result<Response<ChainInfoResult>, Status> {
-        let (mut client, inner) = self.client_for_proxy_worker(request).await?;
+        let (mut client, inner, _guards) = self
+            .client_for_proxy_worker(request, "handle_certificate")
+            .await?;
         Self::log_and_return_proxy_request_outcome(
             client.handle_certificate(inner).await,
             "handle_certificate",
         )
     }
 
+    #[instrument(skip_all, err(Display))]
+    async fn handle_batch(
+        &self,
+        request: Request<BatchRequest>,
+    ) -> Result<Response<BatchResult>, Status> {
+        let client = request.remote_addr().map(|address| address.ip());
+        let request_size = request.get_ref().encoded_len();
+        let BatchRequest {
+            items,
+            wait_for_outgoing_messages,
+        } = request.into_inner();
+        let chain_ids = items
+            .iter()
+            .map(GrpcProxyable::chain_id)
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| Status::not_found("could not find shard for message"))?;
+        let (_admission, admitted) =
+            self.0
+                .admission
+                .admit_batch("handle_batch", client, &chain_ids, request_size)?;
+        // The chains are not migrated while the batch is in progress.
+        let routes = self.0.router.route_all(&chain_ids).await;
+        let num_items = items.len();
+        let mut results = vec![None; num_items];
+        let mut shard_batches = BTreeMap::<ShardId, (Vec<usize>, Vec<BatchItem>)>::new();
+        for (index, ((item, route), admitted)) in
+            items.into_iter().zip(&routes).zip(admitted).enumerate()
+        {
+            if let Err(status) = admitted {
+                // Items rejected by the admission control fail individually.
+                let retry_after_ms = GrpcClient::retry_after(&status).as_millis() as u64;
+                let error = ChainInfoResult::try_from(NodeError::Overloaded { retry_after_ms })
+                    .map_err(|err| Status::from_error(Box::new(err)))?;
+                results[index] = Some(error);
+                continue;
+            }
+            let (indices, items) = shard_batches.entry(route.shard_id()).or_default();
+            indices.push(index);
+            items.push(item);
+        }
+        let shard_results = future::join_all(shard_batches.into_iter().map(
+            |(shard_id, (indices, items))| async move {
+                let shard = self.0.internal_config.shard(shard_id);
+                let request = BatchRequest {
+                    items,
+                    wait_for_outgoing_messages,
+                };
+                let result = match self.worker_client_for_shard(shard) {
+                    Ok(mut client) => client.handle_batch(request).await,
+                    Err(_) => Err(Status::internal("could not connect to shard")),
+                };
+                (indices, result)
+            },
+        ))
+        .await;
+        for (indices, result) in shard_results {
+            let shard_results = match result {
+                Ok(response) => response.into_inner().results,
+                Err(status) => {
+                    // The items of an unavailable shard fail, not the whole batch.
+                    let error = ChainInfoResult::try_from(NodeError::from(status))
+                        .map_err(|err| Status::from_error(Box::new(err)))?;
+                    vec![error; indices.len()]
+                }
+            };
+            if shard_results.len() != indices.len() {
+                return Self::log_and_return_proxy_request_outcome(
+                    Err(Status::internal("unexpected number of results from shard")),
+                    "handle_batch",
+                );
+            }
+            for (index, result) in indices.into_iter().zip(shard_results) {
+                results[index] = Some(result);
+            }
+        }
+        let results = results
+            .into_iter()
+            .map(|result| result.expect("every item is either rejected or in a shard batch"))
+            .collect();
+        Self::log_and_return_proxy_request_outcome(
+            Ok(Response::new(BatchResult { results })),
+            "handle_batch",
+        )
+    }
+
     #[instrument(skip_all, err(Display))]
     async fn handle_chain_info_query(
         &self,
         request: Request<ChainInfoQuery>,
     ) -> Result<Response<ChainInfoResult>, Status> {
-        let (mut client, inner) = self.client_for_proxy_worker(request).await?;
+        let (mut client, inner, _guards) = self
+            .client_for_proxy_worker(request, "handle_chain_info_query")
+            .await?;
         Self::log_and_return_proxy_request_outcome(
             client.handle_chain_info_query(inner).await,
             "handle_chain_info_query",
@@ -400,13 +583,17 @@ where
         &self,
         request: Request<SubscriptionRequest>,
     ) -> Result<Response<Self::SubscribeStream>, Status> {
-        let subscription_request = request.into_inner();
-        let chain_ids = subscription_request
-            .chain_ids
-            .into_iter()
-            .map(ChainId::try_from)
-            .collect::<Result<Vec<ChainId>, _>>()?;
-        let rx = self.0.notifier.subscribe(chain_ids);
+        let _admission = self.admit(&request, None, "subscribe")?;
+        let linera_rpc::SubscriptionRequest {
+            chain_ids,
+            filter,
+            resume_from,
+        } = request.into_inner().try_into()?;
+        let rx = self
+            .0
+            .notifier
+            .resume_with_filter(chain_ids, filter, &resume_from)
+            .map_err(|gap| Status::out_of_range(gap.to_string()))?;
         Ok(Response::new(UnboundedReceiverStream::new(rx)))
     }
 
@@ -432,6 +619,7 @@ where
         &self,
         request: Request<BlobId>,
     ) -> Result<Response<BlobContent>, Status> {
+        let _admission = self.admit(&request, None, "download_blob_content")?;
         let blob_id = request.into_inner().try_into()?;
         let blob = self
             .0
@@ -447,6 +635,7 @@ where
         &self,
         request: Request<CryptoHash>,
     ) -> Result<Response<CertificateValue>, Status> {
+        let _admission = self.admit(&request, None, "download_certificate_value")?;
         let hash = request.into_inner().try_into()?;
         let certificate = self
             .0
@@ -462,6 +651,7 @@ where
         &self,
         request: Request<CryptoHash>,
     ) -> Result<Response<Certificate>, Status> {
+        let _admission = self.admit(&request, None, "download_certificate")?;
         let hash = request.into_inner().try_into()?;
         let certificate = self
             .0
@@ -477,6 +667,7 @@ where
         &self,
         request: Request<CertificatesBatchRequest>,
     ) -> Result<Response<CertificatesBatchResponse>, Status> {
+        let _admission = self.admit(&request, None, "download_certificates")?;
         let hashes: Vec<linera_base::crypto::CryptoHash> = request
             .into_inner()
             .hashes
@@ -517,6 +708,7 @@ where
         &self,
         request: Request<BlobId>,
     ) -> Result<Response<CryptoHash>, Status> {
+        let _admission = self.admit(&request, None, "blob_last_used_by")?;
         let blob_id = request.into_inner().try_into()?;
         let blob_state = self
             .0
@@ -526,6 +718,20 @@ where
             .map_err(|err| Status::from_error(Box::new(err)))?;
         Ok(Response::new(blob_state.last_used_by.into()))
     }
+
+    #[instrument(skip_all, err(Display))]
+    async fn handle_certificate_announcement(
+        &self,
+        request: Request<CertificateAnnouncement>,
+    ) -> Result<Response<()>, Status> {
+        let (mut client, inner, _guards) = self
+            .client_for_proxy_worker(request, "handle_certificate_announcement")
+            .await?;
+        Self::log_and_return_proxy_request_outcome(
+            client.handle_certificate_announcement(inner).await,
+            "handle_certificate_announcement",
+        )
+    }
 }
 
 #[async_trait]
@@ -546,6 +752,78 @@ where
     }
 }
 
+#[async_trait]
+impl<S> ShardAdmin for GrpcProxy<S>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+{
+    #[instrument(skip_all, err(Display))]
+    async fn migrate_chain(
+        &self,
+        request: Request<ChainAssignment>,
+    ) -> Result<Response<()>, Status> {
+        let assignment = request.into_inner();
+        let (chain_id, target): (ChainId, ShardId) = assignment.clone().try_into()?;
+        let shards = &self.0.internal_config.shards;
+        if target >= shards.len() {
+            return Err(Status::invalid_argument(format!(
+                "invalid shard ID {target}"
+            )));
+        }
+        let source = self.0.router.shard_id(chain_id);
+        if source == target {
+            return Ok(Response::new(()));
+        }
+        info!(%chain_id, source, target, "Migrating chain");
+        // Requests for the chain are held back until the old shard has released it and
+        // the other shards know where to send cross-chain requests, and the new assignment
+        // is recorded in the server configuration. A failed migration
+        // can be retried: all the steps are idempotent.
+        let hand_off = async {
+            let mut client = self
+                .worker_client_for_shard(&shards[source])
+                .map_err(|_| Status::internal("could not connect to shard"))?;
+            client.drain_chain(assignment.clone()).await?;
+            for (shard_id, shard) in shards.iter().enumerate() {
+                if shard_id == source {
+                    continue;
+                }
+                let mut client = self
+                    .worker_client_for_shard(shard)
+                    .map_err(|_| Status::internal("could not connect to shard"))?;
+                client.reassign_chain(assignment.clone()).await?;
+            }
+            if let Some(path) = &self.0.server_config_path {
+                let path = path.lock().await;
+                record_assignment(&path, chain_id, target)
+                    .await
+                    .map_err(|error| {
+                        Status::internal(format!("could not record the new assignment: {error}"))
+                    })?;
+            }
+            Ok::<_, Status>(())
+        };
+        self.0.router.reassign(chain_id, target, hand_off).await?;
+        Ok(Response::new(()))
+    }
+}
+
+/// Records the shard of a chain in the server configuration, which the proxy and the
+/// shards read when they start.
+async fn record_assignment(path: &Path, chain_id: ChainId, shard_id: ShardId) -> Result<()> {
+    let mut server_config = persistent::File::<ValidatorServerConfig>::read(path)?;
+    server_config
+        .mutate(|config| {
+            config
+                .internal_network
+                .shard_assignment
+                .overrides
+                .insert(chain_id, shard_id)
+        })
+        .await?;
+    Ok(())
+}
+
 /// A message limiter that keeps track of the remaining capacity in bytes.
 struct GrpcMessageLimiter<T> {
     remaining: usize,
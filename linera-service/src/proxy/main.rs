@@ -3,23 +3,32 @@
 
 #![deny(clippy::large_futures)]
 
-use std::{net::SocketAddr, path::PathBuf, time::Duration};
+use std::{
+    collections::BTreeMap, net::SocketAddr, num::NonZeroUsize, path::PathBuf, time::Duration,
+};
 
 use anyhow::{bail, Result};
 use async_trait::async_trait;
-use futures::{FutureExt as _, SinkExt, StreamExt};
+use futures::{future, FutureExt as _, SinkExt, StreamExt};
 use linera_client::{
     config::{GenesisConfig, ValidatorServerConfig},
     storage::{run_with_storage, Runnable, StorageConfigNamespace},
 };
-use linera_core::{node::NodeError, JoinSetExt as _};
+use linera_core::{
+    data_types::ChainInfoResponse,
+    node::{BatchItem, NodeError},
+    notifier::ReplayConfig,
+    JoinSetExt as _,
+};
 use linera_rpc::{
     config::{
-        NetworkProtocol, ShardConfig, ValidatorInternalNetworkPreConfig,
+        NetworkProtocol, ShardConfig, ShardId, ValidatorInternalNetworkPreConfig,
         ValidatorPublicNetworkPreConfig,
     },
-    simple::{MessageHandler, TransportProtocol},
-    RpcMessage,
+    grpc::InternalTls,
+    sharding::ShardRouter,
+    simple::{Connector, MessageHandler, TransportProtocol},
+    HandleBatchRequest, RpcMessage,
 };
 #[cfg(with_metrics)]
 use linera_service::prometheus_server;
@@ -30,7 +39,10 @@ use tokio::task::JoinSet;
 use tokio_util::sync::CancellationToken;
 use tracing::{error, info, instrument};
 
+mod admission;
 mod grpc;
+mod ws;
+use admission::AdmissionConfig;
 use grpc::GrpcProxy;
 
 /// Options for running the proxy.
@@ -75,6 +87,24 @@ pub struct ProxyOptions {
     /// Path to the file describing the initial user chains (aka genesis state)
     #[arg(long = "genesis")]
     genesis_config_path: PathBuf,
+
+    /// Limits on the requests accepted by the gRPC proxy
+    #[command(flatten)]
+    admission_config: AdmissionConfig,
+
+    /// The number of recent notifications kept for each chain, so that interrupted
+    /// subscriptions can be resumed.
+    #[arg(long, default_value = "64")]
+    notification_replay_size: NonZeroUsize,
+
+    /// The maximal number of chains without subscribers whose recent notifications are kept.
+    #[arg(long, default_value = "10000")]
+    notification_replay_chains: NonZeroUsize,
+
+    /// The port of a WebSocket endpoint serving the public API, e.g. to browsers. Only
+    /// supported by the gRPC proxy.
+    #[arg(long)]
+    websocket_port: Option<u16>,
 }
 
 /// A Linera Proxy, either gRPC or over 'Simple Transport', meaning TCP or UDP.
@@ -90,9 +120,13 @@ where
 
 struct ProxyContext {
     config: ValidatorServerConfig,
+    config_path: PathBuf,
     genesis_config: GenesisConfig,
     send_timeout: Duration,
     recv_timeout: Duration,
+    admission_config: AdmissionConfig,
+    replay_config: ReplayConfig,
+    websocket_port: Option<u16>,
 }
 
 impl ProxyContext {
@@ -101,9 +135,16 @@ impl ProxyContext {
         let genesis_config = util::read_json(&options.genesis_config_path)?;
         Ok(Self {
             config,
+            config_path: options.config_path.clone(),
             send_timeout: options.send_timeout,
             recv_timeout: options.recv_timeout,
             genesis_config,
+            admission_config: options.admission_config.clone(),
+            replay_config: ReplayConfig {
+                notifications_per_chain: options.notification_replay_size,
+                idle_chains: options.notification_replay_chains,
+            },
+            websocket_port: options.websocket_port,
         })
     }
 }
@@ -136,6 +177,7 @@ where
         let external_protocol = context.config.validator.network.protocol;
         let proxy = match (internal_protocol, external_protocol) {
             (NetworkProtocol::Grpc { .. }, NetworkProtocol::Grpc(tls)) => {
+                let internal_tls = InternalTls::load(&context.config.internal_network)?;
                 Self::Grpc(GrpcProxy::new(
                     context.config.validator.network,
                     context.config.internal_network,
@@ -143,13 +185,24 @@ where
                     context.send_timeout,
                     context.recv_timeout,
                     tls,
+                    internal_tls,
                     storage,
+                    &context.admission_config,
+                    context.replay_config,
+                    context.websocket_port,
+                    Some(context.config_path),
                 ))
             }
+            (NetworkProtocol::Simple(_), NetworkProtocol::Simple(_))
+                if context.websocket_port.is_some() =>
+            {
+                bail!("the WebSocket endpoint is only supported by the gRPC proxy");
+            }
             (
                 NetworkProtocol::Simple(internal_transport),
                 NetworkProtocol::Simple(public_transport),
             ) => Self::Simple(Box::new(SimpleProxy {
+                router: ShardRouter::new(&context.config.internal_network),
                 internal_config: context
                     .config
                     .internal_network
@@ -159,6 +212,7 @@ where
                     .validator
                     .network
                     .clone_with_protocol(public_transport),
+                shard_connector: Connector::new(internal_transport),
                 genesis_config: context.genesis_config,
                 send_timeout: context.send_timeout,
                 recv_timeout: context.recv_timeout,
@@ -184,6 +238,8 @@ where
 {
     public_config: ValidatorPublicNetworkPreConfig<TransportProtocol>,
     internal_config: ValidatorInternalNetworkPreConfig<TransportProtocol>,
+    router: ShardRouter,
+    shard_connector: Connector,
     genesis_config: GenesisConfig,
     send_timeout: Duration,
     recv_timeout: Duration,
@@ -209,18 +265,23 @@ where
             }
         }
 
+        if let RpcMessage::Batch(request) = message {
+            return Some(self.proxy_batch(*request).await.into());
+        }
+
         let Some(chain_id) = message.target_chain_id() else {
             error!("Can't proxy message without chain ID");
             return None;
         };
 
-        let shard = self.internal_config.get_shard_for(chain_id).clone();
-        let protocol = self.internal_config.protocol;
+        // The chain is not migrated while the message is being proxied.
+        let route = self.router.route(chain_id).await;
+        let shard = self.internal_config.shard(route.shard_id()).clone();
 
         match Self::try_proxy_message(
             message,
             shard.clone(),
-            protocol,
+            &self.shard_connector,
             self.send_timeout,
             self.recv_timeout,
         )
@@ -274,11 +335,11 @@ where
     async fn try_proxy_message(
         message: RpcMessage,
         shard: ShardConfig,
-        protocol: TransportProtocol,
+        connector: &Connector,
         send_timeout: Duration,
         recv_timeout: Duration,
     ) -> Result<Option<RpcMessage>> {
-        let mut connection = protocol.connect((shard.host, shard.port)).await?;
+        let mut connection = connector.connect((shard.host, shard.port)).await?;
         linera_base::time::timer::timeout(send_timeout, connection.send(message)).await??;
         let message = linera_base::time::timer::timeout(recv_timeout, connection.next())
             .await?
@@ -286,6 +347,75 @@ where
         Ok(message)
     }
 
+    /// Splits a batch into one batch per shard, sends them concurrently, and returns the
+    /// results of the items in their original order.
+    async fn proxy_batch(
+        &self,
+        request: HandleBatchRequest,
+    ) -> Vec<Result<ChainInfoResponse, NodeError>> {
+        let wait_for_outgoing_messages = request.wait_for_outgoing_messages;
+        let mut results = vec![Err(NodeError::UnexpectedMessage); request.items.len()];
+        let chain_ids = request
+            .items
+            .iter()
+            .map(BatchItem::chain_id)
+            .collect::<Vec<_>>();
+        // None of the chains is migrated while the batch is being proxied.
+        let routes = self.router.route_all(&chain_ids).await;
+        let mut shard_batches = BTreeMap::<ShardId, (Vec<usize>, Vec<BatchItem>)>::new();
+        for ((index, item), route) in request.items.into_iter().enumerate().zip(&routes) {
+            let (indices, items) = shard_batches.entry(route.shard_id()).or_default();
+            indices.push(index);
+            items.push(item);
+        }
+        let shard_results = future::join_all(shard_batches.into_iter().map(
+            |(shard_id, (indices, items))| async move {
+                let shard = self.internal_config.shard(shard_id).clone();
+                let message = HandleBatchRequest {
+                    items,
+                    wait_for_outgoing_messages,
+                }
+                .into();
+                let result = match Self::try_proxy_message(
+                    message,
+                    shard.clone(),
+                    &self.shard_connector,
+                    self.send_timeout,
+                    self.recv_timeout,
+                )
+                .await
+                {
+                    Ok(Some(response)) => Vec::try_from(response),
+                    Ok(None) => Err(NodeError::UnexpectedMessage),
+                    Err(error) => {
+                        error!(error = %error, "Failed to proxy batch to {}", shard.address());
+                        Err(NodeError::ClientIoError {
+                            error: error.to_string(),
+                        })
+                    }
+                };
+                (indices, result)
+            },
+        ))
+        .await;
+        for (indices, result) in shard_results {
+            match result {
+                Ok(shard_results) if shard_results.len() == indices.len() => {
+                    for (index, result) in indices.into_iter().zip(shard_results) {
+                        results[index] = result;
+                    }
+                }
+                Ok(_) => error!("Unexpected number of results from shard"),
+                Err(error) => {
+                    for index in indices {
+                        results[index] = Err(error.clone());
+                    }
+                }
+            }
+        }
+        results
+    }
+
     async fn try_local_message(&self, message: RpcMessage) -> Result<Option<RpcMessage>> {
         use RpcMessage::*;
 
@@ -320,9 +450,14 @@ where
             BlobLastUsedBy(blob_id) => Ok(Some(RpcMessage::BlobLastUsedByResponse(Box::new(
                 self.storage.read_blob_state(*blob_id).await?.last_used_by,
             )))),
+            Subscribe(_) => Err(anyhow::Error::from(NodeError::SubscriptionError {
+                transport: self.public_config.protocol.to_string(),
+            })),
             BlockProposal(_)
             | LiteCertificate(_)
             | Certificate(_)
+            | Batch(_)
+            | BatchResponse(_)
             | ChainInfoQuery(_)
             | CrossChainRequest(_)
             | Vote(_)
@@ -334,9 +469,8 @@ where
             | BlobLastUsedByResponse(_)
             | DownloadCertificateValueResponse(_)
             | DownloadCertificateResponse(_)
-            | DownloadCertificatesResponse(_) => {
-                Err(anyhow::Error::from(NodeError::UnexpectedMessage))
-            }
+            | DownloadCertificatesResponse(_)
+            | Notification(_) => Err(anyhow::Error::from(NodeError::UnexpectedMessage)),
         }
     }
 }
@@ -0,0 +1,636 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The WebSocket endpoint of the proxy, for clients that cannot use gRPC, e.g. browsers.
+//! Each binary frame carries a bincode-serialized [`RpcMessage`]. Requests are served by
+//! the handlers of the gRPC API, so the same admission control applies.
+
+use std::net::SocketAddr;
+
+use anyhow::Result;
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        ConnectInfo, State,
+    },
+    response::Response as HttpResponse,
+    routing::get,
+    Router,
+};
+use futures::StreamExt as _;
+use linera_base::{crypto::CryptoHash, data_types::BlobContent};
+use linera_chain::data_types::{Certificate, CertificateValue};
+use linera_core::{node::NodeError, worker::Notification};
+use linera_rpc::{
+    grpc::{
+        api::{self, validator_node_server::ValidatorNode as _},
+        GrpcClient,
+    },
+    RpcMessage, SubscriptionRequest,
+};
+use linera_storage::Storage;
+use tokio::{net::TcpListener, select};
+use tokio_util::sync::CancellationToken;
+use tonic::{transport::server::TcpConnectInfo, Code, Request, Response, Status};
+use tracing::{debug, instrument};
+
+use crate::grpc::GrpcProxy;
+
+impl<S> GrpcProxy<S>
+where
+    S: Storage + Clone + Send + Sync + 'static,
+{
+    /// Serves the WebSocket endpoint on the given listener until the shutdown signal.
+    pub async fn serve_websocket(
+        self,
+        listener: TcpListener,
+        shutdown_signal: CancellationToken,
+    ) -> Result<()> {
+        let router = Router::new()
+            .route("/", get(Self::upgrade))
+            .with_state(self);
+        axum::serve(
+            listener,
+            router.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(shutdown_signal.cancelled_owned())
+        .await?;
+        Ok(())
+    }
+
+    async fn upgrade(
+        State(proxy): State<Self>,
+        ConnectInfo(remote_addr): ConnectInfo<SocketAddr>,
+        upgrade: WebSocketUpgrade,
+    ) -> HttpResponse {
+        upgrade.on_upgrade(move |socket| proxy.handle_websocket(socket, remote_addr))
+    }
+
+    /// Answers the requests received on a WebSocket, one at a time, until the client
+    /// closes it or subscribes to notifications.
+    #[instrument(skip_all, fields(%remote_addr))]
+    async fn handle_websocket(self, mut socket: WebSocket, remote_addr: SocketAddr) {
+        while let Some(Ok(frame)) = socket.recv().await {
+            let Message::Binary(bytes) = frame else {
+                continue;
+            };
+            let response = match bincode::deserialize(&bytes) {
+                Ok(RpcMessage::Subscribe(request)) => {
+                    return Box::pin(self.forward_notifications(socket, *request, remote_addr))
+                        .await;
+                }
+                Ok(message) => Box::pin(self.handle_message(message, remote_addr))
+                    .await
+                    .unwrap_or_else(|status| node_error(status).into()),
+                Err(_) => NodeError::InvalidDecoding.into(),
+            };
+            if send(&mut socket, &response).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Sends the notifications requested by a client until it closes the WebSocket.
+    async fn forward_notifications(
+        self,
+        mut socket: WebSocket,
+        request: SubscriptionRequest,
+        remote_addr: SocketAddr,
+    ) {
+        let subscription = match api::SubscriptionRequest::try_from(request) {
+            Ok(request) => self.subscribe(with_remote_addr(request, remote_addr)).await,
+            Err(error) => Err(error.into()),
+        };
+        let mut notifications = match subscription {
+            Ok(response) => response.into_inner(),
+            Err(status) => {
+                let error = NodeError::SubscriptionFailed {
+                    status: status.to_string(),
+                };
+                let _ = send(&mut socket, &error.into()).await;
+                return;
+            }
+        };
+        loop {
+            select! {
+                notification = notifications.next() => {
+                    let Some(notification) = notification else {
+                        break;
+                    };
+                    let message = match notification.map(Notification::try_from) {
+                        Ok(Ok(notification)) => notification.into(),
+                        Ok(Err(error)) => NodeError::from(error).into(),
+                        Err(status) => node_error(status).into(),
+                    };
+                    if send(&mut socket, &message).await.is_err() {
+                        break;
+                    }
+                }
+                frame = socket.recv() => {
+                    if matches!(frame, None | Some(Err(_)) | Some(Ok(Message::Close(_)))) {
+                        break;
+                    }
+                }
+            }
+        }
+        debug!("Subscription closed");
+    }
+
+    /// Handles a request with the corresponding gRPC handler.
+    async fn handle_message(
+        &self,
+        message: RpcMessage,
+        remote_addr: SocketAddr,
+    ) -> Result<RpcMessage, Status> {
+        let response = match message {
+            RpcMessage::BlockProposal(proposal) => {
+                let request = with_remote_addr((*proposal).try_into()?, remote_addr);
+                chain_info(self.handle_block_proposal(request).await?)
+            }
+            RpcMessage::LiteCertificate(request) => {
+                let request = with_remote_addr((*request).try_into()?, remote_addr);
+                chain_info(self.handle_lite_certificate(request).await?)
+            }
+            RpcMessage::Certificate(request) => {
+                let request = with_remote_addr((*request).try_into()?, remote_addr);
+                chain_info(self.handle_certificate(request).await?)
+            }
+            RpcMessage::Batch(request) => {
+                let request = with_remote_addr((*request).try_into()?, remote_addr);
+                let results = self.handle_batch(request).await?.into_inner().results;
+                results
+                    .into_iter()
+                    .map(GrpcClient::try_into_chain_info)
+                    .collect::<Vec<_>>()
+                    .into()
+            }
+            RpcMessage::ChainInfoQuery(query) => {
+                let request = with_remote_addr((*query).try_into()?, remote_addr);
+                chain_info(self.handle_chain_info_query(request).await?)
+            }
+            RpcMessage::DownloadBlobContent(blob_id) => {
+                let request = with_remote_addr((*blob_id).try_into()?, remote_addr);
+                let response = self.download_blob_content(request).await?;
+                BlobContent::try_from(response.into_inner())?.into()
+            }
+            RpcMessage::DownloadCertificateValue(hash) => {
+                let request = with_remote_addr((*hash).into(), remote_addr);
+                let response = self.download_certificate_value(request).await?;
+                CertificateValue::try_from(response.into_inner())?.into()
+            }
+            RpcMessage::DownloadCertificate(hash) => {
+                let request = with_remote_addr((*hash).into(), remote_addr);
+                let response = self.download_certificate(request).await?;
+                Certificate::try_from(response.into_inner())?.into()
+            }
+            RpcMessage::DownloadCertificates(hashes) => {
+                let request = with_remote_addr((*hashes).into(), remote_addr);
+                let response = self.download_certificates(request).await?;
+                Vec::<Certificate>::try_from(response.into_inner())?.into()
+            }
+            RpcMessage::BlobLastUsedBy(blob_id) => {
+                let request = with_remote_addr((*blob_id).try_into()?, remote_addr);
+                let response = self.blob_last_used_by(request).await?;
+                let hash = CryptoHash::try_from(response.into_inner())?;
+                RpcMessage::BlobLastUsedByResponse(Box::new(hash))
+            }
+            RpcMessage::VersionInfoQuery => {
+                let response = self.get_version_info(Request::new(())).await?;
+                linera_version::VersionInfo::from(response.into_inner()).into()
+            }
+            RpcMessage::GenesisConfigHashQuery => {
+                let response = self.get_genesis_config_hash(Request::new(())).await?;
+                let hash = CryptoHash::try_from(response.into_inner())?;
+                RpcMessage::GenesisConfigHashResponse(Box::new(hash))
+            }
+            RpcMessage::Subscribe(_)
+            | RpcMessage::CrossChainRequest(_)
+            | RpcMessage::Vote(_)
+            | RpcMessage::ChainInfoResponse(_)
+            | RpcMessage::Error(_)
+            | RpcMessage::VersionInfoResponse(_)
+            | RpcMessage::GenesisConfigHashResponse(_)
+            | RpcMessage::DownloadBlobContentResponse(_)
+            | RpcMessage::DownloadCertificateValueResponse(_)
+            | RpcMessage::DownloadCertificateResponse(_)
+            | RpcMessage::DownloadCertificatesResponse(_)
+            | RpcMessage::BlobLastUsedByResponse(_)
+            | RpcMessage::BatchResponse(_)
+            | RpcMessage::Notification(_) => NodeError::UnexpectedMessage.into(),
+        };
+        Ok(response)
+    }
+}
+
+/// Wraps a message into a request from the given client address, for admission control.
+fn with_remote_addr<T>(message: T, remote_addr: SocketAddr) -> Request<T> {
+    let mut request = Request::new(message);
+    request.extensions_mut().insert(TcpConnectInfo {
+        local_addr: None,
+        remote_addr: Some(remote_addr),
+    });
+    request
+}
+
+fn chain_info(response: Response<api::ChainInfoResult>) -> RpcMessage {
+    match GrpcClient::try_into_chain_info(response.into_inner()) {
+        Ok(response) => response.into(),
+        Err(error) => error.into(),
+    }
+}
+
+/// Converts the status of a failed gRPC handler to the error returned to the client.
+fn node_error(status: Status) -> NodeError {
+    if status.code() == Code::ResourceExhausted {
+        let retry_after_ms = GrpcClient::retry_after(&status).as_millis() as u64;
+        NodeError::Overloaded { retry_after_ms }
+    } else {
+        status.into()
+    }
+}
+
+async fn send(socket: &mut WebSocket, message: &RpcMessage) -> Result<(), axum::Error> {
+    let bytes = bincode::serialize(message).expect("messages should be serializable");
+    socket.send(Message::Binary(bytes)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        num::{NonZeroU32, NonZeroUsize},
+        path::PathBuf,
+        time::Duration,
+    };
+
+    use clap::Parser as _;
+    use futures::StreamExt as _;
+    use linera_base::{
+        crypto::{CryptoHash, KeyPair},
+        data_types::{Amount, BlockHeight, Timestamp},
+        identifiers::{ChainDescription, ChainId},
+    };
+    use linera_chain::test::{make_first_block, BlockTestExt as _};
+    use linera_client::{
+        config::{CommitteeConfig, GenesisConfig, ValidatorConfig, ValidatorServerConfig},
+        persistent::{self, Persist as _},
+    };
+    use linera_core::{
+        node::{
+            CrossChainMessageDelivery, NodeError, ValidatorNode as _, ValidatorNodeProvider as _,
+        },
+        notifier::{NotificationFilter, ReplayConfig},
+        worker::{Notification, Reason, WorkerState},
+    };
+    use linera_execution::{
+        committee::{Committee, ValidatorName},
+        ResourceControlPolicy,
+    };
+    use linera_rpc::{
+        config::{
+            CrossChainConfig, GossipConfig, NetworkProtocol, NotificationConfig, ShardConfig,
+            TlsConfig, ValidatorInternalNetworkConfig, ValidatorPublicNetworkConfig,
+        },
+        grpc::{
+            api::{
+                notifier_service_server::NotifierService as _, shard_admin_server::ShardAdmin as _,
+                validator_worker_client::ValidatorWorkerClient, ChainAssignment,
+            },
+            GrpcServer,
+        },
+        sharding::ShardRouter,
+        ws::{WebSocketClient, WebSocketNodeProvider},
+        NodeOptions,
+    };
+    use linera_service::util;
+    use linera_storage::{DbStorage, Storage as _, TestClock};
+    use linera_views::memory::MemoryStore;
+    use tokio::{net::TcpListener, task::JoinSet, time::timeout};
+    use tokio_util::sync::CancellationToken;
+    use tonic::Request;
+
+    use crate::{admission::AdmissionConfig, grpc::GrpcProxy};
+
+    type TestStorage = DbStorage<MemoryStore, TestClock>;
+
+    fn test_genesis_config() -> GenesisConfig {
+        GenesisConfig::new(
+            CommitteeConfig { validators: vec![] },
+            ChainId::root(0),
+            Timestamp::from(0),
+            ResourceControlPolicy::default(),
+            "test".to_string(),
+        )
+    }
+
+    fn test_internal_config(shard_ports: &[u16]) -> ValidatorInternalNetworkConfig {
+        let shards = shard_ports
+            .iter()
+            .map(|&port| ShardConfig {
+                host: "127.0.0.1".to_string(),
+                port,
+                metrics_host: "127.0.0.1".to_string(),
+                metrics_port: None,
+            })
+            .collect();
+        ValidatorInternalNetworkConfig {
+            protocol: NetworkProtocol::Grpc(TlsConfig::ClearText),
+            shards,
+            shard_assignment: Default::default(),
+            tls: None,
+            host: "127.0.0.1".to_string(),
+            port: 0,
+            metrics_host: "127.0.0.1".to_string(),
+            metrics_port: 0,
+        }
+    }
+
+    fn test_public_config() -> ValidatorPublicNetworkConfig {
+        ValidatorPublicNetworkConfig {
+            protocol: NetworkProtocol::Grpc(TlsConfig::ClearText),
+            host: "127.0.0.1".to_string(),
+            port: 0,
+        }
+    }
+
+    /// Starts the given number of shards of a validator, and waits until they accept
+    /// connections.
+    async fn start_shards(
+        storage: &TestStorage,
+        validator_key_pair: &KeyPair,
+        num_shards: usize,
+        shutdown_signal: &CancellationToken,
+        join_set: &mut JoinSet<()>,
+    ) -> ValidatorInternalNetworkConfig {
+        let mut shard_ports = Vec::new();
+        for _ in 0..num_shards {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            shard_ports.push(listener.local_addr().unwrap().port());
+        }
+        let internal_config = test_internal_config(&shard_ports);
+        for (shard_id, shard_port) in shard_ports.into_iter().enumerate() {
+            let worker = WorkerState::new(
+                format!("shard {shard_id}"),
+                Some(validator_key_pair.copy()),
+                storage.clone(),
+                NonZeroUsize::new(10).unwrap(),
+            );
+            let _shard = GrpcServer::spawn(
+                "127.0.0.1".to_string(),
+                shard_port,
+                worker,
+                shard_id,
+                internal_config.clone(),
+                CrossChainConfig::parse_from(["test"]),
+                NotificationConfig::parse_from(["test"]),
+                GossipConfig::parse_from(["test"]),
+                None,
+                shutdown_signal.clone(),
+                join_set,
+            );
+            while ValidatorWorkerClient::connect(format!("http://127.0.0.1:{shard_port}"))
+                .await
+                .is_err()
+            {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }
+        internal_config
+    }
+
+    /// Starts a proxy in front of the given shards, serving WebSockets, and returns it
+    /// together with a client connected to it.
+    async fn start_proxy(
+        storage: TestStorage,
+        genesis_config: GenesisConfig,
+        internal_config: ValidatorInternalNetworkConfig,
+        admission_config: &AdmissionConfig,
+        server_config_path: Option<PathBuf>,
+        shutdown_signal: &CancellationToken,
+    ) -> (GrpcProxy<TestStorage>, WebSocketClient) {
+        let public_config = test_public_config();
+        let replay_config = ReplayConfig {
+            notifications_per_chain: NonZeroUsize::new(10).unwrap(),
+            idle_chains: NonZeroUsize::new(10).unwrap(),
+        };
+        let proxy = GrpcProxy::new(
+            public_config,
+            internal_config,
+            genesis_config,
+            Duration::from_secs(1),
+            Duration::from_secs(1),
+            TlsConfig::ClearText,
+            None,
+            storage,
+            admission_config,
+            replay_config,
+            None,
+            server_config_path,
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(
+            proxy
+                .clone()
+                .serve_websocket(listener, shutdown_signal.clone()),
+        );
+
+        let node = WebSocketNodeProvider::new(NodeOptions {
+            send_timeout: Duration::from_secs(1),
+            recv_timeout: Duration::from_secs(1),
+            retry_delay: Duration::from_millis(10),
+            max_retries: 3,
+        })
+        .make_node(&format!("ws:127.0.0.1:{port}"))
+        .unwrap();
+        (proxy, node)
+    }
+
+    #[tokio::test]
+    async fn test_websocket_endpoint() {
+        let storage = TestStorage::make_test_storage(None).await;
+        let genesis_config = test_genesis_config();
+        let shutdown_signal = CancellationToken::new();
+        let (proxy, node) = start_proxy(
+            storage,
+            genesis_config.clone(),
+            test_internal_config(&[0]),
+            &AdmissionConfig::default(),
+            None,
+            &shutdown_signal,
+        )
+        .await;
+        assert_eq!(
+            node.get_version_info().await.unwrap(),
+            linera_version::VersionInfo::default()
+        );
+        assert_eq!(
+            node.get_genesis_config_hash().await.unwrap(),
+            genesis_config.hash()
+        );
+        let missing = CryptoHash::test_hash("missing");
+        assert!(matches!(
+            node.download_certificate(missing).await,
+            Err(NodeError::GrpcError { .. })
+        ));
+
+        let chain_id = ChainId::root(0);
+        let mut notifications = node
+            .subscribe(vec![chain_id], NotificationFilter::default())
+            .await
+            .unwrap();
+        let notification = Notification {
+            chain_id,
+            reason: Reason::NewBlock {
+                height: BlockHeight(0),
+                hash: missing,
+            },
+            sequence_number: 1,
+        };
+        // The subscription may not be registered yet when the request was sent.
+        let received = loop {
+            let request = Request::new(notification.clone().try_into().unwrap());
+            proxy.notify(request).await.unwrap();
+            if let Ok(received) = timeout(Duration::from_millis(100), notifications.next()).await {
+                break received;
+            }
+        };
+        assert_eq!(received, Some(notification));
+        shutdown_signal.cancel();
+    }
+
+    #[tokio::test]
+    async fn test_batch_results() {
+        let storage = TestStorage::make_test_storage(None).await;
+        let validator_key_pair = KeyPair::generate();
+        let committee = Committee::make_simple(vec![ValidatorName(validator_key_pair.public())]);
+        let owner_key_pair = KeyPair::generate();
+        let chain_id = ChainId::root(1);
+        storage
+            .create_chain(
+                committee,
+                ChainId::root(0),
+                ChainDescription::Root(1),
+                owner_key_pair.public(),
+                Amount::from_tokens(10),
+                Timestamp::from(0),
+            )
+            .await
+            .unwrap();
+
+        let shutdown_signal = CancellationToken::new();
+        let mut join_set = JoinSet::new();
+        let internal_config = start_shards(
+            &storage,
+            &validator_key_pair,
+            1,
+            &shutdown_signal,
+            &mut join_set,
+        )
+        .await;
+
+        // Only one request per chain is accepted at once.
+        let admission_config = AdmissionConfig {
+            max_requests_per_second_per_chain: NonZeroU32::new(1),
+            request_burst_size: 1,
+            max_batch_size: Some(3),
+            ..AdmissionConfig::default()
+        };
+        let (_proxy, node) = start_proxy(
+            storage,
+            test_genesis_config(),
+            internal_config,
+            &admission_config,
+            None,
+            &shutdown_signal,
+        )
+        .await;
+
+        let proposal = make_first_block(chain_id)
+            .with_simple_transfer(ChainId::root(2), Amount::ONE)
+            .into_fast_proposal(&owner_key_pair);
+        let inactive_proposal = make_first_block(ChainId::root(3))
+            .with_simple_transfer(ChainId::root(2), Amount::ONE)
+            .into_fast_proposal(&owner_key_pair);
+        let items = vec![
+            proposal.clone().into(),
+            proposal.clone().into(),
+            inactive_proposal.into(),
+        ];
+        let results = node
+            .handle_batch(items, CrossChainMessageDelivery::NonBlocking)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 3);
+        // The first proposal is voted for by the shard.
+        let info = results[0].as_ref().unwrap();
+        assert_eq!(info.info.chain_id, chain_id);
+        assert!(info.info.manager.pending.is_some());
+        // The second one is rejected by the proxy, without affecting the others...
+        assert!(matches!(results[1], Err(NodeError::Overloaded { .. })));
+        // ... and the third one by the shard, since its chain does not exist.
+        assert!(results[2].is_err());
+        assert!(!matches!(results[2], Err(NodeError::Overloaded { .. })));
+
+        // Batches above the limit are rejected as a whole.
+        let items = vec![proposal.into(); 4];
+        assert!(node
+            .handle_batch(items, CrossChainMessageDelivery::NonBlocking)
+            .await
+            .is_err());
+        shutdown_signal.cancel();
+    }
+
+    #[tokio::test]
+    async fn test_migration_survives_restart() {
+        let storage = TestStorage::make_test_storage(None).await;
+        let validator_key_pair = KeyPair::generate();
+        let shutdown_signal = CancellationToken::new();
+        let mut join_set = JoinSet::new();
+        let internal_config = start_shards(
+            &storage,
+            &validator_key_pair,
+            2,
+            &shutdown_signal,
+            &mut join_set,
+        )
+        .await;
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("server.json");
+        let server_config = ValidatorServerConfig {
+            validator: ValidatorConfig {
+                name: ValidatorName(validator_key_pair.public()),
+                network: test_public_config(),
+                bls_key: None,
+            },
+            key: validator_key_pair,
+            bls_key: None,
+            internal_network: internal_config.clone(),
+        };
+        persistent::File::new(&path, server_config)
+            .unwrap()
+            .persist()
+            .await
+            .unwrap();
+
+        let (proxy, _node) = start_proxy(
+            storage,
+            test_genesis_config(),
+            internal_config.clone(),
+            &AdmissionConfig::default(),
+            Some(path.clone()),
+            &shutdown_signal,
+        )
+        .await;
+        let chain_id = ChainId::root(1);
+        let target = 1 - internal_config.get_shard_id(chain_id);
+        let request = Request::new(ChainAssignment::from((chain_id, target)));
+        proxy.migrate_chain(request).await.unwrap();
+
+        // After a restart, the proxy and the shards read the new assignment from the
+        // server configuration.
+        let server_config: ValidatorServerConfig = util::read_json(&path).unwrap();
+        let router = ShardRouter::new(&server_config.internal_network);
+        assert_eq!(router.shard_id(chain_id), target);
+        shutdown_signal.cancel();
+    }
+}
@@ -18,10 +18,12 @@ use linera_client::{
 use linera_core::{
     client::ChainClient,
     data_types::{ChainInfoQuery, ChainInfoResponse},
+    gossip::CertificateAnnouncement,
     node::{
-        CrossChainMessageDelivery, NodeError, NotificationStream, ValidatorNode,
+        BatchItem, CrossChainMessageDelivery, NodeError, NotificationStream, ValidatorNode,
         ValidatorNodeProvider,
     },
+    notifier::NotificationFilter,
 };
 use linera_execution::committee::{Committee, ValidatorName};
 use linera_service::node_service::NodeService;
@@ -59,6 +61,14 @@ impl ValidatorNode for DummyValidatorNode {
         Err(NodeError::UnexpectedMessage)
     }
 
+    async fn handle_batch(
+        &self,
+        _: Vec<BatchItem>,
+        _: CrossChainMessageDelivery,
+    ) -> Result<Vec<Result<ChainInfoResponse, NodeError>>, NodeError> {
+        Err(NodeError::UnexpectedMessage)
+    }
+
     async fn handle_chain_info_query(
         &self,
         _: ChainInfoQuery,
@@ -66,7 +76,11 @@ impl ValidatorNode for DummyValidatorNode {
         Err(NodeError::UnexpectedMessage)
     }
 
-    async fn subscribe(&self, _: Vec<ChainId>) -> Result<NotificationStream, NodeError> {
+    async fn subscribe(
+        &self,
+        _: Vec<ChainId>,
+        _: NotificationFilter,
+    ) -> Result<NotificationStream, NodeError> {
         Err(NodeError::UnexpectedMessage)
     }
 
@@ -103,6 +117,13 @@ impl ValidatorNode for DummyValidatorNode {
     async fn blob_last_used_by(&self, _: BlobId) -> Result<CryptoHash, NodeError> {
         Err(NodeError::UnexpectedMessage)
     }
+
+    async fn handle_certificate_announcement(
+        &self,
+        _: CertificateAnnouncement,
+    ) -> Result<(), NodeError> {
+        Err(NodeError::UnexpectedMessage)
+    }
 }
 
 struct DummyValidatorNodeProvider;
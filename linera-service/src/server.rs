@@ -14,20 +14,28 @@ use std::{
 use anyhow::bail;
 use async_trait::async_trait;
 use futures::{stream::FuturesUnordered, FutureExt as _, StreamExt, TryFutureExt as _};
-use linera_base::crypto::{CryptoRng, KeyPair};
+use linera_base::{
+    crypto::{BlsKeyPair, CryptoRng, KeyPair},
+    identifiers::ChainId,
+};
 use linera_client::{
     config::{CommitteeConfig, GenesisConfig, ValidatorConfig, ValidatorServerConfig},
     persistent::{self, Persist},
     storage::{full_initialize_storage, run_with_storage, Runnable, StorageConfigNamespace},
 };
 use linera_core::{worker::WorkerState, JoinSetExt as _};
-use linera_execution::{committee::ValidatorName, WasmRuntime, WithWasmDefault};
+use linera_execution::{
+    committee::{BlsValidatorKey, ValidatorName},
+    WasmRuntime, WithWasmDefault,
+};
 use linera_rpc::{
     config::{
-        CrossChainConfig, NetworkProtocol, NotificationConfig, ShardConfig, ShardId, TlsConfig,
-        ValidatorInternalNetworkConfig, ValidatorPublicNetworkConfig,
+        CrossChainConfig, GossipConfig, InternalTlsConfig, NetworkProtocol, NotificationConfig,
+        ShardAssignment, ShardConfig, ShardId, TlsConfig, ValidatorInternalNetworkConfig,
+        ValidatorPublicNetworkConfig,
     },
-    grpc, simple,
+    grpc::{self, api::shard_admin_client::ShardAdminClient, InternalTls},
+    simple,
 };
 #[cfg(with_metrics)]
 use linera_service::prometheus_server;
@@ -43,6 +51,7 @@ struct ServerContext {
     server_config: ValidatorServerConfig,
     cross_chain_config: CrossChainConfig,
     notification_config: NotificationConfig,
+    gossip_config: GossipConfig,
     shard: Option<usize>,
     grace_period: Duration,
 }
@@ -65,6 +74,7 @@ impl ServerContext {
             storage,
             NonZeroUsize::new(400).expect("Chain worker limit should not be zero"),
         )
+        .with_bls_key_pair(self.server_config.bls_key.as_ref().map(BlsKeyPair::copy))
         .with_allow_inactive_chains(false)
         .with_allow_messages_from_deprecated_epochs(false)
         .with_grace_period(self.grace_period);
@@ -128,6 +138,7 @@ impl ServerContext {
         &self,
         listen_address: &str,
         states: Vec<(WorkerState<S>, ShardId, ShardConfig)>,
+        tls: Option<InternalTls>,
         shutdown_signal: CancellationToken,
     ) -> JoinSet<()>
     where
@@ -150,6 +161,8 @@ impl ServerContext {
                 self.server_config.internal_network.clone(),
                 self.cross_chain_config.clone(),
                 self.notification_config.clone(),
+                self.gossip_config.clone(),
+                tls.clone(),
                 shutdown_signal.clone(),
                 &mut join_set,
             );
@@ -212,10 +225,10 @@ impl Runnable for ServerContext {
             NetworkProtocol::Simple(protocol) => {
                 self.spawn_simple(&listen_address, states, protocol, shutdown_notifier)
             }
-            NetworkProtocol::Grpc(tls_config) => match tls_config {
-                TlsConfig::ClearText => self.spawn_grpc(&listen_address, states, shutdown_notifier),
-                TlsConfig::Tls => bail!("TLS not supported between proxy and shards."),
-            },
+            NetworkProtocol::Grpc(_) => {
+                let tls = InternalTls::load(&self.server_config.internal_network)?;
+                self.spawn_grpc(&listen_address, states, tls, shutdown_notifier)
+            }
         };
 
         join_set.await_all_tasks().await;
@@ -271,6 +284,16 @@ struct ValidatorOptions {
 
     /// The public name and the port of each of the shards
     shards: Vec<ShardConfig>,
+
+    /// The assignment of chains to shards.
+    #[serde(default)]
+    shard_assignment: ShardAssignment,
+
+    /// The certificates for mutual TLS between the proxy and the shards. If the internal
+    /// protocol uses TLS and this is not set, new certificates are generated next to the
+    /// server configuration.
+    #[serde(default)]
+    internal_tls: Option<InternalTlsConfig>,
 }
 
 fn make_server_config<R: CryptoRng>(
@@ -283,9 +306,15 @@ fn make_server_config<R: CryptoRng>(
         host: options.host,
         port: options.port,
     };
+    let tls = match (options.internal_protocol, options.internal_tls) {
+        (NetworkProtocol::Grpc(TlsConfig::Tls), None) => Some(generate_internal_tls(path)?),
+        (_, tls) => tls,
+    };
     let internal_network = ValidatorInternalNetworkConfig {
         protocol: options.internal_protocol,
         shards: options.shards,
+        shard_assignment: options.shard_assignment,
+        tls,
         host: options.internal_host,
         port: options.internal_port,
         metrics_host: options.metrics_host,
@@ -293,17 +322,92 @@ fn make_server_config<R: CryptoRng>(
     };
     let key = KeyPair::generate_from(rng);
     let name = ValidatorName(key.public());
-    let validator = ValidatorConfig { network, name };
+    let bls_key = BlsKeyPair::generate_from(rng);
+    let validator = ValidatorConfig {
+        network,
+        name,
+        bls_key: Some(BlsValidatorKey::new(&bls_key)),
+    };
     Ok(persistent::File::new(
         path,
         ValidatorServerConfig {
             validator,
             key,
+            bls_key: Some(bls_key),
             internal_network,
         },
     )?)
 }
 
+/// Generates a certificate authority for the internal network of a validator, and a
+/// certificate signed by it for the proxy and the shards. The files are written next to
+/// the server configuration.
+fn generate_internal_tls(server_config_path: &Path) -> anyhow::Result<InternalTlsConfig> {
+    use rcgen::{
+        BasicConstraints, Certificate, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa,
+        KeyUsagePurpose,
+    };
+
+    let mut ca_params = CertificateParams::new(Vec::new());
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    ca_params.key_usages = vec![KeyUsagePurpose::KeyCertSign, KeyUsagePurpose::CrlSign];
+    ca_params
+        .distinguished_name
+        .push(DnType::CommonName, "Linera validator internal CA");
+    let ca = Certificate::from_params(ca_params)?;
+
+    let mut params = CertificateParams::new(vec![grpc::INTERNAL_TLS_DOMAIN.to_string()]);
+    params.extended_key_usages = vec![
+        ExtendedKeyUsagePurpose::ServerAuth,
+        ExtendedKeyUsagePurpose::ClientAuth,
+    ];
+    let certificate = Certificate::from_params(params)?;
+
+    let stem = server_config_path.with_extension("");
+    let path_with_suffix = |suffix: &str| {
+        let mut path = stem.clone().into_os_string();
+        path.push(suffix);
+        PathBuf::from(path)
+    };
+    let config = InternalTlsConfig {
+        ca_certificate_path: path_with_suffix("_internal_ca.pem"),
+        certificate_path: path_with_suffix("_internal.pem"),
+        private_key_path: path_with_suffix("_internal_key.pem"),
+    };
+    fs_err::write(&config.ca_certificate_path, ca.serialize_pem()?)?;
+    fs_err::write(
+        &config.certificate_path,
+        certificate.serialize_pem_with_signer(&ca)?,
+    )?;
+    write_private_file(
+        &config.private_key_path,
+        certificate.serialize_private_key_pem(),
+    )?;
+    Ok(config)
+}
+
+/// Writes a file that only the current user can read, e.g. a private key.
+fn write_private_file(path: &Path, contents: String) -> anyhow::Result<()> {
+    use std::io::Write as _;
+
+    let mut options = fs_err::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use fs_err::os::unix::fs::OpenOptionsExt as _;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    // The mode only applies to new files: also restrict a file that already existed.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt as _;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(contents.as_bytes())?;
+    Ok(())
+}
+
 #[derive(clap::Parser)]
 enum ServerCommand {
     /// Runs a service for each shard of the Linera validator")
@@ -325,6 +429,10 @@ enum ServerCommand {
         #[command(flatten)]
         notification_config: NotificationConfig,
 
+        /// Configuration for the gossip of new blocks between validators
+        #[command(flatten)]
+        gossip_config: GossipConfig,
+
         /// Path to the file describing the initial user chains (aka genesis state)
         #[arg(long = "genesis")]
         genesis_config_path: PathBuf,
@@ -395,6 +503,25 @@ enum ServerCommand {
         #[arg(long, default_value = "1000")]
         cache_size: usize,
     },
+
+    /// Move a chain to another shard of a running validator, without downtime. This
+    /// requires the gRPC protocol between the proxy and the shards.
+    #[command(name = "migrate-chain")]
+    MigrateChain {
+        /// Path to the file containing the server configuration of this Linera validator.
+        /// The proxy records the new assignment in its own server configuration, so that
+        /// it survives restarts.
+        #[arg(long = "server")]
+        server_config_path: PathBuf,
+
+        /// The chain to migrate.
+        #[arg(long)]
+        chain_id: ChainId,
+
+        /// The shard that will handle the chain.
+        #[arg(long)]
+        shard: ShardId,
+    },
 }
 
 fn main() {
@@ -440,7 +567,9 @@ fn log_file_name_for(command: &ServerCommand) -> Cow<'static, str> {
             }
             .into()
         }
-        ServerCommand::Generate { .. } | ServerCommand::Initialize { .. } => "server".into(),
+        ServerCommand::Generate { .. }
+        | ServerCommand::Initialize { .. }
+        | ServerCommand::MigrateChain { .. } => "server".into(),
     }
 }
 
@@ -453,6 +582,7 @@ async fn run(options: ServerOptions) {
             storage_config,
             cross_chain_config,
             notification_config,
+            gossip_config,
             genesis_config_path,
             shard,
             grace_period,
@@ -477,6 +607,7 @@ async fn run(options: ServerOptions) {
                 server_config,
                 cross_chain_config,
                 notification_config,
+                gossip_config,
                 shard,
                 grace_period,
             };
@@ -558,7 +689,46 @@ async fn run(options: ServerOptions) {
                 .await
                 .unwrap();
         }
+
+        ServerCommand::MigrateChain {
+            server_config_path,
+            chain_id,
+            shard,
+        } => {
+            migrate_chain(&server_config_path, chain_id, shard)
+                .await
+                .expect("Failed to migrate chain");
+        }
+    }
+}
+
+/// Asks the proxy of a validator to migrate a chain. The proxy records the new assignment
+/// in the server configuration once the chain has been handed off.
+async fn migrate_chain(
+    server_config_path: &Path,
+    chain_id: ChainId,
+    shard: ShardId,
+) -> anyhow::Result<()> {
+    // The file is not locked: the proxy updates it during the migration.
+    let server_config: ValidatorServerConfig = util::read_json(server_config_path)?;
+    let internal_network = &server_config.internal_network;
+    if !matches!(internal_network.protocol, NetworkProtocol::Grpc(_)) {
+        bail!("Live migration requires the gRPC protocol between the proxy and the shards.");
+    }
+    if shard >= internal_network.shards.len() {
+        bail!("Invalid shard {shard}");
     }
+    let mut endpoint = tonic::transport::Endpoint::from_shared(internal_network.proxy_address())?;
+    if let Some(tls) = InternalTls::load(internal_network)? {
+        endpoint = endpoint.tls_config(tls.client_config())?;
+    }
+    let channel = endpoint.connect().await?;
+    let mut client = ShardAdminClient::new(channel);
+    client
+        .migrate_chain(grpc::api::ChainAssignment::from((chain_id, shard)))
+        .await?;
+    info!("Chain {chain_id} is now handled by shard {shard}");
+    Ok(())
 }
 
 #[cfg(test)]
@@ -619,7 +789,61 @@ mod test {
                         metrics_port: Some(5002),
                     },
                 ],
+                shard_assignment: ShardAssignment::default(),
+                internal_tls: None,
             }
         );
     }
+
+    #[tokio::test]
+    async fn test_internal_tls_requires_a_client_certificate() -> anyhow::Result<()> {
+        use tonic::transport::{Certificate, ClientTlsConfig, Endpoint};
+        use tonic_health::pb::{health_client::HealthClient, HealthCheckRequest};
+
+        let directory = tempfile::tempdir()?;
+        let tls_config = generate_internal_tls(&directory.path().join("server.json"))?;
+        let network = ValidatorInternalNetworkConfig {
+            protocol: NetworkProtocol::Grpc(TlsConfig::Tls),
+            shards: Vec::new(),
+            shard_assignment: ShardAssignment::default(),
+            tls: Some(tls_config.clone()),
+            host: "127.0.0.1".into(),
+            port: 0,
+            metrics_host: "127.0.0.1".into(),
+            metrics_port: 0,
+        };
+        let tls = InternalTls::load(&network)?.expect("the network uses TLS");
+
+        let endpoint = linera_base::port::get_free_endpoint().await?;
+        let (_health_reporter, health_service) = tonic_health::server::health_reporter();
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .tls_config(tls.server_config())?
+                .add_service(health_service)
+                .serve(endpoint.parse()?),
+        );
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let check = |tls: ClientTlsConfig| {
+            let url = format!("https://{endpoint}");
+            async move {
+                let channel = Endpoint::from_shared(url)?
+                    .tls_config(tls)?
+                    .connect()
+                    .await?;
+                HealthClient::new(channel)
+                    .check(HealthCheckRequest::default())
+                    .await?;
+                Ok::<_, anyhow::Error>(())
+            }
+        };
+        check(tls.client_config()).await?;
+
+        let ca_certificate = Certificate::from_pem(fs_err::read(&tls_config.ca_certificate_path)?);
+        let anonymous = ClientTlsConfig::new()
+            .ca_certificate(ca_certificate)
+            .domain_name(grpc::INTERNAL_TLS_DOMAIN);
+        assert!(check(anonymous).await.is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,393 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Authentication and access control for the storage service.
+//!
+//! Clients authenticate with a token sent in the `authorization` metadata of every
+//! request. The server maps each token to the set of namespaces that it may access.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::Path,
+    sync::Arc,
+};
+
+use serde::Deserialize;
+use tonic::{metadata::MetadataValue, service::Interceptor, Request, Status};
+
+use crate::common::{KeyTag, ServiceStoreError};
+
+/// The metadata key carrying the token.
+const AUTHORIZATION_HEADER: &str = "authorization";
+
+/// The prefix of the value of the `authorization` metadata.
+const BEARER_PREFIX: &str = "Bearer ";
+
+/// The namespace pattern granting access to all the namespaces.
+const ALL_NAMESPACES: &str = "*";
+
+/// The interceptor used by clients to attach their token to requests.
+#[derive(Clone, Debug, Default)]
+pub struct ClientAuthInterceptor {
+    authorization: Option<MetadataValue<tonic::metadata::Ascii>>,
+}
+
+impl ClientAuthInterceptor {
+    /// Creates an interceptor sending the given token, if any.
+    pub fn new(token: Option<&str>) -> Result<Self, ServiceStoreError> {
+        let authorization = token
+            .map(|token| {
+                format!("{BEARER_PREFIX}{token}")
+                    .parse()
+                    .map_err(|_| ServiceStoreError::InvalidAuthToken)
+            })
+            .transpose()?;
+        Ok(Self { authorization })
+    }
+}
+
+impl Interceptor for ClientAuthInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(authorization) = &self.authorization {
+            request
+                .metadata_mut()
+                .insert(AUTHORIZATION_HEADER, authorization.clone());
+        }
+        Ok(request)
+    }
+}
+
+/// The namespaces that a client is allowed to access.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum NamespacePermissions {
+    /// All the namespaces, including the administrative operations on the whole store.
+    All,
+    /// Only the given namespaces.
+    Some(BTreeSet<String>),
+}
+
+/// The identity of an authenticated client and its permissions.
+#[derive(Clone, Debug)]
+pub struct Principal {
+    /// A unique identifier of the client. Names need not be unique, so this is what
+    /// resources owned by a client are keyed on.
+    pub id: u64,
+    /// The name of the client, used for logging.
+    pub name: String,
+    /// The namespaces that the client is allowed to access.
+    pub permissions: NamespacePermissions,
+}
+
+impl Principal {
+    /// The principal used when access control is disabled.
+    pub fn anonymous() -> Self {
+        Self {
+            id: 0,
+            name: "anonymous".to_string(),
+            permissions: NamespacePermissions::All,
+        }
+    }
+
+    /// Returns whether the principal may access the whole store.
+    pub fn is_admin(&self) -> bool {
+        self.permissions == NamespacePermissions::All
+    }
+
+    /// Returns whether the principal may access the given namespace.
+    pub fn can_access(&self, namespace: &str) -> bool {
+        match &self.permissions {
+            NamespacePermissions::All => true,
+            NamespacePermissions::Some(namespaces) => namespaces.contains(namespace),
+        }
+    }
+
+    /// Checks that the principal may access the whole store.
+    pub fn check_admin(&self) -> Result<(), Status> {
+        if self.is_admin() {
+            return Ok(());
+        }
+        Err(Status::permission_denied(format!(
+            "{} is not allowed to access all the namespaces",
+            self.name
+        )))
+    }
+
+    /// Checks that the principal may access the namespace of the given key or key prefix.
+    ///
+    /// Keys that do not start with a complete namespace span several namespaces and are
+    /// only allowed for administrators.
+    pub fn check_key(&self, key: &[u8]) -> Result<(), Status> {
+        if self.is_admin() {
+            return Ok(());
+        }
+        match namespace_of_key(key) {
+            Some(namespace) if self.can_access(&namespace) => Ok(()),
+            Some(namespace) => Err(Status::permission_denied(format!(
+                "{} is not allowed to access namespace {namespace}",
+                self.name
+            ))),
+            None => Err(Status::permission_denied(format!(
+                "{} is not allowed to access keys outside of a namespace",
+                self.name
+            ))),
+        }
+    }
+
+    /// Checks that the principal may access the given BCS-serialized namespace.
+    pub fn check_namespace(&self, namespace: &[u8]) -> Result<(), Status> {
+        if self.is_admin() {
+            return Ok(());
+        }
+        let namespace = bcs::from_bytes::<String>(namespace)
+            .map_err(|_| Status::invalid_argument("the namespace is not a valid string"))?;
+        if self.can_access(&namespace) {
+            return Ok(());
+        }
+        Err(Status::permission_denied(format!(
+            "{} is not allowed to access namespace {namespace}",
+            self.name
+        )))
+    }
+}
+
+/// Extracts the principal inserted by [`AccessControl::authenticate`].
+pub fn principal<T>(request: &Request<T>) -> Result<Arc<Principal>, Status> {
+    request
+        .extensions()
+        .get::<Arc<Principal>>()
+        .cloned()
+        .ok_or_else(|| Status::unauthenticated("the request was not authenticated"))
+}
+
+/// Decodes the namespace from a key of the form `[KeyTag::Key] + bcs(namespace) + ...`.
+///
+/// Returns `None` if the key does not contain a complete namespace.
+pub fn namespace_of_key(key: &[u8]) -> Option<String> {
+    let (tag, rest) = key.split_first()?;
+    if *tag != KeyTag::Key as u8 {
+        return None;
+    }
+    // The BCS serialization of a string is its length in ULEB128 followed by its bytes.
+    let mut length = 0usize;
+    let mut position = 0;
+    loop {
+        let byte = *rest.get(position)?;
+        let bits = usize::from(byte & 0x7f).checked_shl(7 * position as u32)?;
+        length |= bits;
+        position += 1;
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+    let bytes = rest.get(position..position.checked_add(length)?)?;
+    String::from_utf8(bytes.to_vec()).ok()
+}
+
+/// The content of the access control file.
+#[derive(Debug, Deserialize)]
+struct AccessControlConfig {
+    tokens: Vec<TokenConfig>,
+}
+
+/// A token and the namespaces that it gives access to. The namespace `*` gives access
+/// to all the namespaces.
+#[derive(Debug, Deserialize)]
+struct TokenConfig {
+    name: String,
+    token: String,
+    namespaces: Vec<String>,
+}
+
+/// The tokens accepted by the server.
+#[derive(Clone, Debug, Default)]
+pub struct AccessControl {
+    principals: HashMap<String, Arc<Principal>>,
+}
+
+impl AccessControl {
+    /// Reads the access control rules from a TOML file of the form
+    ///
+    /// ```toml
+    /// [[tokens]]
+    /// name = "validator-1"
+    /// token = "..."
+    /// namespaces = ["validator_1"]
+    /// ```
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Self::from_toml(&content)
+    }
+
+    /// Parses the access control rules from TOML.
+    pub fn from_toml(content: &str) -> anyhow::Result<Self> {
+        let config = toml::from_str::<AccessControlConfig>(content)?;
+        let mut principals = HashMap::new();
+        // The identifier 0 is used by the anonymous principal.
+        for (
+            id,
+            TokenConfig {
+                name,
+                token,
+                namespaces,
+            },
+        ) in (1..).zip(config.tokens)
+        {
+            let permissions = if namespaces
+                .iter()
+                .any(|namespace| namespace == ALL_NAMESPACES)
+            {
+                NamespacePermissions::All
+            } else {
+                NamespacePermissions::Some(namespaces.into_iter().collect())
+            };
+            let principal = Arc::new(Principal {
+                id,
+                name,
+                permissions,
+            });
+            anyhow::ensure!(
+                principals.insert(token, principal).is_none(),
+                "duplicate token in the access control rules"
+            );
+        }
+        Ok(Self { principals })
+    }
+
+    /// Authenticates a request and attaches its [`Principal`] to it.
+    pub fn authenticate(&self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let token = request
+            .metadata()
+            .get(AUTHORIZATION_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix(BEARER_PREFIX))
+            .ok_or_else(|| Status::unauthenticated("missing authorization token"))?;
+        let principal = self
+            .principals
+            .get(token)
+            .cloned()
+            .ok_or_else(|| Status::unauthenticated("invalid authorization token"))?;
+        request.extensions_mut().insert(principal);
+        Ok(request)
+    }
+}
+
+/// The interceptor used by the server to authenticate requests.
+///
+/// Without access control rules, every request is accepted with full permissions.
+#[derive(Clone, Debug, Default)]
+pub struct ServerAuthInterceptor {
+    access_control: Option<Arc<AccessControl>>,
+}
+
+impl ServerAuthInterceptor {
+    /// Creates an interceptor enforcing the given rules, if any.
+    pub fn new(access_control: Option<AccessControl>) -> Self {
+        Self {
+            access_control: access_control.map(Arc::new),
+        }
+    }
+}
+
+impl Interceptor for ServerAuthInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        match &self.access_control {
+            Some(access_control) => access_control.authenticate(request),
+            None => {
+                request
+                    .extensions_mut()
+                    .insert(Arc::new(Principal::anonymous()));
+                Ok(request)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: &str = r#"
+        [[tokens]]
+        name = "admin"
+        token = "admin-secret"
+        namespaces = ["*"]
+
+        [[tokens]]
+        name = "validator"
+        token = "validator-secret"
+        namespaces = ["shard_0", "shard_1"]
+    "#;
+
+    fn key_in_namespace(namespace: &str, key: &[u8]) -> Vec<u8> {
+        let mut full_key = vec![KeyTag::Key as u8];
+        bcs::serialize_into(&mut full_key, namespace).unwrap();
+        full_key.extend(key);
+        full_key
+    }
+
+    fn authenticate(access_control: &AccessControl, token: &str) -> Result<Arc<Principal>, Status> {
+        let mut request = Request::new(());
+        request.metadata_mut().insert(
+            AUTHORIZATION_HEADER,
+            format!("{BEARER_PREFIX}{token}").parse().unwrap(),
+        );
+        let request = access_control.authenticate(request)?;
+        principal(&request)
+    }
+
+    #[test]
+    fn test_namespace_of_key() {
+        let long_namespace = "a".repeat(200);
+        for namespace in ["shard_0", "", long_namespace.as_str()] {
+            let key = key_in_namespace(namespace, &[1, 2, 3]);
+            assert_eq!(namespace_of_key(&key).as_deref(), Some(namespace));
+            let prefix = key_in_namespace(namespace, &[]);
+            assert_eq!(namespace_of_key(&prefix).as_deref(), Some(namespace));
+            assert_eq!(namespace_of_key(&prefix[..prefix.len() - 1]), None);
+        }
+        assert_eq!(namespace_of_key(&[KeyTag::Key as u8]), None);
+        assert_eq!(namespace_of_key(&[KeyTag::Namespace as u8, 0]), None);
+    }
+
+    #[test]
+    fn test_access_control() {
+        let access_control = AccessControl::from_toml(RULES).unwrap();
+        let admin = authenticate(&access_control, "admin-secret").unwrap();
+        let validator = authenticate(&access_control, "validator-secret").unwrap();
+        assert!(authenticate(&access_control, "wrong-secret").is_err());
+        assert!(access_control.authenticate(Request::new(())).is_err());
+
+        assert!(admin.check_admin().is_ok());
+        assert!(validator.check_admin().is_err());
+
+        let allowed = key_in_namespace("shard_1", &[5]);
+        let forbidden = key_in_namespace("shard_2", &[5]);
+        assert!(validator.check_key(&allowed).is_ok());
+        assert!(validator.check_key(&forbidden).is_err());
+        assert!(validator.check_key(&[KeyTag::Key as u8]).is_err());
+        assert!(admin.check_key(&forbidden).is_ok());
+
+        let namespace = bcs::to_bytes("shard_0").unwrap();
+        assert!(validator.check_namespace(&namespace).is_ok());
+        let namespace = bcs::to_bytes("shard_3").unwrap();
+        assert!(validator.check_namespace(&namespace).is_err());
+    }
+
+    #[test]
+    fn test_principals_with_the_same_name() {
+        let rules = format!(
+            r#"{RULES}
+            [[tokens]]
+            name = "validator"
+            token = "other-validator-secret"
+            namespaces = ["shard_2"]
+        "#
+        );
+        let access_control = AccessControl::from_toml(&rules).unwrap();
+        let validator = authenticate(&access_control, "validator-secret").unwrap();
+        let other = authenticate(&access_control, "other-validator-secret").unwrap();
+        assert_eq!(validator.name, other.name);
+        assert_ne!(validator.id, other.id);
+        assert_ne!(validator.id, Principal::anonymous().id);
+    }
+}
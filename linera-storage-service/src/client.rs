@@ -3,12 +3,14 @@
 
 use std::{mem, sync::Arc};
 
-use async_lock::{RwLock, RwLockWriteGuard, Semaphore, SemaphoreGuard};
+use async_lock::{RwLock, RwLockWriteGuard, Semaphore, SemaphoreGuard, SemaphoreGuardArc};
+use futures::{future, stream, Stream, StreamExt as _, TryStreamExt as _};
 use linera_base::ensure;
 #[cfg(with_metrics)]
 use linera_views::metering::MeteredStore;
 use linera_views::{
     batch::{Batch, WriteOperation},
+    change_capture::{batch_under_prefix, ChangeEvent},
     lru_caching::LruCachingStore,
     store::{
         AdminKeyValueStore, CommonStoreConfig, ReadableKeyValueStore, WithError,
@@ -18,29 +20,35 @@ use linera_views::{
 #[cfg(with_testing)]
 use linera_views::{random::generate_test_namespace, store::TestKeyValueStore};
 use serde::de::DeserializeOwned;
-use tonic::transport::{Channel, Endpoint};
+use tonic::{
+    service::interceptor::InterceptedService,
+    transport::{Certificate, Channel, ClientTlsConfig, Endpoint},
+};
 
 #[cfg(with_testing)]
 use crate::common::storage_service_test_endpoint;
 #[cfg(with_metrics)]
 use crate::common::{LRU_STORAGE_SERVICE_METRICS, STORAGE_SERVICE_METRICS};
 use crate::{
+    auth::ClientAuthInterceptor,
     common::{KeyTag, ServiceStoreConfig, ServiceStoreError, MAX_PAYLOAD_SIZE},
     key_value_store::{
         statement::Operation, store_processor_client::StoreProcessorClient, KeyValue,
-        KeyValueAppend, ReplyContainsKey, ReplyContainsKeys, ReplyExistsNamespace,
-        ReplyFindKeyValuesByPrefix, ReplyFindKeysByPrefix, ReplyListAll, ReplyReadMultiValues,
-        ReplyReadValue, ReplySpecificChunk, RequestContainsKey, RequestContainsKeys,
+        KeyValueAppend, OptValuePart, ReplyContainsKey, ReplyContainsKeys, ReplyExistsNamespace,
+        ReplyListAll, ReplyReadValue, ReplySpecificChunk, RequestContainsKey, RequestContainsKeys,
         RequestCreateNamespace, RequestDeleteAll, RequestDeleteNamespace, RequestExistsNamespace,
         RequestFindKeyValuesByPrefix, RequestFindKeysByPrefix, RequestListAll,
-        RequestReadMultiValues, RequestReadValue, RequestSpecificChunk, RequestWriteBatchExtended,
-        Statement,
+        RequestReadMultiValues, RequestReadValue, RequestSpecificChunk, RequestTailChanges,
+        RequestWriteBatchExtended, Statement,
     },
 };
 
 // The maximum key size is set to 1M rather arbitrarily.
 const MAX_KEY_SIZE: usize = 1000000;
 
+/// The gRPC client, attaching the authentication token to every request.
+type StoreClient = StoreProcessorClient<InterceptedService<Channel, ClientAuthInterceptor>>;
+
 // The shared store client.
 // * Interior mutability is required for client because
 // accessing requires mutability while the KeyValueStore
@@ -59,7 +67,7 @@ const MAX_KEY_SIZE: usize = 1000000;
 // is stored to indicate the existence of a namespace.
 #[derive(Clone)]
 pub struct ServiceStoreClientInternal {
-    client: Arc<RwLock<StoreProcessorClient<Channel>>>,
+    client: Arc<RwLock<StoreClient>>,
     semaphore: Option<Arc<Semaphore>>,
     max_stream_queries: usize,
     cache_size: usize,
@@ -141,97 +149,30 @@ impl ReadableKeyValueStore for ServiceStoreClientInternal {
         &self,
         keys: Vec<Vec<u8>>,
     ) -> Result<Vec<Option<Vec<u8>>>, ServiceStoreError> {
-        let mut full_keys = Vec::new();
-        for key in keys {
-            ensure!(key.len() <= MAX_KEY_SIZE, ServiceStoreError::KeyTooLong);
-            let mut full_key = self.namespace.clone();
-            full_key.extend(&self.root_key);
-            full_key.extend(&key);
-            full_keys.push(full_key);
-        }
-        let query = RequestReadMultiValues { keys: full_keys };
-        let request = tonic::Request::new(query);
-        let mut client = self.client.write().await;
-        let _guard = self.acquire().await;
-        let response = client.process_read_multi_values(request).await?;
-        let response = response.into_inner();
-        let ReplyReadMultiValues {
-            values,
-            message_index,
-            num_chunks,
-        } = response;
-        if num_chunks == 0 {
-            let values = values.into_iter().map(|x| x.value).collect::<Vec<_>>();
-            Ok(values)
-        } else {
-            Self::read_entries(client, message_index, num_chunks).await
-        }
+        self.stream_read_multi_values_bytes(keys)
+            .await?
+            .try_collect()
+            .await
     }
 
     async fn find_keys_by_prefix(
         &self,
         key_prefix: &[u8],
     ) -> Result<Vec<Vec<u8>>, ServiceStoreError> {
-        ensure!(
-            key_prefix.len() <= MAX_KEY_SIZE,
-            ServiceStoreError::KeyTooLong
-        );
-        let mut full_key_prefix = self.namespace.clone();
-        full_key_prefix.extend(&self.root_key);
-        full_key_prefix.extend(key_prefix);
-        let query = RequestFindKeysByPrefix {
-            key_prefix: full_key_prefix,
-        };
-        let request = tonic::Request::new(query);
-        let mut client = self.client.write().await;
-        let _guard = self.acquire().await;
-        let response = client.process_find_keys_by_prefix(request).await?;
-        let response = response.into_inner();
-        let ReplyFindKeysByPrefix {
-            keys,
-            message_index,
-            num_chunks,
-        } = response;
-        if num_chunks == 0 {
-            Ok(keys)
-        } else {
-            Self::read_entries(client, message_index, num_chunks).await
-        }
+        self.stream_find_keys_by_prefix(key_prefix)
+            .await?
+            .try_collect()
+            .await
     }
 
     async fn find_key_values_by_prefix(
         &self,
         key_prefix: &[u8],
     ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, ServiceStoreError> {
-        ensure!(
-            key_prefix.len() <= MAX_KEY_SIZE,
-            ServiceStoreError::KeyTooLong
-        );
-        let mut full_key_prefix = self.namespace.clone();
-        full_key_prefix.extend(&self.root_key);
-        full_key_prefix.extend(key_prefix);
-        let query = RequestFindKeyValuesByPrefix {
-            key_prefix: full_key_prefix,
-        };
-        let request = tonic::Request::new(query);
-        let mut client = self.client.write().await;
-        let _guard = self.acquire().await;
-        let response = client.process_find_key_values_by_prefix(request).await?;
-        let response = response.into_inner();
-        let ReplyFindKeyValuesByPrefix {
-            key_values,
-            message_index,
-            num_chunks,
-        } = response;
-        if num_chunks == 0 {
-            let key_values = key_values
-                .into_iter()
-                .map(|x| (x.key, x.value))
-                .collect::<Vec<_>>();
-            Ok(key_values)
-        } else {
-            Self::read_entries(client, message_index, num_chunks).await
-        }
+        self.stream_find_key_values_by_prefix(key_prefix)
+            .await?
+            .try_collect()
+            .await
     }
 }
 
@@ -310,6 +251,189 @@ impl ServiceStoreClientInternal {
         }
     }
 
+    /// Obtains the semaphore lock on the database if needed, for the lifetime of a stream.
+    async fn acquire_arc(&self) -> Option<SemaphoreGuardArc> {
+        match &self.semaphore {
+            None => None,
+            Some(count) => Some(count.acquire_arc().await),
+        }
+    }
+
+    fn full_key(&self, key: &[u8]) -> Result<Vec<u8>, ServiceStoreError> {
+        ensure!(key.len() <= MAX_KEY_SIZE, ServiceStoreError::KeyTooLong);
+        let mut full_key = self.namespace.clone();
+        full_key.extend(&self.root_key);
+        full_key.extend(key);
+        Ok(full_key)
+    }
+
+    /// Reads the values of the given keys. The values are streamed by the server, so
+    /// that they are not all held in memory at once.
+    pub async fn stream_read_multi_values_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<
+        impl Stream<Item = Result<Option<Vec<u8>>, ServiceStoreError>> + Send + 'static,
+        ServiceStoreError,
+    > {
+        let keys = keys
+            .iter()
+            .map(|key| self.full_key(key))
+            .collect::<Result<_, _>>()?;
+        let query = RequestReadMultiValues { keys };
+        let request = tonic::Request::new(query);
+        let mut client = self.client.read().await.clone();
+        let guard = self.acquire_arc().await;
+        let replies = client.process_stream_read_multi_values(request).await?;
+        let mut pending = Vec::new();
+        let values = replies
+            .into_inner()
+            .map_err(ServiceStoreError::from)
+            .map_ok(move |reply| {
+                let mut values = Vec::new();
+                for OptValuePart { value, last } in reply.values {
+                    match value {
+                        None => values.push(Ok(None)),
+                        Some(value) => {
+                            pending.extend(value);
+                            if last {
+                                values.push(Ok(Some(mem::take(&mut pending))));
+                            }
+                        }
+                    }
+                }
+                stream::iter(values)
+            })
+            .try_flatten();
+        Ok(values.inspect(move |_| {
+            let _guard = &guard;
+        }))
+    }
+
+    /// Finds the keys matching the prefix. The keys are streamed by the server, so that
+    /// the client does not hold them all in memory at once. The server still reads them
+    /// in a single scan.
+    pub async fn stream_find_keys_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<
+        impl Stream<Item = Result<Vec<u8>, ServiceStoreError>> + Send + 'static,
+        ServiceStoreError,
+    > {
+        let key_prefix = self.full_key(key_prefix)?;
+        let query = RequestFindKeysByPrefix { key_prefix };
+        let request = tonic::Request::new(query);
+        let mut client = self.client.read().await.clone();
+        let guard = self.acquire_arc().await;
+        let replies = client.process_stream_find_keys_by_prefix(request).await?;
+        let keys = replies
+            .into_inner()
+            .map_err(ServiceStoreError::from)
+            .map_ok(|reply| stream::iter(reply.keys.into_iter().map(Ok)))
+            .try_flatten();
+        Ok(keys.inspect(move |_| {
+            let _guard = &guard;
+        }))
+    }
+
+    /// Finds the key-value pairs matching the prefix. The pairs are streamed by the
+    /// server, so that the client does not hold them all in memory at once. The server
+    /// still reads them in a single scan.
+    pub async fn stream_find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<
+        impl Stream<Item = Result<(Vec<u8>, Vec<u8>), ServiceStoreError>> + Send + 'static,
+        ServiceStoreError,
+    > {
+        let key_prefix = self.full_key(key_prefix)?;
+        let query = RequestFindKeyValuesByPrefix { key_prefix };
+        let request = tonic::Request::new(query);
+        let mut client = self.client.read().await.clone();
+        let guard = self.acquire_arc().await;
+        let replies = client
+            .process_stream_find_key_values_by_prefix(request)
+            .await?;
+        let mut pending = Vec::new();
+        let key_values = replies
+            .into_inner()
+            .map_err(ServiceStoreError::from)
+            .map_ok(move |reply| {
+                let mut key_values = Vec::new();
+                for KeyValueAppend { key, value, last } in reply.key_values {
+                    pending.extend(value);
+                    if last {
+                        key_values.push(Ok((key, mem::take(&mut pending))));
+                    }
+                }
+                stream::iter(key_values)
+            })
+            .try_flatten();
+        Ok(key_values.inspect(move |_| {
+            let _guard = &guard;
+        }))
+    }
+
+    /// Follows the changes committed to the namespace, restricted to the keys under the
+    /// root key, from now on.
+    ///
+    /// The stream is long-lived, so it does not hold a permit of the semaphore. It ends
+    /// with an error if the client falls too far behind the server.
+    pub async fn tail_changes(
+        &self,
+    ) -> Result<
+        impl Stream<Item = Result<ChangeEvent, ServiceStoreError>> + Send + 'static,
+        ServiceStoreError,
+    > {
+        // Removes the `KeyTag` to obtain the serialized namespace.
+        let namespace = self.namespace[1..].to_vec();
+        let namespace_name = bcs::from_bytes::<String>(&namespace)?;
+        let root_key = self.root_key.clone();
+        let query = RequestTailChanges { namespace };
+        let request = tonic::Request::new(query);
+        let mut client = self.client.read().await.clone();
+        let replies = client.process_tail_changes(request).await?;
+        let mut batch = Batch::new();
+        let mut pending = Vec::new();
+        let events = replies
+            .into_inner()
+            .map_err(ServiceStoreError::from)
+            .try_filter_map(move |reply| {
+                for statement in reply.statements {
+                    match statement.operation {
+                        Some(Operation::Delete(key)) => batch.delete_key(key),
+                        Some(Operation::Put(KeyValue { key, value })) => {
+                            batch.put_key_value_bytes(key, value)
+                        }
+                        Some(Operation::Append(KeyValueAppend { key, value, last })) => {
+                            pending.extend(value);
+                            if last {
+                                batch.put_key_value_bytes(key, mem::take(&mut pending));
+                            }
+                        }
+                        Some(Operation::DeletePrefix(key_prefix)) => {
+                            batch.delete_key_prefix(key_prefix)
+                        }
+                        None => {}
+                    }
+                }
+                let mut event = None;
+                if reply.last {
+                    let batch = batch_under_prefix(&mem::take(&mut batch), &root_key);
+                    if !batch.operations.is_empty() {
+                        event = Some(ChangeEvent {
+                            sequence: reply.sequence,
+                            namespace: namespace_name.clone(),
+                            root_key: root_key.clone(),
+                            batch,
+                        });
+                    }
+                }
+                future::ready(Ok(event))
+            });
+        Ok(events)
+    }
+
     fn namespace_as_vec(namespace: &str) -> Result<Vec<u8>, ServiceStoreError> {
         let mut key = vec![KeyTag::Key as u8];
         bcs::serialize_into(&mut key, namespace)?;
@@ -357,7 +481,7 @@ impl ServiceStoreClientInternal {
     }
 
     async fn read_entries<S: DeserializeOwned>(
-        mut client: RwLockWriteGuard<'_, StoreProcessorClient<Channel>>,
+        mut client: RwLockWriteGuard<'_, StoreClient>,
         message_index: i64,
         num_chunks: i32,
     ) -> Result<S, ServiceStoreError> {
@@ -385,9 +509,7 @@ impl AdminKeyValueStore for ServiceStoreClientInternal {
         namespace: &str,
         root_key: &[u8],
     ) -> Result<Self, ServiceStoreError> {
-        let endpoint = format!("http://{}", config.endpoint);
-        let endpoint = Endpoint::from_shared(endpoint)?;
-        let client = StoreProcessorClient::connect(endpoint).await?;
+        let client = connect_client(config).await?;
         let client = Arc::new(RwLock::new(client));
         let semaphore = config
             .common_config
@@ -427,9 +549,7 @@ impl AdminKeyValueStore for ServiceStoreClientInternal {
     async fn list_all(config: &Self::Config) -> Result<Vec<String>, ServiceStoreError> {
         let query = RequestListAll {};
         let request = tonic::Request::new(query);
-        let endpoint = format!("http://{}", config.endpoint);
-        let endpoint = Endpoint::from_shared(endpoint)?;
-        let mut client = StoreProcessorClient::connect(endpoint).await?;
+        let mut client = connect_client(config).await?;
         let response = client.process_list_all(request).await?;
         let response = response.into_inner();
         let ReplyListAll { namespaces } = response;
@@ -443,9 +563,7 @@ impl AdminKeyValueStore for ServiceStoreClientInternal {
     async fn delete_all(config: &Self::Config) -> Result<(), ServiceStoreError> {
         let query = RequestDeleteAll {};
         let request = tonic::Request::new(query);
-        let endpoint = format!("http://{}", config.endpoint);
-        let endpoint = Endpoint::from_shared(endpoint)?;
-        let mut client = StoreProcessorClient::connect(endpoint).await?;
+        let mut client = connect_client(config).await?;
         let _response = client.process_delete_all(request).await?;
         Ok(())
     }
@@ -454,9 +572,7 @@ impl AdminKeyValueStore for ServiceStoreClientInternal {
         let namespace = bcs::to_bytes(namespace)?;
         let query = RequestExistsNamespace { namespace };
         let request = tonic::Request::new(query);
-        let endpoint = format!("http://{}", config.endpoint);
-        let endpoint = Endpoint::from_shared(endpoint)?;
-        let mut client = StoreProcessorClient::connect(endpoint).await?;
+        let mut client = connect_client(config).await?;
         let response = client.process_exists_namespace(request).await?;
         let response = response.into_inner();
         let ReplyExistsNamespace { exists } = response;
@@ -467,9 +583,7 @@ impl AdminKeyValueStore for ServiceStoreClientInternal {
         let namespace = bcs::to_bytes(namespace)?;
         let query = RequestCreateNamespace { namespace };
         let request = tonic::Request::new(query);
-        let endpoint = format!("http://{}", config.endpoint);
-        let endpoint = Endpoint::from_shared(endpoint)?;
-        let mut client = StoreProcessorClient::connect(endpoint).await?;
+        let mut client = connect_client(config).await?;
         let _response = client.process_create_namespace(request).await?;
         Ok(())
     }
@@ -478,9 +592,7 @@ impl AdminKeyValueStore for ServiceStoreClientInternal {
         let namespace = bcs::to_bytes(namespace)?;
         let query = RequestDeleteNamespace { namespace };
         let request = tonic::Request::new(query);
-        let endpoint = format!("http://{}", config.endpoint);
-        let endpoint = Endpoint::from_shared(endpoint)?;
-        let mut client = StoreProcessorClient::connect(endpoint).await?;
+        let mut client = connect_client(config).await?;
         let _response = client.process_delete_namespace(request).await?;
         Ok(())
     }
@@ -494,6 +606,24 @@ impl TestKeyValueStore for ServiceStoreClientInternal {
     }
 }
 
+/// Connects to the server, using TLS and authentication if they are configured.
+async fn connect_client(config: &ServiceStoreConfig) -> Result<StoreClient, ServiceStoreError> {
+    let endpoint = match &config.tls {
+        None => Endpoint::from_shared(format!("http://{}", config.endpoint))?,
+        Some(tls) => {
+            let mut tls_config =
+                ClientTlsConfig::new().ca_certificate(Certificate::from_pem(&tls.ca_certificate));
+            if let Some(domain_name) = &tls.domain_name {
+                tls_config = tls_config.domain_name(domain_name);
+            }
+            Endpoint::from_shared(format!("https://{}", config.endpoint))?.tls_config(tls_config)?
+        }
+    };
+    let channel = endpoint.connect().await?;
+    let interceptor = ClientAuthInterceptor::new(config.auth_token.as_deref())?;
+    Ok(StoreProcessorClient::with_interceptor(channel, interceptor))
+}
+
 /// Creates the `CommonStoreConfig` for the `ServiceStoreClientInternal`.
 pub fn create_service_store_common_config() -> CommonStoreConfig {
     let max_stream_queries = 100;
@@ -511,10 +641,7 @@ pub fn service_config_from_endpoint(
 ) -> Result<ServiceStoreConfig, ServiceStoreError> {
     let common_config = create_service_store_common_config();
     let endpoint = endpoint.to_string();
-    Ok(ServiceStoreConfig {
-        endpoint,
-        common_config,
-    })
+    ServiceStoreConfig::new(endpoint, common_config).with_security_from_env()
 }
 
 /// Checks that endpoint is truly absent.
@@ -526,7 +653,7 @@ pub async fn storage_service_check_absence(endpoint: &str) -> Result<bool, Servi
 
 /// Checks whether an endpoint is valid or not.
 pub async fn storage_service_check_validity(endpoint: &str) -> Result<(), ServiceStoreError> {
-    let config = service_config_from_endpoint(endpoint).unwrap();
+    let config = service_config_from_endpoint(endpoint)?;
     let namespace = "namespace";
     let root_key = &[];
     let store = ServiceStoreClientInternal::connect(&config, namespace, root_key).await?;
@@ -658,6 +785,51 @@ impl TestKeyValueStore for ServiceStoreClient {
 }
 
 impl ServiceStoreClient {
+    /// Reads the values of the given keys as a stream, bypassing the cache.
+    pub async fn stream_read_multi_values_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<
+        impl Stream<Item = Result<Option<Vec<u8>>, ServiceStoreError>> + Send + 'static,
+        ServiceStoreError,
+    > {
+        self.inner().stream_read_multi_values_bytes(keys).await
+    }
+
+    /// Finds the keys matching the prefix as a stream, bypassing the cache.
+    pub async fn stream_find_keys_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<
+        impl Stream<Item = Result<Vec<u8>, ServiceStoreError>> + Send + 'static,
+        ServiceStoreError,
+    > {
+        self.inner().stream_find_keys_by_prefix(key_prefix).await
+    }
+
+    /// Finds the key-value pairs matching the prefix as a stream, bypassing the cache.
+    pub async fn stream_find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<
+        impl Stream<Item = Result<(Vec<u8>, Vec<u8>), ServiceStoreError>> + Send + 'static,
+        ServiceStoreError,
+    > {
+        self.inner()
+            .stream_find_key_values_by_prefix(key_prefix)
+            .await
+    }
+
+    /// Follows the changes committed to the namespace under the root key.
+    pub async fn tail_changes(
+        &self,
+    ) -> Result<
+        impl Stream<Item = Result<ChangeEvent, ServiceStoreError>> + Send + 'static,
+        ServiceStoreError,
+    > {
+        self.inner().tail_changes().await
+    }
+
     #[cfg(with_metrics)]
     fn inner(&self) -> &ServiceStoreClientInternal {
         &self.store.store.store.store
@@ -72,6 +72,14 @@ pub enum ServiceStoreError {
     /// An error occurred during BCS serialization
     #[error("An error occurred during BCS serialization")]
     Serialization(#[from] bcs::Error),
+
+    /// I/O error
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+
+    /// The authentication token cannot be sent as gRPC metadata
+    #[error("The authentication token must be made of visible ASCII characters")]
+    InvalidAuthToken,
 }
 
 impl KeyValueStoreError for ServiceStoreError {
@@ -87,6 +95,17 @@ pub fn create_shared_store_common_config() -> CommonStoreConfig {
     }
 }
 
+/// The environment variable containing the path of the certificate of the authority
+/// that signed the certificate of the storage server. If set, TLS is used.
+pub const CA_CERTIFICATE_ENV: &str = "LINERA_STORAGE_SERVICE_CA_CERTIFICATE";
+
+/// The environment variable overriding the domain name checked against the certificate
+/// of the storage server.
+pub const TLS_DOMAIN_NAME_ENV: &str = "LINERA_STORAGE_SERVICE_TLS_DOMAIN_NAME";
+
+/// The environment variable containing the token used to authenticate to the storage server.
+pub const AUTH_TOKEN_ENV: &str = "LINERA_STORAGE_SERVICE_AUTH_TOKEN";
+
 pub fn storage_service_test_endpoint() -> Result<String, ServiceStoreError> {
     Ok(std::env::var("LINERA_STORAGE_SERVICE")?)
 }
@@ -105,6 +124,49 @@ pub struct ServiceStoreConfig {
     pub endpoint: String,
     /// The common configuration of the key value store
     pub common_config: CommonStoreConfig,
+    /// The TLS configuration, if the server is only accessible through TLS
+    pub tls: Option<ServiceStoreTlsConfig>,
+    /// The token used to authenticate to the server, if any
+    pub auth_token: Option<String>,
+}
+
+/// The TLS configuration of a client of the shared store
+#[derive(Debug, Clone)]
+pub struct ServiceStoreTlsConfig {
+    /// The PEM-encoded certificate of the authority that signed the server certificate
+    pub ca_certificate: String,
+    /// The domain name checked against the server certificate. By default, the host of
+    /// the endpoint is used.
+    pub domain_name: Option<String>,
+}
+
+impl ServiceStoreConfig {
+    /// Creates the configuration of a client using neither TLS nor authentication.
+    pub fn new(endpoint: String, common_config: CommonStoreConfig) -> Self {
+        Self {
+            endpoint,
+            common_config,
+            tls: None,
+            auth_token: None,
+        }
+    }
+
+    /// Sets up TLS and authentication from the environment variables [`CA_CERTIFICATE_ENV`],
+    /// [`TLS_DOMAIN_NAME_ENV`] and [`AUTH_TOKEN_ENV`].
+    pub fn with_security_from_env(mut self) -> Result<Self, ServiceStoreError> {
+        if let Some(path) = std::env::var_os(CA_CERTIFICATE_ENV) {
+            let ca_certificate = std::fs::read_to_string(path)?;
+            let domain_name = std::env::var(TLS_DOMAIN_NAME_ENV).ok();
+            self.tls = Some(ServiceStoreTlsConfig {
+                ca_certificate,
+                domain_name,
+            });
+        }
+        if let Ok(auth_token) = std::env::var(AUTH_TOKEN_ENV) {
+            self.auth_token = Some(auth_token);
+        }
+        Ok(self)
+    }
 }
 
 /// Obtains the binary of the executable.
@@ -1,8 +1,10 @@
 // Copyright (c) Zefchain Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-//! This module provides a shared key-value store server based on the RocksDB store and the in-memory store of `linera-views`.
+//! This module provides a shared key-value store server based on the key-value stores of `linera-views`.
+//! The server optionally uses TLS and restricts the namespaces accessible to each client with authentication tokens.
 //! The corresponding client implements the `KeyValueStore` and `AdminKeyValueStore` traits.
+//! Clients may also follow the changes committed to their namespace, e.g. to replicate or index it.
 
 #![deny(clippy::large_futures)]
 
@@ -10,6 +12,7 @@ pub mod key_value_store {
     tonic::include_proto!("key_value_store.v1");
 }
 
+pub mod auth;
 pub mod child;
 pub mod client;
 pub mod common;
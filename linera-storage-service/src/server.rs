@@ -3,174 +3,151 @@
 
 #![allow(clippy::blocks_in_conditions)]
 
-use std::{collections::BTreeMap, sync::Arc};
+use std::{collections::BTreeMap, future::Future, mem, path::PathBuf, sync::Arc};
 
 use async_lock::RwLock;
-use linera_storage_service::common::{KeyTag, MAX_PAYLOAD_SIZE};
-use linera_views::{
-    batch::Batch,
-    memory::MemoryStore,
-    store::{CommonStoreConfig, ReadableKeyValueStore, WritableKeyValueStore},
+use linera_storage_service::{
+    auth::{principal, AccessControl, Principal, ServerAuthInterceptor},
+    common::{KeyTag, MAX_PAYLOAD_SIZE},
 };
 #[cfg(with_rocksdb)]
+use linera_views::rocks_db::{PathWithGuard, RocksDbSpawnMode, RocksDbStore, RocksDbStoreConfig};
+#[cfg(with_scylladb)]
+use linera_views::scylla_db::{ScyllaDbStore, ScyllaDbStoreConfig};
 use linera_views::{
-    rocks_db::{PathWithGuard, RocksDbSpawnMode, RocksDbStore, RocksDbStoreConfig},
-    store::AdminKeyValueStore as _,
+    batch::{Batch, WriteOperation},
+    change_capture::{
+        batch_under_prefix, ChangeCaptureConfig, ChangeCaptureStore, ChangeEvent, ChangeFeed,
+    },
+    memory::{MemoryStore, MemoryStoreConfig},
+    store::{
+        AdminKeyValueStore as _, CommonStoreConfig, KeyIterable as _, KeyValueIterable as _,
+        KeyValueStore, KeyValueStoreError,
+    },
 };
 use serde::Serialize;
-use tonic::{transport::Server, Request, Response, Status};
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    mpsc,
+};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{
+    transport::{Identity, Server, ServerTlsConfig},
+    Request, Response, Status,
+};
 use tracing::{info, instrument};
 use tracing_subscriber::fmt::format::FmtSpan;
 
 use crate::key_value_store::{
     statement::Operation,
     store_processor_server::{StoreProcessor, StoreProcessorServer},
-    KeyValue, OptValue, ReplyContainsKey, ReplyContainsKeys, ReplyCreateNamespace, ReplyDeleteAll,
-    ReplyDeleteNamespace, ReplyExistsNamespace, ReplyFindKeyValuesByPrefix, ReplyFindKeysByPrefix,
-    ReplyListAll, ReplyReadMultiValues, ReplyReadValue, ReplySpecificChunk,
+    KeyValue, KeyValueAppend, OptValue, OptValuePart, ReplyContainsKey, ReplyContainsKeys,
+    ReplyCreateNamespace, ReplyDeleteAll, ReplyDeleteNamespace, ReplyExistsNamespace,
+    ReplyFindKeyValuesByPrefix, ReplyFindKeysByPrefix, ReplyListAll, ReplyReadMultiValues,
+    ReplyReadValue, ReplySpecificChunk, ReplyStreamFindKeyValuesByPrefix,
+    ReplyStreamFindKeysByPrefix, ReplyStreamReadMultiValues, ReplyTailChanges,
     ReplyWriteBatchExtended, RequestContainsKey, RequestContainsKeys, RequestCreateNamespace,
     RequestDeleteAll, RequestDeleteNamespace, RequestExistsNamespace, RequestFindKeyValuesByPrefix,
     RequestFindKeysByPrefix, RequestListAll, RequestReadMultiValues, RequestReadValue,
-    RequestSpecificChunk, RequestWriteBatchExtended,
+    RequestSpecificChunk, RequestTailChanges, RequestWriteBatchExtended, Statement,
 };
 
+/// The number of replies of a stream buffered by the server before it waits for the client.
+const STREAM_BUFFER_SIZE: usize = 4;
+
+/// The number of values read at once from the store when streaming.
+const STREAM_READ_BATCH_SIZE: usize = 64;
+
 pub mod key_value_store {
     tonic::include_proto!("key_value_store.v1");
 }
 
-enum ServiceStoreServerInternal {
-    Memory(MemoryStore),
-    /// The RocksDb key value store
-    #[cfg(with_rocksdb)]
-    RocksDb(RocksDbStore),
+/// The chunks of a big read, to be fetched by the principal that made the read.
+struct PendingBigRead {
+    /// The ID of the principal that made the read.
+    owner: u64,
+    chunks: Vec<Vec<u8>>,
 }
 
 #[derive(Default)]
 struct PendingBigReads {
     index: i64,
-    chunks_by_index: BTreeMap<i64, Vec<Vec<u8>>>,
+    chunks_by_index: BTreeMap<i64, PendingBigRead>,
 }
 
-struct ServiceStoreServer {
-    store: ServiceStoreServerInternal,
+/// The server, backed by any key-value store of `linera-views`.
+struct ServiceStoreServer<S> {
+    store: S,
+    /// The changes committed to `store`.
+    feed: ChangeFeed,
     pending_big_puts: Arc<RwLock<BTreeMap<Vec<u8>, Vec<u8>>>>,
     pending_big_reads: Arc<RwLock<PendingBigReads>>,
 }
 
-impl ServiceStoreServer {
+/// Converts an error of the store into a gRPC status.
+fn store_error<E: KeyValueStoreError>(error: E, operation: &str) -> Status {
+    Status::unknown(format!("{} error {:?} at {}", E::BACKEND, error, operation))
+}
+
+impl<S> ServiceStoreServer<S>
+where
+    S: KeyValueStore + Clone + Send + Sync + 'static,
+{
     pub async fn read_value_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Status> {
-        match &self.store {
-            ServiceStoreServerInternal::Memory(store) => store
-                .read_value_bytes(key)
-                .await
-                .map_err(|e| Status::unknown(format!("Memory error {:?} at read_value_bytes", e))),
-            #[cfg(with_rocksdb)]
-            ServiceStoreServerInternal::RocksDb(store) => store
-                .read_value_bytes(key)
-                .await
-                .map_err(|e| Status::unknown(format!("RocksDB error {:?} at read_value_bytes", e))),
-        }
+        self.store
+            .read_value_bytes(key)
+            .await
+            .map_err(|e| store_error(e, "read_value_bytes"))
     }
 
     pub async fn contains_key(&self, key: &[u8]) -> Result<bool, Status> {
-        match &self.store {
-            ServiceStoreServerInternal::Memory(store) => store
-                .contains_key(key)
-                .await
-                .map_err(|e| Status::unknown(format!("Memory error {:?} at contains_key", e))),
-            #[cfg(with_rocksdb)]
-            ServiceStoreServerInternal::RocksDb(store) => store
-                .contains_key(key)
-                .await
-                .map_err(|e| Status::unknown(format!("RocksDB error {:?} at contains_key", e))),
-        }
+        self.store
+            .contains_key(key)
+            .await
+            .map_err(|e| store_error(e, "contains_key"))
     }
 
     pub async fn contains_keys(&self, keys: Vec<Vec<u8>>) -> Result<Vec<bool>, Status> {
-        match &self.store {
-            ServiceStoreServerInternal::Memory(store) => store
-                .contains_keys(keys)
-                .await
-                .map_err(|e| Status::unknown(format!("Memory error {:?} at contains_keys", e))),
-            #[cfg(with_rocksdb)]
-            ServiceStoreServerInternal::RocksDb(store) => store
-                .contains_keys(keys)
-                .await
-                .map_err(|e| Status::unknown(format!("RocksDB error {:?} at contains_keys", e))),
-        }
+        self.store
+            .contains_keys(keys)
+            .await
+            .map_err(|e| store_error(e, "contains_keys"))
     }
 
     pub async fn read_multi_values_bytes(
         &self,
         keys: Vec<Vec<u8>>,
     ) -> Result<Vec<Option<Vec<u8>>>, Status> {
-        match &self.store {
-            ServiceStoreServerInternal::Memory(store) => {
-                store.read_multi_values_bytes(keys).await.map_err(|e| {
-                    Status::unknown(format!("Memory error {:?} at read_multi_values_bytes", e))
-                })
-            }
-            #[cfg(with_rocksdb)]
-            ServiceStoreServerInternal::RocksDb(store) => {
-                store.read_multi_values_bytes(keys).await.map_err(|e| {
-                    Status::unknown(format!("RocksDB error {:?} at read_multi_values_bytes", e))
-                })
-            }
-        }
+        self.store
+            .read_multi_values_bytes(keys)
+            .await
+            .map_err(|e| store_error(e, "read_multi_values_bytes"))
     }
 
     pub async fn find_keys_by_prefix(&self, key_prefix: &[u8]) -> Result<Vec<Vec<u8>>, Status> {
-        match &self.store {
-            ServiceStoreServerInternal::Memory(store) => {
-                store.find_keys_by_prefix(key_prefix).await.map_err(|e| {
-                    Status::unknown(format!("Memory error {:?} at find_keys_by_prefix", e))
-                })
-            }
-            #[cfg(with_rocksdb)]
-            ServiceStoreServerInternal::RocksDb(store) => {
-                store.find_keys_by_prefix(key_prefix).await.map_err(|e| {
-                    Status::unknown(format!("RocksDB error {:?} at find_keys_by_prefix", e))
-                })
-            }
-        }
+        Self::read_keys(&self.store, key_prefix).await
     }
 
     pub async fn find_key_values_by_prefix(
         &self,
         key_prefix: &[u8],
     ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Status> {
-        match &self.store {
-            ServiceStoreServerInternal::Memory(store) => store
-                .find_key_values_by_prefix(key_prefix)
-                .await
-                .map_err(|e| {
-                    Status::unknown(format!("Memory error {:?} at find_key_values_by_prefix", e))
-                }),
-            #[cfg(with_rocksdb)]
-            ServiceStoreServerInternal::RocksDb(store) => store
-                .find_key_values_by_prefix(key_prefix)
-                .await
-                .map_err(|e| {
-                    Status::unknown(format!(
-                        "RocksDB error {:?} at find_key_values_by_prefix",
-                        e
-                    ))
-                }),
-        }
+        let key_values = self
+            .store
+            .find_key_values_by_prefix(key_prefix)
+            .await
+            .map_err(|e| store_error(e, "find_key_values_by_prefix"))?;
+        key_values
+            .into_iterator_owned()
+            .collect::<Result<_, _>>()
+            .map_err(|e| store_error(e, "find_key_values_by_prefix"))
     }
 
     pub async fn write_batch(&self, batch: Batch) -> Result<(), Status> {
-        match &self.store {
-            ServiceStoreServerInternal::Memory(store) => store
-                .write_batch(batch)
-                .await
-                .map_err(|e| Status::unknown(format!("Memory error {:?} at write_batch", e))),
-            #[cfg(with_rocksdb)]
-            ServiceStoreServerInternal::RocksDb(store) => store
-                .write_batch(batch)
-                .await
-                .map_err(|e| Status::unknown(format!("RocksDB error {:?} at write_batch", e))),
-        }
+        self.store
+            .write_batch(batch)
+            .await
+            .map_err(|e| store_error(e, "write_batch"))
     }
 
     pub async fn list_all(&self) -> Result<Vec<Vec<u8>>, Status> {
@@ -209,7 +186,11 @@ impl ServiceStoreServer {
         self.write_batch(batch).await
     }
 
-    pub async fn insert_pending_read<S: Serialize>(&self, value: S) -> (i64, i32) {
+    pub async fn insert_pending_read<T: Serialize>(
+        &self,
+        owner: &Principal,
+        value: T,
+    ) -> (i64, i32) {
         let value = bcs::to_bytes(&value).unwrap();
         let chunks = value
             .chunks(MAX_PAYLOAD_SIZE)
@@ -219,11 +200,271 @@ impl ServiceStoreServer {
         let mut pending_big_reads = self.pending_big_reads.write().await;
         let message_index = pending_big_reads.index;
         pending_big_reads.index += 1;
+        let owner = owner.id;
         pending_big_reads
             .chunks_by_index
-            .insert(message_index, chunks);
+            .insert(message_index, PendingBigRead { owner, chunks });
         (message_index, num_chunks)
     }
+
+    /// Reads the keys matching a prefix.
+    async fn read_keys(store: &S, key_prefix: &[u8]) -> Result<Vec<Vec<u8>>, Status> {
+        let keys = store
+            .find_keys_by_prefix(key_prefix)
+            .await
+            .map_err(|e| store_error(e, "find_keys_by_prefix"))?;
+        keys.iterator()
+            .map(|key| key.map(<[u8]>::to_vec))
+            .collect::<Result<_, _>>()
+            .map_err(|e| store_error(e, "find_keys_by_prefix"))
+    }
+
+    /// Runs `task` in the background, returning the stream of its replies.
+    fn spawn_stream<R, F>(
+        task: impl FnOnce(mpsc::Sender<Result<R, Status>>) -> F,
+    ) -> ReceiverStream<Result<R, Status>>
+    where
+        R: Send + 'static,
+        F: Future<Output = Result<(), Status>> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel(STREAM_BUFFER_SIZE);
+        let error_sender = sender.clone();
+        let task = task(sender);
+        tokio::spawn(async move {
+            if let Err(status) = task.await {
+                // The client may have closed the stream already.
+                let _ = error_sender.send(Err(status)).await;
+            }
+        });
+        ReceiverStream::new(receiver)
+    }
+
+    async fn stream_read_multi_values(
+        store: S,
+        keys: Vec<Vec<u8>>,
+        sender: mpsc::Sender<Result<ReplyStreamReadMultiValues, Status>>,
+    ) -> Result<(), Status> {
+        let mut replies = ReplySender::new(sender, |values| ReplyStreamReadMultiValues { values });
+        for keys in keys.chunks(STREAM_READ_BATCH_SIZE) {
+            let values = store
+                .read_multi_values_bytes(keys.to_vec())
+                .await
+                .map_err(|e| store_error(e, "read_multi_values_bytes"))?;
+            for value in values {
+                match value {
+                    None => {
+                        let part = OptValuePart {
+                            value: None,
+                            last: true,
+                        };
+                        replies.push(part, 0).await?;
+                    }
+                    Some(value) => {
+                        for (value, last) in value_parts(value) {
+                            let size = value.len();
+                            let value = Some(value);
+                            replies.push(OptValuePart { value, last }, size).await?;
+                        }
+                    }
+                }
+            }
+        }
+        replies.flush().await
+    }
+
+    /// Streams the keys matching the prefix. The store has no paged scans, so the keys are
+    /// read in one call and held by the server while they are sent: streaming only bounds
+    /// the size of the messages and the memory used by the client.
+    async fn stream_find_keys_by_prefix(
+        store: S,
+        key_prefix: Vec<u8>,
+        sender: mpsc::Sender<Result<ReplyStreamFindKeysByPrefix, Status>>,
+    ) -> Result<(), Status> {
+        let mut replies = ReplySender::new(sender, |keys| ReplyStreamFindKeysByPrefix { keys });
+        let keys = Self::read_keys(&store, &key_prefix).await?;
+        for key in keys {
+            let size = key.len();
+            replies.push(key, size).await?;
+        }
+        replies.flush().await
+    }
+
+    /// Streams the key-value pairs read by a single scan of the store, so that the keys and
+    /// values are consistent with each other. As for the keys, the whole scan is held by the
+    /// server while it is sent: streaming only bounds the size of the messages and the
+    /// memory used by the client.
+    async fn stream_find_key_values_by_prefix(
+        store: S,
+        key_prefix: Vec<u8>,
+        sender: mpsc::Sender<Result<ReplyStreamFindKeyValuesByPrefix, Status>>,
+    ) -> Result<(), Status> {
+        let mut replies = ReplySender::new(sender, |key_values| ReplyStreamFindKeyValuesByPrefix {
+            key_values,
+        });
+        let key_values = store
+            .find_key_values_by_prefix(&key_prefix)
+            .await
+            .map_err(|e| store_error(e, "find_key_values_by_prefix"))?;
+        for key_value in key_values.into_iterator_owned() {
+            let (key, value) =
+                key_value.map_err(|e| store_error(e, "find_key_values_by_prefix"))?;
+            for (value, last) in value_parts(value) {
+                let size = key.len() + value.len();
+                let key = key.clone();
+                replies
+                    .push(KeyValueAppend { key, value, last }, size)
+                    .await?;
+            }
+        }
+        replies.flush().await
+    }
+
+    /// Forwards the changes of the keys starting with `key_prefix`, until the client
+    /// closes the stream or falls too far behind the feed.
+    async fn tail_changes(
+        mut receiver: broadcast::Receiver<Arc<ChangeEvent>>,
+        key_prefix: Vec<u8>,
+        sender: mpsc::Sender<Result<ReplyTailChanges, Status>>,
+    ) -> Result<(), Status> {
+        loop {
+            let event = tokio::select! {
+                event = receiver.recv() => event,
+                () = sender.closed() => return Ok(()),
+            };
+            let event = match event {
+                Ok(event) => event,
+                Err(RecvError::Lagged(count)) => {
+                    return Err(Status::data_loss(format!(
+                        "the client fell behind and missed {count} changes"
+                    )));
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            };
+            let batch = batch_under_prefix(&event.batch, &key_prefix);
+            if batch.operations.is_empty() {
+                continue;
+            }
+            let sequence = event.sequence;
+            let mut statements = Vec::new();
+            let mut size = 0;
+            for operation in batch.operations {
+                for (statement, statement_size) in change_statements(operation) {
+                    if !statements.is_empty() && size + statement_size > MAX_PAYLOAD_SIZE {
+                        let statements = mem::take(&mut statements);
+                        let reply = ReplyTailChanges {
+                            sequence,
+                            statements,
+                            last: false,
+                        };
+                        send_reply(&sender, reply).await?;
+                        size = 0;
+                    }
+                    statements.push(statement);
+                    size += statement_size;
+                }
+            }
+            let reply = ReplyTailChanges {
+                sequence,
+                statements,
+                last: true,
+            };
+            send_reply(&sender, reply).await?;
+        }
+    }
+}
+
+/// Sends a reply of a stream, waiting until the channel has room for it.
+async fn send_reply<R>(sender: &mpsc::Sender<Result<R, Status>>, reply: R) -> Result<(), Status> {
+    sender
+        .send(Ok(reply))
+        .await
+        .map_err(|_| Status::cancelled("the client closed the stream"))
+}
+
+/// Converts a committed operation into statements, together with their size. Large
+/// values are split into `append` statements.
+fn change_statements(operation: WriteOperation) -> Vec<(Statement, usize)> {
+    let statement = |operation| Statement {
+        operation: Some(operation),
+    };
+    match operation {
+        WriteOperation::Delete { key } => {
+            let size = key.len();
+            vec![(statement(Operation::Delete(key)), size)]
+        }
+        WriteOperation::DeletePrefix { key_prefix } => {
+            let size = key_prefix.len();
+            vec![(statement(Operation::DeletePrefix(key_prefix)), size)]
+        }
+        WriteOperation::Put { key, value } if value.len() <= MAX_PAYLOAD_SIZE => {
+            let size = key.len() + value.len();
+            vec![(statement(Operation::Put(KeyValue { key, value })), size)]
+        }
+        WriteOperation::Put { key, value } => value_parts(value)
+            .into_iter()
+            .map(|(value, last)| {
+                let size = key.len() + value.len();
+                let key = key.clone();
+                let operation = Operation::Append(KeyValueAppend { key, value, last });
+                (statement(operation), size)
+            })
+            .collect(),
+    }
+}
+
+/// Groups the entries of a stream into replies of at most [`MAX_PAYLOAD_SIZE`] bytes.
+///
+/// Sending a reply waits until the channel has room for it, so that a slow client
+/// slows down the reading of the store.
+struct ReplySender<T, R> {
+    sender: mpsc::Sender<Result<R, Status>>,
+    make_reply: fn(Vec<T>) -> R,
+    entries: Vec<T>,
+    size: usize,
+}
+
+impl<T, R> ReplySender<T, R> {
+    fn new(sender: mpsc::Sender<Result<R, Status>>, make_reply: fn(Vec<T>) -> R) -> Self {
+        Self {
+            sender,
+            make_reply,
+            entries: Vec::new(),
+            size: 0,
+        }
+    }
+
+    /// Adds an entry of the given size, sending the pending reply first if it is full.
+    async fn push(&mut self, entry: T, size: usize) -> Result<(), Status> {
+        if !self.entries.is_empty() && self.size + size > MAX_PAYLOAD_SIZE {
+            self.flush().await?;
+        }
+        self.entries.push(entry);
+        self.size += size;
+        Ok(())
+    }
+
+    /// Sends the pending reply, if any.
+    async fn flush(&mut self) -> Result<(), Status> {
+        if self.entries.is_empty() {
+            return Ok(());
+        }
+        let reply = (self.make_reply)(mem::take(&mut self.entries));
+        self.size = 0;
+        send_reply(&self.sender, reply).await
+    }
+}
+
+/// Splits a value into parts fitting in a reply, marking the last one.
+fn value_parts(value: Vec<u8>) -> Vec<(Vec<u8>, bool)> {
+    if value.len() <= MAX_PAYLOAD_SIZE {
+        return vec![(value, true)];
+    }
+    let num_parts = value.len().div_ceil(MAX_PAYLOAD_SIZE);
+    value
+        .chunks(MAX_PAYLOAD_SIZE)
+        .enumerate()
+        .map(|(index, part)| (part.to_vec(), index + 1 == num_parts))
+        .collect()
 }
 
 #[derive(clap::Parser)]
@@ -237,6 +478,8 @@ enum ServiceStoreServerOptions {
     Memory {
         #[arg(long = "endpoint")]
         endpoint: String,
+        #[command(flatten)]
+        security: SecurityOptions,
     },
 
     #[cfg(with_rocksdb)]
@@ -246,18 +489,51 @@ enum ServiceStoreServerOptions {
         path: String,
         #[arg(long = "endpoint")]
         endpoint: String,
+        #[command(flatten)]
+        security: SecurityOptions,
+    },
+
+    #[cfg(with_scylladb)]
+    #[command(name = "scylladb")]
+    ScyllaDb {
+        #[arg(long = "uri")]
+        uri: String,
+        #[arg(long = "endpoint")]
+        endpoint: String,
+        #[command(flatten)]
+        security: SecurityOptions,
     },
 }
 
+/// The TLS and access control options of the server.
+#[derive(clap::Args)]
+struct SecurityOptions {
+    /// The path of the PEM-encoded certificate of the server. TLS is used if it is set.
+    #[arg(long = "tls-cert", requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+    /// The path of the PEM-encoded private key of the server.
+    #[arg(long = "tls-key", requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+    /// The path of a TOML file listing the accepted tokens and the namespaces that
+    /// they give access to. Without it, clients are not authenticated.
+    #[arg(long = "access-control")]
+    access_control: Option<PathBuf>,
+}
+
 #[tonic::async_trait]
-impl StoreProcessor for ServiceStoreServer {
+impl<S> StoreProcessor for ServiceStoreServer<S>
+where
+    S: KeyValueStore + Clone + Send + Sync + 'static,
+{
     #[instrument(target = "store_server", skip_all, err, fields(key_len = ?request.get_ref().key.len()))]
     async fn process_read_value(
         &self,
         request: Request<RequestReadValue>,
     ) -> Result<Response<ReplyReadValue>, Status> {
+        let principal = principal(&request)?;
         let request = request.into_inner();
         let RequestReadValue { key } = request;
+        principal.check_key(&key)?;
         let value = self.read_value_bytes(&key).await?;
         let size = match &value {
             None => 0,
@@ -270,7 +546,7 @@ impl StoreProcessor for ServiceStoreServer {
                 num_chunks: 0,
             }
         } else {
-            let (message_index, num_chunks) = self.insert_pending_read(value).await;
+            let (message_index, num_chunks) = self.insert_pending_read(&principal, value).await;
             ReplyReadValue {
                 value: None,
                 message_index,
@@ -285,8 +561,10 @@ impl StoreProcessor for ServiceStoreServer {
         &self,
         request: Request<RequestContainsKey>,
     ) -> Result<Response<ReplyContainsKey>, Status> {
+        let principal = principal(&request)?;
         let request = request.into_inner();
         let RequestContainsKey { key } = request;
+        principal.check_key(&key)?;
         let test = self.contains_key(&key).await?;
         let response = ReplyContainsKey { test };
         Ok(Response::new(response))
@@ -297,8 +575,12 @@ impl StoreProcessor for ServiceStoreServer {
         &self,
         request: Request<RequestContainsKeys>,
     ) -> Result<Response<ReplyContainsKeys>, Status> {
+        let principal = principal(&request)?;
         let request = request.into_inner();
         let RequestContainsKeys { keys } = request;
+        for key in &keys {
+            principal.check_key(key)?;
+        }
         let tests = self.contains_keys(keys).await?;
         let response = ReplyContainsKeys { tests };
         Ok(Response::new(response))
@@ -309,8 +591,12 @@ impl StoreProcessor for ServiceStoreServer {
         &self,
         request: Request<RequestReadMultiValues>,
     ) -> Result<Response<ReplyReadMultiValues>, Status> {
+        let principal = principal(&request)?;
         let request = request.into_inner();
         let RequestReadMultiValues { keys } = request;
+        for key in &keys {
+            principal.check_key(key)?;
+        }
         let values = self.read_multi_values_bytes(keys.clone()).await?;
         let size = values
             .iter()
@@ -330,7 +616,7 @@ impl StoreProcessor for ServiceStoreServer {
                 num_chunks: 0,
             }
         } else {
-            let (message_index, num_chunks) = self.insert_pending_read(values).await;
+            let (message_index, num_chunks) = self.insert_pending_read(&principal, values).await;
             ReplyReadMultiValues {
                 values: Vec::default(),
                 message_index,
@@ -345,8 +631,10 @@ impl StoreProcessor for ServiceStoreServer {
         &self,
         request: Request<RequestFindKeysByPrefix>,
     ) -> Result<Response<ReplyFindKeysByPrefix>, Status> {
+        let principal = principal(&request)?;
         let request = request.into_inner();
         let RequestFindKeysByPrefix { key_prefix } = request;
+        principal.check_key(&key_prefix)?;
         let keys = self.find_keys_by_prefix(&key_prefix).await?;
         let size = keys.iter().map(|x| x.len()).sum::<usize>();
         let response = if size < MAX_PAYLOAD_SIZE {
@@ -356,7 +644,7 @@ impl StoreProcessor for ServiceStoreServer {
                 num_chunks: 0,
             }
         } else {
-            let (message_index, num_chunks) = self.insert_pending_read(keys).await;
+            let (message_index, num_chunks) = self.insert_pending_read(&principal, keys).await;
             ReplyFindKeysByPrefix {
                 keys: Vec::default(),
                 message_index,
@@ -371,8 +659,10 @@ impl StoreProcessor for ServiceStoreServer {
         &self,
         request: Request<RequestFindKeyValuesByPrefix>,
     ) -> Result<Response<ReplyFindKeyValuesByPrefix>, Status> {
+        let principal = principal(&request)?;
         let request = request.into_inner();
         let RequestFindKeyValuesByPrefix { key_prefix } = request;
+        principal.check_key(&key_prefix)?;
         let key_values = self.find_key_values_by_prefix(&key_prefix).await?;
         let size = key_values
             .iter()
@@ -392,7 +682,8 @@ impl StoreProcessor for ServiceStoreServer {
                 num_chunks: 0,
             }
         } else {
-            let (message_index, num_chunks) = self.insert_pending_read(key_values).await;
+            let (message_index, num_chunks) =
+                self.insert_pending_read(&principal, key_values).await;
             ReplyFindKeyValuesByPrefix {
                 key_values: Vec::default(),
                 message_index,
@@ -402,16 +693,104 @@ impl StoreProcessor for ServiceStoreServer {
         Ok(Response::new(response))
     }
 
+    type ProcessStreamReadMultiValuesStream =
+        ReceiverStream<Result<ReplyStreamReadMultiValues, Status>>;
+
+    #[instrument(target = "store_server", skip_all, err, fields(n_keys = ?request.get_ref().keys.len()))]
+    async fn process_stream_read_multi_values(
+        &self,
+        request: Request<RequestReadMultiValues>,
+    ) -> Result<Response<Self::ProcessStreamReadMultiValuesStream>, Status> {
+        let principal = principal(&request)?;
+        let request = request.into_inner();
+        let RequestReadMultiValues { keys } = request;
+        for key in &keys {
+            principal.check_key(key)?;
+        }
+        let store = self.store.clone();
+        let stream =
+            Self::spawn_stream(move |sender| Self::stream_read_multi_values(store, keys, sender));
+        Ok(Response::new(stream))
+    }
+
+    type ProcessStreamFindKeysByPrefixStream =
+        ReceiverStream<Result<ReplyStreamFindKeysByPrefix, Status>>;
+
+    #[instrument(target = "store_server", skip_all, err, fields(key_prefix_len = ?request.get_ref().key_prefix.len()))]
+    async fn process_stream_find_keys_by_prefix(
+        &self,
+        request: Request<RequestFindKeysByPrefix>,
+    ) -> Result<Response<Self::ProcessStreamFindKeysByPrefixStream>, Status> {
+        let principal = principal(&request)?;
+        let request = request.into_inner();
+        let RequestFindKeysByPrefix { key_prefix } = request;
+        principal.check_key(&key_prefix)?;
+        let store = self.store.clone();
+        let stream = Self::spawn_stream(move |sender| {
+            Self::stream_find_keys_by_prefix(store, key_prefix, sender)
+        });
+        Ok(Response::new(stream))
+    }
+
+    type ProcessStreamFindKeyValuesByPrefixStream =
+        ReceiverStream<Result<ReplyStreamFindKeyValuesByPrefix, Status>>;
+
+    #[instrument(target = "store_server", skip_all, err, fields(key_prefix_len = ?request.get_ref().key_prefix.len()))]
+    async fn process_stream_find_key_values_by_prefix(
+        &self,
+        request: Request<RequestFindKeyValuesByPrefix>,
+    ) -> Result<Response<Self::ProcessStreamFindKeyValuesByPrefixStream>, Status> {
+        let principal = principal(&request)?;
+        let request = request.into_inner();
+        let RequestFindKeyValuesByPrefix { key_prefix } = request;
+        principal.check_key(&key_prefix)?;
+        let store = self.store.clone();
+        let stream = Self::spawn_stream(move |sender| {
+            Self::stream_find_key_values_by_prefix(store, key_prefix, sender)
+        });
+        Ok(Response::new(stream))
+    }
+
+    type ProcessTailChangesStream = ReceiverStream<Result<ReplyTailChanges, Status>>;
+
+    #[instrument(target = "store_server", skip_all, err, fields(namespace = ?request.get_ref().namespace))]
+    async fn process_tail_changes(
+        &self,
+        request: Request<RequestTailChanges>,
+    ) -> Result<Response<Self::ProcessTailChangesStream>, Status> {
+        let principal = principal(&request)?;
+        let request = request.into_inner();
+        let RequestTailChanges { namespace } = request;
+        principal.check_namespace(&namespace)?;
+        let mut key_prefix = vec![KeyTag::Key as u8];
+        key_prefix.extend(namespace);
+        let receiver = self.feed.subscribe();
+        let stream =
+            Self::spawn_stream(move |sender| Self::tail_changes(receiver, key_prefix, sender));
+        Ok(Response::new(stream))
+    }
+
     #[instrument(target = "store_server", skip_all, err, fields(n_statements = ?request.get_ref().statements.len()))]
     async fn process_write_batch_extended(
         &self,
         request: Request<RequestWriteBatchExtended>,
     ) -> Result<Response<ReplyWriteBatchExtended>, Status> {
+        let principal = principal(&request)?;
         let request = request.into_inner();
         let RequestWriteBatchExtended { statements } = request;
         let mut batch = Batch::default();
         for statement in statements {
-            match statement.operation.unwrap() {
+            let operation = statement
+                .operation
+                .ok_or_else(|| Status::invalid_argument("missing operation"))?;
+            let key = match &operation {
+                Operation::Delete(key) => key,
+                Operation::Put(key_value) => &key_value.key,
+                Operation::Append(key_value_append) => &key_value_append.key,
+                Operation::DeletePrefix(key_prefix) => key_prefix,
+            };
+            principal.check_key(key)?;
+            match operation {
                 Operation::Delete(key) => {
                     batch.delete_key(key);
                 }
@@ -451,6 +830,7 @@ impl StoreProcessor for ServiceStoreServer {
         &self,
         request: Request<RequestSpecificChunk>,
     ) -> Result<Response<ReplySpecificChunk>, Status> {
+        let principal = principal(&request)?;
         let request = request.into_inner();
         let RequestSpecificChunk {
             message_index,
@@ -460,9 +840,15 @@ impl StoreProcessor for ServiceStoreServer {
         let Some(entry) = pending_big_reads.chunks_by_index.get(&message_index) else {
             return Err(Status::not_found("process_specific_chunk"));
         };
-        let index = index as usize;
-        let chunk = entry[index].clone();
-        if entry.len() == index + 1 {
+        if entry.owner != principal.id {
+            return Err(Status::not_found("process_specific_chunk"));
+        }
+        let index = usize::try_from(index)
+            .ok()
+            .filter(|index| *index < entry.chunks.len())
+            .ok_or_else(|| Status::invalid_argument(format!("chunk index {index} out of range")))?;
+        let chunk = entry.chunks[index].clone();
+        if entry.chunks.len() == index + 1 {
             pending_big_reads.chunks_by_index.remove(&message_index);
         }
         let response = ReplySpecificChunk { chunk };
@@ -474,8 +860,10 @@ impl StoreProcessor for ServiceStoreServer {
         &self,
         request: Request<RequestCreateNamespace>,
     ) -> Result<Response<ReplyCreateNamespace>, Status> {
+        let principal = principal(&request)?;
         let request = request.into_inner();
         let RequestCreateNamespace { namespace } = request;
+        principal.check_namespace(&namespace)?;
         self.create_namespace(&namespace).await?;
         let response = ReplyCreateNamespace {};
         Ok(Response::new(response))
@@ -486,8 +874,10 @@ impl StoreProcessor for ServiceStoreServer {
         &self,
         request: Request<RequestExistsNamespace>,
     ) -> Result<Response<ReplyExistsNamespace>, Status> {
+        let principal = principal(&request)?;
         let request = request.into_inner();
         let RequestExistsNamespace { namespace } = request;
+        principal.check_namespace(&namespace)?;
         let exists = self.exists_namespace(&namespace).await?;
         let response = ReplyExistsNamespace { exists };
         Ok(Response::new(response))
@@ -498,8 +888,10 @@ impl StoreProcessor for ServiceStoreServer {
         &self,
         request: Request<RequestDeleteNamespace>,
     ) -> Result<Response<ReplyDeleteNamespace>, Status> {
+        let principal = principal(&request)?;
         let request = request.into_inner();
         let RequestDeleteNamespace { namespace } = request;
+        principal.check_namespace(&namespace)?;
         self.delete_namespace(&namespace).await?;
         let response = ReplyDeleteNamespace {};
         Ok(Response::new(response))
@@ -508,9 +900,11 @@ impl StoreProcessor for ServiceStoreServer {
     #[instrument(target = "store_server", skip_all, err, fields(list_all = "list_all"))]
     async fn process_list_all(
         &self,
-        _request: Request<RequestListAll>,
+        request: Request<RequestListAll>,
     ) -> Result<Response<ReplyListAll>, Status> {
-        let namespaces = self.list_all().await?;
+        let principal = principal(&request)?;
+        let mut namespaces = self.list_all().await?;
+        namespaces.retain(|namespace| principal.check_namespace(namespace).is_ok());
         let response = ReplyListAll { namespaces };
         Ok(Response::new(response))
     }
@@ -523,8 +917,9 @@ impl StoreProcessor for ServiceStoreServer {
     )]
     async fn process_delete_all(
         &self,
-        _request: Request<RequestDeleteAll>,
+        request: Request<RequestDeleteAll>,
     ) -> Result<Response<ReplyDeleteAll>, Status> {
+        principal(&request)?.check_admin()?;
         self.delete_all().await?;
         let response = ReplyDeleteAll {};
         Ok(Response::new(response))
@@ -570,17 +965,17 @@ async fn main() {
 
     let options = <ServiceStoreServerOptions as clap::Parser>::parse();
     let common_config = CommonStoreConfig::default();
-    let namespace = "linera_storage_service";
-    let root_key = &[];
-    let (store, endpoint) = match options {
-        ServiceStoreServerOptions::Memory { endpoint } => {
-            let store =
-                MemoryStore::new(common_config.max_stream_queries, namespace, root_key).unwrap();
-            let store = ServiceStoreServerInternal::Memory(store);
-            (store, endpoint)
+    match options {
+        ServiceStoreServerOptions::Memory { endpoint, security } => {
+            let config = MemoryStoreConfig { common_config };
+            run_server::<MemoryStore>(config, endpoint, security).await;
         }
         #[cfg(with_rocksdb)]
-        ServiceStoreServerOptions::RocksDb { path, endpoint } => {
+        ServiceStoreServerOptions::RocksDb {
+            path,
+            endpoint,
+            security,
+        } => {
             let path_buf = path.into();
             let path_with_guard = PathWithGuard::new(path_buf);
             // The server is run in multi-threaded mode so we can use the block_in_place.
@@ -590,24 +985,64 @@ async fn main() {
                 spawn_mode,
                 common_config,
             };
-            let store = RocksDbStore::maybe_create_and_connect(&config, namespace, root_key)
-                .await
-                .expect("store");
-            let store = ServiceStoreServerInternal::RocksDb(store);
-            (store, endpoint)
+            run_server::<RocksDbStore>(config, endpoint, security).await;
+        }
+        #[cfg(with_scylladb)]
+        ServiceStoreServerOptions::ScyllaDb {
+            uri,
+            endpoint,
+            security,
+        } => {
+            let config = ScyllaDbStoreConfig { uri, common_config };
+            run_server::<ScyllaDbStore>(config, endpoint, security).await;
         }
+    }
+}
+
+/// Runs the server on top of a store of type `S`.
+async fn run_server<S>(config: S::Config, endpoint: String, security: SecurityOptions)
+where
+    S: KeyValueStore + Clone + Send + Sync + 'static,
+{
+    let namespace = "linera_storage_service";
+    let root_key = &[];
+    let feed = ChangeFeed::default();
+    let config = ChangeCaptureConfig {
+        inner_config: config,
+        feed: feed.clone(),
     };
+    let store = ChangeCaptureStore::<S>::maybe_create_and_connect(&config, namespace, root_key)
+        .await
+        .expect("store");
     let pending_big_puts = Arc::new(RwLock::new(BTreeMap::default()));
     let pending_big_reads = Arc::new(RwLock::new(PendingBigReads::default()));
     let store = ServiceStoreServer {
         store,
+        feed,
         pending_big_puts,
         pending_big_reads,
     };
+    let access_control = security
+        .access_control
+        .map(|path| AccessControl::read(&path).expect("a valid access control file"));
+    let interceptor = ServerAuthInterceptor::new(access_control);
+    let mut server = Server::builder();
+    if let (Some(tls_cert), Some(tls_key)) = (security.tls_cert, security.tls_key) {
+        let cert = std::fs::read(tls_cert).expect("a readable TLS certificate");
+        let key = std::fs::read(tls_key).expect("a readable TLS private key");
+        let tls_config = ServerTlsConfig::new().identity(Identity::from_pem(cert, key));
+        server = server
+            .tls_config(tls_config)
+            .expect("a valid TLS configuration");
+    }
     let endpoint = endpoint.parse().unwrap();
-    info!("Starting linera_storage_service on endpoint={}", endpoint);
-    Server::builder()
-        .add_service(StoreProcessorServer::new(store))
+    info!(
+        "Starting linera_storage_service with the {} store on endpoint={}",
+        S::Error::BACKEND,
+        endpoint
+    );
+    server
+        .add_service(StoreProcessorServer::with_interceptor(store, interceptor))
         .serve(endpoint)
         .await
         .expect("a successful running of the server");
@@ -0,0 +1,150 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{net::TcpListener, path::Path, time::Duration};
+
+use anyhow::{bail, Result};
+use linera_storage_service::{
+    client::{create_service_store_common_config, ServiceStoreClientInternal},
+    common::{ServiceStoreConfig, ServiceStoreError, ServiceStoreTlsConfig},
+};
+use linera_views::store::{AdminKeyValueStore as _, ReadableKeyValueStore as _};
+use tokio::process::{Child, Command};
+use tonic::Code;
+
+const ACCESS_CONTROL: &str = r#"
+    [[tokens]]
+    name = "admin"
+    token = "admin-secret"
+    namespaces = ["*"]
+
+    [[tokens]]
+    name = "validator"
+    token = "validator-secret"
+    namespaces = ["validator_namespace"]
+"#;
+
+/// Starts a storage server requiring TLS and authentication, and returns its endpoint
+/// and the PEM-encoded certificate that clients must trust.
+async fn start_secure_server(directory: &Path) -> Result<(Child, String, String)> {
+    let certificate = rcgen::generate_simple_self_signed(vec!["localhost".to_string()])?;
+    let certificate_pem = certificate.serialize_pem()?;
+    let cert_path = directory.join("server.crt");
+    let key_path = directory.join("server.key");
+    let access_control_path = directory.join("access_control.toml");
+    std::fs::write(&cert_path, &certificate_pem)?;
+    std::fs::write(&key_path, certificate.serialize_private_key_pem())?;
+    std::fs::write(&access_control_path, ACCESS_CONTROL)?;
+
+    let port = TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+    let endpoint = format!("127.0.0.1:{port}");
+    let child = Command::new(env!("CARGO_BIN_EXE_linera-storage-server"))
+        .args(["memory", "--endpoint", &endpoint, "--tls-cert"])
+        .arg(&cert_path)
+        .arg("--tls-key")
+        .arg(&key_path)
+        .arg("--access-control")
+        .arg(&access_control_path)
+        .kill_on_drop(true)
+        .spawn()?;
+    Ok((child, endpoint, certificate_pem))
+}
+
+fn client_config(
+    endpoint: &str,
+    certificate_pem: Option<&str>,
+    auth_token: Option<&str>,
+) -> ServiceStoreConfig {
+    let mut config =
+        ServiceStoreConfig::new(endpoint.to_string(), create_service_store_common_config());
+    config.tls = certificate_pem.map(|ca_certificate| ServiceStoreTlsConfig {
+        ca_certificate: ca_certificate.to_string(),
+        domain_name: Some("localhost".to_string()),
+    });
+    config.auth_token = auth_token.map(str::to_string);
+    config
+}
+
+/// Waits until the server accepts requests from an authenticated client.
+async fn wait_for_server(config: &ServiceStoreConfig) -> Result<()> {
+    for _ in 0..50 {
+        if ServiceStoreClientInternal::exists(config, "validator_namespace")
+            .await
+            .is_ok()
+        {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    bail!("the storage server did not start");
+}
+
+async fn read_in_namespace(
+    config: &ServiceStoreConfig,
+    namespace: &str,
+) -> Result<Option<Vec<u8>>, ServiceStoreError> {
+    let store = ServiceStoreClientInternal::connect(config, namespace, &[]).await?;
+    store.read_value_bytes(&[42]).await
+}
+
+fn assert_status(result: Result<Option<Vec<u8>>, ServiceStoreError>, code: Code) {
+    match result {
+        Err(ServiceStoreError::GrpcError(status)) => assert_eq!(status.code(), code),
+        result => panic!("expected an error with code {code:?}, got {result:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_storage_service_rejects_unauthorized_clients() -> Result<()> {
+    let directory = tempfile::tempdir()?;
+    let (_child, endpoint, certificate_pem) = start_secure_server(directory.path()).await?;
+    let admin = client_config(&endpoint, Some(&certificate_pem), Some("admin-secret"));
+    wait_for_server(&admin).await?;
+
+    // Authenticated clients using TLS can access their namespaces.
+    let validator = client_config(&endpoint, Some(&certificate_pem), Some("validator-secret"));
+    assert_eq!(read_in_namespace(&admin, "other_namespace").await?, None);
+    assert_eq!(
+        read_in_namespace(&validator, "validator_namespace").await?,
+        None
+    );
+
+    // Other namespaces and administrative operations are forbidden.
+    assert_status(
+        read_in_namespace(&validator, "other_namespace").await,
+        Code::PermissionDenied,
+    );
+    assert!(ServiceStoreClientInternal::list_all(&validator)
+        .await
+        .is_err());
+
+    // Clients without a valid token are rejected.
+    let anonymous = client_config(&endpoint, Some(&certificate_pem), None);
+    assert_status(
+        read_in_namespace(&anonymous, "validator_namespace").await,
+        Code::Unauthenticated,
+    );
+    let impostor = client_config(&endpoint, Some(&certificate_pem), Some("wrong-secret"));
+    assert_status(
+        read_in_namespace(&impostor, "validator_namespace").await,
+        Code::Unauthenticated,
+    );
+
+    // Clients not using TLS cannot talk to the server, even with a valid token.
+    let plaintext = client_config(&endpoint, None, Some("admin-secret"));
+    assert!(read_in_namespace(&plaintext, "validator_namespace")
+        .await
+        .is_err());
+
+    // Clients not trusting the server's certificate do not connect.
+    let other_certificate = rcgen::generate_simple_self_signed(vec!["localhost".to_string()])?;
+    let untrusting = client_config(
+        &endpoint,
+        Some(&other_certificate.serialize_pem()?),
+        Some("admin-secret"),
+    );
+    assert!(read_in_namespace(&untrusting, "validator_namespace")
+        .await
+        .is_err());
+    Ok(())
+}
@@ -4,9 +4,11 @@
 #![cfg(feature = "storage-service")]
 
 use anyhow::Result;
+use futures::TryStreamExt as _;
 use linera_storage_service::client::{create_service_test_store, ServiceStoreClient};
 use linera_views::{
     batch::Batch,
+    store::{AdminKeyValueStore as _, WritableKeyValueStore as _},
     test_utils::{
         admin_test, get_random_byte_vector, get_random_test_scenarios, run_reads,
         run_test_batch_from_blank, run_writes_from_blank, run_writes_from_state,
@@ -54,3 +56,83 @@ async fn test_storage_service_big_raw_write() -> Result<()> {
     run_test_batch_from_blank(&key_value_store, key_prefix, batch).await;
     Ok(())
 }
+
+#[tokio::test]
+async fn test_storage_service_streaming_reads() -> Result<()> {
+    let key_value_store = create_service_test_store().await?;
+    let mut rng = linera_views::random::make_deterministic_rng();
+    let mut batch = Batch::new();
+    let mut key_values = Vec::new();
+    // Many small values, and values too large to fit in a single reply.
+    for i in 0..200u8 {
+        let size = if i % 50 == 0 { 5000000 } else { 1000 };
+        let value = get_random_byte_vector(&mut rng, &[], size);
+        batch.put_key_value_bytes(vec![7, i], value.clone());
+        key_values.push((vec![i], value));
+    }
+    key_value_store.write_batch(batch).await?;
+
+    let keys = key_value_store
+        .stream_find_keys_by_prefix(&[7])
+        .await?
+        .try_collect::<Vec<_>>()
+        .await?;
+    let expected_keys = key_values.iter().map(|(key, _)| key.clone());
+    assert_eq!(keys, expected_keys.collect::<Vec<_>>());
+
+    let read_key_values = key_value_store
+        .stream_find_key_values_by_prefix(&[7])
+        .await?
+        .try_collect::<Vec<_>>()
+        .await?;
+    assert_eq!(read_key_values, key_values);
+
+    let mut keys = key_values
+        .iter()
+        .map(|(key, _)| [&[7], &key[..]].concat())
+        .collect::<Vec<_>>();
+    keys.push(vec![8]);
+    let values = key_value_store
+        .stream_read_multi_values_bytes(keys)
+        .await?
+        .try_collect::<Vec<_>>()
+        .await?;
+    let mut expected_values = key_values
+        .into_iter()
+        .map(|(_, value)| Some(value))
+        .collect::<Vec<_>>();
+    expected_values.push(None);
+    assert_eq!(values, expected_values);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_storage_service_tail_changes() -> Result<()> {
+    let key_value_store = create_service_test_store().await?;
+    let store = key_value_store.clone_with_root_key(&[1])?;
+    let other_store = key_value_store.clone_with_root_key(&[2])?;
+    let mut changes = Box::pin(store.tail_changes().await?);
+
+    let mut rng = linera_views::random::make_deterministic_rng();
+    let mut big_batch = Batch::new();
+    let value = get_random_byte_vector(&mut rng, &[], 5000000);
+    big_batch.put_key_value_bytes(vec![3], value);
+    store.write_batch(big_batch.clone()).await?;
+    // The changes under another root key are not received.
+    let mut other_batch = Batch::new();
+    other_batch.put_key_value_bytes(vec![3], vec![4]);
+    other_store.write_batch(other_batch).await?;
+    let mut batch = Batch::new();
+    batch.put_key_value_bytes(vec![5], vec![6]);
+    batch.delete_key(vec![3]);
+    batch.delete_key_prefix(vec![]);
+    store.write_batch(batch.clone()).await?;
+
+    let event = changes.try_next().await?.unwrap();
+    assert_eq!(event.root_key, vec![1]);
+    assert_eq!(event.batch, big_batch);
+    let next_event = changes.try_next().await?.unwrap();
+    assert!(next_event.sequence > event.sequence + 1);
+    assert_eq!(next_event.batch, batch);
+    Ok(())
+}
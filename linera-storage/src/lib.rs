@@ -243,10 +243,7 @@ pub trait Storage: Sized {
         system_state.description.set(Some(description));
         system_state.epoch.set(Some(Epoch::ZERO));
         system_state.admin_id.set(Some(admin_id));
-        system_state
-            .committees
-            .get_mut()
-            .insert(Epoch::ZERO, committee);
+        system_state.committees_mut().insert(Epoch::ZERO, committee);
         system_state
             .ownership
             .set(ChainOwnership::single(public_key));
@@ -0,0 +1,455 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Adds a change-data-capture feed to a given store.
+//!
+//! Every batch successfully written through a [`ChangeCaptureStore`] is published, together
+//! with the namespace and the root key of the store, to the listeners of a [`ChangeFeed`].
+//! All the stores connected with the same configuration share the same feed, and the
+//! changes are numbered in the order in which they were committed.
+//!
+//! The feed is not persisted: listeners only receive the changes started after they
+//! subscribed, and a listener that falls behind by more than the capacity of the feed
+//! is told how many changes it missed. While nobody listens, batches are neither numbered
+//! nor serialized, so the feed costs nothing to the writers.
+
+use std::sync::Arc;
+
+use async_lock::{Mutex, MutexGuard};
+use tokio::sync::broadcast;
+
+#[cfg(with_testing)]
+use crate::store::TestKeyValueStore;
+use crate::{
+    batch::{Batch, WriteOperation},
+    store::{AdminKeyValueStore, ReadableKeyValueStore, WithError, WritableKeyValueStore},
+};
+
+/// The default number of changes kept for the listeners that fall behind.
+pub const DEFAULT_CHANGE_FEED_CAPACITY: usize = 1024;
+
+/// A batch committed to a store.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ChangeEvent {
+    /// The position of the change in the feed. It increases with every batch committed
+    /// while the feed has listeners.
+    pub sequence: u64,
+    /// The namespace of the store that committed the batch.
+    pub namespace: String,
+    /// The root key of the store that committed the batch.
+    pub root_key: Vec<u8>,
+    /// The committed operations, with keys relative to the root key.
+    pub batch: Batch,
+}
+
+/// The shared state of a [`ChangeFeed`].
+struct ChangeFeedInner {
+    sender: broadcast::Sender<Arc<ChangeEvent>>,
+    /// The sequence number of the next change. The lock is held while a batch is
+    /// committed and published, so that the changes are numbered and received in the
+    /// order in which they were committed.
+    next_sequence: Mutex<u64>,
+}
+
+/// The ordered stream of changes committed to the stores sharing it.
+#[derive(Clone)]
+pub struct ChangeFeed {
+    inner: Arc<ChangeFeedInner>,
+}
+
+impl Default for ChangeFeed {
+    fn default() -> Self {
+        Self::new(DEFAULT_CHANGE_FEED_CAPACITY)
+    }
+}
+
+impl ChangeFeed {
+    /// Creates a feed keeping up to `capacity` changes for the listeners that fall behind.
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        let inner = ChangeFeedInner {
+            sender,
+            next_sequence: Mutex::new(0),
+        };
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+
+    /// Registers a listener receiving the changes committed from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<ChangeEvent>> {
+        self.inner.sender.subscribe()
+    }
+
+    /// Returns the sequence number that the next change will have.
+    pub async fn next_sequence(&self) -> u64 {
+        *self.inner.next_sequence.lock().await
+    }
+
+    fn has_listeners(&self) -> bool {
+        self.inner.sender.receiver_count() > 0
+    }
+
+    /// Acquires the right to commit the next change.
+    async fn lock(&self) -> MutexGuard<'_, u64> {
+        self.inner.next_sequence.lock().await
+    }
+
+    /// Numbers a committed change and sends it to the listeners. The guard must have been
+    /// held since before the change was committed.
+    fn publish(
+        &self,
+        next_sequence: &mut MutexGuard<'_, u64>,
+        namespace: &str,
+        root_key: &[u8],
+        batch: Batch,
+    ) {
+        let sequence = **next_sequence;
+        **next_sequence += 1;
+        let event = ChangeEvent {
+            sequence,
+            namespace: namespace.to_string(),
+            root_key: root_key.to_vec(),
+            batch,
+        };
+        // Sending only fails if all the listeners left in the meantime.
+        let _ = self.inner.sender.send(Arc::new(event));
+    }
+}
+
+/// The configuration of a [`ChangeCaptureStore`].
+#[derive(Clone)]
+pub struct ChangeCaptureConfig<C> {
+    /// The configuration of the underlying store.
+    pub inner_config: C,
+    /// The feed receiving the changes of all the stores using this configuration.
+    pub feed: ChangeFeed,
+}
+
+/// A key-value store publishing its committed batches to a [`ChangeFeed`].
+#[derive(Clone)]
+pub struct ChangeCaptureStore<K> {
+    /// The underlying store of the transformed store.
+    pub store: K,
+    namespace: String,
+    root_key: Vec<u8>,
+    feed: ChangeFeed,
+}
+
+impl<K> WithError for ChangeCaptureStore<K>
+where
+    K: WithError,
+{
+    type Error = K::Error;
+}
+
+impl<K> ReadableKeyValueStore for ChangeCaptureStore<K>
+where
+    K: ReadableKeyValueStore + Send + Sync,
+{
+    const MAX_KEY_SIZE: usize = K::MAX_KEY_SIZE;
+    type Keys = K::Keys;
+    type KeyValues = K::KeyValues;
+
+    fn max_stream_queries(&self) -> usize {
+        self.store.max_stream_queries()
+    }
+
+    async fn read_value_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        self.store.read_value_bytes(key).await
+    }
+
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, Self::Error> {
+        self.store.contains_key(key).await
+    }
+
+    async fn contains_keys(&self, keys: Vec<Vec<u8>>) -> Result<Vec<bool>, Self::Error> {
+        self.store.contains_keys(keys).await
+    }
+
+    async fn read_multi_values_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, Self::Error> {
+        self.store.read_multi_values_bytes(keys).await
+    }
+
+    async fn find_keys_by_prefix(&self, key_prefix: &[u8]) -> Result<Self::Keys, Self::Error> {
+        self.store.find_keys_by_prefix(key_prefix).await
+    }
+
+    async fn find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Self::KeyValues, Self::Error> {
+        self.store.find_key_values_by_prefix(key_prefix).await
+    }
+}
+
+impl<K> WritableKeyValueStore for ChangeCaptureStore<K>
+where
+    K: WritableKeyValueStore + Send + Sync,
+{
+    const MAX_VALUE_SIZE: usize = K::MAX_VALUE_SIZE;
+
+    async fn write_batch(&self, batch: Batch) -> Result<(), Self::Error> {
+        // Without listeners, there is no order to preserve: the batch is written without
+        // waiting for the other writes.
+        if batch.operations.is_empty() || !self.feed.has_listeners() {
+            return self.store.write_batch(batch).await;
+        }
+        let mut next_sequence = self.feed.lock().await;
+        let captured = batch.clone();
+        self.store.write_batch(batch).await?;
+        self.feed.publish(
+            &mut next_sequence,
+            &self.namespace,
+            &self.root_key,
+            captured,
+        );
+        Ok(())
+    }
+
+    async fn clear_journal(&self) -> Result<(), Self::Error> {
+        self.store.clear_journal().await
+    }
+}
+
+impl<K> AdminKeyValueStore for ChangeCaptureStore<K>
+where
+    K: AdminKeyValueStore + Send + Sync,
+{
+    type Config = ChangeCaptureConfig<K::Config>;
+
+    async fn connect(
+        config: &Self::Config,
+        namespace: &str,
+        root_key: &[u8],
+    ) -> Result<Self, Self::Error> {
+        let store = K::connect(&config.inner_config, namespace, root_key).await?;
+        Ok(Self::new(store, namespace, root_key, config.feed.clone()))
+    }
+
+    fn clone_with_root_key(&self, root_key: &[u8]) -> Result<Self, Self::Error> {
+        let store = self.store.clone_with_root_key(root_key)?;
+        Ok(Self::new(
+            store,
+            &self.namespace,
+            root_key,
+            self.feed.clone(),
+        ))
+    }
+
+    async fn list_all(config: &Self::Config) -> Result<Vec<String>, Self::Error> {
+        K::list_all(&config.inner_config).await
+    }
+
+    async fn delete_all(config: &Self::Config) -> Result<(), Self::Error> {
+        K::delete_all(&config.inner_config).await
+    }
+
+    async fn exists(config: &Self::Config, namespace: &str) -> Result<bool, Self::Error> {
+        K::exists(&config.inner_config, namespace).await
+    }
+
+    async fn create(config: &Self::Config, namespace: &str) -> Result<(), Self::Error> {
+        K::create(&config.inner_config, namespace).await
+    }
+
+    async fn delete(config: &Self::Config, namespace: &str) -> Result<(), Self::Error> {
+        K::delete(&config.inner_config, namespace).await
+    }
+}
+
+#[cfg(with_testing)]
+impl<K> TestKeyValueStore for ChangeCaptureStore<K>
+where
+    K: TestKeyValueStore + Send + Sync,
+{
+    async fn new_test_config() -> Result<Self::Config, Self::Error> {
+        let inner_config = K::new_test_config().await?;
+        let feed = ChangeFeed::default();
+        Ok(ChangeCaptureConfig { inner_config, feed })
+    }
+}
+
+impl<K> ChangeCaptureStore<K> {
+    /// Wraps a store connected to `namespace` with `root_key`, publishing its changes to
+    /// `feed`.
+    pub fn new(store: K, namespace: &str, root_key: &[u8], feed: ChangeFeed) -> Self {
+        ChangeCaptureStore {
+            store,
+            namespace: namespace.to_string(),
+            root_key: root_key.to_vec(),
+            feed,
+        }
+    }
+
+    /// Returns the feed receiving the changes of the store.
+    pub fn feed(&self) -> &ChangeFeed {
+        &self.feed
+    }
+}
+
+/// Returns the operations of `batch` affecting the keys starting with `prefix`, with
+/// the prefix removed from their keys.
+///
+/// Deleting a prefix of `prefix` deletes all the keys starting with `prefix`, so it
+/// becomes the deletion of the empty prefix.
+pub fn batch_under_prefix(batch: &Batch, prefix: &[u8]) -> Batch {
+    let mut result = Batch::new();
+    for operation in &batch.operations {
+        match operation {
+            WriteOperation::Delete { key } => {
+                if let Some(key) = key.strip_prefix(prefix) {
+                    result.delete_key(key.to_vec());
+                }
+            }
+            WriteOperation::Put { key, value } => {
+                if let Some(key) = key.strip_prefix(prefix) {
+                    result.put_key_value_bytes(key.to_vec(), value.clone());
+                }
+            }
+            WriteOperation::DeletePrefix { key_prefix } => {
+                if let Some(key_prefix) = key_prefix.strip_prefix(prefix) {
+                    result.delete_key_prefix(key_prefix.to_vec());
+                } else if prefix.starts_with(key_prefix) {
+                    result.delete_key_prefix(Vec::new());
+                }
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{future, FutureExt as _};
+    use linera_views::{
+        batch::Batch,
+        change_capture::{batch_under_prefix, ChangeCaptureStore, ChangeFeed},
+        memory::MemoryStore,
+        store::{
+            AdminKeyValueStore as _, ReadableKeyValueStore as _, TestKeyValueStore as _,
+            WritableKeyValueStore as _,
+        },
+    };
+    use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+
+    #[tokio::test]
+    async fn test_change_capture_events() {
+        let store = ChangeCaptureStore::<MemoryStore>::new_test_store()
+            .await
+            .unwrap();
+        let other_store = store.clone_with_root_key(&[9]).unwrap();
+        let mut receiver = store.feed().subscribe();
+
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(vec![1], vec![2]);
+        batch.delete_key_prefix(vec![3]);
+        store.write_batch(batch.clone()).await.unwrap();
+        // Empty batches are not published.
+        store.write_batch(Batch::new()).await.unwrap();
+        let mut other_batch = Batch::new();
+        other_batch.delete_key(vec![4]);
+        other_store.write_batch(other_batch.clone()).await.unwrap();
+
+        let event = receiver.recv().await.unwrap();
+        assert_eq!(event.sequence, 0);
+        assert_eq!(event.root_key, Vec::<u8>::new());
+        assert_eq!(event.batch, batch);
+        let other_event = receiver.recv().await.unwrap();
+        assert_eq!(other_event.sequence, 1);
+        assert_eq!(other_event.namespace, event.namespace);
+        assert_eq!(other_event.root_key, vec![9]);
+        assert_eq!(other_event.batch, other_batch);
+        assert_eq!(receiver.try_recv().unwrap_err(), TryRecvError::Empty);
+        assert_eq!(store.feed().next_sequence().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_change_capture_commit_order() {
+        let store = ChangeCaptureStore::<MemoryStore>::new_test_store()
+            .await
+            .unwrap();
+        let mut receiver = store.feed().subscribe();
+        let writes = (0..50u8).map(|i| {
+            let mut batch = Batch::new();
+            batch.put_key_value_bytes(vec![0], vec![i]);
+            store.write_batch(batch)
+        });
+        for result in future::join_all(writes).await {
+            result.unwrap();
+        }
+
+        // The last change in the feed is the last one that was committed.
+        let mut last_event = None;
+        for sequence in 0..50 {
+            let event = receiver.recv().await.unwrap();
+            assert_eq!(event.sequence, sequence);
+            last_event = Some(event);
+        }
+        let value = store.read_value_bytes(&[0]).await.unwrap();
+        let mut expected = Batch::new();
+        expected.put_key_value_bytes(vec![0], value.unwrap());
+        assert_eq!(last_event.unwrap().batch, expected);
+    }
+
+    #[tokio::test]
+    async fn test_change_capture_without_listeners() {
+        let store = ChangeCaptureStore::<MemoryStore>::new_test_store()
+            .await
+            .unwrap();
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(vec![1], vec![2]);
+
+        // Without listeners, writes neither wait for the feed nor consume sequence numbers.
+        let guard = store.feed().lock().await;
+        store
+            .write_batch(batch.clone())
+            .now_or_never()
+            .expect("the write should not wait for the feed")
+            .unwrap();
+        drop(guard);
+        assert_eq!(store.feed().next_sequence().await, 0);
+
+        let mut receiver = store.feed().subscribe();
+        store.write_batch(batch.clone()).await.unwrap();
+        let event = receiver.recv().await.unwrap();
+        assert_eq!(event.sequence, 0);
+        assert_eq!(event.batch, batch);
+    }
+
+    #[tokio::test]
+    async fn test_change_capture_lagging_listener() {
+        let feed = ChangeFeed::new(2);
+        let store = MemoryStore::new_test_store().await.unwrap();
+        let store = ChangeCaptureStore::new(store, "test", &[], feed.clone());
+        let mut receiver = feed.subscribe();
+        for i in 0..3 {
+            let mut batch = Batch::new();
+            batch.put_key_value_bytes(vec![i], vec![]);
+            store.write_batch(batch).await.unwrap();
+        }
+        assert_eq!(receiver.recv().await.unwrap_err(), RecvError::Lagged(1));
+        assert_eq!(receiver.recv().await.unwrap().sequence, 1);
+        assert_eq!(receiver.recv().await.unwrap().sequence, 2);
+    }
+
+    #[test]
+    fn test_batch_under_prefix() {
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(vec![1, 2, 3], vec![4]);
+        batch.put_key_value_bytes(vec![2, 2], vec![5]);
+        batch.delete_key(vec![1, 2]);
+        batch.delete_key_prefix(vec![1, 2, 7]);
+        batch.delete_key_prefix(vec![1]);
+        batch.delete_key_prefix(vec![2]);
+        let mut expected = Batch::new();
+        expected.put_key_value_bytes(vec![3], vec![4]);
+        expected.delete_key(vec![]);
+        expected.delete_key_prefix(vec![7]);
+        expected.delete_key_prefix(vec![]);
+        assert_eq!(batch_under_prefix(&batch, &[1, 2]), expected);
+    }
+}
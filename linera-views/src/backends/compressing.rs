@@ -0,0 +1,387 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Adds transparent compression of large values to a given store.
+//!
+//! Values whose size exceeds a threshold are compressed with zstd before being written
+//! to the inner store. Compressed values are prefixed with a small header so that they
+//! can be told apart from values that were stored uncompressed, including values that
+//! were written before the compression layer was introduced.
+
+#[cfg(with_metrics)]
+use std::sync::LazyLock;
+
+use thiserror::Error;
+#[cfg(with_metrics)]
+use {
+    linera_base::prometheus_util,
+    prometheus::{HistogramVec, IntCounterVec},
+};
+
+#[cfg(with_testing)]
+use crate::memory::{create_test_memory_store, MemoryStore};
+use crate::{
+    batch::{Batch, WriteOperation},
+    store::{
+        KeyIterable, KeyValueIterable, KeyValueStoreError, ReadableKeyValueStore,
+        RestrictedKeyValueStore, WithError, WritableKeyValueStore,
+    },
+};
+
+/// The magic bytes starting every value written with a header by the [`CompressingStore`].
+///
+/// Values not starting with these bytes are returned unchanged, which is how values
+/// written before compression was enabled remain readable.
+const MAGIC: [u8; 4] = [0xff, b'L', b'Z', b'C'];
+
+/// The tag of a value stored uncompressed behind a header. This is only used for small
+/// values that happen to start with the [`MAGIC`] bytes.
+const TAG_UNCOMPRESSED: u8 = 0;
+
+/// The tag of a value compressed with zstd.
+const TAG_ZSTD: u8 = 1;
+
+/// The size of the header of tagged values.
+const HEADER_SIZE: usize = MAGIC.len() + 1;
+
+/// The zstd compression level used for values.
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// The default size above which values are compressed.
+pub const DEFAULT_COMPRESSION_THRESHOLD: usize = 1024;
+
+#[cfg(with_metrics)]
+/// The number of values that were compressed when written.
+static NUM_COMPRESSED_VALUES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "num_compressed_values",
+        "Number of values compressed when written",
+        &[],
+    )
+    .expect("Counter creation should not fail")
+});
+
+#[cfg(with_metrics)]
+/// The number of values above the threshold that were stored uncompressed because
+/// compression did not reduce their size.
+static NUM_INCOMPRESSIBLE_VALUES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    prometheus_util::register_int_counter_vec(
+        "num_incompressible_values",
+        "Number of values above the threshold that did not benefit from compression",
+        &[],
+    )
+    .expect("Counter creation should not fail")
+});
+
+#[cfg(with_metrics)]
+/// The ratio between the compressed and the uncompressed size of the written values.
+static COMPRESSION_RATIO: LazyLock<HistogramVec> = LazyLock::new(|| {
+    prometheus_util::register_histogram_vec(
+        "compression_ratio",
+        "Ratio between the compressed and uncompressed size of written values",
+        &[],
+        Some(vec![
+            0.01, 0.02, 0.05, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 1.0,
+        ]),
+    )
+    .expect("Histogram creation should not fail")
+});
+
+#[cfg(with_metrics)]
+/// The uncompressed size of the values that were compressed.
+static COMPRESSED_VALUE_SIZE: LazyLock<HistogramVec> = LazyLock::new(|| {
+    prometheus_util::register_histogram_vec(
+        "compressed_value_uncompressed_size",
+        "Uncompressed size of the values that were compressed",
+        &[],
+        Some(vec![
+            1024.0, 4096.0, 16384.0, 65536.0, 262144.0, 1048576.0, 4194304.0, 16777216.0,
+        ]),
+    )
+    .expect("Histogram creation should not fail")
+});
+
+/// The composed error type built from the inner error type.
+#[derive(Error, Debug)]
+pub enum CompressingError<E> {
+    /// inner store error
+    #[error("inner store error")]
+    InnerStoreError(#[from] E),
+
+    /// A compression or decompression error
+    #[error("compression error: {0}")]
+    Compression(std::io::Error),
+}
+
+impl<E: KeyValueStoreError> From<bcs::Error> for CompressingError<E> {
+    fn from(error: bcs::Error) -> Self {
+        let error = E::from(error);
+        CompressingError::InnerStoreError(error)
+    }
+}
+
+impl<E: KeyValueStoreError + 'static> KeyValueStoreError for CompressingError<E> {
+    const BACKEND: &'static str = "compressing";
+}
+
+/// A key-value store compressing large values.
+///
+/// It wraps a key-value store and compresses the values whose size exceeds
+/// `threshold`. Values that were stored by the inner store without going through
+/// this wrapper are still read correctly.
+#[derive(Clone)]
+pub struct CompressingStore<K> {
+    /// The underlying store of the transformed store.
+    pub store: K,
+    /// The size above which values are compressed.
+    threshold: usize,
+}
+
+impl<K> WithError for CompressingStore<K>
+where
+    K: WithError,
+    K::Error: 'static,
+{
+    type Error = CompressingError<K::Error>;
+}
+
+impl<K> ReadableKeyValueStore for CompressingStore<K>
+where
+    K: RestrictedKeyValueStore + Send + Sync,
+    K::Error: 'static,
+{
+    const MAX_KEY_SIZE: usize = K::MAX_KEY_SIZE;
+    type Keys = Vec<Vec<u8>>;
+    type KeyValues = Vec<(Vec<u8>, Vec<u8>)>;
+
+    fn max_stream_queries(&self) -> usize {
+        self.store.max_stream_queries()
+    }
+
+    async fn read_value_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+        let value = self.store.read_value_bytes(key).await?;
+        value.map(Self::decode_value).transpose()
+    }
+
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, Self::Error> {
+        Ok(self.store.contains_key(key).await?)
+    }
+
+    async fn contains_keys(&self, keys: Vec<Vec<u8>>) -> Result<Vec<bool>, Self::Error> {
+        Ok(self.store.contains_keys(keys).await?)
+    }
+
+    async fn read_multi_values_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, Self::Error> {
+        let values = self.store.read_multi_values_bytes(keys).await?;
+        values
+            .into_iter()
+            .map(|value| value.map(Self::decode_value).transpose())
+            .collect()
+    }
+
+    async fn find_keys_by_prefix(&self, key_prefix: &[u8]) -> Result<Self::Keys, Self::Error> {
+        let mut keys = Vec::new();
+        for key in self.store.find_keys_by_prefix(key_prefix).await?.iterator() {
+            keys.push(key?.to_vec());
+        }
+        Ok(keys)
+    }
+
+    async fn find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Self::KeyValues, Self::Error> {
+        let key_values = self.store.find_key_values_by_prefix(key_prefix).await?;
+        let mut result = Vec::new();
+        for key_value in key_values.into_iterator_owned() {
+            let (key, value) = key_value?;
+            result.push((key, Self::decode_value(value)?));
+        }
+        Ok(result)
+    }
+}
+
+impl<K> WritableKeyValueStore for CompressingStore<K>
+where
+    K: RestrictedKeyValueStore + Send + Sync,
+    K::Error: 'static,
+{
+    // Values starting with the magic bytes need a header even if they are not compressed.
+    const MAX_VALUE_SIZE: usize = K::MAX_VALUE_SIZE.saturating_sub(HEADER_SIZE);
+
+    async fn write_batch(&self, batch: Batch) -> Result<(), Self::Error> {
+        let mut batch_new = Batch::new();
+        for operation in batch.operations {
+            match operation {
+                WriteOperation::Delete { key } => batch_new.delete_key(key),
+                WriteOperation::Put { key, value } => {
+                    let value = self.encode_value(value)?;
+                    batch_new.put_key_value_bytes(key, value);
+                }
+                WriteOperation::DeletePrefix { key_prefix } => {
+                    batch_new.delete_key_prefix(key_prefix)
+                }
+            }
+        }
+        Ok(self.store.write_batch(batch_new).await?)
+    }
+
+    async fn clear_journal(&self) -> Result<(), Self::Error> {
+        Ok(self.store.clear_journal().await?)
+    }
+}
+
+impl<K> CompressingStore<K>
+where
+    K: RestrictedKeyValueStore + Send + Sync,
+    K::Error: 'static,
+{
+    /// Creates a new store compressing the values larger than `threshold` bytes.
+    pub fn new(store: K, threshold: usize) -> Self {
+        CompressingStore { store, threshold }
+    }
+
+    /// Returns the size above which values are compressed.
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    fn encode_value(&self, value: Vec<u8>) -> Result<Vec<u8>, CompressingError<K::Error>> {
+        if value.len() > self.threshold {
+            let compressed = zstd::stream::encode_all(&*value, COMPRESSION_LEVEL)
+                .map_err(CompressingError::Compression)?;
+            #[cfg(with_metrics)]
+            COMPRESSION_RATIO
+                .with_label_values(&[])
+                .observe(compressed.len() as f64 / value.len() as f64);
+            if compressed.len() + HEADER_SIZE < value.len() {
+                #[cfg(with_metrics)]
+                {
+                    NUM_COMPRESSED_VALUES.with_label_values(&[]).inc();
+                    COMPRESSED_VALUE_SIZE
+                        .with_label_values(&[])
+                        .observe(value.len() as f64);
+                }
+                return Ok(Self::tagged_value(TAG_ZSTD, &compressed));
+            }
+            #[cfg(with_metrics)]
+            NUM_INCOMPRESSIBLE_VALUES.with_label_values(&[]).inc();
+        }
+        if value.starts_with(&MAGIC) {
+            return Ok(Self::tagged_value(TAG_UNCOMPRESSED, &value));
+        }
+        Ok(value)
+    }
+
+    fn tagged_value(tag: u8, payload: &[u8]) -> Vec<u8> {
+        let mut value = Vec::with_capacity(HEADER_SIZE + payload.len());
+        value.extend_from_slice(&MAGIC);
+        value.push(tag);
+        value.extend_from_slice(payload);
+        value
+    }
+
+    fn decode_value(value: Vec<u8>) -> Result<Vec<u8>, CompressingError<K::Error>> {
+        if value.len() < HEADER_SIZE || !value.starts_with(&MAGIC) {
+            return Ok(value);
+        }
+        let payload = &value[HEADER_SIZE..];
+        match value[MAGIC.len()] {
+            TAG_UNCOMPRESSED => Ok(payload.to_vec()),
+            TAG_ZSTD => zstd::stream::decode_all(payload).map_err(CompressingError::Compression),
+            // A value written before compression was introduced, that happens to start with
+            // the magic bytes.
+            _ => Ok(value),
+        }
+    }
+}
+
+/// Provides a `CompressingStore<MemoryStore>` with a small threshold that can be used for tests.
+#[cfg(with_testing)]
+pub fn create_compressing_memory_store() -> CompressingStore<MemoryStore> {
+    CompressingStore::new(create_test_memory_store(), 16)
+}
+
+#[cfg(test)]
+mod tests {
+    use linera_views::{
+        batch::Batch,
+        compressing::CompressingStore,
+        memory::create_test_memory_store,
+        store::{ReadableKeyValueStore, WritableKeyValueStore},
+    };
+    use rand::Rng;
+
+    use super::{MAGIC, TAG_ZSTD};
+
+    #[tokio::test]
+    async fn test_compressing_large_value() {
+        let store = create_test_memory_store();
+        let compressing_store = CompressingStore::new(store.clone(), 100);
+        let key = vec![0, 0];
+        let value = vec![7; 1000];
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(key.clone(), value.clone());
+        compressing_store.write_batch(batch).await.unwrap();
+        let raw_value = store.read_value_bytes(&key).await.unwrap().unwrap();
+        assert!(raw_value.starts_with(&MAGIC));
+        assert_eq!(raw_value[MAGIC.len()], TAG_ZSTD);
+        assert!(raw_value.len() < value.len());
+        let value_read = compressing_store.read_value_bytes(&key).await.unwrap();
+        assert_eq!(value_read, Some(value));
+    }
+
+    #[tokio::test]
+    async fn test_compressing_incompressible_value() {
+        let store = create_test_memory_store();
+        let compressing_store = CompressingStore::new(store.clone(), 100);
+        let key = vec![0, 1];
+        let mut rng = crate::random::make_deterministic_rng();
+        let value = (0..1000).map(|_| rng.gen::<u8>()).collect::<Vec<_>>();
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(key.clone(), value.clone());
+        compressing_store.write_batch(batch).await.unwrap();
+        let raw_value = store.read_value_bytes(&key).await.unwrap();
+        assert_eq!(raw_value.as_ref(), Some(&value));
+        let value_read = compressing_store.read_value_bytes(&key).await.unwrap();
+        assert_eq!(value_read, Some(value));
+    }
+
+    #[tokio::test]
+    async fn test_compressing_legacy_and_magic_values() {
+        let store = create_test_memory_store();
+        let compressing_store = CompressingStore::new(store.clone(), 100);
+        // A value written before the compression layer existed.
+        let legacy_value = vec![1; 1000];
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(vec![1], legacy_value.clone());
+        store.write_batch(batch).await.unwrap();
+        // A small value starting with the magic bytes.
+        let mut magic_value = MAGIC.to_vec();
+        magic_value.push(42);
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(vec![2], magic_value.clone());
+        compressing_store.write_batch(batch).await.unwrap();
+        // A legacy value starting with the magic bytes, followed by an unknown tag.
+        let mut legacy_magic_value = MAGIC.to_vec();
+        legacy_magic_value.extend([42, 1, 2, 3]);
+        let mut batch = Batch::new();
+        batch.put_key_value_bytes(vec![3], legacy_magic_value.clone());
+        store.write_batch(batch).await.unwrap();
+        let key_values = compressing_store
+            .find_key_values_by_prefix(&[])
+            .await
+            .unwrap();
+        assert_eq!(
+            key_values,
+            vec![
+                (vec![1], legacy_value),
+                (vec![2], magic_value),
+                (vec![3], legacy_magic_value)
+            ]
+        );
+    }
+}
@@ -37,10 +37,12 @@ use {
 
 #[cfg(with_metrics)]
 use crate::metering::{
-    MeteredStore, DYNAMO_DB_METRICS, LRU_CACHING_METRICS, VALUE_SPLITTING_METRICS,
+    MeteredStore, COMPRESSING_METRICS, DYNAMO_DB_METRICS, LRU_CACHING_METRICS,
+    VALUE_SPLITTING_METRICS,
 };
 use crate::{
     batch::{Batch, SimpleUnorderedBatch},
+    compressing::{CompressingError, CompressingStore, DEFAULT_COMPRESSION_THRESHOLD},
     journaling::{DirectWritableKeyValueStore, JournalConsistencyError, JournalingKeyValueStore},
     lru_caching::LruCachingStore,
     store::{
@@ -1172,16 +1174,30 @@ pub struct DynamoDbStore {
     store: MeteredStore<
         LruCachingStore<
             MeteredStore<
-                ValueSplittingStore<MeteredStore<JournalingKeyValueStore<DynamoDbStoreInternal>>>,
+                CompressingStore<
+                    MeteredStore<
+                        ValueSplittingStore<
+                            MeteredStore<JournalingKeyValueStore<DynamoDbStoreInternal>>,
+                        >,
+                    >,
+                >,
             >,
         >,
     >,
     #[cfg(not(with_metrics))]
-    store: LruCachingStore<ValueSplittingStore<JournalingKeyValueStore<DynamoDbStoreInternal>>>,
+    store: LruCachingStore<
+        CompressingStore<ValueSplittingStore<JournalingKeyValueStore<DynamoDbStoreInternal>>>,
+    >,
 }
 
 /// The combined error type for the `DynamoDbStore`.
-pub type DynamoDbStoreError = ValueSplittingError<DynamoDbStoreInternalError>;
+pub type DynamoDbStoreError = CompressingError<ValueSplittingError<DynamoDbStoreInternalError>>;
+
+impl From<DynamoDbStoreInternalError> for DynamoDbStoreError {
+    fn from(error: DynamoDbStoreInternalError) -> Self {
+        CompressingError::InnerStoreError(ValueSplittingError::InnerStoreError(error))
+    }
+}
 
 /// Getting a configuration for the system
 pub async fn get_config(use_localstack: bool) -> Result<Config, DynamoDbStoreError> {
@@ -1307,12 +1323,12 @@ impl TestKeyValueStore for DynamoDbStore {
 impl DynamoDbStore {
     #[cfg(with_metrics)]
     fn inner(&self) -> &DynamoDbStoreInternal {
-        &self.store.store.store.store.store.store.store
+        &self.store.store.store.store.store.store.store.store.store
     }
 
     #[cfg(not(with_metrics))]
     fn inner(&self) -> &DynamoDbStoreInternal {
-        &self.store.store.store.store
+        &self.store.store.store.store.store
     }
 
     fn from_inner(simple_store: DynamoDbStoreInternal, cache_size: usize) -> DynamoDbStore {
@@ -1322,6 +1338,9 @@ impl DynamoDbStore {
         let store = ValueSplittingStore::new(store);
         #[cfg(with_metrics)]
         let store = MeteredStore::new(&VALUE_SPLITTING_METRICS, store);
+        let store = CompressingStore::new(store, DEFAULT_COMPRESSION_THRESHOLD);
+        #[cfg(with_metrics)]
+        let store = MeteredStore::new(&COMPRESSING_METRICS, store);
         let store = LruCachingStore::new(store, cache_size);
         #[cfg(with_metrics)]
         let store = MeteredStore::new(&LRU_CACHING_METRICS, store);
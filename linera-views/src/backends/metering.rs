@@ -62,13 +62,23 @@ pub(crate) static DYNAMO_DB_METRICS: LazyLock<KeyValueStoreMetrics> =
 pub(crate) static SCYLLA_DB_METRICS: LazyLock<KeyValueStoreMetrics> =
     LazyLock::new(|| KeyValueStoreMetrics::new("scylla db internal".to_string()));
 
+/// The metrics for the "postgres"
+#[cfg(with_postgres)]
+pub(crate) static POSTGRES_METRICS: LazyLock<KeyValueStoreMetrics> =
+    LazyLock::new(|| KeyValueStoreMetrics::new("postgres internal".to_string()));
+
 /// The metrics for the "scylla db"
 #[cfg(any(with_rocksdb, with_dynamodb))]
 pub(crate) static VALUE_SPLITTING_METRICS: LazyLock<KeyValueStoreMetrics> =
     LazyLock::new(|| KeyValueStoreMetrics::new("value splitting".to_string()));
 
+/// The metrics for the "compressing"
+#[cfg(any(with_rocksdb, with_dynamodb, with_scylladb, with_postgres))]
+pub(crate) static COMPRESSING_METRICS: LazyLock<KeyValueStoreMetrics> =
+    LazyLock::new(|| KeyValueStoreMetrics::new("compressing".to_string()));
+
 /// The metrics for the "lru caching"
-#[cfg(any(with_rocksdb, with_dynamodb, with_scylladb))]
+#[cfg(any(with_rocksdb, with_dynamodb, with_scylladb, with_postgres))]
 pub(crate) static LRU_CACHING_METRICS: LazyLock<KeyValueStoreMetrics> =
     LazyLock::new(|| KeyValueStoreMetrics::new("lru caching".to_string()));
 
@@ -3,11 +3,16 @@
 
 pub mod journaling;
 
+pub mod change_capture;
+
 #[cfg(with_metrics)]
 pub mod metering;
 
 pub mod value_splitting;
 
+#[cfg(with_compression)]
+pub mod compressing;
+
 pub mod memory;
 
 pub mod lru_caching;
@@ -17,6 +22,9 @@ pub mod dual;
 #[cfg(with_scylladb)]
 pub mod scylla_db;
 
+#[cfg(with_postgres)]
+pub mod postgres;
+
 #[cfg(with_rocksdb)]
 pub mod rocks_db;
 
@@ -0,0 +1,903 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Implements [`crate::store::KeyValueStore`] for a PostgreSQL-compatible database.
+//!
+//! Every namespace is a table of the `kv` schema whose rows are made of a root key, a
+//! key and a value, all of type `BYTEA`. Prefix searches are translated into range
+//! predicates on the `BYTEA` keys, which are ordered lexicographically by PostgreSQL.
+
+use std::{collections::HashMap, sync::Arc};
+
+use async_lock::{Mutex, Semaphore, SemaphoreGuard};
+use linera_base::ensure;
+use thiserror::Error;
+use tokio_postgres::{types::ToSql, Client, NoTls, Statement};
+#[cfg(with_testing)]
+use {
+    linera_base::command::CommandExt as _,
+    std::path::PathBuf,
+    tempfile::TempDir,
+    tokio::process::{Child, Command},
+};
+
+#[cfg(with_metrics)]
+use crate::metering::{MeteredStore, COMPRESSING_METRICS, LRU_CACHING_METRICS, POSTGRES_METRICS};
+use crate::{
+    batch::Batch,
+    common::get_upper_bound_option,
+    compressing::{CompressingError, CompressingStore, DEFAULT_COMPRESSION_THRESHOLD},
+    lru_caching::LruCachingStore,
+    store::{
+        AdminKeyValueStore, CommonStoreConfig, KeyValueStoreError, ReadableKeyValueStore,
+        WithError, WritableKeyValueStore,
+    },
+};
+#[cfg(with_testing)]
+use crate::{lru_caching::TEST_CACHE_SIZE, store::TestKeyValueStore};
+
+/// We limit the number of connections that can be done for tests.
+#[cfg(with_testing)]
+const TEST_POSTGRES_MAX_CONCURRENT_QUERIES: usize = 10;
+
+/// The number of connections in the stream is limited for tests.
+#[cfg(with_testing)]
+const TEST_POSTGRES_MAX_STREAM_QUERIES: usize = 10;
+
+/// PostgreSQL limits the size of the entries of a B-tree index to about 2700 bytes.
+/// Since the primary key is made of the root key and the key, we limit the size of
+/// the keys to 1024 bytes.
+const MAX_KEY_SIZE: usize = 1024;
+
+/// A `BYTEA` field can hold at most 1 GB. We keep some margin for the protocol overhead.
+const MAX_VALUE_SIZE: usize = 1_000_000_000;
+
+/// PostgreSQL limits the length of identifiers to 63 bytes.
+const MAX_NAMESPACE_LENGTH: usize = 63;
+
+/// The prepared statements of a namespace.
+struct PostgresStatements {
+    read_value: Statement,
+    contains_key: Statement,
+    read_multi_values: Statement,
+    contains_keys: Statement,
+    find_keys_by_prefix_unbounded: Statement,
+    find_keys_by_prefix_bounded: Statement,
+    find_key_values_by_prefix_unbounded: Statement,
+    find_key_values_by_prefix_bounded: Statement,
+    delete_prefix_unbounded: Statement,
+    delete_prefix_bounded: Statement,
+    delete_keys: Statement,
+    upsert_key_values: Statement,
+}
+
+impl PostgresStatements {
+    async fn new(client: &Client, namespace: &str) -> Result<Self, tokio_postgres::Error> {
+        let table = format!("kv.\"{}\"", namespace);
+        let read_value = client
+            .prepare(&format!(
+                "SELECT v FROM {table} WHERE root_key = $1 AND k = $2"
+            ))
+            .await?;
+        let contains_key = client
+            .prepare(&format!(
+                "SELECT 1 FROM {table} WHERE root_key = $1 AND k = $2"
+            ))
+            .await?;
+        let read_multi_values = client
+            .prepare(&format!(
+                "SELECT k, v FROM {table} WHERE root_key = $1 AND k = ANY($2)"
+            ))
+            .await?;
+        let contains_keys = client
+            .prepare(&format!(
+                "SELECT k FROM {table} WHERE root_key = $1 AND k = ANY($2)"
+            ))
+            .await?;
+        let find_keys_by_prefix_unbounded = client
+            .prepare(&format!(
+                "SELECT k FROM {table} WHERE root_key = $1 AND k >= $2 ORDER BY k"
+            ))
+            .await?;
+        let find_keys_by_prefix_bounded = client
+            .prepare(&format!(
+                "SELECT k FROM {table} WHERE root_key = $1 AND k >= $2 AND k < $3 ORDER BY k"
+            ))
+            .await?;
+        let find_key_values_by_prefix_unbounded = client
+            .prepare(&format!(
+                "SELECT k, v FROM {table} WHERE root_key = $1 AND k >= $2 ORDER BY k"
+            ))
+            .await?;
+        let find_key_values_by_prefix_bounded = client
+            .prepare(&format!(
+                "SELECT k, v FROM {table} WHERE root_key = $1 AND k >= $2 AND k < $3 ORDER BY k"
+            ))
+            .await?;
+        let delete_prefix_unbounded = client
+            .prepare(&format!(
+                "DELETE FROM {table} WHERE root_key = $1 AND k >= $2"
+            ))
+            .await?;
+        let delete_prefix_bounded = client
+            .prepare(&format!(
+                "DELETE FROM {table} WHERE root_key = $1 AND k >= $2 AND k < $3"
+            ))
+            .await?;
+        let delete_keys = client
+            .prepare(&format!(
+                "DELETE FROM {table} WHERE root_key = $1 AND k = ANY($2)"
+            ))
+            .await?;
+        let upsert_key_values = client
+            .prepare(&format!(
+                "INSERT INTO {table} (root_key, k, v) \
+                 SELECT $1, t.k, t.v FROM UNNEST($2::BYTEA[], $3::BYTEA[]) AS t(k, v) \
+                 ON CONFLICT (root_key, k) DO UPDATE SET v = EXCLUDED.v"
+            ))
+            .await?;
+        Ok(Self {
+            read_value,
+            contains_key,
+            read_multi_values,
+            contains_keys,
+            find_keys_by_prefix_unbounded,
+            find_keys_by_prefix_bounded,
+            find_key_values_by_prefix_unbounded,
+            find_key_values_by_prefix_bounded,
+            delete_prefix_unbounded,
+            delete_prefix_bounded,
+            delete_keys,
+            upsert_key_values,
+        })
+    }
+}
+
+/// The clients for PostgreSQL.
+/// * The reader connection is shared by all the read queries, which are pipelined.
+/// * The writer connection is used exclusively for the transactions writing batches.
+///
+/// Prepared statements belong to a connection, so each connection has its own.
+struct PostgresClient {
+    reader: Client,
+    statements: PostgresStatements,
+    writer: Mutex<(Client, PostgresStatements)>,
+}
+
+impl PostgresClient {
+    async fn new(uri: &str, namespace: &str) -> Result<Self, PostgresStoreInternalError> {
+        let reader = connect(uri).await?;
+        let statements = PostgresStatements::new(&reader, namespace).await?;
+        let writer = connect(uri).await?;
+        let writer_statements = PostgresStatements::new(&writer, namespace).await?;
+        Ok(Self {
+            reader,
+            statements,
+            writer: Mutex::new((writer, writer_statements)),
+        })
+    }
+
+    async fn read_value_internal(
+        &self,
+        root_key: &[u8],
+        key: &[u8],
+    ) -> Result<Option<Vec<u8>>, PostgresStoreInternalError> {
+        ensure!(
+            key.len() <= MAX_KEY_SIZE,
+            PostgresStoreInternalError::KeyTooLong
+        );
+        let row = self
+            .reader
+            .query_opt(&self.statements.read_value, &[&root_key, &key])
+            .await?;
+        Ok(row.map(|row| row.get(0)))
+    }
+
+    async fn contains_key_internal(
+        &self,
+        root_key: &[u8],
+        key: &[u8],
+    ) -> Result<bool, PostgresStoreInternalError> {
+        ensure!(
+            key.len() <= MAX_KEY_SIZE,
+            PostgresStoreInternalError::KeyTooLong
+        );
+        let row = self
+            .reader
+            .query_opt(&self.statements.contains_key, &[&root_key, &key])
+            .await?;
+        Ok(row.is_some())
+    }
+
+    async fn read_multi_values_internal(
+        &self,
+        root_key: &[u8],
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, PostgresStoreInternalError> {
+        let mut map = HashMap::<Vec<u8>, Vec<usize>>::new();
+        for (i_key, key) in keys.iter().enumerate() {
+            ensure!(
+                key.len() <= MAX_KEY_SIZE,
+                PostgresStoreInternalError::KeyTooLong
+            );
+            map.entry(key.clone()).or_default().push(i_key);
+        }
+        let rows = self
+            .reader
+            .query(&self.statements.read_multi_values, &[&root_key, &keys])
+            .await?;
+        let mut values = vec![None; keys.len()];
+        for row in rows {
+            let key: Vec<u8> = row.get(0);
+            let value: Vec<u8> = row.get(1);
+            for i_key in &map[&key] {
+                values[*i_key] = Some(value.clone());
+            }
+        }
+        Ok(values)
+    }
+
+    async fn contains_keys_internal(
+        &self,
+        root_key: &[u8],
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<bool>, PostgresStoreInternalError> {
+        let mut map = HashMap::<Vec<u8>, Vec<usize>>::new();
+        for (i_key, key) in keys.iter().enumerate() {
+            ensure!(
+                key.len() <= MAX_KEY_SIZE,
+                PostgresStoreInternalError::KeyTooLong
+            );
+            map.entry(key.clone()).or_default().push(i_key);
+        }
+        let rows = self
+            .reader
+            .query(&self.statements.contains_keys, &[&root_key, &keys])
+            .await?;
+        let mut results = vec![false; keys.len()];
+        for row in rows {
+            let key: Vec<u8> = row.get(0);
+            for i_key in &map[&key] {
+                results[*i_key] = true;
+            }
+        }
+        Ok(results)
+    }
+
+    async fn find_keys_by_prefix_internal(
+        &self,
+        root_key: &[u8],
+        key_prefix: Vec<u8>,
+    ) -> Result<Vec<Vec<u8>>, PostgresStoreInternalError> {
+        ensure!(
+            key_prefix.len() <= MAX_KEY_SIZE,
+            PostgresStoreInternalError::KeyTooLong
+        );
+        let len = key_prefix.len();
+        let rows = match get_upper_bound_option(&key_prefix) {
+            None => {
+                let statement = &self.statements.find_keys_by_prefix_unbounded;
+                self.reader
+                    .query(statement, &[&root_key, &key_prefix])
+                    .await?
+            }
+            Some(upper_bound) => {
+                let statement = &self.statements.find_keys_by_prefix_bounded;
+                let params: [&(dyn ToSql + Sync); 3] = [&root_key, &key_prefix, &upper_bound];
+                self.reader.query(statement, &params).await?
+            }
+        };
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let key: Vec<u8> = row.get(0);
+                key[len..].to_vec()
+            })
+            .collect())
+    }
+
+    async fn find_key_values_by_prefix_internal(
+        &self,
+        root_key: &[u8],
+        key_prefix: Vec<u8>,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, PostgresStoreInternalError> {
+        ensure!(
+            key_prefix.len() <= MAX_KEY_SIZE,
+            PostgresStoreInternalError::KeyTooLong
+        );
+        let len = key_prefix.len();
+        let rows = match get_upper_bound_option(&key_prefix) {
+            None => {
+                let statement = &self.statements.find_key_values_by_prefix_unbounded;
+                self.reader
+                    .query(statement, &[&root_key, &key_prefix])
+                    .await?
+            }
+            Some(upper_bound) => {
+                let statement = &self.statements.find_key_values_by_prefix_bounded;
+                let params: [&(dyn ToSql + Sync); 3] = [&root_key, &key_prefix, &upper_bound];
+                self.reader.query(statement, &params).await?
+            }
+        };
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let key: Vec<u8> = row.get(0);
+                let value: Vec<u8> = row.get(1);
+                (key[len..].to_vec(), value)
+            })
+            .collect())
+    }
+
+    async fn write_batch_internal(
+        &self,
+        root_key: &[u8],
+        batch: Batch,
+    ) -> Result<(), PostgresStoreInternalError> {
+        let batch = batch.simplify();
+        for key_prefix in &batch.key_prefix_deletions {
+            ensure!(
+                key_prefix.len() <= MAX_KEY_SIZE,
+                PostgresStoreInternalError::KeyTooLong
+            );
+        }
+        let deletions = batch.simple_unordered_batch.deletions;
+        let (keys, values): (Vec<_>, Vec<_>) =
+            batch.simple_unordered_batch.insertions.into_iter().unzip();
+        for key in deletions.iter().chain(&keys) {
+            ensure!(
+                key.len() <= MAX_KEY_SIZE,
+                PostgresStoreInternalError::KeyTooLong
+            );
+        }
+        let mut writer = self.writer.lock().await;
+        let (client, statements) = &mut *writer;
+        let transaction = client.transaction().await?;
+        for key_prefix in batch.key_prefix_deletions {
+            match get_upper_bound_option(&key_prefix) {
+                None => {
+                    let statement = &statements.delete_prefix_unbounded;
+                    transaction
+                        .execute(statement, &[&root_key, &key_prefix])
+                        .await?;
+                }
+                Some(upper_bound) => {
+                    let statement = &statements.delete_prefix_bounded;
+                    let params: [&(dyn ToSql + Sync); 3] = [&root_key, &key_prefix, &upper_bound];
+                    transaction.execute(statement, &params).await?;
+                }
+            }
+        }
+        if !deletions.is_empty() {
+            let statement = &statements.delete_keys;
+            transaction
+                .execute(statement, &[&root_key, &deletions])
+                .await?;
+        }
+        if !keys.is_empty() {
+            let statement = &statements.upsert_key_values;
+            let params: [&(dyn ToSql + Sync); 3] = [&root_key, &keys, &values];
+            transaction.execute(statement, &params).await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+}
+
+/// Opens a connection to the server and drives it in the background.
+async fn connect(uri: &str) -> Result<Client, tokio_postgres::Error> {
+    let (client, connection) = tokio_postgres::connect(uri, NoTls).await?;
+    tokio::spawn(async move {
+        if let Err(error) = connection.await {
+            tracing::error!("PostgreSQL connection error: {}", error);
+        }
+    });
+    Ok(client)
+}
+
+/// The client itself and the keeping of the count of active connections.
+#[derive(Clone)]
+pub struct PostgresStoreInternal {
+    client: Arc<PostgresClient>,
+    semaphore: Option<Arc<Semaphore>>,
+    max_stream_queries: usize,
+    cache_size: usize,
+    root_key: Vec<u8>,
+    #[cfg(with_testing)]
+    _server: Option<Arc<PostgresTestServer>>,
+}
+
+/// The error type for [`PostgresStoreInternal`]
+#[derive(Error, Debug)]
+pub enum PostgresStoreInternalError {
+    /// BCS serialization error.
+    #[error("BCS error: {0}")]
+    Bcs(#[from] bcs::Error),
+
+    /// The key must have at most 1024 bytes
+    #[error("The key must have at most 1024 bytes")]
+    KeyTooLong,
+
+    /// An error from the PostgreSQL client
+    #[error(transparent)]
+    PostgresError(#[from] tokio_postgres::Error),
+
+    /// Table name contains forbidden characters
+    #[error("Table name contains forbidden characters")]
+    InvalidTableName,
+
+    /// The test server could not be started
+    #[error("Failed to start the PostgreSQL test server: {0}")]
+    TestServerError(String),
+}
+
+impl KeyValueStoreError for PostgresStoreInternalError {
+    const BACKEND: &'static str = "postgres";
+}
+
+impl WithError for PostgresStoreInternal {
+    type Error = PostgresStoreInternalError;
+}
+
+impl ReadableKeyValueStore for PostgresStoreInternal {
+    const MAX_KEY_SIZE: usize = MAX_KEY_SIZE;
+    type Keys = Vec<Vec<u8>>;
+    type KeyValues = Vec<(Vec<u8>, Vec<u8>)>;
+
+    fn max_stream_queries(&self) -> usize {
+        self.max_stream_queries
+    }
+
+    async fn read_value_bytes(
+        &self,
+        key: &[u8],
+    ) -> Result<Option<Vec<u8>>, PostgresStoreInternalError> {
+        let _guard = self.acquire().await;
+        self.client.read_value_internal(&self.root_key, key).await
+    }
+
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, PostgresStoreInternalError> {
+        let _guard = self.acquire().await;
+        self.client.contains_key_internal(&self.root_key, key).await
+    }
+
+    async fn contains_keys(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<bool>, PostgresStoreInternalError> {
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+        let _guard = self.acquire().await;
+        self.client
+            .contains_keys_internal(&self.root_key, keys)
+            .await
+    }
+
+    async fn read_multi_values_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, PostgresStoreInternalError> {
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+        let _guard = self.acquire().await;
+        self.client
+            .read_multi_values_internal(&self.root_key, keys)
+            .await
+    }
+
+    async fn find_keys_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Self::Keys, PostgresStoreInternalError> {
+        let _guard = self.acquire().await;
+        self.client
+            .find_keys_by_prefix_internal(&self.root_key, key_prefix.to_vec())
+            .await
+    }
+
+    async fn find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Self::KeyValues, PostgresStoreInternalError> {
+        let _guard = self.acquire().await;
+        self.client
+            .find_key_values_by_prefix_internal(&self.root_key, key_prefix.to_vec())
+            .await
+    }
+}
+
+impl WritableKeyValueStore for PostgresStoreInternal {
+    const MAX_VALUE_SIZE: usize = MAX_VALUE_SIZE;
+
+    async fn write_batch(&self, batch: Batch) -> Result<(), PostgresStoreInternalError> {
+        let _guard = self.acquire().await;
+        self.client
+            .write_batch_internal(&self.root_key, batch)
+            .await
+    }
+
+    async fn clear_journal(&self) -> Result<(), PostgresStoreInternalError> {
+        Ok(())
+    }
+}
+
+impl AdminKeyValueStore for PostgresStoreInternal {
+    type Config = PostgresStoreConfig;
+
+    async fn connect(
+        config: &Self::Config,
+        namespace: &str,
+        root_key: &[u8],
+    ) -> Result<Self, PostgresStoreInternalError> {
+        Self::check_namespace(namespace)?;
+        let client = PostgresClient::new(&config.uri, namespace).await?;
+        let semaphore = config
+            .common_config
+            .max_concurrent_queries
+            .map(|n| Arc::new(Semaphore::new(n)));
+        Ok(Self {
+            client: Arc::new(client),
+            semaphore,
+            max_stream_queries: config.common_config.max_stream_queries,
+            cache_size: config.common_config.cache_size,
+            root_key: root_key.to_vec(),
+            #[cfg(with_testing)]
+            _server: config.server.clone(),
+        })
+    }
+
+    fn clone_with_root_key(&self, root_key: &[u8]) -> Result<Self, PostgresStoreInternalError> {
+        let mut store = self.clone();
+        store.root_key = root_key.to_vec();
+        Ok(store)
+    }
+
+    async fn list_all(config: &Self::Config) -> Result<Vec<String>, PostgresStoreInternalError> {
+        let client = connect(&config.uri).await?;
+        let rows = client
+            .query(
+                "SELECT table_name::TEXT FROM information_schema.tables \
+                 WHERE table_schema = 'kv' ORDER BY table_name",
+                &[],
+            )
+            .await?;
+        Ok(rows.into_iter().map(|row| row.get(0)).collect())
+    }
+
+    async fn delete_all(config: &Self::Config) -> Result<(), PostgresStoreInternalError> {
+        let client = connect(&config.uri).await?;
+        client
+            .batch_execute("DROP SCHEMA IF EXISTS kv CASCADE")
+            .await?;
+        Ok(())
+    }
+
+    async fn exists(
+        config: &Self::Config,
+        namespace: &str,
+    ) -> Result<bool, PostgresStoreInternalError> {
+        Self::check_namespace(namespace)?;
+        let client = connect(&config.uri).await?;
+        let row = client
+            .query_opt(
+                "SELECT 1 FROM information_schema.tables \
+                 WHERE table_schema = 'kv' AND table_name = $1",
+                &[&namespace],
+            )
+            .await?;
+        Ok(row.is_some())
+    }
+
+    async fn create(
+        config: &Self::Config,
+        namespace: &str,
+    ) -> Result<(), PostgresStoreInternalError> {
+        Self::check_namespace(namespace)?;
+        let client = connect(&config.uri).await?;
+        let query = format!(
+            "CREATE SCHEMA IF NOT EXISTS kv; \
+             CREATE TABLE kv.\"{}\" (\
+             root_key BYTEA NOT NULL, k BYTEA NOT NULL, v BYTEA NOT NULL, \
+             PRIMARY KEY (root_key, k))",
+            namespace
+        );
+        client.batch_execute(&query).await?;
+        Ok(())
+    }
+
+    async fn delete(
+        config: &Self::Config,
+        namespace: &str,
+    ) -> Result<(), PostgresStoreInternalError> {
+        Self::check_namespace(namespace)?;
+        let client = connect(&config.uri).await?;
+        let query = format!("DROP TABLE IF EXISTS kv.\"{}\"", namespace);
+        client.batch_execute(&query).await?;
+        Ok(())
+    }
+}
+
+#[cfg(with_testing)]
+impl TestKeyValueStore for PostgresStoreInternal {
+    async fn new_test_config() -> Result<PostgresStoreConfig, PostgresStoreInternalError> {
+        let server = PostgresTestServer::spawn()
+            .await
+            .map_err(|error| PostgresStoreInternalError::TestServerError(error.to_string()))?;
+        let common_config = CommonStoreConfig {
+            max_concurrent_queries: Some(TEST_POSTGRES_MAX_CONCURRENT_QUERIES),
+            max_stream_queries: TEST_POSTGRES_MAX_STREAM_QUERIES,
+            cache_size: TEST_CACHE_SIZE,
+        };
+        let mut config = PostgresStoreConfig::new(server.uri.clone(), common_config);
+        config.server = Some(Arc::new(server));
+        Ok(config)
+    }
+}
+
+impl PostgresStoreInternal {
+    /// Obtains the semaphore lock on the database if needed.
+    async fn acquire(&self) -> Option<SemaphoreGuard<'_>> {
+        match &self.semaphore {
+            None => None,
+            Some(count) => Some(count.acquire().await),
+        }
+    }
+
+    fn check_namespace(namespace: &str) -> Result<(), PostgresStoreInternalError> {
+        if !namespace.is_empty()
+            && namespace.len() <= MAX_NAMESPACE_LENGTH
+            && namespace
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Ok(());
+        }
+        Err(PostgresStoreInternalError::InvalidTableName)
+    }
+}
+
+/// The configuration of a PostgreSQL key-value store.
+#[derive(Clone, Debug)]
+pub struct PostgresStoreConfig {
+    /// The connection string of the server, e.g. `postgresql://linera@localhost:5432/linera`
+    pub uri: String,
+    /// The common configuration of the key value store
+    pub common_config: CommonStoreConfig,
+    /// The test server, which is kept alive as long as the configuration is in use.
+    #[cfg(with_testing)]
+    server: Option<Arc<PostgresTestServer>>,
+}
+
+impl PostgresStoreConfig {
+    /// Creates a configuration for the server at the given URI.
+    pub fn new(uri: String, common_config: CommonStoreConfig) -> Self {
+        Self {
+            uri,
+            common_config,
+            #[cfg(with_testing)]
+            server: None,
+        }
+    }
+}
+
+/// A PostgreSQL server running as a child process, used for tests.
+///
+/// The server is run by the `postgres` binary of the local installation, in a temporary
+/// data directory. It is stopped when the guard is dropped.
+#[cfg(with_testing)]
+#[derive(Debug)]
+pub struct PostgresTestServer {
+    uri: String,
+    _child: Child,
+    _dir: TempDir,
+}
+
+#[cfg(with_testing)]
+impl PostgresTestServer {
+    /// Initializes a database cluster in a temporary directory and starts a server on it.
+    pub async fn spawn() -> anyhow::Result<Self> {
+        let bin_dir = Self::bin_dir().await;
+        let dir = TempDir::new()?;
+        let data_dir = dir.path().join("data");
+        Command::new(bin_dir.join("initdb"))
+            .arg("--pgdata")
+            .arg(&data_dir)
+            .args([
+                "--username",
+                "linera",
+                "--auth",
+                "trust",
+                "--encoding",
+                "UTF8",
+            ])
+            .spawn_and_wait_for_stdout()
+            .await?;
+        let port = linera_base::port::get_free_port().await?;
+        let _child = Command::new(bin_dir.join("postgres"))
+            .arg("-D")
+            .arg(&data_dir)
+            .arg("-k")
+            .arg(dir.path())
+            .args(["-h", "127.0.0.1", "-p", &port.to_string()])
+            .spawn_into()?;
+        let uri = format!("postgresql://linera@127.0.0.1:{}/postgres", port);
+        let server = Self {
+            uri,
+            _child,
+            _dir: dir,
+        };
+        // We iterate until the server accepts connections.
+        for i in 1..10 {
+            if connect(&server.uri).await.is_ok() {
+                return Ok(server);
+            }
+            linera_base::time::timer::sleep(linera_base::time::Duration::from_secs(i)).await;
+        }
+        anyhow::bail!("Failed to start the PostgreSQL server");
+    }
+
+    /// Returns the URI to connect to the server.
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// Finds the directory of the PostgreSQL binaries, which is often not in the `PATH`.
+    async fn bin_dir() -> PathBuf {
+        let bin_dir = Command::new("pg_config")
+            .arg("--bindir")
+            .spawn_and_wait_for_stdout()
+            .await;
+        match bin_dir {
+            Ok(bin_dir) => PathBuf::from(bin_dir.trim()),
+            Err(_) => PathBuf::new(),
+        }
+    }
+}
+
+/// A shared DB store for PostgreSQL implementing LruCaching
+#[derive(Clone)]
+pub struct PostgresStore {
+    #[cfg(with_metrics)]
+    store: MeteredStore<
+        LruCachingStore<MeteredStore<CompressingStore<MeteredStore<PostgresStoreInternal>>>>,
+    >,
+    #[cfg(not(with_metrics))]
+    store: LruCachingStore<CompressingStore<PostgresStoreInternal>>,
+}
+
+/// The composed error type for the `PostgresStore`
+pub type PostgresStoreError = CompressingError<PostgresStoreInternalError>;
+
+impl WithError for PostgresStore {
+    type Error = PostgresStoreError;
+}
+
+impl ReadableKeyValueStore for PostgresStore {
+    const MAX_KEY_SIZE: usize = MAX_KEY_SIZE;
+    type Keys = Vec<Vec<u8>>;
+    type KeyValues = Vec<(Vec<u8>, Vec<u8>)>;
+
+    fn max_stream_queries(&self) -> usize {
+        self.store.max_stream_queries()
+    }
+
+    async fn read_value_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, PostgresStoreError> {
+        self.store.read_value_bytes(key).await
+    }
+
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, PostgresStoreError> {
+        self.store.contains_key(key).await
+    }
+
+    async fn contains_keys(&self, keys: Vec<Vec<u8>>) -> Result<Vec<bool>, PostgresStoreError> {
+        self.store.contains_keys(keys).await
+    }
+
+    async fn read_multi_values_bytes(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<Vec<u8>>>, PostgresStoreError> {
+        self.store.read_multi_values_bytes(keys).await
+    }
+
+    async fn find_keys_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Self::Keys, PostgresStoreError> {
+        self.store.find_keys_by_prefix(key_prefix).await
+    }
+
+    async fn find_key_values_by_prefix(
+        &self,
+        key_prefix: &[u8],
+    ) -> Result<Self::KeyValues, PostgresStoreError> {
+        self.store.find_key_values_by_prefix(key_prefix).await
+    }
+}
+
+impl WritableKeyValueStore for PostgresStore {
+    const MAX_VALUE_SIZE: usize = MAX_VALUE_SIZE;
+
+    async fn write_batch(&self, batch: Batch) -> Result<(), PostgresStoreError> {
+        self.store.write_batch(batch).await
+    }
+
+    async fn clear_journal(&self) -> Result<(), PostgresStoreError> {
+        self.store.clear_journal().await
+    }
+}
+
+impl AdminKeyValueStore for PostgresStore {
+    type Config = PostgresStoreConfig;
+
+    async fn connect(
+        config: &Self::Config,
+        namespace: &str,
+        root_key: &[u8],
+    ) -> Result<Self, PostgresStoreError> {
+        let cache_size = config.common_config.cache_size;
+        let simple_store = PostgresStoreInternal::connect(config, namespace, root_key).await?;
+        Ok(Self::from_inner(simple_store, cache_size))
+    }
+
+    fn clone_with_root_key(&self, root_key: &[u8]) -> Result<Self, PostgresStoreError> {
+        let simple_store = self.inner().clone_with_root_key(root_key)?;
+        let cache_size = self.inner().cache_size;
+        Ok(Self::from_inner(simple_store, cache_size))
+    }
+
+    async fn list_all(config: &Self::Config) -> Result<Vec<String>, PostgresStoreError> {
+        Ok(PostgresStoreInternal::list_all(config).await?)
+    }
+
+    async fn delete_all(config: &Self::Config) -> Result<(), PostgresStoreError> {
+        Ok(PostgresStoreInternal::delete_all(config).await?)
+    }
+
+    async fn exists(config: &Self::Config, namespace: &str) -> Result<bool, PostgresStoreError> {
+        Ok(PostgresStoreInternal::exists(config, namespace).await?)
+    }
+
+    async fn create(config: &Self::Config, namespace: &str) -> Result<(), PostgresStoreError> {
+        Ok(PostgresStoreInternal::create(config, namespace).await?)
+    }
+
+    async fn delete(config: &Self::Config, namespace: &str) -> Result<(), PostgresStoreError> {
+        Ok(PostgresStoreInternal::delete(config, namespace).await?)
+    }
+}
+
+#[cfg(with_testing)]
+impl TestKeyValueStore for PostgresStore {
+    async fn new_test_config() -> Result<PostgresStoreConfig, PostgresStoreError> {
+        Ok(PostgresStoreInternal::new_test_config().await?)
+    }
+}
+
+impl PostgresStore {
+    #[cfg(with_metrics)]
+    fn inner(&self) -> &PostgresStoreInternal {
+        &self.store.store.store.store.store.store
+    }
+
+    #[cfg(not(with_metrics))]
+    fn inner(&self) -> &PostgresStoreInternal {
+        &self.store.store.store
+    }
+
+    fn from_inner(simple_store: PostgresStoreInternal, cache_size: usize) -> PostgresStore {
+        #[cfg(with_metrics)]
+        let store = MeteredStore::new(&POSTGRES_METRICS, simple_store);
+        #[cfg(not(with_metrics))]
+        let store = simple_store;
+        let store = CompressingStore::new(store, DEFAULT_COMPRESSION_THRESHOLD);
+        #[cfg(with_metrics)]
+        let store = MeteredStore::new(&COMPRESSING_METRICS, store);
+        let store = LruCachingStore::new(store, cache_size);
+        #[cfg(with_metrics)]
+        let store = MeteredStore::new(&LRU_CACHING_METRICS, store);
+        Self { store }
+    }
+}
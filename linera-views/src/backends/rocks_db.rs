@@ -16,11 +16,13 @@ use thiserror::Error;
 
 #[cfg(with_metrics)]
 use crate::metering::{
-    MeteredStore, LRU_CACHING_METRICS, ROCKS_DB_METRICS, VALUE_SPLITTING_METRICS,
+    MeteredStore, COMPRESSING_METRICS, LRU_CACHING_METRICS, ROCKS_DB_METRICS,
+    VALUE_SPLITTING_METRICS,
 };
 use crate::{
     batch::{Batch, WriteOperation},
     common::get_upper_bound,
+    compressing::{CompressingError, CompressingStore, DEFAULT_COMPRESSION_THRESHOLD},
     lru_caching::LruCachingStore,
     store::{
         AdminKeyValueStore, CommonStoreConfig, KeyValueStoreError, ReadableKeyValueStore,
@@ -600,13 +602,20 @@ impl KeyValueStoreError for RocksDbStoreInternalError {
 
 /// A shared DB client for RocksDB implementing LruCaching
 #[derive(Clone)]
+#[allow(clippy::type_complexity)]
 pub struct RocksDbStore {
     #[cfg(with_metrics)]
     store: MeteredStore<
-        LruCachingStore<MeteredStore<ValueSplittingStore<MeteredStore<RocksDbStoreInternal>>>>,
+        LruCachingStore<
+            MeteredStore<
+                CompressingStore<
+                    MeteredStore<ValueSplittingStore<MeteredStore<RocksDbStoreInternal>>>,
+                >,
+            >,
+        >,
     >,
     #[cfg(not(with_metrics))]
-    store: LruCachingStore<ValueSplittingStore<RocksDbStoreInternal>>,
+    store: LruCachingStore<CompressingStore<ValueSplittingStore<RocksDbStoreInternal>>>,
 }
 
 /// A path and the guard for the temporary directory if needed
@@ -640,12 +649,12 @@ fn create_rocks_db_test_path() -> PathWithGuard {
 impl RocksDbStore {
     #[cfg(with_metrics)]
     fn inner(&self) -> &RocksDbStoreInternal {
-        &self.store.store.store.store.store.store
+        &self.store.store.store.store.store.store.store.store
     }
 
     #[cfg(not(with_metrics))]
     fn inner(&self) -> &RocksDbStoreInternal {
-        &self.store.store.store
+        &self.store.store.store.store
     }
 
     fn from_inner(store: RocksDbStoreInternal, cache_size: usize) -> RocksDbStore {
@@ -654,6 +663,9 @@ impl RocksDbStore {
         let store = ValueSplittingStore::new(store);
         #[cfg(with_metrics)]
         let store = MeteredStore::new(&VALUE_SPLITTING_METRICS, store);
+        let store = CompressingStore::new(store, DEFAULT_COMPRESSION_THRESHOLD);
+        #[cfg(with_metrics)]
+        let store = MeteredStore::new(&COMPRESSING_METRICS, store);
         let store = LruCachingStore::new(store, cache_size);
         #[cfg(with_metrics)]
         let store = MeteredStore::new(&LRU_CACHING_METRICS, store);
@@ -662,7 +674,13 @@ impl RocksDbStore {
 }
 
 /// The composed error type for the `RocksDbStore`
-pub type RocksDbStoreError = ValueSplittingError<RocksDbStoreInternalError>;
+pub type RocksDbStoreError = CompressingError<ValueSplittingError<RocksDbStoreInternalError>>;
+
+impl From<RocksDbStoreInternalError> for RocksDbStoreError {
+    fn from(error: RocksDbStoreInternalError) -> Self {
+        CompressingError::InnerStoreError(ValueSplittingError::InnerStoreError(error))
+    }
+}
 
 impl WithError for RocksDbStore {
     type Error = RocksDbStoreError;
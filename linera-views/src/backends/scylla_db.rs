@@ -30,10 +30,11 @@ use scylla::{
 use thiserror::Error;
 
 #[cfg(with_metrics)]
-use crate::metering::{MeteredStore, LRU_CACHING_METRICS, SCYLLA_DB_METRICS};
+use crate::metering::{MeteredStore, COMPRESSING_METRICS, LRU_CACHING_METRICS, SCYLLA_DB_METRICS};
 use crate::{
     batch::{Batch, UnorderedBatch},
     common::get_upper_bound_option,
+    compressing::{CompressingError, CompressingStore, DEFAULT_COMPRESSION_THRESHOLD},
     journaling::{DirectWritableKeyValueStore, JournalConsistencyError, JournalingKeyValueStore},
     lru_caching::LruCachingStore,
     store::{
@@ -134,8 +135,11 @@ impl ScyllaDbClient {
         &self,
         root_key: &[u8],
         key: Vec<u8>,
-    ) -> Result<Option<Vec<u8>>, ScyllaDbStoreError> {
-        ensure!(key.len() <= MAX_KEY_SIZE, ScyllaDbStoreError::KeyTooLong);
+    ) -> Result<Option<Vec<u8>>, ScyllaDbStoreInternalError> {
+        ensure!(
+            key.len() <= MAX_KEY_SIZE,
+            ScyllaDbStoreInternalError::KeyTooLong
+        );
         let session = &self.session;
         // Read the value of a key
         let values = (root_key.to_vec(), key);
@@ -151,7 +155,7 @@ impl ScyllaDbClient {
         &self,
         root_key: &[u8],
         keys: Vec<Vec<u8>>,
-    ) -> Result<Vec<Option<Vec<u8>>>, ScyllaDbStoreError> {
+    ) -> Result<Vec<Option<Vec<u8>>>, ScyllaDbStoreInternalError> {
         let num_keys = keys.len();
         if num_keys == 0 {
             return Ok(Vec::new());
@@ -161,7 +165,10 @@ impl ScyllaDbClient {
         let mut inputs = Vec::new();
         inputs.push(root_key.to_vec());
         for (i_key, key) in keys.into_iter().enumerate() {
-            ensure!(key.len() <= MAX_KEY_SIZE, ScyllaDbStoreError::KeyTooLong);
+            ensure!(
+                key.len() <= MAX_KEY_SIZE,
+                ScyllaDbStoreInternalError::KeyTooLong
+            );
             match map.entry(key.clone()) {
                 Entry::Occupied(entry) => {
                     let entry = entry.into_mut();
@@ -197,7 +204,7 @@ impl ScyllaDbClient {
         &self,
         root_key: &[u8],
         keys: Vec<Vec<u8>>,
-    ) -> Result<Vec<bool>, ScyllaDbStoreError> {
+    ) -> Result<Vec<bool>, ScyllaDbStoreInternalError> {
         let num_keys = keys.len();
         if num_keys == 0 {
             return Ok(Vec::new());
@@ -207,7 +214,10 @@ impl ScyllaDbClient {
         let mut inputs = Vec::new();
         inputs.push(root_key.to_vec());
         for (i_key, key) in keys.into_iter().enumerate() {
-            ensure!(key.len() <= MAX_KEY_SIZE, ScyllaDbStoreError::KeyTooLong);
+            ensure!(
+                key.len() <= MAX_KEY_SIZE,
+                ScyllaDbStoreInternalError::KeyTooLong
+            );
             match map.entry(key.clone()) {
                 Entry::Occupied(entry) => {
                     let entry = entry.into_mut();
@@ -242,8 +252,11 @@ impl ScyllaDbClient {
         &self,
         root_key: &[u8],
         key: Vec<u8>,
-    ) -> Result<bool, ScyllaDbStoreError> {
-        ensure!(key.len() <= MAX_KEY_SIZE, ScyllaDbStoreError::KeyTooLong);
+    ) -> Result<bool, ScyllaDbStoreInternalError> {
+        ensure!(
+            key.len() <= MAX_KEY_SIZE,
+            ScyllaDbStoreInternalError::KeyTooLong
+        );
         let session = &self.session;
         // Read the value of a key
         let values = (root_key.to_vec(), key);
@@ -256,7 +269,7 @@ impl ScyllaDbClient {
         &self,
         root_key: &[u8],
         batch: UnorderedBatch,
-    ) -> Result<(), ScyllaDbStoreError> {
+    ) -> Result<(), ScyllaDbStoreInternalError> {
         let session = &self.session;
         let mut batch_query = scylla::statement::batch::Batch::new(BatchType::Logged);
         let mut batch_values = Vec::new();
@@ -265,7 +278,7 @@ impl ScyllaDbClient {
         for key_prefix in batch.key_prefix_deletions {
             ensure!(
                 key_prefix.len() <= MAX_KEY_SIZE,
-                ScyllaDbStoreError::KeyTooLong
+                ScyllaDbStoreInternalError::KeyTooLong
             );
             match get_upper_bound_option(&key_prefix) {
                 None => {
@@ -288,7 +301,10 @@ impl ScyllaDbClient {
         }
         let query4 = &self.write_batch_insertion;
         for (key, value) in batch.simple_unordered_batch.insertions {
-            ensure!(key.len() <= MAX_KEY_SIZE, ScyllaDbStoreError::KeyTooLong);
+            ensure!(
+                key.len() <= MAX_KEY_SIZE,
+                ScyllaDbStoreInternalError::KeyTooLong
+            );
             let values = vec![root_key.to_vec(), key, value];
             batch_values.push(values);
             batch_query.append_statement(query4.clone());
@@ -301,10 +317,10 @@ impl ScyllaDbClient {
         &self,
         root_key: &[u8],
         key_prefix: Vec<u8>,
-    ) -> Result<Vec<Vec<u8>>, ScyllaDbStoreError> {
+    ) -> Result<Vec<Vec<u8>>, ScyllaDbStoreInternalError> {
         ensure!(
             key_prefix.len() <= MAX_KEY_SIZE,
-            ScyllaDbStoreError::KeyTooLong
+            ScyllaDbStoreInternalError::KeyTooLong
         );
         let session = &self.session;
         // Read the value of a key
@@ -334,10 +350,10 @@ impl ScyllaDbClient {
         &self,
         root_key: &[u8],
         key_prefix: Vec<u8>,
-    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, ScyllaDbStoreError> {
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, ScyllaDbStoreInternalError> {
         ensure!(
             key_prefix.len() <= MAX_KEY_SIZE,
-            ScyllaDbStoreError::KeyTooLong
+            ScyllaDbStoreInternalError::KeyTooLong
         );
         let session = &self.session;
         // Read the value of a key
@@ -392,7 +408,7 @@ pub struct ScyllaDbStoreInternal {
 
 /// The error type for [`ScyllaDbStoreInternal`]
 #[derive(Error, Debug)]
-pub enum ScyllaDbStoreError {
+pub enum ScyllaDbStoreInternalError {
     /// BCS serialization error.
     #[error("BCS error: {0}")]
     Bcs(#[from] bcs::Error),
@@ -430,12 +446,12 @@ pub enum ScyllaDbStoreError {
     JournalConsistencyError(#[from] JournalConsistencyError),
 }
 
-impl KeyValueStoreError for ScyllaDbStoreError {
+impl KeyValueStoreError for ScyllaDbStoreInternalError {
     const BACKEND: &'static str = "scylla_db";
 }
 
 impl WithError for ScyllaDbStoreInternal {
-    type Error = ScyllaDbStoreError;
+    type Error = ScyllaDbStoreInternalError;
 }
 
 impl ReadableKeyValueStore for ScyllaDbStoreInternal {
@@ -447,7 +463,10 @@ impl ReadableKeyValueStore for ScyllaDbStoreInternal {
         self.max_stream_queries
     }
 
-    async fn read_value_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, ScyllaDbStoreError> {
+    async fn read_value_bytes(
+        &self,
+        key: &[u8],
+    ) -> Result<Option<Vec<u8>>, ScyllaDbStoreInternalError> {
         let store = self.store.deref();
         let _guard = self.acquire().await;
         store
@@ -455,7 +474,7 @@ impl ReadableKeyValueStore for ScyllaDbStoreInternal {
             .await
     }
 
-    async fn contains_key(&self, key: &[u8]) -> Result<bool, ScyllaDbStoreError> {
+    async fn contains_key(&self, key: &[u8]) -> Result<bool, ScyllaDbStoreInternalError> {
         let store = self.store.deref();
         let _guard = self.acquire().await;
         store
@@ -463,7 +482,10 @@ impl ReadableKeyValueStore for ScyllaDbStoreInternal {
             .await
     }
 
-    async fn contains_keys(&self, keys: Vec<Vec<u8>>) -> Result<Vec<bool>, ScyllaDbStoreError> {
+    async fn contains_keys(
+        &self,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<bool>, ScyllaDbStoreInternalError> {
         if keys.is_empty() {
             return Ok(Vec::new());
         }
@@ -482,7 +504,7 @@ impl ReadableKeyValueStore for ScyllaDbStoreInternal {
     async fn read_multi_values_bytes(
         &self,
         keys: Vec<Vec<u8>>,
-    ) -> Result<Vec<Option<Vec<u8>>>, ScyllaDbStoreError> {
+    ) -> Result<Vec<Option<Vec<u8>>>, ScyllaDbStoreInternalError> {
         if keys.is_empty() {
             return Ok(Vec::new());
         }
@@ -501,7 +523,7 @@ impl ReadableKeyValueStore for ScyllaDbStoreInternal {
     async fn find_keys_by_prefix(
         &self,
         key_prefix: &[u8],
-    ) -> Result<Self::Keys, ScyllaDbStoreError> {
+    ) -> Result<Self::Keys, ScyllaDbStoreInternalError> {
         let store = self.store.deref();
         let _guard = self.acquire().await;
         store
@@ -512,7 +534,7 @@ impl ReadableKeyValueStore for ScyllaDbStoreInternal {
     async fn find_key_values_by_prefix(
         &self,
         key_prefix: &[u8],
-    ) -> Result<Self::KeyValues, ScyllaDbStoreError> {
+    ) -> Result<Self::KeyValues, ScyllaDbStoreInternalError> {
         let store = self.store.deref();
         let _guard = self.acquire().await;
         store
@@ -540,7 +562,7 @@ impl DirectWritableKeyValueStore for ScyllaDbStoreInternal {
     // https://github.com/scylladb/scylladb/blob/master/docs/dev/timestamp-conflict-resolution.md
     type Batch = UnorderedBatch;
 
-    async fn write_batch(&self, batch: Self::Batch) -> Result<(), ScyllaDbStoreError> {
+    async fn write_batch(&self, batch: Self::Batch) -> Result<(), ScyllaDbStoreInternalError> {
         let store = self.store.deref();
         let _guard = self.acquire().await;
         store.write_batch_internal(&self.root_key, batch).await
@@ -561,7 +583,7 @@ impl AdminKeyValueStore for ScyllaDbStoreInternal {
         config: &Self::Config,
         namespace: &str,
         root_key: &[u8],
-    ) -> Result<Self, ScyllaDbStoreError> {
+    ) -> Result<Self, ScyllaDbStoreInternalError> {
         Self::check_namespace(namespace)?;
         let session = SessionBuilder::new()
             .known_node(config.uri.as_str())
@@ -586,7 +608,7 @@ impl AdminKeyValueStore for ScyllaDbStoreInternal {
         })
     }
 
-    fn clone_with_root_key(&self, root_key: &[u8]) -> Result<Self, ScyllaDbStoreError> {
+    fn clone_with_root_key(&self, root_key: &[u8]) -> Result<Self, ScyllaDbStoreInternalError> {
         let store = self.store.clone();
         let semaphore = self.semaphore.clone();
         let max_stream_queries = self.max_stream_queries;
@@ -601,7 +623,7 @@ impl AdminKeyValueStore for ScyllaDbStoreInternal {
         })
     }
 
-    async fn list_all(config: &Self::Config) -> Result<Vec<String>, ScyllaDbStoreError> {
+    async fn list_all(config: &Self::Config) -> Result<Vec<String>, ScyllaDbStoreInternalError> {
         let session = SessionBuilder::new()
             .known_node(config.uri.as_str())
             .build()
@@ -626,7 +648,7 @@ impl AdminKeyValueStore for ScyllaDbStoreInternal {
                     if invalid_or_not_found {
                         return Ok(Vec::new());
                     } else {
-                        return Err(ScyllaDbStoreError::ScyllaDbQueryError(error));
+                        return Err(ScyllaDbStoreInternalError::ScyllaDbQueryError(error));
                     }
                 }
             };
@@ -650,7 +672,7 @@ impl AdminKeyValueStore for ScyllaDbStoreInternal {
         }
     }
 
-    async fn delete_all(store_config: &Self::Config) -> Result<(), ScyllaDbStoreError> {
+    async fn delete_all(store_config: &Self::Config) -> Result<(), ScyllaDbStoreInternalError> {
         let session = SessionBuilder::new()
             .known_node(store_config.uri.as_str())
             .build()
@@ -661,7 +683,10 @@ impl AdminKeyValueStore for ScyllaDbStoreInternal {
         Ok(())
     }
 
-    async fn exists(config: &Self::Config, namespace: &str) -> Result<bool, ScyllaDbStoreError> {
+    async fn exists(
+        config: &Self::Config,
+        namespace: &str,
+    ) -> Result<bool, ScyllaDbStoreInternalError> {
         Self::check_namespace(namespace)?;
         let session = SessionBuilder::new()
             .known_node(config.uri.as_str())
@@ -701,11 +726,14 @@ impl AdminKeyValueStore for ScyllaDbStoreInternal {
         if missing_table {
             Ok(false)
         } else {
-            Err(ScyllaDbStoreError::ScyllaDbQueryError(error))
+            Err(ScyllaDbStoreInternalError::ScyllaDbQueryError(error))
         }
     }
 
-    async fn create(config: &Self::Config, namespace: &str) -> Result<(), ScyllaDbStoreError> {
+    async fn create(
+        config: &Self::Config,
+        namespace: &str,
+    ) -> Result<(), ScyllaDbStoreInternalError> {
         Self::check_namespace(namespace)?;
         let session = SessionBuilder::new()
             .known_node(config.uri.as_str())
@@ -730,7 +758,10 @@ impl AdminKeyValueStore for ScyllaDbStoreInternal {
         Ok(())
     }
 
-    async fn delete(config: &Self::Config, namespace: &str) -> Result<(), ScyllaDbStoreError> {
+    async fn delete(
+        config: &Self::Config,
+        namespace: &str,
+    ) -> Result<(), ScyllaDbStoreInternalError> {
         Self::check_namespace(namespace)?;
         let session = SessionBuilder::new()
             .known_node(config.uri.as_str())
@@ -752,7 +783,7 @@ impl ScyllaDbStoreInternal {
         }
     }
 
-    fn check_namespace(namespace: &str) -> Result<(), ScyllaDbStoreError> {
+    fn check_namespace(namespace: &str) -> Result<(), ScyllaDbStoreInternalError> {
         if !namespace.is_empty()
             && namespace.len() <= 48
             && namespace
@@ -761,20 +792,29 @@ impl ScyllaDbStoreInternal {
         {
             return Ok(());
         }
-        Err(ScyllaDbStoreError::InvalidTableName)
+        Err(ScyllaDbStoreInternalError::InvalidTableName)
     }
 }
 
 /// A shared DB store for ScyllaDB implementing LruCaching
 #[derive(Clone)]
+#[allow(clippy::type_complexity)]
 pub struct ScyllaDbStore {
     #[cfg(with_metrics)]
-    store:
-        MeteredStore<LruCachingStore<MeteredStore<JournalingKeyValueStore<ScyllaDbStoreInternal>>>>,
+    store: MeteredStore<
+        LruCachingStore<
+            MeteredStore<
+                CompressingStore<MeteredStore<JournalingKeyValueStore<ScyllaDbStoreInternal>>>,
+            >,
+        >,
+    >,
     #[cfg(not(with_metrics))]
-    store: LruCachingStore<JournalingKeyValueStore<ScyllaDbStoreInternal>>,
+    store: LruCachingStore<CompressingStore<JournalingKeyValueStore<ScyllaDbStoreInternal>>>,
 }
 
+/// The composed error type for the `ScyllaDbStore`
+pub type ScyllaDbStoreError = CompressingError<ScyllaDbStoreInternalError>;
+
 /// The type for building a new ScyllaDB Key Value Store
 #[derive(Debug)]
 pub struct ScyllaDbStoreConfig {
@@ -868,23 +908,23 @@ impl AdminKeyValueStore for ScyllaDbStore {
     }
 
     async fn list_all(config: &Self::Config) -> Result<Vec<String>, ScyllaDbStoreError> {
-        ScyllaDbStoreInternal::list_all(config).await
+        Ok(ScyllaDbStoreInternal::list_all(config).await?)
     }
 
     async fn delete_all(config: &Self::Config) -> Result<(), ScyllaDbStoreError> {
-        ScyllaDbStoreInternal::delete_all(config).await
+        Ok(ScyllaDbStoreInternal::delete_all(config).await?)
     }
 
     async fn exists(config: &Self::Config, namespace: &str) -> Result<bool, ScyllaDbStoreError> {
-        ScyllaDbStoreInternal::exists(config, namespace).await
+        Ok(ScyllaDbStoreInternal::exists(config, namespace).await?)
     }
 
     async fn create(config: &Self::Config, namespace: &str) -> Result<(), ScyllaDbStoreError> {
-        ScyllaDbStoreInternal::create(config, namespace).await
+        Ok(ScyllaDbStoreInternal::create(config, namespace).await?)
     }
 
     async fn delete(config: &Self::Config, namespace: &str) -> Result<(), ScyllaDbStoreError> {
-        ScyllaDbStoreInternal::delete(config, namespace).await
+        Ok(ScyllaDbStoreInternal::delete(config, namespace).await?)
     }
 }
 
@@ -904,18 +944,21 @@ impl TestKeyValueStore for ScyllaDbStore {
 impl ScyllaDbStore {
     #[cfg(with_metrics)]
     fn inner(&self) -> &ScyllaDbStoreInternal {
-        &self.store.store.store.store.store
+        &self.store.store.store.store.store.store.store
     }
 
     #[cfg(not(with_metrics))]
     fn inner(&self) -> &ScyllaDbStoreInternal {
-        &self.store.store.store
+        &self.store.store.store.store
     }
 
     fn from_inner(simple_store: ScyllaDbStoreInternal, cache_size: usize) -> ScyllaDbStore {
         let store = JournalingKeyValueStore::new(simple_store);
         #[cfg(with_metrics)]
         let store = MeteredStore::new(&SCYLLA_DB_METRICS, store);
+        let store = CompressingStore::new(store, DEFAULT_COMPRESSION_THRESHOLD);
+        #[cfg(with_metrics)]
+        let store = MeteredStore::new(&COMPRESSING_METRICS, store);
         let store = LruCachingStore::new(store, cache_size);
         #[cfg(with_metrics)]
         let store = MeteredStore::new(&LRU_CACHING_METRICS, store);
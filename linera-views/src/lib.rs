@@ -21,6 +21,7 @@ We provide support for the following databases:
 * `RocksDbStore` is a disk-based key-value store
 * `DynamoDbStore` is the AWS-based DynamoDB service.
 * `ScyllaDbStore` is a cloud-based Cassandra-compatible database.
+* `PostgresStore` is a PostgreSQL-compatible relational database, used as a key-value store.
 * `ServiceStoreClient` is a gRPC-based storage that uses either memory or RocksDB. It is available in `linera-storage-service`.
 
 The corresponding trait in the code is the [`crate::store::KeyValueStore`](https://docs.rs/linera-views/latest/linera_views/store/trait.KeyValueStore.html).
@@ -92,17 +93,21 @@ pub mod random;
 #[cfg(with_testing)]
 pub mod test_utils;
 
+#[cfg(with_compression)]
+pub use backends::compressing;
 #[cfg(with_dynamodb)]
 pub use backends::dynamo_db;
 #[cfg(with_indexeddb)]
 pub use backends::indexed_db;
 #[cfg(with_metrics)]
 pub use backends::metering;
+#[cfg(with_postgres)]
+pub use backends::postgres;
 #[cfg(with_rocksdb)]
 pub use backends::rocks_db;
 #[cfg(with_scylladb)]
 pub use backends::scylla_db;
-pub use backends::{journaling, lru_caching, memory, value_splitting};
+pub use backends::{change_capture, journaling, lru_caching, memory, value_splitting};
 pub use views::{
     bucket_queue_view, collection_view, hashable_wrapper, key_value_store_view, log_view, map_view,
     queue_view, reentrant_collection_view, register_view, set_view,
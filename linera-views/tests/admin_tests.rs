@@ -3,6 +3,8 @@
 
 #[cfg(with_dynamodb)]
 use linera_views::dynamo_db::DynamoDbStore;
+#[cfg(with_postgres)]
+use linera_views::postgres::PostgresStore;
 #[cfg(with_rocksdb)]
 use linera_views::rocks_db::RocksDbStore;
 #[cfg(with_scylladb)]
@@ -31,3 +33,9 @@ async fn admin_test_dynamo_db() {
 async fn admin_test_scylla_db() {
     admin_test::<ScyllaDbStore>().await;
 }
+
+#[cfg(with_postgres)]
+#[tokio::test]
+async fn admin_test_postgres() {
+    admin_test::<PostgresStore>().await;
+}
@@ -28,6 +28,15 @@ async fn test_reads_test_memory() {
     }
 }
 
+#[cfg(with_compression)]
+#[tokio::test]
+async fn test_reads_compressing_memory() {
+    for scenario in get_random_test_scenarios() {
+        let key_value_store = linera_views::compressing::create_compressing_memory_store();
+        run_reads(key_value_store, scenario).await;
+    }
+}
+
 #[tokio::test]
 async fn test_reads_memory() {
     for scenario in get_random_test_scenarios() {
@@ -69,6 +78,17 @@ async fn test_reads_scylla_db() {
     }
 }
 
+#[cfg(with_postgres)]
+#[tokio::test]
+async fn test_reads_postgres() {
+    for scenario in get_random_test_scenarios() {
+        let store = linera_views::postgres::PostgresStore::new_test_store()
+            .await
+            .unwrap();
+        run_reads(store, scenario).await;
+    }
+}
+
 #[cfg(with_indexeddb)]
 #[wasm_bindgen_test]
 async fn test_reads_indexed_db() {
@@ -105,6 +125,13 @@ async fn test_test_memory_writes_from_blank() {
     run_writes_from_blank(&key_value_store).await;
 }
 
+#[cfg(with_compression)]
+#[tokio::test]
+async fn test_compressing_memory_writes_from_blank() {
+    let key_value_store = linera_views::compressing::create_compressing_memory_store();
+    run_writes_from_blank(&key_value_store).await;
+}
+
 #[tokio::test]
 async fn test_memory_writes_from_blank() {
     let store = MemoryStore::new_test_store().await.unwrap();
@@ -145,6 +172,15 @@ async fn test_scylla_db_writes_from_blank() {
     run_writes_from_blank(&store).await;
 }
 
+#[cfg(with_postgres)]
+#[tokio::test]
+async fn test_postgres_writes_from_blank() {
+    let store = linera_views::postgres::PostgresStore::new_test_store()
+        .await
+        .unwrap();
+    run_writes_from_blank(&store).await;
+}
+
 #[cfg(with_indexeddb)]
 #[wasm_bindgen_test]
 async fn test_indexed_db_writes_from_blank() {
@@ -192,6 +228,17 @@ async fn test_scylla_db_big_write_read() {
     run_big_write_read(store, target_size, value_sizes).await;
 }
 
+#[cfg(with_postgres)]
+#[tokio::test]
+async fn test_postgres_big_write_read() {
+    let store = linera_views::postgres::PostgresStore::new_test_store()
+        .await
+        .unwrap();
+    let value_sizes = vec![100, 1000, 200000, 5000000];
+    let target_size = 20000000;
+    run_big_write_read(store, target_size, value_sizes).await;
+}
+
 #[tokio::test]
 async fn test_memory_big_write_read() {
     let store = MemoryStore::new_test_store().await.unwrap();
@@ -200,6 +247,15 @@ async fn test_memory_big_write_read() {
     run_big_write_read(store, target_size, value_sizes).await;
 }
 
+#[cfg(with_compression)]
+#[tokio::test]
+async fn test_compressing_memory_big_write_read() {
+    let key_value_store = linera_views::compressing::create_compressing_memory_store();
+    let value_sizes = vec![100, 1000, 200000, 5000000];
+    let target_size = 20000000;
+    run_big_write_read(key_value_store, target_size, value_sizes).await;
+}
+
 #[cfg(with_rocksdb)]
 #[tokio::test]
 async fn test_rocks_db_big_write_read() {
@@ -270,3 +326,12 @@ async fn test_scylla_db_writes_from_state() {
         .unwrap();
     run_writes_from_state(&store).await;
 }
+
+#[cfg(with_postgres)]
+#[tokio::test]
+async fn test_postgres_writes_from_state() {
+    let store = linera_views::postgres::PostgresStore::new_test_store()
+        .await
+        .unwrap();
+    run_writes_from_state(&store).await;
+}